@@ -0,0 +1,166 @@
+//! Where the repo stood before each build loop, so `ralph rollback --loop N` can put it back.
+//! Kept at `.ralph/checkpoints/<run-id>/loop-<n>.json` next to the PRD: the HEAD commit, the
+//! PRD and completed.json as they were, and the run's step progress.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::git::{self, GitError};
+use crate::prd;
+use crate::storage::{StorageError, storage};
+
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    #[error("Failed to save checkpoint: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Failed to parse checkpoint: {0}")]
+    ParseError(#[from] serde_json::Error),
+
+    #[error("Failed to read or restore the PRD: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Git(#[from] GitError),
+}
+
+/// The state of the repo before one loop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub run_id: String,
+    pub loop_number: u64,
+    /// HEAD before the loop; None outside a git repo or before the first commit
+    #[serde(default)]
+    pub head: Option<String>,
+    /// The PRD file's contents
+    pub prd: String,
+    /// completed.json's contents; None if there wasn't one
+    #[serde(default)]
+    pub completed: Option<String>,
+    /// The run's step progress, as in the run state
+    #[serde(default)]
+    pub step_progress: BTreeMap<String, Vec<u32>>,
+    pub taken_at: DateTime<Utc>,
+}
+
+impl Checkpoint {
+    /// Where the checkpoint of `loop_number` in `run_id` is kept
+    pub fn file_path(prd_path: &str, run_id: &str, loop_number: u64) -> PathBuf {
+        prd::state_dir_for(prd_path)
+            .join("checkpoints")
+            .join(run_id)
+            .join(format!("loop-{}.json", loop_number))
+    }
+
+    /// Capture the repo in `work_dir` and the PRD at `prd_path` as they are now
+    pub fn take(
+        prd_path: &str,
+        work_dir: &Path,
+        run_id: &str,
+        loop_number: u64,
+        step_progress: &BTreeMap<String, Vec<u32>>,
+    ) -> Result<Self, CheckpointError> {
+        let head = if git::is_repo(work_dir) {
+            git::head_commit(work_dir)?
+        } else {
+            None
+        };
+        let completed = match std::fs::read_to_string(prd::completed_path_for(prd_path)) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            run_id: run_id.to_string(),
+            loop_number,
+            head,
+            prd: std::fs::read_to_string(prd_path)?,
+            completed,
+            step_progress: step_progress.clone(),
+            taken_at: Utc::now(),
+        })
+    }
+
+    pub fn save(&self, prd_path: &str) -> Result<(), CheckpointError> {
+        let content = serde_json::to_string_pretty(self)?;
+        storage().save(
+            &Self::file_path(prd_path, &self.run_id, self.loop_number),
+            &content,
+        )?;
+        Ok(())
+    }
+
+    pub fn load(
+        prd_path: &str,
+        run_id: &str,
+        loop_number: u64,
+    ) -> Result<Option<Self>, CheckpointError> {
+        match storage().load(&Self::file_path(prd_path, run_id, loop_number))? {
+            Some(content) => Ok(Some(serde_json::from_str(&content)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Put `work_dir` back how it was: reset to the recorded HEAD, discarding every commit
+    /// and tracked change since, then write back the PRD and completed.json
+    pub fn restore(&self, prd_path: &str, work_dir: &Path) -> Result<(), CheckpointError> {
+        if let Some(head) = &self.head {
+            git::reset_hard(work_dir, head)?;
+        }
+        std::fs::write(prd_path, &self.prd)?;
+        let completed_path = prd::completed_path_for(prd_path);
+        match &self.completed {
+            Some(content) => std::fs::write(completed_path, content)?,
+            None => {
+                if let Err(e) = std::fs::remove_file(completed_path)
+                    && e.kind() != io::ErrorKind::NotFound
+                {
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn take_save_and_restore_outside_git() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+        std::fs::write(prd_path, "{\"before\": true}").unwrap();
+        let progress = BTreeMap::from([("Add login".to_string(), vec![1])]);
+
+        let checkpoint = Checkpoint::take(prd_path, dir.path(), "run-1", 2, &progress).unwrap();
+        assert_eq!(checkpoint.head, None);
+        assert_eq!(checkpoint.completed, None);
+        checkpoint.save(prd_path).unwrap();
+        assert!(
+            dir.path()
+                .join(".ralph/checkpoints/run-1/loop-2.json")
+                .exists()
+        );
+
+        std::fs::write(prd_path, "{\"after\": true}").unwrap();
+        std::fs::write(dir.path().join("completed.json"), "[]").unwrap();
+        let loaded = Checkpoint::load(prd_path, "run-1", 2).unwrap().unwrap();
+        assert_eq!(loaded.step_progress, progress);
+        loaded.restore(prd_path, dir.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(prd_path).unwrap(),
+            "{\"before\": true}"
+        );
+        assert!(!dir.path().join("completed.json").exists());
+        assert!(Checkpoint::load(prd_path, "run-1", 3).unwrap().is_none());
+    }
+}
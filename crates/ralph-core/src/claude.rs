@@ -0,0 +1,452 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+
+use crate::process;
+
+/// Token usage reported in Claude's `--output-format json` envelope
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+}
+
+impl Usage {
+    /// Add another invocation's usage to this running total
+    pub fn accumulate(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+}
+
+/// Rough token count for a prompt (~4 characters per token for English text)
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// How much extended thinking Claude may do, passed to Claude Code as `MAX_THINKING_TOKENS`.
+/// The CLI has no temperature setting, so this is the knob ralph can turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Thinking {
+    /// No extended thinking
+    Off,
+    /// A moderate thinking budget, like asking Claude to "think hard"
+    Standard,
+    /// The largest budget Claude Code allows, for architecture and tricky debugging
+    Extended,
+}
+
+impl Thinking {
+    pub fn name(self) -> &'static str {
+        match self {
+            Thinking::Off => "off",
+            Thinking::Standard => "standard",
+            Thinking::Extended => "extended",
+        }
+    }
+
+    /// Value for the `MAX_THINKING_TOKENS` environment variable
+    pub fn max_tokens(self) -> u32 {
+        match self {
+            Thinking::Off => 0,
+            Thinking::Standard => 10_000,
+            Thinking::Extended => 31_999,
+        }
+    }
+}
+
+/// What Claude may do without asking. Ralph runs Claude non-interactively, where nobody can
+/// answer a permission prompt, so a tool that would ask is refused instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Permissions {
+    /// Claude Code's own settings decide
+    #[default]
+    Default,
+    /// Every tool runs without asking (--permission-mode bypassPermissions)
+    Bypass,
+    /// Only tools that read: no file edits, and Bash limited to commands that inspect the repo
+    ReadOnly,
+}
+
+/// Tools a `Permissions::ReadOnly` session may use
+pub const READ_ONLY_TOOLS: &[&str] = &[
+    "Read",
+    "Glob",
+    "Grep",
+    "LS",
+    "WebFetch",
+    "WebSearch",
+    "TodoWrite",
+    // The tool --json-schema answers through
+    "StructuredOutput",
+    "Bash(ls:*)",
+    "Bash(cat:*)",
+    "Bash(head:*)",
+    "Bash(tail:*)",
+    "Bash(wc:*)",
+    "Bash(git status:*)",
+    "Bash(git ls-files:*)",
+    // Exact commands: with arguments, `--output=<file>` would let these write files
+    "Bash(git diff)",
+    "Bash(git diff --stat)",
+    "Bash(git log)",
+    "Bash(git log --oneline)",
+    "Bash(git show)",
+    "Bash(git show --stat)",
+];
+
+/// Tools a `Permissions::ReadOnly` session is refused outright, whatever the user's settings
+pub const WRITE_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Options for launching Claude Code
+#[derive(Debug, Default)]
+pub struct ClaudeOptions<'a> {
+    /// The prompt to send
+    pub prompt: &'a str,
+
+    /// Session ID for --session-id flag (starts new named session)
+    pub session_id: Option<&'a str>,
+
+    /// Session ID for --resume flag (resumes existing session by ID)
+    pub resume_session_id: Option<&'a str>,
+
+    /// Whether to continue the previous session (-c flag)
+    pub continue_session: bool,
+
+    /// Fork the resumed session into a new session ID instead of appending to it (--fork-session)
+    pub fork_session: bool,
+
+    /// JSON schema for structured output (--json-schema flag)
+    pub json_schema: Option<&'a str>,
+
+    /// What Claude may do without asking (--permission-mode, --allowedTools, --disallowedTools)
+    pub permissions: Permissions,
+
+    /// Output format (--output-format flag): "text", "json", or "stream-json"
+    pub output_format: Option<&'a str>,
+
+    /// Maximum number of agentic turns before Claude stops (--max-turns flag)
+    pub max_turns: Option<u32>,
+
+    /// Model alias or name (--model flag); Claude Code's default when None
+    pub model: Option<&'a str>,
+
+    /// Extended thinking budget (MAX_THINKING_TOKENS); Claude Code's default when None
+    pub thinking: Option<Thinking>,
+
+    /// MCP server configs, each a JSON file or string (--mcp-config flag, once per entry)
+    pub mcp_config: &'a [String],
+
+    /// Extra directories Claude may read and edit (--add-dir flag, once per entry)
+    pub add_dirs: &'a [String],
+
+    /// Settings file or JSON string loaded on top of the usual settings (--settings flag)
+    pub settings: Option<&'a str>,
+
+    /// Tools Claude may use without asking, e.g. "Bash(git:*)", on top of the permission
+    /// profile's (--allowedTools flag)
+    pub allowed_tools: &'a [String],
+
+    /// Tools Claude may not use at all, even with permissions bypassed (--disallowedTools flag)
+    pub disallowed_tools: &'a [String],
+
+    /// Directory Claude runs in; the current one when None
+    pub cwd: Option<&'a Path>,
+}
+
+/// Launch Claude Code with the given options, its stdout and stderr piped. Must be called on
+/// the async runtime; the process is killed if the child is dropped before it exits.
+pub fn launch_claude_with_options(opts: &ClaudeOptions) -> std::io::Result<tokio::process::Child> {
+    let args = args(opts);
+
+    tracing::debug!(
+        ?args,
+        prompt_bytes = opts.prompt.len(),
+        thinking = ?opts.thinking,
+        "Spawning claude"
+    );
+    tracing::trace!(prompt = opts.prompt, "Claude prompt");
+
+    let mut command = process::command("claude");
+    if let Some(thinking) = opts.thinking {
+        command.env("MAX_THINKING_TOKENS", thinking.max_tokens().to_string());
+    }
+    if let Some(dir) = opts.cwd {
+        command.current_dir(dir);
+    }
+    command
+        .args(args)
+        .arg("-p")
+        .arg(opts.prompt)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    tokio::process::Command::from(command)
+        .kill_on_drop(true)
+        .spawn()
+        .inspect_err(|e| tracing::error!(error = %e, "Failed to spawn claude"))
+}
+
+/// Claude Code's arguments for `opts`, everything but the prompt
+fn args(opts: &ClaudeOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut allowed_tools: Vec<&str> = Vec::new();
+    let mut disallowed_tools: Vec<&str> = Vec::new();
+
+    // Permission profile
+    match opts.permissions {
+        Permissions::Default => {}
+        Permissions::Bypass => {
+            args.push("--permission-mode");
+            args.push("bypassPermissions");
+        }
+        Permissions::ReadOnly => {
+            allowed_tools.extend(READ_ONLY_TOOLS);
+            disallowed_tools.extend(WRITE_TOOLS);
+        }
+    }
+    allowed_tools.extend(opts.allowed_tools.iter().map(String::as_str));
+    disallowed_tools.extend(opts.disallowed_tools.iter().map(String::as_str));
+
+    // Session management
+    // --session-id: Create new session with specific ID
+    // --resume: Resume existing session by ID
+    // -c: Continue most recent session (not used with --session-id or --resume)
+    if let Some(session_id) = opts.resume_session_id {
+        args.push("--resume");
+        args.push(session_id);
+        if opts.fork_session {
+            args.push("--fork-session");
+        }
+    } else if let Some(session_id) = opts.session_id {
+        args.push("--session-id");
+        args.push(session_id);
+    } else if opts.continue_session {
+        args.push("-c");
+    }
+
+    // Model
+    if let Some(model) = opts.model {
+        args.push("--model");
+        args.push(model);
+    }
+
+    // JSON schema for structured output
+    if let Some(schema) = opts.json_schema {
+        args.push("--json-schema");
+        args.push(schema);
+    }
+
+    // Output format; in print mode, stream-json is only accepted with --verbose
+    if let Some(format) = opts.output_format {
+        args.push("--output-format");
+        args.push(format);
+        if format == "stream-json" {
+            args.push("--verbose");
+        }
+    }
+
+    // Max turns
+    let max_turns_str;
+    if let Some(turns) = opts.max_turns {
+        max_turns_str = turns.to_string();
+        args.push("--max-turns");
+        args.push(&max_turns_str);
+    }
+
+    // MCP servers, extra directories and settings
+    for config in opts.mcp_config {
+        args.push("--mcp-config");
+        args.push(config);
+    }
+    for dir in opts.add_dirs {
+        args.push("--add-dir");
+        args.push(dir);
+    }
+    if let Some(settings) = opts.settings {
+        args.push("--settings");
+        args.push(settings);
+    }
+
+    // Tool permissions; both flags take the rest of the list up to the next flag
+    if !allowed_tools.is_empty() {
+        args.push("--allowedTools");
+        args.extend(allowed_tools);
+    }
+    if !disallowed_tools.is_empty() {
+        args.push("--disallowedTools");
+        args.extend(disallowed_tools);
+    }
+
+    args.into_iter().map(str::to_string).collect()
+}
+
+/// Model that repairs malformed output unless `.ralph.toml` names another
+pub const NORMALIZE_MODEL: &str = "haiku";
+
+/// Error returned when normalization fails
+#[derive(Debug)]
+pub struct NormalizationError {
+    pub message: String,
+    pub raw_output: String,
+}
+
+impl std::fmt::Display for NormalizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n\nRaw output:\n{}", self.message, self.raw_output)
+    }
+}
+
+impl std::error::Error for NormalizationError {}
+
+/// The prompt asking the normalizing model to rewrite `raw_output` as JSON matching
+/// `target_schema`
+pub fn normalization_prompt(raw_output: &str, target_schema: &str) -> String {
+    format!(
+        r#"Given this raw output from Claude:
+---
+{raw_output}
+---
+
+Extract the structured data and return it as valid JSON matching this schema:
+{target_schema}
+
+Rules:
+1. Return ONLY valid JSON, no markdown or explanation
+2. If fields are missing, use sensible defaults (empty string, false, empty array)
+3. Fields with a fixed set of values (an "enum" in the schema) MUST use one of those values
+4. Preserve the original data as accurately as possible"#
+    )
+}
+
+/// Use a small model (normally [`NORMALIZE_MODEL`]) to normalize malformed JSON output into
+/// valid JSON matching a schema.
+///
+/// This is a fallback mechanism when strict JSON parsing fails. Haiku is fast and cheap,
+/// making it ideal for this "JSON repair" task.
+pub fn normalize_json(
+    raw_output: &str,
+    target_schema: &str,
+    model: &str,
+) -> Result<String, NormalizationError> {
+    let normalization_prompt = normalization_prompt(raw_output, target_schema);
+
+    let child = process::command("claude")
+        .args(["--model", model, "-p", &normalization_prompt])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to spawn claude for JSON normalization");
+            return Err(NormalizationError {
+                message: format!("Failed to spawn {} process: {}", model, e),
+                raw_output: raw_output.to_string(),
+            });
+        }
+    };
+
+    let output = match child.wait_with_output() {
+        Ok(o) => o,
+        Err(e) => {
+            return Err(NormalizationError {
+                message: format!("Failed to get {} output: {}", model, e),
+                raw_output: raw_output.to_string(),
+            });
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let trimmed = stdout.trim();
+
+    // The model might wrap the JSON in markdown code blocks - strip them
+    let json_str = if trimmed.starts_with("```") {
+        // Find the actual JSON content between code blocks
+        let without_prefix = trimmed
+            .strip_prefix("```json")
+            .or_else(|| trimmed.strip_prefix("```"))
+            .unwrap_or(trimmed);
+        without_prefix
+            .strip_suffix("```")
+            .unwrap_or(without_prefix)
+            .trim()
+    } else {
+        trimmed
+    };
+
+    // Validate it's actual JSON before returning
+    if !json_str.starts_with('{') {
+        return Err(NormalizationError {
+            message: format!("{} did not return valid JSON. Got: {}", model, json_str),
+            raw_output: raw_output.to_string(),
+        });
+    }
+
+    Ok(json_str.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bypass_sets_the_permission_mode() {
+        let args = args(&ClaudeOptions {
+            permissions: Permissions::Bypass,
+            max_turns: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(
+            args,
+            ["--permission-mode", "bypassPermissions", "--max-turns", "5"]
+        );
+    }
+
+    #[test]
+    fn stream_json_adds_verbose() {
+        let args = args(&ClaudeOptions {
+            output_format: Some("stream-json"),
+            ..Default::default()
+        });
+        assert_eq!(args, ["--output-format", "stream-json", "--verbose"]);
+    }
+
+    #[test]
+    fn read_only_allows_reading_and_refuses_edits() {
+        let extra = vec!["Bash(cargo metadata:*)".to_string()];
+        let args = args(&ClaudeOptions {
+            permissions: Permissions::ReadOnly,
+            allowed_tools: &extra,
+            ..Default::default()
+        });
+        assert!(!args.iter().any(|a| a == "bypassPermissions"));
+
+        let allowed = args.iter().position(|a| a == "--allowedTools").unwrap();
+        let disallowed = args.iter().position(|a| a == "--disallowedTools").unwrap();
+        let allowed = &args[allowed + 1..disallowed];
+        assert!(allowed.iter().any(|t| t == "Read"));
+        assert!(allowed.iter().any(|t| t == "Bash(git log)"));
+        // Subagents escape the profile, and git's --output writes files
+        assert!(
+            !allowed
+                .iter()
+                .any(|t| t == "Task" || t.starts_with("Bash(git diff:"))
+        );
+        assert_eq!(allowed.last().unwrap(), "Bash(cargo metadata:*)");
+        assert!(!allowed.iter().any(|t| t == "Bash" || t == "Edit"));
+        assert_eq!(&args[disallowed + 1..], WRITE_TOOLS);
+    }
+}
@@ -0,0 +1,233 @@
+//! Automated code review of a completed task (`ralph build --code-review`).
+//!
+//! Where `--verify` asks whether the task's acceptance criteria are met, the reviewer reads
+//! the diff the way a colleague would: bugs, missing tests, error handling, changes the task
+//! didn't ask for. Requested changes send the task back for a fix loop with the comments.
+
+use serde::Deserialize;
+
+use crate::claude::Usage;
+use crate::prd::Task;
+use crate::verify::diff_for_prompt;
+
+/// Model used when `--code-review` is given without one
+pub const DEFAULT_MODEL: &str = "sonnet";
+
+/// Turns the reviewer may take, enough to read the code around the diff
+pub const MAX_TURNS: u32 = 15;
+
+/// JSON schema for the reviewer's structured output
+pub const REVIEW_OUTPUT_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "verdict": {"type": "string", "enum": ["approve", "request_changes"]},
+    "summary": {"type": "string"},
+    "issues": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "properties": {
+          "file": {"type": "string"},
+          "severity": {"type": "string", "enum": ["blocker", "major", "minor"]},
+          "comment": {"type": "string"}
+        },
+        "required": ["severity", "comment"]
+      }
+    }
+  },
+  "required": ["verdict", "summary", "issues"]
+}"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Approve,
+    RequestChanges,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Wrong or broken; must be fixed
+    Blocker,
+    /// Should be fixed before the task counts as done
+    Major,
+    /// Worth knowing, not worth another loop
+    Minor,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Blocker => "blocker",
+            Severity::Major => "major",
+            Severity::Minor => "minor",
+        }
+    }
+}
+
+/// One review comment
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Issue {
+    /// File the comment is about; empty for the change as a whole
+    #[serde(default)]
+    pub file: String,
+    pub severity: Severity,
+    pub comment: String,
+}
+
+/// The reviewer's verdict and comments
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Review {
+    pub verdict: Verdict,
+    pub summary: String,
+    #[serde(default)]
+    pub issues: Vec<Issue>,
+}
+
+impl Review {
+    pub fn approved(&self) -> bool {
+        self.verdict == Verdict::Approve
+    }
+
+    /// The comments as a list, most severe first; empty if there are none
+    pub fn format_issues(&self) -> String {
+        let mut issues: Vec<&Issue> = self.issues.iter().collect();
+        issues.sort_by_key(|issue| issue.severity as u8);
+        issues
+            .iter()
+            .map(|issue| {
+                if issue.file.is_empty() {
+                    format!("- [{}] {}", issue.severity.name(), issue.comment)
+                } else {
+                    format!(
+                        "- [{}] {}: {}",
+                        issue.severity.name(),
+                        issue.file,
+                        issue.comment
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// What a review call cost, alongside the review
+#[derive(Debug)]
+pub struct CodeReview {
+    pub review: Review,
+    pub cost_usd: f64,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewJsonOutput {
+    is_error: bool,
+    structured_output: Option<Review>,
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Parse the reviewer's `--output-format json` envelope
+pub fn parse_output(stdout: &str) -> Result<CodeReview, String> {
+    let wrapper: ReviewJsonOutput = serde_json::from_str(stdout)
+        .map_err(|e| format!("Parse error: {}\n\nRaw output:\n{}", e, stdout))?;
+    match wrapper.structured_output {
+        Some(review) => Ok(CodeReview {
+            review,
+            cost_usd: wrapper.total_cost_usd.unwrap_or(0.0),
+            usage: wrapper.usage.unwrap_or_default(),
+        }),
+        None if wrapper.is_error => Err(format!("Claude returned an error:\n{}", stdout)),
+        None => Err(format!("No structured output:\n{}", stdout)),
+    }
+}
+
+/// Prompt for the reviewer: the task, the gate results and the diff
+pub fn make_prompt(task_number: usize, task: &Task, diff: &str, gate_report: &str) -> String {
+    let steps = if task.steps.is_empty() {
+        "(no steps listed)".to_string()
+    } else {
+        task.steps
+            .iter()
+            .map(|step| format!("- {}", step))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    format!(
+        r#"You are reviewing a change another agent made for one task, as a careful senior
+colleague would before it's merged. You may read files in the repository for context, but
+don't change anything.
+
+## Task #{task_number} ({category})
+
+{description}
+
+{steps}
+
+## Quality Gates
+
+{gate_report}
+
+## Diff
+
+```diff
+{diff}
+```
+
+Look for bugs and edge cases, missing or weak tests, poor error handling, code that doesn't
+fit the surrounding conventions, and changes the task didn't call for. List each problem as an
+issue with the file it's in and a severity: "blocker" (wrong or broken), "major" (should be
+fixed before the task is done) or "minor" (a nit). Answer "request_changes" only if there is
+a blocker or major issue, otherwise "approve". Make every comment specific enough for the
+next agent to act on without seeing this review."#,
+        category = task.category,
+        description = task.description,
+        diff = diff_for_prompt(diff),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> Task {
+        serde_json::from_str(
+            r#"{"category": "feature", "description": "Add login", "steps": ["Form"], "passes": true}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn prompt_includes_task_gates_and_diff() {
+        let prompt = make_prompt(2, &task(), "+fn login() {}", "- PASS `cargo test`");
+        assert!(prompt.contains("## Task #2 (feature)"));
+        assert!(prompt.contains("- Form"));
+        assert!(prompt.contains("+fn login() {}"));
+        assert!(prompt.contains("PASS `cargo test`"));
+        assert!(make_prompt(2, &task(), "", "").contains("(empty diff)"));
+    }
+
+    #[test]
+    fn parse_output_reads_review_and_cost() {
+        let json = r#"{"type":"result","is_error":false,"total_cost_usd":0.05,"structured_output":{
+            "verdict":"request_changes","summary":"Login skips validation",
+            "issues":[{"severity":"minor","comment":"Typo in a comment"},
+                      {"file":"src/login.rs","severity":"blocker","comment":"Empty passwords pass"}]}}"#;
+        let review = parse_output(json).unwrap();
+        assert!(!review.review.approved());
+        assert_eq!(review.cost_usd, 0.05);
+        assert_eq!(
+            review.review.format_issues(),
+            "- [blocker] src/login.rs: Empty passwords pass\n- [minor] Typo in a comment"
+        );
+
+        let error = r#"{"type":"result","is_error":true,"structured_output":null}"#;
+        assert!(parse_output(error).unwrap_err().contains("error"));
+        assert!(parse_output("not json").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::prd::Prd;
+
+/// Marker Claude is asked to print when every task is done, unless `--exit-clause` overrides it
+pub const DEFAULT_EXIT_CLAUSE: &str = "<promise>COMPLETE</promise>";
+
+/// Ways of deciding that a PRD run is finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Detector {
+    /// The structured output's `prd_complete` field is true
+    Structured,
+    /// Claude's final message or summary contains the exit clause
+    Marker,
+    /// Every task left in the PRD file has passes=true
+    AllTasks,
+}
+
+impl Detector {
+    pub fn name(self) -> &'static str {
+        match self {
+            Detector::Structured => "structured prd_complete",
+            Detector::Marker => "exit clause",
+            Detector::AllTasks => "all tasks passing",
+        }
+    }
+}
+
+/// Which detectors are active and the marker they look for
+#[derive(Debug, Clone)]
+pub struct CompletionCriteria {
+    pub detectors: Vec<Detector>,
+    pub exit_clause: String,
+}
+
+impl Default for CompletionCriteria {
+    fn default() -> Self {
+        Self {
+            detectors: vec![Detector::Structured],
+            exit_clause: DEFAULT_EXIT_CLAUSE.to_string(),
+        }
+    }
+}
+
+/// What an iteration produced, as seen by the detectors
+pub struct IterationSignals<'a> {
+    /// `prd_complete` from the structured output
+    pub prd_complete: bool,
+    /// Claude's free-text result plus the structured summary
+    pub text: &'a str,
+    /// The PRD as re-read after the iteration
+    pub prd: &'a Prd,
+}
+
+impl CompletionCriteria {
+    /// Build criteria from CLI flags. A custom exit clause enables the marker detector.
+    pub fn new(detectors: Vec<Detector>, exit_clause: Option<String>) -> Self {
+        let mut criteria = Self::default();
+        if !detectors.is_empty() {
+            criteria.detectors = detectors;
+        }
+        if let Some(clause) = exit_clause {
+            criteria.exit_clause = clause;
+            if !criteria.detectors.contains(&Detector::Marker) {
+                criteria.detectors.push(Detector::Marker);
+            }
+        }
+        criteria
+    }
+
+    pub fn uses(&self, detector: Detector) -> bool {
+        self.detectors.contains(&detector)
+    }
+
+    /// The first detector that considers the run complete, if any
+    pub fn detect(&self, signals: &IterationSignals) -> Option<Detector> {
+        self.detectors.iter().copied().find(|d| match d {
+            Detector::Structured => signals.prd_complete,
+            Detector::Marker => {
+                !self.exit_clause.is_empty() && signals.text.contains(&self.exit_clause)
+            }
+            Detector::AllTasks => signals.prd.tasks.iter().all(|t| t.passes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prd(passes: &[bool]) -> Prd {
+        let tasks: Vec<String> = passes
+            .iter()
+            .map(|p| {
+                format!(
+                    r#"{{"category": "feature", "description": "t", "steps": [], "passes": {}}}"#,
+                    p
+                )
+            })
+            .collect();
+        serde_json::from_str(&format!(
+            r#"{{"name": "Test", "quality_gates": [], "tasks": [{}]}}"#,
+            tasks.join(",")
+        ))
+        .unwrap()
+    }
+
+    fn signals<'a>(prd_complete: bool, text: &'a str, prd: &'a Prd) -> IterationSignals<'a> {
+        IterationSignals {
+            prd_complete,
+            text,
+            prd,
+        }
+    }
+
+    #[test]
+    fn default_uses_structured_field_only() {
+        let criteria = CompletionCriteria::default();
+        let prd = prd(&[true]);
+        assert_eq!(
+            criteria.detect(&signals(true, "", &prd)),
+            Some(Detector::Structured)
+        );
+        assert_eq!(
+            criteria.detect(&signals(false, DEFAULT_EXIT_CLAUSE, &prd)),
+            None
+        );
+    }
+
+    #[test]
+    fn exit_clause_enables_marker() {
+        let criteria = CompletionCriteria::new(vec![], Some("ALL DONE".to_string()));
+        assert!(criteria.uses(Detector::Structured));
+        assert!(criteria.uses(Detector::Marker));
+
+        let prd = prd(&[false]);
+        assert_eq!(
+            criteria.detect(&signals(false, "work finished. ALL DONE", &prd)),
+            Some(Detector::Marker)
+        );
+        assert_eq!(criteria.detect(&signals(false, "not yet", &prd)), None);
+    }
+
+    #[test]
+    fn marker_detector_uses_default_clause() {
+        let criteria = CompletionCriteria::new(vec![Detector::Marker], None);
+        assert!(!criteria.uses(Detector::Structured));
+        let prd = prd(&[false]);
+        assert_eq!(
+            criteria.detect(&signals(true, "<promise>COMPLETE</promise>", &prd)),
+            Some(Detector::Marker)
+        );
+        assert_eq!(criteria.detect(&signals(true, "", &prd)), None);
+    }
+
+    #[test]
+    fn all_tasks_detector_reads_prd() {
+        let criteria = CompletionCriteria::new(vec![Detector::AllTasks], None);
+        assert_eq!(
+            criteria.detect(&signals(false, "", &prd(&[true, true]))),
+            Some(Detector::AllTasks)
+        );
+        assert_eq!(
+            criteria.detect(&signals(false, "", &prd(&[true, false]))),
+            None
+        );
+        // Every task archived to completed.json counts as done
+        assert_eq!(
+            criteria.detect(&signals(false, "", &prd(&[]))),
+            Some(Detector::AllTasks)
+        );
+    }
+
+    #[test]
+    fn empty_exit_clause_never_matches() {
+        let criteria = CompletionCriteria::new(vec![Detector::Marker], Some(String::new()));
+        let prd = prd(&[false]);
+        assert_eq!(criteria.detect(&signals(false, "anything", &prd)), None);
+    }
+}
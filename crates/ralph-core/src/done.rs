@@ -0,0 +1,218 @@
+//! A PRD's definition of done: what must hold before ralph accepts `prd_complete`.
+//!
+//! Items are free text. Ralph checks the ones it recognises itself: quality gates, a
+//! CHANGELOG entry, documentation changes and `backticked` commands. Anything else is left
+//! to Claude, which sees every item in its prompt.
+
+use crate::gates::GateResult;
+
+/// How ralph can check one item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// The PRD's quality gates passed
+    QualityGates,
+    /// A CHANGELOG file changed during the run
+    Changelog,
+    /// README or docs changed during the run
+    Docs,
+    /// This shell command exits successfully
+    Command(String),
+    /// Nothing ralph can check; Claude's word stands
+    Manual,
+}
+
+/// Work out how to check an item from its wording
+pub fn classify(item: &str) -> Check {
+    if let Some(command) = backticked(item) {
+        return Check::Command(command.to_string());
+    }
+    let lower = item.to_lowercase();
+    if lower.contains("gate") {
+        Check::QualityGates
+    } else if lower.contains("changelog") {
+        Check::Changelog
+    } else if lower.contains("readme") || lower.contains("docs") || lower.contains("documentation")
+    {
+        Check::Docs
+    } else {
+        Check::Manual
+    }
+}
+
+/// Text between the first pair of backticks, if any
+fn backticked(item: &str) -> Option<&str> {
+    let (_, rest) = item.split_once('`')?;
+    let (command, _) = rest.split_once('`')?;
+    let command = command.trim();
+    (!command.is_empty()).then_some(command)
+}
+
+/// What the run can tell the checks
+pub struct Evidence<'a> {
+    /// Whether the quality gates passed; None if they weren't run
+    pub gates_passed: Option<bool>,
+    /// Files changed since the run started; None outside a git repository
+    pub changed_files: Option<&'a [String]>,
+}
+
+/// The outcome for one item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Met,
+    /// Not met, and why
+    Unmet(String),
+    /// Left to Claude
+    Unchecked,
+}
+
+/// Check every item, running command items with `run_command`
+pub fn check(
+    items: &[String],
+    evidence: &Evidence,
+    mut run_command: impl FnMut(&str) -> GateResult,
+) -> Vec<(String, Outcome)> {
+    items
+        .iter()
+        .map(|item| {
+            let outcome = match classify(item) {
+                Check::QualityGates => match evidence.gates_passed {
+                    Some(true) => Outcome::Met,
+                    Some(false) => Outcome::Unmet("the quality gates failed".to_string()),
+                    None => Outcome::Unchecked,
+                },
+                Check::Changelog => changed(evidence, "no CHANGELOG file changed", |file| {
+                    file_name(file).contains("changelog")
+                }),
+                Check::Docs => changed(evidence, "no README or docs file changed", |file| {
+                    let file = file.to_lowercase();
+                    file_name(&file).starts_with("readme")
+                        || file.starts_with("docs/")
+                        || file.starts_with("doc/")
+                        || file.contains("/docs/")
+                }),
+                Check::Command(command) => {
+                    let result = run_command(&command);
+                    if result.passed() {
+                        Outcome::Met
+                    } else {
+                        let code = result
+                            .exit_code
+                            .map_or_else(|| "killed".to_string(), |c| c.to_string());
+                        Outcome::Unmet(format!("`{}` failed (exit {})", command, code))
+                    }
+                }
+                Check::Manual => Outcome::Unchecked,
+            };
+            (item.clone(), outcome)
+        })
+        .collect()
+}
+
+fn changed(evidence: &Evidence, reason: &str, matches: impl Fn(&str) -> bool) -> Outcome {
+    match evidence.changed_files {
+        Some(files) if files.iter().any(|f| matches(f)) => Outcome::Met,
+        Some(_) => Outcome::Unmet(format!("{} during this run", reason)),
+        None => Outcome::Unchecked,
+    }
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit('/').next().unwrap_or(path).to_lowercase()
+}
+
+/// Prompt section listing unmet items, or None if nothing ralph checked is unmet
+pub fn unmet_report(results: &[(String, Outcome)]) -> Option<String> {
+    let unmet: Vec<String> = results
+        .iter()
+        .filter_map(|(item, outcome)| match outcome {
+            Outcome::Unmet(reason) => Some(format!("- {} ({})", item, reason)),
+            _ => None,
+        })
+        .collect();
+    if unmet.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "\n## Definition Of Done Not Met\n\nThe previous iteration reported the PRD complete, \
+but ralph checked the definition of done and these items don't hold yet:\n{}\n\
+Take care of them before reporting prd_complete again.\n",
+        unmet.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn exit(code: i32) -> GateResult {
+        GateResult {
+            command: String::new(),
+            exit_code: Some(code),
+            output: String::new(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn classify_by_wording() {
+        assert_eq!(classify("All quality gates green"), Check::QualityGates);
+        assert_eq!(classify("CHANGELOG entry added"), Check::Changelog);
+        assert_eq!(classify("Docs updated"), Check::Docs);
+        assert_eq!(
+            classify("`cargo doc --no-deps` builds"),
+            Check::Command("cargo doc --no-deps".to_string())
+        );
+        assert_eq!(classify("Product owner signed off"), Check::Manual);
+    }
+
+    #[test]
+    fn check_uses_gates_files_and_commands() {
+        let items: Vec<String> = [
+            "All gates pass",
+            "CHANGELOG entry",
+            "README updated",
+            "`make lint` passes",
+            "Demo recorded",
+        ]
+        .map(String::from)
+        .to_vec();
+        let changed = vec!["src/main.rs".to_string(), "docs/usage.md".to_string()];
+        let results = check(
+            &items,
+            &Evidence {
+                gates_passed: Some(true),
+                changed_files: Some(&changed),
+            },
+            |_| exit(2),
+        );
+        let outcomes: Vec<&Outcome> = results.iter().map(|(_, o)| o).collect();
+        assert_eq!(outcomes[0], &Outcome::Met);
+        assert!(matches!(outcomes[1], Outcome::Unmet(_)));
+        assert_eq!(outcomes[2], &Outcome::Met);
+        assert_eq!(
+            outcomes[3],
+            &Outcome::Unmet("`make lint` failed (exit 2)".to_string())
+        );
+        assert_eq!(outcomes[4], &Outcome::Unchecked);
+
+        let report = unmet_report(&results).unwrap();
+        assert!(report.contains("- CHANGELOG entry (no CHANGELOG file changed during this run)"));
+        assert!(report.contains("`make lint` passes"));
+    }
+
+    #[test]
+    fn check_without_evidence_leaves_items_to_claude() {
+        let items = vec!["Gates green".to_string(), "CHANGELOG entry".to_string()];
+        let results = check(
+            &items,
+            &Evidence {
+                gates_passed: None,
+                changed_files: None,
+            },
+            |_| exit(0),
+        );
+        assert!(results.iter().all(|(_, o)| *o == Outcome::Unchecked));
+        assert!(unmet_report(&results).is_none());
+    }
+}
@@ -0,0 +1,229 @@
+//! Escalation when a task keeps failing (`ralph build --escalate-after N`, or `[escalate]`
+//! in `.ralph.toml`).
+//!
+//! After N failed loops in a row on the same task, each further failure moves one step
+//! along the policy: retry with a stronger model, then with the task's full failure
+//! history in the prompt, then pause the run for the user's guidance. Steps stay in effect
+//! once reached, so the history retry still uses the stronger model.
+
+use serde::Deserialize;
+
+/// Model used for the `model` step when `--escalate-model` isn't given
+pub const DEFAULT_MODEL: &str = "opus";
+
+/// Failed loops in a row before the first step when `--escalate-after` is given no value
+pub const DEFAULT_AFTER: u32 = 2;
+
+/// One step of the escalation chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Step {
+    /// Retry with the policy's stronger model
+    Model,
+    /// Retry with every failure on the task so far in the prompt
+    History,
+    /// Stop and ask the user for guidance before the next loop
+    Pause,
+}
+
+impl Step {
+    pub fn name(self) -> &'static str {
+        match self {
+            Step::Model => "model",
+            Step::History => "history",
+            Step::Pause => "pause",
+        }
+    }
+}
+
+/// When and how to escalate
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// Failed loops in a row on one task before the first step
+    pub after: u32,
+    /// Model the `model` step switches to
+    pub model: String,
+    /// Steps in the order they're taken, one per further failure
+    pub steps: Vec<Step>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            after: DEFAULT_AFTER,
+            model: DEFAULT_MODEL.to_string(),
+            steps: vec![Step::Model, Step::History, Step::Pause],
+        }
+    }
+}
+
+/// One failed loop on the task being tracked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub loop_number: u64,
+    pub reason: String,
+}
+
+/// Consecutive failures on one task and how far escalation has got
+#[derive(Debug, Default)]
+pub struct Tracker {
+    task: Option<String>,
+    failures: Vec<Failure>,
+    level: usize,
+}
+
+impl Tracker {
+    /// Record a failed loop on `task` (by description). Returns the step this failure
+    /// escalates to, if it moved escalation on.
+    pub fn record_failure(
+        &mut self,
+        policy: &Policy,
+        task: &str,
+        failure: Failure,
+    ) -> Option<Step> {
+        if self.task.as_deref() != Some(task) {
+            self.reset();
+            self.task = Some(task.to_string());
+        }
+        self.failures.push(failure);
+        if self.failures.len() < policy.after.max(1) as usize {
+            return None;
+        }
+        let step = policy.steps.get(self.level).copied()?;
+        self.level += 1;
+        Some(step)
+    }
+
+    /// Record a loop that completed `task`; escalation on it is over
+    pub fn record_success(&mut self, task: &str) {
+        if self.task.as_deref() == Some(task) {
+            self.reset();
+        }
+    }
+
+    /// Start over, e.g. once the user has given guidance
+    pub fn reset(&mut self) {
+        self.task = None;
+        self.failures.clear();
+        self.level = 0;
+    }
+
+    /// Steps in effect for the next loop
+    pub fn active<'a>(&self, policy: &'a Policy) -> &'a [Step] {
+        &policy.steps[..self.level.min(policy.steps.len())]
+    }
+
+    /// Model the next loop should run with, if escalation switched it
+    pub fn model<'a>(&self, policy: &'a Policy) -> Option<&'a str> {
+        self.active(policy)
+            .contains(&Step::Model)
+            .then_some(policy.model.as_str())
+    }
+
+    /// Prompt section with every failure on the task, once the `history` step is reached
+    pub fn history_section(&self, policy: &Policy) -> Option<String> {
+        if !self.active(policy).contains(&Step::History) {
+            return None;
+        }
+        let task = self.task.as_deref()?;
+        let failures: Vec<String> = self
+            .failures
+            .iter()
+            .map(|f| format!("- Loop #{}: {}", f.loop_number, f.reason))
+            .collect();
+        Some(format!(
+            "\n## Repeated Failures\n\nThe task \"{}\" has failed {} loop(s) in a row:\n{}\n\
+Step back before trying again: work out why these attempts failed and take a different \
+approach rather than repeating them.\n",
+            task,
+            self.failures.len(),
+            failures.join("\n")
+        ))
+    }
+
+    /// Active steps as recorded in the run history, e.g. "model+history"; None before escalating
+    pub fn label(&self, policy: &Policy) -> Option<String> {
+        let active = self.active(policy);
+        (!active.is_empty()).then(|| {
+            active
+                .iter()
+                .map(|s| s.name())
+                .collect::<Vec<_>>()
+                .join("+")
+        })
+    }
+}
+
+/// The user's answer to a pause, for the next prompt
+pub fn guidance_section(task: &str, guidance: &str) -> String {
+    format!(
+        "\n## Guidance From The User\n\nThe run was paused because the task \"{}\" kept failing. \
+The user says:\n{}\nFollow this guidance in this loop.\n",
+        task, guidance
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(loop_number: u64) -> Failure {
+        Failure {
+            loop_number,
+            reason: format!("gates failed in loop {}", loop_number),
+        }
+    }
+
+    #[test]
+    fn escalates_one_step_per_failure_after_threshold() {
+        let policy = Policy::default();
+        let mut tracker = Tracker::default();
+        assert_eq!(tracker.record_failure(&policy, "Login", failure(1)), None);
+        assert_eq!(tracker.model(&policy), None);
+
+        assert_eq!(
+            tracker.record_failure(&policy, "Login", failure(2)),
+            Some(Step::Model)
+        );
+        assert_eq!(tracker.model(&policy), Some("opus"));
+        assert!(tracker.history_section(&policy).is_none());
+
+        assert_eq!(
+            tracker.record_failure(&policy, "Login", failure(3)),
+            Some(Step::History)
+        );
+        let section = tracker.history_section(&policy).unwrap();
+        assert!(section.contains("failed 3 loop(s)"));
+        assert!(section.contains("- Loop #1: gates failed in loop 1"));
+        assert_eq!(tracker.label(&policy).as_deref(), Some("model+history"));
+
+        assert_eq!(
+            tracker.record_failure(&policy, "Login", failure(4)),
+            Some(Step::Pause)
+        );
+        assert_eq!(tracker.record_failure(&policy, "Login", failure(5)), None);
+    }
+
+    #[test]
+    fn another_task_or_a_success_starts_over() {
+        let policy = Policy {
+            after: 1,
+            ..Policy::default()
+        };
+        let mut tracker = Tracker::default();
+        assert_eq!(
+            tracker.record_failure(&policy, "Login", failure(1)),
+            Some(Step::Model)
+        );
+        assert_eq!(
+            tracker.record_failure(&policy, "Logout", failure(2)),
+            Some(Step::Model)
+        );
+        tracker.record_success("Login");
+        assert_eq!(tracker.model(&policy), Some("opus"));
+        tracker.record_success("Logout");
+        assert_eq!(tracker.model(&policy), None);
+        assert_eq!(tracker.label(&policy), None);
+    }
+}
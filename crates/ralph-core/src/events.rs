@@ -0,0 +1,206 @@
+//! `ralph build --output jsonl` (or `--events`): the run as JSON Lines, one object per
+//! lifecycle event, on stdout or in `--output-file`.
+//!
+//! Every line has `ts` (RFC 3339, UTC) and `event` (the event's name) followed by the
+//! event's own fields. Fields may be added over time but are never renamed or removed, so
+//! wrappers can parse them without pinning a ralph version.
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
+
+/// How `ralph build` reports the run on stdout (or in `--output-file`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    /// The TUI, or timestamped log lines when headless
+    #[default]
+    Text,
+    /// One JSON event per line
+    Jsonl,
+}
+
+/// Something that happened during a build run
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A loop is about to launch Claude; `task` is the task it's expected to pick
+    LoopStarted {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        task: Option<usize>,
+        model: Option<&'a str>,
+        thinking: Option<&'a str>,
+    },
+    /// Output the loop produced: Claude's reply, gate results, warnings
+    StreamChunk {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        text: &'a str,
+    },
+    /// Claude finished an invocation. `outcome` is `success`, `error`, `parse_error` or
+    /// `timed_out`; the task fields are only set on success
+    ClaudeResult {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        outcome: &'a str,
+        task: Option<i32>,
+        status: Option<&'a str>,
+        summary: Option<&'a str>,
+        prd_complete: bool,
+        /// Cumulative spend of the run so far
+        run_cost_usd: f64,
+    },
+    /// One quality gate finished
+    GateResult {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        command: &'a str,
+        passed: bool,
+        /// None if the gate was killed or couldn't start
+        exit_code: Option<i32>,
+        duration_secs: f64,
+    },
+    /// A loop finished a task and ralph accepted it (gates, commit and verification)
+    TaskCompleted {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        task: i32,
+        description: Option<&'a str>,
+        summary: Option<&'a str>,
+        commit: Option<&'a str>,
+        cost_usd: f64,
+    },
+    /// Claude hit a transient error and the loop will try again after `delay_secs`
+    Retry {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        attempt: u32,
+        max_attempts: u32,
+        delay_secs: u64,
+        reason: &'a str,
+    },
+    /// The run is waiting for the user, e.g. escalation reached its `pause` step
+    Paused {
+        #[serde(rename = "loop")]
+        loop_number: u64,
+        task: &'a str,
+        reason: &'a str,
+    },
+    /// The run is over; always the last line
+    Finished {
+        loops: u64,
+        tasks_completed: usize,
+        tasks_remaining: usize,
+        cost_usd: f64,
+        status: &'a str,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    ts: String,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// The JSON line for an event, stamped with the current time
+pub fn line(event: &Event) -> String {
+    let line = Line {
+        ts: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        event,
+    };
+    serde_json::to_string(&line).expect("events serialize to JSON")
+}
+
+/// Where events are written. Clones share the destination, so the build loop and the
+/// `App` that logs `stream_chunk`s can both hold one.
+#[derive(Clone)]
+pub struct EventLog {
+    out: Rc<RefCell<Box<dyn Write>>>,
+    stdout: bool,
+}
+
+impl EventLog {
+    pub fn stdout() -> Self {
+        Self {
+            out: Rc::new(RefCell::new(Box::new(io::stdout()))),
+            stdout: true,
+        }
+    }
+
+    /// Write events to `path`, replacing the file
+    pub fn create(path: &str) -> io::Result<Self> {
+        Ok(Self {
+            out: Rc::new(RefCell::new(Box::new(BufWriter::new(File::create(path)?)))),
+            stdout: false,
+        })
+    }
+
+    /// Whether events take over stdout, leaving no room for log lines
+    pub fn is_stdout(&self) -> bool {
+        self.stdout
+    }
+
+    /// Write one event. Each line is flushed so readers see it as it happens; a failed
+    /// write is dropped rather than stopping the run.
+    pub fn emit(&self, event: &Event) {
+        let mut out = self.out.borrow_mut();
+        let _ = writeln!(out, "{}", line(event));
+        let _ = out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn lines_carry_time_name_and_fields() {
+        let value: Value = serde_json::from_str(&line(&Event::Retry {
+            loop_number: 3,
+            attempt: 1,
+            max_attempts: 3,
+            delay_secs: 5,
+            reason: "API error: overloaded",
+        }))
+        .unwrap();
+        assert_eq!(value["event"], "retry");
+        assert_eq!(value["loop"], 3);
+        assert_eq!(value["delay_secs"], 5);
+        assert!(value["ts"].as_str().unwrap().ends_with('Z'));
+
+        let started = line(&Event::LoopStarted {
+            loop_number: 1,
+            task: None,
+            model: None,
+            thinking: Some("extended"),
+        });
+        assert!(started.starts_with(r#"{"ts":"#));
+        assert!(started.contains(r#""event":"loop_started","loop":1,"task":null"#));
+    }
+
+    #[test]
+    fn event_log_writes_flushed_lines_to_a_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let log = EventLog::create(path.to_str().unwrap()).unwrap();
+        assert!(!log.is_stdout());
+        log.clone().emit(&Event::GateResult {
+            loop_number: 2,
+            command: "cargo test",
+            passed: false,
+            exit_code: Some(101),
+            duration_secs: 1.5,
+        });
+        let content = std::fs::read_to_string(&path).unwrap();
+        let value: Value = serde_json::from_str(content.trim_end()).unwrap();
+        assert_eq!(value["event"], "gate_result");
+        assert_eq!(value["exit_code"], 101);
+        assert!(content.ends_with('\n'));
+    }
+}
@@ -0,0 +1,148 @@
+//! Fixing failed quality gates on the spot (`ralph build --fix-gates N`).
+//!
+//! Normally the failures are handed to the next loop, which may well pick up something else
+//! first. With `--fix-gates`, a loop whose gates fail gets up to N short sessions that do
+//! nothing but make the failing gates pass, with the gate output in the prompt, before ralph
+//! moves on.
+
+use serde::Deserialize;
+
+use crate::claude::Usage;
+use crate::prd::Task;
+
+/// Most fix attempts `--fix-gates` accepts
+pub const MAX_ATTEMPTS: u8 = 10;
+
+/// Turns a fix session may take: reading the failures, fixing and re-running the gates
+pub const MAX_TURNS: u32 = 40;
+
+/// JSON schema for the fixer's structured output
+pub const OUTPUT_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "summary": {"type": "string", "description": "What was wrong and what you changed"},
+    "fixed": {"type": "boolean", "description": "Whether every gate passes now"},
+    "committed": {"type": "boolean"}
+  },
+  "required": ["summary", "fixed", "committed"]
+}"#;
+
+/// What the fixer reports
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Report {
+    pub summary: String,
+    #[serde(default)]
+    pub fixed: bool,
+    #[serde(default)]
+    pub committed: bool,
+}
+
+/// What a fix session cost, alongside its report
+#[derive(Debug)]
+pub struct GateFix {
+    pub report: Report,
+    pub cost_usd: f64,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct FixJsonOutput {
+    is_error: bool,
+    structured_output: Option<Report>,
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Parse the fixer's `--output-format json` envelope
+pub fn parse_output(stdout: &str) -> Result<GateFix, String> {
+    let wrapper: FixJsonOutput = serde_json::from_str(stdout)
+        .map_err(|e| format!("Parse error: {}\n\nRaw output:\n{}", e, stdout))?;
+    match wrapper.structured_output {
+        Some(report) => Ok(GateFix {
+            report,
+            cost_usd: wrapper.total_cost_usd.unwrap_or(0.0),
+            usage: wrapper.usage.unwrap_or_default(),
+        }),
+        None if wrapper.is_error => Err(format!("Claude returned an error:\n{}", stdout)),
+        None => Err(format!("No structured output:\n{}", stdout)),
+    }
+}
+
+/// Prompt for fix attempt `attempt` of `max_attempts`: the failures (as
+/// [`crate::gates::failure_report`] writes them), the gates, and the task the loop worked on
+pub fn make_prompt(
+    attempt: u8,
+    max_attempts: u8,
+    task: Option<(usize, &Task)>,
+    failures: &str,
+    quality_gates: &[String],
+) -> String {
+    let task = match task {
+        Some((number, task)) => format!(
+            "The last loop worked on task #{} ({}): {}",
+            number, task.category, task.description
+        ),
+        None => "The last loop didn't report which task it worked on.".to_string(),
+    };
+    let gates = quality_gates
+        .iter()
+        .map(|gate| format!("- `{}`", gate))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"Your only job is to make the project's quality gates pass again (fix attempt {attempt} of
+{max_attempts}). {task}
+{failures}
+## Quality gates
+
+{gates}
+
+Rules:
+1. Fix the cause of each failure in the code. Don't delete or skip tests, loosen lint
+   settings, or edit the gate commands to make them pass.
+2. Don't start on any other task, and don't edit the PRD.
+3. Run every gate yourself before you finish.
+4. Commit your fixes in one commit whose message starts with "Fix quality gates".
+
+Report what was wrong and what you changed, whether every gate passes now and whether you
+committed."#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_includes_attempt_task_and_failures() {
+        let task: Task = serde_json::from_str(
+            r#"{"category": "feature", "description": "Add login", "steps": [], "passes": false}"#,
+        )
+        .unwrap();
+        let prompt = make_prompt(
+            2,
+            3,
+            Some((4, &task)),
+            "\n## Quality Gate Failures\n\n### `cargo test` (exit 101)\n",
+            &["cargo test".to_string()],
+        );
+        assert!(prompt.contains("fix attempt 2 of\n3"));
+        assert!(prompt.contains("task #4 (feature): Add login"));
+        assert!(prompt.contains("### `cargo test` (exit 101)"));
+        assert!(prompt.contains("- `cargo test`"));
+        assert!(make_prompt(1, 1, None, "", &[]).contains("didn't report which task"));
+    }
+
+    #[test]
+    fn parse_output_reads_report_and_cost() {
+        let json = r#"{"type":"result","is_error":false,"total_cost_usd":0.1,"structured_output":{
+            "summary":"Fixed an off-by-one","fixed":true,"committed":true}}"#;
+        let fix = parse_output(json).unwrap();
+        assert!(fix.report.fixed);
+        assert_eq!(fix.cost_usd, 0.1);
+        assert!(parse_output("not json").is_err());
+    }
+}
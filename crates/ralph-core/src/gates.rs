@@ -0,0 +1,350 @@
+use std::future::Future;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tokio::process::Child;
+
+use crate::process;
+
+/// Lines of gate output kept when reporting failures back to Claude
+const FAILURE_TAIL_LINES: usize = 40;
+
+/// Outcome of running a single quality gate command
+#[derive(Debug, Clone)]
+pub struct GateResult {
+    /// The shell command as written in the PRD
+    pub command: String,
+
+    /// Process exit code (None if killed by a signal or failed to spawn)
+    pub exit_code: Option<i32>,
+
+    /// Combined stdout and stderr
+    pub output: String,
+
+    /// Wall-clock time the gate took
+    pub duration: Duration,
+}
+
+impl GateResult {
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// A gate command running on the async runtime.
+///
+/// Its output is read as it's written so a chatty command (e.g. a large test suite) can't
+/// fill the pipe buffer and stall before it exits.
+pub struct RunningGate {
+    command: String,
+    child: Child,
+    output: process::Output,
+    started: Instant,
+}
+
+impl RunningGate {
+    /// Spawn a gate command through the platform shell, in `dir` or else the current
+    /// directory. Must be called on the runtime.
+    pub fn spawn(command: &str, dir: Option<&Path>) -> std::io::Result<Self> {
+        tracing::debug!(command, ?dir, "Spawning gate");
+        let mut shell = shell_command(command);
+        if let Some(dir) = dir {
+            shell.current_dir(dir);
+        }
+        shell
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = tokio::process::Command::from(shell)
+            .kill_on_drop(true)
+            .spawn()?;
+        let output = process::Output::capture(&mut child);
+
+        Ok(Self {
+            command: command.to_string(),
+            child,
+            output,
+            started: Instant::now(),
+        })
+    }
+
+    /// Wait for the command to exit
+    pub async fn wait(mut self) -> GateResult {
+        let code = self
+            .child
+            .wait()
+            .await
+            .ok()
+            .and_then(|status| status.code());
+        self.collect(code).await
+    }
+
+    /// Kill the command and whatever it started, and return the output it produced
+    pub async fn kill(mut self) -> GateResult {
+        let _ = process::kill_tree(&mut self.child);
+        let _ = self.child.wait().await;
+        let output = self.output.collect_killed().await;
+        result(self.command, self.started, None, output)
+    }
+
+    async fn collect(mut self, exit_code: Option<i32>) -> GateResult {
+        let output = self.output.collect().await;
+        result(self.command, self.started, exit_code, output)
+    }
+}
+
+/// A finished gate's result, with stderr after stdout
+fn result(
+    command: String,
+    started: Instant,
+    exit_code: Option<i32>,
+    (mut output, stderr): (String, String),
+) -> GateResult {
+    if !stderr.is_empty() {
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&stderr);
+    }
+
+    GateResult {
+        command,
+        exit_code,
+        output,
+        duration: started.elapsed(),
+    }
+}
+
+/// Run `command` in `dir` (else the current directory) to completion, or kill it once `stop`
+/// resolves. Must be called on the runtime.
+#[tracing::instrument(name = "gate", skip(stop), fields(exit_code))]
+pub async fn run(command: &str, dir: Option<&Path>, stop: impl Future<Output = ()>) -> GateResult {
+    let mut gate = match RunningGate::spawn(command, dir) {
+        Ok(gate) => gate,
+        Err(e) => return spawn_failure(command, &e),
+    };
+    let result = tokio::select! {
+        status = gate.child.wait() => {
+            let code = status.ok().and_then(|status| status.code());
+            gate.collect(code).await
+        }
+        () = stop => gate.kill().await,
+    };
+    tracing::Span::current().record("exit_code", result.exit_code);
+    result
+}
+
+/// Result for a gate whose command could not be started at all
+pub fn spawn_failure(command: &str, err: &std::io::Error) -> GateResult {
+    GateResult {
+        command: command.to_string(),
+        exit_code: None,
+        output: format!("Failed to spawn gate command: {}", err),
+        duration: Duration::ZERO,
+    }
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+/// Shell builtins and keywords a gate may start with, which aren't programs on PATH
+const SHELL_BUILTINS: &[&str] = &[
+    ".", ":", "[", "[[", "{", "(", "!", "alias", "builtin", "case", "cd", "command", "eval",
+    "exec", "exit", "export", "false", "for", "if", "pushd", "set", "source", "test", "true",
+    "ulimit", "umask", "unset", "until", "while",
+];
+
+/// What a gate command runs, as far as can be told without running it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateProgram<'a> {
+    /// A program that must be on PATH (or at the path given)
+    Program(&'a str),
+    /// A shell builtin or keyword, e.g. `cd web && npm test`: only running it tells whether
+    /// it works
+    Shell,
+    /// Nothing to run
+    Empty,
+}
+
+/// What a gate command would run, skipping leading `VAR=value` assignments
+pub fn gate_program(command: &str) -> GateProgram<'_> {
+    let Some(first) = command
+        .split_whitespace()
+        .find(|token| !token.contains('='))
+    else {
+        return GateProgram::Empty;
+    };
+    if SHELL_BUILTINS.contains(&first) || first.starts_with(['(', '{']) {
+        GateProgram::Shell
+    } else {
+        GateProgram::Program(first)
+    }
+}
+
+/// Whether `program` can be found: as a path if it contains a separator, otherwise on PATH
+pub fn program_on_path(program: &str) -> bool {
+    process::find_program(program).is_some()
+}
+
+/// Build a prompt section describing failed gates, or None if everything passed
+pub fn failure_report(results: &[GateResult]) -> Option<String> {
+    let failures: Vec<&GateResult> = results.iter().filter(|r| !r.passed()).collect();
+    if failures.is_empty() {
+        return None;
+    }
+
+    let mut report = String::from(
+        "\n## Quality Gate Failures\n\nRalph ran the PRD's quality gates after the previous iteration and these FAILED. \
+Fix them before starting any new feature:\n",
+    );
+
+    for failure in failures {
+        let code = failure
+            .exit_code
+            .map_or_else(|| "killed".to_string(), |c| c.to_string());
+        report.push_str(&format!(
+            "\n### `{}` (exit {})\n```\n{}\n```\n",
+            failure.command,
+            code,
+            tail(&failure.output, FAILURE_TAIL_LINES)
+        ));
+    }
+
+    Some(report)
+}
+
+/// Keep only the last `n` lines of `text`
+fn tail(text: &str, n: usize) -> String {
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let start = lines.len().saturating_sub(n);
+    lines[start..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime;
+
+    fn result(command: &str, exit_code: Option<i32>, output: &str) -> GateResult {
+        GateResult {
+            command: command.to_string(),
+            exit_code,
+            output: output.to_string(),
+            duration: Duration::ZERO,
+        }
+    }
+
+    fn run_to_completion(command: &str) -> GateResult {
+        runtime::block_on(run(command, None, std::future::pending()))
+    }
+
+    #[test]
+    fn passed_only_on_zero_exit() {
+        assert!(result("ok", Some(0), "").passed());
+        assert!(!result("fail", Some(1), "").passed());
+        assert!(!result("killed", None, "").passed());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawn_captures_exit_code_and_output() {
+        let ok = run_to_completion("echo hello");
+        assert!(ok.passed());
+        assert_eq!(ok.output.trim(), "hello");
+
+        let failed = run_to_completion("echo oops >&2; exit 3");
+        assert_eq!(failed.exit_code, Some(3));
+        assert!(failed.output.contains("oops"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_in_the_given_directory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("marker"), "").unwrap();
+        let result = runtime::block_on(run("ls", Some(dir.path()), std::future::pending()));
+        assert!(result.passed());
+        assert_eq!(result.output.trim(), "marker");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_kills_the_command_once_told_to_stop() {
+        let result = runtime::block_on(async {
+            let stop = tokio::time::sleep(Duration::from_millis(200));
+            run("echo started; sleep 30", None, stop).await
+        });
+        assert_eq!(result.exit_code, None);
+        assert_eq!(result.output, "started\n");
+        assert!(result.duration < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn failure_report_none_when_all_pass() {
+        let results = vec![result("cargo test", Some(0), "ok")];
+        assert!(failure_report(&results).is_none());
+    }
+
+    #[test]
+    fn failure_report_lists_failed_gates() {
+        let results = vec![
+            result("cargo test", Some(0), "ok"),
+            result("cargo clippy", Some(101), "error: unused variable"),
+        ];
+        let report = failure_report(&results).unwrap();
+        assert!(report.contains("Quality Gate Failures"));
+        assert!(report.contains("`cargo clippy` (exit 101)"));
+        assert!(report.contains("error: unused variable"));
+        assert!(!report.contains("`cargo test`"));
+    }
+
+    #[test]
+    fn gate_program_skips_env_assignments() {
+        assert_eq!(
+            gate_program("cargo test --all"),
+            GateProgram::Program("cargo")
+        );
+        assert_eq!(
+            gate_program("RUST_LOG=debug CI=1 cargo test"),
+            GateProgram::Program("cargo")
+        );
+        assert_eq!(gate_program("   "), GateProgram::Empty);
+    }
+
+    #[test]
+    fn gate_program_leaves_shell_builtins_to_the_shell() {
+        assert_eq!(gate_program("cd web && npm test"), GateProgram::Shell);
+        assert_eq!(gate_program("CI=1 cd . && true"), GateProgram::Shell);
+        assert_eq!(gate_program("(cd web; npm test)"), GateProgram::Shell);
+        assert_eq!(
+            gate_program("sh -c 'cd web && npm test'"),
+            GateProgram::Program("sh")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn program_on_path_finds_shell() {
+        assert!(program_on_path("sh"));
+        assert!(program_on_path("/bin/sh"));
+        assert!(!program_on_path("definitely-not-a-real-program-xyz"));
+        assert!(!program_on_path("./no/such/script.sh"));
+    }
+
+    #[test]
+    fn tail_keeps_last_lines() {
+        assert_eq!(tail("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(tail("a", 5), "a");
+    }
+}
@@ -0,0 +1,544 @@
+use std::path::Path;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("Failed to run git: {0}")]
+    Spawn(#[from] std::io::Error),
+
+    #[error("git {args} failed: {stderr}")]
+    Command { args: String, stderr: String },
+}
+
+/// Run `git <args>` in `dir` and return trimmed stdout
+#[tracing::instrument(name = "git", level = "info", skip_all, fields(args = args.join(" ")))]
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, GitError> {
+    tracing::debug!(dir = %dir.display(), "Running git");
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+
+    if !output.status.success() {
+        return Err(GitError::Command {
+            args: args.join(" "),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether `dir` is inside a git work tree
+pub fn is_repo(dir: &Path) -> bool {
+    run_git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out == "true")
+}
+
+/// Hash of the current HEAD commit, or None if the repository has no commits yet
+pub fn head_commit(dir: &Path) -> Result<Option<String>, GitError> {
+    match run_git(dir, &["rev-parse", "--verify", "--quiet", "HEAD"]) {
+        Ok(hash) => Ok(Some(hash)),
+        // --verify --quiet exits non-zero with no stderr when HEAD is unborn
+        Err(GitError::Command { stderr, .. }) if stderr.is_empty() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Subjects of commits reachable from HEAD but not from `base` (newest first).
+///
+/// With `base` of None (repository had no commits) every commit on HEAD is returned.
+pub fn commits_since(dir: &Path, base: Option<&str>) -> Result<Vec<String>, GitError> {
+    if head_commit(dir)?.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let range = base.map_or_else(|| "HEAD".to_string(), |b| format!("{}..HEAD", b));
+    let log = run_git(dir, &["log", "--format=%s", &range])?;
+    Ok(log.lines().map(str::to_string).collect())
+}
+
+/// Git's well-known empty tree, used to diff against an unborn base
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Changes committed since `base` (None: since the repository had no commits), as a diff
+pub fn diff_since(dir: &Path, base: Option<&str>) -> Result<String, GitError> {
+    if head_commit(dir)?.is_none() {
+        return Ok(String::new());
+    }
+    run_git(dir, &["diff", base.unwrap_or(EMPTY_TREE), "HEAD"])
+}
+
+/// Changes from `base` to `commit`; with no base, just the changes `commit` made itself
+pub fn diff_between(dir: &Path, base: Option<&str>, commit: &str) -> Result<String, GitError> {
+    let parent = format!("{}^", commit);
+    let base = match base {
+        Some(base) => base,
+        None if run_git(dir, &["rev-parse", "--verify", "--quiet", &parent]).is_ok() => &parent,
+        None => EMPTY_TREE,
+    };
+    run_git(dir, &["diff", base, commit])
+}
+
+/// `git diff --stat` of the changes committed since `base` (None: since there were no commits)
+pub fn diff_stat_since(dir: &Path, base: Option<&str>) -> Result<String, GitError> {
+    if head_commit(dir)?.is_none() {
+        return Ok(String::new());
+    }
+    run_git(dir, &["diff", "--stat", base.unwrap_or(EMPTY_TREE), "HEAD"])
+}
+
+/// Tracked files that differ between `base` (the empty tree if None) and the work tree,
+/// committed or not, plus untracked (non-ignored) files
+pub fn files_changed_since(dir: &Path, base: Option<&str>) -> Result<Vec<String>, GitError> {
+    let tracked = run_git(dir, &["diff", "--name-only", base.unwrap_or(EMPTY_TREE)])?;
+    let untracked = run_git(dir, &["ls-files", "--others", "--exclude-standard"])?;
+    Ok(tracked
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Whether the work tree has staged, unstaged or untracked (non-ignored) changes
+pub fn has_uncommitted_changes(dir: &Path) -> Result<bool, GitError> {
+    Ok(!run_git(dir, &["status", "--porcelain"])?.is_empty())
+}
+
+/// Move HEAD back to `commit`, discarding every commit and tracked change made since.
+/// Untracked files are left alone.
+pub fn reset_hard(dir: &Path, commit: &str) -> Result<(), GitError> {
+    run_git(dir, &["reset", "--quiet", "--hard", commit])?;
+    Ok(())
+}
+
+/// Move HEAD back to `commit`, dropping the commits since but keeping uncommitted changes.
+/// Fails rather than overwrite an uncommitted change to a file those commits touched.
+pub fn reset_keep(dir: &Path, commit: &str) -> Result<(), GitError> {
+    run_git(dir, &["reset", "--quiet", "--keep", commit])?;
+    Ok(())
+}
+
+/// Check out a new branch `branch`, created at `base`, in a separate work tree at `path`
+pub fn add_worktree(dir: &Path, path: &Path, branch: &str, base: &str) -> Result<(), GitError> {
+    let path = path.to_string_lossy();
+    run_git(
+        dir,
+        &["worktree", "add", "--quiet", "-b", branch, &path, base],
+    )?;
+    Ok(())
+}
+
+/// Remove the work tree at `path`, along with anything left uncommitted in it
+pub fn remove_worktree(dir: &Path, path: &Path) -> Result<(), GitError> {
+    let path = path.to_string_lossy();
+    run_git(dir, &["worktree", "remove", "--force", &path])?;
+    Ok(())
+}
+
+/// Delete the local branch `name`, merged or not
+pub fn delete_branch(dir: &Path, name: &str) -> Result<(), GitError> {
+    run_git(dir, &["branch", "--quiet", "-D", name])?;
+    Ok(())
+}
+
+/// How `merge` went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Merge {
+    /// The branch was merged with a merge commit
+    Merged,
+    /// The branch conflicts in these files; the merge was aborted and nothing changed
+    Conflict(Vec<String>),
+}
+
+/// Merge `branch` into the checked-out branch with a merge commit, aborting on conflicts
+pub fn merge(dir: &Path, branch: &str, message: &str) -> Result<Merge, GitError> {
+    match run_git(
+        dir,
+        &[
+            "merge",
+            "--quiet",
+            "--no-ff",
+            "--no-edit",
+            "-m",
+            message,
+            branch,
+        ],
+    ) {
+        Ok(_) => Ok(Merge::Merged),
+        Err(e) => {
+            let conflicts = run_git(dir, &["diff", "--name-only", "--diff-filter=U"])?;
+            if conflicts.is_empty() {
+                return Err(e);
+            }
+            run_git(dir, &["merge", "--abort"])?;
+            Ok(Merge::Conflict(
+                conflicts.lines().map(str::to_string).collect(),
+            ))
+        }
+    }
+}
+
+/// What `checkout_branch` had to do to get onto the requested branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchSwitch {
+    /// HEAD was already on the branch
+    AlreadyOn,
+    /// An existing branch was checked out
+    CheckedOut,
+    /// The branch didn't exist and was created from HEAD
+    Created,
+}
+
+/// Name of the checked-out branch, or None if HEAD is detached
+pub fn current_branch(dir: &Path) -> Result<Option<String>, GitError> {
+    match run_git(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]) {
+        Ok(name) => Ok(Some(name)),
+        Err(GitError::Command { stderr, .. }) if stderr.is_empty() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether `name` is a valid branch name
+pub fn is_valid_branch_name(dir: &Path, name: &str) -> bool {
+    run_git(dir, &["check-ref-format", "--branch", name]).is_ok()
+}
+
+/// Whether a local branch called `name` exists
+pub fn branch_exists(dir: &Path, name: &str) -> bool {
+    let refname = format!("refs/heads/{}", name);
+    run_git(dir, &["rev-parse", "--verify", "--quiet", &refname]).is_ok()
+}
+
+/// Check out `name`, creating it from HEAD if it doesn't exist yet
+pub fn checkout_branch(dir: &Path, name: &str) -> Result<BranchSwitch, GitError> {
+    if current_branch(dir)?.as_deref() == Some(name) {
+        return Ok(BranchSwitch::AlreadyOn);
+    }
+
+    if branch_exists(dir, name) {
+        run_git(dir, &["checkout", "--quiet", name])?;
+        Ok(BranchSwitch::CheckedOut)
+    } else {
+        run_git(dir, &["checkout", "--quiet", "-b", name])?;
+        Ok(BranchSwitch::Created)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn init_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        dir
+    }
+
+    fn commit(dir: &Path, message: &str) {
+        git(dir, &["commit", "-q", "--allow-empty", "-m", message]);
+    }
+
+    fn commit_all(dir: &Path, message: &str) {
+        git(dir, &["commit", "-q", "-a", "-m", message]);
+    }
+
+    #[test]
+    fn is_repo_detects_work_tree() {
+        let repo = init_repo();
+        assert!(is_repo(repo.path()));
+
+        let plain = TempDir::new().unwrap();
+        assert!(!is_repo(plain.path()));
+    }
+
+    #[test]
+    fn head_commit_none_before_first_commit() {
+        let repo = init_repo();
+        assert_eq!(head_commit(repo.path()).unwrap(), None);
+
+        commit(repo.path(), "first");
+        let head = head_commit(repo.path()).unwrap().unwrap();
+        assert_eq!(head.len(), 40);
+    }
+
+    #[test]
+    fn commits_since_lists_new_commits() {
+        let repo = init_repo();
+        commit(repo.path(), "first");
+        let base = head_commit(repo.path()).unwrap();
+
+        assert!(
+            commits_since(repo.path(), base.as_deref())
+                .unwrap()
+                .is_empty()
+        );
+
+        commit(repo.path(), "second");
+        commit(repo.path(), "third");
+        assert_eq!(
+            commits_since(repo.path(), base.as_deref()).unwrap(),
+            vec!["third", "second"]
+        );
+    }
+
+    #[test]
+    fn commits_since_unborn_base() {
+        let repo = init_repo();
+        assert!(commits_since(repo.path(), None).unwrap().is_empty());
+
+        commit(repo.path(), "first");
+        assert_eq!(commits_since(repo.path(), None).unwrap(), vec!["first"]);
+    }
+
+    #[test]
+    fn files_changed_since_covers_commits_edits_and_new_files() {
+        let repo = init_repo();
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "first");
+        let base = head_commit(repo.path()).unwrap();
+        assert!(
+            files_changed_since(repo.path(), base.as_deref())
+                .unwrap()
+                .is_empty()
+        );
+
+        std::fs::write(repo.path().join("CHANGELOG.md"), "- new\n").unwrap();
+        git(repo.path(), &["add", "CHANGELOG.md"]);
+        commit(repo.path(), "second");
+        std::fs::write(repo.path().join("a.txt"), "two\n").unwrap();
+        std::fs::write(repo.path().join("new.txt"), "").unwrap();
+
+        let mut changed = files_changed_since(repo.path(), base.as_deref()).unwrap();
+        changed.sort();
+        assert_eq!(changed, vec!["CHANGELOG.md", "a.txt", "new.txt"]);
+    }
+
+    #[test]
+    fn diff_since_shows_committed_changes() {
+        let repo = init_repo();
+        assert_eq!(diff_since(repo.path(), None).unwrap(), "");
+
+        std::fs::write(repo.path().join("a.txt"), "hello\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "add a");
+        assert!(diff_since(repo.path(), None).unwrap().contains("+hello"));
+
+        let base = head_commit(repo.path()).unwrap();
+        assert_eq!(diff_since(repo.path(), base.as_deref()).unwrap(), "");
+    }
+
+    #[test]
+    fn diff_between_covers_the_commits_in_range() {
+        let repo = init_repo();
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "first");
+        let first = head_commit(repo.path()).unwrap().unwrap();
+        assert!(
+            diff_between(repo.path(), None, &first)
+                .unwrap()
+                .contains("+one")
+        );
+
+        std::fs::write(repo.path().join("b.txt"), "two\n").unwrap();
+        git(repo.path(), &["add", "b.txt"]);
+        commit(repo.path(), "second");
+        std::fs::write(repo.path().join("c.txt"), "three\n").unwrap();
+        git(repo.path(), &["add", "c.txt"]);
+        commit(repo.path(), "third");
+        let third = head_commit(repo.path()).unwrap().unwrap();
+
+        let own = diff_between(repo.path(), None, &third).unwrap();
+        assert!(own.contains("+three") && !own.contains("+two"));
+        let range = diff_between(repo.path(), Some(&first), &third).unwrap();
+        assert!(range.contains("+two") && range.contains("+three") && !range.contains("+one"));
+    }
+
+    #[test]
+    fn reset_hard_drops_commits_and_edits() {
+        let repo = init_repo();
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "first");
+        let base = head_commit(repo.path()).unwrap().unwrap();
+
+        std::fs::write(repo.path().join("b.txt"), "new\n").unwrap();
+        git(repo.path(), &["add", "b.txt"]);
+        commit(repo.path(), "second");
+        std::fs::write(repo.path().join("a.txt"), "two\n").unwrap();
+
+        reset_hard(repo.path(), &base).unwrap();
+        assert_eq!(
+            head_commit(repo.path()).unwrap().as_deref(),
+            Some(base.as_str())
+        );
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("a.txt")).unwrap(),
+            "one\n"
+        );
+        assert!(!repo.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn reset_keep_drops_commits_but_not_edits() {
+        let repo = init_repo();
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "first");
+        let base = head_commit(repo.path()).unwrap().unwrap();
+
+        std::fs::write(repo.path().join("test.txt"), "new\n").unwrap();
+        git(repo.path(), &["add", "test.txt"]);
+        commit(repo.path(), "tests");
+        std::fs::write(repo.path().join("a.txt"), "two\n").unwrap();
+
+        reset_keep(repo.path(), &base).unwrap();
+        assert_eq!(
+            head_commit(repo.path()).unwrap().as_deref(),
+            Some(base.as_str())
+        );
+        assert!(!repo.path().join("test.txt").exists());
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("a.txt")).unwrap(),
+            "two\n"
+        );
+    }
+
+    #[test]
+    fn worktree_branch_merges_back() {
+        let repo = init_repo();
+        git(repo.path(), &["config", "user.name", "Test"]);
+        git(repo.path(), &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "first");
+        let base = head_commit(repo.path()).unwrap().unwrap();
+
+        let trees = TempDir::new().unwrap();
+        let tree = trees.path().join("worker");
+        add_worktree(repo.path(), &tree, "ralph/worker-1", &base).unwrap();
+        std::fs::write(tree.join("b.txt"), "new\n").unwrap();
+        git(&tree, &["add", "b.txt"]);
+        commit(&tree, "add b");
+        remove_worktree(repo.path(), &tree).unwrap();
+        assert!(!tree.exists());
+
+        assert_eq!(
+            merge(repo.path(), "ralph/worker-1", "Merge worker 1").unwrap(),
+            Merge::Merged
+        );
+        assert!(repo.path().join("b.txt").exists());
+        assert_eq!(commits_since(repo.path(), Some(&base)).unwrap().len(), 2);
+        delete_branch(repo.path(), "ralph/worker-1").unwrap();
+        assert!(!branch_exists(repo.path(), "ralph/worker-1"));
+    }
+
+    #[test]
+    fn merge_aborts_on_conflict() {
+        let repo = init_repo();
+        git(repo.path(), &["config", "user.name", "Test"]);
+        git(repo.path(), &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+        git(repo.path(), &["add", "a.txt"]);
+        commit(repo.path(), "first");
+        git(repo.path(), &["branch", "other"]);
+
+        std::fs::write(repo.path().join("a.txt"), "two\n").unwrap();
+        commit_all(repo.path(), "here");
+        let head = head_commit(repo.path()).unwrap();
+        git(repo.path(), &["checkout", "-q", "other"]);
+        std::fs::write(repo.path().join("a.txt"), "three\n").unwrap();
+        commit_all(repo.path(), "there");
+        git(repo.path(), &["checkout", "-q", "-"]);
+
+        assert_eq!(
+            merge(repo.path(), "other", "Merge other").unwrap(),
+            Merge::Conflict(vec!["a.txt".to_string()])
+        );
+        assert_eq!(head_commit(repo.path()).unwrap(), head);
+        assert!(!has_uncommitted_changes(repo.path()).unwrap());
+    }
+
+    #[test]
+    fn has_uncommitted_changes_sees_untracked_files() {
+        let repo = init_repo();
+        commit(repo.path(), "first");
+        assert!(!has_uncommitted_changes(repo.path()).unwrap());
+
+        std::fs::write(repo.path().join("new.txt"), "hello").unwrap();
+        assert!(has_uncommitted_changes(repo.path()).unwrap());
+    }
+
+    #[test]
+    fn head_commit_errors_outside_repo() {
+        let plain = TempDir::new().unwrap();
+        assert!(head_commit(plain.path()).is_err());
+    }
+
+    #[test]
+    fn checkout_branch_creates_then_reuses() {
+        let repo = init_repo();
+        commit(repo.path(), "first");
+        let original = current_branch(repo.path()).unwrap().unwrap();
+
+        assert_eq!(
+            checkout_branch(repo.path(), "ralph/feature").unwrap(),
+            BranchSwitch::Created
+        );
+        assert_eq!(
+            current_branch(repo.path()).unwrap().as_deref(),
+            Some("ralph/feature")
+        );
+        assert_eq!(
+            checkout_branch(repo.path(), "ralph/feature").unwrap(),
+            BranchSwitch::AlreadyOn
+        );
+
+        git(repo.path(), &["checkout", "-q", &original]);
+        assert_eq!(
+            checkout_branch(repo.path(), "ralph/feature").unwrap(),
+            BranchSwitch::CheckedOut
+        );
+    }
+
+    #[test]
+    fn checkout_branch_works_before_first_commit() {
+        let repo = init_repo();
+        assert_eq!(
+            checkout_branch(repo.path(), "ralph/new").unwrap(),
+            BranchSwitch::Created
+        );
+        assert_eq!(
+            current_branch(repo.path()).unwrap().as_deref(),
+            Some("ralph/new")
+        );
+    }
+
+    #[test]
+    fn current_branch_none_when_detached() {
+        let repo = init_repo();
+        commit(repo.path(), "first");
+        git(repo.path(), &["checkout", "-q", "--detach"]);
+        assert_eq!(current_branch(repo.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn branch_name_validation() {
+        let repo = init_repo();
+        assert!(is_valid_branch_name(repo.path(), "ralph/my-feature"));
+        assert!(!is_valid_branch_name(repo.path(), "bad..name"));
+        assert!(!is_valid_branch_name(repo.path(), "has space"));
+    }
+}
@@ -0,0 +1,564 @@
+//! One build loop iteration: the structured output Claude is held to, running Claude on a
+//! prompt and reading back what it did, plus the task selection the loop is built on.
+//!
+//! The `ralph build` TUI drives these pieces itself so it can redraw and take keys while
+//! Claude works; `run` is the headless equivalent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use tokio::process::Child;
+
+use crate::claude::{self, Thinking};
+use crate::completion::{CompletionCriteria, Detector, IterationSignals};
+use crate::prd::{self, Prd, PrdError, Task};
+use crate::process;
+use crate::toc;
+
+/// Maximum number of retry attempts for transient API errors
+pub const MAX_RETRIES: u32 = 5;
+/// Base delay for exponential backoff (doubles each retry)
+pub const BASE_RETRY_DELAY_SECS: u64 = 5;
+
+/// JSON schema Claude's structured output is held to in a build iteration
+pub const OUTPUT_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "task_number": {"type": "integer"},
+    "status": {"type": "string", "enum": ["completed", "in_progress", "blocked", "skipped"]},
+    "summary": {"type": "string"},
+    "completed_steps": {"type": "array", "items": {"type": "integer"}},
+    "prd_complete": {"type": "boolean"}
+  },
+  "required": ["task_number", "status", "summary", "prd_complete"]
+}"#;
+
+/// Structured output from a build iteration
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BuildIterationOutput {
+    pub task_number: i32,
+    pub status: String,
+    pub summary: String,
+    /// 1-indexed steps of the task that are done (older outputs may omit this)
+    #[serde(default)]
+    pub completed_steps: Vec<u32>,
+    pub prd_complete: bool,
+    /// Claude's free-text final message from the envelope (not part of the schema)
+    #[serde(skip)]
+    pub result_text: String,
+}
+
+/// Claude Code's JSON output wrapper when using --output-format json
+#[derive(Debug, Deserialize)]
+struct ClaudeJsonOutput {
+    #[serde(rename = "type")]
+    #[allow(dead_code)]
+    output_type: String,
+    is_error: bool,
+    structured_output: Option<BuildIterationOutput>,
+    /// Claude's final free-text message
+    #[serde(default)]
+    result: Option<String>,
+    /// Cost of this invocation as reported by Claude Code
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+    /// Token usage for this invocation
+    #[serde(default)]
+    usage: Option<claude::Usage>,
+    /// Per-model breakdown of this invocation, keyed by model name
+    #[serde(default, rename = "modelUsage")]
+    model_usage: BTreeMap<String, ModelUsage>,
+    // Other fields (duration_ms, session_id, etc.) are ignored
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ModelUsage {
+    #[serde(default, rename = "costUSD")]
+    cost_usd: f64,
+}
+
+impl ClaudeJsonOutput {
+    /// The model that accounted for most of the cost
+    fn main_model(&self) -> Option<String> {
+        self.model_usage
+            .iter()
+            .max_by(|a, b| a.1.cost_usd.total_cmp(&b.1.cost_usd))
+            .map(|(model, _)| model.clone())
+    }
+}
+
+/// How an iteration went, judged from Claude's output
+#[derive(Debug)]
+pub enum Outcome {
+    /// Successfully got structured output
+    Success(BuildIterationOutput),
+    /// Claude reported an error in the response
+    ClaudeError(String),
+    /// Transient error that should be retried (API 500, empty output, etc.)
+    TransientError(String),
+    /// Parse error or other non-retryable failure
+    ParseError(String),
+}
+
+/// What one Claude invocation cost
+#[derive(Debug, Default)]
+pub struct Spend {
+    pub cost_usd: f64,
+    pub usage: claude::Usage,
+    /// The model that accounted for most of the cost
+    pub model: Option<String>,
+}
+
+/// A finished iteration's outcome, and its spend when Claude's envelope could be read
+#[derive(Debug)]
+pub struct Report {
+    pub outcome: Outcome,
+    pub spend: Option<Spend>,
+}
+
+/// Check if stderr indicates a retryable API error
+pub fn is_retryable_error(stderr: &str) -> bool {
+    let stderr_lower = stderr.to_lowercase();
+    stderr_lower.contains("500")
+        || stderr_lower.contains("502")
+        || stderr_lower.contains("503")
+        || stderr_lower.contains("504")
+        || stderr_lower.contains("internal server error")
+        || stderr_lower.contains("service unavailable")
+        || stderr_lower.contains("bad gateway")
+        || stderr_lower.contains("gateway timeout")
+        || stderr_lower.contains("overloaded")
+        || stderr_lower.contains("rate limit")
+}
+
+/// Default max turns per Claude session (generous for complex tasks, catches infinite loops)
+pub const DEFAULT_MAX_TURNS: u32 = 200;
+
+/// How Claude is run for an iteration
+#[derive(Debug, Clone, Copy)]
+pub struct Options<'a> {
+    /// Agentic turns before Claude stops
+    pub max_turns: u32,
+    /// Model alias or name; Claude Code's default when None
+    pub model: Option<&'a str>,
+    pub thinking: Option<Thinking>,
+    /// MCP server configs passed as `--mcp-config`
+    pub mcp_config: &'a [String],
+    /// Directories passed as `--add-dir`
+    pub add_dirs: &'a [String],
+    /// Settings file or JSON passed as `--settings`
+    pub settings: Option<&'a str>,
+    /// The only tools Claude may use; when set, permissions aren't bypassed
+    pub allowed_tools: &'a [String],
+    /// Tools Claude may never use
+    pub disallowed_tools: &'a [String],
+    /// Directory Claude works in, e.g. a parallel worker's work tree; the current one when None
+    pub cwd: Option<&'a Path>,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Self {
+            max_turns: DEFAULT_MAX_TURNS,
+            model: None,
+            thinking: None,
+            mcp_config: &[],
+            add_dirs: &[],
+            settings: None,
+            allowed_tools: &[],
+            disallowed_tools: &[],
+            cwd: None,
+        }
+    }
+}
+
+/// Launch Claude on an iteration prompt, held to `OUTPUT_SCHEMA` and with permission prompts
+/// bypassed unless `allowed_tools` restricts it; with nobody to answer a prompt, unlisted tools
+/// are then refused. Read its output with `process::Output` and `parse_output` once it exits. Must be
+/// called on the runtime.
+pub fn launch(prompt: &str, options: &Options) -> io::Result<Child> {
+    claude::launch_claude_with_options(&claude::ClaudeOptions {
+        prompt,
+        permissions: if options.allowed_tools.is_empty() {
+            claude::Permissions::Bypass
+        } else {
+            claude::Permissions::Default
+        },
+        output_format: Some("json"),
+        json_schema: Some(OUTPUT_SCHEMA),
+        max_turns: Some(options.max_turns),
+        model: options.model,
+        thinking: options.thinking,
+        mcp_config: options.mcp_config,
+        add_dirs: options.add_dirs,
+        settings: options.settings,
+        allowed_tools: options.allowed_tools,
+        disallowed_tools: options.disallowed_tools,
+        cwd: options.cwd,
+        ..Default::default()
+    })
+}
+
+/// Run one iteration without a UI: launch Claude, wait for it to exit and read its report.
+/// Dropping the future kills Claude.
+#[tracing::instrument(name = "claude", skip_all, fields(model = options.model, max_turns = options.max_turns))]
+pub async fn run(prompt: &str, options: &Options<'_>) -> io::Result<Report> {
+    let mut child = launch(prompt, options)?;
+    let mut output = process::Output::capture(&mut child);
+    child.wait().await?;
+    let (stdout, stderr) = output.collect().await;
+    Ok(parse_output(&stdout, &stderr))
+}
+
+/// Interpret a finished Claude invocation's output
+pub fn parse_output(stdout: &str, stderr: &str) -> Report {
+    // Check for empty output (often indicates API error)
+    if stdout.trim().is_empty() {
+        let message = if is_retryable_error(stderr) {
+            format!("API error: {}", stderr.trim())
+        } else if !stderr.trim().is_empty() {
+            format!("Empty output with stderr: {}", stderr.trim())
+        } else {
+            "Empty output from Claude".to_string()
+        };
+        return Report {
+            outcome: Outcome::TransientError(message),
+            spend: None,
+        };
+    }
+
+    // Parse JSON wrapper and extract structured_output
+    let wrapper = match serde_json::from_str::<ClaudeJsonOutput>(stdout) {
+        Ok(wrapper) => wrapper,
+        Err(e) => {
+            return Report {
+                outcome: Outcome::ParseError(format!(
+                    "Parse error: {}\n\nRaw output:\n{}",
+                    e, stdout
+                )),
+                spend: None,
+            };
+        }
+    };
+    // Spend counts even for failed iterations - the tokens were still billed
+    let spend = Spend {
+        cost_usd: wrapper.total_cost_usd.unwrap_or(0.0),
+        usage: wrapper.usage.unwrap_or_default(),
+        model: wrapper.main_model(),
+    };
+    let outcome = if let Some(mut result) = wrapper.structured_output {
+        result.result_text = wrapper.result.unwrap_or_default();
+        Outcome::Success(result)
+    } else if wrapper.is_error {
+        // Check if this is a retryable API error
+        if is_retryable_error(stdout) {
+            Outcome::TransientError(format!("Claude API error:\n{}", stdout))
+        } else {
+            Outcome::ClaudeError(stdout.to_string())
+        }
+    } else {
+        Outcome::ParseError(format!("No structured output:\n{}", stdout))
+    };
+    Report {
+        outcome,
+        spend: Some(spend),
+    }
+}
+
+/// Turns the model repairing an iteration's output may take: one to answer through the
+/// schema tool, and one to spare
+pub const REPAIR_MAX_TURNS: u32 = 2;
+
+/// Start repairing output `parse_output` couldn't read: `model` rewrites Claude's raw output
+/// as JSON matching [`OUTPUT_SCHEMA`], reporting like an iteration does, so `parse_output`
+/// reads its result and what it cost. It runs with the read-only profile.
+pub fn launch_repair(stdout: &str, model: &str) -> io::Result<Child> {
+    claude::launch_claude_with_options(&claude::ClaudeOptions {
+        prompt: &claude::normalization_prompt(stdout, OUTPUT_SCHEMA),
+        permissions: claude::Permissions::ReadOnly,
+        output_format: Some("json"),
+        json_schema: Some(OUTPUT_SCHEMA),
+        max_turns: Some(REPAIR_MAX_TURNS),
+        model: Some(model),
+        ..Default::default()
+    })
+}
+
+/// Find a task by description, returning its 0-based index
+pub fn find_task<'a>(prd: &'a Prd, description: &str) -> Option<(usize, &'a Task)> {
+    prd.tasks
+        .iter()
+        .enumerate()
+        .find(|(_, t)| t.description == description)
+}
+
+/// The task the loop is most likely to start with: the first eligible one in task order
+pub fn next_task<'a>(prd: &'a Prd, categories: &[String]) -> Option<(usize, &'a Task)> {
+    next_task_except(prd, categories, &[])
+}
+
+/// Like `next_task`, passing over the tasks with these descriptions
+pub fn next_task_except<'a>(
+    prd: &'a Prd,
+    categories: &[String],
+    skip: &[String],
+) -> Option<(usize, &'a Task)> {
+    toc::build(prd)
+        .iter()
+        .filter(|e| e.status != toc::TocStatus::Done)
+        .map(|e| (e.number - 1, &prd.tasks[e.number - 1]))
+        .filter(|(_, t)| !skip.contains(&t.description))
+        .find(|(_, t)| t.in_categories(categories))
+}
+
+/// Pending tasks in the given categories (all pending tasks if none are given)
+pub fn eligible_pending<'a>(prd: &'a Prd, categories: &[String]) -> Vec<(usize, &'a Task)> {
+    prd.tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| !t.passes && t.in_categories(categories))
+        .collect()
+}
+
+/// Run the configured completion detectors against a finished iteration
+pub fn detect_completion(
+    criteria: &CompletionCriteria,
+    prd_path: &str,
+    result: &BuildIterationOutput,
+) -> Result<Option<Detector>, PrdError> {
+    // Re-read the PRD: Claude has just updated it
+    let prd = prd::read_prd(prd_path)?;
+    let text = format!("{}\n{}", result.result_text, result.summary);
+    Ok(criteria.detect(&IterationSignals {
+        prd_complete: result.prd_complete,
+        text: &text,
+        prd: &prd,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid_build_output() {
+        let json = r#"{"task_number": 1, "status": "completed", "summary": "Added auth", "prd_complete": false}"#;
+        let result: BuildIterationOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(result.task_number, 1);
+        assert_eq!(result.status, "completed");
+        assert!(!result.prd_complete);
+    }
+
+    #[test]
+    fn parse_prd_complete_output() {
+        let json = r#"{"task_number": 5, "status": "completed", "summary": "Final task", "prd_complete": true}"#;
+        let result: BuildIterationOutput = serde_json::from_str(json).unwrap();
+        assert!(result.prd_complete);
+    }
+
+    #[test]
+    fn parse_blocked_status() {
+        let json = r#"{"task_number": 2, "status": "blocked", "summary": "Needs API key", "prd_complete": false}"#;
+        let result: BuildIterationOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(result.status, "blocked");
+    }
+
+    #[test]
+    fn invalid_json_returns_error() {
+        let json = "not valid json";
+        let result = serde_json::from_str::<BuildIterationOutput>(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn build_output_schema_is_valid_json() {
+        let parsed: serde_json::Value = serde_json::from_str(OUTPUT_SCHEMA).unwrap();
+        assert_eq!(parsed["type"], "object");
+    }
+
+    // Tests for Claude Code JSON wrapper format
+    #[test]
+    fn parse_claude_json_wrapper() {
+        // This is the ACTUAL format from `claude --output-format json`
+        let json = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":100,"structured_output":{"task_number":1,"status":"completed","summary":"Did stuff","prd_complete":false}}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(wrapper.output_type, "result");
+        assert!(!wrapper.is_error);
+        let output = wrapper.structured_output.unwrap();
+        assert_eq!(output.task_number, 1);
+        assert_eq!(output.status, "completed");
+    }
+
+    #[test]
+    fn parse_claude_wrapper_with_prd_complete() {
+        let json = r#"{"type":"result","subtype":"success","is_error":false,"structured_output":{"task_number":5,"status":"completed","summary":"Final","prd_complete":true}}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        let output = wrapper.structured_output.unwrap();
+        assert!(output.prd_complete);
+    }
+
+    #[test]
+    fn parse_claude_wrapper_cost_and_usage() {
+        let json = r#"{"type":"result","is_error":false,"total_cost_usd":0.42,"usage":{"input_tokens":120,"output_tokens":3400,"cache_read_input_tokens":50000},"structured_output":null}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(wrapper.total_cost_usd, Some(0.42));
+        let usage = wrapper.usage.unwrap();
+        assert_eq!(usage.input_tokens, 120);
+        assert_eq!(usage.output_tokens, 3400);
+        assert_eq!(usage.cache_read_input_tokens, 50000);
+        assert_eq!(usage.cache_creation_input_tokens, 0);
+    }
+
+    #[test]
+    fn parse_claude_wrapper_main_model() {
+        let json = r#"{"type":"result","is_error":false,"modelUsage":{"claude-haiku":{"costUSD":0.01},"claude-sonnet":{"costUSD":0.9}},"structured_output":null}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(wrapper.main_model().as_deref(), Some("claude-sonnet"));
+    }
+
+    #[test]
+    fn parse_claude_wrapper_without_cost() {
+        let json = r#"{"type":"result","is_error":true}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        assert!(wrapper.total_cost_usd.is_none());
+        assert!(wrapper.usage.is_none());
+    }
+
+    #[test]
+    fn parse_claude_wrapper_error_case() {
+        let json =
+            r#"{"type":"result","subtype":"error","is_error":true,"structured_output":null}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        assert!(wrapper.is_error);
+        assert!(wrapper.structured_output.is_none());
+    }
+
+    #[test]
+    fn parse_real_claude_output_sample() {
+        // Exact sample from actual failure - ensures we don't regress
+        let json = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":386510,"duration_api_ms":283106,"num_turns":46,"result":"","session_id":"b7e6c276-18db-4a9a-b6ae-6a2ecb2d4a33","total_cost_usd":2.7654437499999998,"usage":{"input_tokens":2},"structured_output":{"task_number":1,"status":"completed","summary":"Created modal","prd_complete":false},"uuid":"f2ff63de-7bba-40fe-9072-0e2073d2c663"}"#;
+        let wrapper: ClaudeJsonOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(wrapper.output_type, "result");
+        assert!(!wrapper.is_error);
+        let output = wrapper.structured_output.unwrap();
+        assert_eq!(output.task_number, 1);
+        assert!(!output.prd_complete);
+    }
+
+    #[test]
+    fn parse_completed_steps() {
+        let json = r#"{"task_number": 1, "status": "in_progress", "summary": "Half", "completed_steps": [1, 2], "prd_complete": false}"#;
+        let result: BuildIterationOutput = serde_json::from_str(json).unwrap();
+        assert_eq!(result.completed_steps, vec![1, 2]);
+    }
+
+    #[test]
+    fn find_task_by_description() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "Login", "steps": ["Form", "API"], "passes": false}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(find_task(&prd, "Login").map(|(i, _)| i), Some(0));
+        assert!(find_task(&prd, "Missing").is_none());
+    }
+
+    #[test]
+    fn next_task_respects_categories() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "Login", "steps": [], "passes": false},
+                {"category": "test", "description": "Cover login", "steps": [], "passes": false}
+            ]}"#,
+        )
+        .unwrap();
+        let (index, _) = next_task(&prd, &["TEST".to_string()]).unwrap();
+        assert_eq!(index, 1);
+        assert!(next_task(&prd, &["docs".to_string()]).is_none());
+    }
+
+    #[test]
+    fn next_task_is_first_pending() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "setup", "description": "Done", "steps": [], "passes": true},
+                {"category": "feature", "description": "Login", "steps": [], "passes": false}
+            ]}"#,
+        )
+        .unwrap();
+        let (index, task) = next_task(&prd, &[]).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(task.description, "Login");
+    }
+
+    // Tests for retryable error detection
+    #[test]
+    fn retryable_error_500() {
+        assert!(is_retryable_error("Error: 500 Internal Server Error"));
+        assert!(is_retryable_error("internal server error"));
+    }
+
+    #[test]
+    fn retryable_error_502() {
+        assert!(is_retryable_error("502 Bad Gateway"));
+        assert!(is_retryable_error("bad gateway"));
+    }
+
+    #[test]
+    fn retryable_error_503() {
+        assert!(is_retryable_error("503 Service Unavailable"));
+        assert!(is_retryable_error("service unavailable"));
+    }
+
+    #[test]
+    fn retryable_error_504() {
+        assert!(is_retryable_error("504 Gateway Timeout"));
+        assert!(is_retryable_error("gateway timeout"));
+    }
+
+    #[test]
+    fn retryable_error_overloaded() {
+        assert!(is_retryable_error("API is overloaded"));
+    }
+
+    #[test]
+    fn retryable_error_rate_limit() {
+        assert!(is_retryable_error("rate limit exceeded"));
+    }
+
+    #[test]
+    fn non_retryable_error() {
+        assert!(!is_retryable_error("invalid request"));
+        assert!(!is_retryable_error("authentication failed"));
+        assert!(!is_retryable_error(""));
+    }
+
+    #[test]
+    fn parse_output_reports_result_and_spend() {
+        let json = r#"{"type":"result","is_error":false,"result":"All done","total_cost_usd":0.5,"modelUsage":{"claude-sonnet":{"costUSD":0.5}},"structured_output":{"task_number":2,"status":"completed","summary":"Login","prd_complete":false}}"#;
+        let report = parse_output(json, "");
+        let Outcome::Success(output) = report.outcome else {
+            panic!("expected success, got {:?}", report.outcome);
+        };
+        assert_eq!(output.task_number, 2);
+        assert_eq!(output.result_text, "All done");
+        let spend = report.spend.unwrap();
+        assert_eq!(spend.cost_usd, 0.5);
+        assert_eq!(spend.model.as_deref(), Some("claude-sonnet"));
+    }
+
+    #[test]
+    fn parse_output_retries_empty_output() {
+        let report = parse_output("", "503 Service Unavailable");
+        assert!(
+            matches!(report.outcome, Outcome::TransientError(ref m) if m.starts_with("API error"))
+        );
+        assert!(report.spend.is_none());
+        assert!(matches!(
+            parse_output("not json", "").outcome,
+            Outcome::ParseError(_)
+        ));
+    }
+}
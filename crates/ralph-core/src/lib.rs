@@ -0,0 +1,65 @@
+//! The engine behind the `ralph` CLI, for tools that want to drive the PRD loop themselves
+//! (editor plugins, chat bots) instead of shelling out to the binary.
+//!
+//! - [`prd`]: the PRD file model, reading, validating and saving it.
+//! - [`plan`]: plan sessions, the conversation with Claude that produces a PRD.
+//! - [`claude`]: launching the `claude` CLI and reading its token usage.
+//! - [`iteration`]: one build loop iteration - picking a task, the output contract Claude
+//!   is held to, running it and reading back what it did.
+//! - [`scheduler`]: picking independent tasks for parallel workers; [`schedule`]: when a
+//!   build starts; [`workspace`]: building several PRDs one after another.
+//! - [`gates`], [`verify`], [`done`], [`completion`], [`escalation`]: the checks run after
+//!   an iteration and how a run decides it's finished or stuck; [`gate_fix`] for fixing
+//!   failed gates before moving on.
+//! - [`run_state`], [`stats`], [`loop_log`], [`events`], [`checkpoint`]: what a run records
+//!   as it goes, kept through [`storage`]; [`progress`] for keeping the loops' notes short.
+//!
+//! Waits on child processes are `async`; callers without a runtime of their own can use
+//! [`runtime::block_on`]. Nothing here draws to a terminal or reads the keyboard.
+//!
+//! ```no_run
+//! use ralph_core::{iteration, prd, runtime};
+//!
+//! let prd = prd::read_prd("prd.json").unwrap();
+//! if let Some((index, task)) = iteration::next_task(&prd, &[]) {
+//!     println!("next up: #{} {}", index + 1, task.description);
+//! }
+//! let prompt = ralph_core::prompt::make_prompt("prd.json");
+//! let report = runtime::block_on(iteration::run(&prompt, &iteration::Options::default()));
+//! match report.unwrap().outcome {
+//!     iteration::Outcome::Success(output) => println!("{}: {}", output.status, output.summary),
+//!     other => eprintln!("iteration failed: {:?}", other),
+//! }
+//! ```
+
+pub mod checkpoint;
+pub mod claude;
+pub mod code_review;
+pub mod completion;
+pub mod done;
+pub mod escalation;
+pub mod events;
+pub mod gate_fix;
+pub mod gates;
+pub mod git;
+pub mod iteration;
+pub mod loop_log;
+pub mod markdown_tasks;
+pub mod plan;
+pub mod prd;
+pub mod process;
+pub mod progress;
+pub mod prompt;
+pub mod redact;
+pub mod run_state;
+pub mod runtime;
+pub mod schedule;
+pub mod scheduler;
+pub mod stats;
+pub mod storage;
+pub mod test_failures;
+pub mod test_writer;
+pub mod toc;
+pub mod todos;
+pub mod verify;
+pub mod workspace;
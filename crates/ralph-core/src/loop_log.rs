@@ -0,0 +1,109 @@
+//! A file per build loop with Claude's raw output and what ralph made of it, so a run can
+//! be picked apart after the process has gone. Kept at
+//! `.ralph/logs/<run-id>/loop-<n>.json` next to the PRD.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::prd;
+use crate::storage::{StorageError, storage};
+
+#[derive(Error, Debug)]
+pub enum LoopLogError {
+    #[error("Failed to write loop log: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Failed to parse loop log: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+/// One Claude invocation's output, exactly as it came back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawOutput {
+    pub stdout: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+}
+
+/// Everything one loop produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoopLog {
+    pub run_id: String,
+    pub loop_number: u64,
+    /// The loop's outcome, as recorded in the stats file
+    pub status: String,
+    pub finished_at: DateTime<Utc>,
+    /// Claude's structured reply (task, status, summary, steps), if it gave one
+    #[serde(default)]
+    pub parsed: Option<serde_json::Value>,
+    /// Every Claude invocation of the loop, retries included
+    #[serde(default)]
+    pub attempts: Vec<RawOutput>,
+    /// What the TUI logged during the loop: Claude's reply, gate results, warnings
+    #[serde(default)]
+    pub log: Vec<String>,
+}
+
+impl LoopLog {
+    /// Where the log of `loop_number` in `run_id` is kept
+    pub fn file_path(prd_path: &str, run_id: &str, loop_number: u64) -> PathBuf {
+        prd::state_dir_for(prd_path)
+            .join("logs")
+            .join(run_id)
+            .join(format!("loop-{}.json", loop_number))
+    }
+
+    pub fn save(&self, prd_path: &str) -> Result<(), LoopLogError> {
+        let content = serde_json::to_string_pretty(self)?;
+        storage().save(
+            &Self::file_path(prd_path, &self.run_id, self.loop_number),
+            &content,
+        )?;
+        Ok(())
+    }
+
+    pub fn load(
+        prd_path: &str,
+        run_id: &str,
+        loop_number: u64,
+    ) -> Result<Option<Self>, LoopLogError> {
+        match storage().load(&Self::file_path(prd_path, run_id, loop_number))? {
+            Some(content) => Ok(Some(serde_json::from_str(&content)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn saves_under_the_run_directory() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+        let log = LoopLog {
+            run_id: "run-1".to_string(),
+            loop_number: 3,
+            status: "completed".to_string(),
+            finished_at: Utc::now(),
+            parsed: Some(serde_json::json!({"task_number": 2, "summary": "Added login"})),
+            attempts: vec![RawOutput {
+                stdout: "{\"type\":\"result\"}".to_string(),
+                stderr: String::new(),
+            }],
+            log: vec!["Task #2: \nStatus: completed".to_string()],
+        };
+        log.save(prd_path).unwrap();
+
+        assert!(dir.path().join(".ralph/logs/run-1/loop-3.json").exists());
+        let loaded = LoopLog::load(prd_path, "run-1", 3).unwrap().unwrap();
+        assert_eq!(loaded.parsed.unwrap()["summary"], "Added login");
+        assert_eq!(loaded.attempts, log.attempts);
+        assert!(LoopLog::load(prd_path, "run-1", 4).unwrap().is_none());
+    }
+}
@@ -0,0 +1,221 @@
+//! Turn a Markdown task list into a PRD (`ralph import`).
+//!
+//! The first `#` heading names the PRD and every other heading sets the category of the
+//! tasks below it. Top-level list items become tasks (`- [x]` marks one as passing) and
+//! their indented sub-bullets become steps. Items under a "Quality gates" heading become
+//! the PRD's quality gates instead, and items under "Definition of done" its definition of done.
+
+use crate::prd::{Prd, SCHEMA_VERSION, Task};
+
+/// Category for tasks listed before any heading
+const DEFAULT_CATEGORY: &str = "general";
+
+/// One list item: its indentation, text, and checkbox state if it had one
+struct ListItem<'a> {
+    indent: usize,
+    text: &'a str,
+    checked: Option<bool>,
+}
+
+fn list_item(line: &str) -> Option<ListItem<'_>> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+        .or_else(|| {
+            // Ordered items: "1. " or "1) "
+            let digits = trimmed.find(|c: char| !c.is_ascii_digit())?;
+            if digits == 0 {
+                return None;
+            }
+            trimmed[digits..]
+                .strip_prefix(". ")
+                .or_else(|| trimmed[digits..].strip_prefix(") "))
+        })?;
+
+    let (checked, text) = if let Some(text) = rest.strip_prefix("[ ] ") {
+        (Some(false), text)
+    } else if let Some(text) = rest
+        .strip_prefix("[x] ")
+        .or_else(|| rest.strip_prefix("[X] "))
+    {
+        (Some(true), text)
+    } else {
+        (None, rest)
+    };
+    let text = text.trim();
+    (!text.is_empty()).then_some(ListItem {
+        indent,
+        text,
+        checked,
+    })
+}
+
+/// Heading level and text, e.g. `## Features` -> (2, "Features")
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 {
+        return None;
+    }
+    let text = line[level..]
+        .strip_prefix(' ')?
+        .trim()
+        .trim_end_matches('#');
+    Some((level, text.trim()))
+}
+
+fn is_quality_gates(heading: &str) -> bool {
+    let heading = heading.to_lowercase();
+    heading == "quality gates" || heading == "quality gate"
+}
+
+fn is_definition_of_done(heading: &str) -> bool {
+    heading.eq_ignore_ascii_case("definition of done")
+}
+
+/// Parse Markdown into a PRD; `fallback_name` is used when there's no `#` title
+pub fn parse(markdown: &str, fallback_name: &str) -> Prd {
+    let mut name = None;
+    let mut category = DEFAULT_CATEGORY.to_string();
+    let mut in_gates = false;
+    let mut in_done = false;
+    let mut in_fence = false;
+    let mut quality_gates = Vec::new();
+    let mut definition_of_done = Vec::new();
+    let mut tasks: Vec<Task> = Vec::new();
+    // Indentation of the current task's item, so deeper items become its steps
+    let mut task_indent: Option<usize> = None;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        if let Some((level, text)) = heading(line) {
+            task_indent = None;
+            if level == 1 && name.is_none() {
+                name = Some(text.to_string());
+                continue;
+            }
+            in_gates = is_quality_gates(text);
+            in_done = is_definition_of_done(text);
+            if !in_gates && !in_done {
+                category = text.to_lowercase();
+            }
+            continue;
+        }
+
+        let Some(item) = list_item(line) else {
+            continue;
+        };
+        if in_gates {
+            quality_gates.push(item.text.trim_matches('`').to_string());
+            continue;
+        }
+        if in_done {
+            definition_of_done.push(item.text.to_string());
+            continue;
+        }
+        match (task_indent, tasks.last_mut()) {
+            (Some(indent), Some(task)) if item.indent > indent => {
+                task.steps.push(item.text.to_string());
+            }
+            _ => {
+                task_indent = Some(item.indent);
+                tasks.push(Task {
+                    category: category.clone(),
+                    description: item.text.to_string(),
+                    steps: Vec::new(),
+                    passes: item.checked.unwrap_or(false),
+                    priority: None,
+                    estimate: None,
+                    depends_on: Vec::new(),
+                    thinking: None,
+                    workdir: None,
+                });
+            }
+        }
+    }
+
+    Prd {
+        schema_version: SCHEMA_VERSION,
+        name: name.unwrap_or_else(|| fallback_name.to_string()),
+        quality_gates,
+        workdir: None,
+        definition_of_done,
+        coverage: None,
+        tasks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_headings_checklists_and_steps() {
+        let prd = parse(
+            "# Todo App\n\
+             \n\
+             Some intro text.\n\
+             \n\
+             ## Quality Gates\n\
+             - `cargo test`\n\
+             - cargo clippy\n\
+             \n\
+             ## Definition of Done\n\
+             - CHANGELOG updated\n\
+             \n\
+             ## Feature\n\
+             - [ ] Add login\n\
+             \x20 - Form with email and password\n\
+             \x20 - [x] API call\n\
+             - [x] Add logout\n\
+             \n\
+             ## Docs\n\
+             1. Write README\n",
+            "tasks",
+        );
+        assert_eq!(prd.name, "Todo App");
+        assert_eq!(prd.schema_version, SCHEMA_VERSION);
+        assert_eq!(prd.quality_gates, vec!["cargo test", "cargo clippy"]);
+        assert_eq!(prd.definition_of_done, vec!["CHANGELOG updated"]);
+        assert_eq!(prd.tasks.len(), 3);
+
+        assert_eq!(prd.tasks[0].category, "feature");
+        assert_eq!(prd.tasks[0].description, "Add login");
+        assert_eq!(
+            prd.tasks[0].steps,
+            vec!["Form with email and password", "API call"]
+        );
+        assert!(!prd.tasks[0].passes);
+        assert!(prd.tasks[1].passes);
+        assert_eq!(prd.tasks[2].category, "docs");
+        assert_eq!(prd.tasks[2].description, "Write README");
+    }
+
+    #[test]
+    fn parse_without_title_or_headings_uses_defaults() {
+        let prd = parse("* First\n* Second\n", "tasks");
+        assert_eq!(prd.name, "tasks");
+        assert!(prd.quality_gates.is_empty());
+        assert_eq!(prd.tasks.len(), 2);
+        assert!(prd.tasks.iter().all(|t| t.category == DEFAULT_CATEGORY));
+    }
+
+    #[test]
+    fn parse_skips_code_blocks_and_prose() {
+        let prd = parse(
+            "## Setup\nIntro - not a task\n```\n- not a task either\n```\n- Real task\n",
+            "tasks",
+        );
+        assert_eq!(prd.tasks.len(), 1);
+        assert_eq!(prd.tasks[0].description, "Real task");
+        assert_eq!(prd.tasks[0].category, "setup");
+    }
+}
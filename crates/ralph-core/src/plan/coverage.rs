@@ -0,0 +1,217 @@
+//! Coverage of a finished plan: which parts of the user's request ended up as tasks.
+//!
+//! The request and the user's written answers are split into items (bullets, numbered
+//! lines or sentences), and each item is matched to the tasks sharing most of its
+//! keywords. It's a word-overlap heuristic, meant to point at requests the plan may have
+//! dropped rather than to prove anything.
+
+use super::protocol::Task;
+use crate::prd::{Coverage, CoverageItem};
+
+/// Words too common to say anything about what was asked for
+const STOPWORDS: &[&str] = &[
+    "about", "also", "and", "any", "are", "but", "can", "could", "each", "etc", "every", "for",
+    "from", "has", "have", "how", "into", "its", "just", "like", "make", "more", "need", "needs",
+    "not", "only", "our", "please", "should", "some", "that", "the", "their", "them", "then",
+    "there", "they", "this", "use", "using", "want", "was", "were", "what", "when", "which",
+    "will", "with", "would", "you", "your",
+];
+
+/// Fewest keywords an item needs to count as a request of its own
+const MIN_KEYWORDS: usize = 2;
+
+/// Match the request and answers against the planned tasks
+pub fn check(request: &str, answers: &[&str], tasks: &[Task]) -> Coverage {
+    let task_words: Vec<Vec<String>> = tasks
+        .iter()
+        .map(|task| {
+            let mut text = task.description.clone();
+            for step in &task.steps {
+                text.push(' ');
+                text.push_str(step);
+            }
+            keywords(&text)
+        })
+        .collect();
+
+    let items = requested_items(request, answers)
+        .into_iter()
+        .map(|request| {
+            let wanted = keywords(&request);
+            let needed = wanted.len().div_ceil(2);
+            let tasks = task_words
+                .iter()
+                .enumerate()
+                .filter(|(_, words)| {
+                    wanted
+                        .iter()
+                        .filter(|w| words.iter().any(|t| same_word(w, t)))
+                        .count()
+                        >= needed
+                })
+                .map(|(i, _)| i + 1)
+                .collect();
+            CoverageItem { request, tasks }
+        })
+        .collect();
+    Coverage { items }
+}
+
+/// Split the request and answers into the separate things they ask for
+fn requested_items(request: &str, answers: &[&str]) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    for text in std::iter::once(request).chain(answers.iter().copied()) {
+        for line in text.lines() {
+            let line = line.trim();
+            let candidates = match strip_list_marker(line) {
+                Some(item) => vec![item.to_string()],
+                None => sentences(line),
+            };
+            for item in candidates {
+                if keywords(&item).len() >= MIN_KEYWORDS && !items.contains(&item) {
+                    items.push(item);
+                }
+            }
+        }
+    }
+    items
+}
+
+/// The text of a bullet (`-`, `*`, `+`) or numbered (`1.`, `1)`) line
+fn strip_list_marker(line: &str) -> Option<&str> {
+    if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        return Some(rest.trim());
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(str::trim)
+}
+
+/// Split prose at sentence ends, keeping "e.g." and "main.rs" whole
+fn sentences(line: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        if matches!(c, '.' | '!' | '?' | ';') && chars.peek().is_none_or(|n| n.is_whitespace()) {
+            sentences.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+    sentences
+}
+
+/// Distinct meaningful words, lowercased and with common endings dropped
+fn keywords(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.len() < 3 || STOPWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        let word = stem(&word);
+        if !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn stem(word: &str) -> String {
+    for suffix in ["ing", "ed", "s"] {
+        if let Some(root) = word.strip_suffix(suffix)
+            && root.len() >= 3
+            && !root.ends_with('s')
+        {
+            return root.to_string();
+        }
+    }
+    word.to_string()
+}
+
+/// Same word, or one a longer form of the other ("export" and "exporter")
+fn same_word(a: &str, b: &str) -> bool {
+    a == b || (a.len().min(b.len()) >= 4 && (a.starts_with(b) || b.starts_with(a)))
+}
+
+/// Coverage as shown on the plan review screen
+pub fn report(coverage: &Coverage) -> String {
+    let covered = coverage.items.len() - coverage.uncovered().count();
+    let mut lines = vec![format!(
+        "Coverage: {} of {} requested item(s) have a task",
+        covered,
+        coverage.items.len()
+    )];
+    for item in &coverage.items {
+        if item.tasks.is_empty() {
+            lines.push(format!("  ✗ {} - no matching task", item.request));
+        } else {
+            let tasks: Vec<String> = item.tasks.iter().map(|n| format!("#{}", n)).collect();
+            lines.push(format!("  ✓ {} → {}", item.request, tasks.join(", ")));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(description: &str, steps: &[&str]) -> Task {
+        Task {
+            category: "feature".to_string(),
+            description: description.to_string(),
+            steps: steps.iter().map(|s| s.to_string()).collect(),
+            passes: false,
+        }
+    }
+
+    #[test]
+    fn items_from_lists_sentences_and_answers() {
+        let request = "Build a todo app.\n- Users can export lists to CSV\n2) Dark mode toggle\nOK";
+        let items = requested_items(request, &["B", "Keep the config in main.rs files"]);
+        assert_eq!(
+            items,
+            vec![
+                "Build a todo app.",
+                "Users can export lists to CSV",
+                "Dark mode toggle",
+                "Keep the config in main.rs files",
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_items_without_a_task() {
+        let tasks = [
+            task("Add CSV exporter", &["Export every list to a CSV file"]),
+            task("Todo list storage", &["Save todos"]),
+        ];
+        let coverage = check(
+            "- Users can export lists to CSV\n- Dark mode toggle",
+            &[],
+            &tasks,
+        );
+        assert_eq!(coverage.items[0].tasks, vec![1]);
+        let uncovered: Vec<&str> = coverage.uncovered().map(|i| i.request.as_str()).collect();
+        assert_eq!(uncovered, vec!["Dark mode toggle"]);
+
+        let report = report(&coverage);
+        assert!(report.starts_with("Coverage: 1 of 2 requested item(s) have a task"));
+        assert!(report.contains("✓ Users can export lists to CSV → #1"));
+        assert!(report.contains("✗ Dark mode toggle - no matching task"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Default answers for questions that come up in every plan, set per category in
+//! `.ralph.toml`:
+//!
+//! ```toml
+//! [plan.defaults]
+//! technical.framework = "axum"
+//! quality.coverage = "yes"
+//! ```
+//!
+//! A default applies to a question in its category whose id or text mentions the topic
+//! ("framework"). Its value picks the option with that key or label, or is the typed answer
+//! for a question without options.
+
+use std::collections::BTreeMap;
+
+use super::protocol::Question;
+
+/// Default answers by category, then by topic
+pub type AnswerDefaults = BTreeMap<String, BTreeMap<String, String>>;
+
+/// What a configured default answers `question` with: the key of the matching option, or
+/// the value itself when the question takes a typed answer. None if no default applies, or
+/// its value matches none of the options and typing isn't allowed.
+pub fn answer_for(defaults: &AnswerDefaults, question: &Question) -> Option<String> {
+    let topics = defaults
+        .iter()
+        .find(|(category, _)| category.eq_ignore_ascii_case(&question.category))?
+        .1;
+    let about = format!("{} {}", question.id, question.text);
+    let value = topics
+        .iter()
+        .find(|(topic, _)| mentions(&about, topic))
+        .map(|(_, value)| value.trim())?;
+    match &question.options {
+        Some(options) => options
+            .iter()
+            .find(|o| o.key.eq_ignore_ascii_case(value))
+            .or_else(|| options.iter().find(|o| mentions(&o.label, value)))
+            .map(|o| o.key.clone())
+            .or_else(|| question.allow_freeform.then(|| value.to_string())),
+        None => Some(value.to_string()),
+    }
+}
+
+/// Whether every word of `words` appears in `text`, ignoring case and a plural "s"
+fn mentions(text: &str, words: &str) -> bool {
+    let text = split_words(text);
+    let wanted = split_words(words);
+    !wanted.is_empty()
+        && wanted.iter().all(|w| {
+            text.iter()
+                .any(|t| t == w || t.strip_suffix('s') == Some(w) || w.strip_suffix('s') == Some(t))
+        })
+}
+
+fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::protocol::QuestionOption;
+
+    fn question(id: &str, category: &str, text: &str, options: &[(&str, &str)]) -> Question {
+        Question {
+            id: id.to_string(),
+            category: category.to_string(),
+            text: text.to_string(),
+            context: None,
+            options: (!options.is_empty()).then(|| {
+                options
+                    .iter()
+                    .map(|(key, label)| QuestionOption {
+                        key: key.to_string(),
+                        label: label.to_string(),
+                        description: None,
+                    })
+                    .collect()
+            }),
+            allow_freeform: false,
+        }
+    }
+
+    fn defaults() -> AnswerDefaults {
+        [
+            ("technical", "framework", "axum"),
+            ("quality", "coverage", "yes"),
+            ("scope", "audience", "internal team"),
+        ]
+        .into_iter()
+        .map(|(category, topic, value)| {
+            let topics = BTreeMap::from([(topic.to_string(), value.to_string())]);
+            (category.to_string(), topics)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn picks_the_option_named_by_the_default() {
+        let q = question(
+            "web_framework",
+            "technical",
+            "Which web framework should the API use?",
+            &[("A", "Actix Web"), ("B", "Axum"), ("C", "Rocket")],
+        );
+        assert_eq!(answer_for(&defaults(), &q).as_deref(), Some("B"));
+
+        let q = question(
+            "q3",
+            "Quality",
+            "Should CI enforce test coverage?",
+            &[("Y", "Yes"), ("N", "No")],
+        );
+        assert_eq!(answer_for(&defaults(), &q).as_deref(), Some("Y"));
+    }
+
+    #[test]
+    fn typed_answers_and_misses() {
+        let q = question("audience", "scope", "Who is this for?", &[]);
+        assert_eq!(
+            answer_for(&defaults(), &q).as_deref(),
+            Some("internal team")
+        );
+
+        // Other category, unmatched topic, or a value no option has
+        let q = question("framework", "scope", "Which framework?", &[]);
+        assert_eq!(answer_for(&defaults(), &q), None);
+        let q = question("db", "technical", "Which database?", &[]);
+        assert_eq!(answer_for(&defaults(), &q), None);
+        let mut q = question(
+            "framework",
+            "technical",
+            "Which framework?",
+            &[("A", "Actix Web")],
+        );
+        assert_eq!(answer_for(&defaults(), &q), None);
+        q.allow_freeform = true;
+        assert_eq!(answer_for(&defaults(), &q).as_deref(), Some("axum"));
+    }
+}
@@ -0,0 +1,199 @@
+//! The live feed of a plan turn: Claude's `--output-format stream-json` output turned into
+//! one line per step (a file read, a search, a command, a remark) while the turn runs, and
+//! the final `result` event, which carries the same envelope as `--output-format json` and
+//! tells a failed turn worth retrying from one that isn't.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::iteration::is_retryable_error;
+
+/// Longest remark shown in the feed, in characters
+const MAX_REMARK: usize = 200;
+
+/// Picks complete lines out of output that is still being written
+#[derive(Debug, Default)]
+pub struct LineReader {
+    read: usize,
+}
+
+impl LineReader {
+    /// Lines of `so_far` that are complete and weren't returned before
+    pub fn new_lines<'a>(&mut self, so_far: &'a str) -> impl Iterator<Item = &'a str> {
+        let start = self.read.min(so_far.len());
+        let end = so_far[start..]
+            .rfind('\n')
+            .map_or(start, |newline| start + newline + 1);
+        self.read = end;
+        so_far[start..end].lines()
+    }
+}
+
+/// Feed lines for one line of `stream-json` output: a line per tool Claude calls and per
+/// remark it makes. Paths under `root` are shown relative to it.
+pub fn describe(line: &str, root: &Path) -> Vec<String> {
+    let Ok(event) = serde_json::from_str::<Value>(line) else {
+        return Vec::new();
+    };
+    if event["type"] != "assistant" {
+        return Vec::new();
+    }
+    let Some(content) = event["message"]["content"].as_array() else {
+        return Vec::new();
+    };
+    content
+        .iter()
+        .filter_map(|block| match block["type"].as_str()? {
+            "tool_use" => Some(describe_tool(
+                block["name"].as_str()?,
+                &block["input"],
+                root,
+            )),
+            "text" => remark(block["text"].as_str()?),
+            _ => None,
+        })
+        .collect()
+}
+
+fn describe_tool(name: &str, input: &Value, root: &Path) -> String {
+    let arg = |key: &str| input[key].as_str().unwrap_or_default();
+    let path = |key: &str| {
+        let path = arg(key);
+        Path::new(path)
+            .strip_prefix(root)
+            .map_or(path.to_string(), |p| p.display().to_string())
+    };
+    match name {
+        "Read" => format!("Reading {}", path("file_path")),
+        "Grep" => format!("Searching for `{}`", arg("pattern")),
+        "Glob" => format!("Listing {}", arg("pattern")),
+        "LS" => format!("Listing {}", path("path")),
+        "Bash" => format!("Running `{}`", arg("command")),
+        "WebFetch" => format!("Fetching {}", arg("url")),
+        "WebSearch" => format!("Searching the web for {}", arg("query")),
+        "Task" => format!("Delegating: {}", arg("description")),
+        "StructuredOutput" => "Writing the response".to_string(),
+        other => format!("Using {}", other),
+    }
+}
+
+/// The first line of something Claude said, shortened
+fn remark(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    Some(match line.char_indices().nth(MAX_REMARK) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    })
+}
+
+/// The `result` event of `stream-json` output: the envelope with the structured output and
+/// the turn's cost
+pub fn result(stdout: &str) -> Option<&str> {
+    stdout.lines().rev().find(|line| {
+        serde_json::from_str::<Value>(line).is_ok_and(|event| event["type"] == "result")
+    })
+}
+
+/// Why a finished turn is worth running again, if it failed in a way that usually passes:
+/// no output at all, an API error (5xx, overloaded, rate limited) in the `result` event, or
+/// one on stderr when Claude stopped before reaching its result
+pub fn transient_error(stdout: &str, stderr: &str) -> Option<String> {
+    if stdout.trim().is_empty() {
+        return Some(if is_retryable_error(stderr) {
+            format!("API error: {}", stderr.trim())
+        } else if !stderr.trim().is_empty() {
+            format!("Empty output with stderr: {}", stderr.trim())
+        } else {
+            "Empty output from Claude".to_string()
+        });
+    }
+    match result(stdout) {
+        Some(result) => {
+            let is_error =
+                serde_json::from_str::<Value>(result).is_ok_and(|event| event["is_error"] == true);
+            (is_error && is_retryable_error(result))
+                .then(|| format!("Claude API error:\n{}", result))
+        }
+        None => is_retryable_error(stderr).then(|| format!("API error: {}", stderr.trim())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_tool_calls_and_remarks() {
+        let line = serde_json::json!({
+            "type": "assistant",
+            "message": {"content": [
+                {"type": "text", "text": "\nLet me look at the routes first.\nThen the models."},
+                {"type": "tool_use", "name": "Read", "input": {"file_path": "/repo/src/routes.rs"}},
+                {"type": "tool_use", "name": "Grep", "input": {"pattern": "fn login"}},
+                {"type": "tool_use", "name": "mcp__docs__search", "input": {}},
+            ]}
+        })
+        .to_string();
+        assert_eq!(
+            describe(&line, Path::new("/repo")),
+            vec![
+                "Let me look at the routes first.",
+                "Reading src/routes.rs",
+                "Searching for `fn login`",
+                "Using mcp__docs__search",
+            ]
+        );
+        assert!(describe(r#"{"type":"user","message":{}}"#, Path::new("/")).is_empty());
+        assert!(describe("not json", Path::new("/")).is_empty());
+    }
+
+    #[test]
+    fn reads_complete_lines_as_they_arrive() {
+        let mut reader = LineReader::default();
+        assert_eq!(
+            reader.new_lines("{\"a\":1}\n{\"b\"").collect::<Vec<_>>(),
+            ["{\"a\":1}"]
+        );
+        assert_eq!(reader.new_lines("{\"a\":1}\n{\"b\"").count(), 0);
+        assert_eq!(
+            reader
+                .new_lines("{\"a\":1}\n{\"b\":2}\n")
+                .collect::<Vec<_>>(),
+            ["{\"b\":2}"]
+        );
+    }
+
+    #[test]
+    fn finds_the_result_event() {
+        let stdout = "{\"type\":\"system\"}\n{\"type\":\"result\",\"total_cost_usd\":0.1}\n";
+        assert_eq!(
+            result(stdout),
+            Some("{\"type\":\"result\",\"total_cost_usd\":0.1}")
+        );
+        assert_eq!(result("{\"type\":\"system\"}"), None);
+    }
+
+    #[test]
+    fn recognises_transient_errors() {
+        let overloaded = "{\"type\":\"system\"}\n{\"type\":\"result\",\"is_error\":true,\"result\":\"API Error: 529 Overloaded\"}\n";
+        assert!(
+            transient_error(overloaded, "")
+                .unwrap()
+                .contains("Overloaded")
+        );
+        assert_eq!(
+            transient_error("", "").as_deref(),
+            Some("Empty output from Claude")
+        );
+        assert_eq!(
+            transient_error("{\"type\":\"system\"}\n", "503 Service Unavailable").as_deref(),
+            Some("API error: 503 Service Unavailable")
+        );
+
+        let refused = "{\"type\":\"result\",\"is_error\":true,\"result\":\"Invalid API key\"}";
+        assert_eq!(transient_error(refused, ""), None);
+        let answered = "{\"type\":\"result\",\"is_error\":false,\"result\":\"Overloaded? No\"}";
+        assert_eq!(transient_error(answered, ""), None);
+    }
+}
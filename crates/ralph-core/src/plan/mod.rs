@@ -0,0 +1,12 @@
+//! Plan sessions: the question-and-answer conversation with Claude that turns an idea
+//! into a PRD.
+
+pub mod coverage;
+pub mod defaults;
+pub mod feed;
+pub mod phases;
+pub mod prompts;
+pub mod protocol;
+pub mod session;
+pub mod speculation;
+pub mod templates;
@@ -0,0 +1,425 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::protocol::Answer;
+use super::templates::PlanTemplate;
+
+/// Where a project's planning guidance lives, relative to the project root
+pub const PLAN_PROMPT_FILE: &str = ".ralph/plan-prompt.md";
+
+/// The part of the system prompt ralph depends on: the response schema, what each phase
+/// means and the task fields. Always sent, whatever `.ralph/plan-prompt.md` says.
+pub const RESPONSE_FORMAT: &str = r#"You are Ralph, an AI assistant that generates Product Requirement Documents (PRDs) for software projects.
+
+## Your Response Format
+
+You MUST respond with valid JSON matching this schema. Your response should be ONLY the JSON object, with no markdown code fences or other formatting.
+
+{
+  "phase": "exploring" | "asking" | "working" | "complete",
+  "status": "optional status message",
+  "questions": [...],  // when phase is "asking"
+  "context": {...},    // accumulated findings
+  "prd": {...}         // when phase is "complete"
+}
+
+## Phase Guidelines
+
+### Phase: exploring
+Use when you need to understand the codebase before proceeding.
+- Read key files to understand project structure
+- Identify languages, frameworks, and patterns
+- Set status to describe what you're learning
+
+### Phase: asking
+Use when you genuinely need user input to proceed.
+- Only ask questions when the answer significantly affects the PRD
+- Skip this phase for well-defined, specific tasks
+- Group related questions together (max 4 per turn)
+- Each question needs: id, category, text, allow_freeform
+- Optionally include options for multiple choice
+
+Question categories: "scope", "technical", "quality", "priority"
+
+### Phase: working
+Use when you're generating requirements and tasks.
+- Set status to describe what you're creating
+- Populate context.requirements and context.tasks as you work
+
+### Phase: complete
+Use when the PRD is ready.
+- Include the full prd object with name, quality_gates, definition_of_done, and tasks
+- Each task needs: category, description, steps
+
+## Task Format
+
+Each task in the PRD should have:
+- category: The type of work (e.g., "feature", "bugfix", "refactor", "test", "docs")
+- description: What needs to be done
+- steps: Specific implementation steps
+- passes: Always false initially (set to true when complete)
+"#;
+
+/// The built-in guidance on writing a good PRD, which `.ralph/plan-prompt.md` extends or
+/// replaces
+pub const GUIDELINES: &str = r#"
+## Important Rules
+
+1. **Skip unnecessary phases** - For clear, specific tasks, go directly to working or complete
+2. **Don't over-ask** - Only ask questions when truly needed. "Add a logout button" doesn't need 10 questions.
+3. **Be efficient** - A simple task might complete in 1-2 turns
+4. **Match project conventions** - Use the same testing/build tools the project already uses
+
+## Quality Gates
+
+Include quality gates appropriate for the project:
+- Use the project's existing test/lint/build commands
+- Common gates: "cargo test", "cargo clippy", "cargo fmt --check"
+
+## Definition of Done
+
+Include definition_of_done: what must be true before the whole PRD counts as finished,
+beyond every task passing. Unless the request is trivial, ask one "quality" question about
+it (e.g. changelog entry, docs updated, coverage, manual sign-off). Keep each item short.
+Ralph checks some items itself before accepting completion, so word them that way when they apply:
+- "All quality gates pass"
+- "CHANGELOG updated" (a CHANGELOG file changed during the run)
+- "README or docs updated" (README or docs/ changed during the run)
+- A command in backticks, e.g. "`cargo doc --no-deps` succeeds" (the command exits 0)
+"#;
+
+/// Answer sent for a question that went past `--max-questions` instead of being asked
+pub const DEFERRED_ANSWER: &str = "Not asked: the session's question limit was reached. \
+Decide this from the codebase and the request, and note the assumption in the PRD.";
+
+/// How `.ralph/plan-prompt.md` combines with the built-in guidelines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptMode {
+    /// Add the project's guidance after the built-in guidelines
+    #[default]
+    Extend,
+    /// Use the project's guidance instead of them
+    Replace,
+}
+
+/// The system prompt a planning session starts with: the response format, then the
+/// built-in guidelines, the project's, or both
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemPrompt {
+    /// The project's guidance from `.ralph/plan-prompt.md`
+    pub guidance: Option<String>,
+    pub mode: PromptMode,
+    /// The file the guidance came from
+    pub path: Option<PathBuf>,
+    /// The project's agent docs, attached for Claude to plan by
+    pub docs: Vec<String>,
+}
+
+impl SystemPrompt {
+    /// The project's `.ralph/plan-prompt.md` under `root`, combined as `mode` says, or just
+    /// the built-in prompt if there is none
+    pub fn load(root: &Path, mode: PromptMode) -> io::Result<Self> {
+        let path = root.join(PLAN_PROMPT_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(guidance) => Ok(Self {
+                guidance: Some(guidance.trim().to_string()),
+                mode,
+                path: Some(path),
+                docs: Vec::new(),
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The prompt's text
+    pub fn text(&self) -> String {
+        let mut text = match (&self.guidance, self.mode) {
+            (None, _) => format!("{RESPONSE_FORMAT}{GUIDELINES}"),
+            (Some(guidance), PromptMode::Extend) => {
+                format!("{RESPONSE_FORMAT}{GUIDELINES}\n## Project Guidelines\n\n{guidance}\n")
+            }
+            (Some(guidance), PromptMode::Replace) => {
+                format!("{RESPONSE_FORMAT}\n## Guidelines\n\n{guidance}\n")
+            }
+        };
+        if !self.docs.is_empty() {
+            let mentions: Vec<String> = self.docs.iter().map(|doc| format!("@{doc}")).collect();
+            text.push_str(&format!(
+                "\n## Project Conventions\n\nThe project documents its conventions in {}. \
+Plan tasks and quality gates that follow them.\n",
+                mentions.join(" ")
+            ));
+        }
+        text
+    }
+}
+
+/// Build the initial prompt for a new planning session, with the template's guidance and
+/// the question limit if there are any
+pub fn build_initial_prompt(
+    user_request: &str,
+    max_questions: Option<u32>,
+    system: &SystemPrompt,
+    template: Option<&PlanTemplate>,
+) -> String {
+    let system = system.text();
+    let template = template
+        .map(|t| format!("\n{}", t.prompt_section()))
+        .unwrap_or_default();
+    let limit = max_questions
+        .map(|max| format!("\n{}\n", question_limit(max, 0)))
+        .unwrap_or_default();
+    format!(
+        r#"{system}{template}{limit}
+## User Request
+
+{user_request}
+
+Begin by exploring the codebase to understand the project structure, then proceed based on your judgment."#
+    )
+}
+
+/// The `--max-questions` rule, with how many of the questions are left after `asked`
+pub fn question_limit(max: u32, asked: u32) -> String {
+    format!(
+        "## Question Limit\n\nAsk at most {max} questions in the whole session; {} remain. \
+Ask only the ones that matter most. Questions past the limit are not shown to the user: \
+they come back answered \"{DEFERRED_ANSWER}\"",
+        max.saturating_sub(asked)
+    )
+}
+
+/// Build a continuation prompt with user answers
+pub fn build_continuation_prompt(answers: &[Answer]) -> String {
+    if answers.is_empty() {
+        return "Continue with the PRD generation.".to_string();
+    }
+
+    let mut prompt = String::from("User provided the following answers:\n\n");
+
+    for answer in answers {
+        prompt.push_str(&format!("- {}: {}\n", answer.question_id, answer.value));
+    }
+
+    prompt.push_str("\nContinue with the PRD generation based on these answers.");
+    prompt
+}
+
+/// Build the prompt sent when the user reviews the final PRD and asks for changes.
+/// `prd_json` is the PRD as reviewed, including any descriptions the user edited.
+pub fn build_feedback_prompt(feedback: &str, prd_json: &str) -> String {
+    format!(
+        r#"The user reviewed the PRD before it was written and wants changes:
+
+{feedback}
+
+This is the PRD as they left it (they may have edited task descriptions; keep those edits unless the feedback says otherwise):
+
+```json
+{prd_json}
+```
+
+Revise the PRD to address the feedback. Respond with phase "complete" and the full revised PRD."#
+    )
+}
+
+/// Build the prompt for a speculative background turn sent while the user is still answering.
+/// The turn runs in a forked session, so it must not finalize anything.
+pub fn build_speculative_prompt(partial_answers: &[Answer]) -> String {
+    let mut prompt = String::from(
+        "SPECULATIVE TURN: the user is still answering your questions. Answers so far:\n\n",
+    );
+
+    for answer in partial_answers {
+        prompt.push_str(&format!("- {}: {}\n", answer.question_id, answer.value));
+    }
+
+    prompt.push_str(
+        "\nUse these partial answers to continue exploring the codebase and drafting requirements \
+so the next turn is faster. Do NOT ask new questions and do NOT produce the final PRD - \
+respond with phase \"exploring\" or \"working\". The remaining answers will follow.",
+    );
+    prompt
+}
+
+/// Build a prompt to resume an interrupted session
+pub fn build_resume_prompt(turn_count: u32, last_phase: &str) -> String {
+    format!(
+        r#"This is a resumed session. Previous state:
+- Turns completed: {turn_count}
+- Last phase: {last_phase}
+
+Continue from where we left off. Respond with your current phase and any questions or the final PRD."#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feedback_prompt_carries_feedback_and_prd() {
+        let prompt = build_feedback_prompt("Split task 2", r#"{"name": "Bookmarks"}"#);
+        assert!(prompt.contains("Split task 2"));
+        assert!(prompt.contains(r#"{"name": "Bookmarks"}"#));
+        assert!(prompt.contains("\"complete\""));
+    }
+
+    #[test]
+    fn system_prompt_contains_phase_keywords() {
+        assert!(RESPONSE_FORMAT.contains("exploring"));
+        assert!(RESPONSE_FORMAT.contains("asking"));
+        assert!(RESPONSE_FORMAT.contains("working"));
+        assert!(RESPONSE_FORMAT.contains("complete"));
+    }
+
+    #[test]
+    fn system_prompt_contains_json_format() {
+        assert!(RESPONSE_FORMAT.contains("JSON"));
+        assert!(RESPONSE_FORMAT.contains("phase"));
+    }
+
+    #[test]
+    fn build_initial_prompt_includes_user_request() {
+        let request = "Add user authentication";
+        let prompt = build_initial_prompt(request, None, &SystemPrompt::default(), None);
+        assert!(prompt.contains(request));
+        assert!(prompt.contains(RESPONSE_FORMAT));
+        assert!(prompt.contains(GUIDELINES));
+        assert!(prompt.contains("User Request"));
+        assert!(!prompt.contains("Question Limit"));
+    }
+
+    #[test]
+    fn build_initial_prompt_states_the_question_limit() {
+        let prompt = build_initial_prompt(
+            "Add a logout button",
+            Some(3),
+            &SystemPrompt::default(),
+            None,
+        );
+        let limit = prompt.find("Ask at most 3 questions").unwrap();
+        assert!(limit < prompt.find("## User Request").unwrap());
+        assert!(question_limit(3, 2).contains("1 remain"));
+        assert!(question_limit(3, 5).contains("0 remain"));
+    }
+
+    #[test]
+    fn build_initial_prompt_adds_the_template() {
+        let template = PlanTemplate::parse(
+            "cli-tool",
+            "Keep output scriptable.\n\n## Quality gates\n- make test\n",
+        );
+        let prompt = build_initial_prompt(
+            "Add a --json flag",
+            None,
+            &SystemPrompt::default(),
+            Some(&template),
+        );
+        let section = prompt.find("## Template: cli-tool").unwrap();
+        assert!(section > prompt.find("## Definition of Done").unwrap());
+        assert!(section < prompt.find("## User Request").unwrap());
+        assert!(prompt.contains("Keep output scriptable."));
+        assert!(prompt.contains("- make test"));
+    }
+
+    #[test]
+    fn project_guidance_extends_or_replaces_the_guidelines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let missing = SystemPrompt::load(dir.path(), PromptMode::Replace).unwrap();
+        assert_eq!(missing, SystemPrompt::default());
+
+        std::fs::create_dir_all(dir.path().join(".ralph")).unwrap();
+        std::fs::write(
+            dir.path().join(PLAN_PROMPT_FILE),
+            "Write acceptance criteria as Given/When/Then.\n",
+        )
+        .unwrap();
+        let extended = SystemPrompt::load(dir.path(), PromptMode::Extend)
+            .unwrap()
+            .text();
+        assert!(extended.starts_with(RESPONSE_FORMAT));
+        assert!(extended.contains(GUIDELINES));
+        assert!(
+            extended.ends_with(
+                "## Project Guidelines\n\nWrite acceptance criteria as Given/When/Then.\n"
+            )
+        );
+
+        let replaced = SystemPrompt::load(dir.path(), PromptMode::Replace)
+            .unwrap()
+            .text();
+        assert!(replaced.starts_with(RESPONSE_FORMAT));
+        assert!(!replaced.contains("## Important Rules"));
+        assert!(replaced.contains("Given/When/Then"));
+
+        let with_docs = SystemPrompt {
+            docs: vec!["CLAUDE.md".to_string(), "AGENTS.md".to_string()],
+            ..SystemPrompt::default()
+        };
+        assert!(
+            with_docs
+                .text()
+                .contains("conventions in @CLAUDE.md @AGENTS.md.")
+        );
+    }
+
+    #[test]
+    fn build_continuation_prompt_empty_answers() {
+        let prompt = build_continuation_prompt(&[]);
+        assert_eq!(prompt, "Continue with the PRD generation.");
+    }
+
+    #[test]
+    fn build_continuation_prompt_with_answers() {
+        let answers = vec![
+            Answer {
+                question_id: "q1".to_string(),
+                value: "React".to_string(),
+            },
+            Answer {
+                question_id: "q2".to_string(),
+                value: "PostgreSQL".to_string(),
+            },
+        ];
+        let prompt = build_continuation_prompt(&answers);
+        assert!(prompt.contains("q1: React"));
+        assert!(prompt.contains("q2: PostgreSQL"));
+        assert!(prompt.contains("User provided the following answers"));
+        assert!(prompt.contains("Continue with the PRD generation based on these answers"));
+    }
+
+    #[test]
+    fn build_speculative_prompt_lists_partial_answers() {
+        let answers = vec![Answer {
+            question_id: "q1".to_string(),
+            value: "React".to_string(),
+        }];
+        let prompt = build_speculative_prompt(&answers);
+        assert!(prompt.starts_with("SPECULATIVE TURN"));
+        assert!(prompt.contains("- q1: React"));
+        assert!(prompt.contains("do NOT produce the final PRD"));
+    }
+
+    #[test]
+    fn build_resume_prompt_includes_turn_count() {
+        let prompt = build_resume_prompt(5, "asking");
+        assert!(prompt.contains("Turns completed: 5"));
+        assert!(prompt.contains("Last phase: asking"));
+        assert!(prompt.contains("resumed session"));
+    }
+
+    #[test]
+    fn build_resume_prompt_different_phases() {
+        let prompt = build_resume_prompt(0, "exploring");
+        assert!(prompt.contains("Last phase: exploring"));
+
+        let prompt = build_resume_prompt(10, "working");
+        assert!(prompt.contains("Turns completed: 10"));
+        assert!(prompt.contains("Last phase: working"));
+    }
+}
@@ -131,9 +131,21 @@ pub struct Task {
 /// The final PRD output
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FinalPrd {
+    /// Freshly planned PRDs are always in the current format
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
     pub name: String,
     pub quality_gates: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub definition_of_done: Vec<String>,
     pub tasks: Vec<Task>,
+    /// Filled in by ralph after planning, not by Claude
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<crate::prd::Coverage>,
+}
+
+fn current_schema_version() -> u32 {
+    crate::prd::SCHEMA_VERSION
 }
 
 /// An answer to a question
@@ -186,6 +198,7 @@ pub const PLAN_RESPONSE_SCHEMA: &str = r#"{
       "properties": {
         "name": { "type": "string" },
         "quality_gates": { "type": "array", "items": { "type": "string" } },
+        "definition_of_done": { "type": "array", "items": { "type": "string" } },
         "tasks": {
           "type": "array",
           "items": {
@@ -272,6 +285,7 @@ mod tests {
         assert_eq!(prd.quality_gates.len(), 2);
         assert_eq!(prd.tasks.len(), 1);
         assert!(!prd.tasks[0].passes);
+        assert_eq!(prd.schema_version, crate::prd::SCHEMA_VERSION);
     }
 
     #[test]
@@ -5,7 +5,8 @@ use thiserror::Error;
 use uuid::Uuid;
 
 use super::phases::PlanPhase;
-use super::protocol::{Answer, PhaseContext};
+use super::protocol::{Answer, PhaseContext, Question};
+use crate::storage::{StorageError, storage};
 
 #[derive(Error, Debug)]
 pub enum SessionError {
@@ -15,12 +16,39 @@ pub enum SessionError {
     #[error("Failed to parse session file: {0}")]
     ParseError(#[from] serde_json::Error),
 
+    #[error("Failed to access session storage: {0}")]
+    Storage(#[from] StorageError),
+
     #[error(
         "Session file exists but --resume not specified. Use --resume to continue or --force to overwrite."
     )]
     SessionExists,
 }
 
+/// A round of questions from Claude, kept until Claude has the answers so `--resume`
+/// reopens the questionnaire where it was left
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuestionRound {
+    /// Questions shown to the user
+    pub questions: Vec<Question>,
+
+    /// Questions past `--max-questions`, answered for the user when the round is sent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deferred: Vec<Question>,
+
+    /// Answers given so far
+    #[serde(default)]
+    pub answers: Vec<Answer>,
+
+    /// Index of the question the user was on
+    #[serde(default)]
+    pub current: usize,
+
+    /// Answers were submitted and are on their way to Claude
+    #[serde(default)]
+    pub submitted: bool,
+}
+
 /// Persistent session state for multi-turn PRD generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanSession {
@@ -40,10 +68,26 @@ pub struct PlanSession {
     #[serde(default)]
     pub context: PhaseContext,
 
+    /// The request the session started from, for the coverage check at the end
+    #[serde(default)]
+    pub request: Option<String>,
+
     /// All collected answers
     #[serde(default)]
     pub answers: Vec<Answer>,
 
+    /// Questions shown to the user so far, counted against `--max-questions`
+    #[serde(default)]
+    pub questions_asked: u32,
+
+    /// Cumulative API cost of all turns so far (USD)
+    #[serde(default)]
+    pub total_cost_usd: f64,
+
+    /// The round of questions being answered, until Claude has the answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round: Option<QuestionRound>,
+
     /// Session creation time
     pub created_at: DateTime<Utc>,
 
@@ -61,7 +105,11 @@ impl PlanSession {
             last_phase: PlanPhase::Exploring,
             turn_count: 0,
             context: PhaseContext::default(),
+            request: None,
             answers: Vec::new(),
+            questions_asked: 0,
+            total_cost_usd: 0.0,
+            round: None,
             created_at: now,
             updated_at: now,
         }
@@ -82,37 +130,37 @@ impl PlanSession {
     ) -> Result<Self, SessionError> {
         let session_path = Self::session_file_path(output_path);
 
-        if session_path.exists() {
-            if resume {
+        match storage().load(&session_path)? {
+            Some(content) if resume => {
                 // Load existing session
-                let content = std::fs::read_to_string(&session_path)?;
                 let session: PlanSession = serde_json::from_str(&content)?;
                 Ok(session)
-            } else if force {
+            }
+            Some(_) if force => {
                 // Delete old session file before creating new to avoid Claude session ID conflicts
-                let _ = std::fs::remove_file(&session_path);
+                let _ = storage().delete(&session_path);
                 Ok(Self::new(output_path))
-            } else {
-                // Session exists but neither resume nor force specified
-                Err(SessionError::SessionExists)
             }
-        } else {
+            // Session exists but neither resume nor force specified
+            Some(_) => Err(SessionError::SessionExists),
             // No existing session, create new
-            Ok(Self::new(output_path))
+            None => Ok(Self::new(output_path)),
+        }
+    }
+
+    /// Load the saved session for an output path, if there is one
+    pub fn load(output_path: &str) -> Result<Option<Self>, SessionError> {
+        match storage().load(&Self::session_file_path(output_path))? {
+            Some(content) => Ok(Some(serde_json::from_str(&content)?)),
+            None => Ok(None),
         }
     }
 
     /// Save the session to disk
     pub fn save(&self) -> Result<(), SessionError> {
         let session_path = Self::session_file_path(&self.output_path);
-
-        // Ensure parent directory exists
-        if let Some(parent) = session_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&session_path, content)?;
+        storage().save(&session_path, &content)?;
         Ok(())
     }
 
@@ -129,6 +177,24 @@ impl PlanSession {
         self.updated_at = Utc::now();
     }
 
+    /// Record progress on the current round of questions
+    pub fn update_round(&mut self, answers: &[Answer], current: usize) {
+        if let Some(round) = &mut self.round {
+            round.answers = answers.to_vec();
+            round.current = current;
+            self.updated_at = Utc::now();
+        }
+    }
+
+    /// Claude has the round's answers: keep them with the session's and drop the round
+    pub fn finish_round(&mut self) {
+        if let Some(round) = self.round.take() {
+            for answer in round.answers {
+                self.add_answer(answer);
+            }
+        }
+    }
+
     /// Merge context from a response
     /// Since context fields are now flexible serde_json::Value, we just replace.
     pub fn merge_context(&mut self, context: PhaseContext) {
@@ -161,11 +227,8 @@ impl PlanSession {
     }
 
     /// Delete the session file
-    pub fn cleanup(&self) -> Result<(), std::io::Error> {
-        let session_path = Self::session_file_path(&self.output_path);
-        if session_path.exists() {
-            std::fs::remove_file(session_path)?;
-        }
+    pub fn cleanup(&self) -> Result<(), SessionError> {
+        storage().delete(&Self::session_file_path(&self.output_path))?;
         Ok(())
     }
 
@@ -207,7 +270,7 @@ mod tests {
     #[test]
     fn session_file_path_calculation() {
         let path = PlanSession::session_file_path("/some/dir/prd.json");
-        assert_eq!(path.to_str().unwrap(), "/some/dir/.ralph-session.json");
+        assert_eq!(path, Path::new("/some/dir").join(".ralph-session.json"));
     }
 
     #[test]
@@ -325,6 +388,35 @@ mod tests {
         assert_eq!(loaded.answers.len(), 1);
     }
 
+    #[test]
+    fn question_round_survives_a_save_until_finished() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        let prd_path_str = prd_path.to_str().unwrap();
+        let answer = |id: &str| Answer {
+            question_id: id.to_string(),
+            value: "yes".to_string(),
+        };
+
+        let mut session = PlanSession::new(prd_path_str);
+        session.update_round(&[answer("ignored")], 1);
+        assert!(session.round.is_none());
+
+        session.round = Some(QuestionRound::default());
+        session.update_round(&[answer("q1")], 1);
+        session.save().unwrap();
+
+        let mut loaded = PlanSession::load_or_create(prd_path_str, true, false).unwrap();
+        let round = loaded.round.as_ref().unwrap();
+        assert_eq!(round.current, 1);
+        assert_eq!(round.answers[0].question_id, "q1");
+        assert!(loaded.answers.is_empty());
+
+        loaded.finish_round();
+        assert!(loaded.round.is_none());
+        assert_eq!(loaded.answers.len(), 1);
+    }
+
     #[test]
     fn load_or_create_without_resume_or_force_errors() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,175 @@
+use serde::Deserialize;
+use std::future::pending;
+use tokio::process::Child;
+
+use super::prompts::build_speculative_prompt;
+use super::protocol::{Answer, PLAN_RESPONSE_SCHEMA};
+use crate::claude::{ClaudeOptions, Permissions, launch_claude_with_options};
+use crate::process;
+
+/// The only part of Claude's JSON envelope a speculative turn cares about
+#[derive(Deserialize)]
+struct ForkOutput {
+    session_id: Option<String>,
+    #[serde(default)]
+    is_error: bool,
+}
+
+/// A speculative background turn sent while the user is still answering questions.
+///
+/// The turn resumes the plan session with `--fork-session`, so the real session is never
+/// touched. If the fork finishes before the user submits, the next turn continues from the
+/// fork (which already holds the pre-work); otherwise it is killed and discarded.
+#[derive(Debug, Default)]
+pub enum Speculation {
+    /// Nothing started for this question round
+    #[default]
+    Idle,
+    /// Forked Claude process still running
+    Running {
+        child: Child,
+        output: process::Output,
+    },
+    /// Fork finished; holds the forked session ID to continue from
+    Ready(String),
+    /// Cancelled by the user or superseded by submission
+    Cancelled,
+    /// Fork exited without a usable session
+    Failed,
+}
+
+impl Speculation {
+    /// Launch a forked turn with the answers collected so far. Must be called on the runtime.
+    pub fn start(session_id: &str, partial_answers: &[Answer]) -> Self {
+        let prompt = build_speculative_prompt(partial_answers);
+        let spawned = launch_claude_with_options(&ClaudeOptions {
+            prompt: &prompt,
+            resume_session_id: Some(session_id),
+            fork_session: true,
+            json_schema: Some(PLAN_RESPONSE_SCHEMA),
+            permissions: Permissions::ReadOnly,
+            output_format: Some("json"),
+            ..Default::default()
+        });
+        match spawned {
+            Ok(mut child) => {
+                let output = process::Output::capture(&mut child);
+                Speculation::Running { child, output }
+            }
+            Err(_) => Speculation::Failed,
+        }
+    }
+
+    /// Wait for a running fork to finish, moving to Ready or Failed. Never resolves if no fork
+    /// is running, and can be cancelled in `select!` without losing the fork.
+    pub async fn finished(&mut self) {
+        let Speculation::Running { child, output } = self else {
+            return pending().await;
+        };
+        let session_id = match child.wait().await {
+            Ok(_) => parse_forked_session_id(&output.collect().await.0),
+            Err(_) => None,
+        };
+        *self = session_id.map_or(Speculation::Failed, Speculation::Ready);
+    }
+
+    /// Kill a running fork; discard a finished one. The runtime reaps a killed fork.
+    pub fn cancel(&mut self) {
+        if let Speculation::Running { child, .. } = self {
+            let _ = process::kill_tree(child);
+        }
+        if !matches!(self, Speculation::Idle) {
+            *self = Speculation::Cancelled;
+        }
+    }
+
+    /// Whether nothing has been attempted yet this round
+    pub fn is_idle(&self) -> bool {
+        matches!(self, Speculation::Idle)
+    }
+
+    /// Take the forked session ID if the fork finished successfully
+    pub fn take_ready(&mut self) -> Option<String> {
+        match std::mem::take(self) {
+            Speculation::Ready(id) => Some(id),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+
+    /// Short label for the TUI header, if anything is happening
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            Speculation::Idle => None,
+            Speculation::Running { .. } => Some("pre-working (x=cancel)"),
+            Speculation::Ready(_) => Some("pre-work ready"),
+            Speculation::Cancelled => Some("pre-work cancelled"),
+            Speculation::Failed => Some("pre-work failed"),
+        }
+    }
+}
+
+/// Extract the forked session ID from Claude's JSON envelope
+fn parse_forked_session_id(stdout: &str) -> Option<String> {
+    let output: ForkOutput = serde_json::from_str(stdout.trim()).ok()?;
+    if output.is_error {
+        return None;
+    }
+    output.session_id.filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_session_id_from_envelope() {
+        let json = r#"{"type":"result","is_error":false,"session_id":"abc-123","structured_output":{"phase":"working"}}"#;
+        assert_eq!(parse_forked_session_id(json), Some("abc-123".to_string()));
+    }
+
+    #[test]
+    fn parse_session_id_rejects_errors() {
+        let json = r#"{"type":"result","is_error":true,"session_id":"abc-123"}"#;
+        assert!(parse_forked_session_id(json).is_none());
+    }
+
+    #[test]
+    fn parse_session_id_rejects_garbage() {
+        assert!(parse_forked_session_id("not json").is_none());
+        assert!(parse_forked_session_id(r#"{"session_id":""}"#).is_none());
+    }
+
+    #[test]
+    fn take_ready_only_consumes_ready() {
+        let mut spec = Speculation::Ready("fork-1".to_string());
+        assert_eq!(spec.take_ready(), Some("fork-1".to_string()));
+        assert!(spec.is_idle());
+
+        let mut failed = Speculation::Failed;
+        assert!(failed.take_ready().is_none());
+        assert!(matches!(failed, Speculation::Failed));
+    }
+
+    #[test]
+    fn cancel_idle_stays_idle() {
+        let mut spec = Speculation::Idle;
+        spec.cancel();
+        assert!(spec.is_idle());
+
+        let mut ready = Speculation::Ready("fork-1".to_string());
+        ready.cancel();
+        assert!(matches!(ready, Speculation::Cancelled));
+    }
+
+    #[test]
+    fn labels() {
+        assert!(Speculation::Idle.label().is_none());
+        assert_eq!(
+            Speculation::Ready("x".to_string()).label(),
+            Some("pre-work ready")
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Plan templates: extra guidance for Claude and default quality gates for a kind of
+//! project, picked with `ralph plan --template <name>`.
+//!
+//! A template is Markdown. Its `## Quality gates` section lists the default gates, one per
+//! bullet; everything else is guidance added to the system prompt. Besides the built-in
+//! templates, `.ralph/templates/<name>.md` in the project adds one, or replaces the
+//! built-in of that name.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+/// Where project templates live, relative to the project root
+pub const DIR: &str = ".ralph/templates";
+
+const QUALITY_GATES_HEADING: &str = "## Quality gates";
+
+const BUILT_IN: &[(&str, &str)] = &[
+    (
+        "web-app",
+        "This is a web application. Split work into vertical slices that each deliver a \
+working page or endpoint, with the data model, API and UI changes together. Include tasks \
+for input validation, error states and loading states, and for tests of the API and the \
+main user flows. Note any migrations and environment variables the PRD needs.
+
+## Quality gates
+- Run the project's test suite
+- Run the project's linter
+- Build the production bundle
+",
+    ),
+    (
+        "cli-tool",
+        "This is a command-line tool. Settle the commands, flags and their defaults before \
+the implementation tasks, and keep output scriptable: stable formats, meaningful exit codes, \
+errors on stderr. Include tasks for `--help` text, for tests that run the binary end to end, \
+and for documenting every new flag in the README.
+
+## Quality gates
+- Run the project's test suite
+- Run the project's linter
+",
+    ),
+    (
+        "library",
+        "This is a library. Design the public API first and keep it small; every public \
+item needs documentation with an example. Avoid breaking changes unless the request asks \
+for them, and call out any that remain. Include tasks for unit tests of each public \
+function and for a changelog entry.
+
+## Quality gates
+- Run the project's test suite
+- Build the documentation without warnings
+- Run the project's linter
+",
+    ),
+    (
+        "bugfix-sprint",
+        "This is a bugfix sprint. Make one task per bug, with category \"bugfix\". Each task \
+starts with a step that reproduces the bug in a failing test, then fixes it, then checks \
+nothing else regressed. Do not add features or refactor beyond what a fix needs. Order \
+tasks by severity.
+
+## Quality gates
+- Run the project's test suite
+",
+    ),
+];
+
+/// A plan template, built in or from the project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanTemplate {
+    pub name: String,
+    /// Added to the system prompt
+    pub guidance: String,
+    /// Quality gates Claude starts the PRD from, as commands or descriptions of one
+    pub quality_gates: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum TemplateError {
+    #[error("Unknown plan template '{name}'; available: {}", available.join(", "))]
+    Unknown {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("Could not read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+impl PlanTemplate {
+    /// Parse a template's Markdown
+    pub fn parse(name: &str, markdown: &str) -> Self {
+        let mut guidance = Vec::new();
+        let mut quality_gates = Vec::new();
+        let mut in_gates = false;
+        for line in markdown.lines() {
+            if line.starts_with("## ") {
+                in_gates = line.trim_end().eq_ignore_ascii_case(QUALITY_GATES_HEADING);
+                if in_gates {
+                    continue;
+                }
+            }
+            if !in_gates {
+                guidance.push(line);
+                continue;
+            }
+            let item = line.trim_start();
+            if let Some(gate) = item.strip_prefix("- ").or_else(|| item.strip_prefix("* ")) {
+                let gate = gate.trim().trim_matches('`').trim();
+                if !gate.is_empty() {
+                    quality_gates.push(gate.to_string());
+                }
+            }
+        }
+        Self {
+            name: name.to_string(),
+            guidance: guidance.join("\n").trim().to_string(),
+            quality_gates,
+        }
+    }
+
+    /// The template's section of the system prompt
+    pub fn prompt_section(&self) -> String {
+        let mut section = format!("## Template: {}\n\n{}\n", self.name, self.guidance);
+        if !self.quality_gates.is_empty() {
+            section.push_str(
+                "\nStart the PRD's quality gates from these, using the project's own command \
+for each:\n",
+            );
+            for gate in &self.quality_gates {
+                section.push_str(&format!("- {}\n", gate));
+            }
+        }
+        section
+    }
+}
+
+/// Find the template called `name`: the project's `.ralph/templates/<name>.md` under
+/// `root` if there is one, otherwise the built-in
+pub fn load(root: &Path, name: &str) -> Result<PlanTemplate, TemplateError> {
+    let path = root.join(DIR).join(format!("{}.md", name));
+    match std::fs::read_to_string(&path) {
+        Ok(markdown) => return Ok(PlanTemplate::parse(name, &markdown)),
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(TemplateError::Io {
+                path: path.display().to_string(),
+                source: e,
+            });
+        }
+        Err(_) => {}
+    }
+    BUILT_IN
+        .iter()
+        .find(|(built_in, _)| *built_in == name)
+        .map(|(name, markdown)| PlanTemplate::parse(name, markdown))
+        .ok_or_else(|| TemplateError::Unknown {
+            name: name.to_string(),
+            available: available(root),
+        })
+}
+
+/// Names of every template: built in, then the project's, sorted and without duplicates
+pub fn available(root: &Path) -> Vec<String> {
+    let mut names: Vec<String> = BUILT_IN.iter().map(|(name, _)| name.to_string()).collect();
+    if let Ok(entries) = std::fs::read_dir(root.join(DIR)) {
+        let mut project: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+            .filter_map(|p| Some(p.file_stem()?.to_string_lossy().into_owned()))
+            .filter(|name| !names.contains(name))
+            .collect();
+        project.sort();
+        names.extend(project);
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn built_in_templates_have_guidance_and_gates() {
+        let dir = TempDir::new().unwrap();
+        for name in ["web-app", "cli-tool", "library", "bugfix-sprint"] {
+            let template = load(dir.path(), name).unwrap();
+            assert!(!template.guidance.is_empty(), "{name}");
+            assert!(!template.quality_gates.is_empty(), "{name}");
+            assert!(!template.guidance.contains(QUALITY_GATES_HEADING), "{name}");
+        }
+    }
+
+    #[test]
+    fn project_templates_add_to_and_replace_built_ins() {
+        let dir = TempDir::new().unwrap();
+        let templates = dir.path().join(DIR);
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(
+            templates.join("service.md"),
+            "# Service\n\nEvery endpoint needs a metric.\n\n## Quality gates\n\n- `make test`\n* make lint\n\n## Notes\n\nDeploys on merge.\n",
+        )
+        .unwrap();
+        std::fs::write(templates.join("library.md"), "Our own library rules.").unwrap();
+
+        let service = load(dir.path(), "service").unwrap();
+        assert_eq!(service.quality_gates, vec!["make test", "make lint"]);
+        assert_eq!(
+            service.guidance,
+            "# Service\n\nEvery endpoint needs a metric.\n\n## Notes\n\nDeploys on merge."
+        );
+        assert!(service.prompt_section().contains("- make lint\n"));
+
+        let library = load(dir.path(), "library").unwrap();
+        assert_eq!(library.guidance, "Our own library rules.");
+        assert!(library.quality_gates.is_empty());
+
+        let err = load(dir.path(), "mobile").unwrap_err().to_string();
+        assert!(err.contains("web-app, cli-tool, library, bugfix-sprint, service"));
+    }
+}
@@ -0,0 +1,904 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::claude::Thinking;
+
+#[derive(Error, Debug)]
+pub enum PrdError {
+    #[error("PRD file not found at path {0}")]
+    NotFound(String),
+
+    #[error("Error reading PRD.json at {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error(
+        "Invalid JSON formatting in prd {path} ({source}). If it predates the current format, run `ralph migrate {path}`"
+    )]
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+
+    #[error(
+        "PRD {path} uses schema version {version}, but this ralph only supports up to {SCHEMA_VERSION}"
+    )]
+    TooNew { path: String, version: u32 },
+
+    #[error("Error reading completed.json at {path}: {source}")]
+    CompletedIo {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid JSON formatting in completed.json at {path}: {source}")]
+    CompletedJson {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CompletedTask {
+    pub category: String,
+    pub description: String,
+    pub steps: Vec<String>,
+    pub completed_at: String,
+    /// HEAD when ralph archived the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Task {
+    pub category: String,
+    pub description: String,
+    pub steps: Vec<String>,
+    pub passes: bool,
+    /// How soon the task should be picked; unset counts as medium
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// Free-form size hint, e.g. "2h" or "S"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<String>,
+    /// Tasks that must pass first, by number ("3") or description
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Extended thinking for loops on this task, overriding `ralph build --thinking`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<Thinking>,
+    /// Package directory the task belongs to, relative to the project root, overriding the
+    /// PRD's `workdir`. Claude and the quality gates run there for loops on this task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>,
+}
+
+/// Version of the PRD format this build reads and writes; `ralph migrate` upgrades older files
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// How a PRD file is laid out on disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum PrdFormat {
+    /// The PRD object, pretty-printed
+    #[default]
+    Json,
+    /// A readable Markdown document, with the PRD object as JSON front matter between `---`
+    /// lines. Only the front matter is read back; the body is rewritten from it.
+    #[cfg_attr(feature = "clap", value(name = "md"))]
+    Markdown,
+}
+
+impl PrdFormat {
+    /// Markdown for `.md` and `.markdown` paths, JSON for anything else
+    pub fn for_path(path: &str) -> Self {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("md" | "markdown") => Self::Markdown,
+            _ => Self::Json,
+        }
+    }
+
+    /// The format of a PRD file's contents: Markdown if it opens with front matter
+    pub fn of(content: &str) -> Self {
+        if front_matter(content).is_some() {
+            Self::Markdown
+        } else {
+            Self::Json
+        }
+    }
+}
+
+const FRONT_MATTER_FENCE: &str = "---";
+
+/// The JSON between the opening and closing `---` lines, if `content` starts with them
+fn front_matter(content: &str) -> Option<&str> {
+    let rest = content.trim_start_matches('\u{feff}');
+    let rest = rest
+        .strip_prefix(FRONT_MATTER_FENCE)?
+        .strip_prefix('\n')
+        .or_else(|| rest.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_FENCE {
+            return Some(&rest[..offset]);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The PRD JSON in a PRD file's contents: the front matter of a Markdown PRD, or all of it
+pub fn prd_json(content: &str) -> &str {
+    front_matter(content).unwrap_or(content)
+}
+
+/// A PRD document as the contents of a file in `format`
+pub fn render_prd(doc: &serde_json::Value, format: PrdFormat) -> serde_json::Result<String> {
+    let json = serde_json::to_string_pretty(doc)?;
+    match format {
+        PrdFormat::Json => Ok(json),
+        PrdFormat::Markdown => {
+            let prd: Prd = serde_json::from_value(doc.clone())?;
+            Ok(format!(
+                "{FRONT_MATTER_FENCE}\n{json}\n{FRONT_MATTER_FENCE}\n\n{}",
+                markdown_body(&prd)
+            ))
+        }
+    }
+}
+
+/// The readable part of a Markdown PRD
+fn markdown_body(prd: &Prd) -> String {
+    let mut out = format!("# {}\n", prd.name);
+    if !prd.quality_gates.is_empty() {
+        out.push_str("\n## Quality gates\n\n");
+        for gate in &prd.quality_gates {
+            out.push_str(&format!("- `{}`\n", gate));
+        }
+    }
+    if !prd.definition_of_done.is_empty() {
+        out.push_str("\n## Definition of done\n\n");
+        for item in &prd.definition_of_done {
+            out.push_str(&format!("- {}\n", item));
+        }
+    }
+    out.push_str("\n## Tasks\n");
+    for (i, task) in prd.tasks.iter().enumerate() {
+        let mark = if task.passes { "x" } else { " " };
+        out.push_str(&format!(
+            "\n### {}. [{}] {}\n\n",
+            i + 1,
+            mark,
+            task.description
+        ));
+        let mut meta = vec![format!("*{}*", task.category)];
+        if let Some(priority) = task.priority {
+            meta.push(format!("priority {}", priority));
+        }
+        if let Some(estimate) = &task.estimate {
+            meta.push(format!("estimate {}", estimate));
+        }
+        if !task.depends_on.is_empty() {
+            meta.push(format!("depends on {}", task.depends_on.join(", ")));
+        }
+        out.push_str(&meta.join(" · "));
+        out.push('\n');
+        if !task.steps.is_empty() {
+            out.push('\n');
+            for (n, step) in task.steps.iter().enumerate() {
+                out.push_str(&format!("{}. {}\n", n + 1, step));
+            }
+        }
+    }
+    out
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Prd {
+    /// PRD format version; missing means a file written before versioning (0)
+    #[serde(default)]
+    pub schema_version: u32,
+    pub name: String,
+    pub quality_gates: Vec<String>,
+    /// Package directory for every task, relative to the project root, for a PRD that
+    /// lives at the top of a monorepo but works on one package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>,
+    /// What must hold before the PRD counts as complete, beyond every task passing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub definition_of_done: Vec<String>,
+    pub tasks: Vec<Task>,
+    /// How the planned tasks line up with the request `ralph plan` started from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<Coverage>,
+}
+
+/// What `ralph plan` was asked for, item by item, and which planned tasks cover each
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Coverage {
+    pub items: Vec<CoverageItem>,
+}
+
+/// One requested item and the tasks (by number) that appear to cover it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CoverageItem {
+    pub request: String,
+    pub tasks: Vec<usize>,
+}
+
+impl Coverage {
+    /// Requested items no task seems to cover
+    pub fn uncovered(&self) -> impl Iterator<Item = &CoverageItem> {
+        self.items.iter().filter(|item| item.tasks.is_empty())
+    }
+}
+
+/// Task priority: `"high"`, `"medium"`, `"low"` or a rank where 1 is the most urgent.
+///
+/// The named levels rank as 1, 2 and 3, so they can be mixed with numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+    Rank(u32),
+}
+
+impl Priority {
+    /// Sort key: lower runs first
+    pub fn rank(self) -> u32 {
+        match self {
+            Priority::High => 1,
+            Priority::Medium => 2,
+            Priority::Low => 3,
+            Priority::Rank(n) => n,
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::High => f.write_str("high"),
+            Priority::Medium => f.write_str("medium"),
+            Priority::Low => f.write_str("low"),
+            Priority::Rank(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Priority::Rank(n) => serializer.serialize_u32(*n),
+            level => serializer.serialize_str(&level.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Rank(u32),
+            Level(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Rank(n) => Ok(Priority::Rank(n)),
+            Raw::Level(level) => level.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "high" => Ok(Priority::High),
+            "medium" => Ok(Priority::Medium),
+            "low" => Ok(Priority::Low),
+            other => other.parse().map(Priority::Rank).map_err(|_| {
+                format!(
+                    "unknown priority '{}': use high, medium, low or a number",
+                    other
+                )
+            }),
+        }
+    }
+}
+
+impl Task {
+    /// Sort key for the task's priority (unset counts as medium)
+    pub fn priority_rank(&self) -> u32 {
+        self.priority.unwrap_or(Priority::Medium).rank()
+    }
+
+    /// Whether the task's category is one of `categories` (case-insensitive); an empty list
+    /// matches everything
+    pub fn in_categories(&self, categories: &[String]) -> bool {
+        categories.is_empty()
+            || categories
+                .iter()
+                .any(|c| c.trim().eq_ignore_ascii_case(self.category.trim()))
+    }
+}
+
+impl Prd {
+    /// Number of tasks that don't pass yet
+    pub fn pending_count(&self) -> usize {
+        self.tasks.iter().filter(|t| !t.passes).count()
+    }
+
+    /// The directory a loop on `task` runs in, relative to the project root: the task's
+    /// `workdir`, else the PRD's. None (or "." or blank) is the project root itself.
+    pub fn workdir_for<'a>(&'a self, task: Option<&'a Task>) -> Option<&'a str> {
+        task.and_then(|t| t.workdir.as_deref())
+            .or(self.workdir.as_deref())
+            .map(|dir| dir.trim().trim_end_matches('/'))
+            .filter(|dir| !dir.is_empty() && *dir != ".")
+    }
+
+    /// Whether any pending task names its own `workdir`, so a loop needs to know its task
+    /// before it starts
+    pub fn has_task_workdirs(&self) -> bool {
+        self.tasks.iter().any(|t| !t.passes && t.workdir.is_some())
+    }
+}
+
+/// The stem of the PRD whose state files keep their plain names
+const DEFAULT_STEM: &str = "prd";
+
+/// The file stem a PRD's state files are keyed by, so several PRDs can share a directory;
+/// None for the default `prd.json`, whose state files keep their plain names
+fn state_key(prd_path: &str) -> Option<&str> {
+    let stem = std::path::Path::new(prd_path).file_stem()?.to_str()?;
+    (stem != DEFAULT_STEM).then_some(stem)
+}
+
+/// Where the PRD at `prd_path` keeps the state file `name` (e.g. `.ralph-run.json`): next to
+/// it, with the PRD's stem before the extension unless it's the default `prd.json`, so
+/// `plans/auth.json` keeps `plans/.ralph-run.auth.json`
+pub fn state_path_for(prd_path: &str, name: &str) -> std::path::PathBuf {
+    let parent = std::path::Path::new(prd_path)
+        .parent()
+        .unwrap_or(std::path::Path::new("."));
+    match (state_key(prd_path), name.rsplit_once('.')) {
+        (Some(key), Some((base, ext))) if !base.is_empty() => {
+            parent.join(format!("{}.{}.{}", base, key, ext))
+        }
+        (Some(key), _) => parent.join(format!("{}.{}", name, key)),
+        (None, _) => parent.join(name),
+    }
+}
+
+/// The directory the PRD at `prd_path` keeps its checkpoints and loop logs under: `.ralph/`
+/// next to it, or `.ralph/<stem>/` for any PRD but the default `prd.json`
+pub fn state_dir_for(prd_path: &str) -> std::path::PathBuf {
+    let dir = std::path::Path::new(prd_path)
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .join(".ralph");
+    match state_key(prd_path) {
+        Some(key) => dir.join(key),
+        None => dir,
+    }
+}
+
+pub fn completed_path_for(prd_path: &str) -> std::path::PathBuf {
+    state_path_for(prd_path, "completed.json")
+}
+
+/// Move tasks with passes=true from the PRD into completed.json, stamped with `completed_at`
+/// and the commit they were archived at.
+///
+/// Other PRD fields are preserved as-is; tasks already in completed.json (by description)
+/// aren't duplicated. Returns the number of tasks removed from the PRD.
+pub fn archive_passing_tasks(
+    prd_path: &str,
+    completed_at: &str,
+    commit: Option<&str>,
+) -> std::io::Result<usize> {
+    let content = std::fs::read_to_string(prd_path)?;
+    let mut prd: serde_json::Value = serde_json::from_str(prd_json(&content))?;
+    let Some(tasks) = prd.get_mut("tasks").and_then(|t| t.as_array_mut()) else {
+        return Ok(0);
+    };
+
+    let (passing, pending): (Vec<_>, Vec<_>) = tasks
+        .drain(..)
+        .partition(|t| t.get("passes").and_then(|p| p.as_bool()) == Some(true));
+    *tasks = pending;
+    if passing.is_empty() {
+        return Ok(0);
+    }
+
+    let completed_path = completed_path_for(prd_path);
+    let mut completed: Vec<serde_json::Value> = if completed_path.exists() {
+        serde_json::from_str(&std::fs::read_to_string(&completed_path)?)?
+    } else {
+        Vec::new()
+    };
+
+    for task in &passing {
+        let description = task.get("description").cloned().unwrap_or_default();
+        if completed
+            .iter()
+            .any(|c| c.get("description") == Some(&description))
+        {
+            continue;
+        }
+        let mut entry = serde_json::json!({
+            "category": task.get("category").cloned().unwrap_or_default(),
+            "description": description,
+            "steps": task.get("steps").cloned().unwrap_or_else(|| serde_json::json!([])),
+            "completed_at": completed_at,
+        });
+        if let Some(commit) = commit {
+            entry["commit"] = serde_json::json!(commit);
+        }
+        completed.push(entry);
+    }
+
+    std::fs::write(&completed_path, serde_json::to_string_pretty(&completed)?)?;
+    std::fs::write(prd_path, render_prd(&prd, PrdFormat::of(&content))?)?;
+    Ok(passing.len())
+}
+
+/// Set `passes` on the task with this description, preserving every other PRD field.
+/// Returns false if no such task exists.
+pub fn set_task_passes(prd_path: &str, description: &str, passes: bool) -> std::io::Result<bool> {
+    let content = std::fs::read_to_string(prd_path)?;
+    let mut prd: serde_json::Value = serde_json::from_str(prd_json(&content))?;
+    let task = prd
+        .get_mut("tasks")
+        .and_then(|t| t.as_array_mut())
+        .and_then(|tasks| {
+            tasks
+                .iter_mut()
+                .find(|t| t.get("description").and_then(|d| d.as_str()) == Some(description))
+        });
+    let Some(task) = task.and_then(|t| t.as_object_mut()) else {
+        return Ok(false);
+    };
+    task.insert("passes".to_string(), serde_json::Value::Bool(passes));
+    std::fs::write(prd_path, render_prd(&prd, PrdFormat::of(&content))?)?;
+    Ok(true)
+}
+
+/// The tasks archived next to the PRD, or None if nothing has been archived yet
+pub fn load_completed_tasks_from_file(
+    prd_path: &str,
+) -> Result<Option<Vec<CompletedTask>>, PrdError> {
+    let completed_path = completed_path_for(prd_path);
+
+    if !completed_path.exists() {
+        return Ok(None);
+    }
+
+    let path = completed_path.display().to_string();
+    let file_content =
+        std::fs::read_to_string(&completed_path).map_err(|source| PrdError::CompletedIo {
+            path: path.clone(),
+            source,
+        })?;
+    serde_json::from_str(&file_content).map_err(|source| PrdError::CompletedJson { path, source })
+}
+
+/// Where `ralph build` looks for a PRD when it isn't given one, in order
+pub const STANDARD_PATHS: &[&str] = &["plans/prd.json", "prd.json", ".ralph/prd.json"];
+
+/// Which of the standard PRD locations under `root` hold a file, as paths relative to it
+pub fn discover(root: &std::path::Path) -> Vec<String> {
+    STANDARD_PATHS
+        .iter()
+        .filter(|path| root.join(path).is_file())
+        .map(|path| path.to_string())
+        .collect()
+}
+
+/// Read and validate a PRD
+pub fn read_prd(prd_path: &str) -> Result<Prd, PrdError> {
+    let path = std::path::PathBuf::from(prd_path);
+    if !path.exists() {
+        return Err(PrdError::NotFound(prd_path.to_string()));
+    }
+
+    let file_content = std::fs::read_to_string(path).map_err(|source| PrdError::Io {
+        path: prd_path.to_string(),
+        source,
+    })?;
+    let prd: Prd =
+        serde_json::from_str(prd_json(&file_content)).map_err(|source| PrdError::Json {
+            path: prd_path.to_string(),
+            source,
+        })?;
+    if prd.schema_version > SCHEMA_VERSION {
+        return Err(PrdError::TooNew {
+            path: prd_path.to_string(),
+            version: prd.schema_version,
+        });
+    }
+    Ok(prd)
+}
+
+/// Write a PRD back to disk, pretty-printed, in the format the file already has (or its
+/// extension suggests, for a new file)
+pub fn write_prd(prd_path: &str, prd: &Prd) -> Result<(), PrdError> {
+    let format = match std::fs::read_to_string(prd_path) {
+        Ok(existing) => PrdFormat::of(&existing),
+        Err(_) => PrdFormat::for_path(prd_path),
+    };
+    let content = serde_json::to_value(prd)
+        .and_then(|doc| render_prd(&doc, format))
+        .map_err(|source| PrdError::Json {
+            path: prd_path.to_string(),
+            source,
+        })?;
+    std::fs::write(prd_path, content).map_err(|source| PrdError::Io {
+        path: prd_path.to_string(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_prd_json() -> &'static str {
+        r#"{
+            "name": "Test PRD",
+            "quality_gates": ["cargo test", "cargo clippy"],
+            "tasks": [
+                {
+                    "category": "feature",
+                    "description": "Add login",
+                    "steps": ["Create form", "Add validation"],
+                    "passes": false
+                },
+                {
+                    "category": "test",
+                    "description": "Add tests",
+                    "steps": ["Unit tests"],
+                    "passes": true
+                }
+            ]
+        }"#
+    }
+
+    fn create_test_completed_json() -> &'static str {
+        r#"[
+            {
+                "category": "setup",
+                "description": "Initial setup",
+                "steps": ["Create project"],
+                "completed_at": "2024-01-15"
+            }
+        ]"#
+    }
+
+    #[test]
+    fn priority_accepts_levels_and_ranks() {
+        let tasks: Vec<Task> = serde_json::from_str(
+            r#"[
+                {"category": "a", "description": "A", "steps": [], "passes": false, "priority": "HIGH"},
+                {"category": "b", "description": "B", "steps": [], "passes": false, "priority": 5},
+                {"category": "c", "description": "C", "steps": [], "passes": false}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(tasks[0].priority, Some(Priority::High));
+        assert_eq!(tasks[1].priority_rank(), 5);
+        assert_eq!(tasks[2].priority_rank(), Priority::Medium.rank());
+
+        let json = serde_json::to_string(&tasks[0]).unwrap();
+        assert!(json.contains(r#""priority":"high""#));
+        let json = serde_json::to_string(&tasks[2]).unwrap();
+        assert!(!json.contains("priority"));
+    }
+
+    #[test]
+    fn priority_rejects_unknown_level() {
+        let result: Result<Task, _> = serde_json::from_str(
+            r#"{"category": "a", "description": "A", "steps": [], "passes": false, "priority": "urgent"}"#,
+        );
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("unknown priority 'urgent'")
+        );
+    }
+
+    #[test]
+    fn task_thinking_is_optional_and_lowercase() {
+        let task: Task = serde_json::from_str(
+            r#"{"category": "a", "description": "A", "steps": [], "passes": false, "thinking": "extended"}"#,
+        )
+        .unwrap();
+        assert_eq!(task.thinking, Some(Thinking::Extended));
+        assert_eq!(Thinking::Extended.max_tokens(), 31_999);
+
+        let task: Task = serde_json::from_str(
+            r#"{"category": "a", "description": "A", "steps": [], "passes": false}"#,
+        )
+        .unwrap();
+        assert!(task.thinking.is_none());
+        assert!(!serde_json::to_string(&task).unwrap().contains("thinking"));
+    }
+
+    #[test]
+    fn load_prd_from_valid_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+
+        let prd = read_prd(prd_path.to_str().unwrap()).unwrap();
+        assert_eq!(prd.name, "Test PRD");
+        assert_eq!(prd.quality_gates.len(), 2);
+        assert_eq!(prd.tasks.len(), 2);
+        assert!(!prd.tasks[0].passes);
+        assert!(prd.tasks[1].passes);
+    }
+
+    #[test]
+    fn discovers_prds_in_the_standard_places() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(discover(temp_dir.path()).is_empty());
+        fs::create_dir_all(temp_dir.path().join(".ralph")).unwrap();
+        fs::write(temp_dir.path().join(".ralph/prd.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("prd.json"), "{}").unwrap();
+        fs::create_dir_all(temp_dir.path().join("plans/prd.json")).unwrap();
+        assert_eq!(discover(temp_dir.path()), ["prd.json", ".ralph/prd.json"]);
+    }
+
+    #[test]
+    fn load_prd_nonexistent_file_errors() {
+        let err = read_prd("/nonexistent/path/prd.json").unwrap_err();
+        assert!(matches!(err, PrdError::NotFound(_)));
+        assert!(err.to_string().contains("PRD file not found"));
+    }
+
+    #[test]
+    fn load_prd_invalid_json_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, "not valid json {{{").unwrap();
+
+        let err = read_prd(prd_path.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Invalid JSON formatting"));
+    }
+
+    #[test]
+    fn load_prd_wrong_schema_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, r#"{"wrong": "schema"}"#).unwrap();
+
+        let err = read_prd(prd_path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, PrdError::Json { .. }));
+    }
+
+    #[test]
+    fn state_files_are_keyed_by_the_prds_stem() {
+        use std::path::Path;
+        assert_eq!(
+            completed_path_for("plans/prd.json"),
+            Path::new("plans/completed.json")
+        );
+        assert_eq!(
+            completed_path_for("plans/auth.json"),
+            Path::new("plans/completed.auth.json")
+        );
+        assert_eq!(
+            state_path_for("plans/billing.md", ".ralph-stats.json"),
+            Path::new("plans/.ralph-stats.billing.json")
+        );
+        assert_eq!(state_dir_for("prd.json"), Path::new(".ralph"));
+        assert_eq!(
+            state_dir_for("plans/auth.json"),
+            Path::new("plans/.ralph/auth")
+        );
+    }
+
+    #[test]
+    fn load_completed_tasks_returns_none_when_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+
+        let result = load_completed_tasks_from_file(prd_path.to_str().unwrap()).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn load_completed_tasks_returns_tasks_when_exists() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        let completed_path = temp_dir.path().join("completed.json");
+
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+        fs::write(&completed_path, create_test_completed_json()).unwrap();
+
+        let result = load_completed_tasks_from_file(prd_path.to_str().unwrap()).unwrap();
+        assert!(result.is_some());
+        let tasks = result.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].category, "setup");
+        assert_eq!(tasks[0].completed_at, "2024-01-15");
+    }
+
+    #[test]
+    fn load_completed_tasks_invalid_json_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        let completed_path = temp_dir.path().join("completed.json");
+
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+        fs::write(&completed_path, "invalid json").unwrap();
+
+        let err = load_completed_tasks_from_file(prd_path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, PrdError::CompletedJson { .. }));
+        assert!(err.to_string().contains("Invalid JSON formatting"));
+    }
+
+    #[test]
+    fn prd_task_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+
+        let prd = read_prd(prd_path.to_str().unwrap()).unwrap();
+        let task = &prd.tasks[0];
+        assert_eq!(task.category, "feature");
+        assert_eq!(task.description, "Add login");
+        assert_eq!(task.steps, vec!["Create form", "Add validation"]);
+    }
+
+    #[test]
+    fn pending_count_ignores_passing_tasks() {
+        let prd: Prd = serde_json::from_str(create_test_prd_json()).unwrap();
+        assert_eq!(prd.pending_count(), 1);
+    }
+
+    #[test]
+    fn task_workdir_overrides_the_prds() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Mono", "quality_gates": [], "workdir": "packages/web", "tasks": [
+                {"category": "api", "description": "Add route", "steps": [], "passes": false,
+                 "workdir": "packages/api/"},
+                {"category": "web", "description": "Add page", "steps": [], "passes": false},
+                {"category": "docs", "description": "Root docs", "steps": [], "passes": false,
+                 "workdir": "."}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(prd.workdir_for(Some(&prd.tasks[0])), Some("packages/api"));
+        assert_eq!(prd.workdir_for(Some(&prd.tasks[1])), Some("packages/web"));
+        assert_eq!(prd.workdir_for(Some(&prd.tasks[2])), None);
+        assert_eq!(prd.workdir_for(None), Some("packages/web"));
+        assert!(prd.has_task_workdirs());
+
+        let plain: Prd = serde_json::from_str(create_test_prd_json()).unwrap();
+        assert_eq!(plain.workdir_for(plain.tasks.first()), None);
+        assert!(!plain.has_task_workdirs());
+        assert!(!serde_json::to_string(&plain).unwrap().contains("workdir"));
+    }
+
+    #[test]
+    fn archive_passing_tasks_moves_to_completed() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        let completed_path = temp_dir.path().join("completed.json");
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+        fs::write(&completed_path, create_test_completed_json()).unwrap();
+        let prd_path = prd_path.to_str().unwrap();
+
+        assert_eq!(
+            archive_passing_tasks(prd_path, "2024-02-01", Some("abc123")).unwrap(),
+            1
+        );
+
+        let prd = read_prd(prd_path).unwrap();
+        assert_eq!(prd.tasks.len(), 1);
+        assert_eq!(prd.tasks[0].description, "Add login");
+        assert_eq!(prd.quality_gates.len(), 2);
+
+        let completed = load_completed_tasks_from_file(prd_path).unwrap().unwrap();
+        assert_eq!(completed.len(), 2);
+        assert_eq!(completed[1].description, "Add tests");
+        assert_eq!(completed[1].steps, vec!["Unit tests"]);
+        assert_eq!(completed[1].completed_at, "2024-02-01");
+        assert_eq!(completed[1].commit.as_deref(), Some("abc123"));
+        assert_eq!(completed[0].commit, None);
+    }
+
+    #[test]
+    fn archive_passing_tasks_creates_completed_and_skips_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+        let prd_path = prd_path.to_str().unwrap();
+
+        assert_eq!(
+            archive_passing_tasks(prd_path, "2024-02-01", None).unwrap(),
+            1
+        );
+        // Nothing passing is left, so a second run is a no-op
+        assert_eq!(
+            archive_passing_tasks(prd_path, "2024-02-02", None).unwrap(),
+            0
+        );
+        assert_eq!(
+            load_completed_tasks_from_file(prd_path)
+                .unwrap()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn set_task_passes_updates_only_that_task() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.json");
+        fs::write(&prd_path, create_test_prd_json()).unwrap();
+        let prd_path = prd_path.to_str().unwrap();
+
+        assert!(set_task_passes(prd_path, "Add tests", false).unwrap());
+        assert!(!set_task_passes(prd_path, "Missing", true).unwrap());
+
+        let prd = read_prd(prd_path).unwrap();
+        assert!(prd.tasks.iter().all(|t| !t.passes));
+        assert_eq!(prd.quality_gates.len(), 2);
+    }
+
+    #[test]
+    fn markdown_prd_round_trips_through_its_front_matter() {
+        let temp_dir = TempDir::new().unwrap();
+        let prd_path = temp_dir.path().join("prd.md");
+        let doc: serde_json::Value = serde_json::from_str(create_test_prd_json()).unwrap();
+        let content = render_prd(&doc, PrdFormat::Markdown).unwrap();
+        assert!(content.starts_with("---\n{"));
+        assert!(content.contains("\n---\n\n# Test PRD\n"));
+        assert!(content.contains("- `cargo clippy`"));
+        assert!(content.contains("### 1. [ ] Add login\n\n*feature*\n\n1. Create form\n"));
+        assert!(content.contains("### 2. [x] Add tests"));
+        assert_eq!(PrdFormat::of(&content), PrdFormat::Markdown);
+        assert_eq!(PrdFormat::of(create_test_prd_json()), PrdFormat::Json);
+        assert_eq!(PrdFormat::for_path("plans/prd.MD"), PrdFormat::Markdown);
+        assert_eq!(PrdFormat::for_path("plans/prd.json"), PrdFormat::Json);
+        fs::write(&prd_path, content).unwrap();
+        let prd_path = prd_path.to_str().unwrap();
+
+        let prd = read_prd(prd_path).unwrap();
+        assert_eq!(prd.tasks.len(), 2);
+
+        // Rewrites keep the Markdown and refresh the body
+        assert!(set_task_passes(prd_path, "Add login", true).unwrap());
+        let content = fs::read_to_string(prd_path).unwrap();
+        assert!(content.contains("### 1. [x] Add login"));
+        assert_eq!(
+            archive_passing_tasks(prd_path, "2024-02-01", None).unwrap(),
+            2
+        );
+        let content = fs::read_to_string(prd_path).unwrap();
+        assert_eq!(PrdFormat::of(&content), PrdFormat::Markdown);
+        assert!(read_prd(prd_path).unwrap().tasks.is_empty());
+    }
+}
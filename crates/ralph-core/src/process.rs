@@ -0,0 +1,236 @@
+//! Finding, stopping and reading child processes the same way on every platform.
+//!
+//! On Windows, npm installs `claude` as `claude.cmd`, which `Command::new("claude")` doesn't
+//! find, and killing a `.cmd` wrapper or a `cmd /C` gate leaves the process it started
+//! running. Everything that spawns or kills Claude or a gate goes through here.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Child;
+use tokio::task::JoinHandle;
+
+/// How long a killed child's pipes get to close before its output is taken as it stands
+/// (something it started may still hold them open)
+const KILLED_OUTPUT_GRACE: Duration = Duration::from_millis(100);
+
+/// Extensions tried for a bare program name on Windows, in PATHEXT's usual order
+const WINDOWS_EXTENSIONS: &[&str] = &["exe", "cmd", "bat"];
+
+/// Where `program` would run from: the path itself if it has a separator, otherwise the
+/// first match on PATH (trying `.exe`, `.cmd` and `.bat` on Windows)
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let candidate = Path::new(program);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).find_map(|dir| {
+        let full = dir.join(program);
+        if full.is_file() {
+            return Some(full);
+        }
+        if !cfg!(windows) {
+            return None;
+        }
+        WINDOWS_EXTENSIONS
+            .iter()
+            .map(|ext| full.with_extension(ext))
+            .find(|f| f.is_file())
+    })
+}
+
+/// A `Command` for `program`. On Windows it's resolved on PATH first, so wrappers like
+/// `claude.cmd` run; elsewhere the OS looks it up as usual.
+pub fn command(program: &str) -> Command {
+    if cfg!(windows)
+        && let Some(path) = find_program(program)
+    {
+        return Command::new(path);
+    }
+    Command::new(program)
+}
+
+/// Kill `child` and whatever it started. On Windows `Child::kill` only ends the process
+/// itself, so the tree is taken down with `taskkill /T` first.
+pub fn kill_tree(child: &mut Child) -> io::Result<()> {
+    if cfg!(windows) {
+        let Some(pid) = child.id() else {
+            // Already reaped
+            return Ok(());
+        };
+        let killed = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+        if killed.is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+    child.start_kill()
+}
+
+/// A child's stdout and stderr, read on the runtime as the child writes them so a chatty
+/// child can't fill a pipe and stall
+#[derive(Debug)]
+pub struct Output {
+    stdout: Pipe,
+    stderr: Pipe,
+}
+
+impl Output {
+    /// Start reading the child's piped stdout and stderr. Must be called on the runtime.
+    pub fn capture(child: &mut Child) -> Self {
+        Self {
+            stdout: Pipe::read(child.stdout.take()),
+            stderr: Pipe::read(child.stderr.take()),
+        }
+    }
+
+    /// Stdout and stderr once the child has closed them. Safe to cancel and call again.
+    pub async fn collect(&mut self) -> (String, String) {
+        (
+            self.stdout.read_to_end().await,
+            self.stderr.read_to_end().await,
+        )
+    }
+
+    /// What the child has written to stdout so far, while it runs
+    pub fn stdout_so_far(&self) -> String {
+        self.stdout.so_far()
+    }
+
+    /// Stdout and stderr of a killed child: what it wrote before it died
+    pub async fn collect_killed(&mut self) -> (String, String) {
+        if tokio::time::timeout(KILLED_OUTPUT_GRACE, self.collect())
+            .await
+            .is_err()
+        {
+            self.stdout.reader.abort();
+            self.stderr.reader.abort();
+        }
+        (self.stdout.so_far(), self.stderr.so_far())
+    }
+}
+
+/// One pipe, read into a buffer by a task on the runtime
+#[derive(Debug)]
+struct Pipe {
+    read: Arc<Mutex<Vec<u8>>>,
+    reader: JoinHandle<()>,
+}
+
+impl Pipe {
+    fn read<R: AsyncRead + Unpin + Send + 'static>(pipe: Option<R>) -> Self {
+        let read = Arc::new(Mutex::new(Vec::new()));
+        let buffer = Arc::clone(&read);
+        let reader = tokio::spawn(async move {
+            let Some(mut pipe) = pipe else {
+                return;
+            };
+            let mut chunk = [0; 8192];
+            while let Ok(n) = pipe.read(&mut chunk).await
+                && n > 0
+            {
+                buffer
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .extend_from_slice(&chunk[..n]);
+            }
+        });
+        Self { read, reader }
+    }
+
+    /// Everything written, once the pipe is closed
+    async fn read_to_end(&mut self) -> String {
+        if !self.reader.is_finished() {
+            let _ = (&mut self.reader).await;
+        }
+        self.so_far()
+    }
+
+    /// What has been read so far
+    fn so_far(&self) -> String {
+        String::from_utf8_lossy(&self.read.lock().unwrap_or_else(PoisonError::into_inner))
+            .into_owned()
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime;
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_programs_on_path_and_by_path() {
+        assert!(find_program("sh").is_some_and(|p| p.ends_with("sh")));
+        assert_eq!(find_program("/bin/sh"), Some(PathBuf::from("/bin/sh")));
+        assert!(find_program("definitely-not-a-real-program-xyz").is_none());
+        assert!(find_program("./no/such/script.sh").is_none());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn finds_programs_without_their_extension() {
+        assert!(find_program("cmd").is_some_and(|p| p.extension().is_some()));
+        assert!(find_program("definitely-not-a-real-program-xyz").is_none());
+    }
+
+    #[test]
+    fn kill_tree_stops_a_running_child() {
+        runtime::block_on(async {
+            let mut child = if cfg!(windows) {
+                tokio::process::Command::new("cmd")
+                    .args(["/C", "ping -n 30 127.0.0.1 > NUL"])
+                    .spawn()
+            } else {
+                tokio::process::Command::new("sleep").arg("30").spawn()
+            }
+            .unwrap();
+            kill_tree(&mut child).unwrap();
+            assert!(!child.wait().await.unwrap().success());
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_is_read_while_the_child_runs() {
+        use std::process::Stdio;
+
+        runtime::block_on(async {
+            let mut child = tokio::process::Command::new("sh")
+                .args(["-c", "echo out; echo err >&2"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut output = Output::capture(&mut child);
+            child.wait().await.unwrap();
+            assert_eq!(output.collect().await, ("out\n".into(), "err\n".into()));
+
+            // A grandchild holding the pipe open doesn't keep a killed child's output waiting
+            let mut child = tokio::process::Command::new("sh")
+                .args(["-c", "echo started; sleep 30; true"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut output = Output::capture(&mut child);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            kill_tree(&mut child).unwrap();
+            child.wait().await.unwrap();
+            assert_eq!(output.collect_killed().await.0, "started\n");
+        });
+    }
+}
@@ -0,0 +1,401 @@
+//! The handoff between build loops: `progress.jsonl`, the journal ralph writes an entry to
+//! after every loop, and `progress.txt`, the notes Claude appends to.
+//!
+//! The journal's most recent entries go into the next loop's prompt, so every loop starts
+//! from the same record of what came before: the task, the summary, the files touched and
+//! the commit, whatever Claude remembered to write down.
+//!
+//! Once `progress.txt` grows past a size limit, its older notes are condensed into a
+//! `## Condensed history` section at the top of the file. A small model can write the
+//! condensed version; otherwise ralph keeps the first line of each note. The file as it was
+//! is archived under `.ralph/progress/` first, and the most recent notes stay word for word.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::process;
+use crate::prompt::PROGRESS_FILE;
+
+/// The journal ralph appends an entry to after every loop, next to `progress.txt`
+pub const JOURNAL_FILE: &str = "progress.jsonl";
+
+/// How many of the latest journal entries a loop's prompt shows
+pub const JOURNAL_TAIL: usize = 5;
+
+/// Most files a journal entry lists in the prompt
+const MAX_PROMPT_FILES: usize = 10;
+
+/// One loop, as recorded in the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub run_id: String,
+    pub loop_number: u64,
+    pub finished_at: DateTime<Utc>,
+    /// 1-indexed task number the loop reported working on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_number: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// The loop's outcome, as recorded in the stats file
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Files the loop changed, committed or not
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Where HEAD moved to, if the loop committed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// Add `entry` to the journal under `root`
+pub fn append_journal(root: &Path, entry: &JournalEntry) -> io::Result<()> {
+    let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(root.join(JOURNAL_FILE))?;
+    writeln!(file, "{}", line)
+}
+
+/// The last `count` entries of the journal under `root`, oldest first. A missing journal
+/// has none, and lines that aren't entries are skipped.
+pub fn recent_journal(root: &Path, count: usize) -> io::Result<Vec<JournalEntry>> {
+    let text = match std::fs::read_to_string(root.join(JOURNAL_FILE)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let entries: Vec<JournalEntry> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(count);
+    Ok(entries.into_iter().skip(skip).collect())
+}
+
+/// The journal entries as a prompt section; None without any
+pub fn journal_section(entries: &[JournalEntry]) -> Option<String> {
+    if entries.is_empty() {
+        return None;
+    }
+    let mut section = String::from(
+        "\n## Recent Loops\n\nWhat the latest loops did, oldest first, as ralph recorded it:\n",
+    );
+    for entry in entries {
+        let task = match (entry.task_number, &entry.task) {
+            (Some(number), Some(task)) => format!("task #{} ({})", number, task),
+            (Some(number), None) => format!("task #{}", number),
+            _ => "no task reported".to_string(),
+        };
+        section.push_str(&format!(
+            "- Loop {}, {}: {}",
+            entry.loop_number, task, entry.status
+        ));
+        if let Some(commit) = &entry.commit {
+            section.push_str(&format!(
+                ", commit {}",
+                commit.get(..7).unwrap_or(commit.as_str())
+            ));
+        }
+        if let Some(summary) = &entry.summary {
+            section.push_str(&format!(". {}", summary.trim()));
+        }
+        section.push('\n');
+        if !entry.files.is_empty() {
+            let shown = &entry.files[..entry.files.len().min(MAX_PROMPT_FILES)];
+            section.push_str(&format!("  Files: {}", shown.join(", ")));
+            if entry.files.len() > shown.len() {
+                section.push_str(&format!(" and {} more", entry.files.len() - shown.len()));
+            }
+            section.push('\n');
+        }
+    }
+    Some(section)
+}
+
+/// Where full copies of `progress.txt` go before it is compacted, relative to the project
+/// root
+pub const ARCHIVE_DIR: &str = ".ralph/progress";
+
+/// Size `progress.txt` may reach before it is compacted
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024;
+
+const HISTORY_HEADING: &str = "## Condensed history";
+
+/// Ends the condensed history; the notes after it are kept as written
+const HISTORY_END: &str = "---";
+
+/// Longest line kept from a note when condensing without a model, in characters
+const MAX_NOTE_LINE: usize = 200;
+
+/// What a compaction did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compacted {
+    /// The copy of the file as it was
+    pub archive: PathBuf,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// How many notes were condensed
+    pub notes: usize,
+    /// Why the model couldn't condense the notes, when one was asked to and ralph fell back
+    /// to first lines
+    pub model_error: Option<String>,
+}
+
+/// The condensed history at the top of the file (its bullets), and the notes after it
+fn split_history(text: &str) -> (Vec<&str>, &str) {
+    let Some(rest) = text.strip_prefix(HISTORY_HEADING) else {
+        return (Vec::new(), text);
+    };
+    let mut history = Vec::new();
+    let mut offset = HISTORY_HEADING.len();
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim() == HISTORY_END {
+            return (history, &text[offset..]);
+        }
+        if line.trim_start().starts_with("- ") {
+            history.push(line.trim());
+        }
+    }
+    // No end marker: treat the whole file as notes
+    (Vec::new(), text)
+}
+
+/// Notes separated by blank lines
+fn notes(text: &str) -> Vec<String> {
+    let mut notes = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                notes.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        notes.push(current.join("\n"));
+    }
+    notes
+}
+
+/// Condense notes without a model: the earlier history as it was, then each note's first
+/// line as a bullet
+fn condense(history: &[&str], notes: &[String]) -> String {
+    let mut bullets: Vec<String> = history.iter().map(|line| line.to_string()).collect();
+    for note in notes {
+        let first = note
+            .lines()
+            .map(|l| l.trim().trim_start_matches(['-', '*', '#']).trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or_default();
+        let first = match first.char_indices().nth(MAX_NOTE_LINE) {
+            Some((cut, _)) => format!("{}…", &first[..cut]),
+            None => first.to_string(),
+        };
+        bullets.push(format!("- {}", first));
+    }
+    bullets.join("\n")
+}
+
+/// Have `model` condense the notes into bullets, keeping what the next loop needs
+fn condense_with_model(model: &str, history: &[&str], notes: &[String]) -> Result<String, String> {
+    let prompt = format!(
+        "These are notes a coding agent left for whoever works on the project next, oldest \
+first, after a condensed history of even older ones. Condense them into a Markdown bullet \
+list of at most 30 bullets, one line each, oldest first. Keep decisions, gotchas, \
+conventions and unfinished work; drop routine detail. Return only the bullets.\n\n\
+Condensed history:\n{}\n\nNotes:\n{}",
+        history.join("\n"),
+        notes.join("\n\n")
+    );
+    let output = process::command("claude")
+        .args(["--model", model, "-p", &prompt])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", model, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            model,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let bullets: Vec<&str> = std::str::from_utf8(&output.stdout)
+        .map_err(|e| format!("{} returned invalid UTF-8: {}", model, e))?
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.starts_with("- "))
+        .collect();
+    if bullets.is_empty() {
+        return Err(format!("{} returned no bullets", model));
+    }
+    Ok(bullets.join("\n"))
+}
+
+/// Compact `progress.txt` under `root` if it is larger than `max_bytes`: archive it as
+/// `.ralph/progress/progress-<stamp>.txt`, then condense all but the newest notes (up to
+/// half of `max_bytes` of them) into the history section, with `model` if given. Returns
+/// None when the file is missing, small enough, or a single note.
+pub fn compact(
+    root: &Path,
+    max_bytes: u64,
+    model: Option<&str>,
+    stamp: &str,
+) -> io::Result<Option<Compacted>> {
+    let path = root.join(PROGRESS_FILE);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let bytes_before = text.len() as u64;
+    if bytes_before <= max_bytes {
+        return Ok(None);
+    }
+
+    let (history, rest) = split_history(&text);
+    let notes = notes(rest);
+    // Keep the newest notes that fit in half the limit, and always the last one
+    let mut kept_bytes = 0;
+    let mut split = notes.len();
+    while split > 0 {
+        let size = notes[split - 1].len() as u64 + 2;
+        if split < notes.len() && kept_bytes + size > max_bytes / 2 {
+            break;
+        }
+        kept_bytes += size;
+        split -= 1;
+    }
+    if split == 0 {
+        return Ok(None);
+    }
+    let (older, recent) = notes.split_at(split);
+
+    let archive_dir = root.join(ARCHIVE_DIR);
+    std::fs::create_dir_all(&archive_dir)?;
+    let archive = archive_dir.join(format!("progress-{}.txt", stamp));
+    std::fs::write(&archive, &text)?;
+
+    let (condensed, model_error) = match model.map(|m| condense_with_model(m, &history, older)) {
+        Some(Ok(condensed)) => (condensed, None),
+        Some(Err(e)) => (condense(&history, older), Some(e)),
+        None => (condense(&history, older), None),
+    };
+    let compacted = format!(
+        "{}\n\nCondensed from earlier notes; the full notes are archived in {}/.\n\n{}\n\n{}\n\n{}\n",
+        HISTORY_HEADING,
+        ARCHIVE_DIR,
+        condensed,
+        HISTORY_END,
+        recent.join("\n\n")
+    );
+    std::fs::write(&path, &compacted)?;
+    Ok(Some(Compacted {
+        archive,
+        bytes_before,
+        bytes_after: compacted.len() as u64,
+        notes: older.len(),
+        model_error,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(loop_number: u64) -> JournalEntry {
+        JournalEntry {
+            run_id: "run-1".to_string(),
+            loop_number,
+            finished_at: Utc::now(),
+            task_number: Some(2),
+            task: Some("Add login".to_string()),
+            status: "completed".to_string(),
+            summary: Some("Added the login form.".to_string()),
+            files: Vec::new(),
+            commit: Some("0123456789abcdef".to_string()),
+        }
+    }
+
+    #[test]
+    fn journal_keeps_the_latest_entries_for_the_prompt() {
+        let dir = TempDir::new().unwrap();
+        assert!(recent_journal(dir.path(), 5).unwrap().is_empty());
+        assert_eq!(journal_section(&[]), None);
+
+        for n in 1..=3 {
+            append_journal(dir.path(), &entry(n)).unwrap();
+        }
+        let recent = recent_journal(dir.path(), 2).unwrap();
+        assert_eq!(
+            recent.iter().map(|e| e.loop_number).collect::<Vec<_>>(),
+            [2, 3]
+        );
+
+        let mut files = entry(4);
+        files.files = (1..=12).map(|n| format!("src/{}.rs", n)).collect();
+        files.commit = None;
+        files.summary = None;
+        let section = journal_section(&[entry(3), files]).unwrap();
+        assert!(section.contains(
+            "- Loop 3, task #2 (Add login): completed, commit 0123456. Added the login form.\n"
+        ));
+        assert!(section.contains("- Loop 4, task #2 (Add login): completed\n"));
+        assert!(section.contains("src/10.rs and 2 more\n"));
+    }
+
+    #[test]
+    fn condenses_older_notes_and_archives_the_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(PROGRESS_FILE);
+        let original = "Task 1: added login.\nUsed bcrypt.\n\n- Task 2: fixed the crash\n- it was a race\n\nTask 3: in progress, tests fail on CI.\n";
+        std::fs::write(&path, original).unwrap();
+
+        assert_eq!(compact(dir.path(), 1024, None, "1").unwrap(), None);
+        let compacted = compact(dir.path(), 60, None, "1").unwrap().unwrap();
+        assert_eq!(compacted.notes, 2);
+        assert_eq!(
+            std::fs::read_to_string(&compacted.archive).unwrap(),
+            original
+        );
+        assert_eq!(
+            compacted.archive,
+            dir.path().join(ARCHIVE_DIR).join("progress-1.txt")
+        );
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(HISTORY_HEADING));
+        assert!(text.contains("- Task 1: added login.\n- Task 2: fixed the crash\n\n---\n"));
+        assert!(text.ends_with("---\n\nTask 3: in progress, tests fail on CI.\n"));
+
+        // A later compaction keeps the history and adds to it
+        std::fs::write(
+            &path,
+            format!("{}\nTask 4: done.\n\nTask 5: started.\n", text),
+        )
+        .unwrap();
+        compact(dir.path(), 60, None, "2").unwrap().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(
+            "- Task 1: added login.\n- Task 2: fixed the crash\n- Task 3: in progress, tests fail on CI.\n- Task 4: done.\n\n---\n\nTask 5: started.\n"
+        ));
+    }
+
+    #[test]
+    fn leaves_a_single_note_alone() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(compact(dir.path(), 10, None, "1").unwrap(), None);
+        std::fs::write(dir.path().join(PROGRESS_FILE), "One very long note.\n").unwrap();
+        assert_eq!(compact(dir.path(), 10, None, "1").unwrap(), None);
+    }
+}
@@ -0,0 +1,729 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::prd::{self, Prd, PrdFormat, Task};
+use crate::stats::IterationRecord;
+use crate::toc::TocEntry;
+
+/// A loop's prompt with the built-in instructions
+pub fn make_prompt(prd_path: &str) -> String {
+    MasterPrompt::default().prompt(prd_path, "")
+}
+
+/// For a Markdown PRD, where the edits Claude makes have to go
+fn markdown_prd_note(prd_path: &str) -> &'static str {
+    match PrdFormat::for_path(prd_path) {
+        PrdFormat::Markdown => {
+            "\n\nThe PRD is Markdown whose data is the JSON front matter between the opening \
+`---` lines. Make PRD updates (like `passes`) in that JSON; ralph rewrites the rest of the file \
+from it."
+        }
+        PrdFormat::Json => "",
+    }
+}
+
+/// Like `make_prompt`, but shows only one task instead of attaching the whole PRD
+/// (`ralph build --task N --once`)
+pub fn make_single_task_prompt(prd_path: &str, task_number: usize, task: &Task) -> String {
+    MasterPrompt::default().single_task_prompt(prd_path, task_number, task, "")
+}
+
+/// Files that tell agents how a project works, referenced in build and plan prompts when
+/// the project has them
+pub const AGENT_DOCS: &[&str] = &["CLAUDE.md", "AGENTS.md", "CONTRIBUTING.md"];
+
+/// The `candidates` that exist under `root`, in the order given
+pub fn find_agent_docs(root: &Path, candidates: &[String]) -> Vec<String> {
+    candidates
+        .iter()
+        .filter(|doc| root.join(doc).is_file())
+        .cloned()
+        .collect()
+}
+
+/// A sentence attaching the project's agent docs with `@` mentions; empty without any
+pub fn agent_docs_note(docs: &[String]) -> String {
+    if docs.is_empty() {
+        return String::new();
+    }
+    let mentions: Vec<String> = docs.iter().map(|doc| format!("@{}", doc)).collect();
+    format!(
+        "\n\nFollow the project's conventions in {}.",
+        mentions.join(" ")
+    )
+}
+
+/// Where a project's own loop instructions live, relative to the project root
+pub const PROMPT_FILE: &str = ".ralph/prompt.md";
+
+/// The progress notes each loop reads and appends to
+pub const PROGRESS_FILE: &str = "progress.txt";
+
+/// The loop instructions every build prompt is built around: the project's
+/// `.ralph/prompt.md`, or the built-in ones.
+///
+/// The instructions may use `{{prd_path}}`, `{{progress_file}}`, `{{completed_file}}` (the
+/// PRD's archive of finished tasks) and `{{task_constraints}}` (the task focus, category
+/// filter and task order for the loop). Constraints the
+/// instructions don't place are added after them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MasterPrompt {
+    template: String,
+    /// The file the instructions came from; None for the built-in ones
+    pub path: Option<PathBuf>,
+    /// Extra instructions for tasks in a category, by category (`[prompt.categories]` in
+    /// `.ralph.toml`)
+    pub addenda: BTreeMap<String, String>,
+    /// The project's agent docs, attached to every prompt
+    pub docs: Vec<String>,
+    /// The package directory a loop runs in (a task's or the PRD's `workdir`), which the
+    /// progress file and agent docs are referred to from; None at the project root
+    workdir: Option<String>,
+}
+
+impl Default for MasterPrompt {
+    fn default() -> Self {
+        Self {
+            template: MASTER_PROMPT.to_string(),
+            path: None,
+            addenda: BTreeMap::new(),
+            docs: Vec::new(),
+            workdir: None,
+        }
+    }
+}
+
+impl MasterPrompt {
+    /// The project's instructions under `root` if it has a `.ralph/prompt.md`, otherwise the
+    /// built-in ones
+    pub fn load(root: &Path) -> io::Result<Self> {
+        let path = root.join(PROMPT_FILE);
+        match std::fs::read_to_string(&path) {
+            // Kept apart from the PRD reference the prompt starts with
+            Ok(template) => Ok(Self {
+                template: format!("\n\n{}", template.trim()),
+                path: Some(path),
+                ..Self::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The addendum for tasks in `category`, as a prompt section. Categories match
+    /// ignoring case, as with `--category`.
+    pub fn addendum_section(&self, category: &str) -> Option<String> {
+        let (_, addendum) = self
+            .addenda
+            .iter()
+            .find(|(c, _)| c.eq_ignore_ascii_case(category))?;
+        Some(format!(
+            "\n## Notes for {} Tasks\n\n{}\n",
+            category,
+            addendum.trim()
+        ))
+    }
+
+    /// These instructions for a loop running in `workdir`, a directory under the project
+    /// root: the progress file and agent docs are referred to from there. The PRD path is
+    /// the caller's to rebase, with `path_from_workdir`.
+    pub fn in_workdir(&self, workdir: &str) -> Self {
+        Self {
+            workdir: Some(workdir.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// `path`, relative to the project root, as the loop's Claude sees it
+    fn seen_from_loop(&self, path: &str) -> String {
+        match &self.workdir {
+            Some(dir) => path_from_workdir(dir, path),
+            None => path.to_string(),
+        }
+    }
+
+    /// The progress file, from where the loop runs
+    pub fn progress_file(&self) -> String {
+        self.seen_from_loop(PROGRESS_FILE)
+    }
+
+    /// The sentence attaching the agent docs, from where the loop runs
+    pub fn docs_note(&self) -> String {
+        let docs: Vec<String> = self.docs.iter().map(|d| self.seen_from_loop(d)).collect();
+        agent_docs_note(&docs)
+    }
+
+    /// The instructions with their variables filled in
+    pub fn render(&self, prd_path: &str, task_constraints: &str) -> String {
+        let mut text = self
+            .template
+            .replace("{{prd_path}}", prd_path)
+            .replace("{{progress_file}}", &self.progress_file())
+            .replace(
+                "{{completed_file}}",
+                &prd::completed_path_for(prd_path).display().to_string(),
+            );
+        if text.contains("{{task_constraints}}") {
+            text = text.replace("{{task_constraints}}", task_constraints);
+        } else {
+            text.push_str(task_constraints);
+        }
+        text
+    }
+
+    /// A loop's prompt: the PRD and agent docs attached, then the instructions
+    pub fn prompt(&self, prd_path: &str, task_constraints: &str) -> String {
+        format!(
+            "@{}{}{}{}",
+            prd_path,
+            markdown_prd_note(prd_path),
+            self.docs_note(),
+            self.render(prd_path, task_constraints)
+        )
+    }
+
+    /// Like `prompt`, but shows only one task instead of attaching the whole PRD
+    /// (`ralph build --task N --once`)
+    pub fn single_task_prompt(
+        &self,
+        prd_path: &str,
+        task_number: usize,
+        task: &Task,
+        task_constraints: &str,
+    ) -> String {
+        let task_json = serde_json::to_string_pretty(task).unwrap_or_default();
+        let mut prompt = format!(
+            "## Your Task\n\nThis iteration works on task #{} of the PRD at {}. The other tasks \
+are deliberately hidden: don't read, change or work on them. When updating the PRD, edit only \
+this task's entry.{}\n\n```json\n{}\n```\n",
+            task_number,
+            prd_path,
+            markdown_prd_note(prd_path),
+            task_json
+        );
+        if task.passes {
+            prompt.push_str(
+                "\nThe task is already marked as passing, but its result wasn't satisfactory. \
+Review the existing work, improve it, and commit the changes.\n",
+            );
+        }
+        prompt.push_str(&self.docs_note());
+        prompt.push_str(&self.render(prd_path, task_constraints));
+        prompt
+    }
+}
+
+const MASTER_PROMPT: &str = r#"
+
+@{{progress_file}}
+1. Pick the task to work on and work only on that task.
+   - Take the first task in the Task Order section below that you can make progress on.
+   - The order comes from each task's `priority` and `depends_on` fields, so don't re-rank it.
+   - If you need to see what completed tasks were written you can check {{completed_file}} for completed tasks.
+2. Run the repo's quality gates (format/lint/typecheck/build/tests) using project-native commands. If a gate is missing, note it.
+3. Update the PRD with the work that was done: set passes=true on the task once it's complete.
+   - Leave finished tasks in the PRD and don't edit {{completed_file}}; ralph archives them after the loop.
+4. Append to the {{progress_file}} file anything the next person working in the code base should know.
+   - Ralph records each loop's task, summary, files and commit itself, so note what those don't say: decisions, gotchas, unfinished work.
+5. Make a git commit of that feature.
+   - Only work on a single feature.
+
+After completing your work, output a JSON summary with:
+- task_number: The task number you worked on (1-indexed from the PRD)
+- status: "completed" if done, "in_progress" if partially done, "blocked" if stuck, "skipped" if not applicable
+- summary: Brief description of what you did
+- completed_steps: The step numbers (1-indexed) of that task's steps that are now done
+- prd_complete: true if all PRD tasks are now done, false otherwise
+"#;
+
+/// Describe steps finished in earlier iterations for tasks that still aren't passing.
+///
+/// `progress` maps task descriptions to their completed 1-indexed step numbers.
+pub fn step_progress_section(prd: &Prd, progress: &BTreeMap<String, Vec<u32>>) -> Option<String> {
+    let mut section = String::new();
+
+    for (i, task) in prd.tasks.iter().enumerate() {
+        let Some(done) = progress.get(&task.description) else {
+            continue;
+        };
+        if task.passes || done.is_empty() {
+            continue;
+        }
+
+        section.push_str(&format!("\nTask #{}: {}\n", i + 1, task.description));
+        for (n, step) in task.steps.iter().enumerate() {
+            let mark = if done.contains(&(n as u32 + 1)) {
+                "x"
+            } else {
+                " "
+            };
+            section.push_str(&format!("- [{}] {}. {}\n", mark, n + 1, step));
+        }
+    }
+
+    if section.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "\n## Step Progress\n\nEarlier iterations finished some steps of these tasks. \
+Continue with the unchecked steps instead of redoing checked ones:\n{}",
+        section
+    ))
+}
+
+/// Restrict an iteration to a single PRD task (`ralph build --task`)
+pub fn task_focus_section(task_number: usize, description: &str) -> String {
+    format!(
+        "\n## Task Focus\n\nThis run is limited to task #{} ({}). Work only on that task, \
+even if another task looks more important, and report task_number {}. \
+If it can't be finished, report status \"blocked\" and explain why in the summary.\n",
+        task_number, description, task_number
+    )
+}
+
+/// The project root as seen from `workdir`, a directory under it: "../.." for packages/api
+pub fn root_from(workdir: &str) -> String {
+    let depth = Path::new(workdir)
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count();
+    vec![".."; depth.max(1)].join("/")
+}
+
+/// `path`, relative to the project root, as seen from `workdir`; absolute paths stay as they
+/// are
+pub fn path_from_workdir(workdir: &str, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+    format!("{}/{}", root_from(workdir), path.trim_start_matches("./"))
+}
+
+/// Tell a loop running in a package directory (a task's or the PRD's `workdir`) where it is,
+/// and where ralph's own files are
+pub fn workdir_section(workdir: &str) -> String {
+    let root = root_from(workdir);
+    format!(
+        "\n## Working Directory\n\nThis loop works in `{}`, so you're running in that directory \
+rather than at the project root, `{}`. Ralph's own files are at the project root: update \
+`{}/{}` there. Run the package's own build and test commands from here; ralph runs the \
+quality gates here too.\n",
+        workdir, root, root, PROGRESS_FILE
+    )
+}
+
+/// Restrict which tasks Claude may pick, listing the eligible ones by number
+pub fn category_filter_section(categories: &[String], eligible: &[usize]) -> String {
+    let numbers: Vec<String> = eligible.iter().map(|n| format!("#{}", n)).collect();
+    format!(
+        "\n## Category Filter\n\nThis run is limited to tasks in these categories: {}. \
+Only tasks {} are eligible; ignore every other task, even if it looks more important.\n",
+        categories.join(", "),
+        numbers.join(", ")
+    )
+}
+
+/// The order to work through pending tasks, highest priority first with dependencies ahead
+/// of the tasks that need them
+pub fn task_order_section(entries: &[&TocEntry]) -> Option<String> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    let mut section = String::from(
+        "\n## Task Order\n\nPending tasks in the order to work on them (highest priority first, \
+dependencies before the tasks that need them):\n",
+    );
+    for (i, entry) in entries.iter().enumerate() {
+        let priority = entry
+            .priority
+            .map(|p| format!(", {} priority", p))
+            .unwrap_or_default();
+        section.push_str(&format!(
+            "{}. Task #{} ({}{}): {}\n",
+            i + 1,
+            entry.number,
+            entry.category,
+            priority,
+            entry.description
+        ));
+    }
+    Some(section)
+}
+
+/// Appended to the next prompt when an iteration picked a task outside the category filter
+pub fn off_filter_reminder(task_number: i32, category: &str) -> String {
+    format!(
+        "\n## Outside The Filter\n\nThe previous iteration worked on task #{} ('{}'), which is \
+not in this run's categories. Leave it alone and pick an eligible task instead.\n",
+        task_number, category
+    )
+}
+
+/// Ask Claude to print the exit clause once the whole PRD is done
+pub fn exit_clause_instruction(clause: &str) -> String {
+    format!(
+        "\nWhen every task in the PRD is complete, end your final message with exactly: {}\n",
+        clause
+    )
+}
+
+/// Appended to the next prompt when an iteration reported "completed" without committing
+pub const MISSING_COMMIT_REMINDER: &str = r#"
+
+## Missing Commit
+
+The previous iteration reported its task as "completed" but no new git commit was found.
+Before doing anything else, check `git status`, and commit the finished work for that task
+(step 5). Do not report a task as completed unless its commit exists.
+"#;
+
+/// Appended to the next prompt when an iteration reported "completed" but changed nothing
+pub const NO_OP_REMINDER: &str = r#"
+
+## No Changes Detected
+
+The previous iteration reported its task as "completed", but no changes were detected: there
+was no new git commit, no uncommitted change, and the PRD was untouched. The task is still
+pending. Do the work for real this time, verify it, update the PRD, and commit it.
+"#;
+
+/// Appended to the next prompt when `--verify` rejected a completed task
+pub fn verify_failed_section(task_number: i32, description: &str, reason: &str) -> String {
+    format!(
+        "\n## Verification Failed\n\nAn independent review rejected task #{} ({}), so it is \
+marked as pending again. Reviewer's reason: {}\nAddress this before reporting the task as \
+completed again.\n",
+        task_number, description, reason
+    )
+}
+
+/// The changes `--code-review` requested in a completed task, for the next loop
+pub fn code_review_section(
+    task_number: i32,
+    description: &str,
+    summary: &str,
+    issues: &str,
+) -> String {
+    format!(
+        "\n## Code Review: Changes Requested\n\nA code review of task #{} ({}) requested \
+changes, so it is marked as pending again. Reviewer's summary: {}\n{}\nFix the blocker and \
+major issues, commit, and report the task as completed again.\n",
+        task_number, description, summary, issues
+    )
+}
+
+/// Why the user rejected a completed task in `--review`, for the next loop
+pub fn review_rejected_section(task_number: i32, description: &str, reason: &str) -> String {
+    let reason = if reason.is_empty() {
+        "none given"
+    } else {
+        reason
+    };
+    format!(
+        "\n## Changes Rejected in Review\n\nThe user reviewed your changes for task #{} ({}) and \
+rejected them. They have been reverted and the task is pending again. The user's feedback: \
+{}\nRedo the task with this in mind.\n",
+        task_number, description, reason
+    )
+}
+
+/// The PRD's definition of done, included in every prompt; None if the PRD has none
+pub fn definition_of_done_section(items: &[String]) -> Option<String> {
+    if items.is_empty() {
+        return None;
+    }
+    let list: Vec<String> = items.iter().map(|item| format!("- {}", item)).collect();
+    Some(format!(
+        "\n## Definition of Done\n\nOnly report prd_complete once every task passes and all of \
+these hold. Ralph checks quality gates, CHANGELOG and docs changes, and commands in backticks \
+itself before accepting completion:\n{}\n",
+        list.join("\n")
+    ))
+}
+
+/// Most rating notes passed on to one prompt
+pub const MAX_RATING_NOTES: usize = 5;
+
+/// How the user rated earlier loops on tasks in the same categories, most recent first;
+/// None when there's nothing to pass on
+pub fn rating_feedback_section(records: &[&IterationRecord]) -> Option<String> {
+    let lines: Vec<String> = records
+        .iter()
+        .filter_map(|r| {
+            Some(format!(
+                "- The user rated similar previous work ({}) {}/5 because: {}",
+                r.category.as_deref()?,
+                r.rating?,
+                r.rating_note.as_deref()?
+            ))
+        })
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "\n## Earlier Feedback\n\nKeep this feedback on earlier loops in mind:\n{}\n",
+        lines.join("\n")
+    ))
+}
+
+const _REGRETS_PROMPT: &str = r#"
+hello
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_prompt_includes_prd_path() {
+        let prompt = make_prompt("/path/to/prd.json");
+        assert!(prompt.starts_with("@/path/to/prd.json"));
+    }
+
+    #[test]
+    fn markdown_prds_point_claude_at_the_front_matter() {
+        assert!(make_prompt("plans/prd.md").contains("JSON front matter"));
+        assert!(!make_prompt("plans/prd.json").contains("front matter"));
+    }
+
+    #[test]
+    fn make_prompt_includes_progress_reference() {
+        let prompt = make_prompt("prd.json");
+        assert!(prompt.contains("@progress.txt"));
+    }
+
+    #[test]
+    fn make_prompt_includes_master_instructions() {
+        let prompt = make_prompt("prd.json");
+        assert!(prompt.contains("Task Order section"));
+        assert!(prompt.contains("quality gates"));
+        assert!(prompt.contains("git commit"));
+    }
+
+    #[test]
+    fn make_prompt_includes_completed_json_reference() {
+        let prompt = make_prompt("prd.json");
+        assert!(prompt.contains("check completed.json"));
+        let prompt = make_prompt("plans/auth.json");
+        assert!(prompt.contains("check plans/completed.auth.json"));
+    }
+
+    #[test]
+    fn project_prompt_replaces_the_instructions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(
+            MasterPrompt::load(dir.path()).unwrap(),
+            MasterPrompt::default()
+        );
+        assert_eq!(
+            MasterPrompt::default().prompt("prd.json", "\n## Task Order\n"),
+            format!("{}\n## Task Order\n", make_prompt("prd.json"))
+        );
+
+        std::fs::create_dir_all(dir.path().join(".ralph")).unwrap();
+        std::fs::write(
+            dir.path().join(PROMPT_FILE),
+            "Read @{{progress_file}}.\n{{task_constraints}}\nThen work on {{prd_path}}.\n",
+        )
+        .unwrap();
+        let master = MasterPrompt::load(dir.path()).unwrap();
+        assert_eq!(master.path, Some(dir.path().join(PROMPT_FILE)));
+        assert_eq!(
+            master.prompt("prd.json", "## Task Order"),
+            "@prd.json\n\nRead @progress.txt.\n## Task Order\nThen work on prd.json."
+        );
+    }
+
+    #[test]
+    fn prompts_attach_the_agent_docs_the_project_has() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("AGENTS.md"), "Use tabs.").unwrap();
+        std::fs::write(dir.path().join("CLAUDE.md"), "Run make.").unwrap();
+        let candidates: Vec<String> = AGENT_DOCS.iter().map(|doc| doc.to_string()).collect();
+        let docs = find_agent_docs(dir.path(), &candidates);
+        assert_eq!(docs, vec!["CLAUDE.md", "AGENTS.md"]);
+
+        let master = MasterPrompt {
+            docs,
+            ..MasterPrompt::default()
+        };
+        assert!(master.prompt("prd.json", "").starts_with(
+            "@prd.json\n\nFollow the project's conventions in @CLAUDE.md @AGENTS.md.\n\n@progress.txt"
+        ));
+        assert_eq!(agent_docs_note(&[]), "");
+    }
+
+    #[test]
+    fn task_order_section_lists_entries_in_order() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "Login", "steps": [], "passes": false},
+                {"category": "bugfix", "description": "Crash", "steps": [], "passes": false, "priority": "high"}
+            ]}"#,
+        )
+        .unwrap();
+        let entries = crate::toc::build(&prd);
+        let refs: Vec<&TocEntry> = entries.iter().collect();
+        let section = task_order_section(&refs).unwrap();
+        assert!(section.contains("1. Task #2 (bugfix, high priority): Crash"));
+        assert!(section.contains("2. Task #1 (feature): Login"));
+        assert!(task_order_section(&[]).is_none());
+    }
+
+    #[test]
+    fn single_task_prompt_hides_other_tasks() {
+        let prd = prd_with_steps();
+        let prompt = make_single_task_prompt("prd.json", 2, &prd.tasks[1]);
+        assert!(!prompt.starts_with('@'));
+        assert!(prompt.contains("task #2 of the PRD at prd.json"));
+        assert!(prompt.contains("\"description\": \"Logout\""));
+        assert!(!prompt.contains("Login"));
+        // Logout already passes, so this is a re-run
+        assert!(prompt.contains("wasn't satisfactory"));
+        assert!(prompt.contains("@progress.txt"));
+    }
+
+    fn prd_with_steps() -> Prd {
+        serde_json::from_str(
+            r#"{
+                "name": "Test",
+                "quality_gates": [],
+                "tasks": [
+                    {"category": "feature", "description": "Login", "steps": ["Form", "API", "Tests"], "passes": false},
+                    {"category": "feature", "description": "Logout", "steps": ["Button"], "passes": true}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn step_progress_section_lists_partial_tasks() {
+        let progress = BTreeMap::from([("Login".to_string(), vec![1, 2])]);
+        let section = step_progress_section(&prd_with_steps(), &progress).unwrap();
+        assert!(section.contains("Task #1: Login"));
+        assert!(section.contains("- [x] 1. Form"));
+        assert!(section.contains("- [x] 2. API"));
+        assert!(section.contains("- [ ] 3. Tests"));
+    }
+
+    #[test]
+    fn step_progress_section_skips_passing_and_unknown_tasks() {
+        let progress = BTreeMap::from([
+            ("Logout".to_string(), vec![1]),
+            ("Removed task".to_string(), vec![1]),
+            ("Login".to_string(), vec![]),
+        ]);
+        assert!(step_progress_section(&prd_with_steps(), &progress).is_none());
+    }
+
+    #[test]
+    fn task_focus_section_names_task() {
+        let section = task_focus_section(3, "Add login");
+        assert!(section.contains("task #3 (Add login)"));
+        assert!(section.contains("report task_number 3"));
+    }
+
+    #[test]
+    fn paths_are_rebased_onto_the_workdir() {
+        assert_eq!(root_from("packages/api"), "../..");
+        assert_eq!(root_from("./web/"), "..");
+        assert_eq!(
+            path_from_workdir("packages/api", "plans/prd.json"),
+            "../../plans/prd.json"
+        );
+        assert_eq!(path_from_workdir("web", "/abs/prd.json"), "/abs/prd.json");
+        let section = workdir_section("packages/api");
+        assert!(section.contains("works in `packages/api`"));
+        assert!(section.contains("`../../progress.txt`"));
+
+        let master = MasterPrompt {
+            docs: vec!["CLAUDE.md".to_string()],
+            ..MasterPrompt::default()
+        }
+        .in_workdir("packages/api");
+        let prompt = master.prompt("../../prd.json", "");
+        assert!(prompt.contains("@../../progress.txt"));
+        assert!(prompt.contains("@../../CLAUDE.md"));
+        assert!(prompt.contains("check ../../completed.json"));
+        assert!(!prompt.contains("@progress.txt"));
+        assert!(!prompt.contains("@CLAUDE.md"));
+    }
+
+    #[test]
+    fn exit_clause_instruction_includes_clause() {
+        let instruction = exit_clause_instruction("<promise>COMPLETE</promise>");
+        assert!(instruction.contains("exactly: <promise>COMPLETE</promise>"));
+    }
+
+    #[test]
+    fn missing_commit_reminder_asks_for_commit() {
+        assert!(MISSING_COMMIT_REMINDER.contains("no new git commit"));
+        assert!(MISSING_COMMIT_REMINDER.contains("git status"));
+    }
+
+    #[test]
+    fn no_op_reminder_says_task_is_still_pending() {
+        assert!(NO_OP_REMINDER.contains("no changes were detected"));
+        assert!(NO_OP_REMINDER.contains("The task is still"));
+    }
+
+    #[test]
+    fn verify_failed_section_includes_reason() {
+        let section = verify_failed_section(2, "Add login", "No tests were added");
+        assert!(section.contains("task #2 (Add login)"));
+        assert!(section.contains("Reviewer's reason: No tests were added"));
+    }
+
+    #[test]
+    fn code_review_section_lists_issues() {
+        let section = code_review_section(
+            4,
+            "Add search",
+            "No tests",
+            "- [major] src/search.rs: untested",
+        );
+        assert!(section.contains("task #4 (Add search)"));
+        assert!(section.contains("Reviewer's summary: No tests"));
+        assert!(section.contains("- [major] src/search.rs: untested"));
+    }
+
+    #[test]
+    fn review_rejected_section_includes_feedback() {
+        let section = review_rejected_section(3, "Add logout", "Keep the old button");
+        assert!(section.contains("task #3 (Add logout)"));
+        assert!(section.contains("The user's feedback: Keep the old button"));
+        assert!(review_rejected_section(3, "Add logout", "").contains("feedback: none given"));
+    }
+
+    #[test]
+    fn definition_of_done_section_lists_items() {
+        let section = definition_of_done_section(&["CHANGELOG updated".to_string()]).unwrap();
+        assert!(section.contains("## Definition of Done"));
+        assert!(section.contains("- CHANGELOG updated"));
+        assert!(definition_of_done_section(&[]).is_none());
+    }
+
+    #[test]
+    fn rating_feedback_section_quotes_notes() {
+        let record: IterationRecord = serde_json::from_str(
+            r#"{"run_id": "r", "loop_number": 1, "category": "feature", "status": "completed",
+                "duration_secs": 1.0, "cost_usd": 0.1, "finished_at": "2026-01-01T00:00:00Z",
+                "rating": 2, "rating_note": "No tests"}"#,
+        )
+        .unwrap();
+        let section = rating_feedback_section(&[&record]).unwrap();
+        assert!(section.contains("rated similar previous work (feature) 2/5 because: No tests"));
+        assert!(rating_feedback_section(&[]).is_none());
+    }
+
+    #[test]
+    fn master_prompt_contains_json_output_instructions() {
+        assert!(MASTER_PROMPT.contains("output a JSON summary"));
+        assert!(MASTER_PROMPT.contains("prd_complete"));
+    }
+}
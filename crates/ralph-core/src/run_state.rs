@@ -0,0 +1,363 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::prd;
+use crate::storage::{StorageError, storage};
+
+#[derive(Error, Debug)]
+pub enum RunStateError {
+    #[error("Failed to access run state: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Failed to parse run state: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+/// Where a task stands with the `--parallel` orchestrator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    /// Handed to a worker, which is working on it
+    Running,
+    /// The worker's branch was merged and the task passes
+    Completed,
+    /// The worker's branch was merged but the task isn't finished
+    Merged,
+    /// The worker's branch conflicted with work merged before it
+    Conflicted,
+    /// The worker's branch failed the quality gates once merged, so the merge was dropped
+    GatesFailed,
+    /// The worker failed, timed out or committed nothing
+    Failed,
+    /// The user stopped the worker
+    Stopped,
+}
+
+impl TaskState {
+    pub fn name(self) -> &'static str {
+        match self {
+            TaskState::Running => "running",
+            TaskState::Completed => "completed",
+            TaskState::Merged => "merged",
+            TaskState::Conflicted => "conflicted",
+            TaskState::GatesFailed => "gates_failed",
+            TaskState::Failed => "failed",
+            TaskState::Stopped => "stopped",
+        }
+    }
+
+    /// Whether the worker's attempt went wrong, leaving nothing merged
+    pub fn is_failure(self) -> bool {
+        matches!(
+            self,
+            TaskState::Conflicted | TaskState::GatesFailed | TaskState::Failed
+        )
+    }
+}
+
+/// A task entering a new state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskTransition {
+    /// Description of the task
+    pub task: String,
+    pub state: TaskState,
+    /// Loop the worker ran as
+    #[serde(rename = "loop")]
+    pub loop_number: u64,
+    pub at: DateTime<Utc>,
+}
+
+/// Persistent state of the current (or most recent) build run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    /// Unique run identifier
+    pub id: String,
+
+    /// PRD the run is executing
+    pub prd_path: String,
+
+    /// Dedicated git branch the run commits to (--branch)
+    #[serde(default)]
+    pub branch: Option<String>,
+
+    /// Human-readable name for the run (--label, or renamed from the TUI)
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// Number of loops started so far
+    #[serde(default)]
+    pub loop_count: u64,
+
+    /// Completed 1-indexed step numbers per unfinished task, keyed by task description.
+    /// Carried over between runs so a resumed build knows which steps remain.
+    #[serde(default)]
+    pub step_progress: BTreeMap<String, Vec<u32>>,
+
+    /// Signal that stopped the run partway (e.g. "SIGTERM"); the next build resumes the run
+    #[serde(default)]
+    pub interrupted_by: Option<String>,
+
+    /// Every state the `--parallel` orchestrator moved a task through this run, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub task_transitions: Vec<TaskTransition>,
+
+    /// Run start time
+    pub started_at: DateTime<Utc>,
+
+    /// Last update time
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RunState {
+    /// Create a new run for the given PRD
+    pub fn new(prd_path: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            prd_path: prd_path.to_string(),
+            branch: None,
+            label: None,
+            loop_count: 0,
+            step_progress: BTreeMap::new(),
+            interrupted_by: None,
+            task_transitions: Vec::new(),
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Start a new run, carrying over step progress from the previous run's state. A run of
+    /// the same PRD that a signal stopped is picked up again instead, loop count and all.
+    pub fn start(prd_path: &str) -> Self {
+        let mut state = Self::new(prd_path);
+        if let Ok(Some(mut previous)) = Self::load(prd_path) {
+            if previous.interrupted_by.is_some() && previous.prd_path == prd_path {
+                previous.interrupted_by = None;
+                previous.updated_at = state.updated_at;
+                return previous;
+            }
+            state.step_progress = previous.step_progress;
+        }
+        state
+    }
+
+    /// Get the run state file path for a given PRD path
+    pub fn file_path(prd_path: &str) -> PathBuf {
+        prd::state_path_for(prd_path, ".ralph-run.json")
+    }
+
+    /// Load the last run's state, if one was saved
+    pub fn load(prd_path: &str) -> Result<Option<Self>, RunStateError> {
+        match storage().load(&Self::file_path(prd_path))? {
+            Some(content) => Ok(Some(serde_json::from_str(&content)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the run state
+    pub fn save(&self) -> Result<(), RunStateError> {
+        let content = serde_json::to_string_pretty(self)?;
+        storage().save(&Self::file_path(&self.prd_path), &content)?;
+        Ok(())
+    }
+
+    /// Record which steps of a task are done; a task with no done steps is forgotten
+    pub fn record_steps(&mut self, task_description: &str, mut steps: Vec<u32>) {
+        steps.sort_unstable();
+        steps.dedup();
+        if steps.is_empty() {
+            self.step_progress.remove(task_description);
+        } else {
+            self.step_progress
+                .insert(task_description.to_string(), steps);
+        }
+        self.updated_at = Utc::now();
+    }
+
+    /// Drop progress for tasks that are no longer pending (finished or removed from the PRD)
+    pub fn retain_pending_tasks(&mut self, pending: &[&str]) {
+        self.step_progress
+            .retain(|task, _| pending.contains(&task.as_str()));
+    }
+
+    /// Rename the run; an empty label clears it. Returns true if the label changed.
+    pub fn set_label(&mut self, label: Option<&str>) -> bool {
+        let label = label.map(str::trim).filter(|l| !l.is_empty());
+        if self.label.as_deref() == label {
+            return false;
+        }
+        self.label = label.map(str::to_string);
+        self.updated_at = Utc::now();
+        true
+    }
+
+    /// Record that `signal` stopped the run, so the next build resumes it
+    pub fn interrupt(&mut self, signal: &str) {
+        self.interrupted_by = Some(signal.to_string());
+        self.updated_at = Utc::now();
+    }
+
+    /// Record that another loop has started
+    pub fn record_loop(&mut self, loop_count: u64) {
+        self.loop_count = loop_count;
+        self.updated_at = Utc::now();
+    }
+
+    /// Record that the orchestrator moved `task` into `state` in loop `loop_number`
+    pub fn record_task_state(&mut self, task: &str, state: TaskState, loop_number: u64) {
+        let now = Utc::now();
+        self.task_transitions.push(TaskTransition {
+            task: task.to_string(),
+            state,
+            loop_number,
+            at: now,
+        });
+        self.updated_at = now;
+    }
+
+    /// The state the orchestrator last moved `task` into this run, if any
+    pub fn task_state(&self, task: &str) -> Option<TaskState> {
+        self.task_transitions
+            .iter()
+            .rev()
+            .find(|t| t.task == task)
+            .map(|t| t.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn file_path_sits_next_to_prd() {
+        let path = RunState::file_path("/some/dir/prd.json");
+        assert_eq!(path, Path::new("/some/dir").join(".ralph-run.json"));
+        let path = RunState::file_path("/some/dir/auth.json");
+        assert_eq!(path, Path::new("/some/dir").join(".ralph-run.auth.json"));
+    }
+
+    #[test]
+    fn load_missing_returns_none() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        assert!(
+            RunState::load(prd_path.to_str().unwrap())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+
+        let mut state = RunState::new(prd_path);
+        state.branch = Some("ralph/feature".to_string());
+        state.record_loop(3);
+        state.save().unwrap();
+
+        let loaded = RunState::load(prd_path).unwrap().unwrap();
+        assert_eq!(loaded.id, state.id);
+        assert_eq!(loaded.branch.as_deref(), Some("ralph/feature"));
+        assert_eq!(loaded.loop_count, 3);
+    }
+
+    #[test]
+    fn set_label_trims_and_clears() {
+        let mut state = RunState::new("prd.json");
+        assert!(state.set_label(Some("  auth-overhaul night 2 ")));
+        assert_eq!(state.label.as_deref(), Some("auth-overhaul night 2"));
+        assert!(!state.set_label(Some("auth-overhaul night 2")));
+        assert!(state.set_label(Some("   ")));
+        assert_eq!(state.label, None);
+    }
+
+    #[test]
+    fn start_carries_over_step_progress() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+
+        let mut previous = RunState::new(prd_path);
+        previous.record_steps("Login", vec![2, 1, 2]);
+        previous.save().unwrap();
+
+        let next = RunState::start(prd_path);
+        assert_ne!(next.id, previous.id);
+        assert_eq!(next.step_progress.get("Login"), Some(&vec![1, 2]));
+    }
+
+    #[test]
+    fn start_resumes_a_run_stopped_by_a_signal() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+
+        let mut previous = RunState::new(prd_path);
+        previous.label = Some("nightly".to_string());
+        previous.record_loop(4);
+        previous.interrupt("SIGTERM");
+        previous.save().unwrap();
+
+        let resumed = RunState::start(prd_path);
+        assert_eq!(resumed.id, previous.id);
+        assert_eq!(resumed.label.as_deref(), Some("nightly"));
+        assert_eq!(resumed.loop_count, 4);
+        assert_eq!(resumed.interrupted_by, None);
+
+        // A finished run, or another PRD's, starts afresh
+        resumed.save().unwrap();
+        assert_ne!(RunState::start(prd_path).id, previous.id);
+        previous.save().unwrap();
+        let other = dir.path().join("other.json");
+        assert_ne!(RunState::start(other.to_str().unwrap()).id, previous.id);
+    }
+
+    #[test]
+    fn record_steps_and_retain_pending() {
+        let mut state = RunState::new("prd.json");
+        state.record_steps("Login", vec![1]);
+        state.record_steps("Logout", vec![1]);
+        state.record_steps("Signup", vec![]);
+        assert_eq!(state.step_progress.len(), 2);
+
+        state.retain_pending_tasks(&["Login"]);
+        assert!(state.step_progress.contains_key("Login"));
+        assert!(!state.step_progress.contains_key("Logout"));
+    }
+
+    #[test]
+    fn task_state_is_the_latest_transition() {
+        let mut state = RunState::new("prd.json");
+        assert_eq!(state.task_state("Login"), None);
+        state.record_task_state("Login", TaskState::Running, 1);
+        state.record_task_state("Logout", TaskState::Running, 2);
+        state.record_task_state("Login", TaskState::Conflicted, 1);
+        assert_eq!(state.task_state("Login"), Some(TaskState::Conflicted));
+        assert_eq!(state.task_state("Logout"), Some(TaskState::Running));
+        assert!(TaskState::Conflicted.is_failure());
+        assert!(!TaskState::Merged.is_failure());
+
+        let json = serde_json::to_value(&state.task_transitions[2]).unwrap();
+        assert_eq!(json["state"], "conflicted");
+        assert_eq!(json["loop"], 1);
+    }
+
+    #[test]
+    fn record_loop_updates_timestamp() {
+        let mut state = RunState::new("prd.json");
+        let started = state.updated_at;
+        state.record_loop(1);
+        assert_eq!(state.loop_count, 1);
+        assert!(state.updated_at >= started);
+    }
+}
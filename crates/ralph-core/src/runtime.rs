@@ -0,0 +1,30 @@
+//! The async runtime ralph's own waits run on.
+//!
+//! Claude, gate commands and plan forks are tokio children. Callers that are already async
+//! can await the `async fn`s in this crate on their own runtime; synchronous callers enter
+//! this thread's current-thread runtime through `block_on`.
+
+use std::future::{Future, pending};
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+thread_local! {
+    // Children spawned on the runtime are reaped by it, so one runtime serves every wait
+    static RUNTIME: Runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the async runtime");
+}
+
+/// Run `future` to completion on this thread's runtime. Not to be called from inside it.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    RUNTIME.with(|runtime| runtime.block_on(future))
+}
+
+/// Wait for `duration`, or forever without one
+pub async fn sleep_or_forever(duration: Option<Duration>) {
+    match duration {
+        Some(duration) => tokio::time::sleep(duration).await,
+        None => pending().await,
+    }
+}
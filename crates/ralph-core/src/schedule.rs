@@ -0,0 +1,300 @@
+//! When builds start: once (`ralph build --start-at`) or on a cron schedule (`ralph daemon`).
+//!
+//! Times are wall-clock times in the local time zone, so "22:30" means half past ten tonight
+//! wherever ralph runs. Everything here works on naive local times; callers convert from and
+//! to the clock.
+
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Timelike};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("Invalid start time '{0}': expected HH:MM or YYYY-MM-DDTHH:MM (seconds optional)")]
+    Invalid(String),
+
+    #[error("Invalid schedule '{expr}': {reason}")]
+    InvalidCron { expr: String, reason: String },
+}
+
+/// A start time as given on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartAt {
+    /// The next time the clock shows this time: today, or tomorrow if it has passed
+    Time(NaiveTime),
+    /// This exact moment
+    DateTime(NaiveDateTime),
+}
+
+impl FromStr for StartAt {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        for format in ["%H:%M", "%H:%M:%S"] {
+            if let Ok(time) = NaiveTime::parse_from_str(s, format) {
+                return Ok(StartAt::Time(time));
+            }
+        }
+        for format in [
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%d %H:%M",
+            "%Y-%m-%d %H:%M:%S",
+        ] {
+            if let Ok(at) = NaiveDateTime::parse_from_str(s, format) {
+                return Ok(StartAt::DateTime(at));
+            }
+        }
+        Err(ScheduleError::Invalid(s.to_string()))
+    }
+}
+
+impl StartAt {
+    /// The moment to start at, seen from `now`; None for a date and time already past
+    pub fn next_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match *self {
+            StartAt::Time(time) => {
+                let today = now.date().and_time(time);
+                if today > now {
+                    Some(today)
+                } else {
+                    now.date()
+                        .succ_opt()
+                        .map(|tomorrow| tomorrow.and_time(time))
+                }
+            }
+            StartAt::DateTime(at) => (at > now).then_some(at),
+        }
+    }
+}
+
+/// Days searched for the next match of a schedule: enough for a February 29th
+const SEARCH_DAYS: i64 = 366 * 8;
+
+/// A five-field cron schedule: minute, hour, day of month, month and day of week. Fields take
+/// `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `8-18/2`); Sunday is 0 or
+/// 7. As in cron, when both the day of month and the day of week are restricted, a day
+/// matching either one is due.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for Cron {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| ScheduleError::InvalidCron {
+            expr: s.to_string(),
+            reason,
+        };
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(format!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            )));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7).map_err(&invalid)?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Cron {
+            expr: s.split_whitespace().collect::<Vec<_>>().join(" "),
+            minutes: parse_field(minute, 0, 59).map_err(&invalid)?,
+            hours: parse_field(hour, 0, 23).map_err(&invalid)?,
+            days: parse_field(day, 1, 31).map_err(&invalid)?,
+            months: parse_field(month, 1, 12).map_err(&invalid)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+}
+
+impl std::fmt::Display for Cron {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl Cron {
+    /// The first minute after `now` the schedule is due; None if it never is (e.g. "0 0 30 2 *")
+    pub fn next_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = now.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for offset in 0..SEARCH_DAYS {
+            let date = start.date() + Duration::days(offset);
+            if !self.day_matches(date) {
+                continue;
+            }
+            let first_day = offset == 0;
+            for hour in 0..24 {
+                if self.hours & (1 << hour) == 0 || (first_day && hour < start.hour()) {
+                    continue;
+                }
+                for minute in 0..60 {
+                    if self.minutes & (1 << minute) == 0
+                        || (first_day && hour == start.hour() && minute < start.minute())
+                    {
+                        continue;
+                    }
+                    return date.and_hms_opt(hour, minute, 0);
+                }
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, date: chrono::NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+}
+
+/// Bitmask of the values a cron field allows, each between `min` and `max`
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|&step| step > 0)
+                    .ok_or_else(|| format!("bad step in '{}'", part))?,
+            ),
+            None => (part, 1),
+        };
+        let value = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or_else(|| format!("'{}' is not a number from {} to {}", s, min, max))
+        };
+        let (from, to) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((from, to)) => (value(from)?, value(to)?),
+            // A step on a single value runs to the end of the field, as in cron
+            None if step > 1 => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if from > to {
+            return Err(format!("range '{}' runs backwards", range));
+        }
+        for v in (from..=to).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn parses_times_and_date_times() {
+        assert_eq!(
+            "22:30".parse::<StartAt>(),
+            Ok(StartAt::Time(NaiveTime::from_hms_opt(22, 30, 0).unwrap()))
+        );
+        assert_eq!(
+            "2024-06-01T02:00".parse::<StartAt>(),
+            Ok(StartAt::DateTime(at("2024-06-01 02:00:00")))
+        );
+        assert_eq!(
+            "2024-06-01 02:00:30".parse::<StartAt>(),
+            Ok(StartAt::DateTime(at("2024-06-01 02:00:30")))
+        );
+        let err = "tonight".parse::<StartAt>().unwrap_err();
+        assert!(err.to_string().contains("Invalid start time 'tonight'"));
+        assert!("25:00".parse::<StartAt>().is_err());
+    }
+
+    #[test]
+    fn a_time_is_today_or_tomorrow() {
+        let now = at("2024-06-01 21:00:00");
+        let tonight: StartAt = "22:30".parse().unwrap();
+        assert_eq!(tonight.next_after(now), Some(at("2024-06-01 22:30:00")));
+        let early: StartAt = "02:00".parse().unwrap();
+        assert_eq!(early.next_after(now), Some(at("2024-06-02 02:00:00")));
+        // Right now counts as passed
+        let nine: StartAt = "21:00".parse().unwrap();
+        assert_eq!(nine.next_after(now), Some(at("2024-06-02 21:00:00")));
+    }
+
+    #[test]
+    fn a_past_date_time_has_no_start() {
+        let now = at("2024-06-01 21:00:00");
+        let past: StartAt = "2024-05-31T02:00".parse().unwrap();
+        assert_eq!(past.next_after(now), None);
+    }
+
+    #[test]
+    fn cron_finds_the_next_due_minute() {
+        let nightly: Cron = "0 2 * * *".parse().unwrap();
+        assert_eq!(
+            nightly.next_after(at("2024-06-01 21:00:00")),
+            Some(at("2024-06-02 02:00:00"))
+        );
+        // Due at exactly now means the next one
+        assert_eq!(
+            nightly.next_after(at("2024-06-02 02:00:00")),
+            Some(at("2024-06-03 02:00:00"))
+        );
+        let quarterly: Cron = "*/15 9-17 * * 1-5".parse().unwrap();
+        // Saturday evening: the next one is Monday at nine
+        assert_eq!(
+            quarterly.next_after(at("2024-06-01 18:07:30")),
+            Some(at("2024-06-03 09:00:00"))
+        );
+        assert_eq!(
+            quarterly.next_after(at("2024-06-03 09:07:00")),
+            Some(at("2024-06-03 09:15:00"))
+        );
+        // Sundays, as 7, or the 1st of the month
+        let either: Cron = "30 3 1 * 7".parse().unwrap();
+        assert_eq!(
+            either.next_after(at("2024-06-01 04:00:00")),
+            Some(at("2024-06-02 03:30:00"))
+        );
+        assert_eq!(either.to_string(), "30 3 1 * 7");
+        let never: Cron = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(never.next_after(at("2024-06-01 00:00:00")), None);
+    }
+
+    #[test]
+    fn cron_rejects_bad_fields() {
+        for (expr, reason) in [
+            ("0 2 * *", "expected 5 fields"),
+            ("60 2 * * *", "'60' is not a number from 0 to 59"),
+            ("0 2 * * */0", "bad step"),
+            ("0 5-2 * * *", "runs backwards"),
+        ] {
+            let err = expr.parse::<Cron>().unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", expr, err);
+        }
+    }
+}
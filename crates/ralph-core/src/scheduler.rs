@@ -0,0 +1,165 @@
+//! Picking tasks for parallel workers (`ralph build --parallel N`).
+//!
+//! Each worker runs its own Claude in a separate git work tree, so the tasks running at the
+//! same time must not depend on each other. When the PRD declares dependencies, ralph trusts
+//! the graph: a task is ready once everything it depends on passes, and a task waiting on a
+//! running one isn't. Without any, it can't tell, so it only runs tasks from different
+//! categories together.
+
+use std::collections::HashSet;
+
+use crate::prd::{self, Prd, Task};
+use crate::prompt::{self, MasterPrompt};
+use crate::toc::{self, TocStatus};
+
+/// Most workers `--parallel` accepts
+pub const MAX_WORKERS: usize = 8;
+
+/// Up to `slots` pending tasks (0-based, in run order) that can start now, alongside the
+/// `running` tasks. Tasks in `skip` aren't handed out. Both lists hold task descriptions.
+pub fn pick(
+    prd: &Prd,
+    categories: &[String],
+    running: &[&str],
+    skip: &[&str],
+    slots: usize,
+) -> Vec<usize> {
+    let has_graph = prd.tasks.iter().any(|t| !t.depends_on.is_empty());
+    let mut taken_categories: HashSet<String> = prd
+        .tasks
+        .iter()
+        .filter(|t| running.contains(&t.description.as_str()))
+        .map(|t| t.category.to_lowercase())
+        .collect();
+    toc::build(prd)
+        .iter()
+        .filter(|e| e.status == TocStatus::Ready)
+        .map(|e| e.number - 1)
+        .filter(|&i| {
+            let task = &prd.tasks[i];
+            task.in_categories(categories)
+                && !running.contains(&task.description.as_str())
+                && !skip.contains(&task.description.as_str())
+        })
+        .filter(|&i| has_graph || taken_categories.insert(prd.tasks[i].category.to_lowercase()))
+        .take(slots)
+        .collect()
+}
+
+/// Name of the branch a worker commits to
+pub fn branch_name(run_id: &str, loop_number: u64, task_number: usize) -> String {
+    let run: String = run_id.chars().take(8).collect();
+    format!(
+        "ralph/parallel/{}-{}-task-{}",
+        run, loop_number, task_number
+    )
+}
+
+/// Prompt for a worker: its task in the project's loop instructions, with the task's
+/// category addendum and the agent docs as a sequential loop gets them, in its own work
+/// tree and leaving the PRD to ralph
+pub fn worker_prompt(
+    master: &MasterPrompt,
+    prd_path: &str,
+    task_number: usize,
+    task: &Task,
+) -> String {
+    let task_json = serde_json::to_string_pretty(task).unwrap_or_default();
+    let mut constraints = prompt::task_focus_section(task_number, &task.description);
+    if let Some(section) = master.addendum_section(&task.category) {
+        constraints.push_str(&section);
+    }
+    format!(
+        r#"## Your Task
+
+You are one of several agents working on the PRD at {prd_path} at the same time, each in its
+own git work tree on its own branch. Ralph merges the branches once everyone is done. Yours is
+task #{task_number}:
+
+```json
+{task_json}
+```{docs}{instructions}
+## Parallel Worker
+
+Work only on task #{task_number}: other agents have the other tasks, so don't touch them. Don't
+edit the PRD, {progress_file} or {completed_file}, whatever the instructions above say: ralph
+updates them after merging, and edits there would conflict with the other agents. Commit your
+work on this branch, and report task_number {task_number} with prd_complete false.
+"#,
+        docs = master.docs_note(),
+        instructions = master.render(prd_path, &constraints),
+        progress_file = master.progress_file(),
+        completed_file = prd::completed_path_for(prd_path).display(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prd(json: &str) -> Prd {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn without_dependencies_one_task_per_category() {
+        let prd = prd(r#"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "api", "description": "A", "steps": [], "passes": false},
+            {"category": "API", "description": "B", "steps": [], "passes": false},
+            {"category": "ui", "description": "C", "steps": [], "passes": false},
+            {"category": "docs", "description": "D", "steps": [], "passes": true},
+            {"category": "docs", "description": "E", "steps": [], "passes": false}
+        ]}"#);
+        assert_eq!(pick(&prd, &[], &[], &[], 4), vec![0, 2, 4]);
+        assert_eq!(pick(&prd, &[], &[], &[], 2), vec![0, 2]);
+        assert_eq!(pick(&prd, &["api".to_string()], &[], &[], 4), vec![0]);
+        // A running task holds its category; a skipped one doesn't
+        assert_eq!(pick(&prd, &[], &["C"], &["A"], 4), vec![1, 4]);
+    }
+
+    #[test]
+    fn with_dependencies_every_ready_task() {
+        let prd = prd(r#"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "api", "description": "A", "steps": [], "passes": false},
+            {"category": "api", "description": "B", "steps": [], "passes": false},
+            {"category": "api", "description": "C", "steps": [], "passes": false, "depends_on": ["A"]}
+        ]}"#);
+        assert_eq!(pick(&prd, &[], &[], &[], 4), vec![0, 1]);
+        // Running tasks aren't handed out again, and C still waits for A
+        assert_eq!(pick(&prd, &[], &["A"], &[], 4), vec![1]);
+    }
+
+    #[test]
+    fn worker_prompt_follows_the_projects_instructions() {
+        let task: Task = serde_json::from_str(
+            r#"{"category": "API", "description": "Add login", "steps": [], "passes": false}"#,
+        )
+        .unwrap();
+        let mut master = MasterPrompt::default();
+        master.addenda = [("api".to_string(), "Version every route.".to_string())].into();
+        master.docs = vec!["CLAUDE.md".to_string()];
+        let master = master.in_workdir("packages/api");
+        let prompt = worker_prompt(&master, "../../prd.json", 1, &task);
+        assert!(prompt.contains("## Notes for API Tasks\n\nVersion every route."));
+        assert!(prompt.contains("@../../CLAUDE.md"));
+        assert!(prompt.contains("@../../progress.txt"));
+        assert!(prompt.contains("../../progress.txt or ../../completed.json"));
+    }
+
+    #[test]
+    fn worker_prompt_names_task_and_keeps_out_of_the_prd() {
+        let task: Task = serde_json::from_str(
+            r#"{"category": "api", "description": "Add login", "steps": [], "passes": false}"#,
+        )
+        .unwrap();
+        let prompt = worker_prompt(&MasterPrompt::default(), "prd.json", 3, &task);
+        assert!(prompt.contains("Yours is\ntask #3"));
+        assert!(prompt.contains("\"Add login\""));
+        assert!(prompt.contains("Don't\nedit the PRD, progress.txt or completed.json"));
+        assert!(prompt.contains("limited to task #3 (Add login)"));
+        assert_eq!(
+            branch_name("0123456789abcdef", 4, 3),
+            "ralph/parallel/01234567-4-task-3"
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! Per-iteration records of every build loop, and the percentile summaries built from them.
+//!
+//! The records double as the run history browsed by `ralph history`. Long-tail tasks (big
+//! refactors) skew averages badly, so summaries and ETAs use the median (p50) and p90
+//! rather than the mean.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::prd::{self, Prd};
+use crate::storage::{StorageError, storage};
+
+#[derive(Error, Debug)]
+pub enum StatsError {
+    #[error("Failed to access stats: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Failed to parse stats: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+/// Status recorded for a loop the user interrupted; such loops are left out of summaries
+pub const INTERRUPTED: &str = "interrupted";
+
+/// Status recorded for a loop the user aborted with Ctrl+X; also left out of summaries
+pub const ABORTED: &str = "aborted";
+
+/// One finished build loop
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IterationRecord {
+    /// Run the loop belonged to
+    pub run_id: String,
+    /// The run's label, if it had one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_label: Option<String>,
+    pub loop_number: u64,
+    /// Task number Claude reported, as numbered in the PRD at the time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_number: Option<i32>,
+    /// Description of that task, which follows it across runs as the numbering changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Category of the task Claude reported working on, if it reported one
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Model that did most of the work (by cost), if Claude reported it
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Task status Claude reported, "failed" if the loop produced no result, or
+    /// "interrupted"
+    pub status: String,
+    /// Claude's summary of the loop's work
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Commit HEAD moved to during the loop, if it made one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Wall-clock time of the loop, quality gates included
+    pub duration_secs: f64,
+    pub cost_usd: f64,
+    pub finished_at: DateTime<Utc>,
+    /// 1-5 rating the user gave the loop's work in the TUI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    /// Why the user gave that rating
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating_note: Option<String>,
+    /// Escalation steps in effect for the loop, e.g. "model+history" (`--escalate-after`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation: Option<String>,
+    /// Extended thinking level the loop ran with; None means Claude Code's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+}
+
+impl IterationRecord {
+    /// Whether the loop wasn't cut short by the user, so its duration and cost mean something
+    pub fn ran_to_end(&self) -> bool {
+        self.status != INTERRUPTED && self.status != ABORTED
+    }
+}
+
+/// Every recorded iteration for a PRD, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    #[serde(default)]
+    pub iterations: Vec<IterationRecord>,
+}
+
+impl Stats {
+    /// Get the stats file path for a given PRD path
+    pub fn file_path(prd_path: &str) -> PathBuf {
+        prd::state_path_for(prd_path, ".ralph-stats.json")
+    }
+
+    /// Load the recorded iterations; a PRD that was never built has none
+    pub fn load(prd_path: &str) -> Result<Self, StatsError> {
+        match storage().load(&Self::file_path(prd_path))? {
+            Some(content) => Ok(serde_json::from_str(&content)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Add one iteration to the PRD's stats file
+    pub fn append(prd_path: &str, record: IterationRecord) -> Result<(), StatsError> {
+        let mut stats = Self::load(prd_path)?;
+        stats.iterations.push(record);
+        let content = serde_json::to_string_pretty(&stats)?;
+        storage().save(&Self::file_path(prd_path), &content)?;
+        Ok(())
+    }
+
+    /// Rate a recorded loop; false if no such loop was recorded
+    pub fn rate(
+        prd_path: &str,
+        run_id: &str,
+        loop_number: u64,
+        rating: u8,
+        note: Option<String>,
+    ) -> Result<bool, StatsError> {
+        let mut stats = Self::load(prd_path)?;
+        let Some(record) = stats
+            .iterations
+            .iter_mut()
+            .rev()
+            .find(|r| r.run_id == run_id && r.loop_number == loop_number)
+        else {
+            return Ok(false);
+        };
+        record.rating = Some(rating);
+        record.rating_note = note;
+        let content = serde_json::to_string_pretty(&stats)?;
+        storage().save(&Self::file_path(prd_path), &content)?;
+        Ok(true)
+    }
+
+    /// Rated loops with a note in any of `categories`, most recent first
+    pub fn rating_notes(&self, categories: &[&str], limit: usize) -> Vec<&IterationRecord> {
+        self.iterations
+            .iter()
+            .rev()
+            .filter(|r| r.rating.is_some() && r.rating_note.is_some())
+            .filter(|r| {
+                r.category
+                    .as_deref()
+                    .is_some_and(|c| categories.contains(&c))
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Summary over every iteration
+    pub fn overall(&self) -> Option<Summary> {
+        Summary::of(self.timed())
+    }
+
+    /// Iterations that ran to the end, so their duration and cost mean something
+    fn timed(&self) -> impl Iterator<Item = &IterationRecord> {
+        self.iterations.iter().filter(|r| r.ran_to_end())
+    }
+
+    /// Summaries per task category; iterations without a category are grouped as "unknown"
+    pub fn by_category(&self) -> BTreeMap<String, Summary> {
+        self.group_by(|r| r.category.as_deref())
+    }
+
+    /// Summaries per model; iterations without a model are grouped as "unknown"
+    pub fn by_model(&self) -> BTreeMap<String, Summary> {
+        self.group_by(|r| r.model.as_deref())
+    }
+
+    /// Summaries per thinking level; loops run with Claude Code's default are "default"
+    pub fn by_thinking(&self) -> BTreeMap<String, Summary> {
+        self.group_by(|r| Some(r.thinking.as_deref().unwrap_or("default")))
+    }
+
+    fn group_by<'a>(
+        &'a self,
+        key: impl Fn(&'a IterationRecord) -> Option<&'a str>,
+    ) -> BTreeMap<String, Summary> {
+        let mut groups: BTreeMap<String, Vec<&IterationRecord>> = BTreeMap::new();
+        for record in self.timed() {
+            let name = key(record).unwrap_or("unknown").to_string();
+            groups.entry(name).or_default().push(record);
+        }
+        groups
+            .into_iter()
+            .filter_map(|(name, records)| Summary::of(records.into_iter()).map(|s| (name, s)))
+            .collect()
+    }
+
+    /// How each task's loops went, across every run, keyed by task description. Loops
+    /// recorded without a description aren't counted.
+    pub fn by_task(&self) -> BTreeMap<String, TaskStats> {
+        let mut tasks: BTreeMap<String, TaskStats> = BTreeMap::new();
+        for record in self.timed() {
+            let Some(task) = &record.task else {
+                continue;
+            };
+            let stats = tasks.entry(task.clone()).or_default();
+            stats.attempts += 1;
+            match record.status.as_str() {
+                "completed" => stats.completed += 1,
+                "failed" | "blocked" => stats.failed += 1,
+                _ => {}
+            }
+            stats.duration_total += record.duration_secs;
+            stats.cost_total += record.cost_usd;
+        }
+        tasks
+    }
+
+    /// Estimated time for the PRD's pending tasks: each pending task is assumed to take
+    /// its category's median (p50) and pessimistic (p90) iteration, falling back to the
+    /// overall figures for categories with no history. Returns (p50, p90) in seconds.
+    pub fn eta_secs(&self, prd: &Prd) -> Option<(f64, f64)> {
+        let overall = self.overall()?;
+        let by_category = self.by_category();
+        let mut eta = (0.0, 0.0);
+        for task in prd.tasks.iter().filter(|t| !t.passes) {
+            let summary = by_category.get(&task.category).unwrap_or(&overall);
+            eta.0 += summary.duration_p50;
+            eta.1 += summary.duration_p90;
+        }
+        Some(eta)
+    }
+}
+
+/// Duration and cost percentiles for a group of iterations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub duration_p50: f64,
+    pub duration_p90: f64,
+    pub duration_mean: f64,
+    pub cost_p50: f64,
+    pub cost_p90: f64,
+    pub cost_total: f64,
+    /// Iterations the user rated
+    pub rated: usize,
+    /// Mean of those ratings; None when nothing was rated
+    pub rating_mean: Option<f64>,
+}
+
+impl Summary {
+    fn of<'a>(records: impl Iterator<Item = &'a IterationRecord>) -> Option<Self> {
+        let records: Vec<&IterationRecord> = records.collect();
+        if records.is_empty() {
+            return None;
+        }
+        let durations: Vec<f64> = records.iter().map(|r| r.duration_secs).collect();
+        let costs: Vec<f64> = records.iter().map(|r| r.cost_usd).collect();
+        let ratings: Vec<f64> = records
+            .iter()
+            .filter_map(|r| r.rating.map(f64::from))
+            .collect();
+        Some(Self {
+            count: records.len(),
+            duration_p50: percentile(&durations, 50.0),
+            duration_p90: percentile(&durations, 90.0),
+            duration_mean: durations.iter().sum::<f64>() / durations.len() as f64,
+            cost_p50: percentile(&costs, 50.0),
+            cost_p90: percentile(&costs, 90.0),
+            cost_total: costs.iter().sum(),
+            rated: ratings.len(),
+            rating_mean: (!ratings.is_empty())
+                .then(|| ratings.iter().sum::<f64>() / ratings.len() as f64),
+        })
+    }
+}
+
+/// Loops spent on one task and how they ended
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TaskStats {
+    /// Loops that worked on the task and ran to the end
+    pub attempts: usize,
+    /// Those that reported the task completed
+    pub completed: usize,
+    /// Those that failed or reported the task blocked
+    pub failed: usize,
+    pub duration_total: f64,
+    pub cost_total: f64,
+}
+
+impl TaskStats {
+    /// Loops beyond the first
+    pub fn retries(&self) -> usize {
+        self.attempts.saturating_sub(1)
+    }
+
+    pub fn duration_mean(&self) -> f64 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.duration_total / self.attempts as f64
+        }
+    }
+}
+
+/// Nearest-rank percentile (`p` in 0..=100); 0.0 for no values
+pub fn percentile(values: &[f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Upper bounds (minutes) of the duration histogram buckets; the last bucket is open-ended
+pub const HISTOGRAM_BUCKETS_MINS: [u64; 5] = [5, 15, 30, 60, 120];
+
+/// Count iterations per duration bucket (see `HISTOGRAM_BUCKETS_MINS`), one extra bucket
+/// for everything longer than the last bound
+pub fn duration_histogram(records: &[IterationRecord]) -> Vec<usize> {
+    let mut counts = vec![0; HISTOGRAM_BUCKETS_MINS.len() + 1];
+    for record in records.iter().filter(|r| r.ran_to_end()) {
+        let bucket = HISTOGRAM_BUCKETS_MINS
+            .iter()
+            .position(|&mins| record.duration_secs < (mins * 60) as f64)
+            .unwrap_or(HISTOGRAM_BUCKETS_MINS.len());
+        counts[bucket] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(category: &str, model: &str, duration_secs: f64, cost_usd: f64) -> IterationRecord {
+        IterationRecord {
+            run_id: "run".to_string(),
+            run_label: None,
+            loop_number: 1,
+            task_number: Some(1),
+            task: Some("Add login".to_string()),
+            category: Some(category.to_string()),
+            model: Some(model.to_string()),
+            status: "completed".to_string(),
+            summary: None,
+            commit: None,
+            duration_secs,
+            cost_usd,
+            finished_at: Utc::now(),
+            rating: None,
+            rating_note: None,
+            escalation: None,
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let values = [10.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
+        assert_eq!(percentile(&values, 50.0), 5.0);
+        assert_eq!(percentile(&values, 90.0), 9.0);
+        assert_eq!(percentile(&values, 100.0), 10.0);
+        assert_eq!(percentile(&values, 0.0), 1.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn summaries_group_by_category_and_model() {
+        let stats = Stats {
+            iterations: vec![
+                record("feature", "sonnet", 60.0, 0.5),
+                record("feature", "sonnet", 120.0, 1.0),
+                record("refactor", "opus", 3600.0, 8.0),
+            ],
+        };
+        let by_category = stats.by_category();
+        assert_eq!(by_category["feature"].count, 2);
+        assert_eq!(by_category["feature"].duration_p50, 60.0);
+        assert_eq!(by_category["refactor"].cost_p90, 8.0);
+        assert_eq!(stats.by_model()["sonnet"].cost_total, 1.5);
+
+        // The refactor outlier drags the mean far above the median
+        let overall = stats.overall().unwrap();
+        assert_eq!(overall.duration_p50, 120.0);
+        assert_eq!(overall.duration_mean, 1260.0);
+    }
+
+    #[test]
+    fn by_thinking_groups_default_loops() {
+        let mut extended = record("feature", "opus", 300.0, 3.0);
+        extended.thinking = Some("extended".to_string());
+        let stats = Stats {
+            iterations: vec![record("feature", "sonnet", 60.0, 0.5), extended],
+        };
+        let by_thinking = stats.by_thinking();
+        assert_eq!(by_thinking["default"].count, 1);
+        assert_eq!(by_thinking["extended"].cost_total, 3.0);
+    }
+
+    #[test]
+    fn interrupted_and_aborted_loops_stay_out_of_summaries() {
+        let mut interrupted = record("feature", "sonnet", 5.0, 0.1);
+        interrupted.status = INTERRUPTED.to_string();
+        let mut aborted = record("feature", "sonnet", 9.0, 0.2);
+        aborted.status = ABORTED.to_string();
+        let stats = Stats {
+            iterations: vec![record("feature", "sonnet", 60.0, 0.5), interrupted, aborted],
+        };
+        assert_eq!(stats.overall().unwrap().count, 1);
+        assert_eq!(stats.by_category()["feature"].duration_p50, 60.0);
+        assert_eq!(
+            duration_histogram(&stats.iterations).iter().sum::<usize>(),
+            1
+        );
+    }
+
+    #[test]
+    fn eta_falls_back_to_overall_for_unseen_categories() {
+        let stats = Stats {
+            iterations: vec![
+                record("feature", "sonnet", 60.0, 0.5),
+                record("docs", "sonnet", 30.0, 0.1),
+            ],
+        };
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "A", "steps": [], "passes": false},
+                {"category": "test", "description": "B", "steps": [], "passes": false},
+                {"category": "docs", "description": "C", "steps": [], "passes": true}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(stats.eta_secs(&prd), Some((90.0, 120.0)));
+        assert_eq!(Stats::default().eta_secs(&prd), None);
+    }
+
+    #[test]
+    fn by_task_counts_retries_and_failures_across_runs() {
+        let mut failed = record("feature", "sonnet", 100.0, 1.0);
+        failed.status = "failed".to_string();
+        let mut interrupted = record("feature", "sonnet", 5.0, 0.1);
+        interrupted.status = INTERRUPTED.to_string();
+        let mut untracked = record("feature", "sonnet", 50.0, 0.5);
+        untracked.task = None;
+        let mut other = record("docs", "sonnet", 30.0, 0.2);
+        other.task = Some("Write README".to_string());
+        let stats = Stats {
+            iterations: vec![
+                failed,
+                interrupted,
+                record("feature", "sonnet", 200.0, 2.0),
+                untracked,
+                other,
+            ],
+        };
+        let by_task = stats.by_task();
+        assert_eq!(by_task.len(), 2);
+        let login = by_task["Add login"];
+        assert_eq!(login.attempts, 2);
+        assert_eq!(login.retries(), 1);
+        assert_eq!(login.completed, 1);
+        assert_eq!(login.failed, 1);
+        assert_eq!(login.duration_mean(), 150.0);
+        assert_eq!(by_task["Write README"].retries(), 0);
+    }
+
+    #[test]
+    fn histogram_buckets_durations() {
+        let records = vec![
+            record("a", "m", 60.0, 0.0),
+            record("a", "m", 10.0 * 60.0, 0.0),
+            record("a", "m", 5.0 * 3600.0, 0.0),
+        ];
+        assert_eq!(duration_histogram(&records), vec![1, 1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn rate_updates_record_and_feeds_summaries() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+        Stats::append(prd_path, record("feature", "sonnet", 60.0, 0.5)).unwrap();
+        let mut second = record("feature", "opus", 60.0, 0.5);
+        second.loop_number = 2;
+        Stats::append(prd_path, second).unwrap();
+
+        assert!(Stats::rate(prd_path, "run", 1, 2, Some("No tests".to_string())).unwrap());
+        assert!(Stats::rate(prd_path, "run", 2, 5, None).unwrap());
+        assert!(!Stats::rate(prd_path, "other", 1, 3, None).unwrap());
+
+        let stats = Stats::load(prd_path).unwrap();
+        let overall = stats.overall().unwrap();
+        assert_eq!(overall.rated, 2);
+        assert_eq!(overall.rating_mean, Some(3.5));
+        assert_eq!(stats.by_model()["sonnet"].rating_mean, Some(2.0));
+
+        let notes = stats.rating_notes(&["feature"], 5);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].rating_note.as_deref(), Some("No tests"));
+        assert!(stats.rating_notes(&["docs"], 5).is_empty());
+    }
+
+    #[test]
+    fn append_and_load_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = dir.path().join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+
+        assert!(Stats::load(prd_path).unwrap().iterations.is_empty());
+        Stats::append(prd_path, record("feature", "sonnet", 60.0, 0.5)).unwrap();
+        Stats::append(prd_path, record("docs", "sonnet", 30.0, 0.1)).unwrap();
+
+        let stats = Stats::load(prd_path).unwrap();
+        assert_eq!(stats.iterations.len(), 2);
+        assert_eq!(stats.iterations[1].category.as_deref(), Some("docs"));
+        assert!(dir.path().join(".ralph-stats.json").exists());
+    }
+}
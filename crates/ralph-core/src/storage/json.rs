@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use super::{Storage, StorageError};
+
+/// Stores each document as a plain file at its path
+pub struct JsonFileStorage;
+
+impl Storage for JsonFileStorage {
+    fn load(&self, path: &Path) -> Result<Option<String>, StorageError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(std::fs::read_to_string(path)?))
+    }
+
+    fn save(&self, path: &Path, contents: &str) -> Result<(), StorageError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), StorageError> {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn save_load_delete_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join("doc.json");
+        let storage = JsonFileStorage;
+
+        assert_eq!(storage.load(&path).unwrap(), None);
+        storage.save(&path, r#"{"a":1}"#).unwrap();
+        assert_eq!(storage.load(&path).unwrap().as_deref(), Some(r#"{"a":1}"#));
+
+        storage.delete(&path).unwrap();
+        assert!(!path.exists());
+        // Deleting again is fine
+        storage.delete(&path).unwrap();
+    }
+}
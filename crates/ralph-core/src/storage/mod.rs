@@ -0,0 +1,102 @@
+//! Persistence for plan sessions and build run state.
+//!
+//! Documents are addressed by the file path the JSON backend has always used (e.g.
+//! `plans/.ralph-session.json`), so switching backends doesn't change any caller.
+//! Database backends treat that path as an opaque key.
+
+pub mod json;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use std::path::Path;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+/// A place to keep JSON documents
+pub trait Storage: Send + Sync {
+    /// Read a document, or None if it doesn't exist
+    fn load(&self, path: &Path) -> Result<Option<String>, StorageError>;
+
+    /// Create or replace a document
+    fn save(&self, path: &Path, contents: &str) -> Result<(), StorageError>;
+
+    /// Remove a document; removing a missing document is not an error
+    fn delete(&self, path: &Path) -> Result<(), StorageError>;
+}
+
+/// Which storage implementation to use (`--storage`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum Backend {
+    /// One JSON file per document, next to the PRD
+    #[default]
+    Json,
+    /// A single ralph.db SQLite database in the working directory
+    Sqlite,
+}
+
+/// SQLite database file used by the sqlite backend
+pub const DB_FILE: &str = "ralph.db";
+
+static STORAGE: OnceLock<Box<dyn Storage>> = OnceLock::new();
+
+/// Select the backend for this process. Must be called before the first `storage()` call.
+pub fn init(backend: Backend) -> Result<(), StorageError> {
+    let storage = open(backend)?;
+    STORAGE
+        .set(storage)
+        .map_err(|_| StorageError::Unavailable("storage already initialised".to_string()))
+}
+
+/// The process-wide storage backend (JSON files unless `init` chose otherwise)
+pub fn storage() -> &'static dyn Storage {
+    STORAGE
+        .get_or_init(|| Box::new(json::JsonFileStorage))
+        .as_ref()
+}
+
+fn open(backend: Backend) -> Result<Box<dyn Storage>, StorageError> {
+    match backend {
+        Backend::Json => Ok(Box::new(json::JsonFileStorage)),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => Ok(Box::new(sqlite::SqliteStorage::open(Path::new(DB_FILE))?)),
+        #[cfg(not(feature = "sqlite"))]
+        Backend::Sqlite => Err(StorageError::Unavailable(format!(
+            "--storage sqlite needs ralph built with the `sqlite` feature (would use {})",
+            DB_FILE
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_storage_is_json_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("doc.json");
+        storage().save(&path, "{}").unwrap();
+        assert!(path.exists());
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn sqlite_unavailable_without_feature() {
+        let err = open(Backend::Sqlite).err().unwrap();
+        assert!(err.to_string().contains("`sqlite` feature"));
+    }
+}
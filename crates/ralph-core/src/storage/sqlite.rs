@@ -0,0 +1,108 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{Storage, StorageError};
+
+/// Stores every document in one SQLite database, keyed by its path
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open (creating if needed) the database at `db_path`
+    pub fn open(db_path: &Path) -> Result<Self, StorageError> {
+        let conn = Connection::open(db_path)?;
+        // WAL plus a busy timeout lets parallel ralph processes share the file
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS documents (
+                key        TEXT PRIMARY KEY,
+                contents   TEXT NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self, path: &Path) -> Result<Option<String>, StorageError> {
+        Ok(self
+            .conn()
+            .query_row(
+                "SELECT contents FROM documents WHERE key = ?1",
+                params![key(path)],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn save(&self, path: &Path, contents: &str) -> Result<(), StorageError> {
+        self.conn().execute(
+            "INSERT INTO documents (key, contents, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
+             ON CONFLICT(key) DO UPDATE SET contents = excluded.contents, updated_at = excluded.updated_at",
+            params![key(path), contents],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), StorageError> {
+        self.conn()
+            .execute("DELETE FROM documents WHERE key = ?1", params![key(path)])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn save_load_delete_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("ralph.db")).unwrap();
+        let doc = Path::new("plans/.ralph-session.json");
+
+        assert_eq!(storage.load(doc).unwrap(), None);
+        storage.save(doc, "one").unwrap();
+        storage.save(doc, "two").unwrap();
+        assert_eq!(storage.load(doc).unwrap().as_deref(), Some("two"));
+
+        storage.delete(doc).unwrap();
+        assert_eq!(storage.load(doc).unwrap(), None);
+        // Nothing is written next to the PRD
+        assert!(!doc.exists());
+    }
+
+    #[test]
+    fn documents_persist_across_connections() {
+        let dir = TempDir::new().unwrap();
+        let db = dir.path().join("ralph.db");
+        SqliteStorage::open(&db)
+            .unwrap()
+            .save(Path::new("a.json"), "kept")
+            .unwrap();
+
+        let reopened = SqliteStorage::open(&db).unwrap();
+        assert_eq!(
+            reopened.load(Path::new("a.json")).unwrap().as_deref(),
+            Some("kept")
+        );
+    }
+}
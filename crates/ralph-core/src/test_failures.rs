@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::prd::{Prd, Task};
+
+/// Test runner whose output format we understand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adapter {
+    Cargo,
+    Jest,
+    Pytest,
+}
+
+impl Adapter {
+    const ALL: [Adapter; 3] = [Adapter::Cargo, Adapter::Jest, Adapter::Pytest];
+
+    /// Guess the adapter from the test command
+    pub fn for_command(command: &str) -> Option<Self> {
+        if command.contains("cargo") {
+            Some(Adapter::Cargo)
+        } else if command.contains("pytest") {
+            Some(Adapter::Pytest)
+        } else if ["jest", "npm", "yarn", "pnpm", "npx"]
+            .iter()
+            .any(|tool| command.contains(tool))
+        {
+            Some(Adapter::Jest)
+        } else {
+            None
+        }
+    }
+
+    fn parse(self, output: &str) -> Vec<FailingTest> {
+        match self {
+            Adapter::Cargo => parse_cargo(output),
+            Adapter::Jest => parse_jest(output),
+            Adapter::Pytest => parse_pytest(output),
+        }
+    }
+}
+
+/// One failing test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailingTest {
+    /// Suite the test belongs to (module, file)
+    pub group: String,
+    pub name: String,
+    /// First line of the failure message, if the output had one
+    pub detail: Option<String>,
+}
+
+/// Pick a test command for the project in `dir`
+pub fn detect_test_command(dir: &Path) -> Option<&'static str> {
+    if dir.join("Cargo.toml").exists() {
+        Some("cargo test")
+    } else if dir.join("package.json").exists() {
+        Some("npm test")
+    } else if ["pytest.ini", "pyproject.toml", "setup.cfg", "tox.ini"]
+        .iter()
+        .any(|f| dir.join(f).exists())
+    {
+        Some("pytest")
+    } else {
+        None
+    }
+}
+
+/// Parse failures with the command's adapter, or the first adapter that finds any
+pub fn parse_failures(command: &str, output: &str) -> Vec<FailingTest> {
+    if let Some(adapter) = Adapter::for_command(command) {
+        return adapter.parse(output);
+    }
+    Adapter::ALL
+        .iter()
+        .map(|a| a.parse(output))
+        .find(|failures| !failures.is_empty())
+        .unwrap_or_default()
+}
+
+/// `test auth::login::rejects_bad_password ... FAILED`, with panic messages from the
+/// `---- name stdout ----` sections
+fn parse_cargo(output: &str) -> Vec<FailingTest> {
+    let mut details: BTreeMap<&str, String> = BTreeMap::new();
+    let mut current: Option<&str> = None;
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            current = Some(name);
+            continue;
+        }
+        if let Some(name) = current
+            && !details.contains_key(name)
+        {
+            let text = line.trim();
+            if !text.is_empty() && !text.starts_with("thread '") && !text.starts_with("note:") {
+                details.insert(name, text.to_string());
+            }
+        }
+    }
+
+    let mut failures = Vec::new();
+    for line in output.lines() {
+        let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|l| l.strip_suffix(" ... FAILED"))
+        else {
+            continue;
+        };
+        let group = name
+            .rsplit_once("::")
+            .map_or("(crate root)", |(module, _)| module);
+        failures.push(FailingTest {
+            group: group.to_string(),
+            name: name.to_string(),
+            detail: details.get(name).cloned(),
+        });
+    }
+    failures
+}
+
+/// `FAIL src/cart.test.js` headers followed by `● Cart › adds items` entries
+fn parse_jest(output: &str) -> Vec<FailingTest> {
+    let mut failures: Vec<FailingTest> = Vec::new();
+    let mut suite: Option<String> = None;
+    let mut awaiting_detail = false;
+
+    for line in output.lines() {
+        let text = line.trim();
+        if let Some(file) = text.strip_prefix("FAIL ") {
+            suite = Some(file.split_whitespace().next().unwrap_or(file).to_string());
+            awaiting_detail = false;
+        } else if text.starts_with("PASS ") {
+            suite = None;
+        } else if let (Some(name), Some(group)) = (text.strip_prefix("● "), &suite) {
+            let name = name.replace(" › ", " > ");
+            // "● Test suite failed to run" etc. are still worth a task
+            if !failures.iter().any(|f| &f.group == group && f.name == name) {
+                failures.push(FailingTest {
+                    group: group.clone(),
+                    name,
+                    detail: None,
+                });
+                awaiting_detail = true;
+            }
+        } else if awaiting_detail && !text.is_empty() {
+            if let Some(last) = failures.last_mut() {
+                last.detail = Some(text.to_string());
+            }
+            awaiting_detail = false;
+        }
+    }
+    failures
+}
+
+/// Short test summary lines: `FAILED tests/test_api.py::TestUsers::test_create - KeyError: 'id'`
+fn parse_pytest(output: &str) -> Vec<FailingTest> {
+    let mut failures = Vec::new();
+    for line in output.lines() {
+        let Some(rest) = line
+            .strip_prefix("FAILED ")
+            .or_else(|| line.strip_prefix("ERROR "))
+        else {
+            continue;
+        };
+        let (id, detail) = match rest.split_once(" - ") {
+            Some((id, detail)) => (id.trim(), Some(detail.trim().to_string())),
+            None => (rest.trim(), None),
+        };
+        let (group, name) = id.split_once("::").unwrap_or((id, id));
+        failures.push(FailingTest {
+            group: group.to_string(),
+            name: name.to_string(),
+            detail,
+        });
+    }
+    failures
+}
+
+/// Build a PRD with one fix task per failing group, gated on the test command
+pub fn failures_to_prd(command: &str, failures: &[FailingTest]) -> Prd {
+    let mut groups: BTreeMap<&str, Vec<&FailingTest>> = BTreeMap::new();
+    for failure in failures {
+        groups.entry(&failure.group).or_default().push(failure);
+    }
+
+    let tasks = groups
+        .into_iter()
+        .map(|(group, tests)| {
+            let mut steps: Vec<String> = tests
+                .iter()
+                .map(|t| match &t.detail {
+                    Some(detail) => format!("Make `{}` pass ({})", t.name, detail),
+                    None => format!("Make `{}` pass", t.name),
+                })
+                .collect();
+            steps.push(format!(
+                "Run `{}` and confirm nothing in {} fails",
+                command, group
+            ));
+            Task {
+                category: "bugfix".to_string(),
+                description: format!("Fix {} failing test(s) in {}", tests.len(), group),
+                steps,
+                passes: false,
+                priority: None,
+                estimate: None,
+                depends_on: Vec::new(),
+                thinking: None,
+                workdir: None,
+            }
+        })
+        .collect();
+
+    Prd {
+        schema_version: crate::prd::SCHEMA_VERSION,
+        name: "Fix failing tests".to_string(),
+        quality_gates: vec![command.to_string()],
+        workdir: None,
+        definition_of_done: Vec::new(),
+        coverage: None,
+        tasks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CARGO_OUTPUT: &str = "\
+running 3 tests
+test auth::login::accepts_valid ... ok
+test auth::login::rejects_bad_password ... FAILED
+test top_level ... FAILED
+
+failures:
+
+---- auth::login::rejects_bad_password stdout ----
+
+thread 'auth::login::rejects_bad_password' panicked at src/auth/login.rs:42:9:
+assertion `left == right` failed
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- top_level stdout ----
+thread 'top_level' panicked at src/lib.rs:3:5:
+boom
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored
+";
+
+    const JEST_OUTPUT: &str = "\
+PASS src/utils.test.js
+FAIL src/cart.test.js
+  ● Cart › adds items
+
+    expect(received).toBe(expected)
+
+  ● Cart › removes items
+
+    TypeError: cannot read property 'id'
+";
+
+    const PYTEST_OUTPUT: &str = "\
+=========================== short test summary info ============================
+FAILED tests/test_api.py::TestUsers::test_create - KeyError: 'id'
+FAILED tests/test_api.py::test_delete
+ERROR tests/test_db.py::test_connect - ConnectionError
+";
+
+    #[test]
+    fn cargo_adapter_reads_failed_tests_and_panics() {
+        let failures = parse_failures("cargo test", CARGO_OUTPUT);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].group, "auth::login");
+        assert_eq!(failures[0].name, "auth::login::rejects_bad_password");
+        assert_eq!(
+            failures[0].detail.as_deref(),
+            Some("assertion `left == right` failed")
+        );
+        assert_eq!(failures[1].group, "(crate root)");
+        assert_eq!(failures[1].detail.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn jest_adapter_groups_by_file() {
+        let failures = parse_failures("npm test", JEST_OUTPUT);
+        assert_eq!(failures.len(), 2);
+        assert!(failures.iter().all(|f| f.group == "src/cart.test.js"));
+        assert_eq!(failures[0].name, "Cart > adds items");
+        assert_eq!(
+            failures[0].detail.as_deref(),
+            Some("expect(received).toBe(expected)")
+        );
+    }
+
+    #[test]
+    fn pytest_adapter_reads_summary() {
+        let failures = parse_failures("pytest -q", PYTEST_OUTPUT);
+        assert_eq!(failures.len(), 3);
+        assert_eq!(failures[0].group, "tests/test_api.py");
+        assert_eq!(failures[0].name, "TestUsers::test_create");
+        assert_eq!(failures[0].detail.as_deref(), Some("KeyError: 'id'"));
+        assert_eq!(failures[1].detail, None);
+        assert_eq!(failures[2].group, "tests/test_db.py");
+    }
+
+    #[test]
+    fn unknown_command_tries_every_adapter() {
+        let failures = parse_failures("make check", PYTEST_OUTPUT);
+        assert_eq!(failures.len(), 3);
+        assert!(parse_failures("make check", "all good").is_empty());
+    }
+
+    #[test]
+    fn prd_has_one_task_per_group() {
+        let failures = parse_failures("pytest", PYTEST_OUTPUT);
+        let prd = failures_to_prd("pytest", &failures);
+        assert_eq!(prd.quality_gates, vec!["pytest"]);
+        assert_eq!(prd.tasks.len(), 2);
+        assert_eq!(
+            prd.tasks[0].description,
+            "Fix 2 failing test(s) in tests/test_api.py"
+        );
+        assert_eq!(
+            prd.tasks[0].steps[0],
+            "Make `TestUsers::test_create` pass (KeyError: 'id')"
+        );
+        assert!(
+            prd.tasks[0]
+                .steps
+                .last()
+                .unwrap()
+                .starts_with("Run `pytest`")
+        );
+        assert!(prd.tasks.iter().all(|t| !t.passes));
+    }
+
+    #[test]
+    fn detect_test_command_from_project_files() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(detect_test_command(dir.path()), None);
+        std::fs::write(dir.path().join("pyproject.toml"), "").unwrap();
+        assert_eq!(detect_test_command(dir.path()), Some("pytest"));
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        assert_eq!(detect_test_command(dir.path()), Some("cargo test"));
+    }
+}
@@ -0,0 +1,181 @@
+//! The test-writing phase after a completed task (`ralph build --with-tests`).
+//!
+//! The implementation loop is told to get the task done; a separate session then does nothing
+//! but write and strengthen tests for the files the task changed, and commits them on its own.
+
+use serde::Deserialize;
+
+use crate::claude::Usage;
+use crate::prd::Task;
+
+/// Turns the test writer may take: reading the change, writing tests and running them
+pub const MAX_TURNS: u32 = 60;
+
+/// Categories whose tasks get no test-writing phase: they're tests or docs already
+const SKIPPED_CATEGORIES: [&str; 5] = ["test", "tests", "testing", "docs", "documentation"];
+
+/// JSON schema for the test writer's structured output
+pub const OUTPUT_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "summary": {"type": "string", "description": "What the new or changed tests cover"},
+    "test_files": {"type": "array", "items": {"type": "string"}},
+    "tests_added": {"type": "integer", "minimum": 0},
+    "committed": {"type": "boolean"}
+  },
+  "required": ["summary", "test_files", "tests_added", "committed"]
+}"#;
+
+/// What the test writer reports
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Report {
+    pub summary: String,
+    #[serde(default)]
+    pub test_files: Vec<String>,
+    #[serde(default)]
+    pub tests_added: u32,
+    #[serde(default)]
+    pub committed: bool,
+}
+
+/// What a test-writing session cost, alongside its report
+#[derive(Debug)]
+pub struct TestWriting {
+    pub report: Report,
+    pub cost_usd: f64,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct TestWriterJsonOutput {
+    is_error: bool,
+    structured_output: Option<Report>,
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Whether a completed task gets a test-writing phase
+pub fn wants_tests(task: &Task) -> bool {
+    !SKIPPED_CATEGORIES
+        .iter()
+        .any(|skipped| task.category.eq_ignore_ascii_case(skipped))
+}
+
+/// Parse the test writer's `--output-format json` envelope
+pub fn parse_output(stdout: &str) -> Result<TestWriting, String> {
+    let wrapper: TestWriterJsonOutput = serde_json::from_str(stdout)
+        .map_err(|e| format!("Parse error: {}\n\nRaw output:\n{}", e, stdout))?;
+    match wrapper.structured_output {
+        Some(report) => Ok(TestWriting {
+            report,
+            cost_usd: wrapper.total_cost_usd.unwrap_or(0.0),
+            usage: wrapper.usage.unwrap_or_default(),
+        }),
+        None if wrapper.is_error => Err(format!("Claude returned an error:\n{}", stdout)),
+        None => Err(format!("No structured output:\n{}", stdout)),
+    }
+}
+
+/// Prompt for the test writer: the task, the files it changed and the gates to keep green
+pub fn make_prompt(
+    task_number: usize,
+    task: &Task,
+    changed_files: &[String],
+    quality_gates: &[String],
+) -> String {
+    let files = if changed_files.is_empty() {
+        "(unknown - look at the most recent commits)".to_string()
+    } else {
+        changed_files
+            .iter()
+            .map(|file| format!("- {}", file))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let gates = if quality_gates.is_empty() {
+        "(none - run the project's usual test command)".to_string()
+    } else {
+        quality_gates
+            .iter()
+            .map(|gate| format!("- `{}`", gate))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    format!(
+        r#"Another agent has just completed task #{task_number} ({category}): {description}
+
+Your only job is testing that work. Write new tests and strengthen existing ones for the
+behaviour this task added or changed: the main paths, edge cases and error handling. Put them
+where this project keeps its tests and follow the style of the tests already there.
+
+## Files the task changed
+
+{files}
+
+## Quality gates
+
+{gates}
+
+Rules:
+1. Don't change non-test code. If a test you write exposes a bug, leave the test out and
+   describe the bug in your summary instead.
+2. Every quality gate must pass when you're done.
+3. Commit only the test changes, in one commit whose message starts with
+   "Add tests for task #{task_number}".
+4. If the change is already well tested, add nothing and say so.
+
+Report what the tests cover, the test files you touched, how many tests you added and whether
+you committed."#,
+        category = task.category,
+        description = task.description,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(category: &str) -> Task {
+        serde_json::from_str(&format!(
+            r#"{{"category": "{}", "description": "Add login", "steps": [], "passes": true}}"#,
+            category
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_and_docs_tasks_get_no_test_phase() {
+        assert!(wants_tests(&task("feature")));
+        assert!(wants_tests(&task("bugfix")));
+        assert!(!wants_tests(&task("Testing")));
+        assert!(!wants_tests(&task("docs")));
+    }
+
+    #[test]
+    fn prompt_lists_files_and_gates() {
+        let prompt = make_prompt(
+            4,
+            &task("feature"),
+            &["src/login.rs".to_string()],
+            &["cargo test".to_string()],
+        );
+        assert!(prompt.contains("task #4 (feature): Add login"));
+        assert!(prompt.contains("- src/login.rs"));
+        assert!(prompt.contains("- `cargo test`"));
+        assert!(prompt.contains("\"Add tests for task #4\""));
+    }
+
+    #[test]
+    fn parse_output_reads_report_and_cost() {
+        let json = r#"{"type":"result","is_error":false,"total_cost_usd":0.2,"structured_output":{
+            "summary":"Covered empty passwords","test_files":["tests/login.rs"],"tests_added":3,"committed":true}}"#;
+        let writing = parse_output(json).unwrap();
+        assert_eq!(writing.report.tests_added, 3);
+        assert!(writing.report.committed);
+        assert_eq!(writing.cost_usd, 0.2);
+        assert!(parse_output("not json").is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Table of contents for a PRD: every task with its status, priority, estimate, dependencies
+//! and the order the build loop is expected to work through them.
+
+use crate::prd::{Prd, Priority, Task};
+
+/// Where a task stands in the expected run order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TocStatus {
+    /// Already passes
+    Done,
+    /// Pending with every dependency satisfied
+    Ready,
+    /// Pending behind a dependency that is itself pending
+    Waiting,
+    /// Part of a dependency cycle, so it can never become ready
+    Cycle,
+}
+
+impl TocStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            TocStatus::Done => "done",
+            TocStatus::Ready => "ready",
+            TocStatus::Waiting => "waiting",
+            TocStatus::Cycle => "cycle",
+        }
+    }
+}
+
+/// A `depends_on` entry resolved against the PRD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dependency {
+    /// Another PRD task (1-indexed) and whether it already passes
+    Task { number: usize, done: bool },
+    /// Not in the PRD - probably archived to completed.json, so treated as satisfied
+    Unknown(String),
+}
+
+impl Dependency {
+    pub fn label(&self) -> String {
+        match self {
+            Dependency::Task { number, done: true } => format!("#{}✓", number),
+            Dependency::Task {
+                number,
+                done: false,
+            } => format!("#{}", number),
+            Dependency::Unknown(name) => format!("{}?", name),
+        }
+    }
+}
+
+/// One row of the table of contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// 1-indexed position in the PRD
+    pub number: usize,
+    /// 1-indexed position in the expected run order; None for tasks that already pass
+    pub order: Option<usize>,
+    pub status: TocStatus,
+    pub category: String,
+    pub description: String,
+    pub priority: Option<Priority>,
+    pub estimate: Option<String>,
+    pub depends_on: Vec<Dependency>,
+}
+
+impl TocEntry {
+    /// Case-insensitive match against the searchable columns
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let priority = self.priority.map(|p| p.to_string()).unwrap_or_default();
+        [
+            self.description.as_str(),
+            self.category.as_str(),
+            self.status.label(),
+            priority.as_str(),
+            self.estimate.as_deref().unwrap_or(""),
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(&query))
+            || format!("#{}", self.number) == query
+    }
+}
+
+/// Resolve a `depends_on` entry: a task number ("3", "#3") or another task's description
+fn resolve(prd: &Prd, reference: &str) -> Option<usize> {
+    let reference = reference.trim();
+    if let Ok(number) = reference.trim_start_matches('#').parse::<usize>()
+        && (1..=prd.tasks.len()).contains(&number)
+    {
+        return Some(number - 1);
+    }
+    prd.tasks
+        .iter()
+        .position(|t| t.description.eq_ignore_ascii_case(reference))
+}
+
+/// List every task, pending ones in the order they're expected to run, then the passing ones.
+///
+/// A pending task becomes eligible once all of its pending dependencies are ahead of it;
+/// among eligible tasks the highest priority goes first, then PRD order. Tasks stuck in a
+/// dependency cycle go last.
+pub fn build(prd: &Prd) -> Vec<TocEntry> {
+    let deps: Vec<Vec<Option<usize>>> = prd
+        .tasks
+        .iter()
+        .map(|t| t.depends_on.iter().map(|d| resolve(prd, d)).collect())
+        .collect();
+    let blocks = |i: usize, scheduled: &[bool]| {
+        deps[i]
+            .iter()
+            .flatten()
+            .any(|&d| d != i && !prd.tasks[d].passes && !scheduled[d])
+    };
+
+    let mut scheduled = vec![false; prd.tasks.len()];
+    let mut order = Vec::new();
+    loop {
+        let next = (0..prd.tasks.len())
+            .filter(|&i| !prd.tasks[i].passes && !scheduled[i] && !blocks(i, &scheduled))
+            .min_by_key(|&i| (prd.tasks[i].priority_rank(), i));
+        let Some(i) = next else { break };
+        scheduled[i] = true;
+        order.push(i);
+    }
+    let in_cycle: Vec<usize> = (0..prd.tasks.len())
+        .filter(|&i| !prd.tasks[i].passes && !scheduled[i])
+        .collect();
+
+    let entry = |i: usize, order: Option<usize>, status: TocStatus| {
+        let task: &Task = &prd.tasks[i];
+        TocEntry {
+            number: i + 1,
+            order,
+            status,
+            category: task.category.clone(),
+            description: task.description.clone(),
+            priority: task.priority,
+            estimate: task.estimate.clone(),
+            depends_on: task
+                .depends_on
+                .iter()
+                .zip(&deps[i])
+                .map(|(reference, resolved)| match resolved {
+                    Some(d) => Dependency::Task {
+                        number: d + 1,
+                        done: prd.tasks[*d].passes,
+                    },
+                    None => Dependency::Unknown(reference.clone()),
+                })
+                .collect(),
+        }
+    };
+
+    let mut entries = Vec::with_capacity(prd.tasks.len());
+    for (position, &i) in order.iter().enumerate() {
+        let waiting = deps[i].iter().flatten().any(|&d| !prd.tasks[d].passes);
+        let status = if waiting {
+            TocStatus::Waiting
+        } else {
+            TocStatus::Ready
+        };
+        entries.push(entry(i, Some(position + 1), status));
+    }
+    for &i in &in_cycle {
+        entries.push(entry(i, None, TocStatus::Cycle));
+    }
+    for i in (0..prd.tasks.len()).filter(|&i| prd.tasks[i].passes) {
+        entries.push(entry(i, None, TocStatus::Done));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prd(json: &str) -> Prd {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn build_orders_dependencies_first() {
+        let prd = prd(r##"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Login page", "steps": [], "passes": false, "depends_on": ["Auth API"]},
+            {"category": "feature", "description": "Auth API", "steps": [], "passes": false, "estimate": "2h"},
+            {"category": "setup", "description": "Scaffold", "steps": [], "passes": true},
+            {"category": "test", "description": "E2E", "steps": [], "passes": false, "depends_on": ["1", "#3"]}
+        ]}"##);
+        let entries = build(&prd);
+        let numbers: Vec<usize> = entries.iter().map(|e| e.number).collect();
+        assert_eq!(numbers, vec![2, 1, 4, 3]);
+
+        assert_eq!(entries[0].order, Some(1));
+        assert_eq!(entries[0].status, TocStatus::Ready);
+        assert_eq!(entries[0].estimate.as_deref(), Some("2h"));
+        assert_eq!(entries[1].status, TocStatus::Waiting);
+        assert_eq!(
+            entries[2].depends_on,
+            vec![
+                Dependency::Task {
+                    number: 1,
+                    done: false
+                },
+                Dependency::Task {
+                    number: 3,
+                    done: true
+                },
+            ]
+        );
+        assert_eq!(entries[3].status, TocStatus::Done);
+        assert_eq!(entries[3].order, None);
+    }
+
+    #[test]
+    fn build_orders_by_priority_then_prd_order() {
+        let prd = prd(r#"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "a", "description": "Unset", "steps": [], "passes": false},
+            {"category": "b", "description": "Low", "steps": [], "passes": false, "priority": "low"},
+            {"category": "c", "description": "Urgent", "steps": [], "passes": false, "priority": 1},
+            {"category": "d", "description": "Blocked high", "steps": [], "passes": false, "priority": "high", "depends_on": ["Low"]}
+        ]}"#);
+        let entries = build(&prd);
+        let order: Vec<&str> = entries.iter().map(|e| e.description.as_str()).collect();
+        assert_eq!(order, vec!["Urgent", "Unset", "Low", "Blocked high"]);
+    }
+
+    #[test]
+    fn build_puts_cycles_last_and_ignores_unknown_dependencies() {
+        let prd = prd(r#"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "a", "description": "A", "steps": [], "passes": false, "depends_on": ["B"]},
+            {"category": "b", "description": "B", "steps": [], "passes": false, "depends_on": ["A"]},
+            {"category": "c", "description": "C", "steps": [], "passes": false, "depends_on": ["Archived task"]}
+        ]}"#);
+        let entries = build(&prd);
+        assert_eq!(entries[0].number, 3);
+        assert_eq!(entries[0].status, TocStatus::Ready);
+        assert_eq!(entries[0].depends_on[0].label(), "Archived task?");
+        assert!(entries[1..].iter().all(|e| e.status == TocStatus::Cycle));
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+
+/// Files larger than this are skipped (generated code, fixtures, lockfiles)
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Directories never worth scanning when walking outside a git repo
+const SKIP_DIRS: &[&str] = &["target", "node_modules", "dist", "build", "vendor"];
+/// Comment leaders a marker must follow to count
+const COMMENT_LEADERS: &[&str] = &["//", "#", "/*", "*", "--", "<!--", ";"];
+
+/// Kind of marker comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TodoKind {
+    Fixme,
+    Hack,
+    Todo,
+}
+
+impl TodoKind {
+    const ALL: [TodoKind; 3] = [TodoKind::Fixme, TodoKind::Hack, TodoKind::Todo];
+
+    fn marker(self) -> &'static str {
+        match self {
+            TodoKind::Fixme => "FIXME",
+            TodoKind::Hack => "HACK",
+            TodoKind::Todo => "TODO",
+        }
+    }
+}
+
+impl fmt::Display for TodoKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.marker())
+    }
+}
+
+/// One marker comment found in the codebase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoItem {
+    pub kind: TodoKind,
+    /// Path relative to the scanned root
+    pub path: PathBuf,
+    /// 1-indexed line number
+    pub line: usize,
+    /// Comment text after the marker
+    pub text: String,
+}
+
+/// TODOs from the same directory, which usually belong to the same feature area
+#[derive(Debug)]
+pub struct TodoCluster<'a> {
+    pub name: String,
+    pub items: Vec<&'a TodoItem>,
+}
+
+/// Find TODO/FIXME/HACK comments under `root`.
+///
+/// Inside a git repository only tracked and unignored files are scanned; otherwise the tree
+/// is walked, skipping hidden and build directories.
+pub fn scan(root: &Path) -> std::io::Result<Vec<TodoItem>> {
+    let files = match list_git_files(root) {
+        Some(files) => files,
+        None => {
+            let mut files = Vec::new();
+            walk(root, root, &mut files)?;
+            files.sort();
+            files
+        }
+    };
+
+    let mut items = Vec::new();
+    for relative in files {
+        let full = root.join(&relative);
+        let Ok(meta) = std::fs::metadata(&full) else {
+            continue;
+        };
+        if !meta.is_file() || meta.len() > MAX_FILE_BYTES {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(&full) else {
+            continue;
+        };
+        // Skip binary files
+        if bytes.contains(&0) {
+            continue;
+        }
+
+        let content = String::from_utf8_lossy(&bytes);
+        for (i, line) in content.lines().enumerate() {
+            if let Some((kind, text)) = parse_line(line) {
+                items.push(TodoItem {
+                    kind,
+                    path: relative.clone(),
+                    line: i + 1,
+                    text,
+                });
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+fn list_git_files(root: &Path) -> Option<Vec<PathBuf>> {
+    if !git::is_repo(root) {
+        return None;
+    }
+    let output = std::process::Command::new("git")
+        .args(["ls-files", "--cached", "--others", "--exclude-standard"])
+        .current_dir(root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(PathBuf::from)
+            .collect(),
+    )
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            if !name.starts_with('.') && !SKIP_DIRS.contains(&name.as_ref()) {
+                walk(root, &path, files)?;
+            }
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Extract a marker comment from a line, e.g. `// TODO(sam): handle errors`
+fn parse_line(line: &str) -> Option<(TodoKind, String)> {
+    for kind in TodoKind::ALL {
+        let marker = kind.marker();
+        let mut search_from = 0;
+        while let Some(offset) = line[search_from..].find(marker) {
+            let start = search_from + offset;
+            let end = start + marker.len();
+            search_from = end;
+
+            let before = &line[..start];
+            let after = &line[end..];
+            let word_start = !before.ends_with(|c: char| c.is_alphanumeric() || c == '_');
+            let word_end = !after.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+            let in_comment = COMMENT_LEADERS.iter().any(|leader| before.contains(leader));
+            if !(word_start && word_end && in_comment) {
+                continue;
+            }
+
+            // Drop an optional "(owner)" and separator
+            let mut rest = after;
+            if rest.starts_with('(')
+                && let Some(close) = rest.find(')')
+            {
+                rest = &rest[close + 1..];
+            }
+            let text = rest
+                .trim_start_matches([':', '-', ' ', '!'])
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim();
+            return Some((kind, text.to_string()));
+        }
+    }
+    None
+}
+
+/// Group items by the directory they live in
+pub fn cluster(items: &[TodoItem]) -> Vec<TodoCluster<'_>> {
+    let mut groups: BTreeMap<String, Vec<&TodoItem>> = BTreeMap::new();
+    for item in items {
+        let dir = item
+            .path
+            .parent()
+            .map(|p| p.to_string_lossy().into_owned())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| ".".to_string());
+        groups.entry(dir).or_default().push(item);
+    }
+
+    groups
+        .into_iter()
+        .map(|(name, mut items)| {
+            items.sort_by_key(|i| (i.kind, i.path.clone(), i.line));
+            TodoCluster { name, items }
+        })
+        .collect()
+}
+
+/// Describe the clusters as a plan request, keeping file:line references
+pub fn format_request(clusters: &[TodoCluster], description: Option<&str>) -> String {
+    let total: usize = clusters.iter().map(|c| c.items.len()).sum();
+    let mut request = String::new();
+    if let Some(description) = description {
+        request.push_str(description.trim());
+        request.push_str("\n\n");
+    }
+    request.push_str(&format!(
+        "Create a PRD whose tasks resolve the {} TODO/FIXME/HACK comments below, grouped by directory. \
+Merge related comments into one task, prioritise FIXMEs, and keep each comment's `file:line` \
+reference in the steps of the task that addresses it.\n",
+        total
+    ));
+
+    for cluster in clusters {
+        request.push_str(&format!(
+            "\n## {} ({})\n",
+            cluster.name,
+            cluster.items.len()
+        ));
+        for item in &cluster.items {
+            request.push_str(&format!(
+                "- {} `{}:{}` {}\n",
+                item.kind,
+                item.path.display(),
+                item.line,
+                item.text
+            ));
+        }
+    }
+
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_line_finds_markers_in_comments() {
+        assert_eq!(
+            parse_line("    // TODO: handle errors"),
+            Some((TodoKind::Todo, "handle errors".to_string()))
+        );
+        assert_eq!(
+            parse_line("x = 1  # FIXME(sam) - off by one"),
+            Some((TodoKind::Fixme, "off by one".to_string()))
+        );
+        assert_eq!(
+            parse_line("/* HACK: temporary */"),
+            Some((TodoKind::Hack, "temporary".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_line_ignores_non_comments_and_substrings() {
+        assert_eq!(parse_line("let TODO = 1;"), None);
+        assert_eq!(parse_line("// TODOS are tracked elsewhere"), None);
+        assert_eq!(parse_line("// MY_TODO list"), None);
+        assert_eq!(parse_line("println!(\"no markers\");"), None);
+    }
+
+    #[test]
+    fn scan_walks_tree_and_skips_build_dirs() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(
+            dir.path().join("src/main.rs"),
+            "fn main() {}\n// TODO: add args\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("target/gen.rs"), "// TODO: generated\n").unwrap();
+        fs::write(dir.path().join("blob.bin"), b"\0// TODO: binary").unwrap();
+
+        let items = scan(dir.path()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, PathBuf::from("src/main.rs"));
+        assert_eq!(items[0].line, 2);
+        assert_eq!(items[0].text, "add args");
+    }
+
+    #[test]
+    fn cluster_groups_by_directory() {
+        let item = |path: &str, kind| TodoItem {
+            kind,
+            path: PathBuf::from(path),
+            line: 1,
+            text: "x".to_string(),
+        };
+        let items = vec![
+            item("src/a.rs", TodoKind::Todo),
+            item("README.md", TodoKind::Todo),
+            item("src/b.rs", TodoKind::Fixme),
+        ];
+        let clusters = cluster(&items);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].name, ".");
+        assert_eq!(clusters[1].name, "src");
+        // FIXMEs sort first within a cluster
+        assert_eq!(clusters[1].items[0].kind, TodoKind::Fixme);
+    }
+
+    #[test]
+    fn format_request_keeps_references() {
+        let items = vec![TodoItem {
+            kind: TodoKind::Todo,
+            path: PathBuf::from("src/main.rs"),
+            line: 42,
+            text: "add args".to_string(),
+        }];
+        let request = format_request(&cluster(&items), Some("CLI cleanup"));
+        assert!(request.starts_with("CLI cleanup"));
+        assert!(request.contains("resolve the 1 TODO/FIXME/HACK"));
+        assert!(request.contains("## src (1)"));
+        assert!(request.contains("- TODO `src/main.rs:42` add args"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Independent verification of a completed task (`ralph build --verify`).
+//!
+//! A second, cheaper Claude call sees only the task's acceptance criteria, the diff and the
+//! quality gate results, and must return its own pass/fail verdict before ralph archives
+//! the task to completed.json.
+
+use serde::Deserialize;
+
+use crate::claude::Usage;
+use crate::prd::Task;
+
+/// Model used when `--verify` is given without one
+pub const DEFAULT_MODEL: &str = "haiku";
+
+/// Diffs longer than this are cut off so the verifier stays cheap
+const MAX_DIFF_CHARS: usize = 60_000;
+
+/// Turns the verifier may take; it only has to read the prompt and answer
+pub const MAX_TURNS: u32 = 5;
+
+/// JSON schema for the verifier's structured output
+pub const VERIFY_OUTPUT_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "verdict": {"type": "string", "enum": ["pass", "fail"]},
+    "reason": {"type": "string"}
+  },
+  "required": ["verdict", "reason"]
+}"#;
+
+/// The verifier's decision
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Verdict {
+    pub verdict: String,
+    pub reason: String,
+}
+
+impl Verdict {
+    pub fn passed(&self) -> bool {
+        self.verdict == "pass"
+    }
+}
+
+/// What a verification call cost, alongside its verdict
+#[derive(Debug)]
+pub struct Verification {
+    pub verdict: Verdict,
+    pub cost_usd: f64,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyJsonOutput {
+    is_error: bool,
+    structured_output: Option<Verdict>,
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Parse the verifier's `--output-format json` envelope
+pub fn parse_output(stdout: &str) -> Result<Verification, String> {
+    let wrapper: VerifyJsonOutput = serde_json::from_str(stdout)
+        .map_err(|e| format!("Parse error: {}\n\nRaw output:\n{}", e, stdout))?;
+    match wrapper.structured_output {
+        Some(verdict) => Ok(Verification {
+            verdict,
+            cost_usd: wrapper.total_cost_usd.unwrap_or(0.0),
+            usage: wrapper.usage.unwrap_or_default(),
+        }),
+        None if wrapper.is_error => Err(format!("Claude returned an error:\n{}", stdout)),
+        None => Err(format!("No structured output:\n{}", stdout)),
+    }
+}
+
+/// Prompt for the verifier: acceptance criteria, diff and gate results, nothing else
+pub fn make_prompt(task_number: usize, task: &Task, diff: &str, gate_report: &str) -> String {
+    let criteria = if task.steps.is_empty() {
+        "(no steps listed - judge against the description)".to_string()
+    } else {
+        task.steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| format!("{}. {}", i + 1, step))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let diff = diff_for_prompt(diff);
+
+    format!(
+        r#"You are independently verifying work another agent reported as complete. Don't trust
+its claims: judge only from the evidence below.
+
+## Task #{task_number} ({category})
+
+{description}
+
+## Acceptance Criteria
+
+{criteria}
+
+## Quality Gates
+
+{gate_report}
+
+## Diff
+
+```diff
+{diff}
+```
+
+Answer with verdict "pass" only if the diff plausibly satisfies every acceptance criterion and
+the quality gates passed. Otherwise answer "fail" and explain in the reason what is missing or
+wrong, specifically enough for the next agent to fix it."#,
+        category = task.category,
+        description = task.description,
+    )
+}
+
+/// A diff as a checking prompt shows it: cut off past `MAX_DIFF_CHARS`, and never blank
+pub(crate) fn diff_for_prompt(diff: &str) -> String {
+    if diff.trim().is_empty() {
+        "(empty diff)".to_string()
+    } else if diff.len() > MAX_DIFF_CHARS {
+        let mut end = MAX_DIFF_CHARS;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}\n... (diff truncated)", &diff[..end])
+    } else {
+        diff.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> Task {
+        serde_json::from_str(
+            r#"{"category": "feature", "description": "Add login", "steps": ["Form", "API"], "passes": true}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn prompt_includes_criteria_diff_and_gates() {
+        let prompt = make_prompt(3, &task(), "+fn login() {}", "- PASS `cargo test`");
+        assert!(prompt.contains("## Task #3 (feature)"));
+        assert!(prompt.contains("1. Form\n2. API"));
+        assert!(prompt.contains("+fn login() {}"));
+        assert!(prompt.contains("PASS `cargo test`"));
+    }
+
+    #[test]
+    fn prompt_truncates_long_diffs() {
+        let diff = "x".repeat(MAX_DIFF_CHARS + 10);
+        let prompt = make_prompt(1, &task(), &diff, "");
+        assert!(prompt.contains("(diff truncated)"));
+        assert!(make_prompt(1, &task(), "", "").contains("(empty diff)"));
+    }
+
+    #[test]
+    fn parse_output_reads_verdict_and_cost() {
+        let json = r#"{"type":"result","is_error":false,"total_cost_usd":0.01,"structured_output":{"verdict":"fail","reason":"No tests"}}"#;
+        let verification = parse_output(json).unwrap();
+        assert!(!verification.verdict.passed());
+        assert_eq!(verification.verdict.reason, "No tests");
+        assert_eq!(verification.cost_usd, 0.01);
+
+        let error = r#"{"type":"result","is_error":true,"structured_output":null}"#;
+        assert!(parse_output(error).unwrap_err().contains("error"));
+        assert!(parse_output("not json").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Building several PRDs in one go (`ralph build --all` or `--prd a.json,b.json`): finding
+//! the PRDs in `plans/`, and recording how far each got in `.ralph/workspace.json`, so
+//! the combined summary (and the next workspace build) knows which are finished.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::prd::{self, PrdError};
+use crate::storage::{StorageError, storage};
+
+/// Where a workspace's PRDs live, relative to the project root
+pub const PLANS_DIR: &str = "plans";
+
+/// The workspace record, relative to the project root
+pub const FILE: &str = ".ralph/workspace.json";
+
+#[derive(Error, Debug)]
+pub enum WorkspaceError {
+    #[error("Failed to access the workspace record: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Failed to parse the workspace record: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+/// The PRDs in `dir`: its `.json` and `.md` files that read as PRDs, sorted, apart from
+/// the completed.json archives and ralph's own dotfiles. The files that don't read as a PRD
+/// come back second, with why, for the caller to warn about.
+pub fn discover(dir: &Path) -> (Vec<String>, Vec<PrdError>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "json" || ext == "md")
+        })
+        .filter(|p| {
+            p.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    !name.starts_with('.')
+                        && !name.starts_with("completed.")
+                        && !name.ends_with(".bak")
+                })
+        })
+        .map(|p| p.display().to_string())
+        .collect();
+    candidates.sort();
+    let mut prds = Vec::new();
+    let mut skipped = Vec::new();
+    for path in candidates {
+        match prd::read_prd(&path) {
+            Ok(_) => prds.push(path),
+            Err(e) => skipped.push(e),
+        }
+    }
+    (prds, skipped)
+}
+
+/// The path of a PRD named on the command line: as given if there's a file there,
+/// otherwise the file of that name in `plans/` under `root`
+pub fn resolve(root: &Path, name: &str) -> String {
+    if root.join(name).is_file() {
+        return name.to_string();
+    }
+    let in_plans = Path::new(PLANS_DIR).join(name);
+    if root.join(&in_plans).is_file() {
+        in_plans.display().to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Where one PRD of a workspace build stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrdState {
+    /// Not built yet
+    Pending,
+    /// Every task passes
+    Complete,
+    /// Built, but the run ended with tasks left
+    Incomplete,
+    /// The build couldn't run (an unreadable PRD, say)
+    Failed,
+}
+
+impl PrdState {
+    pub fn name(self) -> &'static str {
+        match self {
+            PrdState::Pending => "pending",
+            PrdState::Complete => "complete",
+            PrdState::Incomplete => "incomplete",
+            PrdState::Failed => "failed",
+        }
+    }
+}
+
+/// One PRD of a workspace build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrdProgress {
+    pub prd_path: String,
+    pub state: PrdState,
+    /// Tasks passing in the PRD, out of `total`
+    #[serde(default)]
+    pub passing: usize,
+    #[serde(default)]
+    pub total: usize,
+    /// The build run of this PRD, if one ran
+    #[serde(default)]
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub loops: u64,
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Why the build couldn't run, for a failed PRD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A build of several PRDs, one after another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRun {
+    pub id: String,
+    /// The PRDs, in the order they're built
+    pub prds: Vec<PrdProgress>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WorkspaceRun {
+    /// A workspace build of `prd_paths`, none of them built yet
+    pub fn new(prd_paths: &[String]) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            prds: prd_paths
+                .iter()
+                .map(|prd_path| PrdProgress {
+                    prd_path: prd_path.clone(),
+                    state: PrdState::Pending,
+                    passing: 0,
+                    total: 0,
+                    run_id: None,
+                    loops: 0,
+                    cost_usd: 0.0,
+                    error: None,
+                })
+                .collect(),
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// The record under `root`
+    pub fn file_path(root: &Path) -> PathBuf {
+        root.join(FILE)
+    }
+
+    /// Load the last workspace build's record, if one was saved
+    pub fn load(root: &Path) -> Result<Option<Self>, WorkspaceError> {
+        match storage().load(&Self::file_path(root))? {
+            Some(content) => Ok(Some(serde_json::from_str(&content)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the record under `root`
+    pub fn save(&self, root: &Path) -> Result<(), WorkspaceError> {
+        let content = serde_json::to_string_pretty(self)?;
+        storage().save(&Self::file_path(root), &content)?;
+        Ok(())
+    }
+
+    /// The entry for `prd_path`, to fill in once its build is over
+    pub fn prd_mut(&mut self, prd_path: &str) -> Option<&mut PrdProgress> {
+        self.updated_at = Utc::now();
+        self.prds.iter_mut().find(|p| p.prd_path == prd_path)
+    }
+
+    /// Loops and cost across every PRD
+    pub fn totals(&self) -> (u64, f64) {
+        self.prds.iter().fold((0, 0.0), |(loops, cost), p| {
+            (loops + p.loops, cost + p.cost_usd)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finds_and_resolves_the_plans_prds() {
+        let dir = TempDir::new().unwrap();
+        let plans = dir.path().join(PLANS_DIR);
+        std::fs::create_dir_all(&plans).unwrap();
+        let prd = r#"{"name": "Demo", "quality_gates": [], "tasks": []}"#;
+        for (name, content) in [
+            ("billing.json", prd.to_string()),
+            ("auth.json", prd.to_string()),
+            ("roadmap.md", format!("---\n{}\n---\n# Roadmap\n", prd)),
+            ("notes.md", "# Notes\n".to_string()),
+            ("completed.json", "[]".to_string()),
+            ("completed.auth.json", "[]".to_string()),
+            (".ralph-run.json", "{}".to_string()),
+            ("auth.json.v0.bak", prd.to_string()),
+            ("todo.txt", prd.to_string()),
+        ] {
+            std::fs::write(plans.join(name), content).unwrap();
+        }
+        let (prds, skipped) = discover(&plans);
+        let found: Vec<String> = prds
+            .iter()
+            .map(|p| {
+                Path::new(p)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        assert_eq!(found, ["auth.json", "billing.json", "roadmap.md"]);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].to_string().contains("notes.md"));
+
+        assert_eq!(resolve(dir.path(), "auth.json"), "plans/auth.json");
+        assert_eq!(resolve(dir.path(), "plans/auth.json"), "plans/auth.json");
+        assert_eq!(resolve(dir.path(), "missing.json"), "missing.json");
+    }
+
+    #[test]
+    fn records_each_prds_progress() {
+        let dir = TempDir::new().unwrap();
+        let mut run = WorkspaceRun::new(&["plans/a.json".to_string(), "plans/b.json".to_string()]);
+        let a = run.prd_mut("plans/a.json").unwrap();
+        a.state = PrdState::Complete;
+        a.loops = 3;
+        a.cost_usd = 1.5;
+        run.save(dir.path()).unwrap();
+
+        let loaded = WorkspaceRun::load(dir.path()).unwrap().unwrap();
+        assert_eq!(loaded.id, run.id);
+        assert_eq!(loaded.prds[0].state, PrdState::Complete);
+        assert_eq!(loaded.prds[1].state, PrdState::Pending);
+        assert_eq!(loaded.totals(), (3, 1.5));
+        assert!(
+            WorkspaceRun::load(&dir.path().join("elsewhere"))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -1,18 +1,89 @@
 use ratatui::{
     Frame,
-    layout::{Constraint, Layout, Margin, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Layout, Margin, Position, Rect},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
     widgets::{
-        Block, BorderType, Borders, Gauge, Padding, Paragraph, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Wrap,
+        Block, BorderType, Borders, Cell, Gauge, Padding, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, Tabs, Wrap,
     },
 };
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+use crate::help;
+use crate::markdown;
+use crate::theme::Theme;
+use crate::toc::TableOfContents;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ralph_core::claude::{self, Usage};
+use ralph_core::events::{Event, EventLog};
+use ralph_core::gates::GateResult;
+use ralph_core::redact::Redactor;
+use ralph_core::stats::TaskStats;
+use ralph_core::toc::TocStatus;
 
 /// Braille spinner frames for animation
 const SPINNER_FRAMES: [char; 8] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧'];
 
+/// What fills the screen below the progress panel, picked with `1`-`4`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tab {
+    /// Each loop's log, its diff, or the task list
+    #[default]
+    Logs,
+    /// The PRD's tasks in columns by status
+    Tasks,
+    /// Spend and tokens, loop by loop
+    Costs,
+    /// The run itself: ids, branch, timing, gates
+    Session,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 4] = [Tab::Logs, Tab::Tasks, Tab::Costs, Tab::Session];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Logs => "Logs",
+            Tab::Tasks => "Tasks",
+            Tab::Costs => "Costs",
+            Tab::Session => "Session",
+        }
+    }
+
+    /// The tab a number key picks
+    pub fn from_key(c: char) -> Option<Tab> {
+        let index = c.to_digit(10)?.checked_sub(1)?;
+        Tab::ALL.get(index as usize).copied()
+    }
+}
+
+/// Step checklist of the task the last iteration worked on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskChecklist {
+    pub task_number: i32,
+    /// Step text and whether it's done
+    pub steps: Vec<(String, bool)>,
+}
+
+impl TaskChecklist {
+    /// Build a checklist from a task's steps and its done 1-indexed step numbers
+    pub fn new(task_number: i32, steps: &[String], done: &[u32]) -> Self {
+        Self {
+            task_number,
+            steps: steps
+                .iter()
+                .enumerate()
+                .map(|(i, step)| (step.clone(), done.contains(&(i as u32 + 1))))
+                .collect(),
+        }
+    }
+
+    pub fn done_count(&self) -> usize {
+        self.steps.iter().filter(|(_, done)| *done).count()
+    }
+}
+
 pub struct App {
     pub prd_name: String,
     pub remaining_tasks: usize,
@@ -22,6 +93,8 @@ pub struct App {
     pub status_message: String,
     // Store all iteration logs
     pub iteration_logs: Vec<String>,
+    /// Loop each of `iteration_logs` was written in, 0 before the first loop
+    pub log_loops: Vec<u64>,
     pub current_log_index: usize,
     pub log_scroll_offset: usize,
     pub log_scroll_state: ScrollbarState,
@@ -29,6 +102,152 @@ pub struct App {
     pub spinner_frame: u8,
     /// Start time of the current loop iteration (for elapsed display)
     pub loop_start_time: Option<Instant>,
+    /// Cumulative cost of all Claude invocations this run (USD)
+    pub total_cost_usd: f64,
+    /// Cost of the most recent Claude invocation (USD)
+    pub last_cost_usd: Option<f64>,
+    /// Model that did most of the most recent Claude invocation's work, if reported
+    pub last_model: Option<String>,
+    /// Cumulative token usage this run
+    pub total_usage: Usage,
+    /// Spend limit for the run (USD), if any
+    pub max_cost_usd: Option<f64>,
+    /// Read-only inspection mode (`ralph view`) - no loop is running
+    pub read_only: bool,
+    /// Results of the most recent native quality gate run
+    pub gate_results: Vec<GateResult>,
+    /// Loops that reported a completed task but produced no git commit
+    pub suspicious_loops: Vec<u64>,
+    /// Git branch the run commits to (--branch)
+    pub branch: Option<String>,
+    /// Human-readable name of the run (--label)
+    pub label: Option<String>,
+    /// Label being typed after pressing `l`; None when not renaming
+    pub label_input: Option<String>,
+    /// Path being typed after pressing `e` to save the log shown; None when not exporting
+    pub export_input: Option<String>,
+    /// Most recent loop whose stats were recorded, so it can be rated
+    pub last_finished_loop: Option<u64>,
+    /// Rating being given after pressing `s`, waiting for a score and an optional note
+    pub rating_input: Option<RatingInput>,
+    /// Ratings given since the build loop last saved them
+    pub finished_ratings: Vec<RatingInput>,
+    /// Guidance being typed while escalation has paused the run; None when not paused
+    pub guidance_input: Option<String>,
+    /// `--review` waiting on the user's verdict on a completed task
+    pub review_input: Option<ReviewInput>,
+    /// Step checklist reported by the most recent iteration
+    pub current_task: Option<TaskChecklist>,
+    /// Task list screen shown in place of the log (key `t`)
+    pub toc: TableOfContents,
+    /// No screen (`--no-tui` or stdout isn't a terminal): logs and status changes are
+    /// printed as timestamped lines instead of drawn
+    pub headless: bool,
+    /// Where `--output jsonl` events go; logs are also sent there as `stream_chunk`s
+    pub events: Option<EventLog>,
+    /// Scrubs secrets from logs before they're shown, stored or sent anywhere
+    pub redactor: Redactor,
+    /// `--parallel` workers of the current loop, one pane each; empty in a serial loop
+    pub workers: Vec<WorkerPane>,
+    /// When a `--start-at` run starts; the status line counts down to it until then
+    pub starts_at: Option<Instant>,
+    /// How the loops on the last reported task went, across every run
+    pub task_history: Option<TaskStats>,
+    /// What each committing loop changed, oldest first
+    pub loop_diffs: Vec<LoopDiff>,
+    pub current_diff_index: usize,
+    /// The log panel shows `loop_diffs` instead of the logs (toggled with `d`)
+    pub show_diff: bool,
+    /// Where the last draw put the things the mouse can click
+    pub mouse_areas: MouseAreas,
+    /// Colours from `[theme]` in `.ralph.toml`
+    pub theme: Theme,
+    /// The help overlay listing every key is open (key `?`)
+    pub show_help: bool,
+    /// Vim-style keys work alongside the arrows (`[tui] vim_keys` in `.ralph.toml`)
+    pub vim_keys: bool,
+    /// Model that repairs output that can't be parsed; None when `[normalize]` disables it
+    pub normalize_model: Option<String>,
+    /// The tab on screen
+    pub tab: Tab,
+    /// What each loop has cost so far, oldest first
+    pub loop_costs: Vec<LoopCost>,
+    /// Id of the run in `.ralph-run.json`, when there is one
+    pub run_id: Option<String>,
+    pub run_started: Instant,
+}
+
+/// Spend of one loop, including any verify, review or test-writing sessions it started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopCost {
+    pub loop_number: u64,
+    pub cost_usd: f64,
+    pub output_tokens: u64,
+}
+
+/// Lines the mouse wheel scrolls per notch
+const WHEEL_LINES: usize = 3;
+
+/// Screen areas from the last draw, for mapping mouse events back to what was drawn there
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MouseAreas {
+    /// Rows of the task list, below its header; empty while the list is closed
+    pub toc_rows: Rect,
+    /// Each footer shortcut and the key it stands for; empty while the footer asks for input
+    pub footer_buttons: Vec<(Rect, KeyEvent)>,
+}
+
+/// The changes a loop committed: `git diff --stat` followed by the full diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopDiff {
+    pub loop_number: u64,
+    pub diff: String,
+}
+
+/// A 1-5 rating of a finished loop, with the note typed after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RatingInput {
+    pub loop_number: u64,
+    /// 0 until a score has been picked
+    pub rating: u8,
+    pub note: String,
+}
+
+/// One `--parallel` worker: the task it has and how it's going
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerPane {
+    pub task_number: usize,
+    pub description: String,
+    pub status: String,
+    pub started: Instant,
+    /// How long the worker took, once it's done
+    pub finished: Option<Duration>,
+}
+
+impl WorkerPane {
+    pub fn new(task_number: usize, description: &str) -> Self {
+        Self {
+            task_number,
+            description: description.to_string(),
+            status: "working".to_string(),
+            started: Instant::now(),
+            finished: None,
+        }
+    }
+
+    /// Mark the worker done with `status`, stopping its clock
+    pub fn finish(&mut self, status: &str) {
+        self.status = status.to_string();
+        self.finished = Some(self.started.elapsed());
+    }
+}
+
+/// A completed task's changes waiting for approval under `--review`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewInput {
+    pub task_number: i32,
+    /// What should change, being typed after `r`; None while the user decides
+    pub reason: Option<String>,
 }
 
 impl App {
@@ -41,14 +260,76 @@ impl App {
             should_quit: false,
             status_message: String::from("Initialising..."),
             iteration_logs: Vec::new(),
+            log_loops: Vec::new(),
             current_log_index: 0,
             log_scroll_offset: 0,
             log_scroll_state: ScrollbarState::default(),
             spinner_frame: 0,
             loop_start_time: None,
+            total_cost_usd: 0.0,
+            last_cost_usd: None,
+            last_model: None,
+            total_usage: Usage::default(),
+            max_cost_usd: None,
+            read_only: false,
+            gate_results: Vec::new(),
+            suspicious_loops: Vec::new(),
+            branch: None,
+            label: None,
+            label_input: None,
+            export_input: None,
+            last_finished_loop: None,
+            rating_input: None,
+            finished_ratings: Vec::new(),
+            guidance_input: None,
+            review_input: None,
+            current_task: None,
+            toc: TableOfContents::default(),
+            headless: false,
+            events: None,
+            redactor: Redactor::default(),
+            workers: Vec::new(),
+            starts_at: None,
+            task_history: None,
+            loop_diffs: Vec::new(),
+            current_diff_index: 0,
+            show_diff: false,
+            mouse_areas: MouseAreas::default(),
+            theme: Theme::default(),
+            show_help: false,
+            vim_keys: false,
+            normalize_model: Some(claude::NORMALIZE_MODEL.to_string()),
+            tab: Tab::default(),
+            loop_costs: Vec::new(),
+            run_id: None,
+            run_started: Instant::now(),
+        }
+    }
+
+    /// Record the cost and token usage of a finished Claude invocation
+    pub fn record_usage(&mut self, cost_usd: f64, usage: &Usage) {
+        self.total_cost_usd += cost_usd;
+        self.last_cost_usd = Some(cost_usd);
+        self.total_usage.accumulate(usage);
+        match self.loop_costs.last_mut() {
+            Some(last) if last.loop_number == self.loop_count => {
+                last.cost_usd += cost_usd;
+                last.output_tokens += usage.output_tokens;
+            }
+            _ => self.loop_costs.push(LoopCost {
+                loop_number: self.loop_count,
+                cost_usd,
+                output_tokens: usage.output_tokens,
+            }),
         }
     }
 
+    /// Whether cumulative spend has reached the configured budget
+    pub fn budget_exceeded(&self) -> bool {
+        self.max_cost_usd
+            .is_some_and(|max| self.total_cost_usd >= max)
+    }
+
     /// Advance the spinner to the next frame (wraps at 8)
     pub fn advance_spinner(&mut self) {
         self.spinner_frame = (self.spinner_frame + 1) % 8;
@@ -81,8 +362,14 @@ impl App {
         }
     }
 
-    /// Get the current log being viewed, or empty string if none
+    /// Get the current log (or diff, in the diff view) being viewed, or empty string if none
     fn current_log(&self) -> &str {
+        if self.show_diff {
+            return self
+                .loop_diffs
+                .get(self.current_diff_index)
+                .map_or("", |d| d.diff.as_str());
+        }
         self.iteration_logs
             .get(self.current_log_index)
             .map(|s| s.as_str())
@@ -90,20 +377,329 @@ impl App {
     }
 
     pub fn draw(&mut self, frame: &mut Frame) {
-        let [top_area, log_area, footer_area] = Layout::vertical([
-            Constraint::Length(7),
+        let workers_height = if self.workers.is_empty() { 0 } else { 4 };
+        let [top_area, workers_area, tabs_area, main_area, footer_area] = Layout::vertical([
+            Constraint::Length(8),
+            Constraint::Length(workers_height),
+            Constraint::Length(1),
             Constraint::Fill(1),
             Constraint::Length(1),
         ])
         .areas(frame.area());
 
         self.render_top_panel(frame, top_area);
-        self.render_log_panel(frame, log_area);
+        if !self.workers.is_empty() {
+            self.render_worker_panes(frame, workers_area);
+        }
+        self.render_tabs(frame, tabs_area);
+        self.mouse_areas.toc_rows = Rect::default();
+        match self.tab {
+            Tab::Logs if self.toc.visible => self.render_toc(frame, main_area),
+            Tab::Logs => self.render_log_panel(frame, main_area),
+            Tab::Tasks => self.render_task_board(frame, main_area),
+            Tab::Costs => self.render_costs(frame, main_area),
+            Tab::Session => self.render_session(frame, main_area),
+        }
         self.render_footer(frame, footer_area);
+        if self.show_help {
+            help::render(frame, &self.theme, self.mode(), &self.help_sections());
+        }
+    }
+
+    /// "Running", "Quitting" or "Viewing", as the footer and help overlay show it
+    fn mode(&self) -> &'static str {
+        if self.read_only {
+            "Viewing"
+        } else if self.should_quit {
+            "Quitting"
+        } else {
+            "Running"
+        }
+    }
+
+    /// Every key, for the help overlay
+    fn help_sections(&self) -> Vec<help::Section> {
+        let mut sections = Vec::new();
+        if self.read_only {
+            sections.push((
+                "Pages",
+                vec![
+                    ("←/→", "previous/next page"),
+                    ("↑/↓", "scroll"),
+                    ("PgUp/PgDn", "scroll faster"),
+                    ("Home/End", "top/bottom"),
+                    ("q/Esc", "quit"),
+                ],
+            ));
+        } else {
+            sections.push((
+                "Run",
+                vec![
+                    ("q", "stop after this loop"),
+                    ("r", "resume (cancel the stop)"),
+                    ("Ctrl+C", "kill Claude and end the run"),
+                    ("Ctrl+X", "kill Claude and abort only this loop"),
+                    (if self.vim_keys { "L" } else { "l" }, "rename the run"),
+                    ("s, 1-5", "rate the last finished loop"),
+                ],
+            ));
+            sections.push((
+                "Logs",
+                vec![
+                    ("←/→", "previous/next loop"),
+                    ("↑/↓", "scroll"),
+                    ("PgUp/PgDn", "scroll faster"),
+                    ("Home/End", "top/bottom"),
+                    ("d", "switch between logs and each loop's diff"),
+                    ("e", "save the log shown to a Markdown file"),
+                    ("mouse wheel", "scroll"),
+                ],
+            ));
+        }
+        if self.vim_keys {
+            sections.push((
+                "Vim keys",
+                vec![
+                    ("h/l", "previous/next loop or page"),
+                    ("j/k", "scroll"),
+                    ("Ctrl+d/Ctrl+u", "scroll faster"),
+                    ("g/G", "top/bottom"),
+                ],
+            ));
+        }
+        sections.push((
+            "Tabs",
+            vec![
+                ("1", "logs"),
+                ("2", "task board"),
+                ("3", "costs"),
+                ("4", "session"),
+            ],
+        ));
+        sections.push((
+            "Task list",
+            vec![
+                ("t", "open/close"),
+                ("/", "search"),
+                ("↑/↓", "scroll"),
+                ("Esc", "clear the search, then close"),
+            ],
+        ));
+        sections.push(("Help", vec![("?", "show this help")]));
+        sections
+    }
+
+    fn render_tabs(&self, frame: &mut Frame, area: Rect) {
+        let titles = Tab::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| format!("{} {}", i + 1, tab.title()));
+        let selected = Tab::ALL.iter().position(|&t| t == self.tab);
+        let tabs = Tabs::new(titles)
+            .select(selected)
+            .style(Style::default().fg(self.theme.muted))
+            .highlight_style(
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            )
+            .divider(Span::styled("|", Style::default().fg(self.theme.dim)));
+        frame.render_widget(tabs, area);
+    }
+
+    /// The PRD's tasks in Ready, Waiting and Done columns, in expected run order
+    fn render_task_board(&self, frame: &mut Frame, area: Rect) {
+        let columns = [
+            ("Ready", self.theme.accent, vec![TocStatus::Ready]),
+            (
+                "Waiting",
+                self.theme.warning,
+                vec![TocStatus::Waiting, TocStatus::Cycle],
+            ),
+            ("Done", self.theme.success, vec![TocStatus::Done]),
+        ];
+        let areas = Layout::horizontal([Constraint::Fill(1); 3]).split(area);
+        for ((title, color, statuses), &area) in columns.iter().zip(areas.iter()) {
+            let lines: Vec<Line> = self
+                .toc
+                .entries
+                .iter()
+                .filter(|entry| statuses.contains(&entry.status))
+                .map(|entry| {
+                    let mut spans = vec![
+                        Span::styled(format!("#{} ", entry.number), Style::default().fg(*color)),
+                        Span::styled(
+                            entry.description.as_str(),
+                            Style::default().fg(self.theme.text),
+                        ),
+                    ];
+                    if entry.status == TocStatus::Cycle {
+                        spans.push(Span::styled(
+                            " (cycle)",
+                            Style::default().fg(self.theme.error),
+                        ));
+                    }
+                    Line::from(spans)
+                })
+                .collect();
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(*color))
+                .title(format!(" {} [{}] ", title, lines.len()))
+                .title_style(Style::default().fg(*color).add_modifier(Modifier::BOLD))
+                .padding(Padding::horizontal(1));
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+        }
+    }
+
+    /// Spend so far, then each loop's cost with a bar against the priciest loop
+    fn render_costs(&self, frame: &mut Frame, area: Rect) {
+        let label = Style::default().fg(self.theme.text);
+        let value = Style::default().fg(self.theme.accent);
+        let mut lines = vec![Line::from(vec![
+            Span::styled("Total:     ", label),
+            Span::styled(format!("${:.2}", self.total_cost_usd), value),
+        ])];
+        if let Some(max) = self.max_cost_usd {
+            let left = (max - self.total_cost_usd).max(0.0);
+            lines.push(Line::from(vec![
+                Span::styled("Budget:    ", label),
+                Span::styled(format!("${:.2} of ${:.2} left", left, max), value),
+            ]));
+        }
+        if !self.loop_costs.is_empty() {
+            let mean = self.total_cost_usd / self.loop_costs.len() as f64;
+            lines.push(Line::from(vec![
+                Span::styled("Per loop:  ", label),
+                Span::styled(format!("${:.2} on average", mean), value),
+            ]));
+        }
+        let cache =
+            self.total_usage.cache_read_input_tokens + self.total_usage.cache_creation_input_tokens;
+        lines.push(Line::from(vec![
+            Span::styled("Tokens:    ", label),
+            Span::styled(
+                format!(
+                    "{} in / {} out / {} cache",
+                    format_tokens(self.total_usage.input_tokens),
+                    format_tokens(self.total_usage.output_tokens),
+                    format_tokens(cache)
+                ),
+                value,
+            ),
+        ]));
+        lines.push(Line::default());
+
+        if self.loop_costs.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No loop has reported its cost yet",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+        let max = self
+            .loop_costs
+            .iter()
+            .map(|c| c.cost_usd)
+            .fold(0.0, f64::max);
+        // Most recent first
+        for cost in self.loop_costs.iter().rev() {
+            let width = if max > 0.0 {
+                (cost.cost_usd / max * 30.0).round() as usize
+            } else {
+                0
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("#{:<5}", cost.loop_number), label),
+                Span::styled(format!("${:>7.2}  ", cost.cost_usd), value),
+                Span::styled("█".repeat(width), Style::default().fg(self.theme.success)),
+                Span::styled(
+                    format!("  {} out", format_tokens(cost.output_tokens)),
+                    Style::default().fg(self.theme.dim),
+                ),
+            ]));
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border))
+            .title(" Costs ")
+            .title_style(
+                Style::default()
+                    .fg(self.theme.border)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .padding(Padding::horizontal(1));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    /// What this run is: its names, where it commits, how long it's been going
+    fn render_session(&self, frame: &mut Frame, area: Rect) {
+        let label = Style::default().fg(self.theme.text);
+        let value = Style::default().fg(self.theme.accent);
+        let none = || Span::styled("-", Style::default().fg(self.theme.dim));
+        let row = |name: &'static str, text: Option<String>| {
+            Line::from(vec![
+                Span::styled(format!("{:<12}", name), label),
+                text.map_or_else(none, |text| Span::styled(text, value)),
+            ])
+        };
+        let elapsed = self.run_started.elapsed().as_secs();
+        let gates = if self.gate_results.is_empty() {
+            None
+        } else {
+            let passed = self.gate_results.iter().filter(|r| r.passed()).count();
+            Some(format!("{}/{} passed", passed, self.gate_results.len()))
+        };
+        let suspicious = (!self.suspicious_loops.is_empty()).then(|| {
+            self.suspicious_loops
+                .iter()
+                .map(|l| format!("#{}", l))
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+        let lines = vec![
+            row("PRD", Some(self.prd_name.clone())),
+            row("Run", self.run_id.clone()),
+            row("Label", self.label.clone()),
+            row("Branch", self.branch.clone()),
+            row("Mode", Some(self.mode().to_string())),
+            row("Running for", Some(format_countdown(elapsed))),
+            row("Loops", Some(self.loop_count.to_string())),
+            row(
+                "Tasks",
+                Some(format!(
+                    "{} done, {} left",
+                    self.completed_tasks, self.remaining_tasks
+                )),
+            ),
+            row("Model", self.last_model.clone()),
+            row("Gates", gates),
+            row("No commit", suspicious),
+        ];
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border))
+            .title(" Session ")
+            .title_style(
+                Style::default()
+                    .fg(self.theme.border)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .padding(Padding::horizontal(1));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
     }
 
     fn render_top_panel(&self, frame: &mut Frame, area: Rect) {
-        let border_color = Color::Green;
+        let [progress_area, cost_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(32)]).areas(area);
+
+        self.render_progress_panel(frame, progress_area);
+        self.render_cost_panel(frame, cost_area);
+    }
+
+    fn render_progress_panel(&self, frame: &mut Frame, area: Rect) {
+        let border_color = self.theme.success;
         let border_type = BorderType::Plain;
 
         let total_tasks = self.completed_tasks + self.remaining_tasks;
@@ -125,7 +721,7 @@ impl App {
             .title(" Ralph's 'Special' Agent Loop ")
             .title_style(
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(self.theme.success)
                     .add_modifier(Modifier::BOLD),
             );
 
@@ -137,8 +733,17 @@ impl App {
             vertical: 1,
         });
 
-        // Split inner area: PRD line, Gauge, Loop line, Status line
-        let [prd_area, gauge_area, loop_area, status_area] = Layout::vertical([
+        // Split inner area: PRD line, Gauge, Loop line, Gates line, Steps line, Status line
+        let [
+            prd_area,
+            gauge_area,
+            loop_area,
+            gates_area,
+            steps_area,
+            status_area,
+        ] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
             Constraint::Length(1),
             Constraint::Length(1),
             Constraint::Length(1),
@@ -147,56 +752,280 @@ impl App {
         .areas(inner_area);
 
         // PRD line
-        let prd_line = Line::from(vec![
+        let mut prd_spans = vec![
             Span::styled("PRD: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(&self.prd_name, Style::default().fg(Color::White)),
-        ]);
+            Span::styled(&self.prd_name, Style::default().fg(self.theme.text)),
+        ];
+        if let Some(input) = &self.label_input {
+            prd_spans.push(Span::styled(
+                "  Label: ",
+                Style::default().fg(self.theme.warning),
+            ));
+            prd_spans.push(Span::styled(input, Style::default().fg(self.theme.text)));
+            prd_spans.push(Span::styled("█", Style::default().fg(self.theme.muted)));
+            prd_spans.push(Span::styled(
+                "  (enter=save, esc=cancel)",
+                Style::default().fg(self.theme.dim),
+            ));
+        } else if let Some(label) = &self.label {
+            prd_spans.push(Span::styled(
+                format!("  “{}”", label),
+                Style::default().fg(self.theme.accent),
+            ));
+        }
+        if let Some(branch) = &self.branch {
+            prd_spans.push(Span::styled(
+                format!("  ⎇ {}", branch),
+                Style::default().fg(self.theme.highlight),
+            ));
+        }
+        let prd_line = Line::from(prd_spans);
         frame.render_widget(Paragraph::new(prd_line), prd_area);
 
         // Progress Gauge
         let gauge = Gauge::default()
-            .gauge_style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+            .gauge_style(Style::default().fg(self.theme.success).bg(self.theme.bar))
             .ratio(progress_ratio)
             .label(Span::styled(
                 gauge_label,
                 Style::default()
-                    .fg(Color::White)
+                    .fg(self.theme.text)
                     .add_modifier(Modifier::BOLD),
             ));
         frame.render_widget(gauge, gauge_area);
 
-        // Loop line with elapsed time
-        let loop_line = Line::from(vec![
-            Span::styled("Loop: ", Style::default().fg(Color::White)),
-            Span::styled(loop_str, Style::default().fg(Color::Cyan)),
-            Span::styled(
-                format!(" ({})", self.elapsed_display()),
-                Style::default().fg(Color::DarkGray),
-            ),
-        ]);
+        // Loop line with elapsed time (no loop runs in read-only mode)
+        let loop_line = if self.read_only {
+            Line::from(vec![
+                Span::styled("Loop: ", Style::default().fg(self.theme.text)),
+                Span::styled("- (read-only)", Style::default().fg(self.theme.dim)),
+            ])
+        } else {
+            let mut spans = vec![
+                Span::styled("Loop: ", Style::default().fg(self.theme.text)),
+                Span::styled(loop_str, Style::default().fg(self.theme.accent)),
+                Span::styled(
+                    format!(" ({})", self.elapsed_display()),
+                    Style::default().fg(self.theme.dim),
+                ),
+            ];
+            if !self.suspicious_loops.is_empty() {
+                let loops: Vec<String> = self
+                    .suspicious_loops
+                    .iter()
+                    .map(|l| format!("#{}", l))
+                    .collect();
+                spans.push(Span::styled(
+                    format!("  ⚠ no commit: {}", loops.join(", ")),
+                    Style::default().fg(self.theme.warning),
+                ));
+            }
+            Line::from(spans)
+        };
         frame.render_widget(Paragraph::new(loop_line), loop_area);
 
+        // Gates line: pass/fail of the last native gate run
+        let mut gate_spans = vec![Span::styled(
+            "Gates: ",
+            Style::default().fg(self.theme.text),
+        )];
+        if self.gate_results.is_empty() {
+            gate_spans.push(Span::styled(
+                "not run yet",
+                Style::default().fg(self.theme.dim),
+            ));
+        }
+        for result in &self.gate_results {
+            let (mark, color) = if result.passed() {
+                ("✓", self.theme.success)
+            } else {
+                ("✗", self.theme.error)
+            };
+            gate_spans.push(Span::styled(
+                format!("{} {}  ", mark, result.command),
+                Style::default().fg(color),
+            ));
+        }
+        frame.render_widget(Paragraph::new(Line::from(gate_spans)), gates_area);
+
+        // Steps line: checklist of the task the last iteration worked on
+        let mut step_spans = vec![Span::styled(
+            "Steps: ",
+            Style::default().fg(self.theme.text),
+        )];
+        match &self.current_task {
+            None => step_spans.push(Span::styled(
+                "no task reported yet",
+                Style::default().fg(self.theme.dim),
+            )),
+            Some(task) => {
+                step_spans.push(Span::styled(
+                    format!(
+                        "#{} {}/{}  ",
+                        task.task_number,
+                        task.done_count(),
+                        task.steps.len()
+                    ),
+                    Style::default().fg(self.theme.accent),
+                ));
+                if let Some(history) = &self.task_history {
+                    let color = if history.failed > 0 || history.retries() > 1 {
+                        self.theme.warning
+                    } else {
+                        self.theme.dim
+                    };
+                    step_spans.push(Span::styled(
+                        format!("({})  ", format_task_history(history)),
+                        Style::default().fg(color),
+                    ));
+                }
+                for (step, done) in &task.steps {
+                    let (mark, color) = if *done {
+                        ("✓", self.theme.success)
+                    } else {
+                        ("·", self.theme.dim)
+                    };
+                    step_spans.push(Span::styled(
+                        format!("{} {}  ", mark, step),
+                        Style::default().fg(color),
+                    ));
+                }
+            }
+        }
+        frame.render_widget(Paragraph::new(Line::from(step_spans)), steps_area);
+
         // Status line with spinner
-        let status_line = Line::from(vec![
+        let mut status_spans = vec![
             Span::styled(
                 format!("{} ", self.spinner_char()),
-                Style::default().fg(Color::Cyan),
+                Style::default().fg(self.theme.accent),
             ),
-            Span::styled(&self.status_message, Style::default().fg(Color::Gray)),
-        ]);
+            Span::styled(&self.status_message, Style::default().fg(self.theme.muted)),
+        ];
+        if let Some(start) = self.starts_at {
+            let left = start.saturating_duration_since(Instant::now());
+            status_spans.push(Span::styled(
+                format!("  starts in {}", format_countdown(left.as_secs())),
+                Style::default().fg(self.theme.warning),
+            ));
+        }
+        let status_line = Line::from(status_spans);
         frame.render_widget(Paragraph::new(status_line), status_area);
     }
 
+    fn render_worker_panes(&self, frame: &mut Frame, area: Rect) {
+        let areas = Layout::horizontal(vec![Constraint::Fill(1); self.workers.len()]).split(area);
+        for (i, (worker, &area)) in self.workers.iter().zip(areas.iter()).enumerate() {
+            let (mark, color) = match worker.finished {
+                None => (self.spinner_char().to_string(), self.theme.accent),
+                Some(_) if worker.status == "completed" => ("✓".to_string(), self.theme.success),
+                Some(_) => ("✗".to_string(), self.theme.warning),
+            };
+            let secs = worker
+                .finished
+                .unwrap_or_else(|| worker.started.elapsed())
+                .as_secs();
+            let elapsed = if secs >= 60 {
+                format!("{}m {}s", secs / 60, secs % 60)
+            } else {
+                format!("{}s", secs)
+            };
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(color))
+                .title(format!(" Worker {}: task #{} ", i + 1, worker.task_number));
+            let lines = vec![
+                Line::from(Span::styled(
+                    &worker.description,
+                    Style::default().fg(self.theme.text),
+                )),
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} {}", mark, worker.status),
+                        Style::default().fg(color),
+                    ),
+                    Span::styled(
+                        format!("  {}", elapsed),
+                        Style::default().fg(self.theme.dim),
+                    ),
+                ]),
+            ];
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+        }
+    }
+
+    fn render_cost_panel(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Plain)
+            .border_style(Style::default().fg(self.theme.success))
+            .title(" Cost ")
+            .title_style(
+                Style::default()
+                    .fg(self.theme.success)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .padding(Padding::new(1, 1, 1, 0));
+
+        let last = self
+            .last_cost_usd
+            .map_or_else(|| "-".to_string(), |c| format!("${:.2}", c));
+        let cache =
+            self.total_usage.cache_read_input_tokens + self.total_usage.cache_creation_input_tokens;
+
+        let budget = self
+            .max_cost_usd
+            .map_or_else(String::new, |max| format!(" / ${:.2}", max));
+
+        let label = Style::default().fg(self.theme.text);
+        let value = Style::default().fg(self.theme.accent);
+        let total_style = if self.budget_exceeded() {
+            Style::default()
+                .fg(self.theme.error)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            value
+        };
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("Total: ", label),
+                Span::styled(format!("${:.2}", self.total_cost_usd), total_style),
+                Span::styled(budget, Style::default().fg(self.theme.dim)),
+            ]),
+            Line::from(vec![
+                Span::styled("Last:  ", label),
+                Span::styled(last, value),
+            ]),
+            Line::from(vec![
+                Span::styled("Tokens: ", label),
+                Span::styled(
+                    format!(
+                        "{} in / {} out",
+                        format_tokens(self.total_usage.input_tokens),
+                        format_tokens(self.total_usage.output_tokens)
+                    ),
+                    value,
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("Cache:  ", label),
+                Span::styled(format_tokens(cache), Style::default().fg(self.theme.dim)),
+            ]),
+        ];
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
     fn render_log_panel(&mut self, frame: &mut Frame, area: Rect) {
-        let border_color = Color::Blue;
+        let border_color = self.theme.border;
         let border_type = BorderType::Double;
 
-        let current = self.current_log();
-        // Compute content height from source to avoid borrow conflicts
-        let content_height = if current.is_empty() {
-            1
-        } else {
-            current.lines().count()
+        let markdown = (!self.show_diff).then(|| self.render_markdown_output());
+        let content_height = match &markdown {
+            Some(lines) => lines.len(),
+            // Compute content height from source to avoid borrow conflicts
+            None => self.current_log().lines().count().max(1),
         };
         let visible_height = area.height.saturating_sub(2) as usize; // Account for borders
 
@@ -206,13 +1035,32 @@ impl App {
             .viewport_content_length(visible_height)
             .position(self.log_scroll_offset);
 
-        let styled_lines = self.parse_markdown_output();
+        let styled_lines = match markdown {
+            Some(lines) => lines,
+            None => self.parse_diff_output(),
+        };
 
-        let log_title = if self.iteration_logs.is_empty() {
-            " Iteration Log (waiting...) ".to_string()
+        let log_label = if self.read_only {
+            "View"
+        } else {
+            "Iteration Log"
+        };
+        let log_title = if self.show_diff {
+            match self.loop_diffs.get(self.current_diff_index) {
+                Some(diff) => format!(
+                    " Diff of loop #{} [{}/{}] ",
+                    diff.loop_number,
+                    self.current_diff_index + 1,
+                    self.loop_diffs.len()
+                ),
+                None => " Diff (no loop has committed yet) ".to_string(),
+            }
+        } else if self.iteration_logs.is_empty() {
+            format!(" {} (waiting...) ", log_label)
         } else {
             format!(
-                " Iteration Log [{}/{}] ",
+                " {} [{}/{}] ",
+                log_label,
                 self.current_log_index + 1,
                 self.iteration_logs.len()
             )
@@ -225,7 +1073,7 @@ impl App {
             .title(log_title)
             .title_style(
                 Style::default()
-                    .fg(Color::Blue)
+                    .fg(self.theme.border)
                     .add_modifier(Modifier::BOLD),
             )
             .padding(Padding::horizontal(1));
@@ -254,142 +1102,329 @@ impl App {
         }
     }
 
-    fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        let mode = if self.should_quit {
-            "Quitting"
+    fn render_toc(&mut self, frame: &mut Frame, area: Rect) {
+        let entries = self.toc.filtered();
+        let title = if self.toc.query.is_empty() {
+            format!(" Tasks - estimated order [{}] ", entries.len())
         } else {
-            "Running"
+            format!(
+                " Tasks - estimated order [{}/{} matching \"{}\"] ",
+                entries.len(),
+                self.toc.entries.len(),
+                self.toc.query
+            )
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.border))
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(self.theme.border)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .padding(Padding::horizontal(1));
+
+        let [table_area, search_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(block.inner(area));
+        frame.render_widget(block, area);
+        let [_, rows_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(table_area);
+        self.mouse_areas.toc_rows = rows_area;
+
+        let header = Row::new([
+            "Order",
+            "#",
+            "Status",
+            "Priority",
+            "Category",
+            "Estimate",
+            "Depends on",
+            "Task",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = entries
+            .iter()
+            .enumerate()
+            .skip(self.toc.scroll)
+            .map(|(i, entry)| {
+                let status_color = match entry.status {
+                    TocStatus::Done => self.theme.success,
+                    TocStatus::Ready => self.theme.accent,
+                    TocStatus::Waiting => self.theme.warning,
+                    TocStatus::Cycle => self.theme.error,
+                };
+                let deps: Vec<String> = entry.depends_on.iter().map(|d| d.label()).collect();
+                let row = Row::new([
+                    Cell::from(entry.order.map_or("-".to_string(), |o| o.to_string())),
+                    Cell::from(format!("#{}", entry.number)),
+                    Cell::from(entry.status.label()).style(Style::default().fg(status_color)),
+                    Cell::from(entry.priority.map_or("-".to_string(), |p| p.to_string())),
+                    Cell::from(entry.category.as_str()),
+                    Cell::from(entry.estimate.as_deref().unwrap_or("-")),
+                    Cell::from(if deps.is_empty() {
+                        "-".to_string()
+                    } else {
+                        deps.join(" ")
+                    }),
+                    Cell::from(entry.description.as_str()),
+                ]);
+                if self.toc.selected == Some(i) {
+                    row.style(Style::default().add_modifier(Modifier::REVERSED))
+                } else if entry.status == TocStatus::Done {
+                    row.style(Style::default().fg(self.theme.dim))
+                } else {
+                    row
+                }
+            });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(5),
+                Constraint::Length(4),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(12),
+                Constraint::Length(9),
+                Constraint::Length(14),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header);
+        frame.render_widget(table, table_area);
+
+        let selected = self.toc.selected.and_then(|i| entries.get(i));
+        let search_line = if self.toc.searching {
+            Line::from(vec![
+                Span::styled("/", Style::default().fg(self.theme.success)),
+                Span::styled(&self.toc.query, Style::default().fg(self.theme.text)),
+                Span::styled("█", Style::default().fg(self.theme.muted)),
+            ])
+        } else if let Some(entry) = selected {
+            Line::from(vec![
+                Span::styled(
+                    format!("#{} ", entry.number),
+                    Style::default().fg(self.theme.accent),
+                ),
+                Span::styled(&entry.description, Style::default().fg(self.theme.text)),
+            ])
+        } else {
+            Line::from(Span::styled(
+                "</> search  <↑/↓> scroll  <esc> clear/close  <t> close",
+                Style::default().fg(self.theme.dim),
+            ))
+        };
+        frame.render_widget(Paragraph::new(search_line), search_area);
+    }
+
+    fn render_footer(&mut self, frame: &mut Frame, area: Rect) {
+        let mode = self.mode();
+
+        let mut spans = vec![
+            Span::styled(" ralph v0.1.0 ", Style::default().fg(self.theme.accent)),
+            Span::styled("| ", Style::default().fg(self.theme.dim)),
+            Span::styled("Mode: ", Style::default().fg(self.theme.text)),
+            Span::styled(mode, Style::default().fg(self.theme.warning)),
+            Span::styled(" | ", Style::default().fg(self.theme.dim)),
+            Span::styled("<←/→>", Style::default().fg(self.theme.success)),
+            Span::styled(
+                if self.read_only {
+                    " pages  "
+                } else {
+                    " logs  "
+                },
+                Style::default().fg(self.theme.muted),
+            ),
+            Span::styled("<↑/↓>", Style::default().fg(self.theme.success)),
+            Span::styled(" scroll  ", Style::default().fg(self.theme.muted)),
+            Span::styled("<t>", Style::default().fg(self.theme.success)),
+            Span::styled(" tasks  ", Style::default().fg(self.theme.muted)),
+            Span::styled("<q>", Style::default().fg(self.theme.success)),
+            Span::styled(" quit  ", Style::default().fg(self.theme.muted)),
+            Span::styled("<?>", Style::default().fg(self.theme.success)),
+            Span::styled(" help  ", Style::default().fg(self.theme.muted)),
+        ];
+        if !self.read_only {
+            spans.push(Span::styled("<d>", Style::default().fg(self.theme.success)));
+            spans.push(Span::styled(
+                if self.show_diff { " logs  " } else { " diff  " },
+                Style::default().fg(self.theme.muted),
+            ));
+            spans.push(Span::styled("<r>", Style::default().fg(self.theme.success)));
+            spans.push(Span::styled(
+                " resume  ",
+                Style::default().fg(self.theme.muted),
+            ));
+            spans.push(Span::styled(
+                if self.vim_keys { "<L>" } else { "<l>" },
+                Style::default().fg(self.theme.success),
+            ));
+            spans.push(Span::styled(
+                " label  ",
+                Style::default().fg(self.theme.muted),
+            ));
+            spans.push(Span::styled("<e>", Style::default().fg(self.theme.success)));
+            spans.push(Span::styled(
+                " export  ",
+                Style::default().fg(self.theme.muted),
+            ));
+            spans.push(Span::styled(
+                "<^x>",
+                Style::default().fg(self.theme.success),
+            ));
+            spans.push(Span::styled(
+                " abort loop",
+                Style::default().fg(self.theme.muted),
+            ));
+            if self.last_finished_loop.is_some() {
+                spans.push(Span::styled(
+                    "  <s>",
+                    Style::default().fg(self.theme.success),
+                ));
+                spans.push(Span::styled(" rate", Style::default().fg(self.theme.muted)));
+            }
+        }
+        spans.push(Span::styled(
+            "  <1-4>",
+            Style::default().fg(self.theme.success),
+        ));
+        spans.push(Span::styled(" tabs", Style::default().fg(self.theme.muted)));
+        if let Some(input) = self.rating_input.as_ref().filter(|input| input.rating == 0) {
+            spans = vec![
+                Span::styled(
+                    format!(" Rating loop #{}: ", input.loop_number),
+                    Style::default().fg(self.theme.text),
+                ),
+                Span::styled("<1-5>", Style::default().fg(self.theme.success)),
+                Span::styled(" 5 is best  ", Style::default().fg(self.theme.muted)),
+                Span::styled("<esc>", Style::default().fg(self.theme.success)),
+                Span::styled(" cancel", Style::default().fg(self.theme.muted)),
+            ];
+        } else if let Some(input) = &self.rating_input {
+            let stars = "★".repeat(input.rating as usize) + &"☆".repeat(5 - input.rating as usize);
+            spans = vec![
+                Span::styled(
+                    format!(" Rating loop #{}: ", input.loop_number),
+                    Style::default().fg(self.theme.text),
+                ),
+                Span::styled(stars, Style::default().fg(self.theme.warning)),
+                Span::styled("  Why? ", Style::default().fg(self.theme.text)),
+                Span::styled(&input.note, Style::default().fg(self.theme.text)),
+                Span::styled("█", Style::default().fg(self.theme.muted)),
+                Span::styled(
+                    "  (enter=save, esc=save without note)",
+                    Style::default().fg(self.theme.muted),
+                ),
+            ];
+        }
+        if let Some(input) = &self.export_input {
+            spans = vec![
+                Span::styled(" Save log to: ", Style::default().fg(self.theme.warning)),
+                Span::styled(input, Style::default().fg(self.theme.text)),
+                Span::styled("█", Style::default().fg(self.theme.muted)),
+                Span::styled(
+                    "  (enter=save, esc=cancel)",
+                    Style::default().fg(self.theme.muted),
+                ),
+            ];
+        }
+        if let Some(input) = &self.guidance_input {
+            spans = vec![
+                Span::styled(
+                    " Paused. Guidance: ",
+                    Style::default().fg(self.theme.warning),
+                ),
+                Span::styled(input, Style::default().fg(self.theme.text)),
+                Span::styled("█", Style::default().fg(self.theme.muted)),
+                Span::styled(
+                    "  (enter=continue, esc=stop the run)",
+                    Style::default().fg(self.theme.muted),
+                ),
+            ];
+        }
+        if let Some(input) = &self.review_input {
+            spans = match &input.reason {
+                None => vec![
+                    Span::styled(
+                        format!(" Review task #{}: ", input.task_number),
+                        Style::default().fg(self.theme.warning),
+                    ),
+                    Span::styled("<a>", Style::default().fg(self.theme.success)),
+                    Span::styled(" approve  ", Style::default().fg(self.theme.muted)),
+                    Span::styled("<r>", Style::default().fg(self.theme.success)),
+                    Span::styled(" reject  ", Style::default().fg(self.theme.muted)),
+                    Span::styled("<↑/↓>", Style::default().fg(self.theme.success)),
+                    Span::styled(" scroll the diff  ", Style::default().fg(self.theme.muted)),
+                    Span::styled("<esc>", Style::default().fg(self.theme.success)),
+                    Span::styled(" stop the run", Style::default().fg(self.theme.muted)),
+                ],
+                Some(reason) => vec![
+                    Span::styled(
+                        format!(
+                            " Rejecting task #{}. What should change? ",
+                            input.task_number
+                        ),
+                        Style::default().fg(self.theme.warning),
+                    ),
+                    Span::styled(reason, Style::default().fg(self.theme.text)),
+                    Span::styled("█", Style::default().fg(self.theme.muted)),
+                    Span::styled(
+                        "  (enter=reject and revert, esc=back)",
+                        Style::default().fg(self.theme.muted),
+                    ),
+                ],
+            };
+        }
+        let asking = self.rating_input.is_some()
+            || self.export_input.is_some()
+            || self.guidance_input.is_some()
+            || self.review_input.is_some();
+        self.mouse_areas.footer_buttons = if asking {
+            Vec::new()
+        } else {
+            footer_buttons(&spans, area)
         };
+        let footer_text = Line::from(spans);
 
-        let footer_text = Line::from(vec![
-            Span::styled(" ralph v0.1.0 ", Style::default().fg(Color::Cyan)),
-            Span::styled("| ", Style::default().fg(Color::DarkGray)),
-            Span::styled("Mode: ", Style::default().fg(Color::White)),
-            Span::styled(mode, Style::default().fg(Color::Yellow)),
-            Span::styled(" | ", Style::default().fg(Color::DarkGray)),
-            Span::styled("<←/→>", Style::default().fg(Color::Green)),
-            Span::styled(" logs  ", Style::default().fg(Color::Gray)),
-            Span::styled("<↑/↓>", Style::default().fg(Color::Green)),
-            Span::styled(" scroll  ", Style::default().fg(Color::Gray)),
-            Span::styled("<q>", Style::default().fg(Color::Green)),
-            Span::styled(" quit  ", Style::default().fg(Color::Gray)),
-            Span::styled("<r>", Style::default().fg(Color::Green)),
-            Span::styled(" resume", Style::default().fg(Color::Gray)),
-        ]);
-
-        let paragraph = Paragraph::new(footer_text).style(Style::default().bg(Color::DarkGray));
+        let paragraph = Paragraph::new(footer_text).style(Style::default().bg(self.theme.bar));
 
         frame.render_widget(paragraph, area);
     }
 
-    fn parse_markdown_output(&self) -> Vec<Line<'_>> {
+    fn render_markdown_output(&self) -> Vec<Line<'static>> {
         let current = self.current_log();
         if current.is_empty() {
             return vec![Line::from(Span::styled(
                 "Waiting for output...",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(self.theme.dim),
             ))];
         }
+        markdown::render(current, &self.theme)
+    }
 
+    /// Diff lines coloured as `git diff` colours them: additions green, removals red
+    fn parse_diff_output(&self) -> Vec<Line<'_>> {
+        let current = self.current_log();
+        if current.is_empty() {
+            return vec![Line::from(Span::styled(
+                "No loop has committed anything yet.",
+                Style::default().fg(self.theme.dim),
+            ))];
+        }
         current
             .lines()
-            .map(|line| {
-                if line.starts_with("### ") {
-                    // Header: cyan bold
-                    Line::from(Span::styled(
-                        line.strip_prefix("### ").unwrap_or(line),
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD),
-                    ))
-                } else if line.starts_with("## ") {
-                    // H2: cyan bold
-                    Line::from(Span::styled(
-                        line.strip_prefix("## ").unwrap_or(line),
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD),
-                    ))
-                } else if line.starts_with("# ") {
-                    // H1: cyan bold underline
-                    Line::from(Span::styled(
-                        line.strip_prefix("# ").unwrap_or(line),
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-                    ))
-                } else if line.trim_start().starts_with("* ") || line.trim_start().starts_with("- ")
-                {
-                    // Bullet point
-                    let indent = line.len() - line.trim_start().len();
-                    let content = line
-                        .trim_start()
-                        .strip_prefix("* ")
-                        .or_else(|| line.trim_start().strip_prefix("- "))
-                        .unwrap_or(line);
-
-                    let bullet_color = if indent > 0 {
-                        Color::Gray
-                    } else {
-                        Color::Yellow
-                    };
-                    let bullet_char = if indent > 0 { "  -" } else { "*" };
-
-                    Line::from(vec![
-                        Span::styled(" ".repeat(indent), Style::default()),
-                        Span::styled(
-                            format!("{} ", bullet_char),
-                            Style::default().fg(bullet_color),
-                        ),
-                        Span::styled(content, Style::default().fg(Color::White)),
-                    ])
-                } else if line.contains('`') {
-                    // Line with inline code - parse backticks
-                    self.parse_inline_code(line)
-                } else {
-                    // Regular line
-                    Line::from(Span::styled(line, Style::default().fg(Color::White)))
-                }
-            })
+            .map(|line| Line::from(Span::styled(line, diff_line_style(&self.theme, line))))
             .collect()
     }
 
-    fn parse_inline_code(&self, line: &str) -> Line<'_> {
-        let mut spans = Vec::new();
-        let mut in_code = false;
-        let mut current = String::new();
-
-        for ch in line.chars() {
-            if ch == '`' {
-                if !current.is_empty() {
-                    let style = if in_code {
-                        Style::default().fg(Color::Magenta).bg(Color::Black)
-                    } else {
-                        Style::default().fg(Color::White)
-                    };
-                    spans.push(Span::styled(current.clone(), style));
-                    current.clear();
-                }
-                in_code = !in_code;
-            } else {
-                current.push(ch);
+    pub fn prev_log(&mut self) {
+        if self.show_diff {
+            if self.current_diff_index > 0 {
+                self.current_diff_index -= 1;
+                self.log_scroll_offset = 0;
             }
+            return;
         }
-
-        // Handle remaining text
-        if !current.is_empty() {
-            let style = if in_code {
-                Style::default().fg(Color::Magenta).bg(Color::Black)
-            } else {
-                Style::default().fg(Color::White)
-            };
-            spans.push(Span::styled(current, style));
-        }
-
-        Line::from(spans)
-    }
-
-    pub fn prev_log(&mut self) {
         if self.current_log_index > 0 {
             self.current_log_index -= 1;
             self.log_scroll_offset = 0;
@@ -397,25 +1432,193 @@ impl App {
     }
 
     pub fn next_log(&mut self) {
+        if self.show_diff {
+            if self.current_diff_index + 1 < self.loop_diffs.len() {
+                self.current_diff_index += 1;
+                self.log_scroll_offset = 0;
+            }
+            return;
+        }
         if self.current_log_index + 1 < self.iteration_logs.len() {
             self.current_log_index += 1;
             self.log_scroll_offset = 0;
         }
     }
 
+    /// Act on a mouse event: the wheel scrolls the log panel or the task list, and a click
+    /// selects a task in the task list. A click on a footer shortcut returns its key, for the
+    /// caller to handle as if it had been pressed.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) -> Option<KeyEvent> {
+        match mouse.kind {
+            MouseEventKind::ScrollUp if self.toc.visible => self.toc.scroll_up(WHEEL_LINES),
+            MouseEventKind::ScrollDown if self.toc.visible => self.toc.scroll_down(WHEEL_LINES),
+            MouseEventKind::ScrollUp => self.scroll_up(WHEEL_LINES),
+            MouseEventKind::ScrollDown => self.scroll_down(WHEEL_LINES),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let rows = self.mouse_areas.toc_rows;
+                let at = Position::new(mouse.column, mouse.row);
+                if self.toc.visible && rows.contains(at) {
+                    self.toc.click_row((mouse.row - rows.y) as usize);
+                }
+                return self
+                    .mouse_areas
+                    .footer_buttons
+                    .iter()
+                    .find(|(button, _)| button.contains(at))
+                    .map(|(_, key)| *key);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// With `vim_keys`, the arrow or paging key a vim key stands for; any other key as is.
+    /// Not for keys typed into an input.
+    pub fn vim_key(&self, key: KeyEvent) -> KeyEvent {
+        if !self.vim_keys {
+            return key;
+        }
+        let code = match (key.code, key.modifiers.contains(KeyModifiers::CONTROL)) {
+            (KeyCode::Char('j'), false) => KeyCode::Down,
+            (KeyCode::Char('k'), false) => KeyCode::Up,
+            (KeyCode::Char('h'), false) => KeyCode::Left,
+            (KeyCode::Char('l'), false) => KeyCode::Right,
+            (KeyCode::Char('g'), false) => KeyCode::Home,
+            (KeyCode::Char('G'), false) => KeyCode::End,
+            (KeyCode::Char('d'), true) => KeyCode::PageDown,
+            (KeyCode::Char('u'), true) => KeyCode::PageUp,
+            _ => return key,
+        };
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.log_scroll_offset = 0;
+    }
+
+    /// Scroll so the end of the log is in view
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll_down(usize::MAX);
+    }
+
     pub fn scroll_up(&mut self, amount: usize) {
         self.log_scroll_offset = self.log_scroll_offset.saturating_sub(amount);
     }
 
     pub fn scroll_down(&mut self, amount: usize) {
-        let content_height = self.current_log().lines().count();
+        let content_height = if self.show_diff {
+            self.current_log().lines().count()
+        } else {
+            self.render_markdown_output().len()
+        };
         self.log_scroll_offset = self
             .log_scroll_offset
             .saturating_add(amount)
             .min(content_height);
     }
 
+    /// Start editing the run label, pre-filled with the current one
+    pub fn start_renaming(&mut self) {
+        self.label_input = Some(self.label.clone().unwrap_or_default());
+    }
+
+    /// Stop editing the label, keeping the typed text if `save` is set
+    pub fn finish_renaming(&mut self, save: bool) {
+        if let Some(input) = self.label_input.take()
+            && save
+        {
+            let input = input.trim();
+            self.label = (!input.is_empty()).then(|| input.to_string());
+        }
+    }
+
+    /// Start typing where to save the log shown (key `e`), pre-filled with
+    /// `ralph-loop-<n>.md`; false if there is nothing to save yet
+    pub fn start_export(&mut self) -> bool {
+        if self.current_log().is_empty() {
+            return false;
+        }
+        let loop_number = if self.show_diff {
+            self.loop_diffs
+                .get(self.current_diff_index)
+                .map_or(0, |d| d.loop_number)
+        } else {
+            self.log_loops
+                .get(self.current_log_index)
+                .copied()
+                .unwrap_or(0)
+        };
+        self.export_input = Some(format!("ralph-loop-{}.md", loop_number));
+        true
+    }
+
+    /// Stop typing the export path, writing the log shown there if `save` is set. The
+    /// status line says where it went or why it couldn't be written.
+    pub fn finish_export(&mut self, save: bool) {
+        let Some(input) = self.export_input.take().filter(|_| save) else {
+            return;
+        };
+        let path = input.trim();
+        if path.is_empty() {
+            return;
+        }
+        let contents = if self.show_diff {
+            format!("```diff\n{}\n```\n", self.current_log().trim_end())
+        } else {
+            format!("{}\n", self.current_log().trim_end())
+        };
+        match std::fs::write(path, contents) {
+            Ok(()) => self.set_status(&format!("Saved the log to {}", path)),
+            Err(e) => self.set_status(&format!("Could not save the log to {}: {}", path, e)),
+        }
+    }
+
+    /// Start rating the last finished loop 1-5; false if no loop has finished yet
+    pub fn start_rating(&mut self, rating: u8) -> bool {
+        let Some(loop_number) = self.last_finished_loop else {
+            return false;
+        };
+        self.rating_input = Some(RatingInput {
+            loop_number,
+            rating: rating.clamp(1, 5),
+            note: String::new(),
+        });
+        true
+    }
+
+    /// Ask for a score for the last finished loop (key `s`); false if no loop has finished
+    /// yet
+    pub fn ask_rating(&mut self) -> bool {
+        let Some(loop_number) = self.last_finished_loop else {
+            return false;
+        };
+        self.rating_input = Some(RatingInput {
+            loop_number,
+            rating: 0,
+            note: String::new(),
+        });
+        true
+    }
+
+    /// Queue the rating being given for saving, dropping the typed note unless `keep_note`.
+    /// A rating still waiting for its score is dropped.
+    pub fn finish_rating(&mut self, keep_note: bool) {
+        if let Some(mut input) = self.rating_input.take().filter(|input| input.rating > 0) {
+            if !keep_note {
+                input.note.clear();
+            }
+            input.note = input.note.trim().to_string();
+            self.finished_ratings.push(input);
+        }
+    }
+
     pub fn set_status(&mut self, msg: &str) {
+        if self.status_message != msg {
+            tracing::info!(loop_number = self.loop_count, "{}", msg);
+            if self.prints_lines() {
+                print_timestamped(msg);
+            }
+        }
         self.status_message = msg.to_string();
     }
 
@@ -428,23 +1631,315 @@ impl App {
         self.completed_tasks = completed;
     }
 
+    /// Headless with stdout to itself (not taken by events): logs are printed as lines
+    fn prints_lines(&self) -> bool {
+        self.headless && !self.events.as_ref().is_some_and(EventLog::is_stdout)
+    }
+
     /// Add a new iteration log and switch to viewing it
     pub fn push_log(&mut self, output: String) {
+        let output = self.redactor.redact(&output);
+        tracing::debug!(loop_number = self.loop_count, "{}", output);
+        if let Some(events) = &self.events {
+            events.emit(&Event::StreamChunk {
+                loop_number: self.loop_count,
+                text: &output,
+            });
+        }
+        if self.prints_lines() {
+            print_timestamped(&output);
+        }
         self.iteration_logs.push(output);
+        self.log_loops.push(self.loop_count);
         self.current_log_index = self.iteration_logs.len() - 1;
         self.log_scroll_offset = 0;
     }
 
+    /// Keep what a loop committed for the diff view, which then shows it
+    pub fn push_diff(&mut self, loop_number: u64, diff: &str) {
+        self.loop_diffs.push(LoopDiff {
+            loop_number,
+            diff: self.redactor.redact(diff),
+        });
+        self.current_diff_index = self.loop_diffs.len() - 1;
+        if self.show_diff {
+            self.log_scroll_offset = 0;
+        }
+    }
+
+    /// Switch the log panel between the logs and the loop diffs
+    pub fn toggle_diff(&mut self) {
+        self.show_diff = !self.show_diff;
+        self.log_scroll_offset = 0;
+    }
+
     /// Get the latest log content (for exit clause checking)
     pub fn latest_log(&self) -> Option<&str> {
         self.iteration_logs.last().map(|s| s.as_str())
     }
 }
 
+/// "3 loops, avg 4m 10s, 1 failed": how a task has gone across runs
+pub fn format_task_history(history: &TaskStats) -> String {
+    let mut text = format!(
+        "{} loop{}, avg {}",
+        history.attempts,
+        if history.attempts == 1 { "" } else { "s" },
+        format_secs(history.duration_mean())
+    );
+    if history.failed > 0 {
+        text.push_str(&format!(", {} failed", history.failed));
+    }
+    text
+}
+
+/// The footer shortcuts a click can stand in for, and the key each one sends. Shortcuts
+/// for more than one key (`<←/→>`, `<1-4>`) aren't buttons.
+fn footer_key(label: &str) -> Option<KeyEvent> {
+    let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+    match label {
+        "<t>" => Some(key('t')),
+        "<s>" => Some(key('s')),
+        "<?>" => Some(key('?')),
+        "<q>" => Some(key('q')),
+        "<d>" => Some(key('d')),
+        "<r>" => Some(key('r')),
+        "<l>" => Some(key('l')),
+        "<L>" => Some(key('L')),
+        "<^x>" => Some(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)),
+        _ => None,
+    }
+}
+
+/// Where each clickable shortcut in the footer `spans` lands on screen: the shortcut and
+/// the label after it
+fn footer_buttons(spans: &[Span], area: Rect) -> Vec<(Rect, KeyEvent)> {
+    let mut buttons = Vec::new();
+    let mut x = area.x;
+    for (i, span) in spans.iter().enumerate() {
+        let width = span.width() as u16;
+        if let Some(key) = footer_key(span.content.trim()) {
+            let label = spans.get(i + 1).map_or(0, |s| s.width() as u16);
+            let button = Rect::new(x, area.y, width + label, 1).intersection(area);
+            buttons.push((button, key));
+        }
+        x = x.saturating_add(width);
+    }
+    buttons
+}
+
+/// How the diff view shows a line of `git diff --stat` or `git diff` output
+fn diff_line_style(theme: &Theme, line: &str) -> Style {
+    if line.starts_with("diff --git") {
+        Style::default()
+            .fg(theme.warning)
+            .add_modifier(Modifier::BOLD)
+    } else if line.starts_with("+++") || line.starts_with("---") {
+        Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+    } else if line.starts_with('+') {
+        Style::default().fg(theme.success)
+    } else if line.starts_with('-') {
+        Style::default().fg(theme.error)
+    } else if line.starts_with("@@") {
+        Style::default().fg(theme.accent)
+    } else {
+        Style::default().fg(theme.muted)
+    }
+}
+
+/// Print each line of `text` after the local time, for headless runs
+pub fn print_timestamped(text: &str) {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    for line in text.lines() {
+        println!("[{}] {}", now, line);
+    }
+}
+
+/// Format the seconds left before a scheduled start (e.g. 2h 05m 09s, 4m 30s, 12s)
+pub fn format_countdown(secs: u64) -> String {
+    if secs >= 3600 {
+        format!(
+            "{}h {:02}m {:02}s",
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// "45s", "4m 10s", "1h 50m"
+pub fn format_secs(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Format a token count compactly (e.g. 950, 12.3k, 1.5M)
+pub fn format_tokens(count: u64) -> String {
+    if count >= 1_000_000 {
+        format!("{:.1}M", count as f64 / 1_000_000.0)
+    } else if count >= 1_000 {
+        format!("{:.1}k", count as f64 / 1_000.0)
+    } else {
+        count.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn export_writes_the_log_shown_under_its_loop_number() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = App::new("Test", 1, 0);
+        assert!(!app.start_export());
+
+        app.push_log("Starting".to_string());
+        app.increment_loop();
+        app.increment_loop();
+        app.push_log("## Loop two\n\nDone".to_string());
+        assert!(app.start_export());
+        assert_eq!(app.export_input.as_deref(), Some("ralph-loop-2.md"));
+
+        let path = dir.path().join("loop.md");
+        app.export_input = Some(path.display().to_string());
+        app.finish_export(true);
+        assert!(app.export_input.is_none());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "## Loop two\n\nDone\n"
+        );
+        assert!(app.status_message.starts_with("Saved the log to"));
+
+        app.prev_log();
+        app.start_export();
+        assert_eq!(app.export_input.as_deref(), Some("ralph-loop-0.md"));
+        app.finish_export(false);
+        assert!(app.export_input.is_none());
+    }
+
+    #[test]
+    fn renaming_saves_or_cancels() {
+        let mut app = App::new("Test", 1, 0);
+        app.label = Some("night 1".to_string());
+
+        app.start_renaming();
+        assert_eq!(app.label_input.as_deref(), Some("night 1"));
+        app.label_input = Some("night 2 ".to_string());
+        app.finish_renaming(true);
+        assert_eq!(app.label.as_deref(), Some("night 2"));
+        assert!(app.label_input.is_none());
+
+        app.start_renaming();
+        app.label_input = Some("discarded".to_string());
+        app.finish_renaming(false);
+        assert_eq!(app.label.as_deref(), Some("night 2"));
+
+        app.start_renaming();
+        app.label_input = Some(String::new());
+        app.finish_renaming(true);
+        assert_eq!(app.label, None);
+    }
+
+    #[test]
+    fn asked_rating_without_a_score_is_dropped() {
+        let mut app = App::new("Test", 1, 0);
+        assert!(!app.ask_rating());
+        app.last_finished_loop = Some(3);
+        assert!(app.ask_rating());
+        assert_eq!(app.rating_input.as_ref().unwrap().rating, 0);
+        app.finish_rating(true);
+        assert!(app.rating_input.is_none());
+        assert!(app.finished_ratings.is_empty());
+    }
+
+    #[test]
+    fn number_keys_pick_tabs() {
+        assert_eq!(Tab::from_key('1'), Some(Tab::Logs));
+        assert_eq!(Tab::from_key('4'), Some(Tab::Session));
+        assert_eq!(Tab::from_key('0'), None);
+        assert_eq!(Tab::from_key('5'), None);
+    }
+
+    #[test]
+    fn usage_is_summed_per_loop() {
+        let mut app = App::new("Test", 1, 0);
+        let usage = Usage {
+            output_tokens: 100,
+            ..Usage::default()
+        };
+        app.loop_count = 1;
+        app.record_usage(0.5, &usage);
+        // A verify session in the same loop adds to it
+        app.record_usage(0.25, &usage);
+        app.loop_count = 2;
+        app.record_usage(1.0, &usage);
+        assert_eq!(
+            app.loop_costs,
+            vec![
+                LoopCost {
+                    loop_number: 1,
+                    cost_usd: 0.75,
+                    output_tokens: 200,
+                },
+                LoopCost {
+                    loop_number: 2,
+                    cost_usd: 1.0,
+                    output_tokens: 100,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rating_needs_a_finished_loop() {
+        let mut app = App::new("Test", 1, 0);
+        assert!(!app.start_rating(4));
+        assert!(app.rating_input.is_none());
+
+        app.last_finished_loop = Some(2);
+        assert!(app.start_rating(4));
+        app.rating_input
+            .as_mut()
+            .unwrap()
+            .note
+            .push_str(" no tests ");
+        app.finish_rating(true);
+        assert!(app.start_rating(9));
+        app.rating_input
+            .as_mut()
+            .unwrap()
+            .note
+            .push_str("discarded");
+        app.finish_rating(false);
+
+        assert_eq!(
+            app.finished_ratings,
+            vec![
+                RatingInput {
+                    loop_number: 2,
+                    rating: 4,
+                    note: "no tests".to_string()
+                },
+                RatingInput {
+                    loop_number: 2,
+                    rating: 5,
+                    note: String::new()
+                },
+            ]
+        );
+    }
+
     #[test]
     fn new_app_initialization() {
         let app = App::new("Test PRD", 5, 3);
@@ -459,6 +1954,99 @@ mod tests {
         assert_eq!(app.log_scroll_offset, 0);
         assert_eq!(app.spinner_frame, 0);
         assert!(app.loop_start_time.is_none());
+        assert_eq!(app.total_cost_usd, 0.0);
+        assert!(app.last_cost_usd.is_none());
+        assert_eq!(app.total_usage, Usage::default());
+    }
+
+    #[test]
+    fn record_usage_accumulates() {
+        let mut app = App::new("Test", 1, 0);
+        app.record_usage(
+            0.5,
+            &Usage {
+                input_tokens: 100,
+                output_tokens: 2000,
+                cache_read_input_tokens: 40_000,
+                ..Default::default()
+            },
+        );
+        app.record_usage(
+            0.25,
+            &Usage {
+                input_tokens: 50,
+                output_tokens: 1000,
+                ..Default::default()
+            },
+        );
+
+        assert!((app.total_cost_usd - 0.75).abs() < f64::EPSILON);
+        assert_eq!(app.last_cost_usd, Some(0.25));
+        assert_eq!(app.total_usage.input_tokens, 150);
+        assert_eq!(app.total_usage.output_tokens, 3000);
+        assert_eq!(app.total_usage.cache_read_input_tokens, 40_000);
+    }
+
+    #[test]
+    fn budget_exceeded_requires_limit() {
+        let mut app = App::new("Test", 1, 0);
+        app.record_usage(100.0, &Usage::default());
+        assert!(!app.budget_exceeded());
+    }
+
+    #[test]
+    fn budget_exceeded_at_or_over_limit() {
+        let mut app = App::new("Test", 1, 0);
+        app.max_cost_usd = Some(1.0);
+
+        app.record_usage(0.6, &Usage::default());
+        assert!(!app.budget_exceeded());
+
+        app.record_usage(0.4, &Usage::default());
+        assert!(app.budget_exceeded());
+
+        app.record_usage(0.5, &Usage::default());
+        assert!(app.budget_exceeded());
+    }
+
+    #[test]
+    fn worker_pane_stops_its_clock_when_finished() {
+        let mut worker = WorkerPane::new(3, "Add login");
+        assert_eq!(worker.status, "working");
+        assert!(worker.finished.is_none());
+        worker.finish("completed");
+        assert_eq!(worker.status, "completed");
+        assert!(worker.finished.is_some());
+    }
+
+    #[test]
+    fn task_checklist_marks_done_steps() {
+        let steps = vec!["Form".to_string(), "API".to_string(), "Tests".to_string()];
+        let checklist = TaskChecklist::new(2, &steps, &[1, 3]);
+        assert_eq!(
+            checklist.steps,
+            vec![
+                ("Form".to_string(), true),
+                ("API".to_string(), false),
+                ("Tests".to_string(), true),
+            ]
+        );
+        assert_eq!(checklist.done_count(), 2);
+    }
+
+    #[test]
+    fn format_secs_picks_largest_units() {
+        assert_eq!(format_secs(45.4), "45s");
+        assert_eq!(format_secs(250.0), "4m 10s");
+        assert_eq!(format_secs(6600.0), "1h 50m");
+    }
+
+    #[test]
+    fn format_tokens_scales_units() {
+        assert_eq!(format_tokens(0), "0");
+        assert_eq!(format_tokens(950), "950");
+        assert_eq!(format_tokens(12_345), "12.3k");
+        assert_eq!(format_tokens(1_500_000), "1.5M");
     }
 
     #[test]
@@ -515,6 +2103,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn countdown_formats_hours_minutes_and_seconds() {
+        assert_eq!(format_countdown(7509), "2h 05m 09s");
+        assert_eq!(format_countdown(270), "4m 30s");
+        assert_eq!(format_countdown(12), "12s");
+    }
+
     #[test]
     fn increment_loop() {
         let mut app = App::new("Test", 1, 0);
@@ -570,6 +2165,143 @@ mod tests {
         assert_eq!(app.current_log_index, 2);
     }
 
+    #[test]
+    fn diff_view_toggles_and_navigates_diffs() {
+        let mut app = App::new("Test", 1, 0);
+        app.push_log("Log 1".to_string());
+        app.push_diff(1, "+a");
+        app.push_diff(3, "-b");
+        assert_eq!(app.current_diff_index, 1);
+
+        app.toggle_diff();
+        assert_eq!(app.current_log(), "-b");
+        app.prev_log();
+        assert_eq!(app.current_log(), "+a");
+        app.prev_log();
+        assert_eq!(app.current_diff_index, 0);
+        assert_eq!(app.current_log_index, 0);
+
+        app.toggle_diff();
+        assert_eq!(app.current_log(), "Log 1");
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn wheel_scrolls_and_footer_clicks_send_their_key() {
+        let mut app = App::new("Test", 1, 0);
+        app.push_log((1..=20).map(|i| format!("line {}\n", i)).collect());
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+
+        app.handle_mouse(mouse(MouseEventKind::ScrollDown, 10, 15));
+        assert_eq!(app.log_scroll_offset, WHEEL_LINES);
+        app.handle_mouse(mouse(MouseEventKind::ScrollUp, 10, 15));
+        assert_eq!(app.log_scroll_offset, 0);
+
+        let (tasks, key) = app
+            .mouse_areas
+            .footer_buttons
+            .iter()
+            .find(|(_, key)| key.code == KeyCode::Char('t'))
+            .copied()
+            .unwrap();
+        let click = mouse(MouseEventKind::Down(MouseButton::Left), tasks.x + 4, 29);
+        assert_eq!(app.handle_mouse(click), Some(key));
+        let elsewhere = mouse(MouseEventKind::Down(MouseButton::Left), 0, 15);
+        assert_eq!(app.handle_mouse(elsewhere), None);
+
+        // While the footer asks for a rating note, its text isn't a button
+        app.last_finished_loop = Some(1);
+        app.start_rating(4);
+        terminal.draw(|f| app.draw(f)).unwrap();
+        assert!(app.mouse_areas.footer_buttons.is_empty());
+    }
+
+    #[test]
+    fn vim_keys_stand_for_arrows_only_when_enabled() {
+        let mut app = App::new("Test", 1, 0);
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(app.vim_key(j), j);
+
+        app.vim_keys = true;
+        assert_eq!(app.vim_key(j).code, KeyCode::Down);
+        assert_eq!(app.vim_key(ctrl_d).code, KeyCode::PageDown);
+        let big_g = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(app.vim_key(big_g), KeyEvent::from(KeyCode::End));
+        // Plain d still toggles the diff view
+        let d = KeyEvent::from(KeyCode::Char('d'));
+        assert_eq!(app.vim_key(d), d);
+    }
+
+    #[test]
+    fn help_overlay_lists_keys_for_the_mode() {
+        let mut app = App::new("Test", 1, 0);
+        app.show_help = true;
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Help - Running"));
+        assert!(screen.contains("abort only this loop"));
+
+        app.read_only = true;
+        let keys: Vec<_> = app
+            .help_sections()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(keys, vec!["Pages", "Tabs", "Task list", "Help"]);
+    }
+
+    #[test]
+    fn task_history_shows_loops_average_and_failures() {
+        let history = TaskStats {
+            attempts: 3,
+            completed: 1,
+            failed: 1,
+            duration_total: 750.0,
+            cost_total: 1.5,
+        };
+        assert_eq!(
+            format_task_history(&history),
+            "3 loops, avg 4m 10s, 1 failed"
+        );
+        let once = TaskStats {
+            attempts: 1,
+            completed: 1,
+            duration_total: 45.0,
+            ..TaskStats::default()
+        };
+        assert_eq!(format_task_history(&once), "1 loop, avg 45s");
+    }
+
+    #[test]
+    fn diff_lines_are_coloured_by_kind() {
+        let theme = Theme::light();
+        let style = |line| diff_line_style(&theme, line).fg;
+        assert_eq!(style("+added"), Some(theme.success));
+        assert_eq!(style("-removed"), Some(theme.error));
+        assert_eq!(style("@@ -1 +1 @@"), Some(theme.accent));
+        assert_eq!(style("+++ b/src/main.rs"), Some(theme.text));
+        assert_eq!(style(" src/main.rs | 2 +-"), Some(theme.muted));
+    }
+
     #[test]
     fn push_log_resets_scroll() {
         let mut app = App::new("Test", 1, 0);
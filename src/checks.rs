@@ -0,0 +1,217 @@
+//! Checks of what a build needs, shared by `ralph doctor` and `ralph build --dry-run`: each
+//! comes out Ok, a warning or a failure, with a fix for anything that isn't Ok.
+
+use std::path::Path;
+
+use ralph_core::gates::{self, GateProgram};
+use ralph_core::prd::Prd;
+use ralph_core::process;
+
+/// Oldest claude CLI ralph supports
+pub const MIN_CLAUDE_VERSION: (u32, u32, u32) = (2, 0, 0);
+
+/// How one check came out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One check's outcome, and what to do about it when it isn't Ok
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub level: Level,
+    pub message: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    pub fn warn(message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            level: Level::Warn,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn fail(message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            level: Level::Fail,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    /// Print the outcome, and its fix on the line below, indented by `indent`
+    pub fn print(&self, indent: &str) {
+        let mark = match self.level {
+            Level::Ok => "✓",
+            Level::Warn => "!",
+            Level::Fail => "✗",
+        };
+        println!("{}{} {}", indent, mark, self.message);
+        if let Some(fix) = &self.fix {
+            println!("{}    Fix: {}", indent, fix);
+        }
+    }
+}
+
+/// Whether any of `checks` failed
+pub fn any_failed(checks: &[Check]) -> bool {
+    checks.iter().any(|c| c.level == Level::Fail)
+}
+
+/// The version number at the start of `claude --version` output, e.g. "2.0.14 (Claude Code)"
+pub fn parse_version(output: &str) -> Option<(u32, u32, u32)> {
+    let version = output.split_whitespace().next()?;
+    let mut parts = version.trim_start_matches('v').split('.');
+    let mut next = || parts.next()?.parse::<u32>().ok();
+    Some((next()?, next()?, next().unwrap_or(0)))
+}
+
+/// That claude is on PATH, runs, and is recent enough
+pub fn claude() -> Check {
+    const INSTALL: &str = "install it with `npm install -g @anthropic-ai/claude-code`";
+    if !gates::program_on_path("claude") {
+        return Check::fail("claude not found on PATH", INSTALL);
+    }
+    let output = match process::command("claude").arg("--version").output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            return Check::fail(
+                format!("`claude --version` exited with {}", output.status),
+                format!("check the installation, or reinstall: {}", INSTALL),
+            );
+        }
+        Err(e) => {
+            return Check::fail(
+                format!("claude couldn't be run: {}", e),
+                format!("check the installation, or reinstall: {}", INSTALL),
+            );
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (major, minor, patch) = MIN_CLAUDE_VERSION;
+    match parse_version(&stdout) {
+        Some(version) if version >= MIN_CLAUDE_VERSION => {
+            Check::ok(format!("claude {}", stdout.trim()))
+        }
+        Some(_) => Check::fail(
+            format!(
+                "claude {} is older than {}.{}.{}",
+                stdout.trim(),
+                major,
+                minor,
+                patch
+            ),
+            "update it with `claude update`",
+        ),
+        None => Check::warn(
+            format!("couldn't read claude's version from `{}`", stdout.trim()),
+            format!(
+                "make sure `claude --version` reports {}.{}.{} or later",
+                major, minor, patch
+            ),
+        ),
+    }
+}
+
+/// That the PRD at `prd_path` has quality gates, and that each one's program can be found.
+/// A gate starting with a shell builtin (`cd web && npm test`) is left for running to check.
+pub fn quality_gates(prd: &Prd, prd_path: &str) -> Vec<Check> {
+    if prd.quality_gates.is_empty() {
+        return vec![Check::warn(
+            "PRD has no quality gates",
+            "add the project's test, lint and build commands to quality_gates",
+        )];
+    }
+    prd.quality_gates
+        .iter()
+        .map(|gate| match gates::gate_program(gate) {
+            GateProgram::Program(program) if gates::program_on_path(program) => {
+                Check::ok(format!("gate `{}`", gate))
+            }
+            GateProgram::Program(program) => Check::fail(
+                format!("gate `{}`: {} not found on PATH", gate, program),
+                format!("install {}, or change the gate in {}", program, prd_path),
+            ),
+            GateProgram::Shell => Check::ok(format!(
+                "gate `{}` (a shell command, checked when it runs)",
+                gate
+            )),
+            GateProgram::Empty => Check::fail(
+                "empty quality gate command",
+                format!("remove it from {}", prd_path),
+            ),
+        })
+        .collect()
+}
+
+/// That every package a pending task (or the whole PRD) works in exists under `root`
+pub fn workdirs(prd: &Prd, prd_path: &str, root: &Path) -> Vec<Check> {
+    let mut workdirs: Vec<&str> = prd
+        .tasks
+        .iter()
+        .filter(|t| !t.passes)
+        .filter_map(|t| prd.workdir_for(Some(t)))
+        .collect();
+    workdirs.sort_unstable();
+    workdirs.dedup();
+    workdirs
+        .into_iter()
+        .map(|dir| {
+            if root.join(dir).is_dir() {
+                Check::ok(format!("workdir {}", dir))
+            } else {
+                Check::fail(
+                    format!("workdir {} doesn't exist", dir),
+                    format!("create it, or fix the workdir in {}", prd_path),
+                )
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_claude_versions() {
+        assert_eq!(parse_version("2.0.14 (Claude Code)\n"), Some((2, 0, 14)));
+        assert_eq!(parse_version("v1.2"), Some((1, 2, 0)));
+        assert_eq!(parse_version("unknown"), None);
+        assert!(parse_version("1.0.128 (Claude Code)").unwrap() < MIN_CLAUDE_VERSION);
+    }
+
+    #[test]
+    fn checks_each_gate_and_workdir() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Demo", "quality_gates": ["ralph-no-such-program --all", "cd . && true"],
+                "tasks": [{"category": "api", "description": "Route", "steps": [],
+                           "passes": false, "workdir": "missing"}]}"#,
+        )
+        .unwrap();
+        let gates = quality_gates(&prd, "prd.json");
+        assert_eq!(gates[0].level, Level::Fail);
+        assert_eq!(
+            gates[0].fix.as_deref(),
+            Some("install ralph-no-such-program, or change the gate in prd.json")
+        );
+        assert_eq!(gates[1].level, Level::Ok);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let workdirs = workdirs(&prd, "prd.json", dir.path());
+        assert_eq!(workdirs[0].message, "workdir missing doesn't exist");
+        assert!(any_failed(&workdirs));
+    }
+}
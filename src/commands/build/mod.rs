@@ -0,0 +1,4395 @@
+mod parallel;
+
+use clap::{Args, ValueEnum};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use ratatui::Terminal;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::Instrument;
+use tracing::field::Empty;
+
+use crate::app::{App, ReviewInput, Tab, TaskChecklist, format_countdown};
+use crate::checks::{self, Check};
+use crate::commands::{CommandResult, Registered};
+use crate::config::{self, ProgressConfig};
+use crate::error::RalphError;
+use crate::logging;
+use crate::notify::{self, Notifier, Preference};
+use crate::runtime::{self, Input, Keys};
+use crate::tui;
+use ralph_core::checkpoint::Checkpoint;
+use ralph_core::claude::{self, Thinking};
+use ralph_core::code_review;
+use ralph_core::completion::{CompletionCriteria, Detector};
+use ralph_core::done;
+use ralph_core::escalation::{self, Tracker};
+use ralph_core::events::{Event as RunEvent, EventLog, OutputFormat};
+use ralph_core::gate_fix;
+use ralph_core::gates;
+use ralph_core::git;
+use ralph_core::iteration::{
+    self, BASE_RETRY_DELAY_SECS, BuildIterationOutput, DEFAULT_MAX_TURNS, MAX_RETRIES, Outcome,
+    detect_completion, eligible_pending, find_task, next_task, next_task_except,
+};
+use ralph_core::loop_log::{LoopLog, RawOutput};
+use ralph_core::prd;
+use ralph_core::process;
+use ralph_core::progress;
+use ralph_core::prompt::{self, MasterPrompt};
+use ralph_core::redact::Redactor;
+use ralph_core::run_state::RunState;
+use ralph_core::schedule::StartAt;
+use ralph_core::scheduler;
+use ralph_core::stats::{self, IterationRecord, Stats};
+use ralph_core::test_writer;
+use ralph_core::toc;
+use ralph_core::verify;
+use ralph_core::workspace::{self, PrdProgress, PrdState, WorkspaceRun};
+
+/// Result of attempting to run Claude
+enum ClaudeResult {
+    /// Successfully got structured output
+    Success(BuildIterationOutput),
+    /// Claude reported an error in the response
+    ClaudeError(String),
+    /// Transient error that should be retried (API 500, empty output, etc.)
+    TransientError(String),
+    /// Parse error or other non-retryable failure
+    ParseError(String),
+    /// User interrupted the process
+    Interrupted,
+    /// User aborted just this loop (Ctrl+X)
+    Aborted,
+    /// Claude exceeded the per-loop timeout and was killed
+    TimedOut(Duration),
+}
+
+impl From<Outcome> for ClaudeResult {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Success(result) => ClaudeResult::Success(result),
+            Outcome::ClaudeError(message) => ClaudeResult::ClaudeError(message),
+            Outcome::TransientError(message) => ClaudeResult::TransientError(message),
+            Outcome::ParseError(message) => ClaudeResult::ParseError(message),
+        }
+    }
+}
+
+/// Run Claude and wait for output, handling keyboard events. Also returns Claude's raw
+/// output, unless it was killed before finishing.
+/// Returns the result of the Claude invocation
+fn run_claude_iteration<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    prompt: &str,
+    options: &iteration::Options,
+    timeout: Option<Duration>,
+) -> Result<(ClaudeResult, Option<RawOutput>), RalphError> {
+    let span = tracing::info_span!(
+        "claude",
+        model = options.model,
+        max_turns = options.max_turns,
+        exit_code = Empty,
+    );
+    runtime::block_on(async {
+        let mut child = iteration::launch(prompt, options).map_err(RalphError::Claude)?;
+        let mut output = process::Output::capture(&mut child);
+
+        let started = Instant::now();
+        let timed_out = runtime::sleep_or_forever(timeout);
+        tokio::pin!(timed_out);
+        let mut keys = keys(app);
+        let mut redraw = runtime::redraw_timer();
+
+        let status = loop {
+            let stopped = tokio::select! {
+                status = child.wait() => break status.map_err(RalphError::Claude)?,
+                () = &mut timed_out => Some(ClaudeResult::TimedOut(timeout.unwrap_or_default())),
+                input = next_input(&mut keys) => handle_claude_input(app, input?),
+                _ = redraw.tick() => {
+                    app.advance_spinner();
+                    None
+                }
+            };
+            if let Some(result) = stopped {
+                process::kill_tree(&mut child).map_err(RalphError::Claude)?;
+                // Reap the killed process so it doesn't linger as a zombie
+                let _ = child.wait().await;
+                return Ok((result, None));
+            }
+            terminal.draw(|f| app.draw(f))?;
+        };
+
+        tracing::Span::current().record("exit_code", status.code());
+        let (stdout, stderr) = output.collect().await;
+        let raw = RawOutput { stdout, stderr };
+        tracing::debug!(
+            %status,
+            secs = started.elapsed().as_secs_f64(),
+            stderr = app.redactor.redact(raw.stderr.trim()),
+            "Claude exited"
+        );
+        tracing::trace!(stdout = app.redactor.redact(&raw.stdout), "Claude output");
+        // The keys are the repair's to read while it runs
+        drop(keys);
+        let left = timeout.map(|limit| limit.saturating_sub(started.elapsed()));
+        let result = match parse_claude_output(terminal, app, &raw.stdout, &raw.stderr, left).await? {
+            // The loop's limit, not what was left of it for the repair
+            ClaudeResult::TimedOut(_) => ClaudeResult::TimedOut(timeout.unwrap_or_default()),
+            result => result,
+        };
+        Ok((result, Some(raw)))
+    }
+    .instrument(span))
+}
+
+/// Act on a key or mouse event while Claude works. A click on a footer shortcut counts as
+/// pressing its key.
+fn handle_claude_input(app: &mut App, input: Input) -> Option<ClaudeResult> {
+    match input {
+        Input::Key(key) => handle_claude_key(app, key),
+        Input::Mouse(mouse) => app
+            .handle_mouse(mouse)
+            .and_then(|key| handle_claude_key(app, key)),
+    }
+}
+
+/// Act on a key pressed while Claude works. Returns the loop's result if the key stops
+/// Claude: Ctrl+C ends the run, Ctrl+X just this loop.
+fn handle_claude_key(app: &mut App, key: KeyEvent) -> Option<ClaudeResult> {
+    // Typing a new run label takes every key until enter/esc
+    if let Some(input) = app.label_input.as_mut()
+        && !key.modifiers.contains(KeyModifiers::CONTROL)
+    {
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => app.finish_renaming(true),
+            KeyCode::Esc => app.finish_renaming(false),
+            _ => {}
+        }
+        return None;
+    }
+    // So does the path to save the log to
+    if let Some(input) = app.export_input.as_mut()
+        && !key.modifiers.contains(KeyModifiers::CONTROL)
+    {
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => app.finish_export(true),
+            KeyCode::Esc => app.finish_export(false),
+            _ => {}
+        }
+        return None;
+    }
+    // So does the note explaining a rating
+    if let Some(input) = app.rating_input.as_mut()
+        && !key.modifiers.contains(KeyModifiers::CONTROL)
+    {
+        match key.code {
+            // The score comes first
+            KeyCode::Char(c @ '1'..='5') if input.rating == 0 => input.rating = c as u8 - b'0',
+            KeyCode::Esc if input.rating == 0 => app.rating_input = None,
+            _ if input.rating == 0 => {}
+            KeyCode::Char(c) => input.note.push(c),
+            KeyCode::Backspace => {
+                input.note.pop();
+            }
+            KeyCode::Enter => app.finish_rating(true),
+            KeyCode::Esc => app.finish_rating(false),
+            _ => {}
+        }
+        return None;
+    }
+    // Any key closes the help overlay
+    if app.show_help && !key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.show_help = false;
+        return None;
+    }
+    let key = if app.toc.searching {
+        key
+    } else {
+        app.vim_key(key)
+    };
+    // The task list takes its own keys while open (search, scroll, close)
+    if app.tab == Tab::Logs
+        && app.toc.visible
+        && !key.modifiers.contains(KeyModifiers::CONTROL)
+        && app.toc.handle_key(key.code)
+    {
+        return None;
+    }
+    match (key.code, key.modifiers) {
+        // Ctrl+C: kill Claude and quit immediately
+        (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
+            app.should_quit = true;
+            app.set_status("Interrupted by user");
+            return Some(ClaudeResult::Interrupted);
+        }
+        // Ctrl+X: kill Claude and abandon only this loop
+        (KeyCode::Char('x'), m) if m.contains(KeyModifiers::CONTROL) => {
+            app.set_status("Loop aborted");
+            return Some(ClaudeResult::Aborted);
+        }
+        // q/Q: quit after Claude finishes
+        (KeyCode::Char('q') | KeyCode::Char('Q'), _) => {
+            app.should_quit = true;
+            app.set_status("Will quit after Claude finishes this loop... (r=resume)");
+        }
+        // r/R: resume (cancel quit)
+        (KeyCode::Char('r') | KeyCode::Char('R'), _) => {
+            app.should_quit = false;
+            app.set_status("Resumed. Waiting for Claude...");
+        }
+        // l/L: rename the run
+        (KeyCode::Char('l') | KeyCode::Char('L'), _) => {
+            app.start_renaming();
+        }
+        // e/E: save the log shown to a file
+        (KeyCode::Char('e') | KeyCode::Char('E'), _) => {
+            let started = app.start_export();
+            if !started {
+                app.set_status("Nothing to export yet");
+            }
+        }
+        // s/S: rate the last finished loop's work 1-5
+        (KeyCode::Char('s') | KeyCode::Char('S'), _) => {
+            let started = app.ask_rating();
+            if !started {
+                app.set_status("Nothing to rate until a loop has finished");
+            }
+        }
+        // 1-4: switch tabs
+        (KeyCode::Char(c @ '1'..='4'), _) => {
+            app.tab = Tab::from_key(c).unwrap_or_default();
+        }
+        // t/T: show the task list in estimated order
+        (KeyCode::Char('t') | KeyCode::Char('T'), _) => {
+            app.tab = Tab::Logs;
+            app.toc.visible = true;
+        }
+        // ?: list every key
+        (KeyCode::Char('?'), _) => {
+            app.show_help = true;
+        }
+        // d/D: switch the log panel between the logs and what each loop committed
+        (KeyCode::Char('d') | KeyCode::Char('D'), _) => {
+            app.tab = Tab::Logs;
+            app.toggle_diff();
+        }
+        // Left/Right: navigate between iteration logs
+        (KeyCode::Left, _) => {
+            app.prev_log();
+        }
+        (KeyCode::Right, _) => {
+            app.next_log();
+        }
+        // Up/Down: scroll within current log
+        (KeyCode::Up, _) => {
+            app.scroll_up(1);
+        }
+        (KeyCode::Down, _) => {
+            app.scroll_down(1);
+        }
+        (KeyCode::PageUp, _) => {
+            app.scroll_up(10);
+        }
+        (KeyCode::PageDown, _) => {
+            app.scroll_down(10);
+        }
+        (KeyCode::Home, _) => {
+            app.scroll_to_top();
+        }
+        (KeyCode::End, _) => {
+            app.scroll_to_bottom();
+        }
+        _ => {}
+    }
+    None
+}
+
+/// Interpret a finished Claude invocation's output, recording its cost in `app`
+async fn parse_claude_output<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    stdout: &str,
+    stderr: &str,
+    timeout: Option<Duration>,
+) -> Result<ClaudeResult, RalphError> {
+    let report = match parse_and_repair(terminal, app, stdout, stderr, timeout).await? {
+        Ok(report) => report,
+        Err(stopped) => return Ok(stopped),
+    };
+    if let Some(spend) = report.spend {
+        app.record_usage(spend.cost_usd, &spend.usage);
+        app.last_model = spend.model;
+    }
+    Ok(report.outcome.into())
+}
+
+/// `iteration::parse_output`, with output it can't read repaired by the `[normalize]` model
+/// unless that is disabled. The repair waits like a loop does: the TUI keeps drawing and
+/// taking keys, and it's stopped after `timeout`. Its cost is recorded in `app`, and the log
+/// records whether it worked. A key or the timeout stopping the repair comes back as `Err`.
+async fn parse_and_repair<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    stdout: &str,
+    stderr: &str,
+    timeout: Option<Duration>,
+) -> Result<Result<iteration::Report, ClaudeResult>, RalphError> {
+    let mut report = iteration::parse_output(stdout, stderr);
+    let Outcome::ParseError(message) = &report.outcome else {
+        return Ok(Ok(report));
+    };
+    let Some(model) = app.normalize_model.clone() else {
+        return Ok(Ok(report));
+    };
+    app.set_status(&format!("Repairing Claude's output with {}...", model));
+    let mut child = iteration::launch_repair(stdout, &model).map_err(RalphError::Claude)?;
+    let mut output = process::Output::capture(&mut child);
+    let timed_out = runtime::sleep_or_forever(timeout);
+    tokio::pin!(timed_out);
+    let mut keys = keys(app);
+    let mut redraw = runtime::redraw_timer();
+
+    loop {
+        let stopped = tokio::select! {
+            status = child.wait() => {
+                status.map_err(RalphError::Claude)?;
+                break;
+            }
+            () = &mut timed_out => Some(ClaudeResult::TimedOut(timeout.unwrap_or_default())),
+            input = next_input(&mut keys) => handle_claude_input(app, input?),
+            _ = redraw.tick() => {
+                app.advance_spinner();
+                None
+            }
+        };
+        if let Some(result) = stopped {
+            process::kill_tree(&mut child).map_err(RalphError::Claude)?;
+            let _ = child.wait().await;
+            if matches!(result, ClaudeResult::TimedOut(_)) {
+                app.push_log(format!(
+                    "The loop timed out while {} was repairing Claude's output, so it was killed",
+                    model
+                ));
+            }
+            return Ok(Err(result));
+        }
+        terminal.draw(|f| app.draw(f))?;
+    }
+
+    let (repair_stdout, repair_stderr) = output.collect().await;
+    let repaired = iteration::parse_output(&repair_stdout, &repair_stderr);
+    if let Some(spend) = &repaired.spend {
+        app.record_usage(spend.cost_usd, &spend.usage);
+    }
+    match repaired.outcome {
+        Outcome::Success(result) => {
+            app.push_log(format!(
+                "Repaired Claude's output with {} after: {}",
+                model,
+                message.lines().next().unwrap_or_default()
+            ));
+            report.outcome = Outcome::Success(result);
+        }
+        Outcome::ClaudeError(e) | Outcome::TransientError(e) | Outcome::ParseError(e) => app
+            .push_log(format!(
+                "{} could not repair Claude's output: {}",
+                model,
+                app.redactor.redact(&e)
+            )),
+    }
+    Ok(Ok(report))
+}
+
+/// Execute tasks from an existing PRD file
+#[derive(Args, Debug)]
+pub struct BuildArgs {
+    /// Path to the PRD JSON file. Without one, ralph looks for plans/prd.json, prd.json and
+    /// .ralph/prd.json, and asks which to build if it finds several
+    #[arg(value_name = "PRD", conflicts_with_all = ["prd_path", "all", "prds"])]
+    pub path: Option<String>,
+
+    /// Path to the PRD JSON file, as an option
+    #[arg(short, long, value_name = "PRD", conflicts_with_all = ["all", "prds"])]
+    pub prd_path: Option<String>,
+
+    /// Build every PRD in plans/, one after another, stopping at the first that ends with
+    /// tasks left
+    #[arg(long, conflicts_with = "prds")]
+    pub all: bool,
+
+    /// Build these PRDs one after another (names are looked up in plans/ too), e.g.
+    /// --prd auth.json,billing.json
+    #[arg(long = "prd", value_name = "PRDS", value_delimiter = ',')]
+    pub prds: Vec<String>,
+
+    /// Maximum number of loops to run
+    #[arg(short = 'l', long)]
+    pub max_loops: Option<u64>,
+
+    /// Maximum agentic turns per Claude session (prevents hung sessions)
+    #[arg(short = 't', long)]
+    pub max_turns: Option<u32>,
+
+    /// Stop the run once cumulative Claude spend reaches this many USD
+    #[arg(long, value_name = "USD")]
+    pub max_cost: Option<f64>,
+
+    /// Kill a Claude invocation that runs longer than this many minutes
+    #[arg(long, value_name = "MINUTES")]
+    pub loop_timeout: Option<u64>,
+
+    /// Wait until this local time before starting, e.g. 22:30 (the next one) or
+    /// 2024-06-01T02:00
+    #[arg(long, value_name = "TIME")]
+    pub start_at: Option<StartAt>,
+
+    /// Don't run the PRD's quality gates after each iteration
+    #[arg(long)]
+    pub no_gates: bool,
+
+    /// Run the quality gates at the same time instead of one after another
+    #[arg(long)]
+    pub parallel_gates: bool,
+
+    /// Commit to a dedicated branch (created if missing; defaults to ralph/<prd-name>)
+    #[arg(long, value_name = "NAME", num_args = 0..=1, default_missing_value = "")]
+    pub branch: Option<String>,
+
+    /// Completion detectors that end the run (any one firing is enough)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "DETECTOR",
+        value_delimiter = ',',
+        default_value = "structured"
+    )]
+    pub complete_when: Vec<Detector>,
+
+    /// Text Claude prints when the PRD is done (enables the marker detector)
+    #[arg(long, value_name = "TEXT")]
+    pub exit_clause: Option<String>,
+
+    /// Show the next task and prompt and validate the setup, without launching Claude
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Work only on this PRD task (1-indexed) and stop once it's completed or blocked
+    #[arg(long, value_name = "N")]
+    pub task: Option<usize>,
+
+    /// Name for this run, shown in the TUI and saved with the run state
+    #[arg(long, value_name = "TEXT")]
+    pub label: Option<String>,
+
+    /// With --task: run exactly one iteration, even if the task passes, hiding other tasks
+    #[arg(long, requires = "task")]
+    pub once: bool,
+
+    /// Only work on tasks in these categories (comma-separated, e.g. feature,test)
+    #[arg(long, value_name = "CATEGORY", value_delimiter = ',')]
+    pub category: Vec<String>,
+
+    /// Send notifications, e.g. desktop or desktop=errors-only (levels: all, errors-only,
+    /// completion-only, attention-needed, off)
+    #[arg(long, value_name = "CHANNEL[=LEVEL]", value_delimiter = ',')]
+    pub notify: Vec<notify::Preference>,
+
+    /// Slack incoming webhook URL for --notify slack [default: $RALPH_SLACK_WEBHOOK]
+    #[arg(long, value_name = "URL")]
+    pub slack_webhook: Option<String>,
+
+    /// Have a second model independently verify each completed task before it's archived
+    #[arg(long, value_name = "MODEL", num_args = 0..=1, default_missing_value = verify::DEFAULT_MODEL)]
+    pub verify: Option<String>,
+
+    /// Have a reviewer agent read each completed task's diff for bugs, missing tests and the
+    /// like; requested changes send the task back for a fix loop
+    #[arg(long, value_name = "MODEL", num_args = 0..=1, default_missing_value = code_review::DEFAULT_MODEL)]
+    pub code_review: Option<String>,
+
+    /// After each accepted task, run a separate Claude session that only writes tests for the
+    /// files it changed, and commits them on its own (skipped for test and docs tasks)
+    #[arg(long)]
+    pub with_tests: bool,
+
+    /// When the quality gates fail, run up to N sessions that only fix the failures, with the
+    /// gate output in the prompt, before moving on; still failing marks the task blocked (1-10)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(1..=gate_fix::MAX_ATTEMPTS as i64),
+        conflicts_with = "no_gates"
+    )]
+    pub fix_gates: Option<u8>,
+
+    /// Show each completed task's diff and wait for you to approve it; rejecting reverts the
+    /// loop's commits and tells the next loop why (needs the TUI and a git repository)
+    #[arg(long)]
+    pub review: bool,
+
+    /// Run up to N workers at once on independent tasks, each Claude in its own git work
+    /// tree, then merge their branches one by one (2-8; needs a git repository)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(2..=scheduler::MAX_WORKERS as i64),
+        conflicts_with_all = ["task", "verify", "code_review", "with_tests", "review"]
+    )]
+    pub parallel: Option<u8>,
+
+    /// Tell Claude how you rated earlier loops (keys 1-5 in the TUI) on similar tasks
+    #[arg(long)]
+    pub rating_feedback: bool,
+
+    /// Extended thinking for every loop (off, standard, extended); a task's own `thinking`
+    /// field overrides it
+    #[arg(long, value_enum, value_name = "LEVEL")]
+    pub thinking: Option<Thinking>,
+
+    /// MCP server config (a JSON file or string) for Claude in every loop; repeatable, and
+    /// added to `[claude] mcp_config` in .ralph.toml
+    #[arg(long, value_name = "CONFIG")]
+    pub mcp_config: Vec<String>,
+
+    /// Extra directory Claude may read and edit in every loop; repeatable, and added to
+    /// `[claude] add_dirs` in .ralph.toml
+    #[arg(long, value_name = "DIR")]
+    pub add_dir: Vec<String>,
+
+    /// Claude settings file or JSON string for every loop, overriding `[claude] settings`
+    /// in .ralph.toml
+    #[arg(long, value_name = "FILE_OR_JSON")]
+    pub settings: Option<String>,
+
+    /// Tool Claude may use in every loop, e.g. "Bash(cargo:*)"; repeatable, added to
+    /// `[claude] allowed_tools`. With any allowed tools, loops stop bypassing permissions
+    /// and unlisted tools are refused
+    #[arg(long, value_name = "TOOL")]
+    pub allowed_tool: Vec<String>,
+
+    /// Tool Claude may never use in a loop, e.g. WebSearch; repeatable, added to
+    /// `[claude] disallowed_tools`
+    #[arg(long, value_name = "TOOL")]
+    pub disallowed_tool: Vec<String>,
+
+    /// Escalate a task that fails this many loops in a row (default 2), one step of
+    /// --escalate-steps per further failure
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "2")]
+    pub escalate_after: Option<u32>,
+
+    /// Stronger model the `model` escalation step retries with
+    #[arg(long, value_name = "MODEL", default_value = escalation::DEFAULT_MODEL)]
+    pub escalate_model: String,
+
+    /// What to do after Ctrl+X aborts a loop: start the next one, or pause until you press Enter
+    #[arg(long, value_enum, value_name = "ACTION", default_value = "next")]
+    pub after_abort: AfterAbort,
+
+    /// Run without the TUI, printing timestamped log lines instead (automatic when stdout
+    /// isn't a terminal, e.g. in CI or piped to a file)
+    #[arg(long)]
+    pub no_tui: bool,
+
+    /// How to report the run: text (the TUI, or log lines when headless) or jsonl (one JSON
+    /// event per line; on stdout it replaces the TUI)
+    #[arg(long, value_enum, value_name = "FORMAT", default_value = "text")]
+    pub output: OutputFormat,
+
+    /// Write --output jsonl events to this file instead of stdout, keeping the TUI
+    #[arg(long, value_name = "PATH")]
+    pub output_file: Option<String>,
+
+    /// Same as --output jsonl
+    #[arg(long)]
+    pub events: bool,
+
+    /// Escalation steps in order: model, history (failure history in the prompt), pause
+    /// (wait for your guidance)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "STEP",
+        value_delimiter = ',',
+        default_value = "model,history,pause"
+    )]
+    pub escalate_steps: Vec<escalation::Step>,
+}
+
+pub static COMMAND: Registered<BuildArgs> = Registered::new("build", run_args);
+
+fn run_args(args: BuildArgs) -> CommandResult {
+    let output = if args.events {
+        OutputFormat::Jsonl
+    } else {
+        args.output
+    };
+    let opts = BuildOptions {
+        max_loops: args.max_loops.unwrap_or(u64::MAX),
+        max_turns: args.max_turns,
+        max_cost: args.max_cost,
+        loop_timeout: args.loop_timeout.map(|m| Duration::from_secs(m * 60)),
+        start_at: args.start_at,
+        skip_gates: args.no_gates,
+        parallel_gates: args.parallel_gates,
+        branch: args.branch,
+        completion: CompletionCriteria::new(args.complete_when, args.exit_clause),
+        dry_run: args.dry_run,
+        task: args.task,
+        categories: args.category,
+        label: args.label,
+        once: args.once,
+        notify: args.notify,
+        slack_webhook: args.slack_webhook.or_else(|| {
+            std::env::var(notify::SLACK_WEBHOOK_ENV)
+                .ok()
+                .filter(|url| !url.is_empty())
+        }),
+        verify: args.verify,
+        code_review: args.code_review,
+        review: args.review,
+        with_tests: args.with_tests,
+        fix_gates: args.fix_gates.unwrap_or(0),
+        parallel: args.parallel.map_or(1, usize::from),
+        rating_feedback: args.rating_feedback,
+        thinking: args.thinking,
+        mcp_config: args.mcp_config,
+        add_dirs: args.add_dir,
+        settings: args.settings,
+        allowed_tools: args.allowed_tool,
+        disallowed_tools: args.disallowed_tool,
+        escalation: args.escalate_after.map(|after| escalation::Policy {
+            after,
+            model: args.escalate_model,
+            steps: args.escalate_steps,
+        }),
+        after_abort: args.after_abort,
+        headless: args.no_tui
+            || (output == OutputFormat::Jsonl && args.output_file.is_none())
+            || !tui::is_interactive(),
+        output,
+        output_file: args.output_file,
+    };
+    if args.all || !args.prds.is_empty() {
+        let prd_paths = workspace_prds(args.all, &args.prds)?;
+        build_workspace(&prd_paths, &opts)?;
+        return Ok(());
+    }
+    let prd_path = match args.path.or(args.prd_path) {
+        Some(prd_path) => prd_path,
+        None => discover_prd()?,
+    };
+    run(&prd_path, &opts)?;
+    Ok(())
+}
+
+/// The PRDs of a workspace build: every one in plans/ for --all, or the ones --prd lists
+fn workspace_prds(all: bool, names: &[String]) -> Result<Vec<String>, RalphError> {
+    let root = Path::new(".");
+    let prd_paths = if all {
+        let (prds, skipped) = workspace::discover(Path::new(workspace::PLANS_DIR));
+        for e in skipped {
+            eprintln!("Warning: skipping a file that isn't a PRD: {}", e);
+        }
+        prds
+    } else {
+        names
+            .iter()
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| workspace::resolve(root, name.trim()))
+            .collect()
+    };
+    if prd_paths.is_empty() {
+        return Err(RalphError::NoWorkspacePrds);
+    }
+    Ok(prd_paths)
+}
+
+/// Build each PRD in turn, recording how far each got in .ralph/workspace.json, and print a
+/// combined summary. A PRD whose run fails or ends with tasks left (quit, budget, max loops)
+/// stops the workspace there, since later PRDs may build on it; a dry run goes through them
+/// all. `--start-at` is waited for once, and `--max-loops` and `--max-cost` are shared: each
+/// PRD's run gets what the ones before it left. The first failure is returned once the
+/// summary is out.
+pub fn build_workspace(prd_paths: &[String], opts: &BuildOptions) -> Result<(), RalphError> {
+    let root = Path::new(".");
+    // Events on stdout leave it no room for messages meant for people
+    let quiet = opts.output == OutputFormat::Jsonl && opts.output_file.is_none();
+    if !opts.dry_run {
+        wait_for_workspace_start(opts.start_at, quiet)?;
+    }
+    let mut run = WorkspaceRun::new(prd_paths);
+    let started_at = run.started_at;
+    let mut failure = None;
+    let mut out_of_budget = None;
+
+    for (i, prd_path) in prd_paths.iter().enumerate() {
+        let result = match prd::read_prd(prd_path) {
+            Ok(prd) if prd.pending_count() > 0 => {
+                let (loops, cost) = run.totals();
+                let prd_opts = BuildOptions {
+                    max_loops: opts.max_loops.saturating_sub(loops),
+                    max_cost: opts.max_cost.map(|max| max - cost),
+                    start_at: if opts.dry_run { opts.start_at } else { None },
+                    ..opts.clone()
+                };
+                if prd_opts.max_loops == 0 {
+                    out_of_budget = Some("--max-loops");
+                    break;
+                }
+                if prd_opts.max_cost.is_some_and(|left| left <= 0.0) {
+                    out_of_budget = Some("--max-cost");
+                    break;
+                }
+                if !quiet {
+                    println!("==> {} ({} of {})", prd_path, i + 1, prd_paths.len());
+                }
+                self::run(prd_path, &prd_opts)
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        };
+        let Some(entry) = run.prd_mut(prd_path) else {
+            continue;
+        };
+        let stop = match result {
+            Ok(()) => {
+                record_workspace_prd(entry, started_at);
+                entry.state != PrdState::Complete
+            }
+            Err(e) => {
+                entry.state = PrdState::Failed;
+                entry.error = Some(e.to_string());
+                failure.get_or_insert(e);
+                true
+            }
+        };
+        save_workspace(&run, root);
+        if stop && !opts.dry_run {
+            break;
+        }
+    }
+
+    save_workspace(&run, root);
+    let mut summary = workspace_summary(&run);
+    if let Some(limit) = out_of_budget {
+        summary.push_str(&format!(
+            "\nStopped: the workspace's {} budget is used up",
+            limit
+        ));
+    }
+    if quiet {
+        eprintln!("{}", summary);
+    } else {
+        println!("{}", summary);
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Wait for `--start-at` once for the whole workspace, rather than in each PRD's run. A
+/// date and time already past is a mistake, as it is for a single PRD.
+fn wait_for_workspace_start(start_at: Option<StartAt>, quiet: bool) -> Result<(), RalphError> {
+    let Some(start_at) = start_at else {
+        return Ok(());
+    };
+    let Some((wait, at)) = time_until(start_at) else {
+        return Err(RalphError::Usage(
+            "the --start-at time has already passed".to_string(),
+        ));
+    };
+    if !quiet {
+        println!(
+            "Waiting to start at {} (in {})",
+            at.format("%Y-%m-%d %H:%M:%S"),
+            format_countdown(wait.as_secs())
+        );
+    }
+    std::thread::sleep(wait);
+    Ok(())
+}
+
+/// Fill in a PRD's entry once its build is over: its tasks from the PRD file, and its loops
+/// and cost from the run state and stats of a run saved since the workspace build began
+fn record_workspace_prd(entry: &mut PrdProgress, since: chrono::DateTime<chrono::Utc>) {
+    match prd::read_prd(&entry.prd_path) {
+        Ok(prd) => {
+            entry.total = prd.tasks.len();
+            entry.passing = entry.total - prd.pending_count();
+            entry.state = if prd.pending_count() == 0 {
+                PrdState::Complete
+            } else {
+                PrdState::Incomplete
+            };
+        }
+        Err(e) => {
+            entry.state = PrdState::Failed;
+            entry.error = Some(e.to_string());
+            return;
+        }
+    }
+    let Ok(Some(state)) = RunState::load(&entry.prd_path) else {
+        return;
+    };
+    if state.prd_path != entry.prd_path || state.updated_at < since {
+        return;
+    }
+    entry.loops = state.loop_count;
+    entry.cost_usd = Stats::load(&entry.prd_path)
+        .map(|stats| {
+            stats
+                .iterations
+                .iter()
+                .filter(|record| record.run_id == state.id)
+                .map(|record| record.cost_usd)
+                .sum()
+        })
+        .unwrap_or(0.0);
+    entry.run_id = Some(state.id);
+}
+
+fn save_workspace(run: &WorkspaceRun, root: &Path) {
+    if let Err(e) = run.save(root) {
+        eprintln!("Warning: couldn't save {}: {}", workspace::FILE, e);
+    }
+}
+
+/// The combined summary of a workspace build: each PRD's state and tasks, then the totals
+fn workspace_summary(run: &WorkspaceRun) -> String {
+    let complete = run
+        .prds
+        .iter()
+        .filter(|p| p.state == PrdState::Complete)
+        .count();
+    let (loops, cost) = run.totals();
+    let mut lines = vec![format!(
+        "Workspace: {} of {} PRD(s) complete, {} loop(s), ${:.2}",
+        complete,
+        run.prds.len(),
+        loops,
+        cost
+    )];
+    for p in &run.prds {
+        lines.push(match p.state {
+            PrdState::Pending => format!("  - {}: not started", p.prd_path),
+            PrdState::Failed => format!(
+                "  ✗ {}: failed: {}",
+                p.prd_path,
+                p.error.as_deref().unwrap_or("unknown error")
+            ),
+            state => format!(
+                "  {} {}: {}, {}/{} task(s) passing, {} loop(s), ${:.2}",
+                if state == PrdState::Complete {
+                    "✓"
+                } else {
+                    "!"
+                },
+                p.prd_path,
+                state.name(),
+                p.passing,
+                p.total,
+                p.loops,
+                p.cost_usd
+            ),
+        });
+    }
+    lines.join("\n")
+}
+
+/// The PRD to build when none was given: the only one in the standard places, or the one
+/// the user picks if there are several
+fn discover_prd() -> Result<String, RalphError> {
+    let mut found = prd::discover(Path::new("."));
+    if found.len() < 2 {
+        return found.pop().ok_or(RalphError::NoPrd);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(RalphError::SeveralPrds(found));
+    }
+    println!("Found several PRDs:");
+    for (i, path) in found.iter().enumerate() {
+        println!("  {}) {}", i + 1, path);
+    }
+    print!("Which one should ralph build? [1] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match pick(&answer, found.len()) {
+        Some(index) => Ok(found.swap_remove(index)),
+        None => Err(RalphError::SeveralPrds(found)),
+    }
+}
+
+/// The 0-based index of the choice typed for a numbered list of `count`; blank picks the
+/// first
+fn pick(answer: &str, count: usize) -> Option<usize> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Some(0);
+    }
+    answer
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=count).contains(n))
+        .map(|n| n - 1)
+}
+
+/// What a build does once Ctrl+X has aborted a loop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum AfterAbort {
+    /// Start the next loop straight away
+    #[default]
+    Next,
+    /// Wait for Enter before the next loop (Esc or q stops the run)
+    Pause,
+}
+
+/// Options controlling a build run
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// Maximum number of loops to run
+    pub max_loops: u64,
+
+    /// Maximum agentic turns per Claude session
+    pub max_turns: Option<u32>,
+
+    /// Stop the run once cumulative spend reaches this many USD
+    pub max_cost: Option<f64>,
+
+    /// Kill a single Claude invocation that runs longer than this
+    pub loop_timeout: Option<Duration>,
+
+    /// Local time to wait for before the first loop
+    pub start_at: Option<StartAt>,
+
+    /// Don't run the PRD's quality gates natively after each iteration
+    pub skip_gates: bool,
+
+    /// Run the quality gates concurrently rather than in order
+    pub parallel_gates: bool,
+
+    /// Commit to a dedicated git branch; an empty name means one derived from the PRD
+    pub branch: Option<String>,
+
+    /// How to decide that the PRD is finished
+    pub completion: CompletionCriteria,
+
+    /// Validate and print the first loop's prompt without launching Claude
+    pub dry_run: bool,
+
+    /// Work only on this 1-indexed PRD task and stop once it's completed or blocked
+    pub task: Option<usize>,
+
+    /// Only tasks in these categories are eligible; empty means every task
+    pub categories: Vec<String>,
+
+    /// Human-readable name for the run
+    pub label: Option<String>,
+
+    /// Run one iteration of `task` (even if it passes) with the other tasks hidden
+    pub once: bool,
+
+    /// Notification channels and the events each one wants
+    pub notify: Vec<Preference>,
+
+    /// Incoming webhook the Slack channel posts to
+    pub slack_webhook: Option<String>,
+
+    /// Model for an independent pass/fail check of each completed task before it's archived
+    pub verify: Option<String>,
+
+    /// Model for a code review of each completed task before it's archived
+    pub code_review: Option<String>,
+
+    /// Wait for the user to approve each completed task's diff before it's archived
+    pub review: bool,
+
+    /// Have a separate session write tests for each accepted task
+    pub with_tests: bool,
+
+    /// Sessions given to fixing failed quality gates before the loop moves on; 0 for none
+    pub fix_gates: u8,
+
+    /// Most tasks worked on at once, each by a worker in its own git work tree; 1 is serial
+    pub parallel: usize,
+
+    /// Tell Claude how the user rated earlier loops on tasks like the ones left
+    pub rating_feedback: bool,
+
+    /// Extended thinking budget for loops whose task doesn't set its own; None leaves
+    /// Claude Code's default
+    pub thinking: Option<Thinking>,
+
+    /// MCP server configs passed to Claude, after any from .ralph.toml
+    pub mcp_config: Vec<String>,
+
+    /// Extra directories passed to Claude, after any from .ralph.toml
+    pub add_dirs: Vec<String>,
+
+    /// Claude settings, in place of any from .ralph.toml
+    pub settings: Option<String>,
+
+    /// The only tools Claude may use, after any from .ralph.toml
+    pub allowed_tools: Vec<String>,
+
+    /// Tools Claude may never use, after any from .ralph.toml
+    pub disallowed_tools: Vec<String>,
+
+    /// What to do when a task keeps failing; None falls back to `[escalate]` in
+    /// .ralph.toml, and without that never escalates
+    pub escalation: Option<escalation::Policy>,
+
+    /// What to do after the user aborts a loop with Ctrl+X
+    pub after_abort: AfterAbort,
+
+    /// Print plain log lines instead of drawing the TUI
+    pub headless: bool,
+
+    /// Report the run as text or as JSON Lines events (see `events`)
+    pub output: OutputFormat,
+
+    /// File for JSON Lines events; stdout when unset
+    pub output_file: Option<String>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            max_loops: u64::MAX,
+            max_turns: None,
+            max_cost: None,
+            loop_timeout: None,
+            start_at: None,
+            skip_gates: false,
+            parallel_gates: false,
+            branch: None,
+            completion: CompletionCriteria::default(),
+            dry_run: false,
+            task: None,
+            categories: Vec::new(),
+            label: None,
+            once: false,
+            notify: Vec::new(),
+            slack_webhook: None,
+            verify: None,
+            code_review: None,
+            review: false,
+            with_tests: false,
+            fix_gates: 0,
+            parallel: 1,
+            rating_feedback: false,
+            thinking: None,
+            mcp_config: Vec::new(),
+            add_dirs: Vec::new(),
+            settings: None,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+            escalation: None,
+            after_abort: AfterAbort::default(),
+            headless: false,
+            output: OutputFormat::default(),
+            output_file: None,
+        }
+    }
+}
+
+impl BuildOptions {
+    /// Loops the run may take: `--once` overrides `--max-loops`
+    fn loop_limit(&self) -> u64 {
+        if self.once { 1 } else { self.max_loops }
+    }
+}
+
+/// What one loop leaves behind for the next loop's prompt
+#[derive(Debug, Default)]
+struct LoopFeedback {
+    /// Failures from the last native gate run
+    gate_failures: Option<String>,
+
+    /// The last loop claimed a completed task without committing it
+    missing_commit: bool,
+
+    /// The last loop claimed a completed task but changed nothing at all
+    no_op: bool,
+
+    /// Why `--verify` rejected the last loop's completed task, as a prompt section
+    verify_failure: Option<String>,
+
+    /// The changes `--code-review` requested in the last loop's task, as a prompt section
+    code_review: Option<String>,
+
+    /// Why the user rejected the last loop's changes in `--review`, as a prompt section
+    review_rejection: Option<String>,
+
+    /// Number and category of a task the last loop picked outside `--category`
+    off_filter: Option<(i32, String)>,
+
+    /// Definition-of-done items that didn't hold when the last loop reported the PRD complete
+    unmet_done: Option<String>,
+
+    /// Every failure on a task escalation has reached the `history` step for
+    failure_history: Option<String>,
+
+    /// What the user typed when escalation paused the run, as a prompt section
+    guidance: Option<String>,
+
+    /// The latest entries of progress.jsonl, as a prompt section
+    recent_loops: Option<String>,
+}
+
+impl LoopFeedback {
+    /// Whether the last loop left work behind even if every task passes
+    fn work_left(&self) -> bool {
+        self.gate_failures.is_some()
+            || self.missing_commit
+            || self.no_op
+            || self.verify_failure.is_some()
+            || self.code_review.is_some()
+            || self.review_rejection.is_some()
+            || self.unmet_done.is_some()
+    }
+}
+
+/// Run the build command - executes PRD tasks in a loop
+pub fn run(prd_path: &str, opts: &BuildOptions) -> Result<(), RalphError> {
+    let max_turns = opts.max_turns.unwrap_or(DEFAULT_MAX_TURNS);
+    let prd = prd::read_prd(prd_path)?;
+    let completed = prd::load_completed_tasks_from_file(prd_path)?;
+    let remaining = prd.tasks.len();
+    let completed_count = completed.map_or(0, |t| t.len());
+    let work_dir = Path::new(".");
+    let events = match (opts.output, &opts.output_file) {
+        (OutputFormat::Text, _) => None,
+        (OutputFormat::Jsonl, None) => Some(EventLog::stdout()),
+        (OutputFormat::Jsonl, Some(path)) => {
+            Some(EventLog::create(path).map_err(|source| RalphError::Events {
+                path: path.clone(),
+                source,
+            })?)
+        }
+    };
+    // Events on stdout leave it no room for messages meant for people
+    let events_on_stdout = events.as_ref().is_some_and(EventLog::is_stdout);
+
+    // Pin --task by description: task numbers shift as finished tasks are archived
+    let focus_description = match opts.task {
+        None => None,
+        Some(n) => match n.checked_sub(1).and_then(|i| prd.tasks.get(i)) {
+            None => {
+                return Err(RalphError::Usage(format!(
+                    "--task {} is out of range: the PRD has {} task(s)",
+                    n,
+                    prd.tasks.len()
+                )));
+            }
+            Some(task) if !task.in_categories(&opts.categories) => {
+                return Err(RalphError::Usage(format!(
+                    "--task {} is a '{}' task, outside --category {}",
+                    n,
+                    task.category,
+                    opts.categories.join(",")
+                )));
+            }
+            Some(task) if task.passes && !opts.once => {
+                let message = format!(
+                    "Nothing to do: task #{} ({}) already passes.",
+                    n, task.description
+                );
+                finished_early(events.as_ref(), &prd, &message);
+                if !events_on_stdout {
+                    println!("{}", message);
+                }
+                return Ok(());
+            }
+            Some(task) => Some(task.description.clone()),
+        },
+    };
+
+    let config = config::load(work_dir)?;
+    let discord = config.notify.discord;
+    let redactor = Redactor::new(&config.redact.patterns)?;
+    let theme = config.theme.resolve()?;
+    let mut master_prompt = MasterPrompt::load(work_dir).map_err(RalphError::Prompt)?;
+    master_prompt.addenda = config.prompt.categories;
+    master_prompt.docs = prompt::find_agent_docs(work_dir, config.agent_docs.files());
+    let progress_config = config.progress;
+    // Flags add to the configured lists and replace its settings
+    let mut mcp_config = config.claude.mcp_config;
+    mcp_config.extend(opts.mcp_config.iter().cloned());
+    let mut add_dirs = config.claude.add_dirs;
+    add_dirs.extend(opts.add_dirs.iter().cloned());
+    let settings = opts.settings.clone().or(config.claude.settings);
+    let mut allowed_tools = config.claude.allowed_tools;
+    allowed_tools.extend(opts.allowed_tools.iter().cloned());
+    let mut disallowed_tools = config.claude.disallowed_tools;
+    disallowed_tools.extend(opts.disallowed_tools.iter().cloned());
+    // --escalate-after replaces the [escalate] table
+    let escalation_policy = opts.escalation.clone().or_else(|| config.escalate.policy());
+    // A configured Discord webhook is on by default; --notify discord=LEVEL overrides it
+    let mut preferences: Vec<Preference> = discord
+        .iter()
+        .map(|d| Preference {
+            channel: notify::Channel::Discord,
+            level: d.level.unwrap_or(notify::Level::All),
+        })
+        .collect();
+    preferences.extend(opts.notify.iter().copied());
+
+    let wanted = Notifier::new(&preferences);
+    if wanted.uses(notify::Channel::Slack) && opts.slack_webhook.is_none() {
+        return Err(RalphError::Usage(format!(
+            "--notify slack needs a webhook: pass --slack-webhook or set {}",
+            notify::SLACK_WEBHOOK_ENV
+        )));
+    }
+    if opts.review && opts.headless {
+        return Err(RalphError::Usage(
+            "--review shows each diff in the TUI, so it can't run headless (--no-tui, \
+--output jsonl on stdout, or no terminal)"
+                .to_string(),
+        ));
+    }
+    if opts.review && !git::is_repo(work_dir) {
+        return Err(RalphError::Usage(
+            "--review needs a git repository to show and revert each task's changes".to_string(),
+        ));
+    }
+    if opts.parallel > 1 && !git::is_repo(work_dir) {
+        return Err(RalphError::Usage(
+            "--parallel needs a git repository to give each worker its own work tree".to_string(),
+        ));
+    }
+    if wanted.uses(notify::Channel::Discord) && discord.is_none() {
+        return Err(RalphError::Usage(format!(
+            "--notify discord needs a webhook: add [notify.discord] with webhook = \"...\" to {}",
+            config::FILE_NAME
+        )));
+    }
+
+    if opts.dry_run {
+        let ok = dry_run(
+            prd_path,
+            &prd,
+            completed_count,
+            opts,
+            &master_prompt,
+            escalation_policy.as_ref(),
+        );
+        return if ok {
+            Ok(())
+        } else {
+            Err(RalphError::DryRunFailed)
+        };
+    }
+
+    // Don't spend an iteration asking Claude to find work that doesn't exist
+    if prd.pending_count() == 0 && !opts.once {
+        finished_early(
+            events.as_ref(),
+            &prd,
+            "Nothing to do: every task already passes.",
+        );
+        if !events_on_stdout {
+            report_nothing_to_do(prd_path, &prd);
+        }
+        return Ok(());
+    }
+    if eligible_pending(&prd, &opts.categories).is_empty() && !opts.once {
+        let message = format!(
+            "Nothing to do: no pending tasks in categories {}.",
+            opts.categories.join(", ")
+        );
+        finished_early(events.as_ref(), &prd, &message);
+        if !events_on_stdout {
+            println!("{}", message);
+        }
+        return Ok(());
+    }
+
+    // --start-at: a date and time already past is a mistake, not "start now"
+    let start = match opts.start_at.map(time_until) {
+        Some(None) => {
+            return Err(RalphError::Usage(
+                "the --start-at time has already passed".to_string(),
+            ));
+        }
+        Some(Some(start)) => Some(start),
+        None => None,
+    };
+
+    // Get onto the run's branch before anything can commit. A run a signal stopped is
+    // resumed, back on its branch unless --branch says otherwise.
+    let mut run_state = RunState::start(prd_path);
+    let resumed_loops = run_state.loop_count;
+    run_state.set_label(opts.label.as_deref());
+    if let Some(requested) = opts.branch.clone().or_else(|| run_state.branch.clone()) {
+        run_state.branch = Some(
+            switch_to_run_branch(work_dir, &requested, &prd.name).map_err(RalphError::Branch)?,
+        );
+    }
+
+    // SIGTERM and SIGHUP stop the loop like Ctrl+C, leaving a checkpoint to resume from
+    tui::defer_termination();
+    let mut terminal = tui::init_run_terminal(opts.headless)?;
+    let mut app = App::new(&prd.name, remaining, completed_count);
+    app.loop_count = resumed_loops;
+    app.headless = opts.headless;
+    app.events = events.clone();
+    app.redactor = redactor;
+    app.theme = theme;
+    app.vim_keys = config.tui.vim_keys;
+    app.normalize_model = config.normalize.model().map(str::to_string);
+    app.max_cost_usd = opts.max_cost;
+    app.branch = run_state.branch.clone();
+    app.label = run_state.label.clone();
+    app.run_id = Some(run_state.id.clone());
+    let mut notifier = Notifier::new(&preferences);
+    notifier.label = run_state.label.clone();
+    notifier.slack_webhook = opts.slack_webhook.clone();
+    if let Some(discord) = discord {
+        notifier.discord_webhook = Some(discord.webhook);
+        notifier.discord_ping = discord.ping;
+    }
+    if let Err(e) = run_state.save() {
+        app.push_log(format!("Warning: {}", e));
+    }
+    if resumed_loops > 0 {
+        app.push_log(format!(
+            "Resuming run {} after loop #{}, where it was stopped",
+            run_state.id, resumed_loops
+        ));
+    }
+    if let (Some(n), Some(description)) = (opts.task, &focus_description) {
+        app.push_log(format!("Working only on task #{}: {}", n, description));
+    }
+    if !opts.categories.is_empty() {
+        app.push_log(format!(
+            "Working only on categories: {}",
+            opts.categories.join(", ")
+        ));
+    }
+
+    if let Some((wait, at)) = start {
+        app.starts_at = Some(Instant::now() + wait);
+        app.set_status(&format!(
+            "Waiting to start at {} (Ctrl+C=cancel)",
+            at.format("%Y-%m-%d %H:%M:%S")
+        ));
+        terminal.draw(|f| app.draw(f))?;
+        wait_before_retry(&mut terminal, &mut app, wait)?;
+        app.starts_at = None;
+    }
+
+    let mut feedback = LoopFeedback::default();
+    let mut escalation = Tracker::default();
+    // Tasks reported blocked this run, which ralph doesn't pick again
+    let mut blocked_tasks: Vec<String> = Vec::new();
+    let track_commits = git::is_repo(work_dir);
+    // HEAD when the run started, to tell which files the run changed
+    let run_base = if track_commits {
+        git::head_commit(work_dir).ok()
+    } else {
+        None
+    };
+
+    while !app.should_quit
+        && tui::termination_signal().is_none()
+        && app.loop_count - resumed_loops < opts.loop_limit()
+    {
+        let prd = prd::read_prd(prd_path)?;
+        let completed = prd::load_completed_tasks_from_file(prd_path)?;
+        app.reload_progress(prd.tasks.len(), completed.map_or(0, |t| t.len()));
+        app.toc.set_entries(toc::build(&prd));
+
+        // Every task passes but no detector fired - only keep going if last loop left work
+        // A --once re-run goes ahead even if its task already passes
+        let work_left = feedback.work_left() || opts.once;
+        if prd.pending_count() == 0 && !work_left {
+            app.set_status("Nothing left to do - every PRD task passes");
+            break;
+        }
+        if eligible_pending(&prd, &opts.categories).is_empty() && !work_left {
+            app.set_status("Nothing left to do in the selected categories");
+            break;
+        }
+
+        // Where the --task target sits now; gone or passing means it's finished
+        let focus = match &focus_description {
+            None => None,
+            Some(description) => match find_task(&prd, description) {
+                Some((i, task)) if !task.passes || work_left => Some((i, task)),
+                _ => {
+                    app.set_status("Target task finished");
+                    break;
+                }
+            },
+        };
+
+        // --parallel: once two tasks can run at once, the orchestrator hands ready tasks to
+        // workers until none is left for it. Tasks a worker failed this run, and anything the
+        // last loop left to fix, get normal loops.
+        app.workers.clear();
+        if opts.parallel > 1
+            && !feedback.work_left()
+            && matches!(git::head_commit(work_dir), Ok(Some(_)))
+            && scheduler::pick(
+                &prd,
+                &opts.categories,
+                &[],
+                &parallel::failed_tasks(&run_state, &prd),
+                opts.parallel,
+            )
+            .len()
+                > 1
+        {
+            let loops_left = opts
+                .loop_limit()
+                .saturating_sub(app.loop_count - resumed_loops);
+            let span = tracing::info_span!("orchestrator", run_id = %run_state.id, workers = opts.parallel);
+            let _in_orchestrator = span.enter();
+            parallel::orchestrate(
+                &mut terminal,
+                &mut app,
+                &mut run_state,
+                prd_path,
+                &master_prompt,
+                &iteration::Options {
+                    max_turns,
+                    model: None,
+                    thinking: opts.thinking,
+                    mcp_config: &mcp_config,
+                    add_dirs: &add_dirs,
+                    settings: settings.as_deref(),
+                    allowed_tools: &allowed_tools,
+                    disallowed_tools: &disallowed_tools,
+                    cwd: None,
+                },
+                opts,
+                loops_left,
+            )?;
+            if app.budget_exceeded() {
+                app.set_status(&format!(
+                    "Budget exceeded: ${:.2} spent of ${:.2} limit",
+                    app.total_cost_usd,
+                    app.max_cost_usd.unwrap_or_default()
+                ));
+                notifier.notify(
+                    notify::Event::AttentionNeeded,
+                    &format!("Budget exceeded: ${:.2} spent", app.total_cost_usd),
+                );
+                app.should_quit = true;
+            }
+            terminal.draw(|f| app.draw(f))?;
+            continue;
+        }
+
+        app.increment_loop();
+        app.start_loop_timer();
+        let cost_before = app.total_cost_usd;
+        app.last_model = None;
+        run_state.record_loop(app.loop_count);
+        take_checkpoint(&mut app, &run_state, prd_path, work_dir);
+        compact_progress(&mut app, &progress_config, work_dir);
+        feedback.recent_loops = match progress::recent_journal(work_dir, progress::JOURNAL_TAIL) {
+            Ok(entries) => progress::journal_section(&entries),
+            Err(e) => {
+                app.push_log(format!(
+                    "Warning: can't read {}: {}",
+                    progress::JOURNAL_FILE,
+                    e
+                ));
+                None
+            }
+        };
+        // Parent of the loop's Claude, gate and git spans; filled in once the loop is over
+        let loop_span = tracing::info_span!(
+            "loop",
+            number = app.loop_count,
+            run_id = %run_state.id,
+            task = Empty,
+            status = Empty,
+            model = Empty,
+            cost_usd = Empty,
+        );
+        let _in_loop = loop_span.enter();
+        if let Err(e) = run_state.save() {
+            app.push_log(format!("Warning: {}", e));
+        }
+        app.set_status("Spawning Claude...");
+        terminal.draw(|f| app.draw(f))?;
+
+        let pending: Vec<&str> = prd
+            .tasks
+            .iter()
+            .filter(|t| !t.passes)
+            .map(|t| t.description.as_str())
+            .collect();
+        run_state.retain_pending_tasks(&pending);
+        // Category addenda and task workdirs only fit a loop whose task is known, so with
+        // either ralph picks the task itself, passing over tasks blocked earlier in the run
+        let picked = match focus {
+            None if !master_prompt.addenda.is_empty() || prd.has_task_workdirs() => {
+                next_task_except(&prd, &opts.categories, &blocked_tasks)
+            }
+            _ => None,
+        };
+        // A monorepo task runs Claude and the gates in its package, with the PRD's path
+        // rebased for a Claude running there
+        let workdir = loop_workdir(&mut app, &prd, focus.or(picked).map(|(_, t)| t), work_dir);
+        let loop_dir = workdir.as_deref().map(|dir| work_dir.join(dir));
+        let loop_master = workdir.as_deref().map(|dir| master_prompt.in_workdir(dir));
+        let mut prompt = build_iteration_prompt(
+            &workdir.as_deref().map_or_else(
+                || prd_path.to_string(),
+                |dir| prompt::path_from_workdir(dir, prd_path),
+            ),
+            &prd,
+            &run_state,
+            opts,
+            loop_master.as_ref().unwrap_or(&master_prompt),
+            focus.or(picked),
+            &feedback,
+        );
+        let mut loop_add_dirs = add_dirs.clone();
+        if let Some(dir) = &workdir {
+            prompt.push_str(&prompt::workdir_section(dir));
+            loop_add_dirs.push(prompt::root_from(dir));
+        }
+        feedback.off_filter = None;
+        feedback.guidance = None;
+
+        // Escalation in effect for this loop, for the run history
+        let policy = escalation_policy.as_ref();
+        let escalated = policy.and_then(|p| escalation.label(p));
+        let model = policy.and_then(|p| escalation.model(p));
+        // Thinking follows the task the loop is expected to pick
+        let thinking = focus
+            .or(picked)
+            .or_else(|| next_task(&prd, &opts.categories))
+            .and_then(|(_, task)| task.thinking)
+            .or(opts.thinking);
+        if let Some(events) = &events {
+            events.emit(&RunEvent::LoopStarted {
+                loop_number: app.loop_count,
+                task: focus
+                    .or(picked)
+                    .or_else(|| next_task(&prd, &opts.categories))
+                    .map(|(i, _)| i + 1),
+                model,
+                thinking: thinking.map(Thinking::name),
+            });
+        }
+        if let Some(steps) = &escalated {
+            app.push_log(format!(
+                "Escalated ({}): {}",
+                steps,
+                match model {
+                    Some(model) => format!("running this loop with {}", model),
+                    None => "running with the usual model".to_string(),
+                }
+            ));
+        }
+
+        let claude_options = iteration::Options {
+            max_turns,
+            model,
+            thinking,
+            mcp_config: &mcp_config,
+            add_dirs: &loop_add_dirs,
+            settings: settings.as_deref(),
+            allowed_tools: &allowed_tools,
+            disallowed_tools: &disallowed_tools,
+            cwd: loop_dir.as_deref(),
+        };
+
+        // HEAD before Claude runs; None if we can't tell (not a repo, git error)
+        let head_before = if track_commits {
+            git::head_commit(work_dir).ok()
+        } else {
+            None
+        };
+        // PRD contents before Claude runs, to tell a no-op iteration from uncommitted work
+        let prd_before = std::fs::read_to_string(prd_path).ok();
+
+        let mut iteration_succeeded = false;
+        let mut completion: Option<Detector> = None;
+        let mut task_completed = false;
+        // Task number and status reported by a successful iteration
+        let mut reported: Option<(i32, String)> = None;
+        let mut reported_summary: Option<String> = None;
+        let mut aborted = false;
+        // For the loop's log file: Claude's output and what the TUI logged from here on
+        let mut attempts: Vec<RawOutput> = Vec::new();
+        let mut parsed: Option<serde_json::Value> = None;
+        let log_start = app.iteration_logs.len();
+
+        // Retry loop for transient errors
+        let mut retry_count = 0;
+        loop {
+            if retry_count > 0 {
+                let delay = BASE_RETRY_DELAY_SECS * 2u64.pow(retry_count - 1);
+                app.set_status(&format!(
+                    "Retry {}/{} in {}s... (API error)",
+                    retry_count, MAX_RETRIES, delay
+                ));
+                terminal.draw(|f| app.draw(f))?;
+
+                wait_before_retry(&mut terminal, &mut app, Duration::from_secs(delay))?;
+
+                if app.should_quit {
+                    break;
+                }
+
+                app.set_status(&format!("Retrying ({}/{})...", retry_count, MAX_RETRIES));
+            } else {
+                app.set_status("Waiting for Claude... (q=quit, r=resume, Ctrl+C=kill)");
+            }
+            terminal.draw(|f| app.draw(f))?;
+            app.advance_spinner();
+
+            let (claude_result, raw) = run_claude_iteration(
+                &mut terminal,
+                &mut app,
+                &prompt,
+                &claude_options,
+                opts.loop_timeout,
+            )?;
+            // Secrets Claude echoed stay out of the loop log and everything the summary feeds
+            attempts.extend(raw.map(|raw| RawOutput {
+                stdout: app.redactor.redact(&raw.stdout),
+                stderr: app.redactor.redact(&raw.stderr),
+            }));
+            match claude_result {
+                ClaudeResult::Success(mut result) => {
+                    result.summary = app.redactor.redact(&result.summary);
+                    parsed = serde_json::to_value(&result).ok();
+                    // Format for display
+                    let display_log = format!(
+                        "Task #{}: {}\nStatus: {}\nSummary: {}",
+                        result.task_number,
+                        if result.prd_complete {
+                            "PRD COMPLETE"
+                        } else {
+                            ""
+                        },
+                        result.status,
+                        result.summary
+                    );
+                    app.push_log(display_log);
+                    if let Some(events) = &events {
+                        events.emit(&RunEvent::ClaudeResult {
+                            loop_number: app.loop_count,
+                            outcome: "success",
+                            task: Some(result.task_number),
+                            status: Some(&result.status),
+                            summary: Some(&result.summary),
+                            prd_complete: result.prd_complete,
+                            run_cost_usd: app.total_cost_usd,
+                        });
+                    }
+                    record_step_progress(&mut app, &mut run_state, &prd, &result);
+                    if let Err(e) = run_state.save() {
+                        app.push_log(format!("Warning: {}", e));
+                    }
+
+                    // PRD completion is confirmed after the quality gates below
+                    iteration_succeeded = true;
+                    completion = detect_completion(&opts.completion, prd_path, &result)?;
+                    if let Some(detector) = completion {
+                        app.push_log(format!("Completion detected: {}", detector.name()));
+                    }
+                    task_completed = result.status == "completed";
+                    if let Some(task) = usize::try_from(result.task_number - 1)
+                        .ok()
+                        .and_then(|i| prd.tasks.get(i))
+                        && !task.in_categories(&opts.categories)
+                    {
+                        app.push_log(format!(
+                            "Warning: task #{} is a '{}' task, outside --category {}. \
+The next loop will be told to stay within the filter.",
+                            result.task_number,
+                            task.category,
+                            opts.categories.join(",")
+                        ));
+                        feedback.off_filter = Some((result.task_number, task.category.clone()));
+                    }
+                    reported = Some((result.task_number, result.status.clone()));
+                    reported_summary = Some(result.summary.clone());
+                    let status_msg = format!("Task {} {}", result.task_number, result.status);
+                    app.set_status(&status_msg);
+                    break;
+                }
+                ClaudeResult::ClaudeError(output) => {
+                    app.push_log(format!(
+                        "Claude returned error\n\nRaw output:\n```\n{}\n```",
+                        output.trim_end()
+                    ));
+                    claude_failure_event(events.as_ref(), &app, "error");
+                    app.set_status("Error: Claude reported failure");
+                    notifier.notify(notify::Event::Error, "Claude reported a failure");
+                    break;
+                }
+                ClaudeResult::TransientError(msg) => {
+                    if app.budget_exceeded() {
+                        app.push_log(format!("Transient error (not retrying): {}", msg));
+                        break;
+                    }
+                    retry_count += 1;
+                    if retry_count > MAX_RETRIES {
+                        app.push_log(format!(
+                            "Failed after {} retries\n\nLast error: {}",
+                            MAX_RETRIES, msg
+                        ));
+                        app.set_status("Error: Max retries exceeded");
+                        notifier.notify(
+                            notify::Event::Error,
+                            &format!("Max retries exceeded: {}", msg),
+                        );
+                        break;
+                    }
+                    app.push_log(format!("Transient error (will retry): {}", msg));
+                    if let Some(events) = &events {
+                        events.emit(&RunEvent::Retry {
+                            loop_number: app.loop_count,
+                            attempt: retry_count,
+                            max_attempts: MAX_RETRIES,
+                            delay_secs: BASE_RETRY_DELAY_SECS * 2u64.pow(retry_count - 1),
+                            reason: &msg,
+                        });
+                    }
+                    // Continue to next iteration of retry loop
+                }
+                ClaudeResult::ParseError(msg) => {
+                    app.push_log(msg);
+                    claude_failure_event(events.as_ref(), &app, "parse_error");
+                    app.set_status("Warning: Failed to parse Claude output");
+                    notifier.notify(notify::Event::Error, "Failed to parse Claude output");
+                    break;
+                }
+                ClaudeResult::Interrupted => {
+                    // app.should_quit already set
+                    break;
+                }
+                ClaudeResult::Aborted => {
+                    app.push_log(format!(
+                        "Loop #{} aborted with Ctrl+X. Claude was killed and its work is left as is.",
+                        app.loop_count
+                    ));
+                    aborted = true;
+                    break;
+                }
+                ClaudeResult::TimedOut(limit) => {
+                    claude_failure_event(events.as_ref(), &app, "timed_out");
+                    // Don't retry the same hung invocation - the next loop starts fresh
+                    app.push_log(format!(
+                        "Loop timed out after {} and Claude was killed. Moving on to the next loop.",
+                        format_duration(limit)
+                    ));
+                    app.set_status("Warning: Loop timed out");
+                    notifier.notify(
+                        notify::Event::Error,
+                        &format!("Loop #{} timed out", app.loop_count),
+                    );
+                    break;
+                }
+            }
+        }
+
+        // Keep a label renamed in the TUI
+        if run_state.set_label(app.label.as_deref()) {
+            notifier.label = run_state.label.clone();
+            if let Err(e) = run_state.save() {
+                app.push_log(format!("Warning: {}", e));
+            }
+        }
+
+        let loop_checks = LoopChecks {
+            prd_path,
+            prd: &prd,
+            opts,
+            notifier: &notifier,
+            claude: claude_options,
+            work_dir,
+            head_before: head_before.as_ref(),
+        };
+
+        // Verify Claude's work ourselves rather than trusting its claim that gates passed
+        let (gates_passed, mut gates_interrupted) =
+            if iteration_succeeded && !opts.skip_gates && !prd.quality_gates.is_empty() {
+                loop_checks.gates(&mut terminal, &mut app, &mut feedback, reported.as_mut())?
+            } else {
+                (true, false)
+            };
+
+        // A "completed" task must leave a commit behind; otherwise Claude likely lost its work.
+        // With no uncommitted changes and an untouched PRD either, nothing was done at all.
+        feedback.missing_commit = false;
+        feedback.no_op = false;
+        if task_completed
+            && let Some(base) = &head_before
+            && git::commits_since(work_dir, base.as_deref()).is_ok_and(|c| c.is_empty())
+        {
+            app.suspicious_loops.push(app.loop_count);
+            let prd_unchanged = std::fs::read_to_string(prd_path).ok() == prd_before;
+            if prd_unchanged && git::has_uncommitted_changes(work_dir).is_ok_and(|dirty| !dirty) {
+                feedback.no_op = true;
+                if let Some((_, status)) = &mut reported {
+                    *status = "no-op".to_string();
+                }
+                app.push_log(format!(
+                    "No-op iteration: loop #{} reported a completed task but changed nothing. \
+The task still counts as pending and the next loop will be told no changes were detected.",
+                    app.loop_count
+                ));
+            } else {
+                feedback.missing_commit = true;
+                app.push_log(format!(
+                    "Suspicious iteration: loop #{} reported a completed task but made no git commit. \
+The next loop will be told to commit its work.",
+                    app.loop_count
+                ));
+            }
+        }
+
+        // --verify: an independent check before the task is archived to completed.json
+        feedback.verify_failure = None;
+        let mut accepted = false;
+        if let Some(model) = &opts.verify
+            && task_completed
+            && gates_passed
+            && !gates_interrupted
+            && !feedback.missing_commit
+            && !feedback.no_op
+            && let Some(report) = &mut reported
+            && let Some(task) = usize::try_from(report.0 - 1)
+                .ok()
+                .and_then(|i| prd.tasks.get(i))
+        {
+            accepted =
+                loop_checks.verify(&mut terminal, &mut app, &mut feedback, model, report, task)?;
+        } else if opts.verify.is_none()
+            && gates_passed
+            && !gates_interrupted
+            && !feedback.missing_commit
+            && !feedback.no_op
+        {
+            accepted = true;
+        }
+
+        // --code-review: a reviewer agent reads the diff; requested changes mean a fix loop
+        feedback.code_review = None;
+        if accepted
+            && let Some(model) = &opts.code_review
+            && task_completed
+            && let Some(report) = &mut reported
+            && let Some(task) = usize::try_from(report.0 - 1)
+                .ok()
+                .and_then(|i| prd.tasks.get(i))
+        {
+            accepted = loop_checks.code_review(
+                &mut terminal,
+                &mut app,
+                &mut feedback,
+                model,
+                report,
+                task,
+            )?;
+        }
+
+        // --review: the user approves the task's changes before they're accepted
+        feedback.review_rejection = None;
+        if accepted
+            && opts.review
+            && task_completed
+            && let Some(Some(base)) = &head_before
+            && let Some(report) = &mut reported
+            && let Some(task) = usize::try_from(report.0 - 1)
+                .ok()
+                .and_then(|i| prd.tasks.get(i))
+        {
+            accepted =
+                loop_checks.review(&mut terminal, &mut app, &mut feedback, base, report, task)?;
+        }
+        // --with-tests: a separate session writes tests for the accepted task
+        if accepted
+            && opts.with_tests
+            && task_completed
+            && !app.should_quit
+            && let Some((number, _)) = &reported
+            && let Some(task) = usize::try_from(*number - 1)
+                .ok()
+                .and_then(|i| prd.tasks.get(i))
+            && test_writer::wants_tests(task)
+        {
+            loop_checks.write_tests(&mut terminal, &mut app, *number, task)?;
+        }
+
+        if accepted {
+            // Ralph does the completed.json bookkeeping, once every check has accepted the task
+            archive_passing(&mut app, prd_path, work_dir);
+        }
+
+        // Ralph checks the PRD's definition of done itself before accepting completion
+        feedback.unmet_done = None;
+        if completion.is_some()
+            && gates_passed
+            && !gates_interrupted
+            && !feedback.work_left()
+            && !prd.definition_of_done.is_empty()
+        {
+            let changed_files = run_base
+                .as_ref()
+                .and_then(|base| git::files_changed_since(work_dir, base.as_deref()).ok());
+            let evidence = done::Evidence {
+                gates_passed: (!opts.skip_gates && !prd.quality_gates.is_empty())
+                    .then_some(gates_passed),
+                changed_files: changed_files.as_deref(),
+            };
+            // The check can't stop on an error, so one is held until it returns
+            let mut gate_error = None;
+            let results = done::check(&prd.definition_of_done, &evidence, |command| {
+                if app.should_quit || gate_error.is_some() {
+                    return gates::spawn_failure(command, &io::ErrorKind::Interrupted.into());
+                }
+                app.set_status(&format!("Checking definition of done: {}", command));
+                run_gate(&mut terminal, &mut app, command).unwrap_or_else(|e| {
+                    gate_error = Some(e);
+                    gates::spawn_failure(command, &io::ErrorKind::Interrupted.into())
+                })
+            });
+            if let Some(e) = gate_error {
+                return Err(e);
+            }
+            gates_interrupted = app.should_quit;
+            if !gates_interrupted {
+                app.push_log(format_done_log(&results));
+                feedback.unmet_done = done::unmet_report(&results);
+            }
+        }
+
+        if completion.is_some() && !gates_interrupted {
+            if feedback.verify_failure.is_some() {
+                app.set_status("PRD reported complete but verification failed - continuing");
+            } else if feedback.code_review.is_some() {
+                app.set_status(
+                    "PRD reported complete but code review requested changes - continuing",
+                );
+            } else if feedback.review_rejection.is_some() {
+                app.set_status("PRD reported complete but you rejected the last task - continuing");
+            } else if feedback.no_op {
+                app.set_status("PRD reported complete but no changes were detected - continuing");
+            } else if feedback.missing_commit {
+                app.set_status("PRD reported complete but nothing was committed - continuing");
+            } else if gates_passed && feedback.unmet_done.is_some() {
+                app.set_status(
+                    "PRD reported complete but the definition of done isn't met - continuing",
+                );
+            } else if gates_passed {
+                app.set_status("PRD Complete!");
+                notifier.notify(
+                    notify::Event::Completed,
+                    &format!("PRD {} is complete", prd.name),
+                );
+                app.should_quit = true;
+            } else {
+                app.set_status("PRD reported complete but quality gates failed - continuing");
+            }
+        } else if !gates_passed && !app.should_quit {
+            app.set_status("Quality gates failed - failures will be sent to the next loop");
+        } else if feedback.verify_failure.is_some() && !app.should_quit {
+            app.set_status("Verification failed - the task is pending again");
+        } else if feedback.code_review.is_some() && !app.should_quit {
+            app.set_status("Code review requested changes - the task is pending again");
+        } else if feedback.review_rejection.is_some() && !app.should_quit {
+            app.set_status("Rejected in review - the changes were reverted");
+        } else if feedback.no_op && !app.should_quit {
+            app.set_status("No-op: task reported completed but nothing changed");
+        } else if feedback.missing_commit && !app.should_quit {
+            app.set_status("Suspicious: task completed without a git commit");
+        }
+        if feedback.no_op {
+            notifier.notify(
+                notify::Event::AttentionNeeded,
+                &format!(
+                    "Loop #{} reported a completed task but changed nothing",
+                    app.loop_count
+                ),
+            );
+        } else if feedback.missing_commit {
+            notifier.notify(
+                notify::Event::AttentionNeeded,
+                &format!(
+                    "Loop #{} completed a task without a git commit",
+                    app.loop_count
+                ),
+            );
+        }
+
+        if let Some((number, status)) = &reported
+            && status == "blocked"
+            && let Some(task) = usize::try_from(number - 1)
+                .ok()
+                .and_then(|i| prd.tasks.get(i))
+        {
+            blocked_tasks.push(task.description.clone());
+        }
+
+        // --task: stop once the target is done (and verified) or stuck
+        if let (Some((index, _)), Some((number, status))) = (focus, &reported) {
+            let target = index as i32 + 1;
+            if *number != target {
+                app.push_log(format!(
+                    "Warning: asked to work on task #{} but Claude reported task #{}",
+                    target, number
+                ));
+            } else if status == "blocked" {
+                app.set_status(&format!("Task #{} is blocked - stopping", target));
+                notifier.notify(
+                    notify::Event::AttentionNeeded,
+                    &format!("Task #{} is blocked", target),
+                );
+                app.should_quit = true;
+            } else if status == "completed"
+                && gates_passed
+                && !feedback.missing_commit
+                && !gates_interrupted
+            {
+                app.set_status(&format!("Task #{} completed", target));
+                notifier.notify(
+                    notify::Event::Completed,
+                    &format!("Task #{} completed", target),
+                );
+                app.should_quit = true;
+            }
+        }
+
+        // Every loop goes into the run history; an interrupted one is kept out of the stats
+        let interrupted = reported.is_none() && app.should_quit;
+
+        // A task that keeps failing escalates one step per further failure
+        let mut escalated = escalated;
+        let mut pause_task = None;
+        if let Some(policy) = policy
+            && !interrupted
+            && !aborted
+            && let Some(task) = reported
+                .as_ref()
+                .and_then(|(number, _)| usize::try_from(number - 1).ok())
+                .and_then(|i| prd.tasks.get(i))
+                .or_else(|| {
+                    focus
+                        .or_else(|| next_task(&prd, &opts.categories))
+                        .map(|(_, t)| t)
+                })
+        {
+            match loop_failure(
+                reported.as_ref(),
+                reported_summary.as_deref(),
+                gates_passed,
+                &feedback,
+            ) {
+                Some(reason) => {
+                    let failure = escalation::Failure {
+                        loop_number: app.loop_count,
+                        reason,
+                    };
+                    match escalation.record_failure(policy, &task.description, failure) {
+                        Some(escalation::Step::Pause) => {
+                            escalated = Some(match escalated {
+                                Some(steps) => format!("{}+pause", steps),
+                                None => "pause".to_string(),
+                            });
+                            pause_task = Some(task.description.clone());
+                        }
+                        Some(step) => app.push_log(format!(
+                            "Task \"{}\" keeps failing: escalating to the {} step for the next loop",
+                            task.description,
+                            step.name()
+                        )),
+                        None => {}
+                    }
+                }
+                None if matches!(&reported, Some((_, status)) if status == "completed") => {
+                    escalation.record_success(&task.description);
+                }
+                None => {}
+            }
+            feedback.failure_history = escalation.history_section(policy);
+        }
+        // Where HEAD moved to, if the loop committed
+        let commit = head_before.as_ref().and_then(|before| {
+            git::head_commit(work_dir)
+                .ok()
+                .flatten()
+                .filter(|head| Some(head) != before.as_ref())
+        });
+        let reported_task = reported
+            .as_ref()
+            .and_then(|(number, _)| usize::try_from(number - 1).ok())
+            .and_then(|i| prd.tasks.get(i));
+        let record = IterationRecord {
+            run_id: run_state.id.clone(),
+            run_label: run_state.label.clone(),
+            loop_number: app.loop_count,
+            task_number: reported.as_ref().map(|(number, _)| *number),
+            task: reported_task.map(|task| task.description.clone()),
+            category: reported_task.map(|task| task.category.clone()),
+            model: app.last_model.clone(),
+            status: match &reported {
+                Some((_, status)) => status.clone(),
+                None if interrupted => stats::INTERRUPTED.to_string(),
+                None if aborted => stats::ABORTED.to_string(),
+                None => "failed".to_string(),
+            },
+            summary: reported_summary,
+            commit,
+            duration_secs: app
+                .loop_start_time
+                .map_or(0.0, |start| start.elapsed().as_secs_f64()),
+            cost_usd: app.total_cost_usd - cost_before,
+            finished_at: chrono::Utc::now(),
+            rating: None,
+            rating_note: None,
+            escalation: escalated,
+            thinking: thinking.map(|t| t.name().to_string()),
+        };
+        let fields = loop_fields(&record, &prd, app.total_cost_usd);
+        if let Some(number) = record.task_number {
+            loop_span.record("task", number);
+        }
+        if let Some(model) = &record.model {
+            loop_span.record("model", model.as_str());
+        }
+        loop_span.record("status", record.status.as_str());
+        loop_span.record("cost_usd", record.cost_usd);
+        loop_checks.archive_loop(&mut app, record, parsed, attempts, log_start);
+
+        if app.budget_exceeded() {
+            app.set_status(&format!(
+                "Budget exceeded: ${:.2} spent of ${:.2} limit",
+                app.total_cost_usd,
+                app.max_cost_usd.unwrap_or_default()
+            ));
+            notifier.notify(
+                notify::Event::AttentionNeeded,
+                &format!("Budget exceeded: ${:.2} spent", app.total_cost_usd),
+            );
+            app.should_quit = true;
+        } else if let Some((number, status)) = &reported
+            && !app.should_quit
+        {
+            // A blocked task needs someone to unblock it, so it's worth more than a progress note
+            let event = if status == "blocked" {
+                notify::Event::AttentionNeeded
+            } else {
+                notify::Event::Progress
+            };
+            notifier.notify_with(
+                event,
+                &format!("Loop #{}: task {} {}", app.loop_count, number, status),
+                &fields,
+            );
+        }
+
+        if aborted && !app.should_quit {
+            match opts.after_abort {
+                AfterAbort::Next => app.set_status("Loop aborted - starting the next one"),
+                AfterAbort::Pause => wait_after_abort(&mut terminal, &mut app)?,
+            }
+        }
+
+        if let Some(task) = pause_task
+            && !app.should_quit
+        {
+            notifier.notify(
+                notify::Event::AttentionNeeded,
+                &format!("Paused: \"{}\" keeps failing and needs your guidance", task),
+            );
+            if let Some(events) = &events {
+                events.emit(&RunEvent::Paused {
+                    loop_number: app.loop_count,
+                    task: &task,
+                    reason: "escalation",
+                });
+            }
+            if let Some(guidance) = pause_for_guidance(&mut terminal, &mut app, &task)? {
+                escalation.reset();
+                feedback.failure_history = None;
+                feedback.guidance =
+                    (!guidance.is_empty()).then(|| escalation::guidance_section(&task, &guidance));
+                app.push_log(format!(
+                    "Resumed after the escalation pause on \"{}\"",
+                    task
+                ));
+            }
+        }
+
+        terminal.draw(|f| app.draw(f))?;
+    }
+
+    // A rating typed during the last loop is kept even if its note wasn't finished
+    app.finish_rating(true);
+    save_ratings(&mut app, prd_path, &run_state.id);
+    let signal = tui::termination_signal();
+    if let Some(signal) = signal {
+        let name = tui::signal_name(signal);
+        run_state.interrupt(&name);
+        match run_state.save() {
+            Ok(()) => app.set_status(&format!(
+                "Stopped by {} - run `ralph build` again to resume the run",
+                name
+            )),
+            Err(e) => app.set_status(&format!("Stopped by {} - {}", name, e)),
+        }
+    }
+    drop(terminal);
+
+    let mut report = vec![
+        ("Loops", app.loop_count.to_string()),
+        (
+            "Tasks",
+            format!(
+                "{} completed, {} remaining",
+                app.completed_tasks, app.remaining_tasks
+            ),
+        ),
+        ("Cost", format!("${:.2}", app.total_cost_usd)),
+    ];
+    if let Some(branch) = &app.branch {
+        report.push(("Branch", branch.clone()));
+    }
+    notifier.notify_with(
+        notify::Event::Report,
+        &format!("Run finished: {}", app.status_message),
+        &report,
+    );
+
+    if let Some(events) = &events {
+        events.emit(&RunEvent::Finished {
+            loops: app.loop_count,
+            tasks_completed: app.completed_tasks,
+            tasks_remaining: app.remaining_tasks,
+            cost_usd: app.total_cost_usd,
+            status: &app.status_message,
+        });
+    }
+    if events_on_stdout {
+        exit_on_signal(signal);
+        return Ok(());
+    }
+
+    print_summary(&app);
+    exit_on_signal(signal);
+    Ok(())
+}
+
+/// Print the end-of-run summary for a person to read
+fn print_summary(app: &App) {
+    println!("\n═══════════════════════════════════════════════════════════════");
+    println!("Ralph Session Complete");
+    if let Some(label) = &app.label {
+        println!("Label: {}", label);
+    }
+    println!("Loops: {}", app.loop_count);
+    if let Some(branch) = &app.branch {
+        println!("Branch: {}", branch);
+    }
+    println!("Final status: {}", app.status_message);
+    println!(
+        "Cost: ${:.2} ({} in / {} out tokens)",
+        app.total_cost_usd, app.total_usage.input_tokens, app.total_usage.output_tokens
+    );
+    if let Some(max_cost) = app.max_cost_usd {
+        println!(
+            "Budget: ${:.2}{}",
+            max_cost,
+            if app.budget_exceeded() {
+                " (exceeded - run stopped)"
+            } else {
+                ""
+            }
+        );
+    }
+    if let Some(latest) = app.latest_log() {
+        println!("\n─── Last Claude Output ───\n{}", latest);
+    }
+}
+
+/// What the checks on a loop's work share: the PRD as the loop started, the run's options,
+/// how the loop's Claude was launched and where HEAD stood before it
+struct LoopChecks<'a> {
+    prd_path: &'a str,
+    prd: &'a prd::Prd,
+    opts: &'a BuildOptions,
+    notifier: &'a Notifier,
+    /// How the loop's Claude ran; gate fixes and tests are written the same way
+    claude: iteration::Options<'a>,
+    work_dir: &'a Path,
+    /// HEAD before Claude ran; None if we can't tell (not a repo, git error)
+    head_before: Option<&'a Option<String>>,
+}
+
+impl LoopChecks<'_> {
+    /// Run the quality gates on the loop's work, then `--fix-gates` sessions while they fail.
+    /// Returns whether the gates passed and whether they were interrupted. A completed task
+    /// whose gates still fail after every attempt is reported blocked.
+    fn gates<B: ratatui::backend::Backend<Error = io::Error>>(
+        &self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        feedback: &mut LoopFeedback,
+        reported: Option<&mut (i32, String)>,
+    ) -> Result<(bool, bool), RalphError> {
+        let (prd, opts) = (self.prd, self.opts);
+        let mut interrupted = !run_quality_gates(
+            terminal,
+            app,
+            &prd.quality_gates,
+            opts.parallel_gates,
+            self.claude.cwd,
+        )?;
+        feedback.gate_failures = gates::failure_report(&app.gate_results);
+        app.push_log(format_gate_log(&app.gate_results));
+
+        // --fix-gates: sessions that do nothing but fix the failures, before moving on
+        let mut attempt = 0;
+        while let Some(failures) = &feedback.gate_failures
+            && attempt < opts.fix_gates
+            && !interrupted
+            && !app.should_quit
+        {
+            attempt += 1;
+            if !self.fix_gates(terminal, app, attempt, failures, reported.as_deref())? {
+                break;
+            }
+            interrupted = !run_quality_gates(
+                terminal,
+                app,
+                &prd.quality_gates,
+                opts.parallel_gates,
+                self.claude.cwd,
+            )?;
+            feedback.gate_failures = gates::failure_report(&app.gate_results);
+            app.push_log(format_gate_log(&app.gate_results));
+        }
+        let passed = feedback.gate_failures.is_none();
+
+        // Still failing after every attempt: the task is blocked rather than done
+        if !passed && attempt > 0 && attempt == opts.fix_gates && !interrupted && !app.should_quit {
+            app.push_log(format!(
+                "Quality gates still fail after {} fix attempt(s). The failures will be sent \
+to the next loop.",
+                attempt
+            ));
+            if let Some((number, status)) = reported {
+                if status == "completed"
+                    && let Some(task) = usize::try_from(*number - 1)
+                        .ok()
+                        .and_then(|i| prd.tasks.get(i))
+                    && let Err(e) = prd::set_task_passes(self.prd_path, &task.description, false)
+                {
+                    app.push_log(format!("Warning: failed to update the PRD: {}", e));
+                }
+                *status = "blocked".to_string();
+                self.notifier.notify(
+                    notify::Event::AttentionNeeded,
+                    &format!(
+                        "Task #{} is blocked: quality gates still fail after {} fix attempt(s)",
+                        number, attempt
+                    ),
+                );
+            }
+        }
+        Ok((passed, interrupted))
+    }
+
+    /// One `--fix-gates` session on `failures`; false if it was interrupted, as the run is
+    /// stopping anyway
+    fn fix_gates<B: ratatui::backend::Backend<Error = io::Error>>(
+        &self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        attempt: u8,
+        failures: &str,
+        reported: Option<&(i32, String)>,
+    ) -> Result<bool, RalphError> {
+        let task = reported.and_then(|(number, _)| {
+            usize::try_from(*number - 1)
+                .ok()
+                .and_then(|i| self.prd.tasks.get(i))
+                .map(|task| (*number as usize, task))
+        });
+        let fix_prompt = gate_fix::make_prompt(
+            attempt,
+            self.opts.fix_gates,
+            task,
+            failures,
+            &self.prd.quality_gates,
+        );
+        app.set_status(&format!(
+            "Fixing the quality gates (attempt {} of {})...",
+            attempt, self.opts.fix_gates
+        ));
+        let output = run_check(
+            terminal,
+            app,
+            &self.working_session(&fix_prompt, gate_fix::OUTPUT_SCHEMA, gate_fix::MAX_TURNS),
+            tracing::info_span!("gate_fix", attempt),
+        )?;
+        match output.map(|stdout| gate_fix::parse_output(&stdout)) {
+            None => return Ok(false),
+            Some(Err(e)) => app.push_log(format!(
+                "Warning: gate fix attempt {} failed: {}",
+                attempt, e
+            )),
+            Some(Ok(fix)) => {
+                app.record_usage(fix.cost_usd, &fix.usage);
+                app.push_log(format!(
+                    "## Gate fix attempt {} of {}\n\n{}",
+                    attempt,
+                    self.opts.fix_gates,
+                    app.redactor.redact(&fix.report.summary)
+                ));
+            }
+        }
+        Ok(true)
+    }
+
+    /// `--verify`: an independent check with `model` of the task Claude reported completed.
+    /// Returns whether it's accepted; a rejected task is pending again.
+    fn verify<B: ratatui::backend::Backend<Error = io::Error>>(
+        &self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        feedback: &mut LoopFeedback,
+        model: &str,
+        reported: &mut (i32, String),
+        task: &prd::Task,
+    ) -> Result<bool, RalphError> {
+        let number = reported.0;
+        let verify_prompt = verify::make_prompt(
+            number as usize,
+            task,
+            &loop_diff(self.work_dir, self.head_before),
+            &gate_report(&app.gate_results),
+        );
+        app.set_status(&format!("Verifying task #{} with {}...", number, model));
+        let verification = run_check(
+            terminal,
+            app,
+            &claude::ClaudeOptions {
+                prompt: &verify_prompt,
+                model: Some(model),
+                output_format: Some("json"),
+                json_schema: Some(verify::VERIFY_OUTPUT_SCHEMA),
+                max_turns: Some(verify::MAX_TURNS),
+                permissions: claude::Permissions::ReadOnly,
+                add_dirs: self.claude.add_dirs,
+                cwd: self.claude.cwd,
+                ..Default::default()
+            },
+            tracing::info_span!("verify", model),
+        )?;
+        Ok(
+            match verification.map(|stdout| verify::parse_output(&stdout)) {
+                // Interrupted: leave the task as Claude left it
+                None => false,
+                Some(Ok(verification)) if verification.verdict.passed() => {
+                    app.record_usage(verification.cost_usd, &verification.usage);
+                    app.push_log(format!(
+                        "Verification of task #{} passed: {}",
+                        number, verification.verdict.reason
+                    ));
+                    true
+                }
+                Some(Ok(verification)) => {
+                    app.record_usage(verification.cost_usd, &verification.usage);
+                    app.push_log(format!(
+                        "Verification of task #{} failed: {}\n\nThe task is pending again and the \
+next loop will be told why.",
+                        number, verification.verdict.reason
+                    ));
+                    if let Err(e) = prd::set_task_passes(self.prd_path, &task.description, false) {
+                        app.push_log(format!("Warning: failed to update the PRD: {}", e));
+                    }
+                    feedback.verify_failure = Some(prompt::verify_failed_section(
+                        number,
+                        &task.description,
+                        &verification.verdict.reason,
+                    ));
+                    reported.1 = "rejected".to_string();
+                    self.notifier.notify(
+                        notify::Event::AttentionNeeded,
+                        &format!("Verification rejected task #{}", number),
+                    );
+                    false
+                }
+                // Gates already passed, so a verifier that can't run doesn't block the task
+                Some(Err(e)) => {
+                    app.push_log(format!(
+                    "Warning: verification of task #{} couldn't run, archiving it unverified: {}",
+                    number, e
+                ));
+                    true
+                }
+            },
+        )
+    }
+
+    /// `--code-review`: a reviewer agent with `model` reads the task's diff. Returns whether
+    /// the task is accepted; requested changes put it back to pending for a fix loop.
+    fn code_review<B: ratatui::backend::Backend<Error = io::Error>>(
+        &self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        feedback: &mut LoopFeedback,
+        model: &str,
+        reported: &mut (i32, String),
+        task: &prd::Task,
+    ) -> Result<bool, RalphError> {
+        let number = reported.0;
+        let review_prompt = code_review::make_prompt(
+            number as usize,
+            task,
+            &loop_diff(self.work_dir, self.head_before),
+            &gate_report(&app.gate_results),
+        );
+        app.set_status(&format!("Reviewing task #{} with {}...", number, model));
+        let review = run_check(
+            terminal,
+            app,
+            &claude::ClaudeOptions {
+                prompt: &review_prompt,
+                model: Some(model),
+                output_format: Some("json"),
+                json_schema: Some(code_review::REVIEW_OUTPUT_SCHEMA),
+                max_turns: Some(code_review::MAX_TURNS),
+                add_dirs: self.claude.add_dirs,
+                cwd: self.claude.cwd,
+                permissions: claude::Permissions::ReadOnly,
+                ..Default::default()
+            },
+            tracing::info_span!("code_review", model),
+        )?;
+        Ok(
+            match review.map(|stdout| code_review::parse_output(&stdout)) {
+                // Interrupted: leave the task as Claude left it
+                None => false,
+                Some(Ok(result)) if result.review.approved() => {
+                    app.record_usage(result.cost_usd, &result.usage);
+                    let issues = result.review.format_issues();
+                    app.push_log(format!(
+                        "Code review of task #{} approved: {}{}",
+                        number,
+                        result.review.summary,
+                        if issues.is_empty() {
+                            String::new()
+                        } else {
+                            format!("\n\n{}", issues)
+                        }
+                    ));
+                    true
+                }
+                Some(Ok(result)) => {
+                    app.record_usage(result.cost_usd, &result.usage);
+                    let issues = result.review.format_issues();
+                    app.push_log(format!(
+                        "Code review of task #{} requested changes: {}\n\n{}\n\nThe task is \
+pending again and the next loop will address the review.",
+                        number, result.review.summary, issues
+                    ));
+                    if let Err(e) = prd::set_task_passes(self.prd_path, &task.description, false) {
+                        app.push_log(format!("Warning: failed to update the PRD: {}", e));
+                    }
+                    feedback.code_review = Some(prompt::code_review_section(
+                        number,
+                        &task.description,
+                        &result.review.summary,
+                        &issues,
+                    ));
+                    reported.1 = "rejected".to_string();
+                    self.notifier.notify(
+                        notify::Event::AttentionNeeded,
+                        &format!("Code review requested changes to task #{}", number),
+                    );
+                    false
+                }
+                // The gates passed, so a reviewer that can't run doesn't block the task
+                Some(Err(e)) => {
+                    app.push_log(format!(
+                    "Warning: code review of task #{} couldn't run, accepting it unreviewed: {}",
+                    number, e
+                ));
+                    true
+                }
+            },
+        )
+    }
+
+    /// `--review`: the user approves the task's changes since `base`, or rejects them and
+    /// they're reverted. Returns whether the task is accepted.
+    fn review<B: ratatui::backend::Backend<Error = io::Error>>(
+        &self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        feedback: &mut LoopFeedback,
+        base: &str,
+        reported: &mut (i32, String),
+        task: &prd::Task,
+    ) -> Result<bool, RalphError> {
+        let number = reported.0;
+        let diff = git::diff_since(self.work_dir, Some(base)).unwrap_or_default();
+        self.notifier.notify(
+            notify::Event::AttentionNeeded,
+            &format!("Task #{} is waiting for your review", number),
+        );
+        Ok(match review_changes(terminal, app, number, &diff)? {
+            Review::Approved => {
+                app.push_log(format!("You approved task #{}", number));
+                true
+            }
+            Review::Rejected(reason) => {
+                match git::reset_hard(self.work_dir, base) {
+                    Ok(()) => app.push_log(format!(
+                        "You rejected task #{}. Its changes were reverted, the task is pending \
+again and the next loop will be told why.",
+                        number
+                    )),
+                    Err(e) => app.push_log(format!(
+                        "Warning: failed to revert the changes to task #{}: {}",
+                        number, e
+                    )),
+                }
+                if let Err(e) = prd::set_task_passes(self.prd_path, &task.description, false) {
+                    app.push_log(format!("Warning: failed to update the PRD: {}", e));
+                }
+                feedback.review_rejection = Some(prompt::review_rejected_section(
+                    number,
+                    &task.description,
+                    &reason,
+                ));
+                reported.1 = "rejected".to_string();
+                false
+            }
+            // Stopped: leave the task as Claude left it, unarchived
+            Review::Stopped => false,
+        })
+    }
+
+    /// `--with-tests`: a separate session writes tests for the accepted task `number`.
+    /// Committed tests that break the gates are dropped again.
+    fn write_tests<B: ratatui::backend::Backend<Error = io::Error>>(
+        &self,
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+        number: i32,
+        task: &prd::Task,
+    ) -> Result<(), RalphError> {
+        let (prd, opts) = (self.prd, self.opts);
+        let changed_files = match self.head_before {
+            Some(base) => {
+                git::files_changed_since(self.work_dir, base.as_deref()).unwrap_or_default()
+            }
+            None => Vec::new(),
+        };
+        let tests_base = git::head_commit(self.work_dir).ok().flatten();
+        let tests_prompt =
+            test_writer::make_prompt(number as usize, task, &changed_files, &prd.quality_gates);
+        app.set_status(&format!("Writing tests for task #{}...", number));
+        let output = run_check(
+            terminal,
+            app,
+            &self.working_session(
+                &tests_prompt,
+                test_writer::OUTPUT_SCHEMA,
+                test_writer::MAX_TURNS,
+            ),
+            tracing::info_span!("test_writer", task = number),
+        )?;
+        let writing = match output.map(|stdout| test_writer::parse_output(&stdout)) {
+            // Interrupted: the run is stopping anyway
+            None => return Ok(()),
+            Some(Err(e)) => {
+                app.push_log(format!(
+                    "Warning: writing tests for task #{} failed: {}",
+                    number, e
+                ));
+                return Ok(());
+            }
+            Some(Ok(writing)) => writing,
+        };
+        app.record_usage(writing.cost_usd, &writing.usage);
+        let report = &writing.report;
+        app.push_log(format!(
+            "## Tests for task #{}\n\n{} test(s) added{}: {}",
+            number,
+            report.tests_added,
+            if report.test_files.is_empty() {
+                String::new()
+            } else {
+                format!(" in {}", report.test_files.join(", "))
+            },
+            report.summary
+        ));
+        if report.tests_added > 0 && !report.committed {
+            app.push_log(format!(
+                "Warning: the tests for task #{} were left uncommitted",
+                number
+            ));
+        }
+        // Ralph checks the gates itself; committed tests that break them are dropped
+        if !opts.skip_gates && !prd.quality_gates.is_empty() && !app.should_quit {
+            let finished = run_quality_gates(
+                terminal,
+                app,
+                &prd.quality_gates,
+                opts.parallel_gates,
+                self.claude.cwd,
+            )?;
+            app.push_log(format_gate_log(&app.gate_results));
+            if finished
+                && gates::failure_report(&app.gate_results).is_some()
+                && let Some(base) = &tests_base
+            {
+                match git::reset_keep(self.work_dir, base) {
+                    Ok(()) => app.push_log(format!(
+                        "The new tests for task #{} fail the quality gates, so their commits \
+were dropped. The task itself stays accepted.",
+                        number
+                    )),
+                    Err(e) => app.push_log(format!(
+                        "Warning: the new tests for task #{} fail the quality gates and \
+couldn't be dropped: {}",
+                        number, e
+                    )),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep the finished loop: its diff for the TUI, its log file, journal entry and stats
+    /// record, and any ratings given during it
+    fn archive_loop(
+        &self,
+        app: &mut App,
+        record: IterationRecord,
+        parsed: Option<serde_json::Value>,
+        attempts: Vec<RawOutput>,
+        log_start: usize,
+    ) {
+        let files = match self.head_before {
+            Some(base) => {
+                if record.commit.is_some() {
+                    app.push_diff(
+                        record.loop_number,
+                        &committed_changes(self.work_dir, base.as_deref()),
+                    );
+                }
+                git::files_changed_since(self.work_dir, base.as_deref()).unwrap_or_default()
+            }
+            None => Vec::new(),
+        };
+        let loop_log = LoopLog {
+            run_id: record.run_id.clone(),
+            loop_number: record.loop_number,
+            status: record.status.clone(),
+            finished_at: record.finished_at,
+            parsed,
+            attempts,
+            log: app.iteration_logs[log_start..].to_vec(),
+        };
+        if let Err(e) = loop_log.save(self.prd_path) {
+            app.push_log(format!("Warning: {}", e));
+        }
+        journal_loop(app, &record, files, self.work_dir);
+        if let Some(events) = &app.events
+            && record.status == "completed"
+            && let Some(number) = record.task_number
+        {
+            events.emit(&RunEvent::TaskCompleted {
+                loop_number: record.loop_number,
+                task: number,
+                description: usize::try_from(number - 1)
+                    .ok()
+                    .and_then(|i| self.prd.tasks.get(i))
+                    .map(|task| task.description.as_str()),
+                summary: record.summary.as_deref(),
+                commit: record.commit.as_deref(),
+                cost_usd: record.cost_usd,
+            });
+        }
+        let run_id = record.run_id.clone();
+        let task = record.task.clone();
+        match Stats::append(self.prd_path, record) {
+            Ok(()) => app.last_finished_loop = Some(app.loop_count),
+            Err(e) => app.push_log(format!("Warning: {}", e)),
+        }
+        app.task_history = task.and_then(|task| {
+            Stats::load(self.prd_path)
+                .ok()
+                .and_then(|stats| stats.by_task().remove(&task))
+        });
+        save_ratings(app, self.prd_path, &run_id);
+    }
+
+    /// Options for a session that works on the loop's code the way its Claude did: the same
+    /// tools, config and directory
+    fn working_session<'p>(
+        &'p self,
+        prompt: &'p str,
+        json_schema: &'static str,
+        max_turns: u32,
+    ) -> claude::ClaudeOptions<'p> {
+        let claude = &self.claude;
+        claude::ClaudeOptions {
+            prompt,
+            permissions: if claude.allowed_tools.is_empty() {
+                claude::Permissions::Bypass
+            } else {
+                claude::Permissions::Default
+            },
+            output_format: Some("json"),
+            json_schema: Some(json_schema),
+            max_turns: Some(max_turns),
+            mcp_config: claude.mcp_config,
+            add_dirs: claude.add_dirs,
+            settings: claude.settings,
+            allowed_tools: claude.allowed_tools,
+            disallowed_tools: claude.disallowed_tools,
+            cwd: claude.cwd,
+            ..Default::default()
+        }
+    }
+}
+
+/// How long until `start` on the local clock, and the local time that is; None for a date
+/// and time already past
+fn time_until(start: StartAt) -> Option<(Duration, chrono::NaiveDateTime)> {
+    let now = chrono::Local::now().naive_local();
+    let at = start.next_after(now)?;
+    Some(((at - now).to_std().unwrap_or_default(), at))
+}
+
+/// Exit with the shell's 128 + signal status if a signal stopped the run
+fn exit_on_signal(signal: Option<i32>) {
+    if let Some(signal) = signal {
+        logging::shutdown();
+        std::process::exit(128 + signal);
+    }
+}
+
+/// The directory a loop on `task` runs Claude and the gates in, relative to the project root:
+/// the task's or the PRD's `workdir`. None for the root itself, or a workdir that isn't there.
+fn loop_workdir(
+    app: &mut App,
+    prd: &prd::Prd,
+    task: Option<&prd::Task>,
+    work_dir: &Path,
+) -> Option<String> {
+    let dir = prd.workdir_for(task)?;
+    if work_dir.join(dir).is_dir() {
+        Some(dir.to_string())
+    } else {
+        app.push_log(format!(
+            "Warning: workdir {} doesn't exist, so this loop runs from the project root",
+            dir
+        ));
+        None
+    }
+}
+
+/// Assemble the prompt for one loop from the master prompt plus any carried-over context
+fn build_iteration_prompt(
+    prd_path: &str,
+    prd: &prd::Prd,
+    run_state: &RunState,
+    opts: &BuildOptions,
+    master: &MasterPrompt,
+    focus: Option<(usize, &prd::Task)>,
+    feedback: &LoopFeedback,
+) -> String {
+    let completion = &opts.completion;
+    // Which tasks the loop may pick; the master prompt decides where this goes
+    let mut constraints = String::new();
+    if let Some((index, task)) = focus {
+        constraints.push_str(&prompt::task_focus_section(index + 1, &task.description));
+        if let Some(section) = master.addendum_section(&task.category) {
+            constraints.push_str(&section);
+        }
+    } else {
+        if !opts.categories.is_empty() {
+            let eligible: Vec<usize> = eligible_pending(prd, &opts.categories)
+                .iter()
+                .map(|(i, _)| i + 1)
+                .collect();
+            constraints.push_str(&prompt::category_filter_section(
+                &opts.categories,
+                &eligible,
+            ));
+        }
+        let entries = toc::build(prd);
+        let order: Vec<&toc::TocEntry> = entries
+            .iter()
+            .filter(|e| e.status != toc::TocStatus::Done)
+            .filter(|e| prd.tasks[e.number - 1].in_categories(&opts.categories))
+            .collect();
+        if let Some(section) = prompt::task_order_section(&order) {
+            constraints.push_str(&section);
+        }
+    }
+    let mut prompt = match focus {
+        Some((index, task)) if opts.once => {
+            master.single_task_prompt(prd_path, index + 1, task, &constraints)
+        }
+        _ => master.prompt(prd_path, &constraints),
+    };
+    // Like step progress, the journal would show a masked prompt the other tasks
+    if let Some(section) = &feedback.recent_loops
+        && !(focus.is_some() && opts.once)
+    {
+        prompt.push_str(section);
+    }
+    if let Some(report) = &feedback.gate_failures {
+        prompt.push_str(report);
+    }
+    if feedback.missing_commit {
+        prompt.push_str(prompt::MISSING_COMMIT_REMINDER);
+    }
+    if feedback.no_op {
+        prompt.push_str(prompt::NO_OP_REMINDER);
+    }
+    if let Some(section) = &feedback.verify_failure {
+        prompt.push_str(section);
+    }
+    if let Some(section) = &feedback.code_review {
+        prompt.push_str(section);
+    }
+    if let Some(section) = &feedback.review_rejection {
+        prompt.push_str(section);
+    }
+    if let Some(section) = prompt::definition_of_done_section(&prd.definition_of_done) {
+        prompt.push_str(&section);
+    }
+    if let Some(section) = &feedback.unmet_done {
+        prompt.push_str(section);
+    }
+    if let Some(section) = &feedback.failure_history {
+        prompt.push_str(section);
+    }
+    if let Some(section) = &feedback.guidance {
+        prompt.push_str(section);
+    }
+    if let Some((number, category)) = &feedback.off_filter {
+        prompt.push_str(&prompt::off_filter_reminder(*number, category));
+    }
+    if opts.rating_feedback {
+        let categories: Vec<&str> = match focus {
+            Some((_, task)) => vec![task.category.as_str()],
+            None => eligible_pending(prd, &opts.categories)
+                .iter()
+                .map(|(_, task)| task.category.as_str())
+                .collect(),
+        };
+        // Feedback is a nice-to-have; an unreadable stats file just means none
+        if let Ok(stats) = Stats::load(prd_path)
+            && let Some(section) = prompt::rating_feedback_section(
+                &stats.rating_notes(&categories, prompt::MAX_RATING_NOTES),
+            )
+        {
+            prompt.push_str(&section);
+        }
+    }
+    // A masked prompt mustn't leak the other tasks through their step progress
+    let mut progress = run_state.step_progress.clone();
+    if let Some((_, task)) = focus
+        && opts.once
+    {
+        progress.retain(|description, _| *description == task.description);
+    }
+    if let Some(section) = prompt::step_progress_section(prd, &progress) {
+        prompt.push_str(&section);
+    }
+    if completion.uses(Detector::Marker) {
+        prompt.push_str(&prompt::exit_clause_instruction(&completion.exit_clause));
+    }
+    prompt
+}
+
+/// Print what the first loop would do, without launching Claude or changing anything.
+/// Returns false if any check failed.
+fn dry_run(
+    prd_path: &str,
+    prd: &prd::Prd,
+    completed_count: usize,
+    opts: &BuildOptions,
+    master: &MasterPrompt,
+    escalation: Option<&escalation::Policy>,
+) -> bool {
+    let work_dir = Path::new(".");
+    let run_state = RunState::start(prd_path);
+
+    println!("Dry run: Claude will not be launched and nothing will be changed.\n");
+    println!("PRD:       {} ({})", prd_path, prd.name);
+    if let Some(label) = &opts.label {
+        println!("Label:     {}", label);
+    }
+    println!(
+        "Tasks:     {} pending, {} passing, {} archived",
+        prd.pending_count(),
+        prd.tasks.len() - prd.pending_count(),
+        completed_count
+    );
+
+    let focus = opts
+        .task
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| prd.tasks.get(i).map(|t| (i, t)));
+    let picked = match focus {
+        None if !master.addenda.is_empty() || prd.has_task_workdirs() => {
+            next_task(prd, &opts.categories)
+        }
+        _ => None,
+    };
+    match focus.or_else(|| next_task(prd, &opts.categories)) {
+        Some((i, task)) => {
+            let how = if focus.is_some() {
+                "--task"
+            } else if picked.is_some() && !master.addenda.is_empty() {
+                "picked by ralph for [prompt.categories]"
+            } else if picked.is_some() {
+                "picked by ralph for its workdir"
+            } else {
+                "first in task order"
+            };
+            println!(
+                "Next task: #{} ({}) {} [{}]",
+                i + 1,
+                task.category,
+                task.description,
+                how
+            );
+            if let Some(done) = run_state.step_progress.get(&task.description) {
+                println!(
+                    "           {}/{} steps done in earlier runs",
+                    done.len(),
+                    task.steps.len()
+                );
+            }
+        }
+        None => println!("Next task: none - build would report nothing to do and exit"),
+    }
+    let workdir = prd.workdir_for(focus.or(picked).map(|(_, task)| task));
+    if let Some(dir) = workdir {
+        println!(
+            "Workdir:   {} (Claude and the quality gates run there)",
+            dir
+        );
+    }
+
+    let detectors: Vec<&str> = opts.completion.detectors.iter().map(|d| d.name()).collect();
+    println!(
+        "Limits:    loops {}, turns {}, cost {}, loop timeout {}",
+        match opts.loop_limit() {
+            u64::MAX => "unlimited".to_string(),
+            limit => limit.to_string(),
+        },
+        opts.max_turns.unwrap_or(DEFAULT_MAX_TURNS),
+        opts.max_cost
+            .map_or_else(|| "unlimited".to_string(), |c| format!("${:.2}", c)),
+        opts.loop_timeout
+            .map_or_else(|| "none".to_string(), format_duration)
+    );
+    println!("Complete:  {}", detectors.join(", "));
+    if let Some(path) = &master.path {
+        println!("Prompt:    loop instructions from {}", path.display());
+    }
+    if !master.docs.is_empty() {
+        println!("Docs:      {}", master.docs.join(", "));
+    }
+    if !master.addenda.is_empty() {
+        let categories: Vec<&str> = master.addenda.keys().map(String::as_str).collect();
+        println!("Addenda:   for {} tasks", categories.join(", "));
+    }
+    if let Some((wait, at)) = opts.start_at.and_then(time_until) {
+        println!(
+            "Start at:  {} (in {})",
+            at.format("%Y-%m-%d %H:%M:%S"),
+            format_countdown(wait.as_secs())
+        );
+    }
+    if let Some(model) = &opts.verify {
+        println!(
+            "Verify:    each completed task with {} before archiving",
+            model
+        );
+    }
+    if let Some(model) = &opts.code_review {
+        println!(
+            "Review:    code review of each completed task with {}",
+            model
+        );
+    }
+    if opts.fix_gates > 0 {
+        println!(
+            "Fix gates: up to {} session(s) fixing failed quality gates",
+            opts.fix_gates
+        );
+    }
+    if opts.with_tests {
+        println!("Tests:     a test-writing session after each accepted task");
+    }
+    if opts.parallel > 1 {
+        let picked: Vec<String> = scheduler::pick(prd, &opts.categories, &[], &[], opts.parallel)
+            .iter()
+            .map(|i| format!("#{}", i + 1))
+            .collect();
+        println!(
+            "Parallel:  up to {} workers; {}",
+            opts.parallel,
+            if picked.len() > 1 {
+                format!("workers would start on tasks {}", picked.join(", "))
+            } else {
+                "only one task is ready, so it gets a normal loop".to_string()
+            }
+        );
+    }
+    if !prd.definition_of_done.is_empty() {
+        println!("Done when: {}", prd.definition_of_done.join("; "));
+    }
+    if let Some(thinking) = opts.thinking {
+        println!(
+            "Thinking:  {} (tasks with their own `thinking` keep it)",
+            thinking.name()
+        );
+    }
+    if let Some(policy) = escalation {
+        let steps: Vec<&str> = policy.steps.iter().map(|s| s.name()).collect();
+        println!(
+            "Escalate:  after {} failed loop(s) on a task: {} (model {})",
+            policy.after,
+            steps.join(", "),
+            policy.model
+        );
+    }
+    if !opts.categories.is_empty() {
+        println!(
+            "Filter:    categories {} ({} pending task(s) eligible)",
+            opts.categories.join(", "),
+            eligible_pending(prd, &opts.categories).len()
+        );
+    }
+
+    let found = dry_run_checks(prd, prd_path, opts, work_dir);
+    println!("\nChecks:");
+    for check in &found {
+        check.print("  ");
+    }
+
+    let loop_master = workdir.map(|dir| master.in_workdir(dir));
+    let mut prompt = build_iteration_prompt(
+        &workdir.map_or_else(
+            || prd_path.to_string(),
+            |dir| prompt::path_from_workdir(dir, prd_path),
+        ),
+        prd,
+        &run_state,
+        opts,
+        loop_master.as_ref().unwrap_or(master),
+        focus.or(picked),
+        &LoopFeedback::default(),
+    );
+    if let Some(dir) = workdir {
+        prompt.push_str(&prompt::workdir_section(dir));
+    }
+    println!(
+        "\n─── Prompt ({} chars, ~{} tokens) ───\n{}",
+        prompt.chars().count(),
+        claude::estimate_tokens(&prompt),
+        prompt
+    );
+
+    !checks::any_failed(&found)
+}
+
+/// Details of a finished loop for notification channels that can show them
+fn loop_fields(
+    record: &IterationRecord,
+    prd: &prd::Prd,
+    run_cost: f64,
+) -> Vec<(&'static str, String)> {
+    let task = match record.task_number {
+        Some(n) => match usize::try_from(n - 1).ok().and_then(|i| prd.tasks.get(i)) {
+            Some(task) => format!("#{} {}", n, task.description),
+            None => format!("#{}", n),
+        },
+        None => "-".to_string(),
+    };
+    let mut fields = vec![
+        ("Task", task),
+        ("Status", record.status.clone()),
+        (
+            "Cost",
+            format!("${:.2} (run total ${:.2})", record.cost_usd, run_cost),
+        ),
+    ];
+    if let Some(summary) = &record.summary {
+        fields.push(("Summary", summary.clone()));
+    }
+    fields
+}
+
+/// Why a loop failed its task, for escalation; None if it completed it or made progress
+fn loop_failure(
+    reported: Option<&(i32, String)>,
+    summary: Option<&str>,
+    gates_passed: bool,
+    feedback: &LoopFeedback,
+) -> Option<String> {
+    let Some((_, status)) = reported else {
+        return Some("Claude failed without reporting a result".to_string());
+    };
+    if !gates_passed {
+        Some("the quality gates failed".to_string())
+    } else if feedback.verify_failure.is_some() {
+        Some("verification rejected the task".to_string())
+    } else if feedback.code_review.is_some() {
+        Some("code review requested changes".to_string())
+    } else if feedback.review_rejection.is_some() {
+        Some("you rejected the task in review".to_string())
+    } else if feedback.no_op {
+        Some("reported the task completed but changed nothing".to_string())
+    } else if feedback.missing_commit {
+        Some("reported the task completed without a git commit".to_string())
+    } else if status == "blocked" {
+        Some(format!("blocked: {}", summary.unwrap_or_default()))
+    } else {
+        None
+    }
+}
+
+/// Validate the environment a build run depends on
+fn dry_run_checks(
+    prd: &prd::Prd,
+    prd_path: &str,
+    opts: &BuildOptions,
+    work_dir: &Path,
+) -> Vec<Check> {
+    let mut found = vec![checks::claude()];
+
+    if work_dir.join(prompt::PROGRESS_FILE).exists() {
+        found.push(Check::ok("progress.txt exists"));
+    } else {
+        found.push(Check::warn(
+            "progress.txt missing",
+            "nothing to do: Claude will create it",
+        ));
+    }
+
+    if opts.skip_gates {
+        found.push(Check::ok("quality gates skipped (--no-gates)"));
+    } else {
+        found.extend(checks::quality_gates(prd, prd_path));
+    }
+    found.extend(checks::workdirs(prd, prd_path, work_dir));
+
+    let in_repo = git::is_repo(work_dir);
+    match (&opts.branch, in_repo) {
+        (None, true) => found.push(Check::ok("git repository")),
+        (None, false) => found.push(Check::warn(
+            "not a git repository (commits can't be verified)",
+            "run `git init` and commit the project",
+        )),
+        (Some(_), false) => found.push(Check::fail(
+            "--branch requires running inside a git repository",
+            "run `git init`, or drop --branch",
+        )),
+        (Some(requested), true) => {
+            let branch = if requested.is_empty() {
+                default_branch_name(&prd.name)
+            } else {
+                requested.clone()
+            };
+            if !git::is_valid_branch_name(work_dir, &branch) {
+                found.push(Check::fail(
+                    format!("'{}' is not a valid branch name", branch),
+                    "pass a different name to --branch",
+                ));
+            } else if git::branch_exists(work_dir, &branch) {
+                found.push(Check::ok(format!(
+                    "branch {} exists and would be checked out",
+                    branch
+                )));
+            } else {
+                found.push(Check::ok(format!("branch {} would be created", branch)));
+            }
+        }
+    }
+    if opts.parallel > 1 && !in_repo {
+        found.push(Check::fail(
+            "--parallel requires running inside a git repository",
+            "run `git init`, or drop --parallel",
+        ));
+    }
+    if opts
+        .start_at
+        .is_some_and(|start| time_until(start).is_none())
+    {
+        found.push(Check::fail(
+            "the --start-at time has already passed",
+            "pass a time still to come",
+        ));
+    }
+
+    found
+}
+
+/// `claude_result` for an invocation that produced no usable result
+fn claude_failure_event(events: Option<&EventLog>, app: &App, outcome: &str) {
+    if let Some(events) = events {
+        events.emit(&RunEvent::ClaudeResult {
+            loop_number: app.loop_count,
+            outcome,
+            task: None,
+            status: None,
+            summary: None,
+            prd_complete: false,
+            run_cost_usd: app.total_cost_usd,
+        });
+    }
+}
+
+/// Record a run that ended before its first loop as a `finished` event
+fn finished_early(events: Option<&EventLog>, prd: &prd::Prd, message: &str) {
+    let Some(events) = events else {
+        return;
+    };
+    let remaining = prd.pending_count();
+    events.emit(&RunEvent::Finished {
+        loops: 0,
+        tasks_completed: prd.tasks.len() - remaining,
+        tasks_remaining: remaining,
+        cost_usd: 0.0,
+        status: message,
+    });
+}
+
+/// Tell the user there's nothing to build and offer to archive passing tasks
+fn report_nothing_to_do(prd_path: &str, prd: &prd::Prd) {
+    if prd.tasks.is_empty() {
+        println!("Nothing to do: {} has no tasks.", prd_path);
+        return;
+    }
+
+    println!(
+        "Nothing to do: all {} task(s) in {} already pass.",
+        prd.tasks.len(),
+        prd_path
+    );
+
+    if !std::io::stdin().is_terminal() {
+        println!("Move them to completed.json to archive them.");
+        return;
+    }
+
+    print!("Archive them to completed.json now? [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() || !is_yes(&answer) {
+        return;
+    }
+
+    let head = git::head_commit(Path::new(".")).ok().flatten();
+    match prd::archive_passing_tasks(prd_path, &completion_timestamp(), head.as_deref()) {
+        Ok(count) => println!("Archived {} task(s) to completed.json.", count),
+        Err(e) => eprintln!("Error: failed to archive tasks: {}", e),
+    }
+}
+
+/// `completed_at` for tasks archived now
+fn completion_timestamp() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+/// Move passing tasks to completed.json, stamped with the time and HEAD, and log it
+fn archive_passing(app: &mut App, prd_path: &str, work_dir: &Path) {
+    let head = git::head_commit(work_dir).ok().flatten();
+    match prd::archive_passing_tasks(prd_path, &completion_timestamp(), head.as_deref()) {
+        Ok(0) => {}
+        Ok(count) => app.push_log(format!("Archived {} task(s) to completed.json", count)),
+        Err(e) => app.push_log(format!("Warning: failed to archive tasks: {}", e)),
+    }
+}
+
+/// Record the loop in progress.jsonl, the handoff the next loops' prompts show
+fn journal_loop(app: &mut App, record: &IterationRecord, files: Vec<String>, work_dir: &Path) {
+    let entry = progress::JournalEntry {
+        run_id: record.run_id.clone(),
+        loop_number: record.loop_number,
+        finished_at: record.finished_at,
+        task_number: record.task_number,
+        task: record.task.clone(),
+        status: record.status.clone(),
+        summary: record.summary.clone(),
+        // The journal itself changes every loop
+        files: files
+            .into_iter()
+            .filter(|file| file != progress::JOURNAL_FILE)
+            .collect(),
+        commit: record.commit.clone(),
+    };
+    if let Err(e) = progress::append_journal(work_dir, &entry) {
+        app.push_log(format!(
+            "Warning: failed to write {}: {}",
+            progress::JOURNAL_FILE,
+            e
+        ));
+    }
+}
+
+/// Condense progress.txt's older notes once it has grown past `[progress] max_bytes`, so
+/// the loop doesn't start with hundreds of loops' worth of notes
+fn compact_progress(app: &mut App, config: &ProgressConfig, work_dir: &Path) {
+    if config.max_bytes == 0 {
+        return;
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    match progress::compact(work_dir, config.max_bytes, config.model.as_deref(), &stamp) {
+        Ok(None) => {}
+        Ok(Some(compacted)) => {
+            if let Some(e) = compacted.model_error {
+                app.push_log(format!(
+                    "Warning: couldn't condense {} with a model, keeping each note's first \
+line: {}",
+                    prompt::PROGRESS_FILE,
+                    e
+                ));
+            }
+            app.push_log(format!(
+                "Condensed {} older note(s) in {} ({} -> {} bytes); the full file is in {}",
+                compacted.notes,
+                prompt::PROGRESS_FILE,
+                compacted.bytes_before,
+                compacted.bytes_after,
+                compacted.archive.display()
+            ));
+        }
+        Err(e) => app.push_log(format!(
+            "Warning: failed to compact {}: {}",
+            prompt::PROGRESS_FILE,
+            e
+        )),
+    }
+}
+
+/// Record where the repo and PRD stand before the loop that has just started, for
+/// `ralph rollback`
+fn take_checkpoint(app: &mut App, run_state: &RunState, prd_path: &str, work_dir: &Path) {
+    let saved = Checkpoint::take(
+        prd_path,
+        work_dir,
+        &run_state.id,
+        app.loop_count,
+        &run_state.step_progress,
+    )
+    .and_then(|checkpoint| checkpoint.save(prd_path));
+    if let Err(e) = saved {
+        app.push_log(format!("Warning: {}", e));
+    }
+}
+
+pub(crate) fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Update the TUI checklist and persisted step progress from an iteration's report
+fn record_step_progress(
+    app: &mut App,
+    run_state: &mut RunState,
+    prd: &prd::Prd,
+    result: &BuildIterationOutput,
+) {
+    let Some(task) = usize::try_from(result.task_number - 1)
+        .ok()
+        .and_then(|i| prd.tasks.get(i))
+    else {
+        return;
+    };
+
+    let done: Vec<u32> = if result.status == "completed" {
+        (1..=task.steps.len() as u32).collect()
+    } else {
+        result.completed_steps.clone()
+    };
+    app.current_task = Some(TaskChecklist::new(result.task_number, &task.steps, &done));
+
+    // A finished task has nothing left to resume
+    if result.status == "completed" {
+        run_state.record_steps(&task.description, Vec::new());
+    } else {
+        run_state.record_steps(&task.description, done);
+    }
+}
+
+/// Check out (creating if needed) the branch for this run and return its name.
+/// An empty `requested` name falls back to one derived from the PRD name.
+fn switch_to_run_branch(dir: &Path, requested: &str, prd_name: &str) -> Result<String, String> {
+    if !git::is_repo(dir) {
+        return Err("--branch requires running inside a git repository".to_string());
+    }
+
+    let branch = if requested.is_empty() {
+        default_branch_name(prd_name)
+    } else {
+        requested.to_string()
+    };
+
+    if !git::is_valid_branch_name(dir, &branch) {
+        return Err(format!("'{}' is not a valid branch name", branch));
+    }
+
+    git::checkout_branch(dir, &branch).map_err(|e| e.to_string())?;
+    Ok(branch)
+}
+
+/// Branch name for a PRD, e.g. "My Todo App" -> "ralph/my-todo-app"
+fn default_branch_name(prd_name: &str) -> String {
+    let mut slug = String::new();
+    for c in prd_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        "ralph/build".to_string()
+    } else {
+        format!("ralph/{}", slug)
+    }
+}
+
+/// Run the quality gates in `dir` (else the project root), one after another or with
+/// `parallel` all at once. Returns false if the user interrupted them with Ctrl+C.
+fn run_quality_gates<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    commands: &[String],
+    parallel: bool,
+    dir: Option<&Path>,
+) -> Result<bool, RalphError> {
+    app.gate_results.clear();
+
+    if parallel {
+        app.set_status(&format!("Running {} gates in parallel", commands.len()));
+        let (results, interrupted) = run_gates(terminal, app, commands, dir)?;
+        for result in results {
+            record_gate(app, result);
+        }
+        return Ok(!interrupted);
+    }
+
+    for (i, command) in commands.iter().enumerate() {
+        app.set_status(&format!(
+            "Running gate {}/{}: {}",
+            i + 1,
+            commands.len(),
+            command
+        ));
+
+        let (results, interrupted) = run_gates(terminal, app, std::slice::from_ref(command), dir)?;
+        for result in results {
+            record_gate(app, result);
+        }
+        if interrupted {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Log a finished gate, report it as an event and keep it for the TUI
+fn record_gate(app: &mut App, result: gates::GateResult) {
+    tracing::info!(
+        command = result.command,
+        exit_code = ?result.exit_code,
+        secs = result.duration.as_secs_f64(),
+        "Gate finished"
+    );
+    if let Some(events) = &app.events {
+        events.emit(&RunEvent::GateResult {
+            loop_number: app.loop_count,
+            command: &result.command,
+            passed: result.passed(),
+            exit_code: result.exit_code,
+            duration_secs: result.duration.as_secs_f64(),
+        });
+    }
+    app.gate_results.push(result);
+}
+
+/// Run one command while keeping the TUI responsive. Ctrl+C kills it and sets `should_quit`.
+fn run_gate<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    command: &str,
+) -> Result<gates::GateResult, RalphError> {
+    let (mut results, _) = run_gates(terminal, app, &[command.to_string()], None)?;
+    Ok(results.remove(0))
+}
+
+/// Run `commands` in `dir` (else the project root) at the same time while keeping the TUI
+/// responsive. Ctrl+C kills them all and sets `should_quit`. Returns the results in command
+/// order, and whether they were interrupted.
+fn run_gates<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    commands: &[String],
+    dir: Option<&Path>,
+) -> Result<(Vec<gates::GateResult>, bool), RalphError> {
+    runtime::block_on(async {
+        let (stop, stopped) = watch::channel(false);
+        let mut running: FuturesUnordered<_> = commands
+            .iter()
+            .enumerate()
+            .map(|(i, command)| {
+                let mut stopped = stopped.clone();
+                async move {
+                    let stop = async move {
+                        let _ = stopped.wait_for(|&stop| stop).await;
+                    };
+                    (i, gates::run(command, dir, stop).await)
+                }
+            })
+            .collect();
+        let mut results = Vec::with_capacity(commands.len());
+        let mut keys = keys(app);
+        let mut redraw = runtime::redraw_timer();
+
+        loop {
+            tokio::select! {
+                finished = running.next() => match finished {
+                    Some(finished) => {
+                        results.push(finished);
+                        if commands.len() > 1 && !*stop.borrow() {
+                            app.set_status(&format!(
+                                "Running gates in parallel: {}/{} finished",
+                                results.len(),
+                                commands.len()
+                            ));
+                        }
+                    }
+                    None => break,
+                },
+                key = next_key(&mut keys), if !*stop.borrow() => {
+                    if is_ctrl_c(&key?) {
+                        app.should_quit = true;
+                        app.set_status("Interrupted by user");
+                        stop.send_replace(true);
+                    }
+                }
+                _ = redraw.tick() => app.advance_spinner(),
+            }
+            terminal.draw(|f| app.draw(f))?;
+        }
+
+        let interrupted = *stop.borrow();
+        results.sort_by_key(|(i, _)| *i);
+        Ok((
+            results.into_iter().map(|(_, result)| result).collect(),
+            interrupted,
+        ))
+    })
+}
+
+/// The keyboard, unless the run is headless
+fn keys(app: &App) -> Keys {
+    if app.headless {
+        Keys::none()
+    } else {
+        Keys::new()
+    }
+}
+
+/// The next key press. A SIGTERM or SIGHUP comes back as Ctrl+C, so every wait stops the
+/// way it does for the user.
+async fn next_key(keys: &mut Keys) -> io::Result<KeyEvent> {
+    tokio::select! {
+        key = keys.next() => key,
+        () = tui::terminated() => Ok(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+    }
+}
+
+/// `next_key`, with mouse events too
+async fn next_input(keys: &mut Keys) -> io::Result<Input> {
+    tokio::select! {
+        input = keys.next_input() => input,
+        () = tui::terminated() => Ok(Input::Key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        ))),
+    }
+}
+
+fn is_ctrl_c(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Wait `delay` before retrying Claude, keeping the TUI responsive. Ctrl+C sets `should_quit`.
+fn wait_before_retry<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    delay: Duration,
+) -> Result<(), RalphError> {
+    runtime::block_on(async {
+        let done = tokio::time::sleep(delay);
+        tokio::pin!(done);
+        let mut keys = keys(app);
+        let mut redraw = runtime::redraw_timer();
+        loop {
+            tokio::select! {
+                () = &mut done => return Ok(()),
+                key = next_key(&mut keys) => {
+                    if is_ctrl_c(&key?) {
+                        app.should_quit = true;
+                        app.set_status("Interrupted by user");
+                        return Ok(());
+                    }
+                }
+                _ = redraw.tick() => app.advance_spinner(),
+            }
+            terminal.draw(|f| app.draw(f))?;
+        }
+    })
+}
+
+/// Hold the run after an aborted loop until the user presses Enter (next loop) or Esc/q/Ctrl+C
+/// (stop the run)
+fn wait_after_abort<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+) -> Result<(), RalphError> {
+    app.set_status("Loop aborted - paused (enter=next loop, esc/q=stop)");
+    runtime::block_on(async {
+        let mut keys = keys(app);
+        loop {
+            terminal.draw(|f| app.draw(f))?;
+            let key = next_key(&mut keys).await?;
+            match key.code {
+                KeyCode::Enter => {
+                    app.set_status("Starting the next loop...");
+                    return Ok(());
+                }
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('Q') => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                _ => {}
+            }
+        }
+        app.should_quit = true;
+        app.set_status("Stopped after an aborted loop");
+        Ok(())
+    })
+}
+
+/// Wait for the user's guidance after escalation paused on `task`. Returns what they typed
+/// (possibly nothing) when they continue, or None if they stopped the run.
+fn pause_for_guidance<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    task: &str,
+) -> Result<Option<String>, RalphError> {
+    if app.headless {
+        app.should_quit = true;
+        app.set_status(&format!(
+            "Stopped: \"{}\" keeps failing and the escalation pause needs the TUI",
+            task
+        ));
+        return Ok(None);
+    }
+    app.guidance_input = Some(String::new());
+    app.set_status(&format!(
+        "Paused: \"{}\" keeps failing. Type guidance for the next loop",
+        task
+    ));
+    runtime::block_on(async {
+        let mut keys = keys(app);
+        loop {
+            terminal.draw(|f| app.draw(f))?;
+            let key = next_key(&mut keys).await?;
+            let input = app.guidance_input.get_or_insert_with(String::new);
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let guidance = app.guidance_input.take().unwrap_or_default();
+                    app.set_status("Resuming with your guidance...");
+                    return Ok(Some(guidance.trim().to_string()));
+                }
+                KeyCode::Esc => break,
+                _ => {}
+            }
+        }
+        app.guidance_input = None;
+        app.should_quit = true;
+        app.set_status("Stopped at the escalation pause");
+        Ok(None)
+    })
+}
+
+/// What the user made of a completed task's changes under `--review`
+enum Review {
+    Approved,
+    /// Rejected, with what should change (possibly nothing)
+    Rejected(String),
+    /// The user stopped the run instead of deciding
+    Stopped,
+}
+
+/// Show the changes a loop committed for task `number` and wait for the user to approve or
+/// reject them
+fn review_changes<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    number: i32,
+    diff: &str,
+) -> Result<Review, RalphError> {
+    let diff = if diff.is_empty() {
+        "(no committed changes)"
+    } else {
+        diff
+    };
+    app.push_log(format!(
+        "## Review task #{}\n\n```diff\n{}\n```",
+        number, diff
+    ));
+    app.tab = Tab::Logs;
+    app.review_input = Some(ReviewInput {
+        task_number: number,
+        reason: None,
+    });
+    app.set_status(&format!("Review task #{}: a=approve, r=reject", number));
+    runtime::block_on(async {
+        let mut keys = keys(app);
+        let review = loop {
+            terminal.draw(|f| app.draw(f))?;
+            let key = next_key(&mut keys).await?;
+            if is_ctrl_c(&key) {
+                break Review::Stopped;
+            }
+            let Some(input) = app.review_input.as_mut() else {
+                break Review::Stopped;
+            };
+            // Typing the reason for a rejection takes every key until enter/esc
+            if let Some(reason) = input.reason.as_mut() {
+                match key.code {
+                    KeyCode::Char(c) => reason.push(c),
+                    KeyCode::Backspace => {
+                        reason.pop();
+                    }
+                    KeyCode::Enter => break Review::Rejected(reason.trim().to_string()),
+                    KeyCode::Esc => input.reason = None,
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('a' | 'A') => break Review::Approved,
+                KeyCode::Char('r' | 'R') => input.reason = Some(String::new()),
+                KeyCode::Esc => break Review::Stopped,
+                KeyCode::Up => app.scroll_up(1),
+                KeyCode::Down => app.scroll_down(1),
+                KeyCode::PageUp => app.scroll_up(10),
+                KeyCode::PageDown => app.scroll_down(10),
+                _ => {}
+            }
+        };
+        app.review_input = None;
+        if let Review::Stopped = review {
+            app.should_quit = true;
+            app.set_status(&format!("Stopped at the review of task #{}", number));
+        }
+        Ok(review)
+    })
+}
+
+/// Write ratings given in the TUI to the stats file
+fn save_ratings(app: &mut App, prd_path: &str, run_id: &str) {
+    for rating in std::mem::take(&mut app.finished_ratings) {
+        let note = (!rating.note.is_empty()).then_some(rating.note);
+        match Stats::rate(prd_path, run_id, rating.loop_number, rating.rating, note) {
+            Ok(true) => app.set_status(&format!(
+                "Rated loop #{} {}/5",
+                rating.loop_number, rating.rating
+            )),
+            Ok(false) => {}
+            Err(e) => app.push_log(format!("Warning: {}", e)),
+        }
+    }
+}
+
+/// Run a one-shot checking call (`--verify`, `--code-review`) inside `span`, keeping the
+/// TUI responsive, and return its stdout. None if the user interrupted with Ctrl+C.
+fn run_check<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    options: &claude::ClaudeOptions,
+    span: tracing::Span,
+) -> Result<Option<String>, RalphError> {
+    runtime::block_on(
+        async {
+            let mut child =
+                claude::launch_claude_with_options(options).map_err(RalphError::Claude)?;
+            let mut output = process::Output::capture(&mut child);
+            let mut keys = keys(app);
+            let mut redraw = runtime::redraw_timer();
+
+            loop {
+                tokio::select! {
+                    status = child.wait() => {
+                        status.map_err(RalphError::Claude)?;
+                        break;
+                    }
+                    key = next_key(&mut keys) => {
+                        if is_ctrl_c(&key?) {
+                            process::kill_tree(&mut child).map_err(RalphError::Claude)?;
+                            let _ = child.wait().await;
+                            app.should_quit = true;
+                            app.set_status("Interrupted by user");
+                            return Ok(None);
+                        }
+                    }
+                    _ = redraw.tick() => app.advance_spinner(),
+                }
+                terminal.draw(|f| app.draw(f))?;
+            }
+
+            let (stdout, _) = output.collect().await;
+            Ok(Some(stdout))
+        }
+        .instrument(span),
+    )
+}
+
+/// The changes committed since `head_before` (see `git::diff_since`); empty outside a repo
+fn loop_diff(work_dir: &Path, head_before: Option<&Option<String>>) -> String {
+    match head_before {
+        Some(base) => git::diff_since(work_dir, base.as_deref()).unwrap_or_default(),
+        None => String::new(),
+    }
+}
+
+/// What the diff view shows for a loop: the stat, then the full diff
+fn committed_changes(work_dir: &Path, base: Option<&str>) -> String {
+    let stat = git::diff_stat_since(work_dir, base).unwrap_or_default();
+    let diff = git::diff_since(work_dir, base).unwrap_or_default();
+    format!("{}\n{}", stat, diff)
+}
+
+/// Gate results as the verifier and reviewer see them
+fn gate_report(results: &[gates::GateResult]) -> String {
+    if results.is_empty() {
+        "No quality gates were run.".to_string()
+    } else {
+        format_gate_log(results)
+            .trim_start_matches("## Quality Gates\n")
+            .to_string()
+    }
+}
+
+/// Format gate results as an iteration log entry
+fn format_gate_log(results: &[gates::GateResult]) -> String {
+    let mut log = String::from("## Quality Gates\n");
+    for result in results {
+        let mark = if result.passed() { "PASS" } else { "FAIL" };
+        log.push_str(&format!(
+            "- {} `{}` ({:.1}s)\n",
+            mark,
+            result.command,
+            result.duration.as_secs_f64()
+        ));
+    }
+    for result in results.iter().filter(|r| !r.passed()) {
+        log.push_str(&format!(
+            "\n### {}\n{}\n",
+            result.command,
+            result.output.trim_end()
+        ));
+    }
+    log
+}
+
+/// Format definition-of-done results as an iteration log entry
+fn format_done_log(results: &[(String, done::Outcome)]) -> String {
+    let mut log = String::from("## Definition of Done\n");
+    for (item, outcome) in results {
+        let line = match outcome {
+            done::Outcome::Met => format!("- MET {}\n", item),
+            done::Outcome::Unmet(reason) => format!("- NOT MET {} ({})\n", item, reason),
+            done::Outcome::Unchecked => format!("- UNCHECKED {}\n", item),
+        };
+        log.push_str(&line);
+    }
+    log
+}
+
+/// Format a duration as whole minutes where possible ("15m"), else seconds ("90s")
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 && secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Level;
+
+    #[test]
+    fn gate_log_marks_pass_and_fail() {
+        let results = vec![
+            gates::GateResult {
+                command: "cargo test".to_string(),
+                exit_code: Some(0),
+                output: "ok".to_string(),
+                duration: Duration::from_millis(1500),
+            },
+            gates::GateResult {
+                command: "cargo clippy".to_string(),
+                exit_code: Some(101),
+                output: "error: unused".to_string(),
+                duration: Duration::ZERO,
+            },
+        ];
+        let log = format_gate_log(&results);
+        assert!(log.contains("- PASS `cargo test` (1.5s)"));
+        assert!(log.contains("- FAIL `cargo clippy`"));
+        assert!(log.contains("### cargo clippy\nerror: unused"));
+        assert!(!log.contains("### cargo test"));
+    }
+
+    fn step_prd() -> prd::Prd {
+        serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "Login", "steps": ["Form", "API", "Tests"], "passes": false}
+            ]}"#,
+        )
+        .unwrap()
+    }
+
+    fn iteration(status: &str, completed_steps: Vec<u32>) -> BuildIterationOutput {
+        BuildIterationOutput {
+            task_number: 1,
+            status: status.to_string(),
+            summary: String::new(),
+            completed_steps,
+            prd_complete: false,
+            result_text: String::new(),
+        }
+    }
+
+    #[test]
+    fn record_step_progress_persists_partial_task() {
+        let mut app = App::new("Test", 1, 0);
+        let mut run_state = RunState::new("prd.json");
+        record_step_progress(
+            &mut app,
+            &mut run_state,
+            &step_prd(),
+            &iteration("in_progress", vec![1]),
+        );
+
+        assert_eq!(app.current_task.as_ref().unwrap().done_count(), 1);
+        assert_eq!(run_state.step_progress.get("Login"), Some(&vec![1]));
+    }
+
+    #[test]
+    fn record_step_progress_clears_completed_task() {
+        let mut app = App::new("Test", 1, 0);
+        let mut run_state = RunState::new("prd.json");
+        run_state.record_steps("Login", vec![1]);
+        record_step_progress(
+            &mut app,
+            &mut run_state,
+            &step_prd(),
+            &iteration("completed", vec![]),
+        );
+
+        assert_eq!(app.current_task.as_ref().unwrap().done_count(), 3);
+        assert!(run_state.step_progress.is_empty());
+    }
+
+    #[test]
+    fn record_step_progress_ignores_unknown_task() {
+        let mut app = App::new("Test", 1, 0);
+        let mut run_state = RunState::new("prd.json");
+        let mut result = iteration("in_progress", vec![1]);
+        result.task_number = 9;
+        record_step_progress(&mut app, &mut run_state, &step_prd(), &result);
+
+        assert!(app.current_task.is_none());
+        assert!(run_state.step_progress.is_empty());
+    }
+
+    #[test]
+    fn iteration_prompt_includes_focus() {
+        let prd = step_prd();
+        let run_state = RunState::new("prd.json");
+        let prompt = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &run_state,
+            &BuildOptions::default(),
+            &MasterPrompt::default(),
+            Some((0, &prd.tasks[0])),
+            &LoopFeedback::default(),
+        );
+        assert!(prompt.contains("task #1 (Login)"));
+
+        let unfocused = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &run_state,
+            &BuildOptions::default(),
+            &MasterPrompt::default(),
+            None,
+            &LoopFeedback::default(),
+        );
+        assert!(!unfocused.contains("Task Focus"));
+        assert!(!unfocused.contains("Category Filter"));
+    }
+
+    #[test]
+    fn iteration_prompt_adds_the_focused_tasks_category_addendum() {
+        let prd = step_prd();
+        let mut master = MasterPrompt::default();
+        master
+            .addenda
+            .insert("Feature".to_string(), "Put it behind a flag.".to_string());
+        let prompt = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &RunState::new("prd.json"),
+            &BuildOptions::default(),
+            &master,
+            Some((0, &prd.tasks[0])),
+            &LoopFeedback::default(),
+        );
+        assert!(prompt.contains("## Notes for feature Tasks\n\nPut it behind a flag."));
+
+        let unfocused = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &RunState::new("prd.json"),
+            &BuildOptions::default(),
+            &master,
+            None,
+            &LoopFeedback::default(),
+        );
+        assert!(!unfocused.contains("behind a flag"));
+    }
+
+    #[test]
+    fn iteration_prompt_shows_recent_loops_unless_masked() {
+        let prd = step_prd();
+        let feedback = LoopFeedback {
+            recent_loops: Some("\n## Recent Loops\n\n- Loop 1\n".to_string()),
+            ..LoopFeedback::default()
+        };
+        let prompt = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &RunState::new("prd.json"),
+            &BuildOptions::default(),
+            &MasterPrompt::default(),
+            None,
+            &feedback,
+        );
+        assert!(prompt.contains("## Recent Loops"));
+
+        let masked = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &RunState::new("prd.json"),
+            &BuildOptions {
+                once: true,
+                ..BuildOptions::default()
+            },
+            &MasterPrompt::default(),
+            Some((0, &prd.tasks[0])),
+            &feedback,
+        );
+        assert!(!masked.contains("## Recent Loops"));
+    }
+
+    #[test]
+    fn iteration_prompt_includes_category_filter_and_reminder() {
+        let prd: prd::Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "Login", "steps": [], "passes": false},
+                {"category": "docs", "description": "Readme", "steps": [], "passes": false},
+                {"category": "Test", "description": "Cover login", "steps": [], "passes": false}
+            ]}"#,
+        )
+        .unwrap();
+        let opts = BuildOptions {
+            categories: vec!["feature".to_string(), "test".to_string()],
+            ..BuildOptions::default()
+        };
+        let feedback = LoopFeedback {
+            off_filter: Some((2, "docs".to_string())),
+            ..LoopFeedback::default()
+        };
+        let prompt = build_iteration_prompt(
+            "prd.json",
+            &prd,
+            &RunState::new("prd.json"),
+            &opts,
+            &MasterPrompt::default(),
+            None,
+            &feedback,
+        );
+        assert!(prompt.contains("## Category Filter"));
+        assert!(prompt.contains("tasks #1, #3"));
+        assert!(prompt.contains("task #2 ('docs')"));
+    }
+
+    #[test]
+    fn loop_failure_counts_blocked_and_rejected_loops() {
+        let feedback = LoopFeedback::default();
+        let completed = (1, "completed".to_string());
+        let blocked = (1, "blocked".to_string());
+        let progress = (1, "in_progress".to_string());
+        assert_eq!(loop_failure(Some(&completed), None, true, &feedback), None);
+        assert_eq!(loop_failure(Some(&progress), None, true, &feedback), None);
+        assert_eq!(
+            loop_failure(Some(&blocked), Some("needs a key"), true, &feedback).as_deref(),
+            Some("blocked: needs a key")
+        );
+        assert!(loop_failure(None, None, true, &feedback).is_some());
+        assert_eq!(
+            loop_failure(Some(&completed), None, false, &feedback).as_deref(),
+            Some("the quality gates failed")
+        );
+        let rejected = LoopFeedback {
+            verify_failure: Some("section".to_string()),
+            ..LoopFeedback::default()
+        };
+        assert!(loop_failure(Some(&completed), None, true, &rejected).is_some());
+        let reviewed = LoopFeedback {
+            review_rejection: Some("section".to_string()),
+            ..LoopFeedback::default()
+        };
+        assert_eq!(
+            loop_failure(Some(&completed), None, true, &reviewed).as_deref(),
+            Some("you rejected the task in review")
+        );
+    }
+
+    #[test]
+    fn dry_run_checks_flag_missing_gate_program() {
+        let prd: prd::Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": ["definitely-not-a-real-program-xyz --check"], "tasks": []}"#,
+        )
+        .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let found = dry_run_checks(&prd, "prd.json", &BuildOptions::default(), dir.path());
+        assert!(found.iter().any(|c| {
+            c.level == Level::Fail
+                && c.message
+                    .contains("definitely-not-a-real-program-xyz not found")
+        }));
+
+        let skipped = BuildOptions {
+            skip_gates: true,
+            ..Default::default()
+        };
+        let found = dry_run_checks(&prd, "prd.json", &skipped, dir.path());
+        assert!(!found.iter().any(|c| c.message.contains("definitely-not")));
+    }
+
+    #[test]
+    fn dry_run_checks_pass_gates_that_start_with_a_builtin() {
+        let prd: prd::Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": ["cd . && true"], "tasks": []}"#,
+        )
+        .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let found = dry_run_checks(&prd, "prd.json", &BuildOptions::default(), dir.path());
+        assert!(
+            found
+                .iter()
+                .any(|c| c.level == Level::Ok && c.message.starts_with("gate `cd . && true`"))
+        );
+    }
+
+    #[test]
+    fn dry_run_checks_branch_outside_repo_fails() {
+        let prd: prd::Prd =
+            serde_json::from_str(r#"{"name": "Test", "quality_gates": [], "tasks": []}"#).unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let opts = BuildOptions {
+            branch: Some(String::new()),
+            ..Default::default()
+        };
+        let found = dry_run_checks(&prd, "prd.json", &opts, dir.path());
+        assert!(found.iter().any(|c| {
+            c.level == Level::Fail
+                && c.message
+                    .contains("requires running inside a git repository")
+        }));
+    }
+
+    #[test]
+    fn pick_reads_a_numbered_choice() {
+        assert_eq!(pick("\n", 3), Some(0));
+        assert_eq!(pick(" 2\n", 3), Some(1));
+        assert_eq!(pick("4", 3), None);
+        assert_eq!(pick("0", 3), None);
+        assert_eq!(pick("prd.json", 3), None);
+    }
+
+    #[test]
+    fn is_yes_accepts_y_and_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES "));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("no"));
+    }
+
+    #[test]
+    fn default_branch_name_slugifies_prd_name() {
+        assert_eq!(default_branch_name("My Todo App"), "ralph/my-todo-app");
+        assert_eq!(default_branch_name("  API v2 (beta)!"), "ralph/api-v2-beta");
+        assert_eq!(default_branch_name("???"), "ralph/build");
+    }
+
+    #[test]
+    fn format_duration_prefers_minutes() {
+        assert_eq!(format_duration(Duration::from_secs(15 * 60)), "15m");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_secs(30)), "30s");
+    }
+}
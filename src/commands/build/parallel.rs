@@ -0,0 +1,685 @@
+//! The `--parallel` orchestrator: ready tasks handed to workers, each Claude in its own
+//! git work tree, merged back one by one as they finish. Which tasks can run side by side
+//! is `ralph_core::scheduler`'s call.
+
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use ratatui::Terminal;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::{
+    BuildOptions, archive_passing, format_duration, format_gate_log, handle_claude_input,
+    journal_loop, keys, next_input, parse_and_repair, run_quality_gates, take_checkpoint,
+};
+use crate::app::{App, WorkerPane};
+use crate::error::RalphError;
+use crate::runtime;
+use crate::tui;
+use ralph_core::claude::Thinking;
+use ralph_core::events::Event as RunEvent;
+use ralph_core::gates;
+use ralph_core::git;
+use ralph_core::iteration::{self, Outcome, find_task};
+use ralph_core::loop_log::{LoopLog, RawOutput};
+use ralph_core::prd;
+use ralph_core::process;
+use ralph_core::progress;
+use ralph_core::prompt::{self, MasterPrompt};
+use ralph_core::run_state::{RunState, TaskState};
+use ralph_core::scheduler;
+use ralph_core::stats::{self, IterationRecord, Stats};
+
+/// A `--parallel` worker: Claude doing one task in its own work tree, on its own branch
+struct Worker {
+    /// Description of the task, which stays put while task numbers shift
+    description: String,
+    category: String,
+    /// 1-indexed task number when the worker started
+    number: usize,
+    /// Pane the worker is shown in
+    slot: usize,
+    /// Loop the worker runs as
+    loop_number: u64,
+    tree: PathBuf,
+    branch: String,
+    /// Commit the worker's branch started from
+    base: String,
+    thinking: Option<Thinking>,
+    child: tokio::process::Child,
+    output: process::Output,
+    started: Instant,
+}
+
+/// How a worker's Claude ended
+enum WorkerEnd {
+    /// Claude exited on its own
+    Exited {
+        raw: RawOutput,
+        report: Box<iteration::Report>,
+    },
+    /// Killed after running past the loop timeout
+    TimedOut,
+    /// Killed because the user stopped the workers or the run
+    Stopped,
+    /// Claude couldn't be waited on
+    Failed(io::Error),
+}
+
+/// What the wait for the workers came back with
+enum Waited {
+    /// The worker at this position exited
+    Exited(usize),
+    /// The worker at this position ran past the loop timeout
+    TimedOut(usize),
+    /// Waiting on the worker at this position failed
+    Failed(usize, io::Error),
+    /// The user stopped every worker
+    Stopped,
+}
+
+/// Pending tasks a worker has failed this run; they're left to normal loops, which tell
+/// Claude what went wrong
+pub(super) fn failed_tasks<'a>(run_state: &RunState, prd: &'a prd::Prd) -> Vec<&'a str> {
+    prd.tasks
+        .iter()
+        .filter(|t| !t.passes)
+        .filter(|t| {
+            run_state
+                .task_state(&t.description)
+                .is_some_and(|state| state.is_failure())
+        })
+        .map(|t| t.description.as_str())
+        .collect()
+}
+
+/// The `--parallel` orchestrator. Ready tasks go to free workers, each Claude working in its
+/// own git work tree, started from the latest HEAD. Each worker's branch is merged as soon as
+/// it finishes, and the gates run on the result, so tasks depending on it become ready for the
+/// next free worker. A branch that conflicts or fails the gates is left unmerged and its task
+/// pending. Every worker counts as a loop, and every task's moves are kept in the run state.
+/// Returns once nothing is running and no ready task is left for a worker.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn orchestrate<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    run_state: &mut RunState,
+    prd_path: &str,
+    master: &MasterPrompt,
+    options: &iteration::Options,
+    opts: &BuildOptions,
+    mut loops_left: u64,
+) -> Result<(), RalphError> {
+    let run_id: String = run_state.id.chars().take(8).collect();
+    let trees = std::env::temp_dir().join(format!("ralph-{}", run_id));
+    let mut workers: Vec<Worker> = Vec::new();
+    let mut completed = 0;
+    let mut started = 0;
+    app.start_loop_timer();
+
+    loop {
+        // Hand ready tasks to free workers
+        if !app.should_quit
+            && !app.budget_exceeded()
+            && tui::termination_signal().is_none()
+            && loops_left > 0
+            && workers.len() < opts.parallel
+        {
+            let prd = prd::read_prd(prd_path)?;
+            let running: Vec<&str> = workers.iter().map(|w| w.description.as_str()).collect();
+            let skip = failed_tasks(run_state, &prd);
+            let free = (opts.parallel - workers.len()).min(loops_left as usize);
+            for index in scheduler::pick(&prd, &opts.categories, &running, &skip, free) {
+                let slot = (0..opts.parallel)
+                    .find(|slot| !workers.iter().any(|w| w.slot == *slot))
+                    .unwrap_or_default();
+                let started_worker = start_worker(
+                    app, run_state, prd_path, &prd, index, slot, &trees, master, options,
+                );
+                if let Some(worker) = started_worker {
+                    workers.push(worker);
+                    started += 1;
+                    loops_left -= 1;
+                }
+            }
+        }
+        if workers.is_empty() {
+            break;
+        }
+
+        app.set_status(&format!(
+            "{} worker(s) running... (q=stop starting more, Ctrl+X=stop workers, Ctrl+C=kill)",
+            workers.len()
+        ));
+        terminal.draw(|f| app.draw(f))?;
+        match wait_for_worker(terminal, app, &mut workers, opts.loop_timeout)? {
+            Waited::Exited(i) => {
+                let mut worker = workers.remove(i);
+                let left = opts
+                    .loop_timeout
+                    .map(|limit| limit.saturating_sub(worker.started.elapsed()));
+                let (stdout, stderr, report) = runtime::block_on(async {
+                    let (stdout, stderr) = worker.output.collect().await;
+                    let report = parse_and_repair(terminal, app, &stdout, &stderr, left).await;
+                    (stdout, stderr, report)
+                });
+                // A stopped repair leaves the output as Claude wrote it
+                let report =
+                    Box::new(report?.unwrap_or_else(|_| iteration::parse_output(&stdout, &stderr)));
+                let end = WorkerEnd::Exited {
+                    raw: RawOutput { stdout, stderr },
+                    report,
+                };
+                completed += usize::from(finish_worker(
+                    terminal, app, run_state, prd_path, worker, end, opts,
+                )?);
+            }
+            Waited::TimedOut(i) => {
+                let mut worker = workers.remove(i);
+                stop_worker(&mut worker);
+                finish_worker(
+                    terminal,
+                    app,
+                    run_state,
+                    prd_path,
+                    worker,
+                    WorkerEnd::TimedOut,
+                    opts,
+                )?;
+            }
+            Waited::Failed(i, e) => {
+                let mut worker = workers.remove(i);
+                stop_worker(&mut worker);
+                finish_worker(
+                    terminal,
+                    app,
+                    run_state,
+                    prd_path,
+                    worker,
+                    WorkerEnd::Failed(e),
+                    opts,
+                )?;
+            }
+            Waited::Stopped => {
+                for mut worker in std::mem::take(&mut workers) {
+                    stop_worker(&mut worker);
+                    finish_worker(
+                        terminal,
+                        app,
+                        run_state,
+                        prd_path,
+                        worker,
+                        WorkerEnd::Stopped,
+                        opts,
+                    )?;
+                }
+                // Ctrl+X stops the workers but not the run
+                if !app.should_quit {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = std::fs::remove_dir(&trees);
+
+    if !app.should_quit {
+        app.set_status(&format!(
+            "Workers done: {} of {} task(s) completed",
+            completed, started
+        ));
+    }
+    Ok(())
+}
+
+/// Give the task at `index` to a new worker in pane `slot`: a work tree on a fresh branch
+/// from HEAD, and Claude started in it. Counts as a loop.
+#[allow(clippy::too_many_arguments)]
+fn start_worker(
+    app: &mut App,
+    run_state: &mut RunState,
+    prd_path: &str,
+    prd: &prd::Prd,
+    index: usize,
+    slot: usize,
+    trees: &Path,
+    master: &MasterPrompt,
+    options: &iteration::Options,
+) -> Option<Worker> {
+    let work_dir = Path::new(".");
+    let task = &prd.tasks[index];
+    let number = index + 1;
+    let base = match git::head_commit(work_dir) {
+        Ok(Some(base)) => base,
+        Ok(None) => return None,
+        Err(e) => {
+            app.push_log(format!("Warning: can't start a worker: {}", e));
+            return None;
+        }
+    };
+
+    app.increment_loop();
+    run_state.record_loop(app.loop_count);
+    take_checkpoint(app, run_state, prd_path, work_dir);
+    let loop_number = app.loop_count;
+    let branch = scheduler::branch_name(&run_state.id, loop_number, number);
+    let tree = trees.join(format!("loop-{}-task-{}", loop_number, number));
+    if let Err(e) = git::add_worktree(work_dir, &tree, &branch, &base) {
+        app.push_log(format!(
+            "Warning: couldn't set up a work tree for task #{}: {}",
+            number, e
+        ));
+        run_state.record_task_state(&task.description, TaskState::Failed, loop_number);
+        if let Err(e) = run_state.save() {
+            app.push_log(format!("Warning: {}", e));
+        }
+        return None;
+    }
+    let thinking = task.thinking.or(options.thinking);
+    // A monorepo task's worker runs in its package within the work tree
+    let workdir = prd
+        .workdir_for(Some(task))
+        .filter(|dir| tree.join(dir).is_dir());
+    let (mut prompt, cwd, add_dirs) = match workdir {
+        Some(dir) => {
+            let mut prompt = scheduler::worker_prompt(
+                &master.in_workdir(dir),
+                &prompt::path_from_workdir(dir, prd_path),
+                number,
+                task,
+            );
+            prompt.push_str(&prompt::workdir_section(dir));
+            let mut add_dirs = options.add_dirs.to_vec();
+            add_dirs.push(prompt::root_from(dir));
+            (prompt, tree.join(dir), add_dirs)
+        }
+        None => (
+            scheduler::worker_prompt(master, prd_path, number, task),
+            tree.clone(),
+            options.add_dirs.to_vec(),
+        ),
+    };
+    match progress::recent_journal(work_dir, progress::JOURNAL_TAIL) {
+        Ok(entries) => prompt.extend(progress::journal_section(&entries)),
+        Err(e) => app.push_log(format!(
+            "Warning: can't read {}: {}",
+            progress::JOURNAL_FILE,
+            e
+        )),
+    }
+    let launched = runtime::block_on(async {
+        let mut child = iteration::launch(
+            &prompt,
+            &iteration::Options {
+                cwd: Some(&cwd),
+                add_dirs: &add_dirs,
+                thinking,
+                ..*options
+            },
+        )?;
+        let output = process::Output::capture(&mut child);
+        io::Result::Ok((child, output))
+    });
+    let (child, output) = match launched {
+        Ok(launched) => launched,
+        Err(e) => {
+            app.push_log(format!(
+                "Worker on task #{} couldn't start Claude: {}",
+                number, e
+            ));
+            let _ = git::remove_worktree(work_dir, &tree);
+            let _ = git::delete_branch(work_dir, &branch);
+            run_state.record_task_state(&task.description, TaskState::Failed, loop_number);
+            if let Err(e) = run_state.save() {
+                app.push_log(format!("Warning: {}", e));
+            }
+            return None;
+        }
+    };
+
+    run_state.record_task_state(&task.description, TaskState::Running, loop_number);
+    if let Err(e) = run_state.save() {
+        app.push_log(format!("Warning: {}", e));
+    }
+    let pane = WorkerPane::new(number, &task.description);
+    match app.workers.get_mut(slot) {
+        Some(existing) => *existing = pane,
+        None => app.workers.push(pane),
+    }
+    app.push_log(format!(
+        "Loop #{}: worker {} started on task #{}: {}",
+        loop_number,
+        slot + 1,
+        number,
+        task.description
+    ));
+    if let Some(events) = &app.events {
+        events.emit(&RunEvent::LoopStarted {
+            loop_number,
+            task: Some(number),
+            model: options.model,
+            thinking: thinking.map(Thinking::name),
+        });
+    }
+    tracing::info!(loop_number, task = number, %branch, "Worker started");
+    Some(Worker {
+        description: task.description.clone(),
+        category: task.category.clone(),
+        number,
+        slot,
+        loop_number,
+        tree,
+        branch,
+        base,
+        thinking,
+        child,
+        output,
+        started: Instant::now(),
+    })
+}
+
+/// Kill a worker's Claude and reap it
+fn stop_worker(worker: &mut Worker) {
+    let _ = process::kill_tree(&mut worker.child);
+    let _ = runtime::block_on(worker.child.wait());
+}
+
+/// Wait until a worker's Claude exits or runs out of time, keeping the TUI responsive.
+/// Ctrl+C stops every worker and the run, Ctrl+X just the workers.
+fn wait_for_worker<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    workers: &mut [Worker],
+    timeout: Option<Duration>,
+) -> Result<Waited, RalphError> {
+    runtime::block_on(async {
+        let mut exits: FuturesUnordered<_> = workers
+            .iter_mut()
+            .enumerate()
+            .map(|(i, worker)| {
+                let left = timeout.map(|limit| limit.saturating_sub(worker.started.elapsed()));
+                async move {
+                    tokio::select! {
+                        status = worker.child.wait() => match status {
+                            Ok(_) => Waited::Exited(i),
+                            Err(e) => Waited::Failed(i, e),
+                        },
+                        () = runtime::sleep_or_forever(left) => Waited::TimedOut(i),
+                    }
+                }
+            })
+            .collect();
+        let mut keys = keys(app);
+        let mut redraw = runtime::redraw_timer();
+
+        loop {
+            tokio::select! {
+                Some(waited) = exits.next() => return Ok(waited),
+                input = next_input(&mut keys) => {
+                    if handle_claude_input(app, input?).is_some() {
+                        return Ok(Waited::Stopped);
+                    }
+                }
+                _ = redraw.tick() => app.advance_spinner(),
+            }
+            terminal.draw(|f| app.draw(f))?;
+        }
+    })
+}
+
+/// Deal with a finished worker: remove its work tree, merge its branch if it reported back
+/// with commits, check the gates, and record the loop. Returns whether its task now passes.
+fn finish_worker<B: ratatui::backend::Backend<Error = io::Error>>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    run_state: &mut RunState,
+    prd_path: &str,
+    worker: Worker,
+    end: WorkerEnd,
+    opts: &BuildOptions,
+) -> Result<bool, RalphError> {
+    let work_dir = Path::new(".");
+    let log_start = app.iteration_logs.len();
+    let number = worker.number;
+    let commits = git::commits_since(&worker.tree, Some(&worker.base)).unwrap_or_default();
+    if let Err(e) = git::remove_worktree(work_dir, &worker.tree) {
+        app.push_log(format!(
+            "Warning: failed to remove the work tree for task #{}: {}",
+            number, e
+        ));
+    }
+
+    let mut cost_usd = 0.0;
+    let mut model = None;
+    let mut summary = None;
+    let mut parsed = None;
+    let mut attempts = Vec::new();
+    let mut status = match end {
+        WorkerEnd::Exited { raw, report } => {
+            attempts.push(RawOutput {
+                stdout: app.redactor.redact(&raw.stdout),
+                stderr: app.redactor.redact(&raw.stderr),
+            });
+            let report = *report;
+            if let Some(spend) = report.spend {
+                app.record_usage(spend.cost_usd, &spend.usage);
+                cost_usd = spend.cost_usd;
+                model = spend.model;
+            }
+            match report.outcome {
+                Outcome::Success(mut result) => {
+                    result.summary = app.redactor.redact(&result.summary);
+                    parsed = serde_json::to_value(&result).ok();
+                    app.push_log(format!(
+                        "Task #{}: {}\nSummary: {}",
+                        number, result.status, result.summary
+                    ));
+                    summary = Some(result.summary);
+                    result.status
+                }
+                Outcome::ClaudeError(message)
+                | Outcome::TransientError(message)
+                | Outcome::ParseError(message) => {
+                    app.push_log(format!(
+                        "Worker on task #{} failed: {}",
+                        number,
+                        app.redactor.redact(&message)
+                    ));
+                    "failed".to_string()
+                }
+            }
+        }
+        WorkerEnd::TimedOut => {
+            app.push_log(format!(
+                "Worker on task #{} timed out after {} and was killed",
+                number,
+                format_duration(opts.loop_timeout.unwrap_or_default())
+            ));
+            "failed".to_string()
+        }
+        WorkerEnd::Stopped if app.should_quit => stats::INTERRUPTED.to_string(),
+        WorkerEnd::Stopped => stats::ABORTED.to_string(),
+        WorkerEnd::Failed(e) => {
+            app.push_log(format!("Worker on task #{} couldn't run: {}", number, e));
+            "failed".to_string()
+        }
+    };
+    let mut state = match status.as_str() {
+        "failed" => TaskState::Failed,
+        _ if summary.is_none() => TaskState::Stopped,
+        _ => TaskState::Merged,
+    };
+
+    // Committed work from a worker that reported back is merged, even a partial task
+    let mut commit = None;
+    let mut files = Vec::new();
+    let mut passes = false;
+    let mut keep_branch = !commits.is_empty();
+    if summary.is_some() && commits.is_empty() {
+        if status == "completed" {
+            app.push_log(format!(
+                "Task #{} was reported completed but nothing was committed; it stays pending",
+                number
+            ));
+            status = "no-op".to_string();
+            state = TaskState::Failed;
+        } else {
+            state = TaskState::Stopped;
+        }
+    } else if summary.is_some() && !app.should_quit {
+        app.set_status(&format!("Merging task #{}...", number));
+        let before = git::head_commit(work_dir).ok().flatten();
+        let message = format!("Merge task #{}: {}", number, worker.description);
+        match git::merge(work_dir, &worker.branch, &message) {
+            Ok(git::Merge::Merged) => {
+                let prd = prd::read_prd(prd_path)?;
+                let gate_dir = prd
+                    .workdir_for(find_task(&prd, &worker.description).map(|(_, task)| task))
+                    .map(|dir| work_dir.join(dir))
+                    .filter(|dir| dir.is_dir());
+                let gates_passed = opts.skip_gates
+                    || prd.quality_gates.is_empty()
+                    || (run_quality_gates(
+                        terminal,
+                        app,
+                        &prd.quality_gates,
+                        opts.parallel_gates,
+                        gate_dir.as_deref(),
+                    )? && gates::failure_report(&app.gate_results).is_none());
+                if !opts.skip_gates && !prd.quality_gates.is_empty() {
+                    app.push_log(format_gate_log(&app.gate_results));
+                }
+                if gates_passed {
+                    keep_branch = false;
+                    commit = git::head_commit(work_dir).ok().flatten();
+                    files =
+                        git::files_changed_since(work_dir, before.as_deref()).unwrap_or_default();
+                    if status == "completed" {
+                        state = TaskState::Completed;
+                        match prd::set_task_passes(prd_path, &worker.description, true) {
+                            Ok(_) => passes = true,
+                            Err(e) => {
+                                app.push_log(format!("Warning: failed to update the PRD: {}", e))
+                            }
+                        }
+                    }
+                } else {
+                    status = "gates_failed".to_string();
+                    state = TaskState::GatesFailed;
+                    let undone = before.as_deref().map(|b| git::reset_keep(work_dir, b));
+                    app.push_log(match undone {
+                        Some(Ok(())) => format!(
+                            "Task #{} fails the quality gates once merged, so the merge was \
+dropped. Its commits are left on branch {} and the task stays pending.",
+                            number, worker.branch
+                        ),
+                        Some(Err(e)) => format!(
+                            "Warning: task #{} fails the quality gates once merged and the \
+merge couldn't be dropped: {}",
+                            number, e
+                        ),
+                        None => format!(
+                            "Warning: task #{} fails the quality gates once merged",
+                            number
+                        ),
+                    });
+                }
+            }
+            Ok(git::Merge::Conflict(files)) => {
+                status = "conflict".to_string();
+                state = TaskState::Conflicted;
+                app.push_log(format!(
+                    "Task #{} conflicts with work merged before it ({}). Its commits are left on \
+branch {} and the task stays pending.",
+                    number,
+                    files.join(", "),
+                    worker.branch
+                ));
+            }
+            Err(e) => {
+                status = "failed".to_string();
+                state = TaskState::Failed;
+                app.push_log(format!(
+                    "Warning: failed to merge task #{}: {}. Its commits are left on branch {}.",
+                    number, e, worker.branch
+                ));
+            }
+        }
+    } else if keep_branch {
+        state = TaskState::Stopped;
+        app.push_log(format!(
+            "Task #{}'s commits weren't merged and are left on branch {}",
+            number, worker.branch
+        ));
+    }
+    if !keep_branch && let Err(e) = git::delete_branch(work_dir, &worker.branch) {
+        app.push_log(format!("Warning: {}", e));
+    }
+    if passes {
+        archive_passing(app, prd_path, work_dir);
+    }
+    if let Some(pane) = app.workers.get_mut(worker.slot) {
+        pane.finish(&status);
+    }
+    run_state.record_task_state(&worker.description, state, worker.loop_number);
+    if let Err(e) = run_state.save() {
+        app.push_log(format!("Warning: {}", e));
+    }
+    tracing::info!(
+        loop_number = worker.loop_number,
+        task = number,
+        state = state.name(),
+        "Worker finished"
+    );
+
+    let record = IterationRecord {
+        run_id: run_state.id.clone(),
+        run_label: run_state.label.clone(),
+        loop_number: worker.loop_number,
+        task_number: Some(number as i32),
+        task: Some(worker.description.clone()),
+        category: Some(worker.category),
+        model,
+        status,
+        summary,
+        commit,
+        duration_secs: worker.started.elapsed().as_secs_f64(),
+        cost_usd,
+        finished_at: chrono::Utc::now(),
+        rating: None,
+        rating_note: None,
+        escalation: None,
+        thinking: worker.thinking.map(|t| t.name().to_string()),
+    };
+    if let Some(events) = &app.events
+        && passes
+    {
+        events.emit(&RunEvent::TaskCompleted {
+            loop_number: record.loop_number,
+            task: number as i32,
+            description: Some(&worker.description),
+            summary: record.summary.as_deref(),
+            commit: record.commit.as_deref(),
+            cost_usd: record.cost_usd,
+        });
+    }
+    let loop_log = LoopLog {
+        run_id: record.run_id.clone(),
+        loop_number: record.loop_number,
+        status: record.status.clone(),
+        finished_at: record.finished_at,
+        parsed,
+        attempts,
+        log: app.iteration_logs[log_start..].to_vec(),
+    };
+    if let Err(e) = loop_log.save(prd_path) {
+        app.push_log(format!("Warning: {}", e));
+    }
+    journal_loop(app, &record, files, work_dir);
+    match Stats::append(prd_path, record) {
+        Ok(()) => app.last_finished_loop = Some(worker.loop_number),
+        Err(e) => app.push_log(format!("Warning: {}", e)),
+    }
+    terminal.draw(|f| app.draw(f))?;
+    Ok(passes)
+}
@@ -0,0 +1,143 @@
+//! `ralph daemon`: stay running and start build passes on a schedule.
+//!
+//! Each pass is a headless `ralph build` in a process of its own, labelled with the time it
+//! started, so it lands in the run history like any other run and a pass that fails can't
+//! take the daemon down with it. The daemon exits once the PRD has no pending tasks.
+
+use chrono::Local;
+use clap::Args;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::app::print_timestamped;
+use crate::commands::{CommandResult, Registered};
+use crate::config::{self, ConfigError};
+use ralph_core::prd::{self, PrdError};
+use ralph_core::schedule::{Cron, ScheduleError};
+
+/// Longest single sleep while waiting for a pass, so a suspended machine doesn't oversleep
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug)]
+pub enum DaemonError {
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+
+    #[error("{0}")]
+    Schedule(#[from] ScheduleError),
+
+    #[error("{0}")]
+    Prd(#[from] PrdError),
+
+    #[error("Failed to start a build pass: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("No schedule: pass --schedule or set `schedule` under [daemon] in .ralph.toml")]
+    NoSchedule,
+
+    #[error("The schedule '{0}' never comes round")]
+    NeverDue(Cron),
+}
+
+/// Stay running and start a headless build pass on a cron schedule while tasks remain
+#[derive(Args, Debug)]
+pub struct DaemonArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+
+    /// Cron schedule in local time (minute hour day month weekday), e.g. "0 2 * * *" for
+    /// 2am every night; overrides `schedule` under [daemon] in .ralph.toml
+    #[arg(long, value_name = "CRON")]
+    pub schedule: Option<String>,
+
+    /// Start the first pass straight away, then follow the schedule
+    #[arg(long)]
+    pub now: bool,
+
+    /// Stop after this many passes
+    #[arg(long, value_name = "N")]
+    pub max_passes: Option<u64>,
+}
+
+pub static COMMAND: Registered<DaemonArgs> = Registered::new("daemon", run_args);
+
+fn run_args(args: DaemonArgs) -> CommandResult {
+    Ok(run(&args)?)
+}
+
+/// Run build passes on the schedule until the PRD is complete, the pass limit is reached or
+/// a pass is stopped by a signal
+pub fn run(args: &DaemonArgs) -> Result<(), DaemonError> {
+    let config = config::load(Path::new("."))?;
+    let cron: Cron = args
+        .schedule
+        .as_deref()
+        .or(config.daemon.schedule.as_deref())
+        .ok_or(DaemonError::NoSchedule)?
+        .parse()?;
+    let ralph = std::env::current_exe()?;
+    let mut passes = 0;
+    let mut due = args.now;
+
+    loop {
+        let prd = prd::read_prd(&args.prd_path)?;
+        let pending = prd.tasks.iter().filter(|t| !t.passes).count();
+        if pending == 0 {
+            print_timestamped(&format!(
+                "PRD {} is complete; the daemon is stopping",
+                prd.name
+            ));
+            return Ok(());
+        }
+        if args.max_passes.is_some_and(|max| passes >= max) {
+            print_timestamped(&format!(
+                "Ran {} pass(es) with {} task(s) still pending; the daemon is stopping",
+                passes, pending
+            ));
+            return Ok(());
+        }
+        if !due {
+            let next = cron
+                .next_after(Local::now().naive_local())
+                .ok_or_else(|| DaemonError::NeverDue(cron.clone()))?;
+            print_timestamped(&format!(
+                "Next build pass at {} ({}), {} task(s) pending",
+                next.format("%Y-%m-%d %H:%M"),
+                cron,
+                pending
+            ));
+            sleep_until(next);
+        }
+        due = false;
+
+        passes += 1;
+        let label = format!("daemon {}", Local::now().format("%Y-%m-%d %H:%M"));
+        print_timestamped(&format!("Starting build pass #{}: {}", passes, label));
+        let status = Command::new(&ralph)
+            .args(["build", "--no-tui", "--prd-path", &args.prd_path, "--label"])
+            .arg(&label)
+            .args(&config.daemon.build_args)
+            .status()?;
+        print_timestamped(&format!("Build pass #{} finished: {}", passes, status));
+        // 128 + SIGINT or SIGTERM: someone stopped the pass, so stop the daemon too
+        if status.code().is_none_or(|code| code == 130 || code == 143) {
+            print_timestamped("The build pass was stopped by a signal; the daemon is stopping");
+            return Ok(());
+        }
+    }
+}
+
+/// Sleep until the local clock reaches `at`, a minute at a time
+fn sleep_until(at: chrono::NaiveDateTime) {
+    loop {
+        let left = at - Local::now().naive_local();
+        match left.to_std() {
+            Ok(left) if !left.is_zero() => std::thread::sleep(left.min(MAX_SLEEP)),
+            _ => return,
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! `ralph doctor`: check the environment a build or plan needs before starting one, with
+//! a fix for everything that's wrong.
+
+use clap::Args;
+use std::io::IsTerminal;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::checks::{self, Check, Level};
+use crate::commands::{CommandResult, Registered};
+use crate::tui;
+use ralph_core::gates;
+use ralph_core::git;
+use ralph_core::prd;
+
+#[derive(Error, Debug)]
+pub enum DoctorError {
+    #[error("{0} check(s) failed")]
+    Failed(usize),
+}
+
+/// Check that claude, git, the PRD, its quality gates and the terminal are ready for ralph
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+}
+
+pub static COMMAND: Registered<DoctorArgs> = Registered::new("doctor", run_args);
+
+fn run_args(args: DoctorArgs) -> CommandResult {
+    Ok(run(&args.prd_path)?)
+}
+
+/// Run every check and print the outcomes; fails if any check did
+pub fn run(prd_path: &str) -> Result<(), DoctorError> {
+    let root = Path::new(".");
+    let mut found = vec![checks::claude()];
+    found.extend(git_checks(root));
+    found.extend(prd_checks(prd_path, root));
+    found.push(terminal_check());
+
+    for check in &found {
+        check.print("");
+    }
+    match found.iter().filter(|c| c.level == Level::Fail).count() {
+        0 => Ok(()),
+        failed => Err(DoctorError::Failed(failed)),
+    }
+}
+
+fn git_checks(root: &Path) -> Vec<Check> {
+    if !gates::program_on_path("git") {
+        return vec![Check::fail(
+            "git not found on PATH",
+            "install git; ralph uses it to verify and checkpoint each loop's commits",
+        )];
+    }
+    if !git::is_repo(root) {
+        return vec![Check::warn(
+            "not a git repository (commits can't be verified)",
+            "run `git init` and commit the project",
+        )];
+    }
+    match git::has_uncommitted_changes(root) {
+        Ok(false) => vec![Check::ok("git repository, working tree clean")],
+        Ok(true) => vec![Check::warn(
+            "the working tree has uncommitted changes",
+            "commit or stash them so each loop's changes are its own",
+        )],
+        Err(e) => vec![Check::fail(
+            format!("git status failed: {}", e),
+            "check that `git status` works here",
+        )],
+    }
+}
+
+fn prd_checks(prd_path: &str, root: &Path) -> Vec<Check> {
+    let prd = match prd::read_prd(prd_path) {
+        Ok(prd) => prd,
+        Err(prd::PrdError::NotFound(_)) => {
+            return vec![Check::fail(
+                format!("no PRD at {}", prd_path),
+                "create one with `ralph plan`, or pass --prd-path",
+            )];
+        }
+        Err(e) => {
+            return vec![Check::fail(
+                e.to_string(),
+                format!(
+                    "fix the file, or regenerate it with `ralph plan -o {}`",
+                    prd_path
+                ),
+            )];
+        }
+    };
+    let mut found = vec![Check::ok(format!(
+        "PRD {} parses ({} task(s))",
+        prd_path,
+        prd.tasks.len()
+    ))];
+    found.extend(checks::quality_gates(&prd, prd_path));
+    found.extend(checks::workdirs(&prd, prd_path, root));
+    found
+}
+
+fn terminal_check() -> Check {
+    if !std::io::stdin().is_terminal() || !tui::is_interactive() {
+        return Check::warn(
+            "not running in a terminal: `ralph build` will run headless, `ralph plan` won't start",
+            "run ralph from an interactive terminal for the TUI",
+        );
+    }
+    match tui::probe_raw_mode() {
+        Ok(()) => Check::ok("terminal supports raw mode"),
+        Err(e) => Check::fail(
+            format!("the terminal can't switch to raw mode: {}", e),
+            "use a different terminal emulator, or run `ralph build --no-tui`",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn checks_the_prd_and_its_gates() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("prd.json");
+        let path = path.to_str().unwrap();
+        let missing = prd_checks(path, dir.path());
+        assert_eq!(missing[0].level, Level::Fail);
+        assert!(missing[0].fix.as_deref().unwrap().contains("ralph plan"));
+
+        std::fs::write(
+            path,
+            r#"{"name": "Demo", "quality_gates": ["ralph-doctor-no-such-program --all"], "tasks": []}"#,
+        )
+        .unwrap();
+        let found = prd_checks(path, dir.path());
+        assert_eq!(found[0].level, Level::Ok);
+        assert_eq!(found[1].level, Level::Fail);
+        assert!(
+            found[1]
+                .message
+                .contains("ralph-doctor-no-such-program not found on PATH")
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use chrono::{DateTime, Local, Utc};
+use clap::Args;
+use thiserror::Error;
+
+use crate::app::format_secs;
+use crate::commands::{CommandResult, Registered};
+use ralph_core::loop_log::{LoopLog, LoopLogError};
+use ralph_core::stats::{IterationRecord, Stats, StatsError};
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("{0}")]
+    Stats(#[from] StatsError),
+
+    #[error("No run matches '{0}'. Run `ralph history` to list them.")]
+    NoSuchRun(String),
+
+    #[error("'{0}' matches more than one run; give more of the run id")]
+    AmbiguousRun(String),
+
+    #[error("{0}")]
+    LoopLog(#[from] LoopLogError),
+
+    #[error(
+        "No log file for loop #{0} of this run (logs are kept from runs since they were added)"
+    )]
+    NoLoopLog(u64),
+}
+
+/// Browse past build runs and every loop they ran
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Show every loop of this run, by label or id (a unique prefix is enough)
+    #[arg(value_name = "RUN")]
+    pub run: Option<String>,
+
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+
+    /// List at most this many runs
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Show the saved log of this loop of RUN: what ralph logged and Claude's raw output
+    #[arg(long = "loop", value_name = "N", requires = "run")]
+    pub loop_number: Option<u64>,
+}
+
+pub static COMMAND: Registered<HistoryArgs> = Registered::new("history", run_args);
+
+fn run_args(args: HistoryArgs) -> CommandResult {
+    if let (Some(query), Some(n)) = (args.run.as_deref(), args.loop_number) {
+        return Ok(show_loop(&args.prd_path, query, n)?);
+    }
+    Ok(run(&args.prd_path, args.run.as_deref(), args.limit)?)
+}
+
+/// The loops of one build run, oldest first
+struct Run<'a> {
+    id: &'a str,
+    label: Option<&'a str>,
+    loops: Vec<&'a IterationRecord>,
+}
+
+impl Run<'_> {
+    fn last_finished(&self) -> DateTime<Utc> {
+        self.loops
+            .iter()
+            .map(|r| r.finished_at)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn cost(&self) -> f64 {
+        self.loops.iter().map(|r| r.cost_usd).sum()
+    }
+
+    fn completed(&self) -> usize {
+        self.loops
+            .iter()
+            .filter(|r| r.status == "completed")
+            .count()
+    }
+}
+
+/// Group records by run, most recently active run first
+fn runs(stats: &Stats) -> Vec<Run<'_>> {
+    let mut runs: Vec<Run> = Vec::new();
+    for record in &stats.iterations {
+        match runs.iter_mut().find(|run| run.id == record.run_id) {
+            Some(run) => {
+                run.loops.push(record);
+                if record.run_label.is_some() {
+                    run.label = record.run_label.as_deref();
+                }
+            }
+            None => runs.push(Run {
+                id: &record.run_id,
+                label: record.run_label.as_deref(),
+                loops: vec![record],
+            }),
+        }
+    }
+    runs.sort_by_key(|run| std::cmp::Reverse(run.last_finished()));
+    runs
+}
+
+/// The one run whose label is `query` or whose id starts with it
+fn find_run<'s, 'a>(runs: &'s [Run<'a>], query: &str) -> Result<&'s Run<'a>, HistoryError> {
+    let matches: Vec<&Run> = runs
+        .iter()
+        .filter(|run| run.label == Some(query) || run.id.starts_with(query))
+        .collect();
+    match matches.as_slice() {
+        [run] => Ok(run),
+        [] => Err(HistoryError::NoSuchRun(query.to_string())),
+        _ => Err(HistoryError::AmbiguousRun(query.to_string())),
+    }
+}
+
+/// Run the history command - list past runs, or the loops of one run
+pub fn run(prd_path: &str, query: Option<&str>, limit: usize) -> Result<(), HistoryError> {
+    let stats = Stats::load(prd_path)?;
+    let runs = runs(&stats);
+    if runs.is_empty() {
+        println!(
+            "No runs recorded for {} yet. History is kept by `ralph build`.",
+            prd_path
+        );
+        return Ok(());
+    }
+
+    let Some(query) = query else {
+        println!("Runs for {} (most recent first):", prd_path);
+        println!(
+            "  {:<8}  {:<16}  {:>5}  {:>4}  {:>8}  label",
+            "run", "last loop", "loops", "done", "cost"
+        );
+        for run in runs.iter().take(limit) {
+            println!(
+                "  {:<8}  {:<16}  {:>5}  {:>4}  {:>8}  {}",
+                short(run.id, 8),
+                local_time(run.last_finished()),
+                run.loops.len(),
+                run.completed(),
+                format!("${:.2}", run.cost()),
+                run.label.unwrap_or("-")
+            );
+        }
+        if runs.len() > limit {
+            println!(
+                "  ... {} older run(s); use -n to see more",
+                runs.len() - limit
+            );
+        }
+        return Ok(());
+    };
+
+    let run = find_run(&runs, query)?;
+    match run.label {
+        Some(label) => println!("Run {} ({})", run.id, label),
+        None => println!("Run {}", run.id),
+    }
+    println!(
+        "{} loop(s), {} completed, ${:.2}\n",
+        run.loops.len(),
+        run.completed(),
+        run.cost()
+    );
+    for record in &run.loops {
+        let task = record
+            .task_number
+            .map_or_else(|| "-".to_string(), |n| format!("task {}", n));
+        println!(
+            "  #{:<3} {}  {:<8} {:<11} {:>7}  {:>6}  {}",
+            record.loop_number,
+            local_time(record.finished_at),
+            task,
+            record.status,
+            format_secs(record.duration_secs),
+            format!("${:.2}", record.cost_usd),
+            record.commit.as_deref().map_or("-", |c| short(c, 7))
+        );
+        if let Some(summary) = &record.summary {
+            println!("        {}", summary);
+        }
+        if let Some(steps) = &record.escalation {
+            println!("        escalated: {}", steps);
+        }
+        if let Some(thinking) = &record.thinking {
+            println!("        thinking: {}", thinking);
+        }
+    }
+    Ok(())
+}
+
+/// Print loop `n` of the run matching `query` from its log file
+pub fn show_loop(prd_path: &str, query: &str, n: u64) -> Result<(), HistoryError> {
+    let stats = Stats::load(prd_path)?;
+    let runs = runs(&stats);
+    let run = find_run(&runs, query)?;
+    let log = LoopLog::load(prd_path, run.id, n)?.ok_or(HistoryError::NoLoopLog(n))?;
+
+    println!(
+        "Run {} loop #{}: {} ({})",
+        run.id,
+        n,
+        log.status,
+        local_time(log.finished_at)
+    );
+    println!("{}", LoopLog::file_path(prd_path, run.id, n).display());
+    for entry in &log.log {
+        println!("\n{}", entry);
+    }
+    for (i, attempt) in log.attempts.iter().enumerate() {
+        println!(
+            "\n─── Claude output (attempt {}) ───\n{}",
+            i + 1,
+            attempt.stdout.trim_end()
+        );
+        if !attempt.stderr.trim().is_empty() {
+            println!("─── stderr ───\n{}", attempt.stderr.trim_end());
+        }
+    }
+    Ok(())
+}
+
+fn local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+fn short(id: &str, len: usize) -> &str {
+    id.get(..len).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(run_id: &str, label: Option<&str>, minute: u32, status: &str) -> IterationRecord {
+        IterationRecord {
+            run_id: run_id.to_string(),
+            run_label: label.map(str::to_string),
+            loop_number: 1,
+            task_number: Some(1),
+            task: None,
+            category: None,
+            model: None,
+            status: status.to_string(),
+            summary: None,
+            commit: None,
+            duration_secs: 60.0,
+            cost_usd: 0.5,
+            finished_at: Utc.with_ymd_and_hms(2026, 1, 1, 12, minute, 0).unwrap(),
+            rating: None,
+            rating_note: None,
+            escalation: None,
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn runs_group_loops_newest_run_first() {
+        let stats = Stats {
+            iterations: vec![
+                record("aaaa1111", None, 0, "completed"),
+                record("bbbb2222", Some("night"), 1, "failed"),
+                record("aaaa1111", None, 2, "completed"),
+            ],
+        };
+        let runs = runs(&stats);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, "aaaa1111");
+        assert_eq!(runs[0].loops.len(), 2);
+        assert_eq!(runs[0].completed(), 2);
+        assert_eq!(runs[0].cost(), 1.0);
+        assert_eq!(runs[1].label, Some("night"));
+    }
+
+    #[test]
+    fn find_run_by_prefix_or_label() {
+        let stats = Stats {
+            iterations: vec![
+                record("aaaa1111", None, 0, "completed"),
+                record("aaab2222", Some("night"), 1, "completed"),
+            ],
+        };
+        let runs = runs(&stats);
+        assert_eq!(find_run(&runs, "aaaa").unwrap().id, "aaaa1111");
+        assert_eq!(find_run(&runs, "night").unwrap().id, "aaab2222");
+        assert!(matches!(
+            find_run(&runs, "aaa"),
+            Err(HistoryError::AmbiguousRun(_))
+        ));
+        assert!(matches!(
+            find_run(&runs, "zzz"),
+            Err(HistoryError::NoSuchRun(_))
+        ));
+    }
+}
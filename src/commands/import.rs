@@ -0,0 +1,89 @@
+use clap::Args;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::commands::{CommandResult, Registered};
+use ralph_core::markdown_tasks;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("No tasks found in {0}: expected list items such as `- [ ] Add login`")]
+    NoTasks(String),
+
+    #[error("{0} already exists. Use --force to overwrite.")]
+    OutputExists(String),
+}
+
+/// Convert a Markdown task list into a PRD
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// Markdown file: headings become categories, list items tasks, sub-bullets steps
+    #[arg(value_name = "FILE")]
+    pub markdown: String,
+
+    /// Output path for the generated PRD
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub output: String,
+
+    /// Overwrite the output file if it exists
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+pub static COMMAND: Registered<ImportArgs> = Registered::new("import", run_args);
+
+fn run_args(args: ImportArgs) -> CommandResult {
+    Ok(run(&args.markdown, &args.output, args.force)?)
+}
+
+/// Run the import command - convert a Markdown task list into a PRD
+pub fn run(markdown_path: &str, output: &str, force: bool) -> Result<(), ImportError> {
+    let output_path = Path::new(output);
+    if output_path.exists() && !force {
+        return Err(ImportError::OutputExists(output.to_string()));
+    }
+
+    let markdown = std::fs::read_to_string(markdown_path)?;
+    let fallback_name = Path::new(markdown_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "PRD".to_string());
+    let prd = markdown_tasks::parse(&markdown, &fallback_name);
+    if prd.tasks.is_empty() {
+        return Err(ImportError::NoTasks(markdown_path.to_string()));
+    }
+
+    if let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output_path, serde_json::to_string_pretty(&prd)?)?;
+
+    println!(
+        "Imported {} task(s) from {} into {}",
+        prd.tasks.len(),
+        markdown_path,
+        output
+    );
+    for (i, task) in prd.tasks.iter().enumerate() {
+        let done = if task.passes { " (done)" } else { "" };
+        println!(
+            "  {}. [{}] {}{}",
+            i + 1,
+            task.category,
+            task.description,
+            done
+        );
+    }
+    if prd.quality_gates.is_empty() {
+        println!("No quality gates found; add a \"Quality gates\" section or edit the PRD.");
+    }
+    Ok(())
+}
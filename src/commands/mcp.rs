@@ -0,0 +1,478 @@
+//! `ralph mcp`: the PRD as a Model Context Protocol server on stdin/stdout.
+//!
+//! Other Claude sessions and IDE agents can read the PRD, completed tasks and progress log
+//! as resources, and list, add and finish tasks through tools, while `ralph build` works
+//! through the same files. Messages are newline-delimited JSON-RPC 2.0.
+
+use clap::Args;
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::commands::tasks::{self, NewTask, TasksError};
+use crate::commands::view::PROGRESS_FILE;
+use crate::commands::{CommandResult, Registered};
+use ralph_core::prd;
+
+/// Protocol revisions this server speaks, newest first
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// MCP's code for a resource URI the server doesn't have
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+#[derive(Error, Debug)]
+pub enum McpError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Serve the PRD, completed tasks and progress log over MCP (stdio), for other agents
+#[derive(Args, Debug)]
+pub struct McpArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+}
+
+pub static COMMAND: Registered<McpArgs> = Registered::new("mcp", run_args);
+
+fn run_args(args: McpArgs) -> CommandResult {
+    Ok(run(&args.prd_path)?)
+}
+
+/// Answer requests from stdin until the client closes it
+pub fn run(prd_path: &str) -> Result<(), McpError> {
+    let server = Server { prd_path };
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_line(&line) {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// A JSON-RPC error, before it's wrapped in a response
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+struct Server<'a> {
+    prd_path: &'a str,
+}
+
+impl Server<'_> {
+    /// The response to one line from the client; None for notifications
+    fn handle_line(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, e.to_string()),
+                ));
+            }
+        };
+        // Requests carry an id; notifications (e.g. notifications/initialized) don't
+        let id = message.get("id")?.clone();
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        tracing::debug!(method, "MCP request");
+        Some(match self.handle(method, &params) {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    fn handle(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(initialize(params)),
+            "ping" => Ok(json!({})),
+            "resources/list" => Ok(json!({"resources": resources()})),
+            "resources/read" => self.read_resource(params["uri"].as_str().unwrap_or_default()),
+            "tools/list" => Ok(json!({"tools": tools()})),
+            "tools/call" => self.call_tool(
+                params["name"].as_str().unwrap_or_default(),
+                &params["arguments"],
+            ),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+
+    fn read_resource(&self, uri: &str) -> Result<Value, RpcError> {
+        let (mime_type, path) = match uri {
+            "ralph://prd" => ("application/json", Path::new(self.prd_path).to_path_buf()),
+            "ralph://completed" => ("application/json", prd::completed_path_for(self.prd_path)),
+            "ralph://progress" => ("text/plain", Path::new(PROGRESS_FILE).to_path_buf()),
+            _ => {
+                return Err(RpcError::new(
+                    RESOURCE_NOT_FOUND,
+                    format!("Unknown resource: {}", uri),
+                ));
+            }
+        };
+        // Nothing archived or logged yet reads as empty rather than an error
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && uri != "ralph://prd" => {
+                if mime_type == "application/json" {
+                    "[]".to_string()
+                } else {
+                    String::new()
+                }
+            }
+            Err(e) => {
+                return Err(RpcError::new(
+                    RESOURCE_NOT_FOUND,
+                    format!("Can't read {}: {}", path.display(), e),
+                ));
+            }
+        };
+        Ok(json!({"contents": [{"uri": uri, "mimeType": mime_type, "text": text}]}))
+    }
+
+    fn call_tool(&self, name: &str, arguments: &Value) -> Result<Value, RpcError> {
+        let outcome = match name {
+            "list_tasks" => self.list_tasks(),
+            "mark_task_done" => self.mark_task_done(number_argument(arguments)?),
+            "add_task" => self.add_task(new_task(arguments)?),
+            "append_progress" => append_progress(string_argument(arguments, "note")?),
+            _ => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("Unknown tool: {}", name),
+                ));
+            }
+        };
+        // A tool that ran but failed is reported to the model, not as a protocol error
+        Ok(match outcome {
+            Ok(text) => json!({"content": [{"type": "text", "text": text}], "isError": false}),
+            Err(e) => {
+                json!({"content": [{"type": "text", "text": e.to_string()}], "isError": true})
+            }
+        })
+    }
+
+    fn list_tasks(&self) -> Result<String, TasksError> {
+        let prd = prd::read_prd(self.prd_path)?;
+        let tasks: Vec<Value> = prd
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| {
+                json!({
+                    "number": i + 1,
+                    "category": task.category,
+                    "description": task.description,
+                    "steps": task.steps,
+                    "passes": task.passes,
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&tasks)?)
+    }
+
+    /// Set `passes` on a task, as Claude does in a build loop; the loop archives it later
+    fn mark_task_done(&self, number: usize) -> Result<String, TasksError> {
+        let mut prd = prd::read_prd(self.prd_path)?;
+        let index = tasks::index_of(&prd, number)?;
+        prd.tasks[index].passes = true;
+        prd::write_prd(self.prd_path, &prd)?;
+        Ok(format!(
+            "Task #{} marked done: {}",
+            number, prd.tasks[index].description
+        ))
+    }
+
+    fn add_task(&self, new: NewTask) -> Result<String, TasksError> {
+        let mut prd = prd::read_prd(self.prd_path)?;
+        prd.tasks.push(new.into_task());
+        prd::write_prd(self.prd_path, &prd)?;
+        Ok(format!("Added task #{}", prd.tasks.len()))
+    }
+}
+
+/// The task an `add_task` call describes
+fn new_task(arguments: &Value) -> Result<NewTask, RpcError> {
+    Ok(NewTask {
+        description: string_argument(arguments, "description")?.to_string(),
+        category: arguments["category"].as_str().map(str::to_string),
+        steps: arguments["steps"]
+            .as_array()
+            .map(|steps| {
+                steps
+                    .iter()
+                    .filter_map(|s| s.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        ..NewTask::default()
+    })
+}
+
+/// Append a note to the progress log the build loop keeps
+fn append_progress(note: &str) -> Result<String, TasksError> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(PROGRESS_FILE)?;
+    writeln!(file, "{}", note.trim_end())?;
+    Ok(format!("Appended to {}", PROGRESS_FILE))
+}
+
+/// Agree on the client's protocol revision if we speak it, otherwise offer our newest
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| **v == requested)
+        .unwrap_or(&PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": {"resources": {}, "tools": {}},
+        "serverInfo": {"name": "ralph", "version": env!("CARGO_PKG_VERSION")},
+    })
+}
+
+fn resources() -> Value {
+    json!([
+        {
+            "uri": "ralph://prd",
+            "name": "PRD",
+            "description": "The PRD ralph is executing: tasks, steps and quality gates",
+            "mimeType": "application/json",
+        },
+        {
+            "uri": "ralph://completed",
+            "name": "Completed tasks",
+            "description": "Tasks archived to completed.json once they passed",
+            "mimeType": "application/json",
+        },
+        {
+            "uri": "ralph://progress",
+            "name": "Progress log",
+            "description": "progress.txt, the notes each build loop appends",
+            "mimeType": "text/plain",
+        },
+    ])
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "list_tasks",
+            "description": "List the PRD's tasks with their 1-indexed numbers and whether they pass",
+            "inputSchema": {"type": "object", "properties": {}},
+        },
+        {
+            "name": "mark_task_done",
+            "description": "Mark a task as passing; the build loop archives it to completed.json",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"number": {"type": "integer", "description": "Task number (1-indexed)"}},
+                "required": ["number"],
+            },
+        },
+        {
+            "name": "add_task",
+            "description": "Append a pending task to the PRD",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "description": {"type": "string"},
+                    "category": {"type": "string"},
+                    "steps": {"type": "array", "items": {"type": "string"}},
+                },
+                "required": ["description"],
+            },
+        },
+        {
+            "name": "append_progress",
+            "description": "Append a note to the progress log",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"note": {"type": "string"}},
+                "required": ["note"],
+            },
+        },
+    ])
+}
+
+fn number_argument(arguments: &Value) -> Result<usize, RpcError> {
+    arguments["number"]
+        .as_u64()
+        .and_then(|n| usize::try_from(n).ok())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "`number` must be a positive integer"))
+}
+
+fn string_argument<'a>(arguments: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    arguments[name]
+        .as_str()
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("`{}` must be a string", name)))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn server_with_prd(dir: &TempDir) -> String {
+        let path = dir.path().join("prd.json");
+        std::fs::write(
+            &path,
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+                {"category": "feature", "description": "Login", "steps": ["Form"], "passes": false}
+            ]}"#,
+        )
+        .unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn request(server: &Server, method: &str, params: Value) -> Value {
+        let line = json!({"jsonrpc": "2.0", "id": 7, "method": method, "params": params});
+        server.handle_line(&line.to_string()).unwrap()
+    }
+
+    #[test]
+    fn initialize_echoes_a_known_protocol_version() {
+        assert_eq!(
+            initialize(&json!({"protocolVersion": "2024-11-05"}))["protocolVersion"],
+            "2024-11-05"
+        );
+        assert_eq!(
+            initialize(&json!({"protocolVersion": "1999-01-01"}))["protocolVersion"],
+            PROTOCOL_VERSIONS[0]
+        );
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let server = Server {
+            prd_path: "prd.json",
+        };
+        let line = r#"{"jsonrpc": "2.0", "method": "notifications/initialized"}"#;
+        assert!(server.handle_line(line).is_none());
+    }
+
+    #[test]
+    fn bad_json_and_unknown_methods_are_errors() {
+        let server = Server {
+            prd_path: "prd.json",
+        };
+        let response = server.handle_line("{not json").unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        assert_eq!(response["id"], Value::Null);
+
+        let response = request(&server, "sampling/createMessage", json!({}));
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn reads_the_prd_and_empty_completed_tasks() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = server_with_prd(&dir);
+        let server = Server {
+            prd_path: &prd_path,
+        };
+
+        let response = request(&server, "resources/read", json!({"uri": "ralph://prd"}));
+        let text = response["result"]["contents"][0]["text"].as_str().unwrap();
+        assert!(text.contains("Login"));
+
+        let response = request(
+            &server,
+            "resources/read",
+            json!({"uri": "ralph://completed"}),
+        );
+        assert_eq!(response["result"]["contents"][0]["text"], "[]");
+
+        let response = request(&server, "resources/read", json!({"uri": "ralph://nope"}));
+        assert_eq!(response["error"]["code"], RESOURCE_NOT_FOUND);
+    }
+
+    #[test]
+    fn mark_task_done_sets_passes() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = server_with_prd(&dir);
+        let server = Server {
+            prd_path: &prd_path,
+        };
+
+        let response = request(
+            &server,
+            "tools/call",
+            json!({"name": "mark_task_done", "arguments": {"number": 1}}),
+        );
+        assert_eq!(response["result"]["isError"], false);
+        assert!(prd::read_prd(&prd_path).unwrap().tasks[0].passes);
+
+        // A missing task is a tool failure the model sees, not a protocol error
+        let response = request(
+            &server,
+            "tools/call",
+            json!({"name": "mark_task_done", "arguments": {"number": 5}}),
+        );
+        assert_eq!(response["result"]["isError"], true);
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        assert!(text.contains("No task #5"));
+    }
+
+    #[test]
+    fn add_task_appends_a_pending_task() {
+        let dir = TempDir::new().unwrap();
+        let prd_path = server_with_prd(&dir);
+        let server = Server {
+            prd_path: &prd_path,
+        };
+
+        let response = request(
+            &server,
+            "tools/call",
+            json!({"name": "add_task", "arguments": {"description": "Logout", "steps": ["Button"]}}),
+        );
+        assert_eq!(response["result"]["content"][0]["text"], "Added task #2");
+        let prd = prd::read_prd(&prd_path).unwrap();
+        assert_eq!(prd.tasks[1].description, "Logout");
+        assert_eq!(prd.tasks[1].steps, vec!["Button"]);
+        assert!(!prd.tasks[1].passes);
+
+        let response = request(
+            &server,
+            "tools/call",
+            json!({"name": "add_task", "arguments": {}}),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+}
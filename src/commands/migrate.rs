@@ -0,0 +1,262 @@
+use clap::Args;
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::commands::{CommandResult, Registered};
+use ralph_core::prd::SCHEMA_VERSION;
+
+/// Category given to tasks that don't have one
+const DEFAULT_CATEGORY: &str = "general";
+
+#[derive(Error, Debug)]
+pub enum MigrateError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0} is not a PRD: expected a JSON object")]
+    NotAnObject(String),
+
+    #[error(
+        "{path} uses schema version {version}, newer than this ralph supports ({SCHEMA_VERSION})"
+    )]
+    TooNew { path: String, version: u64 },
+}
+
+/// Upgrade a PRD to the current schema in place, keeping a backup of the original
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    /// Path to the PRD JSON file
+    #[arg(value_name = "PRD", default_value = "plans/prd.json")]
+    pub prd_path: String,
+}
+
+pub static COMMAND: Registered<MigrateArgs> = Registered::new("migrate", run_args);
+
+fn run_args(args: MigrateArgs) -> CommandResult {
+    Ok(run(&args.prd_path)?)
+}
+
+/// Run the migrate command - upgrade a PRD to the current schema in place, keeping a backup
+pub fn run(prd_path: &str) -> Result<(), MigrateError> {
+    let path = Path::new(prd_path);
+    let mut doc: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let fallback_name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "PRD".to_string());
+
+    let from = schema_version(&doc);
+    let changes = migrate(&mut doc, &fallback_name, prd_path)?;
+    if changes.is_empty() {
+        println!(
+            "{} is already at schema version {}; nothing to do.",
+            prd_path, SCHEMA_VERSION
+        );
+        return Ok(());
+    }
+
+    let backup = backup_path(path, from);
+    std::fs::copy(path, &backup)?;
+    std::fs::write(path, serde_json::to_string_pretty(&doc)?)?;
+
+    println!(
+        "Migrated {} from schema version {} to {}:",
+        prd_path, from, SCHEMA_VERSION
+    );
+    for change in &changes {
+        println!("  - {}", change);
+    }
+    println!("Backup: {}", backup.display());
+    Ok(())
+}
+
+fn schema_version(doc: &Value) -> u64 {
+    doc.get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// e.g. `plans/prd.json` at version 0 -> `plans/prd.json.v0.bak`
+fn backup_path(path: &Path, version: u64) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".v{}.bak", version));
+    PathBuf::from(name)
+}
+
+/// Upgrade a PRD document to `SCHEMA_VERSION`, describing each change made.
+///
+/// Version 0 is anything written before PRDs were versioned; fields it may lack or spell
+/// loosely are filled in with the defaults ralph assumed at the time.
+fn migrate(doc: &mut Value, fallback_name: &str, path: &str) -> Result<Vec<String>, MigrateError> {
+    let version = schema_version(doc);
+    if version > SCHEMA_VERSION as u64 {
+        return Err(MigrateError::TooNew {
+            path: path.to_string(),
+            version,
+        });
+    }
+    let Some(prd) = doc.as_object_mut() else {
+        return Err(MigrateError::NotAnObject(path.to_string()));
+    };
+
+    let mut changes = Vec::new();
+    if version < 1 {
+        migrate_v0_to_v1(prd, fallback_name, &mut changes);
+    }
+    if version < SCHEMA_VERSION as u64 {
+        prd.insert("schema_version".to_string(), json!(SCHEMA_VERSION));
+        changes.push(format!("set schema_version to {}", SCHEMA_VERSION));
+    }
+    Ok(changes)
+}
+
+fn migrate_v0_to_v1(prd: &mut Map<String, Value>, fallback_name: &str, changes: &mut Vec<String>) {
+    if !prd.get("name").is_some_and(Value::is_string) {
+        prd.insert("name".to_string(), json!(fallback_name));
+        changes.push(format!("added name \"{}\"", fallback_name));
+    }
+
+    match prd.get("quality_gates") {
+        Some(Value::Array(_)) => {}
+        Some(Value::String(gate)) => {
+            let gates = json!([gate]);
+            prd.insert("quality_gates".to_string(), gates);
+            changes.push("wrapped the single quality gate in a list".to_string());
+        }
+        _ => {
+            prd.insert("quality_gates".to_string(), json!([]));
+            changes.push("added an empty quality_gates list".to_string());
+        }
+    }
+
+    let tasks = prd.entry("tasks").or_insert_with(|| {
+        changes.push("added an empty tasks list".to_string());
+        json!([])
+    });
+    let Some(tasks) = tasks.as_array_mut() else {
+        *tasks = json!([]);
+        changes.push("replaced a tasks value that wasn't a list".to_string());
+        return;
+    };
+
+    for (i, task) in tasks.iter_mut().enumerate() {
+        let number = i + 1;
+        if let Value::String(description) = task {
+            *task = json!({ "description": description });
+            changes.push(format!(
+                "task #{}: expanded a plain string into a task",
+                number
+            ));
+        }
+        let Some(task) = task.as_object_mut() else {
+            continue;
+        };
+
+        if !task.get("category").is_some_and(Value::is_string) {
+            task.insert("category".to_string(), json!(DEFAULT_CATEGORY));
+            changes.push(format!(
+                "task #{}: added category \"{}\"",
+                number, DEFAULT_CATEGORY
+            ));
+        }
+        if !task.get("description").is_some_and(Value::is_string) {
+            task.insert("description".to_string(), json!(format!("Task {}", number)));
+            changes.push(format!("task #{}: added a placeholder description", number));
+        }
+        match task.get("steps") {
+            Some(Value::Array(_)) => {}
+            Some(Value::String(step)) => {
+                let steps = json!([step]);
+                task.insert("steps".to_string(), steps);
+                changes.push(format!(
+                    "task #{}: wrapped the single step in a list",
+                    number
+                ));
+            }
+            _ => {
+                task.insert("steps".to_string(), json!([]));
+                changes.push(format!("task #{}: added an empty steps list", number));
+            }
+        }
+        if !task.get("passes").is_some_and(Value::is_boolean) {
+            task.insert("passes".to_string(), json!(false));
+            changes.push(format!("task #{}: added passes: false", number));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ralph_core::prd::{Prd, read_prd};
+    use tempfile::TempDir;
+
+    #[test]
+    fn migrate_fills_in_missing_fields() {
+        let mut doc = json!({
+            "quality_gates": "cargo test",
+            "tasks": [
+                "Add login",
+                {"description": "Add logout", "steps": "Button works"},
+                {"category": "docs", "description": "Readme", "steps": [], "passes": true}
+            ]
+        });
+        let changes = migrate(&mut doc, "prd", "prd.json").unwrap();
+        assert!(changes.contains(&"added name \"prd\"".to_string()));
+        assert!(changes.contains(&"set schema_version to 1".to_string()));
+
+        let prd: Prd = serde_json::from_value(doc).unwrap();
+        assert_eq!(prd.schema_version, SCHEMA_VERSION);
+        assert_eq!(prd.quality_gates, vec!["cargo test"]);
+        assert_eq!(prd.tasks[0].description, "Add login");
+        assert_eq!(prd.tasks[0].category, DEFAULT_CATEGORY);
+        assert!(!prd.tasks[0].passes);
+        assert_eq!(prd.tasks[1].steps, vec!["Button works"]);
+        assert!(prd.tasks[2].passes);
+    }
+
+    #[test]
+    fn migrate_current_version_is_a_no_op() {
+        let mut doc = json!({
+            "schema_version": SCHEMA_VERSION,
+            "name": "Test",
+            "quality_gates": [],
+            "tasks": []
+        });
+        assert!(migrate(&mut doc, "prd", "prd.json").unwrap().is_empty());
+    }
+
+    #[test]
+    fn migrate_rejects_newer_versions_and_non_objects() {
+        let mut newer = json!({"schema_version": SCHEMA_VERSION + 1});
+        assert!(matches!(
+            migrate(&mut newer, "prd", "prd.json"),
+            Err(MigrateError::TooNew { .. })
+        ));
+        let mut list = json!([]);
+        assert!(matches!(
+            migrate(&mut list, "prd", "prd.json"),
+            Err(MigrateError::NotAnObject(_))
+        ));
+    }
+
+    #[test]
+    fn run_writes_backup_and_upgrades_in_place() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("prd.json");
+        let original = r#"{"name": "Old", "tasks": [{"description": "Add login"}]}"#;
+        std::fs::write(&path, original).unwrap();
+
+        run(path.to_str().unwrap()).unwrap();
+
+        let backup = dir.path().join("prd.json.v0.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), original);
+        let prd = read_prd(path.to_str().unwrap()).unwrap();
+        assert_eq!(prd.schema_version, SCHEMA_VERSION);
+        assert_eq!(prd.tasks[0].category, DEFAULT_CATEGORY);
+    }
+}
@@ -1,2 +1,119 @@
+//! `ralph` subcommands and the registry `main` builds the CLI from.
+//!
+//! A subcommand is anything implementing [`Command`]. Most are a derived `clap::Args`
+//! struct plus a run function wrapped in [`Registered`]; adding one means writing its
+//! module and listing it in [`registry`].
+
+use clap::{ArgMatches, Args, FromArgMatches};
+use std::error::Error;
+use std::marker::PhantomData;
+
 pub mod build;
+pub mod daemon;
+pub mod doctor;
+pub mod history;
+pub mod import;
+pub mod mcp;
+pub mod migrate;
 pub mod plan;
+pub mod prompt;
+pub mod rollback;
+pub mod stats;
+pub mod tasks;
+pub mod tutorial;
+pub mod view;
+
+/// What a subcommand's run function returns; `main` prints the error and exits non-zero
+pub type CommandResult = Result<(), Box<dyn Error>>;
+
+/// A `ralph` subcommand
+pub trait Command: Sync {
+    /// Name typed on the command line, e.g. "build"
+    fn name(&self) -> &'static str;
+
+    /// The subcommand's clap definition: help text and arguments
+    fn command(&self) -> clap::Command;
+
+    /// Run with the arguments clap matched for this subcommand
+    fn run(&self, matches: &ArgMatches) -> CommandResult;
+}
+
+/// A subcommand defined by a derived `clap::Args` struct, whose doc comment is its help
+/// text, and the function that runs it
+pub struct Registered<A> {
+    pub name: &'static str,
+    pub run: fn(A) -> CommandResult,
+    pub args: PhantomData<fn() -> A>,
+}
+
+impl<A> Registered<A> {
+    pub const fn new(name: &'static str, run: fn(A) -> CommandResult) -> Self {
+        Self {
+            name,
+            run,
+            args: PhantomData,
+        }
+    }
+}
+
+impl<A: Args + FromArgMatches> Command for Registered<A> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn command(&self) -> clap::Command {
+        A::augment_args(clap::Command::new(self.name))
+    }
+
+    fn run(&self, matches: &ArgMatches) -> CommandResult {
+        (self.run)(A::from_arg_matches(matches)?)
+    }
+}
+
+/// Every subcommand, in the order `ralph --help` lists them
+pub fn registry() -> Vec<&'static dyn Command> {
+    vec![
+        &build::COMMAND,
+        &daemon::COMMAND,
+        &plan::COMMAND,
+        &import::COMMAND,
+        &tasks::COMMAND,
+        &prompt::COMMAND,
+        &migrate::COMMAND,
+        &stats::COMMAND,
+        &history::COMMAND,
+        &rollback::COMMAND,
+        &view::COMMAND,
+        &doctor::COMMAND,
+        &mcp::COMMAND,
+        &tutorial::COMMAND,
+    ]
+}
+
+/// Find a subcommand by name
+pub fn find(name: &str) -> Option<&'static dyn Command> {
+    registry().into_iter().find(|c| c.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_names_are_unique_and_match_their_commands() {
+        let commands = registry();
+        let mut cli = clap::Command::new("ralph");
+        for (i, command) in commands.iter().enumerate() {
+            assert_eq!(command.command().get_name(), command.name());
+            assert!(
+                commands[..i].iter().all(|c| c.name() != command.name()),
+                "duplicate subcommand {}",
+                command.name()
+            );
+            cli = cli.subcommand(command.command());
+        }
+        cli.debug_assert();
+        assert!(find("build").is_some());
+        assert!(find("nope").is_none());
+    }
+}
@@ -1,26 +1,50 @@
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use std::io::Write;
-use std::path::Path;
+use clap::Args;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::future::pending;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use thiserror::Error;
 
-use crate::claude::{ClaudeOptions, launch_claude_with_options, normalize_json_with_haiku};
+use crate::commands::{CommandResult, Registered};
+use crate::config::{self, ConfigError};
+use crate::error::RalphError;
+use ralph_core::claude::{
+    ClaudeOptions, NormalizationError, Permissions, launch_claude_with_options, normalize_json,
+};
 use serde::Deserialize;
 
-use crate::plan::{
-    app::{InputMode, PlanApp},
+use crate::plan::app::{InputMode, PlanApp};
+use crate::plan::review::{PrdReview, ReviewOutcome};
+use crate::runtime::{self, Keys};
+use crate::tui;
+use ralph_core::gates::RunningGate;
+use ralph_core::iteration::{BASE_RETRY_DELAY_SECS, MAX_RETRIES};
+use ralph_core::plan::{
+    coverage, feed,
     phases::PlanPhase,
-    prompts::{build_continuation_prompt, build_initial_prompt, build_resume_prompt},
-    protocol::{PLAN_RESPONSE_SCHEMA, PlanResponse},
-    session::{PlanSession, SessionError},
+    prompts::{
+        DEFERRED_ANSWER, PLAN_PROMPT_FILE, SystemPrompt, build_continuation_prompt,
+        build_feedback_prompt, build_initial_prompt, build_resume_prompt, question_limit,
+    },
+    protocol::{Answer, PLAN_RESPONSE_SCHEMA, PlanResponse},
+    session::{PlanSession, QuestionRound, SessionError},
+    speculation::Speculation,
+    templates::{self, TemplateError},
 };
-use crate::tui;
+use ralph_core::prd::{PrdFormat, render_prd};
+use ralph_core::process;
+use ralph_core::prompt;
+use ralph_core::test_failures;
+use ralph_core::todos;
 
 /// Wrapper for Claude's JSON output format when using --output-format json
 /// The structured_output field contains the response matching the JSON schema
 #[derive(Deserialize)]
 struct ClaudeJsonOutput {
     structured_output: Option<PlanResponse>,
+    #[serde(default)]
+    total_cost_usd: Option<f64>,
 }
 
 #[derive(Error, Debug)]
@@ -28,26 +52,210 @@ pub enum PlanError {
     #[error("Session error: {0}")]
     Session(#[from] SessionError),
 
+    #[error("{0}")]
+    Ralph(#[from] RalphError),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
     #[error("JSON parse error: {0}")]
     Json(#[from] serde_json::Error),
 
+    #[error("No TODO/FIXME/HACK comments found in {0}")]
+    NoTodos(String),
+
+    #[error("No test command given and none detected; pass --test-cmd")]
+    NoTestCommand,
+
+    #[error("`{0}` passed; there are no failing tests to plan fixes for")]
+    TestsPassing(String),
+
+    #[error("`{0}` failed but no failing tests could be recognised in its output:\n{1}")]
+    UnrecognisedFailures(String, String),
+
     #[error("Claude returned invalid output (not JSON):\n{0}")]
     InvalidOutput(String),
 
     #[error("Output file already exists. Use --resume to continue or --force to overwrite.")]
     OutputExists,
+
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+
+    #[error("{0}")]
+    Template(#[from] TemplateError),
+
+    #[error("Could not read {PLAN_PROMPT_FILE}: {0}")]
+    PlanPrompt(std::io::Error),
+
+    #[error("Could not read the description from {0}: {1}")]
+    ReadDescription(String, std::io::Error),
+
+    #[error("The description in {0} is empty")]
+    EmptyDescription(String),
+}
+
+/// Generate a new PRD through interactive multi-turn conversation
+#[derive(Args, Debug)]
+pub struct PlanArgs {
+    /// Output path for the generated PRD
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub output: String,
+
+    /// Resume an interrupted session
+    #[arg(short, long)]
+    pub resume: bool,
+
+    /// Force overwrite existing files
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Description of what to build (optional)
+    #[arg(short = 'd', long, conflicts_with_all = ["description_file", "stdin"])]
+    pub description: Option<String>,
+
+    /// Read the description of what to build from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "stdin")]
+    pub description_file: Option<PathBuf>,
+
+    /// Read the description of what to build from standard input
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// Let Claude pre-work on partial answers in a forked session while you answer
+    #[arg(long)]
+    pub speculate: bool,
+
+    /// Warn when the session's cumulative cost crosses these USD amounts (e.g. 1,5,10)
+    #[arg(long, value_name = "USD", value_delimiter = ',')]
+    pub cost_warn: Vec<f64>,
+
+    /// Seed the plan from TODO/FIXME/HACK comments in the current directory
+    #[arg(long, conflicts_with = "from_failing_tests")]
+    pub from_todos: bool,
+
+    /// Run the test suite and write a PRD with one fix task per failing suite
+    #[arg(long)]
+    pub from_failing_tests: bool,
+
+    /// Test command for --from-failing-tests (detected from project files if omitted)
+    #[arg(long, value_name = "CMD", requires = "from_failing_tests")]
+    pub test_cmd: Option<String>,
+
+    /// Answer questions covered by [plan.defaults] in .ralph.toml without asking
+    #[arg(long)]
+    pub accept_defaults: bool,
+
+    /// Ask at most this many questions in the session; Claude is told to decide the rest
+    #[arg(long, value_name = "N")]
+    pub max_questions: Option<u32>,
+
+    /// File format of the PRD: json, or md for Markdown with the JSON as front matter
+    /// [default: md for a .md output, otherwise json]
+    #[arg(long, value_enum)]
+    pub format: Option<PrdFormat>,
+
+    /// Plan with a template's guidance and default quality gates: web-app, cli-tool,
+    /// library, bugfix-sprint, or one from .ralph/templates/<name>.md
+    #[arg(long, value_name = "NAME")]
+    pub template: Option<String>,
+}
+
+pub static COMMAND: Registered<PlanArgs> = Registered::new("plan", run_args);
+
+fn run_args(args: PlanArgs) -> CommandResult {
+    let description = match (&args.description_file, args.stdin) {
+        (Some(path), _) => Some(read_description_file(path)?),
+        (None, true) => Some(read_description_stdin()?),
+        (None, false) => args.description,
+    };
+    let opts = PlanOptions {
+        resume: args.resume,
+        force: args.force,
+        description: description.as_deref(),
+        speculate: args.speculate,
+        cost_warn: args.cost_warn,
+        from_todos: args.from_todos,
+        from_failing_tests: args.from_failing_tests,
+        test_cmd: args.test_cmd.as_deref(),
+        accept_defaults: args.accept_defaults,
+        max_questions: args.max_questions,
+        format: args
+            .format
+            .unwrap_or_else(|| PrdFormat::for_path(&args.output)),
+        template: args.template.as_deref(),
+    };
+    Ok(run(&args.output, &opts)?)
+}
+
+fn read_description_file(path: &Path) -> Result<String, PlanError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| PlanError::ReadDescription(path.display().to_string(), e))?;
+    non_empty_description(text, &path.display().to_string())
+}
+
+/// The description piped in; the TUI still reads keys from the terminal itself
+fn read_description_stdin() -> Result<String, PlanError> {
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| PlanError::ReadDescription("standard input".to_string(), e))?;
+    non_empty_description(text, "standard input")
+}
+
+fn non_empty_description(text: String, source: &str) -> Result<String, PlanError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(PlanError::EmptyDescription(source.to_string()));
+    }
+    Ok(text.to_string())
+}
+
+/// Options controlling a plan session
+#[derive(Debug, Default)]
+pub struct PlanOptions<'a> {
+    /// Resume an interrupted session
+    pub resume: bool,
+
+    /// Force overwrite existing files
+    pub force: bool,
+
+    /// Description of what to build (skips the idea input screen)
+    pub description: Option<&'a str>,
+
+    /// Send a speculative pre-work turn while the user answers questions
+    pub speculate: bool,
+
+    /// Session cost levels (USD) at which to warn
+    pub cost_warn: Vec<f64>,
+
+    /// Seed the plan from TODO/FIXME/HACK comments in the working directory
+    pub from_todos: bool,
+
+    /// Write a fix-it PRD from the test suite's failures instead of talking to Claude
+    pub from_failing_tests: bool,
+
+    /// Test command for `from_failing_tests`
+    pub test_cmd: Option<&'a str>,
+
+    /// Answer questions from the configured defaults instead of asking; a round is only
+    /// shown if some question has no default
+    pub accept_defaults: bool,
+
+    /// Most questions shown over the whole session; later ones are deferred to Claude
+    pub max_questions: Option<u32>,
+
+    /// How the PRD file is written
+    pub format: PrdFormat,
+
+    /// Name of the plan template to seed the session with
+    pub template: Option<&'a str>,
 }
 
 /// Run the plan command - multi-turn PRD generation
-pub fn run(
-    output: &str,
-    resume: bool,
-    force: bool,
-    request: Option<&str>,
-) -> Result<(), PlanError> {
+pub fn run(output: &str, opts: &PlanOptions) -> Result<(), PlanError> {
+    let (resume, force, request) = (opts.resume, opts.force, opts.description);
+
     // Check if output file exists
     let output_path = Path::new(output);
     if output_path.exists() && !resume && !force {
@@ -61,41 +269,114 @@ pub fn run(
         std::fs::create_dir_all(parent)?;
     }
 
+    if opts.from_failing_tests {
+        return plan_from_failing_tests(output_path, opts.test_cmd, opts.format);
+    }
+
     // Load or create session
     let mut session = PlanSession::load_or_create(output, resume, force)?;
 
+    // Scan before the TUI starts so errors print normally
+    let todo_request = if opts.from_todos && session.is_fresh() {
+        Some(todo_request(request)?)
+    } else {
+        None
+    };
+    let template = match opts.template {
+        Some(name) if session.is_fresh() => Some(templates::load(Path::new("."), name)?),
+        _ => None,
+    };
+
+    let config = config::load(Path::new("."))?;
+    let theme = config.theme.resolve().map_err(ConfigError::from)?;
+    let system_prompt = if session.is_fresh() {
+        let root = Path::new(".");
+        let mut system_prompt =
+            SystemPrompt::load(root, config.plan.prompt).map_err(PlanError::PlanPrompt)?;
+        system_prompt.docs = prompt::find_agent_docs(root, config.agent_docs.files());
+        system_prompt
+    } else {
+        SystemPrompt::default()
+    };
+    let normalize_model = config.normalize.model().map(str::to_string);
+    let normalize = |stdout: &str| match &normalize_model {
+        Some(model) => normalize_json(stdout, PLAN_RESPONSE_SCHEMA, model),
+        None => Err(NormalizationError {
+            message: "Normalization is disabled by [normalize] in .ralph.toml".to_string(),
+            raw_output: stdout.to_string(),
+        }),
+    };
+
     // Initialize TUI
-    let mut terminal = tui::init_terminal();
+    let mut terminal = tui::init_terminal()?;
     let mut app = PlanApp::new();
+    app.theme = theme;
+    app.answer_defaults = config.plan.defaults;
 
     // If no description provided via CLI, show idea input screen first
-    let user_request: String = if let Some(desc) = request {
+    let user_request: String = if let Some(seed) = todo_request {
+        seed
+    } else if let Some(desc) = request {
         desc.to_string()
     } else {
         collect_idea(&mut terminal, &mut app)?;
         if app.should_quit {
-            tui::restore_terminal();
             return Ok(());
         }
         app.idea_input.clone()
     };
 
+    if session.is_fresh() {
+        session.request = Some(user_request.clone());
+    }
+
     // Build initial prompt
     let initial_prompt = if session.is_fresh() {
-        build_initial_prompt(&user_request)
+        build_initial_prompt(
+            &user_request,
+            opts.max_questions,
+            &system_prompt,
+            template.as_ref(),
+        )
     } else {
-        build_resume_prompt(session.turn_count, &session.last_phase.to_string())
+        let mut prompt = build_resume_prompt(session.turn_count, &session.last_phase.to_string());
+        if let Some(max) = opts.max_questions {
+            prompt.push_str("\n\n");
+            prompt.push_str(&question_limit(max, session.questions_asked));
+        }
+        prompt
     };
 
     app.status = format!("Starting plan session: {}", session.id);
     app.turn_count = session.turn_count;
+    app.cost_thresholds = opts.cost_warn.clone();
+    if session.total_cost_usd > 0.0 {
+        app.session_cost_usd = session.total_cost_usd;
+        app.priced_turns = session.turn_count;
+    }
+
+    // Feedback on a reviewed PRD, sent instead of the usual prompt
+    let mut next_prompt: Option<String> = None;
+
+    // Questions left open by an earlier run are picked up where they were left
+    let mut open_round = session.round.clone();
 
     // Main loop
     loop {
-        terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+        if let Some(round) = open_round.take() {
+            app.restore_round(&round);
+            // Submitted answers go straight back to Claude
+            if !round.submitted && !answer_round(&mut terminal, &mut app, &mut session, opts)? {
+                break;
+            }
+        }
+
+        terminal.draw(|f| app.draw(f))?;
 
         // Build prompt for this turn
-        let prompt = if session.is_fresh() {
+        let prompt = if let Some(prompt) = next_prompt.take() {
+            prompt
+        } else if session.is_fresh() {
             initial_prompt.clone()
         } else if !app.answers.is_empty() {
             build_continuation_prompt(&app.take_answers())
@@ -105,126 +386,200 @@ pub fn run(
 
         // Launch Claude
         app.status = "Invoking Claude...".to_string();
-        terminal.draw(|f| app.draw(f)).expect("Failed to draw");
-
-        // Session management:
-        // - Turn 1 (fresh): Use --session-id to create new session with our UUID
-        // - Turn 2+: Use --resume to continue that specific session by ID
-        // (using -c alone would continue the "last" session, which might not be ours
-        // if the user ran other claude commands in between)
-        let (session_id, resume_session_id) = if session.is_fresh() {
-            (Some(session.id.as_str()), None)
-        } else {
-            (None, Some(session.id.as_str()))
-        };
+        terminal.draw(|f| app.draw(f))?;
 
-        let opts = ClaudeOptions {
-            prompt: &prompt,
-            session_id,
-            resume_session_id,
-            continue_session: false,
-            json_schema: Some(PLAN_RESPONSE_SCHEMA),
-            bypass_permissions: true,
-            output_format: Some("json"), // Ensures clean JSON envelope with structured_output
-            ..Default::default()
-        };
+        // Retry transient failures (API 5xx, overloaded, empty output) with backoff
+        let mut retry_count = 0;
+        // Whether an attempt got far enough for Claude to create the session
+        let mut session_started = false;
+        let finished = loop {
+            if retry_count > 0 {
+                let delay = BASE_RETRY_DELAY_SECS * 2u64.pow(retry_count - 1);
+                let waiting = format!(
+                    "Retry {}/{} in {}s... (API error)",
+                    retry_count, MAX_RETRIES, delay
+                );
+                if app.processing {
+                    app.set_processing(true, &waiting);
+                } else {
+                    app.status = waiting;
+                }
+                wait_before_retry(&mut terminal, &mut app, Duration::from_secs(delay))?;
+                if app.should_quit {
+                    break None;
+                }
+            }
 
-        let mut child = launch_claude_with_options(&opts);
+            // Session management:
+            // - Turn 1 (fresh): Use --session-id to create new session with our UUID
+            // - Turn 2+: Use --resume to continue that specific session by ID
+            // (using -c alone would continue the "last" session, which might not be ours
+            // if the user ran other claude commands in between)
+            let (session_id, resume_session_id) = if session.is_fresh() && !session_started {
+                (Some(session.id.as_str()), None)
+            } else {
+                (None, Some(session.id.as_str()))
+            };
 
-        // Update processing message if in processing state, otherwise use status
-        if app.processing {
-            app.set_processing(true, "Waiting for Claude...");
-        } else {
-            app.status = "Waiting for Claude... (q=quit, Ctrl+C=kill)".to_string();
-        }
+            let claude_opts = ClaudeOptions {
+                prompt: &prompt,
+                session_id,
+                resume_session_id,
+                continue_session: false,
+                json_schema: Some(PLAN_RESPONSE_SCHEMA),
+                // Planning reads the repo; ralph writes the PRD from the structured output
+                permissions: Permissions::ReadOnly,
+                // One event per line for the live feed; the last one is the JSON envelope
+                // with structured_output
+                output_format: Some("stream-json"),
+                ..Default::default()
+            };
+            app.feed.clear();
+            let root = std::env::current_dir()?;
 
-        // Wait for Claude with event handling
-        while child.try_wait().expect("Failed to check child").is_none() {
-            // Advance spinner for visual feedback
-            app.advance_spinner();
-            terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+            // Wait for Claude with event handling; None if the user killed it
+            let finished = runtime::block_on(async {
+                let mut child =
+                    launch_claude_with_options(&claude_opts).map_err(RalphError::Claude)?;
+                let mut output = process::Output::capture(&mut child);
 
-            if event::poll(Duration::from_millis(100)).expect("Poll failed")
-                && let Event::Key(key) = event::read().expect("Failed to read event")
-            {
-                match (key.code, key.modifiers) {
-                    (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
-                        child.kill().expect("Failed to kill Claude");
-                        app.should_quit = true;
-                        app.set_processing(false, "");
-                        app.status = "Interrupted by user".to_string();
-                        break;
-                    }
-                    (KeyCode::Char('q') | KeyCode::Char('Q'), _) => {
-                        app.should_quit = true;
-                        app.status = "Will quit after Claude finishes...".to_string();
+                // Update processing message if in processing state, otherwise use status
+                if app.processing {
+                    app.set_processing(true, "Waiting for Claude...");
+                } else {
+                    app.status = "Waiting for Claude... (q=quit, Ctrl+C=kill)".to_string();
+                }
+
+                let mut keys = Keys::new();
+                let mut redraw = runtime::redraw_timer();
+                let mut lines = feed::LineReader::default();
+                loop {
+                    tokio::select! {
+                        status = child.wait() => {
+                            status.map_err(RalphError::Claude)?;
+                            break;
+                        }
+                        key = keys.next() => {
+                            let key = key?;
+                            if app.show_help && !key.modifiers.contains(KeyModifiers::CONTROL) {
+                                app.show_help = false;
+                                terminal.draw(|f| app.draw(f))?;
+                                continue;
+                            }
+                            match (key.code, key.modifiers) {
+                                (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
+                                    process::kill_tree(&mut child).map_err(RalphError::Claude)?;
+                                    let _ = child.wait().await;
+                                    app.should_quit = true;
+                                    app.set_processing(false, "");
+                                    app.status = "Interrupted by user".to_string();
+                                    return Ok::<_, RalphError>(None);
+                                }
+                                (KeyCode::Char('q') | KeyCode::Char('Q'), _) => {
+                                    app.should_quit = true;
+                                    app.status = "Will quit after Claude finishes...".to_string();
+                                }
+                                (KeyCode::Up, _) => app.scroll_up(1),
+                                (KeyCode::Down, _) => app.scroll_down(1),
+                                (KeyCode::Char('?'), _) => app.show_help = true,
+                                _ => {}
+                            }
+                        }
+                        // Advance spinner for visual feedback, and catch up on Claude's steps
+                        _ = redraw.tick() => {
+                            app.advance_spinner();
+                            let so_far = output.stdout_so_far();
+                            for line in lines.new_lines(&so_far) {
+                                app.feed.extend(feed::describe(line, &root));
+                            }
+                        }
                     }
-                    (KeyCode::Up, _) => app.scroll_up(1),
-                    (KeyCode::Down, _) => app.scroll_down(1),
-                    _ => {}
+                    terminal.draw(|f| app.draw(f))?;
                 }
+                Ok(Some(output.collect().await))
+            })?;
+
+            let Some((stream, stderr)) = finished.filter(|_| !app.should_quit) else {
+                break None;
+            };
+            let Some(error) = feed::transient_error(&stream, &stderr) else {
+                break Some((stream, stderr));
+            };
+            retry_count += 1;
+            if retry_count > MAX_RETRIES {
+                app.push_log(format!(
+                    "Failed after {} retries\n\nLast error: {}",
+                    MAX_RETRIES, error
+                ));
+                break Some((stream, stderr));
             }
-        }
+            app.push_log(format!("Transient error (will retry): {}", error));
+            // Claude's first event comes once the session exists
+            session_started |= !stream.trim().is_empty();
+        };
 
-        if app.should_quit {
+        let Some((stream, stderr)) = finished else {
             session.save()?;
             break;
-        }
-
-        // Get Claude's output
-        let output_result = child.wait_with_output().expect("Failed to get output");
-        let stdout = String::from_utf8_lossy(&output_result.stdout);
-        let stderr = String::from_utf8_lossy(&output_result.stderr);
+        };
+        let stdout = feed::result(&stream).unwrap_or(&stream).to_string();
 
         // Log the raw output
-        app.push_log(stdout.to_string());
+        app.push_log(format!("```json\n{}\n```", stdout.trim_end()));
 
         // Parse JSON response from Claude's output envelope
         // With --output-format json, the response is wrapped: { "structured_output": {...}, ... }
         //
         // Tier 1: Try strict parsing of the wrapper
-        // Tier 2: If that fails but looks like JSON, use Haiku to normalize
+        // Tier 2: If that fails but looks like JSON, use the [normalize] model to repair it
         // Tier 3: If both fail, return a clear error
         let response: PlanResponse = match serde_json::from_str::<ClaudeJsonOutput>(&stdout) {
-            Ok(wrapper) => match wrapper.structured_output {
-                Some(r) => r,
-                None => {
-                    // No structured_output - try Haiku fallback on the raw stdout
-                    app.status = "No structured_output, trying Haiku normalization...".to_string();
-                    terminal.draw(|f| app.draw(f)).expect("Failed to draw");
-                    app.push_log(
-                        "Tier 1 failed: No structured_output in wrapper. Trying Haiku..."
-                            .to_string(),
-                    );
-
-                    match normalize_json_with_haiku(&stdout, PLAN_RESPONSE_SCHEMA) {
-                        Ok(normalized) => match serde_json::from_str(&normalized) {
-                            Ok(r) => {
-                                app.push_log("Haiku normalization succeeded!".to_string());
-                                r
-                            }
+            Ok(wrapper) => {
+                if let Some(cost) = wrapper.total_cost_usd {
+                    session.total_cost_usd += cost;
+                    if let Some(threshold) = app.record_turn_cost(cost) {
+                        app.push_log(format!(
+                            "WARNING: plan session cost ${:.2} has crossed ${:.2}",
+                            app.session_cost_usd, threshold
+                        ));
+                    }
+                }
+                match wrapper.structured_output {
+                    Some(r) => r,
+                    None => {
+                        // No structured_output - try normalization on the raw stdout
+                        app.status = "No structured_output, trying normalization...".to_string();
+                        terminal.draw(|f| app.draw(f))?;
+                        app.push_log(
+                            "Tier 1 failed: No structured_output in wrapper. Trying normalization..."
+                                .to_string(),
+                        );
+
+                        match normalize(&stdout) {
+                            Ok(normalized) => match serde_json::from_str(&normalized) {
+                                Ok(r) => {
+                                    app.push_log("Normalization succeeded!".to_string());
+                                    r
+                                }
+                                Err(e) => {
+                                    let error_detail = format!(
+                                        "Normalization returned invalid JSON: {}\n\nNormalized output:\n{}",
+                                        e, normalized
+                                    );
+                                    app.push_log(format!("ERROR: {}", error_detail));
+                                    return Err(PlanError::InvalidOutput(error_detail));
+                                }
+                            },
                             Err(e) => {
-                                let error_detail = format!(
-                                    "Haiku returned invalid JSON: {}\n\nNormalized output:\n{}",
-                                    e, normalized
-                                );
+                                let error_detail =
+                                    format!("Both strict parsing and normalization failed:\n{}", e);
                                 app.push_log(format!("ERROR: {}", error_detail));
-                                tui::restore_terminal();
                                 return Err(PlanError::InvalidOutput(error_detail));
                             }
-                        },
-                        Err(e) => {
-                            let error_detail = format!(
-                                "Both strict parsing and Haiku normalization failed:\n{}",
-                                e
-                            );
-                            app.push_log(format!("ERROR: {}", error_detail));
-                            tui::restore_terminal();
-                            return Err(PlanError::InvalidOutput(error_detail));
                         }
                     }
                 }
-            },
+            }
             Err(parse_err) => {
                 // Check if it looks like JSON at all
                 let trimmed = stdout.trim();
@@ -237,44 +592,41 @@ pub fn run(
                         format!("stdout: {}\nstderr: {}", stdout, stderr)
                     };
                     app.push_log(format!(
-                        "ERROR: Expected JSON but got plain text.\n\nRaw output:\n{}",
+                        "ERROR: Expected JSON but got plain text.\n\nRaw output:\n```\n{}\n```",
                         error_detail
                     ));
-                    tui::restore_terminal();
                     return Err(PlanError::InvalidOutput(error_detail));
                 }
 
-                // Looks like JSON but malformed - try Haiku normalization
-                app.status = "Normalizing response with Haiku...".to_string();
-                terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+                // Looks like JSON but malformed - try normalization
+                app.status = "Normalizing response...".to_string();
+                terminal.draw(|f| app.draw(f))?;
                 app.push_log(format!(
-                    "Tier 1 failed: Parse error: {}\nTrying Haiku normalization...",
+                    "Tier 1 failed: Parse error: {}\nTrying normalization...",
                     parse_err
                 ));
 
-                match normalize_json_with_haiku(&stdout, PLAN_RESPONSE_SCHEMA) {
+                match normalize(&stdout) {
                     Ok(normalized) => match serde_json::from_str(&normalized) {
                         Ok(r) => {
-                            app.push_log("Haiku normalization succeeded!".to_string());
+                            app.push_log("Normalization succeeded!".to_string());
                             r
                         }
                         Err(e) => {
                             let error_detail = format!(
-                                "Haiku returned invalid JSON: {}\n\nNormalized output:\n{}",
+                                "Normalization returned invalid JSON: {}\n\nNormalized output:\n{}",
                                 e, normalized
                             );
                             app.push_log(format!("ERROR: {}", error_detail));
-                            tui::restore_terminal();
                             return Err(PlanError::InvalidOutput(error_detail));
                         }
                     },
                     Err(e) => {
                         let error_detail = format!(
-                            "Both strict parsing and Haiku normalization failed.\n\nOriginal error: {}\n\nHaiku error: {}",
+                            "Both strict parsing and normalization failed.\n\nOriginal error: {}\n\nNormalization error: {}",
                             parse_err, e
                         );
                         app.push_log(format!("ERROR: {}", error_detail));
-                        tui::restore_terminal();
                         return Err(PlanError::InvalidOutput(error_detail));
                     }
                 }
@@ -287,6 +639,7 @@ pub fn run(
         // Update app state from response
         app.update_from_response(&response);
         session.advance(response.phase);
+        session.finish_round();
 
         // Merge any context
         if let Some(context) = response.context {
@@ -299,19 +652,56 @@ pub fn run(
         // Handle phase-specific logic
         match response.phase {
             PlanPhase::Complete => {
-                // PRD is ready - write to output file
-                if let Some(prd) = response.prd {
+                // PRD is ready - review it, then write it to the output file
+                if let Some(mut prd) = response.prd {
+                    let request = session.request.as_deref().unwrap_or(&user_request);
+                    let answers: Vec<&str> =
+                        session.answers.iter().map(|a| a.value.as_str()).collect();
+                    let coverage = coverage::check(request, &answers, &prd.tasks);
+                    let coverage_report = coverage::report(&coverage);
+                    prd.coverage = Some(coverage);
+
+                    app.review = Some(PrdReview::new(prd, coverage_report));
+                    let outcome = review_prd(&mut terminal, &mut app)?;
+                    let PrdReview {
+                        prd,
+                        coverage_report,
+                        ..
+                    } = app
+                        .review
+                        .take()
+                        .expect("review_prd leaves the review in place");
+
+                    match outcome {
+                        ReviewOutcome::Accept => {}
+                        ReviewOutcome::Regenerate(feedback) => {
+                            let prd_json = serde_json::to_string_pretty(&prd)?;
+                            app.push_log(format!("Sent feedback on the PRD:\n\n{}", feedback));
+                            next_prompt = Some(build_feedback_prompt(&feedback, &prd_json));
+                            app.set_processing(true, "Sending feedback to Claude...");
+                            continue;
+                        }
+                        ReviewOutcome::Quit => {
+                            session.save()?;
+                            break;
+                        }
+                    }
+
                     let prd_json = serde_json::to_string_pretty(&prd)?;
+                    let content = render_prd(&serde_json::to_value(&prd)?, opts.format)?;
                     let mut file = std::fs::File::create(output)?;
-                    file.write_all(prd_json.as_bytes())?;
+                    file.write_all(content.as_bytes())?;
 
                     app.status = format!("PRD written to {}", output);
-                    app.push_log(format!("PRD generated successfully!\n\n{}", prd_json));
+                    app.push_log(format!(
+                        "PRD generated successfully!\n\n{}\n\n```json\n{}\n```",
+                        coverage_report, prd_json
+                    ));
 
                     // Cleanup session file on success
                     let _ = session.cleanup();
                 }
-                terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+                terminal.draw(|f| app.draw(f))?;
 
                 // Wait for user to acknowledge
                 wait_for_key(&mut terminal, &mut app)?;
@@ -319,33 +709,45 @@ pub fn run(
             }
             PlanPhase::Asking => {
                 // Claude needs input - show questions and collect answers
-                if let Some(questions) = response.questions {
+                if let Some(mut questions) = response.questions {
+                    // Past --max-questions, questions are answered for the user
+                    let mut deferred = Vec::new();
+                    if let Some(max) = opts.max_questions {
+                        let remaining = max.saturating_sub(session.questions_asked) as usize;
+                        if questions.len() > remaining {
+                            deferred = questions.split_off(remaining);
+                            app.push_log(format!(
+                                "Deferred {} question(s) past --max-questions {}: {}",
+                                deferred.len(),
+                                max,
+                                deferred
+                                    .iter()
+                                    .map(|q| q.id.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ));
+                        }
+                    }
+                    session.questions_asked += questions.len() as u32;
                     app.set_questions(questions);
-                    collect_answers(&mut terminal, &mut app)?;
-
-                    if app.should_quit {
-                        session.save()?;
-                        break;
+                    session.round = Some(QuestionRound {
+                        questions: app.questions.clone(),
+                        deferred,
+                        ..Default::default()
+                    });
+                    if opts.accept_defaults {
+                        let answered = app.answer_from_defaults();
+                        if answered > 0 {
+                            app.push_log(format!(
+                                "Answered {} of {} question(s) from [plan.defaults]",
+                                answered,
+                                app.questions.len()
+                            ));
+                        }
                     }
-
-                    // Only proceed if user explicitly submitted
-                    if !app.should_submit {
-                        // User didn't submit (maybe navigated away) - save and break
-                        session.save()?;
+                    if !answer_round(&mut terminal, &mut app, &mut session, opts)? {
                         break;
                     }
-
-                    // Immediately show processing state for user feedback
-                    app.set_processing(true, "Sending answers to Claude...");
-                    terminal.draw(|f| app.draw(f)).expect("Failed to draw");
-
-                    // Store answers in session
-                    for answer in &app.answers {
-                        session.add_answer(answer.clone());
-                    }
-
-                    // Reset for next round
-                    app.reset_submit();
                 }
             }
             PlanPhase::Exploring | PlanPhase::Working => {
@@ -354,10 +756,10 @@ pub fn run(
             }
         }
 
-        terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+        terminal.draw(|f| app.draw(f))?;
     }
 
-    tui::restore_terminal();
+    drop(terminal);
 
     println!("\n═══════════════════════════════════════════════════════════════");
     println!("Ralph Plan Session Complete");
@@ -372,19 +774,85 @@ pub fn run(
 }
 
 /// Collect the user's idea/description via TUI before starting Claude
-fn collect_idea(
+/// Build the initial request from the working directory's TODO comments
+fn todo_request(description: Option<&str>) -> Result<String, PlanError> {
+    let root = Path::new(".");
+    let items = todos::scan(root)?;
+    if items.is_empty() {
+        let cwd = std::env::current_dir()?;
+        return Err(PlanError::NoTodos(cwd.display().to_string()));
+    }
+    Ok(todos::format_request(&todos::cluster(&items), description))
+}
+
+/// Run the tests and write a PRD with one task per failing suite
+fn plan_from_failing_tests(
+    output: &Path,
+    test_cmd: Option<&str>,
+    format: PrdFormat,
+) -> Result<(), PlanError> {
+    let command = match test_cmd {
+        Some(cmd) => cmd,
+        None => {
+            test_failures::detect_test_command(Path::new(".")).ok_or(PlanError::NoTestCommand)?
+        }
+    };
+
+    println!("Running `{}`...", command);
+    let result = runtime::block_on(async {
+        let gate = RunningGate::spawn(command, None)?;
+        std::io::Result::Ok(gate.wait().await)
+    })?;
+    let failures = test_failures::parse_failures(command, &result.output);
+    if failures.is_empty() {
+        if result.passed() {
+            return Err(PlanError::TestsPassing(command.to_string()));
+        }
+        let tail: Vec<&str> = result.output.lines().rev().take(20).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(PlanError::UnrecognisedFailures(
+            command.to_string(),
+            tail.join("\n"),
+        ));
+    }
+
+    let prd = test_failures::failures_to_prd(command, &failures);
+    std::fs::write(output, render_prd(&serde_json::to_value(&prd)?, format)?)?;
+
+    println!(
+        "Found {} failing test(s) in {} suite(s)",
+        failures.len(),
+        prd.tasks.len()
+    );
+    for (i, task) in prd.tasks.iter().enumerate() {
+        println!("  {}. {}", i + 1, task.description);
+    }
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+pub fn collect_idea(
     terminal: &mut ratatui::DefaultTerminal,
     app: &mut PlanApp,
 ) -> Result<(), PlanError> {
     app.awaiting_idea = true;
+    tui::enable_key_disambiguation();
 
-    loop {
-        terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+    runtime::block_on(async {
+        let mut keys = Keys::new();
+        loop {
+            terminal.draw(|f| app.draw(f))?;
 
-        if event::poll(Duration::from_millis(100)).expect("Poll failed")
-            && let Event::Key(key) = event::read().expect("Failed to read event")
-        {
+            let key = keys.next().await?;
             match key.code {
+                // Shift+Enter where the terminal reports it, Alt+Enter everywhere
+                KeyCode::Enter
+                    if key
+                        .modifiers
+                        .intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) =>
+                {
+                    app.idea_insert('\n');
+                }
                 KeyCode::Enter if !app.idea_input.trim().is_empty() => {
                     app.awaiting_idea = false;
                     return Ok(());
@@ -394,40 +862,62 @@ fn collect_idea(
                     app.awaiting_idea = false;
                     return Ok(());
                 }
-                KeyCode::Char(c) => {
-                    app.idea_input.insert(app.idea_cursor, c);
-                    app.idea_cursor += 1;
-                }
-                KeyCode::Backspace if app.idea_cursor > 0 => {
-                    app.idea_cursor -= 1;
-                    app.idea_input.remove(app.idea_cursor);
-                }
-                KeyCode::Left if app.idea_cursor > 0 => {
-                    app.idea_cursor -= 1;
-                }
-                KeyCode::Right if app.idea_cursor < app.idea_input.len() => {
-                    app.idea_cursor += 1;
-                }
+                KeyCode::Char(c) => app.idea_insert(c),
+                KeyCode::Backspace => app.idea_backspace(),
+                KeyCode::Left => app.idea_left(),
+                KeyCode::Right => app.idea_right(),
+                KeyCode::Up => app.idea_up_down(false),
+                KeyCode::Down => app.idea_up_down(true),
+                KeyCode::Home => app.idea_home_end(false),
+                KeyCode::End => app.idea_home_end(true),
                 _ => {}
             }
         }
-    }
+    })
 }
 
 /// Collect answers from the user via TUI
 /// Requires explicit Ctrl+Enter to submit all answers
-fn collect_answers(
+///
+/// With `speculation`, a forked background turn is launched once the first answer is in,
+/// and can be cancelled with `x`.
+pub fn collect_answers(
     terminal: &mut ratatui::DefaultTerminal,
     app: &mut PlanApp,
+    mut speculation: Option<(&str, &mut Speculation)>,
+    mut session: Option<&mut PlanSession>,
 ) -> Result<(), PlanError> {
     app.reset_submit();
 
-    loop {
-        terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+    runtime::block_on(async {
+        let mut keys = Keys::new();
+        loop {
+            if let Some((session_id, spec)) = speculation.as_mut() {
+                if spec.is_idle() && !app.answers.is_empty() && !app.all_answered() {
+                    **spec = Speculation::start(session_id, &app.answers);
+                }
+                app.speculation_label = spec.label();
+            }
 
-        if event::poll(Duration::from_millis(100)).expect("Poll failed")
-            && let Event::Key(key) = event::read().expect("Failed to read event")
-        {
+            terminal.draw(|f| app.draw(f))?;
+
+            // A finished fork only changes the header, which the next pass redraws
+            let fork_finished = async {
+                match speculation.as_mut() {
+                    Some((_, spec)) => spec.finished().await,
+                    None => pending().await,
+                }
+            };
+            let key = tokio::select! {
+                key = keys.next() => key?,
+                () = fork_finished => continue,
+            };
+            // Any key closes the help overlay
+            if app.show_help && !key.modifiers.contains(KeyModifiers::CONTROL) {
+                app.show_help = false;
+                continue;
+            }
+            let before = progress(app);
             match app.input_mode {
                 InputMode::Editing => {
                     match key.code {
@@ -483,6 +973,16 @@ fn collect_answers(
                             app.should_quit = true;
                             return Ok(());
                         }
+                        // ?: list every key
+                        (KeyCode::Char('?'), _) => {
+                            app.show_help = true;
+                        }
+                        // x: cancel the speculative background turn
+                        (KeyCode::Char('x'), _) => {
+                            if let Some((_, spec)) = speculation.as_mut() {
+                                spec.cancel();
+                            }
+                        }
                         // i: enter editing mode for freeform input
                         (KeyCode::Char('i'), _) => {
                             if let Some(q) = app.current_question()
@@ -491,6 +991,13 @@ fn collect_answers(
                                 app.enter_editing();
                             }
                         }
+                        // u: take back the last answer and return to its question
+                        (KeyCode::Char('u'), _) => {
+                            let undone = app.undo_answer();
+                            if !undone {
+                                app.status = "No answer to undo".to_string();
+                            }
+                        }
                         // Up/Down: navigate options
                         (KeyCode::Up, _) => {
                             app.prev_option();
@@ -499,10 +1006,8 @@ fn collect_answers(
                             app.next_option();
                         }
                         // Tab: next question
-                        (KeyCode::Tab, _) => {
-                            if app.current_question + 1 < app.questions.len() {
-                                app.next_question();
-                            }
+                        (KeyCode::Tab, _) if app.current_question + 1 < app.questions.len() => {
+                            app.next_question();
                         }
                         // Shift+Tab: previous question
                         (KeyCode::BackTab, _) => {
@@ -523,8 +1028,202 @@ fn collect_answers(
                     }
                 }
             }
+
+            // Save every answer, so a crash or quit doesn't lose the round
+            if let Some(session) = session.as_mut()
+                && progress(app) != before
+            {
+                session.update_round(&app.answers, app.current_question);
+                session.save()?;
+            }
         }
+    })
+}
+
+/// What saving a round of questions records: the answers and the question shown
+fn progress(app: &PlanApp) -> (usize, Option<(String, String)>, usize) {
+    let last = app
+        .answers
+        .last()
+        .map(|a| (a.question_id.clone(), a.value.clone()));
+    (app.answers.len(), last, app.current_question)
+}
+
+/// Have the user answer the session's round of questions, saving it after every answer.
+/// Returns false if they quit instead of submitting.
+fn answer_round(
+    terminal: &mut ratatui::DefaultTerminal,
+    app: &mut PlanApp,
+    session: &mut PlanSession,
+    opts: &PlanOptions,
+) -> Result<bool, PlanError> {
+    session.update_round(&app.answers, app.current_question);
+    session.save()?;
+
+    let mut speculation = Speculation::Idle;
+    if app.questions.is_empty() || app.all_answered() {
+        // Nothing left to ask
+        app.should_submit = true;
+    } else {
+        let session_id = session.id.clone();
+        collect_answers(
+            terminal,
+            app,
+            opts.speculate
+                .then_some((session_id.as_str(), &mut speculation)),
+            Some(&mut *session),
+        )?;
+    }
+
+    // A finished fork already holds pre-work; continue from it. Anything
+    // still running is discarded so the real turn isn't raced.
+    if app.should_submit
+        && let Some(fork_id) = speculation.take_ready()
+    {
+        app.push_log(format!(
+            "Continuing from speculative fork {} (was {})",
+            fork_id, session.id
+        ));
+        session.id = fork_id;
+    }
+    speculation.cancel();
+    app.speculation_label = None;
+
+    // Quit, or left without submitting: the round stays in the session for --resume
+    if app.should_quit || !app.should_submit {
+        session.update_round(&app.answers, app.current_question);
+        session.save()?;
+        return Ok(false);
     }
+
+    // Immediately show processing state for user feedback
+    app.set_processing(true, "Sending answers to Claude...");
+    terminal.draw(|f| app.draw(f))?;
+
+    let deferred = session
+        .round
+        .as_ref()
+        .map(|round| round.deferred.clone())
+        .unwrap_or_default();
+    app.answers.extend(deferred.into_iter().map(|q| Answer {
+        question_id: q.id,
+        value: DEFERRED_ANSWER.to_string(),
+    }));
+
+    // Kept until Claude responds, so a crash on the way resends them on --resume
+    session.update_round(&app.answers, app.current_question);
+    if let Some(round) = &mut session.round {
+        round.submitted = true;
+    }
+    session.save()?;
+
+    // Reset for next round
+    app.reset_submit();
+    Ok(true)
+}
+
+/// Let the user go over the PRD in `app.review` before it is written: accept it, edit task
+/// descriptions, or send feedback for Claude to regenerate it
+fn review_prd(
+    terminal: &mut ratatui::DefaultTerminal,
+    app: &mut PlanApp,
+) -> Result<ReviewOutcome, PlanError> {
+    app.status = "Review the PRD before it is written".to_string();
+
+    runtime::block_on(async {
+        let mut keys = Keys::new();
+        loop {
+            terminal.draw(|f| app.draw(f))?;
+            let key = keys.next().await?;
+            // Any key closes the help overlay
+            if app.show_help && !key.modifiers.contains(KeyModifiers::CONTROL) {
+                app.show_help = false;
+                continue;
+            }
+            let Some(review) = app.review.as_mut() else {
+                return Ok(ReviewOutcome::Accept);
+            };
+
+            if review.input.is_some() {
+                match key.code {
+                    KeyCode::Esc => {
+                        review.finish_input(false);
+                    }
+                    KeyCode::Enter => {
+                        if let Some(feedback) = review.finish_input(true) {
+                            return Ok(ReviewOutcome::Regenerate(feedback));
+                        }
+                    }
+                    KeyCode::Backspace => review.pop_char(),
+                    KeyCode::Char(c) => review.push_char(c),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
+                    return Ok(ReviewOutcome::Quit);
+                }
+                (KeyCode::Char('q') | KeyCode::Char('Q'), _) => {
+                    return Ok(ReviewOutcome::Quit);
+                }
+                (KeyCode::Enter | KeyCode::Char('a'), _) => {
+                    return Ok(ReviewOutcome::Accept);
+                }
+                (KeyCode::Char('?'), _) => {
+                    app.show_help = true;
+                }
+                (KeyCode::Char('e'), _) => review.start_editing(),
+                (KeyCode::Char('f'), _) => review.start_feedback(),
+                (KeyCode::Up | KeyCode::Char('k'), _) => review.select_prev(),
+                (KeyCode::Down | KeyCode::Char('j'), _) => review.select_next(),
+                _ => {}
+            }
+        }
+    })
+}
+
+/// Wait `delay` before retrying Claude, keeping the TUI responsive. Ctrl+C or q sets
+/// `should_quit`.
+fn wait_before_retry(
+    terminal: &mut ratatui::DefaultTerminal,
+    app: &mut PlanApp,
+    delay: Duration,
+) -> Result<(), PlanError> {
+    runtime::block_on(async {
+        let done = tokio::time::sleep(delay);
+        tokio::pin!(done);
+        let mut keys = Keys::new();
+        let mut redraw = runtime::redraw_timer();
+        loop {
+            terminal.draw(|f| app.draw(f))?;
+            tokio::select! {
+                () = &mut done => return Ok(()),
+                key = keys.next() => {
+                    let key = key?;
+                    if app.show_help && !key.modifiers.contains(KeyModifiers::CONTROL) {
+                        app.show_help = false;
+                        continue;
+                    }
+                    match (key.code, key.modifiers) {
+                        (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                            app.status = "Interrupted by user".to_string();
+                            return Ok(());
+                        }
+                        (KeyCode::Char('q') | KeyCode::Char('Q'), _) => {
+                            app.should_quit = true;
+                            return Ok(());
+                        }
+                        (KeyCode::Char('?'), _) => app.show_help = true,
+                        _ => {}
+                    }
+                }
+                _ = redraw.tick() => app.advance_spinner(),
+            }
+        }
+    })
 }
 
 /// Wait for user to press any key
@@ -533,13 +1232,8 @@ fn wait_for_key(
     app: &mut PlanApp,
 ) -> Result<(), PlanError> {
     app.status = "PRD complete! Press any key to exit...".to_string();
-    terminal.draw(|f| app.draw(f)).expect("Failed to draw");
+    terminal.draw(|f| app.draw(f))?;
 
-    loop {
-        if event::poll(Duration::from_millis(100)).expect("Poll failed")
-            && let Event::Key(_) = event::read().expect("Failed to read event")
-        {
-            return Ok(());
-        }
-    }
+    runtime::block_on(Keys::new().next())?;
+    Ok(())
 }
@@ -0,0 +1,83 @@
+use clap::{Args, Subcommand};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::commands::{CommandResult, Registered};
+use crate::config::{self, ConfigError};
+use ralph_core::iteration;
+use ralph_core::prd;
+use ralph_core::prompt::{self, MasterPrompt};
+use ralph_core::toc;
+
+#[derive(Error, Debug)]
+pub enum PromptError {
+    #[error("Could not read {}: {0}", prompt::PROMPT_FILE)]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+}
+
+/// Inspect the build loop's prompt
+#[derive(Args, Debug)]
+pub struct PromptArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json", global = true)]
+    pub prd_path: String,
+
+    #[command(subcommand)]
+    pub action: PromptAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PromptAction {
+    /// Print the prompt a build loop starts from: the instructions in .ralph/prompt.md, or
+    /// the built-in ones, with their variables and the PRD's task order filled in
+    Show,
+}
+
+pub static COMMAND: Registered<PromptArgs> = Registered::new("prompt", run_args);
+
+fn run_args(args: PromptArgs) -> CommandResult {
+    match args.action {
+        PromptAction::Show => Ok(show(&args.prd_path)?),
+    }
+}
+
+/// Print the effective loop prompt for the PRD at `prd_path`
+pub fn show(prd_path: &str) -> Result<(), PromptError> {
+    let root = Path::new(".");
+    let mut master = MasterPrompt::load(root)?;
+    let config = config::load(root)?;
+    master.addenda = config.prompt.categories;
+    master.docs = prompt::find_agent_docs(root, config.agent_docs.files());
+    match &master.path {
+        Some(path) => eprintln!("Loop instructions from {}", path.display()),
+        None => eprintln!(
+            "Built-in loop instructions ({} overrides them)",
+            prompt::PROMPT_FILE
+        ),
+    }
+    // Without a readable PRD there's just no task order to show
+    let constraints = match prd::read_prd(prd_path) {
+        // With category addenda, the build picks the task and adds its addendum
+        Ok(prd) if !master.addenda.is_empty() => iteration::next_task(&prd, &[])
+            .map(|(i, task)| {
+                let mut section = prompt::task_focus_section(i + 1, &task.description);
+                section.extend(master.addendum_section(&task.category));
+                section
+            })
+            .unwrap_or_default(),
+        Ok(prd) => {
+            let entries = toc::build(&prd);
+            let order: Vec<&toc::TocEntry> = entries
+                .iter()
+                .filter(|e| e.status != toc::TocStatus::Done)
+                .collect();
+            prompt::task_order_section(&order).unwrap_or_default()
+        }
+        Err(_) => String::new(),
+    };
+    println!("{}", master.prompt(prd_path, &constraints));
+    Ok(())
+}
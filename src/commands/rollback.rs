@@ -0,0 +1,152 @@
+//! `ralph rollback`: put the repo, PRD and completed.json back how they were before a loop
+//! of the last build run, from the checkpoint the build took when the loop started.
+
+use clap::Args;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::commands::build::is_yes;
+use crate::commands::{CommandResult, Registered};
+use ralph_core::checkpoint::{Checkpoint, CheckpointError};
+use ralph_core::git::{self, GitError};
+use ralph_core::prd;
+use ralph_core::run_state::{RunState, RunStateError};
+
+#[derive(Error, Debug)]
+pub enum RollbackError {
+    #[error("{0}")]
+    RunState(#[from] RunStateError),
+
+    #[error("{0}")]
+    Checkpoint(#[from] CheckpointError),
+
+    #[error("{0}")]
+    Git(#[from] GitError),
+
+    #[error("No build run recorded for {0}")]
+    NoRun(String),
+
+    #[error(
+        "The last run recorded next to {prd} built {run_prd}; pass -p {run_prd} to roll it back"
+    )]
+    OtherPrd { prd: String, run_prd: String },
+
+    #[error("No checkpoint for loop #{0} of the last run (it ran {1} loop(s))")]
+    NoCheckpoint(u64, u64),
+
+    #[error("Rolling back discards work; pass --yes to confirm when not at a terminal")]
+    NotConfirmed,
+}
+
+/// Reset the repo, PRD and completed.json to how they were before a loop of the last run
+#[derive(Args, Debug)]
+pub struct RollbackArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+
+    /// The loop to undo, along with every loop after it
+    #[arg(long = "loop", value_name = "N")]
+    pub loop_number: u64,
+
+    /// Don't ask for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+pub static COMMAND: Registered<RollbackArgs> = Registered::new("rollback", run_args);
+
+fn run_args(args: RollbackArgs) -> CommandResult {
+    Ok(run(&args)?)
+}
+
+pub fn run(args: &RollbackArgs) -> Result<(), RollbackError> {
+    let work_dir = Path::new(".");
+    let mut run_state = RunState::load(&args.prd_path)?
+        .ok_or_else(|| RollbackError::NoRun(args.prd_path.clone()))?;
+    if !same_file(&run_state.prd_path, &args.prd_path) {
+        return Err(RollbackError::OtherPrd {
+            prd: args.prd_path.clone(),
+            run_prd: run_state.prd_path,
+        });
+    }
+    let checkpoint = Checkpoint::load(&args.prd_path, &run_state.id, args.loop_number)?.ok_or(
+        RollbackError::NoCheckpoint(args.loop_number, run_state.loop_count),
+    )?;
+    let current = if checkpoint.head.is_some() {
+        git::head_commit(work_dir)?
+    } else {
+        None
+    };
+
+    println!(
+        "Rolling back to before loop #{} (started {}):",
+        args.loop_number,
+        checkpoint
+            .taken_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+    );
+    if let Some(head) = &checkpoint.head {
+        let commits = git::commits_since(work_dir, Some(head))?;
+        println!(
+            "  reset HEAD to {} ({} commit(s) dropped)",
+            short(head),
+            commits.len()
+        );
+        if git::has_uncommitted_changes(work_dir)? {
+            println!("  uncommitted changes to tracked files are discarded");
+        }
+    }
+    println!(
+        "  restore {} and {}",
+        args.prd_path,
+        prd::completed_path_for(&args.prd_path).display()
+    );
+
+    if !args.yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(RollbackError::NotConfirmed);
+        }
+        print!("Continue? [y/N] ");
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_err() || !is_yes(&answer) {
+            println!("Nothing changed.");
+            return Ok(());
+        }
+    }
+
+    checkpoint.restore(&args.prd_path, work_dir)?;
+    run_state.loop_count = args.loop_number - 1;
+    run_state.step_progress = checkpoint.step_progress.clone();
+    run_state
+        .task_transitions
+        .retain(|t| t.loop_number < args.loop_number);
+    run_state.save()?;
+
+    println!("Rolled back to before loop #{}.", args.loop_number);
+    if let Some(current) = current
+        && checkpoint.head.as_ref() != Some(&current)
+    {
+        println!(
+            "The old HEAD was {}; `git reset --hard {}` brings it back.",
+            short(&current),
+            current
+        );
+    }
+    Ok(())
+}
+
+/// Whether two PRD paths name the same file, however each was spelled
+fn same_file(a: &str, b: &str) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(8)]
+}
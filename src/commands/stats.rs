@@ -0,0 +1,173 @@
+use clap::Args;
+
+use crate::app::format_secs;
+use crate::commands::{CommandResult, Registered};
+use ralph_core::prd;
+use ralph_core::stats::{
+    HISTOGRAM_BUCKETS_MINS, Stats, StatsError, Summary, TaskStats, duration_histogram,
+};
+
+/// Widest histogram bar, in characters
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Show iteration duration and cost stats for a PRD's builds
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+
+    /// Break the stats down per task category, model and task, with a duration histogram
+    #[arg(long)]
+    pub detailed: bool,
+}
+
+pub static COMMAND: Registered<StatsArgs> = Registered::new("stats", run_args);
+
+fn run_args(args: StatsArgs) -> CommandResult {
+    Ok(run(&args.prd_path, args.detailed)?)
+}
+
+/// Run the stats command - summarise recorded build iterations for a PRD
+pub fn run(prd_path: &str, detailed: bool) -> Result<(), StatsError> {
+    let stats = Stats::load(prd_path)?;
+    let Some(overall) = stats.overall() else {
+        println!(
+            "No iterations recorded for {} yet. Stats are collected by `ralph build`.",
+            prd_path
+        );
+        return Ok(());
+    };
+
+    println!(
+        "{}: {} iterations, ${:.2} total",
+        prd_path, overall.count, overall.cost_total
+    );
+    println!(
+        "Duration: p50 {}, p90 {} (mean {})",
+        format_secs(overall.duration_p50),
+        format_secs(overall.duration_p90),
+        format_secs(overall.duration_mean)
+    );
+    println!(
+        "Cost per iteration: p50 ${:.2}, p90 ${:.2}",
+        overall.cost_p50, overall.cost_p90
+    );
+    if let Some(mean) = overall.rating_mean {
+        println!(
+            "Rating: {:.1}/5 from {} rated iterations",
+            mean, overall.rated
+        );
+    }
+
+    // The ETA is extra: skip it rather than fail the stats when the PRD can't be read
+    if let Ok(prd) = prd::read_prd(prd_path) {
+        let pending = prd.pending_count();
+        if pending > 0
+            && let Some((p50, p90)) = stats.eta_secs(&prd)
+        {
+            println!(
+                "ETA for {} pending tasks: ~{} (p50), up to {} (p90)",
+                pending,
+                format_secs(p50),
+                format_secs(p90)
+            );
+        }
+    }
+
+    if detailed {
+        println!("\nBy category:");
+        print_table(&stats.by_category().into_iter().collect::<Vec<_>>());
+        println!("\nBy model:");
+        print_table(&stats.by_model().into_iter().collect::<Vec<_>>());
+        println!("\nBy thinking:");
+        print_table(&stats.by_thinking().into_iter().collect::<Vec<_>>());
+        let tasks = stats.by_task();
+        if !tasks.is_empty() {
+            println!("\nBy task:");
+            print_task_table(tasks.into_iter().collect());
+        }
+        println!("\nIteration durations:");
+        print_histogram(&duration_histogram(&stats.iterations));
+    }
+    Ok(())
+}
+
+fn print_table(rows: &[(String, Summary)]) {
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("name".len());
+    println!(
+        "  {:<width$}  {:>5}  {:>8}  {:>8}  {:>8}  {:>8}  {:>9}  {:>9}",
+        "name", "iters", "p50 time", "p90 time", "p50 cost", "p90 cost", "total", "rating"
+    );
+    for (name, s) in rows {
+        let rating = match s.rating_mean {
+            Some(mean) => format!("{:.1} ({})", mean, s.rated),
+            None => "-".to_string(),
+        };
+        println!(
+            "  {:<width$}  {:>5}  {:>8}  {:>8}  {:>8}  {:>8}  {:>9}  {:>9}",
+            name,
+            s.count,
+            format_secs(s.duration_p50),
+            format_secs(s.duration_p90),
+            format!("${:.2}", s.cost_p50),
+            format!("${:.2}", s.cost_p90),
+            format!("${:.2}", s.cost_total),
+            rating
+        );
+    }
+}
+
+/// Longest task description shown before it's cut off
+const TASK_WIDTH: usize = 50;
+
+/// Slowest tasks on average first, so the ones that balloon are at the top
+fn print_task_table(mut rows: Vec<(String, TaskStats)>) {
+    rows.sort_by(|a, b| b.1.duration_mean().total_cmp(&a.1.duration_mean()));
+    println!(
+        "  {:<TASK_WIDTH$}  {:>5}  {:>7}  {:>6}  {:>8}  {:>9}",
+        "task", "loops", "retries", "failed", "avg time", "total"
+    );
+    for (task, s) in rows {
+        let task = if task.chars().count() > TASK_WIDTH {
+            let cut: String = task.chars().take(TASK_WIDTH - 1).collect();
+            format!("{}…", cut)
+        } else {
+            task
+        };
+        println!(
+            "  {:<TASK_WIDTH$}  {:>5}  {:>7}  {:>6}  {:>8}  {:>9}",
+            task,
+            s.attempts,
+            s.retries(),
+            s.failed,
+            format_secs(s.duration_mean()),
+            format!("${:.2}", s.cost_total)
+        );
+    }
+}
+
+fn print_histogram(counts: &[usize]) {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    for (i, &count) in counts.iter().enumerate() {
+        let label = match i {
+            0 => format!("< {}m", HISTOGRAM_BUCKETS_MINS[0]),
+            i if i < HISTOGRAM_BUCKETS_MINS.len() => format!(
+                "{}-{}m",
+                HISTOGRAM_BUCKETS_MINS[i - 1],
+                HISTOGRAM_BUCKETS_MINS[i]
+            ),
+            _ => format!(
+                ">= {}m",
+                HISTOGRAM_BUCKETS_MINS[HISTOGRAM_BUCKETS_MINS.len() - 1]
+            ),
+        };
+        let bar = "█".repeat(count * HISTOGRAM_WIDTH / max);
+        println!("  {:<8} {} {}", label, bar, count);
+    }
+}
@@ -0,0 +1,585 @@
+use clap::{Args, Subcommand};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::commands::{CommandResult, Registered};
+use ralph_core::claude::Thinking;
+use ralph_core::git;
+use ralph_core::prd::{self, CompletedTask, Prd, PrdError, Priority, Task};
+
+/// Category given to tasks added without one
+const DEFAULT_CATEGORY: &str = "general";
+
+#[derive(Error, Debug)]
+pub enum TasksError {
+    #[error("{0}")]
+    Prd(#[from] PrdError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error in completed.json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("No task #{number}: the PRD has {count} task(s)")]
+    NoSuchTask { number: usize, count: usize },
+
+    #[error("Nothing to change: pass at least one field to edit")]
+    NothingToEdit,
+}
+
+/// Fields for `ralph tasks add`
+#[derive(Debug, Default)]
+pub struct NewTask {
+    pub description: String,
+    pub category: Option<String>,
+    pub steps: Vec<String>,
+    pub priority: Option<Priority>,
+    pub estimate: Option<String>,
+    pub depends_on: Vec<String>,
+    pub thinking: Option<Thinking>,
+    pub workdir: Option<String>,
+}
+
+impl NewTask {
+    /// The pending task these fields describe
+    pub fn into_task(self) -> Task {
+        Task {
+            category: self
+                .category
+                .unwrap_or_else(|| DEFAULT_CATEGORY.to_string()),
+            description: self.description,
+            steps: self.steps,
+            passes: false,
+            priority: self.priority,
+            estimate: self.estimate,
+            depends_on: self.depends_on,
+            thinking: self.thinking,
+            workdir: self.workdir,
+        }
+    }
+}
+
+/// Changes for `ralph tasks edit`; None (or empty) leaves a field as it is
+#[derive(Debug, Default)]
+pub struct TaskEdit {
+    pub description: Option<String>,
+    pub category: Option<String>,
+    /// Replaces every step
+    pub steps: Vec<String>,
+    /// Appended after the existing (or replaced) steps
+    pub add_steps: Vec<String>,
+    pub priority: Option<Priority>,
+    pub estimate: Option<String>,
+    /// Replaces every dependency
+    pub depends_on: Vec<String>,
+    pub thinking: Option<Thinking>,
+    pub workdir: Option<String>,
+    pub passes: Option<bool>,
+}
+
+impl TaskEdit {
+    fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.category.is_none()
+            && self.steps.is_empty()
+            && self.add_steps.is_empty()
+            && self.priority.is_none()
+            && self.estimate.is_none()
+            && self.depends_on.is_empty()
+            && self.thinking.is_none()
+            && self.workdir.is_none()
+            && self.passes.is_none()
+    }
+
+    fn apply(self, task: &mut Task) {
+        if let Some(description) = self.description {
+            task.description = description;
+        }
+        if let Some(category) = self.category {
+            task.category = category;
+        }
+        if !self.steps.is_empty() {
+            task.steps = self.steps;
+        }
+        task.steps.extend(self.add_steps);
+        if let Some(priority) = self.priority {
+            task.priority = Some(priority);
+        }
+        if let Some(estimate) = self.estimate {
+            task.estimate = Some(estimate);
+        }
+        if !self.depends_on.is_empty() {
+            task.depends_on = self.depends_on;
+        }
+        if let Some(thinking) = self.thinking {
+            task.thinking = Some(thinking);
+        }
+        if let Some(workdir) = self.workdir {
+            task.workdir = Some(workdir);
+        }
+        if let Some(passes) = self.passes {
+            task.passes = passes;
+        }
+    }
+}
+
+/// List, add, edit, remove or complete PRD tasks without hand-editing JSON
+#[derive(Args, Debug)]
+pub struct TasksArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json", global = true)]
+    pub prd_path: String,
+
+    #[command(subcommand)]
+    pub action: TasksAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TasksAction {
+    /// List the PRD's tasks with their steps
+    List {
+        /// Also list tasks already moved to completed.json
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Append a pending task
+    Add {
+        /// What the task is
+        description: String,
+
+        /// Task category [default: general]
+        #[arg(short, long)]
+        category: Option<String>,
+
+        /// A step (repeatable)
+        #[arg(short, long = "step", value_name = "STEP")]
+        steps: Vec<String>,
+
+        /// high, medium, low or a number (1 is the most urgent)
+        #[arg(long)]
+        priority: Option<Priority>,
+
+        /// Free-form size hint, e.g. 2h
+        #[arg(long)]
+        estimate: Option<String>,
+
+        /// A task that must pass first, by number or description (repeatable)
+        #[arg(long, value_name = "TASK")]
+        depends_on: Vec<String>,
+
+        /// Extended thinking for loops on this task: off, standard or extended
+        #[arg(long, value_enum, value_name = "LEVEL")]
+        thinking: Option<Thinking>,
+
+        /// Package directory the task belongs to, e.g. packages/api; Claude and the quality
+        /// gates run there for loops on it
+        #[arg(long, value_name = "DIR")]
+        workdir: Option<String>,
+    },
+
+    /// Change fields of a task
+    Edit {
+        /// Task number (1-indexed)
+        number: usize,
+
+        #[arg(short, long)]
+        description: Option<String>,
+
+        #[arg(short, long)]
+        category: Option<String>,
+
+        /// Replace all steps with these (repeatable)
+        #[arg(short, long = "step", value_name = "STEP")]
+        steps: Vec<String>,
+
+        /// Append a step (repeatable)
+        #[arg(long = "add-step", value_name = "STEP")]
+        add_steps: Vec<String>,
+
+        #[arg(long)]
+        priority: Option<Priority>,
+
+        #[arg(long)]
+        estimate: Option<String>,
+
+        /// Replace all dependencies with these (repeatable)
+        #[arg(long, value_name = "TASK")]
+        depends_on: Vec<String>,
+
+        #[arg(long, value_enum, value_name = "LEVEL")]
+        thinking: Option<Thinking>,
+
+        #[arg(long, value_name = "DIR")]
+        workdir: Option<String>,
+
+        /// Mark the task as passing (true) or pending (false)
+        #[arg(long)]
+        passes: Option<bool>,
+    },
+
+    /// Delete a task
+    Remove {
+        /// Task number (1-indexed)
+        number: usize,
+    },
+
+    /// Move a task to completed.json
+    Done {
+        /// Task number (1-indexed)
+        number: usize,
+    },
+}
+
+pub static COMMAND: Registered<TasksArgs> = Registered::new("tasks", run_args);
+
+fn run_args(args: TasksArgs) -> CommandResult {
+    match args.action {
+        TasksAction::List { all } => list(&args.prd_path, all),
+        TasksAction::Add {
+            description,
+            category,
+            steps,
+            priority,
+            estimate,
+            depends_on,
+            thinking,
+            workdir,
+        } => add(
+            &args.prd_path,
+            NewTask {
+                description,
+                category,
+                steps,
+                priority,
+                estimate,
+                depends_on,
+                thinking,
+                workdir,
+            },
+        ),
+        TasksAction::Edit {
+            number,
+            description,
+            category,
+            steps,
+            add_steps,
+            priority,
+            estimate,
+            depends_on,
+            thinking,
+            workdir,
+            passes,
+        } => edit(
+            &args.prd_path,
+            number,
+            TaskEdit {
+                description,
+                category,
+                steps,
+                add_steps,
+                priority,
+                estimate,
+                depends_on,
+                thinking,
+                workdir,
+                passes,
+            },
+        ),
+        TasksAction::Remove { number } => remove(&args.prd_path, number),
+        TasksAction::Done { number } => done(&args.prd_path, number),
+    }?;
+    Ok(())
+}
+
+/// `ralph tasks list` - the PRD's tasks, plus completed.json with `all`
+pub fn list(prd_path: &str, all: bool) -> Result<(), TasksError> {
+    let prd = prd::read_prd(prd_path)?;
+    let completed = read_completed(prd_path)?;
+    println!(
+        "{} ({}): {} pending, {} passing, {} completed",
+        prd.name,
+        prd_path,
+        prd.pending_count(),
+        prd.tasks.len() - prd.pending_count(),
+        completed.len()
+    );
+
+    let width = prd
+        .tasks
+        .iter()
+        .map(|t| t.category.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (i, task) in prd.tasks.iter().enumerate() {
+        let mark = if task.passes { "x" } else { " " };
+        println!(
+            "{:>3}. [{}] {:<width$}  {}{}",
+            i + 1,
+            mark,
+            task.category,
+            task.description,
+            details(task)
+        );
+        for (n, step) in task.steps.iter().enumerate() {
+            println!("{:>width$}     {}. {}", "", n + 1, step, width = width + 7);
+        }
+    }
+
+    if all && !completed.is_empty() {
+        println!("\nCompleted (completed.json):");
+        for task in &completed {
+            println!(
+                "  - [{}] {} ({})",
+                task.category, task.description, task.completed_at
+            );
+        }
+    }
+    Ok(())
+}
+
+/// "  (high priority, 2h, depends on 1, Auth API)" or "" when the task has none of these
+fn details(task: &Task) -> String {
+    let mut parts = Vec::new();
+    if let Some(priority) = task.priority {
+        parts.push(format!("{} priority", priority));
+    }
+    if let Some(estimate) = &task.estimate {
+        parts.push(estimate.clone());
+    }
+    if !task.depends_on.is_empty() {
+        parts.push(format!("depends on {}", task.depends_on.join(", ")));
+    }
+    if let Some(thinking) = task.thinking {
+        parts.push(format!("{} thinking", thinking.name()));
+    }
+    if let Some(workdir) = &task.workdir {
+        parts.push(format!("in {}", workdir));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("  ({})", parts.join(", "))
+    }
+}
+
+/// `ralph tasks add` - append a pending task
+pub fn add(prd_path: &str, new: NewTask) -> Result<(), TasksError> {
+    let mut prd = prd::read_prd(prd_path)?;
+    prd.tasks.push(new.into_task());
+    prd::write_prd(prd_path, &prd)?;
+    println!(
+        "Added task #{}: {}",
+        prd.tasks.len(),
+        prd.tasks[prd.tasks.len() - 1].description
+    );
+    Ok(())
+}
+
+/// `ralph tasks edit` - change fields of task `number` (1-indexed)
+pub fn edit(prd_path: &str, number: usize, edit: TaskEdit) -> Result<(), TasksError> {
+    if edit.is_empty() {
+        return Err(TasksError::NothingToEdit);
+    }
+    let mut prd = prd::read_prd(prd_path)?;
+    let index = index_of(&prd, number)?;
+    edit.apply(&mut prd.tasks[index]);
+    prd::write_prd(prd_path, &prd)?;
+    println!("Updated task #{}: {}", number, prd.tasks[index].description);
+    Ok(())
+}
+
+/// `ralph tasks remove` - delete task `number` (1-indexed)
+pub fn remove(prd_path: &str, number: usize) -> Result<(), TasksError> {
+    let mut prd = prd::read_prd(prd_path)?;
+    let task = take_task(&mut prd, number)?;
+    prd::write_prd(prd_path, &prd)?;
+    println!("Removed task #{}: {}", number, task.description);
+    Ok(())
+}
+
+/// `ralph tasks done` - move task `number` (1-indexed) to completed.json
+pub fn done(prd_path: &str, number: usize) -> Result<(), TasksError> {
+    let mut prd = prd::read_prd(prd_path)?;
+    let task = take_task(&mut prd, number)?;
+
+    let mut completed = read_completed(prd_path)?;
+    if !completed.iter().any(|c| c.description == task.description) {
+        completed.push(CompletedTask {
+            category: task.category,
+            description: task.description.clone(),
+            steps: task.steps,
+            completed_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            commit: git::head_commit(Path::new(".")).ok().flatten(),
+        });
+    }
+    std::fs::write(
+        prd::completed_path_for(prd_path),
+        serde_json::to_string_pretty(&completed)?,
+    )?;
+    prd::write_prd(prd_path, &prd)?;
+    println!(
+        "Moved task #{} to completed.json: {}",
+        number, task.description
+    );
+    Ok(())
+}
+
+/// 0-based index of task `number` (1-indexed), or an error naming how many tasks there are
+pub fn index_of(prd: &Prd, number: usize) -> Result<usize, TasksError> {
+    if (1..=prd.tasks.len()).contains(&number) {
+        Ok(number - 1)
+    } else {
+        Err(TasksError::NoSuchTask {
+            number,
+            count: prd.tasks.len(),
+        })
+    }
+}
+
+/// Remove task `number` and fix up numeric `depends_on` references: later tasks move up
+/// one, and references to the removed task are dropped
+fn take_task(prd: &mut Prd, number: usize) -> Result<Task, TasksError> {
+    let index = index_of(prd, number)?;
+    let task = prd.tasks.remove(index);
+    for other in &mut prd.tasks {
+        other.depends_on.retain_mut(|reference| {
+            let Ok(n) = reference.trim().trim_start_matches('#').parse::<usize>() else {
+                return *reference != task.description;
+            };
+            if n == number {
+                return false;
+            }
+            if n > number {
+                *reference = (n - 1).to_string();
+            }
+            true
+        });
+    }
+    Ok(task)
+}
+
+fn read_completed(prd_path: &str) -> Result<Vec<CompletedTask>, TasksError> {
+    let path = prd::completed_path_for(prd_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_prd(dir: &TempDir) -> String {
+        let path = dir.path().join("prd.json");
+        std::fs::write(
+            &path,
+            r##"{"schema_version": 1, "name": "Test", "quality_gates": ["cargo test"], "tasks": [
+                {"category": "feature", "description": "Auth API", "steps": [], "passes": false},
+                {"category": "feature", "description": "Login page", "steps": ["Form"], "passes": false, "depends_on": ["1"]},
+                {"category": "test", "description": "E2E", "steps": [], "passes": false, "depends_on": ["#3", "Login page"]}
+            ]}"##,
+        )
+        .unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn add_appends_pending_task() {
+        let dir = TempDir::new().unwrap();
+        let path = write_prd(&dir);
+        add(
+            &path,
+            NewTask {
+                description: "Docs".to_string(),
+                steps: vec!["README".to_string()],
+                priority: Some(Priority::High),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let prd = prd::read_prd(&path).unwrap();
+        assert_eq!(prd.tasks.len(), 4);
+        assert_eq!(prd.tasks[3].category, DEFAULT_CATEGORY);
+        assert_eq!(prd.tasks[3].priority, Some(Priority::High));
+        assert!(!prd.tasks[3].passes);
+        assert_eq!(prd.quality_gates, vec!["cargo test"]);
+    }
+
+    #[test]
+    fn edit_changes_only_given_fields() {
+        let dir = TempDir::new().unwrap();
+        let path = write_prd(&dir);
+        edit(
+            &path,
+            2,
+            TaskEdit {
+                add_steps: vec!["Validation".to_string()],
+                thinking: Some(Thinking::Extended),
+                workdir: Some("packages/web".to_string()),
+                passes: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let prd = prd::read_prd(&path).unwrap();
+        assert_eq!(prd.tasks[1].steps, vec!["Form", "Validation"]);
+        assert!(prd.tasks[1].passes);
+        assert_eq!(prd.tasks[1].thinking, Some(Thinking::Extended));
+        assert_eq!(prd.tasks[1].workdir.as_deref(), Some("packages/web"));
+        assert_eq!(prd.tasks[1].description, "Login page");
+
+        assert!(matches!(
+            edit(&path, 2, TaskEdit::default()),
+            Err(TasksError::NothingToEdit)
+        ));
+        assert!(matches!(
+            edit(
+                &path,
+                9,
+                TaskEdit {
+                    passes: Some(false),
+                    ..Default::default()
+                }
+            ),
+            Err(TasksError::NoSuchTask {
+                number: 9,
+                count: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn remove_renumbers_dependencies() {
+        let dir = TempDir::new().unwrap();
+        let path = write_prd(&dir);
+        remove(&path, 1).unwrap();
+
+        let prd = prd::read_prd(&path).unwrap();
+        assert_eq!(prd.tasks.len(), 2);
+        assert!(prd.tasks[0].depends_on.is_empty());
+        assert_eq!(prd.tasks[1].depends_on, vec!["2", "Login page"]);
+    }
+
+    #[test]
+    fn done_moves_task_to_completed() {
+        let dir = TempDir::new().unwrap();
+        let path = write_prd(&dir);
+        done(&path, 2).unwrap();
+
+        let prd = prd::read_prd(&path).unwrap();
+        assert_eq!(prd.tasks.len(), 2);
+        // "Login page" is done, so the reference by description goes; #3 moved up to #2
+        assert_eq!(prd.tasks[1].depends_on, vec!["2"]);
+
+        let completed = read_completed(&path).unwrap();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].description, "Login page");
+        assert_eq!(completed[0].steps, vec!["Form"]);
+    }
+}
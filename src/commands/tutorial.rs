@@ -0,0 +1,525 @@
+//! `ralph tutorial` - a scripted walk through plan mode and two build loops.
+//!
+//! Every agent response is canned, so nothing calls Claude or costs money. The PRD, run
+//! state and stats are written for real into a sandbox directory, so `ralph view`,
+//! `ralph stats` and `ralph tasks` can be tried on the result afterwards.
+
+use clap::Args;
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::app::App;
+use crate::commands::plan::{PlanError, collect_answers, collect_idea};
+use crate::commands::{CommandResult, Registered};
+use crate::config::{self, ConfigError};
+use crate::plan::app::PlanApp;
+use crate::theme::Theme;
+use crate::tui;
+use ralph_core::claude::Usage;
+use ralph_core::plan::protocol::PlanResponse;
+use ralph_core::prd::{self, PrdError};
+use ralph_core::run_state::{RunState, RunStateError};
+use ralph_core::stats::{IterationRecord, Stats, StatsError};
+use ralph_core::toc;
+
+/// Label the tutorial's run is saved under
+const RUN_LABEL: &str = "tutorial";
+
+/// Canned plan-mode turn: Claude has explored and asks two questions
+const CANNED_QUESTIONS: &str = r#"{
+  "phase": "asking",
+  "status": "Tutorial: answer Claude's questions",
+  "questions": [
+    {
+      "id": "storage",
+      "category": "technical",
+      "text": "Where should the todo items be stored?",
+      "context": "Pick an option with Up/Down and press Enter.",
+      "options": [
+        {"key": "A", "label": "A JSON file", "description": "Simple and human-readable"},
+        {"key": "B", "label": "SQLite", "description": "Handles large lists well"}
+      ],
+      "allow_freeform": true
+    },
+    {
+      "id": "scope",
+      "category": "scope",
+      "text": "Anything the first version must not include?",
+      "context": "Press i to type a free-form answer, Enter to save it, then Enter again to send.",
+      "allow_freeform": true
+    }
+  ]
+}"#;
+
+/// Canned final plan-mode turn: the PRD
+const CANNED_PRD: &str = r#"{
+  "phase": "complete",
+  "status": "PRD ready",
+  "prd": {
+    "name": "Tutorial Todo CLI",
+    "quality_gates": ["cargo test"],
+    "tasks": [
+      {"category": "feature", "description": "Add and list todo items", "steps": ["todo add <text> saves an item", "todo list prints every item"], "passes": false},
+      {"category": "feature", "description": "Mark items as done", "steps": ["todo done <n> marks item n", "todo list shows done items struck through"], "passes": false},
+      {"category": "docs", "description": "Document the commands in the README", "steps": ["Usage section with an example per command"], "passes": false}
+    ]
+  }
+}"#;
+
+/// Canned build loops, each finishing the first pending task: summary, cost (USD) and
+/// duration (seconds)
+const CANNED_LOOPS: [(&str, f64, f64); 2] = [
+    (
+        "Added `todo add` and `todo list` backed by todos.json, with tests for both.",
+        0.42,
+        312.0,
+    ),
+    (
+        "Added `todo done <n>`; done items are listed struck through. Tests cover both.",
+        0.31,
+        244.0,
+    ),
+];
+
+/// How long each simulated Claude call spins
+const SIMULATED_WORK: Duration = Duration::from_secs(3);
+
+#[derive(Error, Debug)]
+pub enum TutorialError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Plan(#[from] PlanError),
+
+    #[error("{0}")]
+    Prd(#[from] PrdError),
+
+    #[error("{0}")]
+    RunState(#[from] RunStateError),
+
+    #[error("{0}")]
+    Stats(#[from] StatsError),
+
+    #[error("{0} already exists. Use --force to reuse it, or pick another --dir.")]
+    DirExists(String),
+
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+}
+
+/// Learn ralph hands-on with a scripted session (no Claude calls, no cost)
+#[derive(Args, Debug)]
+pub struct TutorialArgs {
+    /// Sandbox directory for the tutorial's PRD and run files
+    #[arg(long, default_value = "ralph-tutorial")]
+    pub dir: String,
+
+    /// Reuse the sandbox directory if it already exists
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+pub static COMMAND: Registered<TutorialArgs> = Registered::new("tutorial", run_args);
+
+fn run_args(args: TutorialArgs) -> CommandResult {
+    Ok(run(&args.dir, args.force)?)
+}
+
+/// Something the tutorial can draw and animate while it waits
+trait Screen {
+    fn render(&mut self, frame: &mut Frame);
+    fn tick(&mut self);
+}
+
+impl Screen for App {
+    fn render(&mut self, frame: &mut Frame) {
+        self.draw(frame);
+    }
+
+    fn tick(&mut self) {
+        self.advance_spinner();
+    }
+}
+
+impl Screen for PlanApp {
+    fn render(&mut self, frame: &mut Frame) {
+        self.draw(frame);
+    }
+
+    fn tick(&mut self) {
+        self.advance_spinner();
+    }
+}
+
+/// Run the tutorial in `dir`
+pub fn run(dir: &str, force: bool) -> Result<(), TutorialError> {
+    let theme = config::load_theme(Path::new("."))?;
+    let prd_path = prepare_sandbox(Path::new(dir), force)?;
+    let prd_path = prd_path.to_string_lossy().into_owned();
+
+    let mut terminal = tui::init_terminal()?;
+    let planned = plan_phase(&mut terminal, &prd_path, theme);
+    let built = match planned {
+        Ok(true) => build_phase(&mut terminal, &prd_path, theme),
+        other => other,
+    };
+    drop(terminal);
+
+    match built? {
+        true => print_report(&prd_path),
+        false => println!(
+            "Tutorial stopped. Run `ralph tutorial --dir {} --force` to start again.",
+            dir
+        ),
+    }
+    Ok(())
+}
+
+/// Create the sandbox and return where its PRD goes
+fn prepare_sandbox(dir: &Path, force: bool) -> Result<PathBuf, TutorialError> {
+    if dir.exists() && !force {
+        return Err(TutorialError::DirExists(dir.display().to_string()));
+    }
+    let plans = dir.join("plans");
+    std::fs::create_dir_all(&plans)?;
+    // A rerun starts from a clean slate
+    let _ = std::fs::remove_file(plans.join("completed.json"));
+    let _ = std::fs::remove_file(Stats::file_path(&plans.join("prd.json").to_string_lossy()));
+    Ok(plans.join("prd.json"))
+}
+
+/// Plan mode: idea, canned questions, canned PRD. False if the user quit.
+fn plan_phase(
+    terminal: &mut DefaultTerminal,
+    prd_path: &str,
+    theme: Theme,
+) -> Result<bool, TutorialError> {
+    let mut app = PlanApp::new();
+    app.theme = theme;
+    app.push_log(
+        "Tutorial, step 1 of 3: plan mode.\n\n\
+`ralph plan` asks Claude to explore your codebase and interview you, then writes a PRD: \
+a JSON list of tasks with steps and quality gates. In this tutorial Claude's answers are \
+canned, so anything you type works."
+            .to_string(),
+    );
+
+    collect_idea(terminal, &mut app)?;
+    if app.should_quit {
+        return Ok(false);
+    }
+
+    app.set_processing(true, "Claude is exploring the codebase (simulated)...");
+    if !simulate(terminal, &mut app, SIMULATED_WORK)? {
+        return Ok(false);
+    }
+    app.set_processing(false, "");
+
+    let questions: PlanResponse = serde_json::from_str(CANNED_QUESTIONS)?;
+    app.update_from_response(&questions);
+    app.set_questions(questions.questions.unwrap_or_default());
+    collect_answers(terminal, &mut app, None, None)?;
+    if app.should_quit || !app.should_submit {
+        return Ok(false);
+    }
+
+    app.set_processing(true, "Claude is writing the PRD (simulated)...");
+    if !simulate(terminal, &mut app, SIMULATED_WORK)? {
+        return Ok(false);
+    }
+    app.set_processing(false, "");
+
+    let complete: PlanResponse = serde_json::from_str(CANNED_PRD)?;
+    app.update_from_response(&complete);
+    let prd_json = serde_json::to_string_pretty(&complete.prd)?;
+    std::fs::write(prd_path, &prd_json)?;
+    app.push_log(format!(
+        "PRD written to {}\n\n```json\n{}\n```",
+        prd_path, prd_json
+    ));
+    app.status = format!("PRD written to {}. Press Enter to start building", prd_path);
+    Ok(wait_for(terminal, &mut app, |code| code == KeyCode::Enter)?.is_some())
+}
+
+/// Two build loops on the canned PRD, with a lesson between each. False if the user quit.
+fn build_phase(
+    terminal: &mut DefaultTerminal,
+    prd_path: &str,
+    theme: Theme,
+) -> Result<bool, TutorialError> {
+    let prd = prd::read_prd(prd_path)?;
+    let mut run_state = RunState::start(prd_path);
+    run_state.set_label(Some(RUN_LABEL));
+    run_state.save()?;
+
+    let mut app = App::new(&prd.name, prd.tasks.len(), 0);
+    app.theme = theme;
+    app.label = Some(RUN_LABEL.to_string());
+    app.toc.set_entries(toc::build(&prd));
+    app.push_log(
+        "Tutorial, step 2 of 3: build mode.\n\n\
+`ralph build` runs Claude in a loop. Each loop picks the next pending task, implements it, \
+runs the quality gates and commits. Ralph then moves passing tasks from prd.json to \
+completed.json. The header shows progress, and this pane shows each loop's log. \
+Follow the instructions in the status line."
+            .to_string(),
+    );
+
+    app.set_status("Press t to open the task list, in the order ralph will work through it");
+    if wait_for(terminal, &mut app, |c| c == KeyCode::Char('t'))?.is_none() {
+        return Ok(false);
+    }
+    app.toc.visible = true;
+    app.set_status("Press t or Esc to close the task list");
+    if wait_for(terminal, &mut app, |c| {
+        matches!(c, KeyCode::Char('t') | KeyCode::Esc)
+    })?
+    .is_none()
+    {
+        return Ok(false);
+    }
+    app.toc.visible = false;
+
+    for (i, &(summary, cost_usd, duration_secs)) in CANNED_LOOPS.iter().enumerate() {
+        let Some(task) = simulate_loop(terminal, &mut app, prd_path, summary)? else {
+            return Ok(false);
+        };
+        app.record_usage(cost_usd, &Usage::default());
+        Stats::append(
+            prd_path,
+            IterationRecord {
+                run_id: run_state.id.clone(),
+                run_label: Some(RUN_LABEL.to_string()),
+                loop_number: app.loop_count,
+                task_number: Some(1),
+                task: Some(task.description.clone()),
+                category: Some(task.category),
+                model: Some("sonnet".to_string()),
+                status: "completed".to_string(),
+                summary: Some(summary.to_string()),
+                commit: None,
+                duration_secs,
+                cost_usd,
+                finished_at: chrono::Utc::now(),
+                rating: None,
+                rating_note: None,
+                escalation: None,
+                thinking: None,
+            },
+        )?;
+        app.last_finished_loop = Some(app.loop_count);
+
+        if i == 0 && !rating_lesson(terminal, &mut app, prd_path, &run_state)? {
+            return Ok(false);
+        }
+    }
+
+    app.set_status("Press Left to go back to loop #1's log (Right moves forward again)");
+    if wait_for(terminal, &mut app, |c| c == KeyCode::Left)?.is_none() {
+        return Ok(false);
+    }
+    app.prev_log();
+    app.set_status(
+        "Press q to finish. In a real run, q lets the current loop finish first; r cancels it",
+    );
+    Ok(wait_for(terminal, &mut app, |c| {
+        matches!(c, KeyCode::Char('q' | 'Q'))
+    })?
+    .is_some())
+}
+
+/// One simulated loop: spin, then log the canned result and archive the first pending
+/// task. Returns that task, or None if the user quit.
+fn simulate_loop(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    prd_path: &str,
+    summary: &str,
+) -> Result<Option<prd::Task>, TutorialError> {
+    let prd = prd::read_prd(prd_path)?;
+    let (index, task) = prd
+        .tasks
+        .into_iter()
+        .enumerate()
+        .find(|(_, t)| !t.passes)
+        .expect("the canned PRD has a task for every canned loop");
+    app.increment_loop();
+    app.start_loop_timer();
+    app.set_status("Waiting for Claude... (simulated)");
+    if !simulate(terminal, app, SIMULATED_WORK)? {
+        return Ok(None);
+    }
+
+    app.push_log(format!(
+        "Task #{}: \nStatus: completed\nSummary: {}\n\n## Quality Gates\n- PASS `cargo test` (simulated)",
+        index + 1,
+        summary
+    ));
+    prd::set_task_passes(prd_path, &task.description, true)?;
+    let completed_at = chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    let archived = prd::archive_passing_tasks(prd_path, &completed_at, None)?;
+    app.push_log(format!(
+        "Archived {} passing task(s) to completed.json",
+        archived
+    ));
+
+    let prd = prd::read_prd(prd_path)?;
+    let completed = prd::load_completed_tasks_from_file(prd_path)?.map_or(0, |t| t.len());
+    app.reload_progress(prd.tasks.len(), completed);
+    app.toc.set_entries(toc::build(&prd));
+    Ok(Some(task))
+}
+
+/// Have the user rate the loop that just finished, note included
+fn rating_lesson(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    prd_path: &str,
+    run_state: &RunState,
+) -> Result<bool, TutorialError> {
+    app.set_status("Press s to rate the loop's work");
+    if wait_for(terminal, app, |c| c == KeyCode::Char('s'))?.is_none() {
+        return Ok(false);
+    }
+    app.ask_rating();
+    app.set_status("Now pick a score from 1 to 5 (5 is best)");
+    let Some(KeyCode::Char(c)) =
+        wait_for(terminal, app, |c| matches!(c, KeyCode::Char('1'..='5')))?
+    else {
+        return Ok(false);
+    };
+    app.start_rating(c as u8 - b'0');
+
+    while app.rating_input.is_some() {
+        let Some(code) = wait_for(terminal, app, |_| true)? else {
+            return Ok(false);
+        };
+        if let Some(input) = app.rating_input.as_mut() {
+            match code {
+                KeyCode::Char(c) => input.note.push(c),
+                KeyCode::Backspace => {
+                    input.note.pop();
+                }
+                KeyCode::Enter => app.finish_rating(true),
+                KeyCode::Esc => app.finish_rating(false),
+                _ => {}
+            }
+        }
+    }
+    for rating in std::mem::take(&mut app.finished_ratings) {
+        let note = (!rating.note.is_empty()).then_some(rating.note);
+        Stats::rate(
+            prd_path,
+            &run_state.id,
+            rating.loop_number,
+            rating.rating,
+            note,
+        )?;
+    }
+    app.push_log(
+        "Ratings are saved with the loop's stats. `ralph stats --detailed` averages them, and \
+`ralph build --rating-feedback` passes your notes on to later loops."
+            .to_string(),
+    );
+    Ok(true)
+}
+
+/// Animate `screen` for `duration`; false if the user pressed Ctrl+C
+fn simulate(
+    terminal: &mut DefaultTerminal,
+    screen: &mut impl Screen,
+    duration: Duration,
+) -> io::Result<bool> {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        terminal.draw(|f| screen.render(f))?;
+        screen.tick();
+        if let Some(key) = tui::poll_key_press(Duration::from_millis(100))?
+            && key.code == KeyCode::Char('c')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Wait for a key `accept` takes and return it; None if the user pressed Ctrl+C
+fn wait_for(
+    terminal: &mut DefaultTerminal,
+    screen: &mut impl Screen,
+    accept: impl Fn(KeyCode) -> bool,
+) -> io::Result<Option<KeyCode>> {
+    loop {
+        terminal.draw(|f| screen.render(f))?;
+        screen.tick();
+        if let Some(key) = tui::poll_key_press(Duration::from_millis(100))? {
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(None);
+            }
+            if accept(key.code) {
+                return Ok(Some(key.code));
+            }
+        }
+    }
+}
+
+/// Step 3: explain what the session left behind
+fn print_report(prd_path: &str) {
+    let dir = Path::new(prd_path).parent().unwrap_or(Path::new("."));
+    println!("\n═══════════════════════════════════════════════════════════════");
+    println!("Tutorial, step 3 of 3: what the session left behind");
+    println!("\nA real `ralph build` ends with a report like this one: loops run, cost,");
+    println!("final status and Claude's last output. The files it works from:\n");
+    println!("  {}", prd_path);
+    println!("      The PRD: tasks still to do, with their steps and quality gates");
+    println!("  {}", prd::completed_path_for(prd_path).display());
+    println!("      Finished tasks, archived by ralph with a timestamp and commit");
+    println!("  {}", dir.join(".ralph-run.json").display());
+    println!("      The current run's state: id, label, branch");
+    println!("  {}", Stats::file_path(prd_path).display());
+    println!("      Duration, cost and rating of every loop");
+    println!("\nTry them on the tutorial's files:\n");
+    println!("  ralph view -p {}", prd_path);
+    println!("  ralph stats -p {} --detailed", prd_path);
+    println!("  ralph tasks -p {} list --all", prd_path);
+    println!("\nWhen you're ready for the real thing: `ralph plan`, then `ralph build`.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn canned_responses_parse() {
+        let questions: PlanResponse = serde_json::from_str(CANNED_QUESTIONS).unwrap();
+        assert_eq!(questions.questions.unwrap().len(), 2);
+
+        let complete: PlanResponse = serde_json::from_str(CANNED_PRD).unwrap();
+        let prd: prd::Prd =
+            serde_json::from_str(&serde_json::to_string(&complete.prd.unwrap()).unwrap()).unwrap();
+        assert!(CANNED_LOOPS.len() <= prd.tasks.len());
+    }
+
+    #[test]
+    fn prepare_sandbox_refuses_existing_dir_without_force() {
+        let dir = TempDir::new().unwrap();
+        assert!(matches!(
+            prepare_sandbox(dir.path(), false),
+            Err(TutorialError::DirExists(_))
+        ));
+
+        let prd_path = prepare_sandbox(dir.path(), true).unwrap();
+        assert_eq!(prd_path, dir.path().join("plans").join("prd.json"));
+        assert!(dir.path().join("plans").is_dir());
+    }
+}
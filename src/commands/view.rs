@@ -0,0 +1,568 @@
+use clap::Args;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::app::{App, Tab, format_secs};
+use crate::commands::{CommandResult, Registered};
+use crate::config::{self, ConfigError};
+use crate::tui;
+use ralph_core::git;
+use ralph_core::loop_log::{LoopLog, LoopLogError};
+use ralph_core::plan::session::{PlanSession, SessionError};
+use ralph_core::prd::{self, CompletedTask, Prd, PrdError};
+use ralph_core::progress::{self, JournalEntry};
+use ralph_core::run_state::{RunState, RunStateError};
+use ralph_core::stats::{Stats, StatsError};
+use ralph_core::toc;
+
+/// Progress log the build loop asks Claude to maintain (relative to the working directory)
+pub const PROGRESS_FILE: &str = "progress.txt";
+
+#[derive(Error, Debug)]
+pub enum ViewError {
+    #[error("Session error: {0}")]
+    Session(#[from] SessionError),
+
+    #[error("Run state error: {0}")]
+    RunState(#[from] RunStateError),
+
+    #[error("{0}")]
+    LoopLog(#[from] LoopLogError),
+
+    #[error("{0}")]
+    Stats(#[from] StatsError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Prd(#[from] PrdError),
+
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+}
+
+/// Browse an existing PRD, completed tasks, progress log and plan session (read-only)
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    /// Path to the PRD JSON file
+    #[arg(short, long, default_value = "plans/prd.json")]
+    pub prd_path: String,
+}
+
+pub static COMMAND: Registered<ViewArgs> = Registered::new("view", run_args);
+
+fn run_args(args: ViewArgs) -> CommandResult {
+    Ok(run(&args.prd_path)?)
+}
+
+/// Run the view command - read-only inspection of an existing run's files
+pub fn run(prd_path: &str) -> Result<(), ViewError> {
+    let prd = prd::read_prd(prd_path)?;
+    let completed = prd::load_completed_tasks_from_file(prd_path)?.unwrap_or_default();
+    let pages = collect_pages(prd_path, &prd, &completed, Path::new("."))?;
+    let config = config::load(Path::new("."))?;
+    let theme = config.theme.resolve().map_err(ConfigError::from)?;
+
+    let mut terminal = tui::init_terminal()?;
+    let mut app = App::new(&prd.name, prd.tasks.len(), completed.len());
+    app.read_only = true;
+    app.theme = theme;
+    app.vim_keys = config.tui.vim_keys;
+    app.toc.set_entries(toc::build(&prd));
+    for page in pages {
+        app.push_log(page);
+    }
+    // Start on the first page rather than the last pushed one
+    app.current_log_index = 0;
+    app.set_status(&format!("Read-only view of {}", prd_path));
+
+    loop {
+        terminal.draw(|f| app.draw(f))?;
+
+        if let Some(key) = tui::poll_key_press(Duration::from_millis(100))? {
+            if app.show_help && !key.modifiers.contains(KeyModifiers::CONTROL) {
+                app.show_help = false;
+                continue;
+            }
+            let key = if app.toc.searching {
+                key
+            } else {
+                app.vim_key(key)
+            };
+            if app.tab == Tab::Logs
+                && app.toc.visible
+                && !key.modifiers.contains(KeyModifiers::CONTROL)
+                && app.toc.handle_key(key.code)
+            {
+                continue;
+            }
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('c'), m) if m.contains(KeyModifiers::CONTROL) => break,
+                (KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc, _) => break,
+                (KeyCode::Char('t') | KeyCode::Char('T'), _) => {
+                    app.tab = Tab::Logs;
+                    app.toc.visible = true;
+                }
+                (KeyCode::Char(c @ '1'..='4'), _) => app.tab = Tab::from_key(c).unwrap_or_default(),
+                (KeyCode::Char('?'), _) => app.show_help = true,
+                (KeyCode::Left, _) => app.prev_log(),
+                (KeyCode::Right, _) => app.next_log(),
+                (KeyCode::Up, _) => app.scroll_up(1),
+                (KeyCode::Down, _) => app.scroll_down(1),
+                (KeyCode::PageUp, _) => app.scroll_up(10),
+                (KeyCode::PageDown, _) => app.scroll_down(10),
+                (KeyCode::Home, _) => app.scroll_to_top(),
+                (KeyCode::End, _) => app.scroll_to_bottom(),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Gather every page available for the given PRD, skipping files that don't exist.
+/// `work_dir` is where the build ran, holding the progress log and journal.
+fn collect_pages(
+    prd_path: &str,
+    prd: &Prd,
+    completed: &[CompletedTask],
+    work_dir: &Path,
+) -> Result<Vec<String>, ViewError> {
+    let mut pages = vec![format_prd_page(prd, completed.len())];
+
+    if !completed.is_empty() {
+        pages.push(format_completed_page(completed));
+    }
+
+    if let Some(run) = RunState::load(prd_path)? {
+        pages.push(format_run_page(&run));
+        let journal = progress::recent_journal(work_dir, usize::MAX)?;
+        for loop_number in 1..=run.loop_count {
+            if let Some(log) = LoopLog::load(prd_path, &run.id, loop_number)? {
+                pages.push(format_loop_page(&log));
+            }
+            if let Some(diff) = loop_diff(work_dir, &journal, &run.id, loop_number) {
+                pages.push(diff);
+            }
+        }
+    }
+
+    let stats = Stats::load(prd_path)?;
+    if stats.overall().is_some() {
+        pages.push(format_stats_page(&stats));
+    }
+
+    let progress_file = work_dir.join(PROGRESS_FILE);
+    if progress_file.exists() {
+        let progress = std::fs::read_to_string(progress_file)?;
+        pages.push(format!("# Progress Log\n\n{}", progress.trim_end()));
+    }
+
+    if let Some(session) = PlanSession::load(prd_path)? {
+        pages.push(format_session_page(&session));
+    }
+
+    Ok(pages)
+}
+
+/// Render the PRD's tasks and quality gates as a Markdown-ish page
+fn format_prd_page(prd: &Prd, completed_count: usize) -> String {
+    let passing = prd.tasks.iter().filter(|t| t.passes).count();
+    let mut page = format!(
+        "# PRD: {}\n\nTasks: {} in PRD ({} passing), {} archived in completed.json\n\n## Quality Gates\n",
+        prd.name,
+        prd.tasks.len(),
+        passing,
+        completed_count
+    );
+
+    if prd.quality_gates.is_empty() {
+        page.push_str("(none)\n");
+    }
+    for gate in &prd.quality_gates {
+        page.push_str(&format!("- `{}`\n", gate));
+    }
+
+    if !prd.definition_of_done.is_empty() {
+        page.push_str("\n## Definition of Done\n");
+        for item in &prd.definition_of_done {
+            page.push_str(&format!("- {}\n", item));
+        }
+    }
+
+    page.push_str("\n## Tasks\n");
+    if prd.tasks.is_empty() {
+        page.push_str("(none)\n");
+    }
+    for (i, task) in prd.tasks.iter().enumerate() {
+        let mark = if task.passes { "x" } else { " " };
+        let priority = task
+            .priority
+            .map(|p| format!(", {} priority", p))
+            .unwrap_or_default();
+        page.push_str(&format!(
+            "- [{}] #{} ({}{}) {}\n",
+            mark,
+            i + 1,
+            task.category,
+            priority,
+            task.description
+        ));
+        for step in &task.steps {
+            page.push_str(&format!("  - {}\n", step));
+        }
+    }
+
+    page
+}
+
+/// Render archived tasks from completed.json
+fn format_completed_page(completed: &[CompletedTask]) -> String {
+    let mut page = format!("# Completed Tasks ({})\n\n", completed.len());
+    for task in completed {
+        page.push_str(&format!(
+            "- {} ({}) {}\n",
+            task.completed_at, task.category, task.description
+        ));
+    }
+    page
+}
+
+/// Render the state of the last build run
+fn format_run_page(run: &RunState) -> String {
+    format!(
+        "# Last Build Run\n\n- ID: `{}`\n- Label: {}\n- Branch: {}\n- Loops: {}\n- Started: {}\n- Updated: {}\n",
+        run.id,
+        run.label.as_deref().unwrap_or("(none)"),
+        run.branch.as_deref().unwrap_or("(current branch)"),
+        run.loop_count,
+        run.started_at.format("%Y-%m-%d %H:%M UTC"),
+        run.updated_at.format("%Y-%m-%d %H:%M UTC")
+    )
+}
+
+/// Render what the TUI logged during one loop of the last run
+fn format_loop_page(log: &LoopLog) -> String {
+    let mut page = format!(
+        "# Loop {}: {}\n\nFinished {}\n",
+        log.loop_number,
+        log.status,
+        log.finished_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    for entry in &log.log {
+        page.push_str(&format!("\n{}\n", entry.trim_end()));
+    }
+    page
+}
+
+/// The changes `loop_number` of `run_id` committed, going by the journal: from the commit
+/// the journal last recorded before it to the loop's own. None if the loop made no commit
+/// or git no longer has it.
+fn loop_diff(
+    work_dir: &Path,
+    journal: &[JournalEntry],
+    run_id: &str,
+    loop_number: u64,
+) -> Option<String> {
+    let index = journal
+        .iter()
+        .rposition(|e| e.run_id == run_id && e.loop_number == loop_number)?;
+    let commit = journal[index].commit.as_deref()?;
+    let base = journal[..index]
+        .iter()
+        .rev()
+        .find_map(|e| e.commit.as_deref())
+        .filter(|base| *base != commit);
+    let diff = git::diff_between(work_dir, base, commit).ok()?;
+    Some(format!(
+        "# Loop {} Diff\n\nCommit `{}`\n\n```diff\n{}\n```",
+        loop_number,
+        commit.get(..7).unwrap_or(commit),
+        diff
+    ))
+}
+
+/// Render the duration and cost stats of the PRD's recorded loops
+fn format_stats_page(stats: &Stats) -> String {
+    let mut page = String::from("# Stats\n\n");
+    if let Some(overall) = stats.overall() {
+        page.push_str(&format!(
+            "- Iterations: {}, ${:.2} total\n- Duration: p50 {}, p90 {} (mean {})\n- Cost per iteration: p50 ${:.2}, p90 ${:.2}\n",
+            overall.count,
+            overall.cost_total,
+            format_secs(overall.duration_p50),
+            format_secs(overall.duration_p90),
+            format_secs(overall.duration_mean),
+            overall.cost_p50,
+            overall.cost_p90
+        ));
+        if let Some(mean) = overall.rating_mean {
+            page.push_str(&format!(
+                "- Rating: {:.1}/5 from {} rated iterations\n",
+                mean, overall.rated
+            ));
+        }
+    }
+
+    page.push_str("\n## By Category\n\n| category | iters | p50 time | p90 time | total |\n|---|---|---|---|---|\n");
+    for (category, summary) in stats.by_category() {
+        page.push_str(&format!(
+            "| {} | {} | {} | {} | ${:.2} |\n",
+            category,
+            summary.count,
+            format_secs(summary.duration_p50),
+            format_secs(summary.duration_p90),
+            summary.cost_total
+        ));
+    }
+    page
+}
+
+/// Render a summary of an in-progress plan session
+fn format_session_page(session: &PlanSession) -> String {
+    let mut page = format!(
+        "# Plan Session\n\n- ID: `{}`\n- Turns: {}\n- Last phase: {}\n- Updated: {}\n",
+        session.id,
+        session.turn_count,
+        session.last_phase,
+        session.updated_at.format("%Y-%m-%d %H:%M UTC")
+    );
+
+    if !session.answers.is_empty() {
+        page.push_str("\n## Answers\n");
+        for answer in &session.answers {
+            page.push_str(&format!("- {}: {}\n", answer.question_id, answer.value));
+        }
+    }
+
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ralph_core::plan::protocol::Answer;
+    use ralph_core::prd::Task;
+
+    fn test_prd() -> Prd {
+        Prd {
+            schema_version: prd::SCHEMA_VERSION,
+            name: "Test PRD".to_string(),
+            quality_gates: vec!["cargo test".to_string()],
+            workdir: None,
+            definition_of_done: vec!["CHANGELOG updated".to_string()],
+            coverage: None,
+            tasks: vec![
+                Task {
+                    category: "feature".to_string(),
+                    description: "Add login".to_string(),
+                    steps: vec!["Create form".to_string()],
+                    passes: false,
+                    priority: Some(ralph_core::prd::Priority::High),
+                    estimate: None,
+                    depends_on: vec![],
+                    thinking: None,
+                    workdir: None,
+                },
+                Task {
+                    category: "test".to_string(),
+                    description: "Add tests".to_string(),
+                    steps: vec![],
+                    passes: true,
+                    priority: None,
+                    estimate: None,
+                    depends_on: vec![],
+                    thinking: None,
+                    workdir: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn prd_page_lists_tasks_and_gates() {
+        let page = format_prd_page(&test_prd(), 3);
+        assert!(page.starts_with("# PRD: Test PRD"));
+        assert!(page.contains("2 in PRD (1 passing), 3 archived"));
+        assert!(page.contains("- `cargo test`"));
+        assert!(page.contains("## Definition of Done\n- CHANGELOG updated"));
+        assert!(page.contains("- [ ] #1 (feature, high priority) Add login"));
+        assert!(page.contains("  - Create form"));
+        assert!(page.contains("- [x] #2 (test) Add tests"));
+    }
+
+    #[test]
+    fn prd_page_handles_empty_prd() {
+        let prd = Prd {
+            schema_version: prd::SCHEMA_VERSION,
+            name: "Empty".to_string(),
+            quality_gates: vec![],
+            workdir: None,
+            definition_of_done: vec![],
+            coverage: None,
+            tasks: vec![],
+        };
+        let page = format_prd_page(&prd, 0);
+        assert_eq!(page.matches("(none)").count(), 2);
+    }
+
+    #[test]
+    fn completed_page_lists_dates() {
+        let completed = vec![CompletedTask {
+            category: "setup".to_string(),
+            description: "Initial setup".to_string(),
+            steps: vec![],
+            completed_at: "2024-01-15".to_string(),
+            commit: None,
+        }];
+        let page = format_completed_page(&completed);
+        assert!(page.contains("# Completed Tasks (1)"));
+        assert!(page.contains("- 2024-01-15 (setup) Initial setup"));
+    }
+
+    #[test]
+    fn run_page_shows_branch() {
+        let mut run = RunState::new("plans/prd.json");
+        run.branch = Some("ralph/todo".to_string());
+        run.record_loop(4);
+        run.set_label(Some("night 2"));
+        let page = format_run_page(&run);
+        assert!(page.contains("- Label: night 2"));
+        assert!(page.contains("- Branch: ralph/todo"));
+        assert!(page.contains("- Loops: 4"));
+
+        run.branch = None;
+        assert!(format_run_page(&run).contains("(current branch)"));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "Test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    /// Commit `file` with `text` and return the new HEAD
+    fn commit_file(dir: &Path, file: &str, text: &str) -> String {
+        std::fs::write(dir.join(file), text).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-q", "-m", file]);
+        git::head_commit(dir).unwrap().unwrap()
+    }
+
+    fn journal_entry(run_id: &str, loop_number: u64, commit: Option<String>) -> JournalEntry {
+        JournalEntry {
+            run_id: run_id.to_string(),
+            loop_number,
+            finished_at: chrono::Utc::now(),
+            task_number: Some(1),
+            task: None,
+            status: "completed".to_string(),
+            summary: None,
+            files: vec![],
+            commit,
+        }
+    }
+
+    #[test]
+    fn pages_include_loop_logs_diffs_and_stats() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        let prd_path = root.join("prd.json");
+        let prd_path = prd_path.to_str().unwrap();
+
+        // A loop of an earlier run, then two loops of the last one: the first commits
+        // twice, the second commits nothing
+        let earlier = commit_file(root, "old.txt", "old\n");
+        commit_file(root, "a.txt", "alpha\n");
+        let first = commit_file(root, "b.txt", "beta\n");
+        let mut run = RunState::new(prd_path);
+        run.record_loop(2);
+        run.save().unwrap();
+        for entry in [
+            journal_entry("earlier", 1, Some(earlier)),
+            journal_entry(&run.id, 1, Some(first)),
+            journal_entry(&run.id, 2, None),
+        ] {
+            progress::append_journal(root, &entry).unwrap();
+        }
+
+        LoopLog {
+            run_id: run.id.clone(),
+            loop_number: 1,
+            status: "completed".to_string(),
+            finished_at: chrono::Utc::now(),
+            parsed: None,
+            attempts: vec![],
+            log: vec!["Task #1: Add login\nStatus: completed".to_string()],
+        }
+        .save(prd_path)
+        .unwrap();
+        Stats::append(
+            prd_path,
+            ralph_core::stats::IterationRecord {
+                run_id: run.id.clone(),
+                run_label: None,
+                loop_number: 1,
+                task_number: Some(1),
+                task: None,
+                category: Some("feature".to_string()),
+                model: None,
+                status: "completed".to_string(),
+                summary: None,
+                commit: None,
+                duration_secs: 90.0,
+                cost_usd: 0.25,
+                finished_at: chrono::Utc::now(),
+                rating: None,
+                rating_note: None,
+                escalation: None,
+                thinking: None,
+            },
+        )
+        .unwrap();
+
+        let pages = collect_pages(prd_path, &test_prd(), &[], root).unwrap();
+        let titles: Vec<&str> = pages.iter().filter_map(|p| p.lines().next()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "# PRD: Test PRD",
+                "# Last Build Run",
+                "# Loop 1: completed",
+                "# Loop 1 Diff",
+                "# Stats"
+            ]
+        );
+        assert!(pages[2].contains("Task #1: Add login"));
+        // Both of the loop's commits, but not the earlier run's
+        assert!(pages[3].contains("+alpha") && pages[3].contains("+beta"));
+        assert!(!pages[3].contains("+old"));
+        assert!(pages[4].contains("- Iterations: 1, $0.25 total"));
+        assert!(pages[4].contains("| feature | 1 |"));
+    }
+
+    #[test]
+    fn session_page_includes_answers() {
+        let mut session = PlanSession::new("/tmp/prd.json");
+        session.add_answer(Answer {
+            question_id: "q1".to_string(),
+            value: "React".to_string(),
+        });
+        let page = format_session_page(&session);
+        assert!(page.contains(&session.id));
+        assert!(page.contains("- Turns: 0"));
+        assert!(page.contains("- q1: React"));
+    }
+}
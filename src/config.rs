@@ -0,0 +1,542 @@
+//! Project settings read from `.ralph.toml` in the directory ralph runs in.
+//!
+//! ```toml
+//! [notify.discord]
+//! webhook = "https://discord.com/api/webhooks/..."
+//! level = "all"        # optional, same levels as --notify
+//! ping = "<@123456>"   # optional, sent with completion and blocked/attention messages
+//!
+//! [claude]
+//! mcp_config = [".mcp.json"]       # passed to every build loop as --mcp-config
+//! add_dirs = ["../shared"]         # passed as --add-dir
+//! settings = ".claude/ralph.json"  # passed as --settings
+//! allowed_tools = ["Read", "Edit", "Bash(cargo:*)"]  # passed as --allowedTools
+//! disallowed_tools = ["WebSearch"]                   # passed as --disallowedTools
+//!
+//! [redact]
+//! patterns = ["internal-[0-9]+"]  # scrubbed from logs on top of the built-in secret shapes
+//!
+//! [daemon]
+//! schedule = "0 2 * * *"              # cron schedule `ralph daemon` starts build passes on
+//! build_args = ["--max-loops", "10"]  # added to each pass's `ralph build`
+//!
+//! [normalize]
+//! enabled = true   # have a small model repair Claude output that isn't valid JSON
+//! model = "haiku"  # the model that does it
+//!
+//! [escalate]                 # like --escalate-after, which replaces this table
+//! after = 2                  # failed loops in a row on a task before escalating
+//! model = "opus"             # model the `model` step retries with
+//! steps = ["model", "history", "pause"]
+//!
+//! [progress]
+//! max_bytes = 65536  # size progress.txt may reach before older notes are condensed; 0 never
+//! model = "haiku"    # condense them with this model instead of keeping each note's first line
+//!
+//! [agent_docs]
+//! enabled = true  # attach the project's agent docs to build and plan prompts
+//! files = ["CLAUDE.md", "AGENTS.md", "CONTRIBUTING.md"]  # the ones to look for
+//!
+//! [prompt.categories]  # added to the loop prompt for tasks in these categories
+//! migration = "Make every migration reversible and test the rollback."
+//!
+//! [tui]
+//! vim_keys = true  # j/k/g/G/Ctrl-d/Ctrl-u scroll the log, h/l move between loops
+//!
+//! [plan]
+//! prompt = "replace"  # .ralph/plan-prompt.md replaces the built-in planning guidelines
+//!                     # instead of extending them
+//!
+//! [plan.defaults]   # answers pre-selected in `ralph plan`, by question category and topic
+//! technical.framework = "axum"
+//! quality.coverage = "yes"
+//!
+//! [theme]
+//! preset = "light"   # colours for light terminals; see `theme` for every slot
+//! accent = "#005f87"
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::notify::Level;
+use crate::theme::{Theme, ThemeConfig, ThemeError};
+use ralph_core::claude;
+use ralph_core::escalation;
+use ralph_core::plan::defaults::AnswerDefaults;
+use ralph_core::plan::prompts::PromptMode;
+use ralph_core::progress;
+use ralph_core::prompt;
+
+/// Settings file looked for in the working directory
+pub const FILE_NAME: &str = ".ralph.toml";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid {path}: {source}")]
+    Toml {
+        path: String,
+        source: toml::de::Error,
+    },
+
+    #[error("{0} in {FILE_NAME}")]
+    Theme(#[from] ThemeError),
+}
+
+/// Everything `.ralph.toml` can set; a missing file means all defaults
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    #[serde(default)]
+    pub claude: ClaudeConfig,
+    #[serde(default)]
+    pub redact: RedactConfig,
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    #[serde(default)]
+    pub tui: TuiConfig,
+    #[serde(default)]
+    pub plan: PlanConfig,
+    #[serde(default)]
+    pub normalize: NormalizeConfig,
+    #[serde(default)]
+    pub escalate: EscalateConfig,
+    #[serde(default)]
+    pub prompt: PromptConfig,
+    #[serde(default)]
+    pub agent_docs: AgentDocsConfig,
+    #[serde(default)]
+    pub progress: ProgressConfig,
+}
+
+/// `[progress]`: compacting progress.txt as loops add to it
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProgressConfig {
+    /// Size in bytes the file may reach before its older notes are condensed; 0 turns
+    /// compaction off
+    #[serde(default = "progress_max_bytes")]
+    pub max_bytes: u64,
+    /// Model that condenses the notes; without one ralph keeps each note's first line
+    pub model: Option<String>,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: progress_max_bytes(),
+            model: None,
+        }
+    }
+}
+
+fn progress_max_bytes() -> u64 {
+    progress::DEFAULT_MAX_BYTES
+}
+
+/// `[agent_docs]`: the files that describe the project's conventions, attached to build
+/// and plan prompts with `@` mentions when they exist
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentDocsConfig {
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default = "agent_docs")]
+    pub files: Vec<String>,
+}
+
+impl Default for AgentDocsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            files: agent_docs(),
+        }
+    }
+}
+
+impl AgentDocsConfig {
+    /// The docs to look for, unless attaching them is disabled
+    pub fn files(&self) -> &[String] {
+        if self.enabled { &self.files } else { &[] }
+    }
+}
+
+fn agent_docs() -> Vec<String> {
+    prompt::AGENT_DOCS
+        .iter()
+        .map(|doc| doc.to_string())
+        .collect()
+}
+
+/// `[prompt]`: additions to the build loop's prompt
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptConfig {
+    /// Instructions for tasks in a category, by category. With any set, ralph picks each
+    /// loop's task itself so the instructions match it.
+    #[serde(default)]
+    pub categories: BTreeMap<String, String>,
+}
+
+/// `[normalize]`: repairing Claude output that can't be parsed, in `ralph build` and
+/// `ralph plan`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NormalizeConfig {
+    /// Hand unparseable output to `model` to rewrite as JSON matching the schema before
+    /// giving up on it
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default = "normalize_model")]
+    pub model: String,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            model: normalize_model(),
+        }
+    }
+}
+
+impl NormalizeConfig {
+    /// The model that repairs output, unless repairs are disabled
+    pub fn model(&self) -> Option<&str> {
+        self.enabled.then_some(self.model.as_str())
+    }
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn normalize_model() -> String {
+    claude::NORMALIZE_MODEL.to_string()
+}
+
+/// `[escalate]`: escalation for every `ralph build` run; `--escalate-after` replaces it
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EscalateConfig {
+    /// Failed loops in a row on a task before the first step; no escalation without it
+    pub after: Option<u32>,
+    /// Model the `model` step switches to
+    pub model: Option<String>,
+    /// Steps in the order they're taken
+    pub steps: Option<Vec<escalation::Step>>,
+}
+
+impl EscalateConfig {
+    /// The configured policy, with the usual defaults for what isn't set
+    pub fn policy(&self) -> Option<escalation::Policy> {
+        let defaults = escalation::Policy::default();
+        Some(escalation::Policy {
+            after: self.after?,
+            model: self.model.clone().unwrap_or(defaults.model),
+            steps: self.steps.clone().unwrap_or(defaults.steps),
+        })
+    }
+}
+
+/// `[plan]`: settings for `ralph plan`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlanConfig {
+    /// Whether `.ralph/plan-prompt.md` extends or replaces the built-in guidelines
+    #[serde(default)]
+    pub prompt: PromptMode,
+    /// Answers for recurring questions, as `category.topic = "answer"`; see
+    /// `ralph_core::plan::defaults`
+    #[serde(default)]
+    pub defaults: AnswerDefaults,
+}
+
+/// `[tui]`: how the build and view TUIs take keys
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TuiConfig {
+    /// Vim-style keys alongside the arrows; `l` then moves to the next loop and `L` renames
+    /// the run
+    #[serde(default)]
+    pub vim_keys: bool,
+}
+
+/// `[daemon]`: when `ralph daemon` starts build passes, and how they run
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// Five-field cron schedule in local time; `--schedule` overrides it
+    pub schedule: Option<String>,
+    /// Extra `ralph build` arguments for every pass
+    #[serde(default)]
+    pub build_args: Vec<String>,
+}
+
+/// `[redact]`: more secrets to scrub from loop logs and the TUI
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactConfig {
+    /// Regular expressions; with a capture group only the group is replaced
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+/// `[claude]`: extra arguments for the Claude Code session each build loop starts. The
+/// matching `ralph build` flags add to the lists and override `settings`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClaudeConfig {
+    /// MCP server config files or JSON strings
+    #[serde(default)]
+    pub mcp_config: Vec<String>,
+    /// Directories outside the project Claude may use
+    #[serde(default)]
+    pub add_dirs: Vec<String>,
+    /// Settings file or JSON string
+    pub settings: Option<String>,
+    /// The only tools Claude may use; every tool when empty
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Tools Claude may never use
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+}
+
+/// `[notify.*]` tables: notification channels that need more than a flag
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    pub discord: Option<DiscordConfig>,
+}
+
+/// `[notify.discord]`: post to a Discord webhook
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    /// Webhook URL from the channel's Integrations settings
+    pub webhook: String,
+    /// Which events to post; every event when unset. `--notify discord=LEVEL` overrides it
+    pub level: Option<Level>,
+    /// Mention added to completion and attention-needed messages, e.g. "<@123>" or "@here"
+    pub ping: Option<String>,
+}
+
+/// Load `.ralph.toml` from `dir`, or the defaults if there isn't one
+pub fn load(dir: &Path) -> Result<Config, ConfigError> {
+    let path = dir.join(FILE_NAME);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(source) => {
+            return Err(ConfigError::Io {
+                path: path.display().to_string(),
+                source,
+            });
+        }
+    };
+    toml::from_str(&content).map_err(|source| ConfigError::Toml {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+/// The TUI colours `.ralph.toml` in `dir` sets, or the default dark theme
+pub fn load_theme(dir: &Path) -> Result<Theme, ConfigError> {
+    Ok(load(dir)?.theme.resolve()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn missing_file_is_default() {
+        let dir = TempDir::new().unwrap();
+        assert!(load(dir.path()).unwrap().notify.discord.is_none());
+    }
+
+    #[test]
+    fn reads_discord_settings() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[notify.discord]\nwebhook = \"https://example.test/hook\"\nlevel = \"failures\"\nping = \"@here\"\n",
+        )
+        .unwrap();
+        let discord = load(dir.path()).unwrap().notify.discord.unwrap();
+        assert_eq!(discord.webhook, "https://example.test/hook");
+        assert_eq!(discord.level, Some(Level::AttentionNeeded));
+        assert_eq!(discord.ping.as_deref(), Some("@here"));
+    }
+
+    #[test]
+    fn reads_claude_settings() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[claude]\nmcp_config = [\".mcp.json\", \"db.json\"]\nsettings = \"ralph.json\"\n",
+        )
+        .unwrap();
+        let claude = load(dir.path()).unwrap().claude;
+        assert_eq!(claude.mcp_config, vec![".mcp.json", "db.json"]);
+        assert!(claude.add_dirs.is_empty());
+        assert_eq!(claude.settings.as_deref(), Some("ralph.json"));
+        assert!(claude.allowed_tools.is_empty());
+    }
+
+    #[test]
+    fn reads_tool_lists() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[claude]\nallowed_tools = [\"Read\", \"Bash(cargo:*)\"]\ndisallowed_tools = [\"WebSearch\"]\n",
+        )
+        .unwrap();
+        let claude = load(dir.path()).unwrap().claude;
+        assert_eq!(claude.allowed_tools, vec!["Read", "Bash(cargo:*)"]);
+        assert_eq!(claude.disallowed_tools, vec!["WebSearch"]);
+    }
+
+    #[test]
+    fn normalization_is_on_unless_disabled() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(load(dir.path()).unwrap().normalize.model(), Some("haiku"));
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[normalize]\nmodel = \"sonnet\"\n",
+        )
+        .unwrap();
+        assert_eq!(load(dir.path()).unwrap().normalize.model(), Some("sonnet"));
+        std::fs::write(dir.path().join(FILE_NAME), "[normalize]\nenabled = false\n").unwrap();
+        assert_eq!(load(dir.path()).unwrap().normalize.model(), None);
+    }
+
+    #[test]
+    fn agent_docs_default_to_the_usual_files() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(
+            load(dir.path()).unwrap().agent_docs.files(),
+            ["CLAUDE.md", "AGENTS.md", "CONTRIBUTING.md"]
+        );
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[agent_docs]
+enabled = false
+",
+        )
+        .unwrap();
+        assert!(load(dir.path()).unwrap().agent_docs.files().is_empty());
+    }
+
+    #[test]
+    fn reads_progress_compaction() {
+        let dir = TempDir::new().unwrap();
+        let progress = load(dir.path()).unwrap().progress;
+        assert_eq!(progress.max_bytes, progress::DEFAULT_MAX_BYTES);
+        assert_eq!(progress.model, None);
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[progress]\nmax_bytes = 0\nmodel = \"haiku\"\n",
+        )
+        .unwrap();
+        let progress = load(dir.path()).unwrap().progress;
+        assert_eq!(progress.max_bytes, 0);
+        assert_eq!(progress.model.as_deref(), Some("haiku"));
+    }
+
+    #[test]
+    fn escalation_needs_after() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(FILE_NAME), "[escalate]\nmodel = \"opus\"\n").unwrap();
+        assert!(load(dir.path()).unwrap().escalate.policy().is_none());
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[escalate]\nafter = 3\nsteps = [\"model\", \"pause\"]\n",
+        )
+        .unwrap();
+        let policy = load(dir.path()).unwrap().escalate.policy().unwrap();
+        assert_eq!(policy.after, 3);
+        assert_eq!(policy.model, escalation::DEFAULT_MODEL);
+        assert_eq!(
+            policy.steps,
+            vec![escalation::Step::Model, escalation::Step::Pause]
+        );
+    }
+
+    #[test]
+    fn reads_category_addenda() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[prompt.categories]\nmigration = \"Test the rollback.\"\n",
+        )
+        .unwrap();
+        let categories = load(dir.path()).unwrap().prompt.categories;
+        assert_eq!(categories["migration"], "Test the rollback.");
+    }
+
+    #[test]
+    fn reads_daemon_settings() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[daemon]\nschedule = \"0 2 * * *\"\nbuild_args = [\"--max-loops\", \"10\"]\n",
+        )
+        .unwrap();
+        let daemon = load(dir.path()).unwrap().daemon;
+        assert_eq!(daemon.schedule.as_deref(), Some("0 2 * * *"));
+        assert_eq!(daemon.build_args, vec!["--max-loops", "10"]);
+    }
+
+    #[test]
+    fn reads_plan_defaults() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(FILE_NAME),
+            "[plan]\nprompt = \"replace\"\n\n[plan.defaults]\ntechnical.framework = \"axum\"\nquality.coverage = \"yes\"\n",
+        )
+        .unwrap();
+        let plan = load(dir.path()).unwrap().plan;
+        assert_eq!(plan.prompt, PromptMode::Replace);
+        assert_eq!(plan.defaults["technical"]["framework"], "axum");
+        assert_eq!(plan.defaults["quality"]["coverage"], "yes");
+    }
+
+    #[test]
+    fn reads_tui_settings() {
+        let dir = TempDir::new().unwrap();
+        assert!(!load(dir.path()).unwrap().tui.vim_keys);
+        std::fs::write(dir.path().join(FILE_NAME), "[tui]\nvim_keys = true\n").unwrap();
+        assert!(load(dir.path()).unwrap().tui.vim_keys);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_levels() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        std::fs::write(&path, "[notify.discord]\nwebhok = \"x\"\n").unwrap();
+        assert!(matches!(load(dir.path()), Err(ConfigError::Toml { .. })));
+
+        std::fs::write(
+            &path,
+            "[notify.discord]\nwebhook = \"x\"\nlevel = \"loud\"\n",
+        )
+        .unwrap();
+        let err = load(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("unknown notification level 'loud'"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Errors shared by the commands that drive Claude from a TUI: a bad PRD, a terminal that
+//! can't be drawn to, a claude process that can't be run, or a config or set of options a
+//! build can't start with. Returning one unwinds to `main`, restoring the terminal and
+//! flushing the logs on the way, instead of exiting or panicking with raw mode still on.
+
+use std::io;
+use thiserror::Error;
+
+use crate::config::{self, ConfigError};
+use crate::theme::ThemeError;
+use ralph_core::prd::{PrdError, STANDARD_PATHS};
+use ralph_core::prompt;
+use ralph_core::redact::RedactError;
+use ralph_core::workspace;
+
+#[derive(Error, Debug)]
+pub enum RalphError {
+    #[error("{0}")]
+    Prd(#[from] PrdError),
+
+    #[error("Terminal error: {0}")]
+    Terminal(#[from] io::Error),
+
+    #[error("Failed to run claude: {0}. Is the claude CLI installed and on PATH?")]
+    Claude(#[source] io::Error),
+
+    #[error(
+        "No PRD found at {}. Create one with `ralph plan`, or pass its path",
+        STANDARD_PATHS.join(", ")
+    )]
+    NoPrd,
+
+    #[error("Found several PRDs ({}); pass the path of the one to build", .0.join(", "))]
+    SeveralPrds(Vec<String>),
+
+    #[error(
+        "No PRDs to build: put them in {}/ or list them with --prd",
+        workspace::PLANS_DIR
+    )]
+    NoWorkspacePrds,
+
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+
+    #[error("{0} in {}", config::FILE_NAME)]
+    Redact(#[from] RedactError),
+
+    #[error("{0} in {}", config::FILE_NAME)]
+    Theme(#[from] ThemeError),
+
+    #[error("Can't read {}: {0}", prompt::PROMPT_FILE)]
+    Prompt(#[source] io::Error),
+
+    #[error("Can't write events to {path}: {source}")]
+    Events { path: String, source: io::Error },
+
+    #[error("{0}")]
+    Branch(String),
+
+    /// Options that don't fit the PRD or each other, e.g. --task out of range
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("The dry run found problems (see the checks above)")]
+    DryRunFailed,
+}
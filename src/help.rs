@@ -0,0 +1,79 @@
+//! The help overlay (key `?`): every keybinding of a TUI in a centered popup, since the
+//! footer only has room for a few.
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+};
+
+use crate::theme::Theme;
+
+/// A heading and the keys under it, as (key, what it does)
+pub type Section = (&'static str, Vec<(&'static str, &'static str)>);
+
+/// Draw the overlay over whatever is on screen, titled with the TUI's current mode
+pub fn render(frame: &mut Frame, theme: &Theme, mode: &str, sections: &[Section]) {
+    let key_width = sections
+        .iter()
+        .flat_map(|(_, keys)| keys.iter().map(|(key, _)| key.chars().count()))
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (i, (heading, keys)) in sections.iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(Span::styled(
+            *heading,
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (key, action) in keys {
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {:<key_width$}  ", key),
+                    Style::default().fg(theme.success),
+                ),
+                Span::styled(*action, Style::default().fg(theme.text)),
+            ]));
+        }
+    }
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        "Press any key to close",
+        Style::default().fg(theme.dim),
+    )));
+
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+    let area = centered(frame.area(), width, lines.len() as u16 + 2);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.border))
+        .title(format!(" Help - {} ", mode))
+        .title_style(
+            Style::default()
+                .fg(theme.border)
+                .add_modifier(Modifier::BOLD),
+        )
+        .padding(Padding::horizontal(1));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// A `width` x `height` rectangle in the middle of `area`, shrunk to fit it
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
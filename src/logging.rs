@@ -0,0 +1,131 @@
+//! Diagnostic log at `.ralph/ralph.log` in the directory ralph runs in.
+//!
+//! Everything ralph does behind the TUI goes through `tracing`: status changes, Claude
+//! and git invocations, gate runs, retries. `-v` adds what the TUI logged and the exact
+//! Claude command line, `-vv` adds Claude's raw output. The file is appended to and only
+//! created once something is logged. Built with the `otel` feature, spans also go to an
+//! OpenTelemetry collector (see `otel`).
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+
+/// Log file, relative to the working directory
+pub const FILE_PATH: &str = ".ralph/ralph.log";
+
+/// Most detailed level logged for a `-v` count
+pub fn level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Send `tracing` events at `level(verbosity)` and above to the log file
+pub fn init(verbosity: u8) {
+    let log = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(LogFile::new(PathBuf::from(FILE_PATH)))
+        .with_filter(level(verbosity));
+    let subscriber = tracing_subscriber::registry().with(log);
+    #[cfg(feature = "otel")]
+    let subscriber = subscriber.with(crate::otel::layer().with_filter(LevelFilter::INFO));
+    // Only fails if a subscriber is already set, which leaves logging working anyway
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
+/// Flush whatever is still buffered for export. Call before exiting.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    crate::otel::shutdown();
+}
+
+/// The log file, opened on the first write so commands that log nothing leave no file
+struct LogFile {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl LogFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: Mutex::new(None),
+        }
+    }
+}
+
+impl io::Write for &LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        if file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let opened = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            *file = Some(opened);
+        }
+        match file.as_mut() {
+            Some(file) => file.write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self
+            .file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+        {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFile {
+    type Writer = &'a LogFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn verbosity_raises_the_level() {
+        assert_eq!(level(0), LevelFilter::INFO);
+        assert_eq!(level(1), LevelFilter::DEBUG);
+        assert_eq!(level(2), LevelFilter::TRACE);
+        assert_eq!(level(5), LevelFilter::TRACE);
+    }
+
+    #[test]
+    fn log_file_is_created_on_first_write_and_appended() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".ralph/ralph.log");
+        let log = LogFile::new(path.clone());
+        assert!(!path.exists());
+
+        log.make_writer().write_all(b"one\n").unwrap();
+        LogFile::new(path.clone())
+            .make_writer()
+            .write_all(b"two\n")
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+    }
+}
@@ -1,84 +1,64 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use ralph_core::storage;
 
 mod app;
-mod claude;
+mod checks;
 mod commands;
+mod config;
+mod error;
+mod help;
+mod logging;
+mod markdown;
+mod notify;
+#[cfg(feature = "otel")]
+mod otel;
 mod plan;
-mod prd;
-mod prompt;
+mod runtime;
+mod theme;
+mod toc;
 mod tui;
 
 #[derive(Parser, Debug)]
 #[command(name = "ralph")]
 #[command(version, about = "Ralph - AI-powered PRD execution and generation", long_about = None)]
-#[command(arg_required_else_help = true)]
+#[command(arg_required_else_help = true, subcommand_required = true)]
 struct Cli {
-    #[command(subcommand)]
-    command: Option<Commands>,
-}
-
-#[derive(Subcommand, Debug)]
-enum Commands {
-    /// Execute tasks from an existing PRD file
-    Build {
-        /// Path to the PRD JSON file
-        #[arg(short, long, default_value = "plans/prd.json")]
-        prd_path: String,
-
-        /// Maximum number of loops to run
-        #[arg(short = 'l', long)]
-        max_loops: Option<u64>,
-
-        /// Maximum agentic turns per Claude session (prevents hung sessions)
-        #[arg(short = 't', long)]
-        max_turns: Option<u32>,
-    },
-
-    /// Generate a new PRD through interactive multi-turn conversation
-    Plan {
-        /// Output path for the generated PRD
-        #[arg(short, long, default_value = "plans/prd.json")]
-        output: String,
+    /// Where plan sessions and run state are kept
+    #[arg(long, global = true, value_enum, default_value = "json")]
+    storage: storage::Backend,
 
-        /// Resume an interrupted session
-        #[arg(short, long)]
-        resume: bool,
-
-        /// Force overwrite existing files
-        #[arg(short, long)]
-        force: bool,
+    /// Log more detail to .ralph/ralph.log (-v: debug, -vv: trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
 
-        /// Description of what to build (optional)
-        #[arg(short = 'd', long)]
-        description: Option<String>,
-    },
+/// The full CLI: global options plus every registered subcommand
+fn cli() -> clap::Command {
+    commands::registry()
+        .iter()
+        .fold(Cli::command(), |cli, command| {
+            cli.subcommand(command.command())
+        })
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let matches = cli().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(cli.verbose);
+    tui::install_restore_hooks();
+
+    if let Err(e) = storage::init(cli.storage) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 
-    match cli.command {
-        Some(Commands::Build {
-            prd_path,
-            max_loops,
-            max_turns,
-        }) => {
-            commands::build::run(&prd_path, max_loops.unwrap_or(u64::MAX), max_turns);
-        }
-        Some(Commands::Plan {
-            output,
-            resume,
-            force,
-            description,
-        }) => {
-            if let Err(e) = commands::plan::run(&output, resume, force, description.as_deref()) {
-                eprintln!("Error: {}", e);
-                std::process::exit(1);
-            }
-        }
-        None => {
-            // arg_required_else_help ensures this is unreachable in normal CLI usage
-            unreachable!("clap should show help when no subcommand is provided");
-        }
+    // subcommand_required ensures clap shows help or an error without one
+    let (name, sub_matches) = matches.subcommand().expect("clap requires a subcommand");
+    let command = commands::find(name).expect("clap only accepts registered subcommands");
+    let result = command.run(sub_matches);
+    logging::shutdown();
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,616 @@
+//! Markdown for the log panels of the build and plan TUIs.
+//!
+//! Claude's output and ralph's own notes are parsed with pulldown-cmark and drawn as styled
+//! lines: headings, emphasis, inline code, nested lists, block quotes, tables and fenced
+//! code blocks, the last with light highlighting for common languages. Line breaks inside a
+//! paragraph are kept, since the logs are terminal output rather than prose to reflow.
+
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::theme::Theme;
+
+/// Draw `text` as styled lines
+pub fn render(text: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(theme);
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// A table being collected: its rows, each a list of cells made of spans
+#[derive(Default)]
+struct TableBuf {
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    row: Vec<Vec<Span<'static>>>,
+    cell: Vec<Span<'static>>,
+}
+
+struct Renderer<'t> {
+    theme: &'t Theme,
+    lines: Vec<Line<'static>>,
+    /// The line being built
+    spans: Vec<Span<'static>>,
+    /// Inline styles in effect, innermost last
+    styles: Vec<Style>,
+    /// Open lists, innermost last: the next number of an ordered list, None for bullets
+    lists: Vec<Option<u64>>,
+    /// Bullet or number of a list item whose first line hasn't started yet
+    marker: Option<Span<'static>>,
+    /// Open block quotes
+    quotes: usize,
+    /// Language and text of the fenced code block being read
+    code: Option<(String, String)>,
+    table: Option<TableBuf>,
+}
+
+impl<'t> Renderer<'t> {
+    fn new(theme: &'t Theme) -> Self {
+        Self {
+            theme,
+            lines: Vec::new(),
+            spans: Vec::new(),
+            styles: vec![Style::default().fg(theme.text)],
+            lists: Vec::new(),
+            marker: None,
+            quotes: 0,
+            code: None,
+            table: None,
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        let style = self.style().patch(style);
+        self.styles.push(style);
+    }
+
+    fn pop_style(&mut self) {
+        if self.styles.len() > 1 {
+            self.styles.pop();
+        }
+    }
+
+    /// Spaces for the open lists, so wrapped item lines sit under the item's text
+    fn indent(&self) -> String {
+        "  ".repeat(self.lists.len())
+    }
+
+    /// Add a span, starting the line with quote bars, list indent and any item marker
+    fn push(&mut self, span: Span<'static>) {
+        if let Some(table) = self.table.as_mut() {
+            table.cell.push(span);
+            return;
+        }
+        if self.spans.is_empty() {
+            if self.quotes > 0 {
+                self.spans.push(Span::styled(
+                    "▎ ".repeat(self.quotes),
+                    Style::default().fg(self.theme.dim),
+                ));
+            }
+            match self.marker.take() {
+                Some(marker) => {
+                    let depth = self.lists.len().saturating_sub(1);
+                    self.spans.push(Span::raw("  ".repeat(depth)));
+                    self.spans.push(marker);
+                }
+                None if !self.lists.is_empty() => self.spans.push(Span::raw(self.indent())),
+                None => {}
+            }
+        }
+        self.spans.push(span);
+    }
+
+    fn text(&mut self, text: &str) {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush();
+            }
+            if !part.is_empty() {
+                let style = self.style();
+                self.push(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    /// End the line being built, if there is one
+    fn flush(&mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    /// A blank line between blocks, but never two in a row or one at the top
+    fn gap(&mut self) {
+        self.flush();
+        if self.lists.is_empty() && self.lines.last().is_some_and(|l| l.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        if let Some((_, code)) = self.code.as_mut() {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.end_code_block(),
+                _ => {}
+            }
+            return;
+        }
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => self.text(&text),
+            Event::Code(code) => {
+                let style = Style::default()
+                    .fg(self.theme.highlight)
+                    .bg(self.theme.code_bg);
+                self.push(Span::styled(code.to_string(), style));
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if self.table.is_some() {
+                    self.text(" ");
+                } else {
+                    self.flush();
+                }
+            }
+            Event::Rule => {
+                self.flush();
+                self.push(Span::styled(
+                    "─".repeat(40),
+                    Style::default().fg(self.theme.dim),
+                ));
+                self.gap();
+            }
+            Event::TaskListMarker(done) => {
+                let (mark, color) = if done {
+                    ("[x] ", self.theme.success)
+                } else {
+                    ("[ ] ", self.theme.muted)
+                };
+                self.push(Span::styled(mark, Style::default().fg(color)));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                let mut style = Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD);
+                if level == HeadingLevel::H1 {
+                    style = style.add_modifier(Modifier::UNDERLINED);
+                }
+                self.push_style(style);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quotes += 1;
+                self.push_style(Style::default().fg(self.theme.muted));
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_lowercase()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((lang, String::new()));
+            }
+            Tag::List(start) => {
+                // A list nested in a tight item follows the item's text on a new line
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let depth = self.lists.len();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ if depth > 1 => "- ".to_string(),
+                    _ => "* ".to_string(),
+                };
+                let color = if depth > 1 {
+                    self.theme.muted
+                } else {
+                    self.theme.warning
+                };
+                self.marker = Some(Span::styled(marker, Style::default().fg(color)));
+            }
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { .. } => self.push_style(
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::UNDERLINED),
+            ),
+            Tag::Table(_) => {
+                self.flush();
+                self.table = Some(TableBuf::default());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph if self.table.is_none() => self.gap(),
+            TagEnd::Heading(_) => {
+                self.pop_style();
+                self.gap();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.pop_style();
+                self.flush();
+                self.quotes = self.quotes.saturating_sub(1);
+                self.gap();
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                self.gap();
+            }
+            TagEnd::Item => {
+                // An empty item still shows its marker
+                if self.marker.is_some() {
+                    self.push(Span::raw(""));
+                }
+                self.flush();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => {
+                self.pop_style()
+            }
+            TagEnd::TableCell => {
+                if let Some(table) = self.table.as_mut() {
+                    let cell = std::mem::take(&mut table.cell);
+                    table.row.push(cell);
+                }
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::Table => self.end_table(),
+            _ => {}
+        }
+    }
+
+    fn end_code_block(&mut self) {
+        let Some((lang, code)) = self.code.take() else {
+            return;
+        };
+        let gutter = Style::default().fg(self.theme.dim);
+        if !lang.is_empty() {
+            self.push(Span::styled(format!("╭ {}", lang), gutter));
+            self.flush();
+        }
+        for line in code.trim_end_matches('\n').split('\n') {
+            self.push(Span::styled("│ ", gutter));
+            for span in highlight(&lang, line, self.theme) {
+                self.push(span);
+            }
+            self.flush();
+        }
+        self.gap();
+    }
+
+    /// Lay the collected table out in padded columns, the header row in bold
+    fn end_table(&mut self) {
+        let Some(table) = self.table.take() else {
+            return;
+        };
+        let width = |cell: &Vec<Span>| cell.iter().map(Span::width).sum::<usize>();
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| {
+                table
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(c))
+                    .map(width)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let border = Style::default().fg(self.theme.dim);
+        for (r, row) in table.rows.into_iter().enumerate() {
+            for (c, cell) in row.into_iter().enumerate() {
+                if c > 0 {
+                    self.push(Span::styled(" │ ", border));
+                }
+                let pad = widths[c].saturating_sub(width(&cell));
+                for span in cell {
+                    let span = if r == 0 {
+                        let style = span.style.add_modifier(Modifier::BOLD);
+                        span.style(style)
+                    } else {
+                        span
+                    };
+                    self.push(span);
+                }
+                self.push(Span::raw(" ".repeat(pad)));
+            }
+            self.flush();
+            if r == 0 {
+                let rule: Vec<String> = widths.iter().map(|w| "─".repeat(*w)).collect();
+                self.push(Span::styled(rule.join("─┼─"), border));
+                self.flush();
+            }
+        }
+        self.gap();
+    }
+
+    fn finish(mut self) -> Vec<Line<'static>> {
+        self.flush();
+        while self.lines.last().is_some_and(|l| l.width() == 0) {
+            self.lines.pop();
+        }
+        self.lines
+    }
+}
+
+/// Keywords picked out in fenced code, by language
+fn keywords(lang: &str) -> &'static [&'static str] {
+    match lang {
+        "rust" | "rs" => &[
+            "as", "async", "await", "const", "crate", "else", "enum", "fn", "for", "if", "impl",
+            "in", "let", "loop", "match", "mod", "mut", "pub", "return", "self", "Self", "struct",
+            "trait", "type", "use", "where", "while",
+        ],
+        "js" | "javascript" | "ts" | "typescript" | "tsx" | "jsx" => &[
+            "async",
+            "await",
+            "class",
+            "const",
+            "else",
+            "export",
+            "extends",
+            "for",
+            "function",
+            "if",
+            "import",
+            "interface",
+            "let",
+            "new",
+            "return",
+            "this",
+            "type",
+            "var",
+            "while",
+        ],
+        "python" | "py" => &[
+            "and", "as", "async", "await", "class", "def", "elif", "else", "for", "from", "if",
+            "import", "in", "is", "lambda", "not", "or", "return", "self", "while", "with",
+            "yield",
+        ],
+        "sh" | "bash" | "shell" | "zsh" | "console" => &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "then", "while",
+        ],
+        "go" => &[
+            "defer",
+            "else",
+            "for",
+            "func",
+            "go",
+            "if",
+            "import",
+            "interface",
+            "package",
+            "range",
+            "return",
+            "struct",
+            "type",
+            "var",
+        ],
+        "json" | "toml" | "yaml" | "yml" => &["true", "false", "null"],
+        _ => &[],
+    }
+}
+
+/// Where a line comment starts in `lang`
+fn comment_marker(lang: &str) -> Option<&'static str> {
+    match lang {
+        "rust" | "rs" | "js" | "javascript" | "ts" | "typescript" | "tsx" | "jsx" | "go" | "c"
+        | "cpp" | "java" | "kotlin" | "swift" => Some("//"),
+        "python" | "py" | "sh" | "bash" | "shell" | "zsh" | "console" | "toml" | "yaml" | "yml"
+        | "ruby" | "rb" => Some("#"),
+        "sql" | "lua" => Some("--"),
+        _ => None,
+    }
+}
+
+/// One line of fenced code as styled spans: diffs by line, other known languages by
+/// keyword, string, number and comment
+fn highlight(lang: &str, line: &str, theme: &Theme) -> Vec<Span<'static>> {
+    let plain = Style::default().fg(theme.text);
+    if lang == "diff" || lang == "patch" {
+        let color = if line.starts_with("+++") || line.starts_with("---") {
+            theme.text
+        } else if line.starts_with('+') {
+            theme.success
+        } else if line.starts_with('-') {
+            theme.error
+        } else if line.starts_with("@@") {
+            theme.accent
+        } else {
+            theme.muted
+        };
+        return vec![Span::styled(line.to_string(), Style::default().fg(color))];
+    }
+    let keywords = keywords(lang);
+    let comment = comment_marker(lang);
+    if keywords.is_empty() && comment.is_none() {
+        return vec![Span::styled(line.to_string(), plain)];
+    }
+
+    let mut spans = Vec::new();
+    let mut word = String::new();
+    let flush_word = |word: &mut String, spans: &mut Vec<Span<'static>>| {
+        if word.is_empty() {
+            return;
+        }
+        let style = if keywords.contains(&word.as_str()) {
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+            Style::default().fg(theme.warning)
+        } else {
+            plain
+        };
+        spans.push(Span::styled(std::mem::take(word), style));
+    };
+
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if let Some(marker) = comment
+            && rest.starts_with(marker)
+        {
+            flush_word(&mut word, &mut spans);
+            spans.push(Span::styled(
+                rest.to_string(),
+                Style::default().fg(theme.dim),
+            ));
+            return spans;
+        }
+        if c == '"' || c == '\'' {
+            flush_word(&mut word, &mut spans);
+            // Up to the matching quote, skipping escaped ones; to the end if unclosed
+            let mut end = rest.len();
+            let mut escaped = false;
+            for (i, ch) in rest.char_indices().skip(1) {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == c {
+                    end = i + ch.len_utf8();
+                    break;
+                }
+            }
+            spans.push(Span::styled(
+                rest[..end].to_string(),
+                Style::default().fg(theme.success),
+            ));
+            rest = &rest[end..];
+            continue;
+        }
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+        } else {
+            flush_word(&mut word, &mut spans);
+            spans.push(Span::styled(c.to_string(), plain));
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    flush_word(&mut word, &mut spans);
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn headings_lists_and_line_breaks() {
+        let theme = Theme::default();
+        let lines = render(
+            "# Done\nFirst line\nsecond line\n\n- one\n  - nested\n- two\n\n1. a\n2. b\n",
+            &theme,
+        );
+        assert_eq!(
+            text(&lines),
+            vec![
+                "Done",
+                "",
+                "First line",
+                "second line",
+                "",
+                "* one",
+                "  - nested",
+                "* two",
+                "",
+                "1. a",
+                "2. b",
+            ]
+        );
+        assert!(
+            lines[0].spans[0]
+                .style
+                .add_modifier
+                .contains(Modifier::BOLD | Modifier::UNDERLINED)
+        );
+    }
+
+    #[test]
+    fn inline_styles() {
+        let theme = Theme::default();
+        let lines = render("**bold** *it* `code`", &theme);
+        let spans = &lines[0].spans;
+        assert!(spans[0].style.add_modifier.contains(Modifier::BOLD));
+        assert!(spans[2].style.add_modifier.contains(Modifier::ITALIC));
+        assert_eq!(spans[4].content, "code");
+        assert_eq!(spans[4].style.bg, Some(theme.code_bg));
+    }
+
+    #[test]
+    fn tables_line_up_their_columns() {
+        let theme = Theme::default();
+        let lines = render("| a | long |\n|---|---|\n| xyz | b |\n", &theme);
+        assert_eq!(text(&lines), vec!["a   │ long", "────┼─────", "xyz │ b   "]);
+    }
+
+    #[test]
+    fn fenced_code_is_highlighted_by_language() {
+        let theme = Theme::default();
+        let lines = render(
+            "```rust\nlet s = \"hi\"; // note\n```\n\n```diff\n+added\n```\n",
+            &theme,
+        );
+        assert_eq!(
+            text(&lines),
+            vec![
+                "╭ rust",
+                "│ let s = \"hi\"; // note",
+                "",
+                "╭ diff",
+                "│ +added"
+            ]
+        );
+        let code = &lines[1].spans;
+        assert_eq!(code[1].content, "let");
+        assert_eq!(code[1].style.fg, Some(theme.accent));
+        let string = code.iter().find(|s| s.content == "\"hi\"").unwrap();
+        assert_eq!(string.style.fg, Some(theme.success));
+        assert_eq!(code.last().unwrap().style.fg, Some(theme.dim));
+        assert_eq!(lines[4].spans[1].style.fg, Some(theme.success));
+    }
+}
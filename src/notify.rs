@@ -0,0 +1,462 @@
+//! Notifications about a build run, filtered per channel by preference level.
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use std::fmt;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// Environment variable holding the Slack incoming webhook when `--slack-webhook` isn't given
+pub const SLACK_WEBHOOK_ENV: &str = "RALPH_SLACK_WEBHOOK";
+
+/// Seconds a webhook post may take before it's given up on
+const WEBHOOK_TIMEOUT_SECS: &str = "10";
+
+/// Something that happened during a run worth telling the user about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A loop finished normally
+    Progress,
+    /// The PRD (or the --task target) is done
+    Completed,
+    /// Claude failed, timed out or ran out of retries
+    Error,
+    /// The run needs a human: a blocked task, a missing commit, an exhausted budget
+    AttentionNeeded,
+    /// The run ended; the message is its final report
+    Report,
+}
+
+/// Which events a channel notifies about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    All,
+    ErrorsOnly,
+    CompletionOnly,
+    /// Errors and anything else that needs a human
+    AttentionNeeded,
+    Off,
+}
+
+impl Level {
+    pub fn wants(self, event: Event) -> bool {
+        match self {
+            Level::All => true,
+            Level::ErrorsOnly => event == Event::Error,
+            Level::CompletionOnly => matches!(event, Event::Completed | Event::Report),
+            Level::AttentionNeeded => {
+                matches!(event, Event::Error | Event::AttentionNeeded | Event::Report)
+            }
+            Level::Off => false,
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "all" => Ok(Level::All),
+            "errors-only" | "errors" => Ok(Level::ErrorsOnly),
+            "completion-only" | "completion" => Ok(Level::CompletionOnly),
+            "attention-needed" | "attention" | "failures" => Ok(Level::AttentionNeeded),
+            "off" | "none" => Ok(Level::Off),
+            other => Err(format!(
+                "unknown notification level '{}': use all, errors-only, completion-only, attention-needed or off",
+                other
+            )),
+        }
+    }
+}
+
+/// Levels are written the same way in `.ralph.toml` as on the command line
+impl<'de> Deserialize<'de> for Level {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Where notifications are delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    /// notify-send on Linux, osascript on macOS
+    Desktop,
+    /// A Slack incoming webhook (--slack-webhook or RALPH_SLACK_WEBHOOK), posted with curl
+    Slack,
+    /// A Discord webhook from `[notify.discord]` in `.ralph.toml`, posted with curl
+    Discord,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::Desktop => f.write_str("desktop"),
+            Channel::Slack => f.write_str("slack"),
+            Channel::Discord => f.write_str("discord"),
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "desktop" => Ok(Channel::Desktop),
+            "slack" => Ok(Channel::Slack),
+            "discord" => Ok(Channel::Discord),
+            other => Err(format!(
+                "unknown notification channel '{}': available channels: desktop, slack, discord",
+                other
+            )),
+        }
+    }
+}
+
+/// One `--notify` preference: `desktop=errors-only`, or just `desktop` for every event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preference {
+    pub channel: Channel,
+    pub level: Level,
+}
+
+impl FromStr for Preference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (channel, level) = match s.split_once('=') {
+            Some((channel, level)) => (channel.parse()?, level.parse()?),
+            None => (s.parse()?, Level::All),
+        };
+        Ok(Self { channel, level })
+    }
+}
+
+/// Sends run events to every channel whose level wants them
+#[derive(Debug, Default)]
+pub struct Notifier {
+    preferences: Vec<Preference>,
+    /// Run label (--label), used to tell runs apart
+    pub label: Option<String>,
+    /// Incoming webhook URL for the Slack channel
+    pub slack_webhook: Option<String>,
+    /// Webhook URL for the Discord channel
+    pub discord_webhook: Option<String>,
+    /// Mention sent with Discord messages that need someone to look
+    pub discord_ping: Option<String>,
+}
+
+impl Notifier {
+    /// Later preferences for the same channel override earlier ones
+    pub fn new(preferences: &[Preference]) -> Self {
+        let mut merged: Vec<Preference> = Vec::new();
+        for pref in preferences {
+            merged.retain(|p| p.channel != pref.channel);
+            merged.push(*pref);
+        }
+        Self {
+            preferences: merged,
+            label: None,
+            slack_webhook: None,
+            discord_webhook: None,
+            discord_ping: None,
+        }
+    }
+
+    /// Whether any preference uses `channel`, whatever its level
+    pub fn uses(&self, channel: Channel) -> bool {
+        self.preferences
+            .iter()
+            .any(|p| p.channel == channel && p.level != Level::Off)
+    }
+
+    /// Channels that would receive `event`
+    pub fn channels_for(&self, event: Event) -> Vec<Channel> {
+        self.preferences
+            .iter()
+            .filter(|p| p.level.wants(event))
+            .map(|p| p.channel)
+            .collect()
+    }
+
+    /// Deliver an event; delivery failures are ignored so notifications never stop a run
+    pub fn notify(&self, event: Event, message: &str) {
+        self.notify_with(event, message, &[]);
+    }
+
+    /// Like `notify`, with labelled details (task, status, cost...) for channels that can
+    /// lay them out; the desktop gets only the message
+    pub fn notify_with(&self, event: Event, message: &str, fields: &[(&str, String)]) {
+        let title = match &self.label {
+            Some(label) => format!("ralph: {}", label),
+            None => "ralph".to_string(),
+        };
+        for channel in self.channels_for(event) {
+            match channel {
+                Channel::Desktop => send_desktop(&title, message),
+                Channel::Slack => {
+                    if let Some(url) = &self.slack_webhook {
+                        post_json(url, &slack_payload(&title, message, fields));
+                    }
+                }
+                Channel::Discord => {
+                    if let Some(url) = &self.discord_webhook {
+                        let ping = self.discord_ping.as_deref();
+                        post_json(url, &discord_payload(&title, event, message, fields, ping));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Slack message for a notification: the title and message, then the fields two per row
+pub fn slack_payload(title: &str, message: &str, fields: &[(&str, String)]) -> Value {
+    let mut blocks = vec![json!({
+        "type": "section",
+        "text": {
+            "type": "mrkdwn",
+            "text": format!("*{}*\n{}", slack_escape(title), slack_escape(message)),
+        },
+    })];
+    // Slack allows at most 10 fields per section
+    for chunk in fields.chunks(10) {
+        let fields: Vec<Value> = chunk
+            .iter()
+            .map(|(name, value)| {
+                json!({
+                    "type": "mrkdwn",
+                    "text": format!("*{}*\n{}", slack_escape(name), slack_escape(value)),
+                })
+            })
+            .collect();
+        blocks.push(json!({ "type": "section", "fields": fields }));
+    }
+    json!({
+        "text": format!("{}: {}", title, message),
+        "blocks": blocks,
+    })
+}
+
+/// Discord message for a notification: one embed coloured by event, with the fields inline.
+/// `ping` is only sent when the run is done or needs someone.
+pub fn discord_payload(
+    title: &str,
+    event: Event,
+    message: &str,
+    fields: &[(&str, String)],
+    ping: Option<&str>,
+) -> Value {
+    let color = match event {
+        Event::Progress => 0x3498db,
+        Event::Completed => 0x2ecc71,
+        Event::Error => 0xe74c3c,
+        Event::AttentionNeeded => 0xe67e22,
+        Event::Report => 0x95a5a6,
+    };
+    // Discord rejects embeds over its limits: 25 fields, 1024 characters per value
+    let fields: Vec<Value> = fields
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .take(25)
+        .map(|(name, value)| {
+            json!({
+                "name": name,
+                "value": value.chars().take(1024).collect::<String>(),
+                "inline": value.chars().count() <= 40,
+            })
+        })
+        .collect();
+    let mut payload = json!({
+        "embeds": [{
+            "title": title.chars().take(256).collect::<String>(),
+            "description": message.chars().take(4096).collect::<String>(),
+            "color": color,
+            "fields": fields,
+        }],
+    });
+    if let Some(ping) = ping
+        && matches!(event, Event::Completed | Event::AttentionNeeded)
+    {
+        payload["content"] = json!(ping);
+    }
+    payload
+}
+
+/// Escape the characters Slack's mrkdwn treats as markup
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// POST a JSON body to a webhook with curl
+fn post_json(url: &str, body: &Value) {
+    let Ok(mut child) = Command::new("curl")
+        .args([
+            "-sS",
+            "--max-time",
+            WEBHOOK_TIMEOUT_SECS,
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        tracing::warn!("Failed to spawn curl for a webhook notification");
+        return;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(body.to_string().as_bytes());
+    }
+    match child.wait() {
+        Ok(status) if !status.success() => {
+            tracing::warn!(%status, "Webhook notification failed");
+        }
+        _ => {}
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn send_desktop(title: &str, message: &str) {
+    let script = format!(
+        "display notification {:?} with title {:?}",
+        message.replace('"', "'"),
+        title.replace('"', "'")
+    );
+    let _ = Command::new("osascript")
+        .args(["-e", &script])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(not(target_os = "macos"))]
+fn send_desktop(title: &str, message: &str) {
+    let _ = Command::new("notify-send")
+        .args([title, message])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_filter_events() {
+        assert!(Level::All.wants(Event::Progress));
+        assert!(Level::ErrorsOnly.wants(Event::Error));
+        assert!(!Level::ErrorsOnly.wants(Event::AttentionNeeded));
+        assert!(Level::CompletionOnly.wants(Event::Completed));
+        assert!(!Level::CompletionOnly.wants(Event::Error));
+        assert!(Level::AttentionNeeded.wants(Event::Error));
+        assert!(Level::AttentionNeeded.wants(Event::AttentionNeeded));
+        assert!(!Level::AttentionNeeded.wants(Event::Progress));
+        assert!(!Level::Off.wants(Event::Completed));
+        assert!(Level::CompletionOnly.wants(Event::Report));
+        assert!(!Level::ErrorsOnly.wants(Event::Report));
+        assert_eq!("failures".parse::<Level>().unwrap(), Level::AttentionNeeded);
+    }
+
+    #[test]
+    fn slack_payload_lays_out_fields() {
+        let payload = slack_payload(
+            "ralph: night",
+            "Loop #3: task 2 completed",
+            &[
+                ("Task", "#2 Add <login>".to_string()),
+                ("Cost", "$0.42".to_string()),
+            ],
+        );
+        assert_eq!(payload["text"], "ralph: night: Loop #3: task 2 completed");
+        assert_eq!(
+            payload["blocks"][0]["text"]["text"],
+            "*ralph: night*\nLoop #3: task 2 completed"
+        );
+        let fields = payload["blocks"][1]["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0]["text"], "*Task*\n#2 Add &lt;login&gt;");
+        assert!(
+            slack_payload("ralph", "done", &[])["blocks"]
+                .as_array()
+                .unwrap()
+                .len()
+                == 1
+        );
+    }
+
+    #[test]
+    fn preference_parses_channel_and_level() {
+        assert_eq!(
+            "desktop=errors-only".parse::<Preference>().unwrap(),
+            Preference {
+                channel: Channel::Desktop,
+                level: Level::ErrorsOnly
+            }
+        );
+        assert_eq!("Desktop".parse::<Preference>().unwrap().level, Level::All);
+        assert!(
+            "pager=all"
+                .parse::<Preference>()
+                .unwrap_err()
+                .contains("unknown notification channel")
+        );
+        assert!(
+            "desktop=loud"
+                .parse::<Preference>()
+                .unwrap_err()
+                .contains("unknown notification level")
+        );
+    }
+
+    #[test]
+    fn discord_payload_pings_only_when_needed() {
+        let fields = [
+            ("Status", "blocked".to_string()),
+            ("Summary", "x".repeat(2000)),
+        ];
+        let payload = discord_payload(
+            "ralph",
+            Event::AttentionNeeded,
+            "Loop #2: task 1 blocked",
+            &fields,
+            Some("@here"),
+        );
+        assert_eq!(payload["content"], "@here");
+        let embed = &payload["embeds"][0];
+        assert_eq!(embed["color"], 0xe67e22);
+        assert_eq!(embed["fields"][0]["inline"], true);
+        assert_eq!(embed["fields"][1]["inline"], false);
+        assert_eq!(embed["fields"][1]["value"].as_str().unwrap().len(), 1024);
+
+        let progress = discord_payload("ralph", Event::Progress, "Loop #1", &[], Some("@here"));
+        assert!(progress.get("content").is_none());
+    }
+
+    #[test]
+    fn later_preferences_override_earlier_ones() {
+        let notifier = Notifier::new(&[
+            "desktop=all".parse().unwrap(),
+            "desktop=completion-only".parse().unwrap(),
+        ]);
+        assert_eq!(
+            notifier.channels_for(Event::Completed),
+            vec![Channel::Desktop]
+        );
+        assert!(notifier.channels_for(Event::Progress).is_empty());
+        assert!(Notifier::default().channels_for(Event::Error).is_empty());
+    }
+}
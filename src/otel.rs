@@ -0,0 +1,62 @@
+//! OpenTelemetry trace export (the `otel` feature).
+//!
+//! With `OTEL_EXPORTER_OTLP_ENDPOINT` (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) set, the
+//! `tracing` spans ralph opens - one per build loop, with the Claude invocation, gate runs
+//! and git commands inside it - are sent to that collector over OTLP/HTTP. The other
+//! standard `OTEL_*` variables (headers, service name, resource attributes) apply as usual.
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+use std::env;
+use std::sync::OnceLock;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Variables that name a collector; without one of them nothing is exported
+const ENDPOINT_VARS: [&str; 2] = [
+    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+];
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// A layer exporting spans to the configured collector, or None if there isn't one
+pub fn layer<S>() -> Option<OpenTelemetryLayer<S, Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if !ENDPOINT_VARS.iter().any(|var| env::var_os(var).is_some()) {
+        return None;
+    }
+    let exporter = match SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Warning: not exporting traces: {}", e);
+            return None;
+        }
+    };
+    let mut resource = Resource::builder();
+    if env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name("ralph");
+    }
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build();
+    let tracer = provider.tracer("ralph");
+    let _ = PROVIDER.set(provider);
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Send any spans still queued. Ralph exits with `process::exit`, which skips destructors,
+/// so this has to be called on the way out.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        tracing::warn!(error = %e, "Failed to flush traces");
+    }
+}
@@ -1,7 +1,7 @@
 use ratatui::{
     Frame,
     layout::{Constraint, Layout, Margin, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
     widgets::{
         Block, BorderType, Borders, List, ListItem, ListState, Padding, Paragraph, Scrollbar,
@@ -9,8 +9,19 @@ use ratatui::{
     },
 };
 
-use super::phases::PlanPhase;
-use super::protocol::{Answer, PlanResponse, Question};
+use std::ops::Range;
+
+use crate::app::format_tokens;
+use crate::help;
+use crate::markdown;
+use crate::plan::review::{PrdReview, ReviewInput};
+use crate::theme::Theme;
+use ralph_core::claude::estimate_tokens;
+use ralph_core::plan::defaults::{self, AnswerDefaults};
+use ralph_core::plan::phases::PlanPhase;
+use ralph_core::plan::prompts::build_continuation_prompt;
+use ralph_core::plan::protocol::{Answer, PlanResponse, Question};
+use ralph_core::plan::session::QuestionRound;
 
 /// Input mode for the TUI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -50,9 +61,15 @@ pub struct PlanApp {
     /// The user's idea/description input
     pub idea_input: String,
 
-    /// Cursor position for idea input
+    /// Cursor position for idea input, as a byte offset into `idea_input`
     pub idea_cursor: usize,
 
+    /// First wrapped line of the idea shown in its box
+    pub idea_scroll: usize,
+
+    /// Width the idea is wrapped to, from the last draw
+    pub idea_width: usize,
+
     /// Questions to display (when in asking phase)
     pub questions: Vec<Question>,
 
@@ -97,6 +114,34 @@ pub struct PlanApp {
 
     /// Scrollbar state for log viewing
     pub log_scroll_state: ScrollbarState,
+
+    /// Status of the speculative background turn, if one is active this round
+    pub speculation_label: Option<&'static str>,
+
+    /// Cumulative cost of this plan session (USD)
+    pub session_cost_usd: f64,
+
+    /// Number of turns that contributed to `session_cost_usd`
+    pub priced_turns: u32,
+
+    /// Session cost levels (USD) that trigger a warning once crossed
+    pub cost_thresholds: Vec<f64>,
+
+    /// Colours from `[theme]` in `.ralph.toml`
+    pub theme: Theme,
+
+    /// The help overlay listing every key is open (key `?`)
+    pub show_help: bool,
+
+    /// Answers from `[plan.defaults]` in `.ralph.toml`, pre-selected when their question
+    /// comes up
+    pub answer_defaults: AnswerDefaults,
+
+    /// The generated PRD, while it is being reviewed before it is written
+    pub review: Option<PrdReview>,
+
+    /// What Claude has done so far this turn: files read, searches, remarks
+    pub feed: Vec<String>,
 }
 
 impl PlanApp {
@@ -112,6 +157,8 @@ impl PlanApp {
             submitted_total: 0,
             idea_input: String::new(),
             idea_cursor: 0,
+            idea_scroll: 0,
+            idea_width: 80,
             questions: Vec::new(),
             current_question: 0,
             selected_option: None,
@@ -127,9 +174,52 @@ impl PlanApp {
             current_log_index: 0,
             log_scroll_offset: 0,
             log_scroll_state: ScrollbarState::default(),
+            speculation_label: None,
+            session_cost_usd: 0.0,
+            priced_turns: 0,
+            cost_thresholds: Vec::new(),
+            theme: Theme::default(),
+            show_help: false,
+            answer_defaults: AnswerDefaults::new(),
+            review: None,
+            feed: Vec::new(),
         }
     }
 
+    /// Add a finished turn's cost to the session total.
+    ///
+    /// Returns the threshold newly crossed by this turn, if any.
+    pub fn record_turn_cost(&mut self, cost_usd: f64) -> Option<f64> {
+        let before = self.crossed_threshold();
+        self.session_cost_usd += cost_usd;
+        self.priced_turns += 1;
+        let after = self.crossed_threshold();
+        if after != before { after } else { None }
+    }
+
+    /// Highest configured threshold the session cost has reached
+    pub fn crossed_threshold(&self) -> Option<f64> {
+        self.cost_thresholds
+            .iter()
+            .copied()
+            .filter(|t| self.session_cost_usd >= *t)
+            .reduce(f64::max)
+    }
+
+    /// Average cost of a turn so far, used to estimate the next one
+    pub fn avg_turn_cost(&self) -> Option<f64> {
+        (self.priced_turns > 0).then(|| self.session_cost_usd / self.priced_turns as f64)
+    }
+
+    /// Estimated prompt tokens and cost of submitting the current answers.
+    ///
+    /// The cost is the average of previous turns, since a resumed turn re-reads the
+    /// whole conversation and the answers themselves are a small fraction of it.
+    pub fn next_turn_preview(&self) -> (u64, Option<f64>) {
+        let tokens = estimate_tokens(&build_continuation_prompt(&self.answers));
+        (tokens, self.avg_turn_cost())
+    }
+
     /// Update TUI state from a Claude response
     pub fn update_from_response(&mut self, response: &PlanResponse) {
         self.phase = response.phase;
@@ -154,10 +244,74 @@ impl PlanApp {
     pub fn set_questions(&mut self, questions: Vec<Question>) {
         self.questions = questions;
         self.current_question = 0;
+        self.reset_question_input();
+    }
+
+    /// Reopen a round of questions saved in the session, on the question the user was on
+    pub fn restore_round(&mut self, round: &QuestionRound) {
+        self.questions = round.questions.clone();
+        self.answers = round.answers.clone();
+        self.current_question = round.current.min(self.questions.len().saturating_sub(1));
+        self.reset_question_input();
+    }
+
+    /// Fill in the question now shown with its answer so far, so a revisited question shows
+    /// what was chosen, or else with its configured default
+    fn reset_question_input(&mut self) {
+        let answer = self.current_question().and_then(|q| {
+            self.answers
+                .iter()
+                .find(|a| a.question_id == q.id)
+                .map(|a| a.value.clone())
+                .or_else(|| defaults::answer_for(&self.answer_defaults, q))
+        });
+        self.show_answer(answer);
+    }
+
+    /// Select the option `answer` names on the question shown, or type it in if it names
+    /// none; with no answer, clear both
+    fn show_answer(&mut self, answer: Option<String>) {
+        let option = answer.as_ref().and_then(|answer| {
+            self.current_question()?
+                .options
+                .as_ref()?
+                .iter()
+                .position(|o| &o.key == answer)
+        });
         self.selected_option = None;
-        self.option_list_state.select(Some(0));
-        self.freeform_input.clear();
-        self.cursor_position = 0;
+        self.option_list_state.select(Some(option.unwrap_or(0)));
+        self.freeform_input = match option {
+            Some(_) => String::new(),
+            None => answer.unwrap_or_default(),
+        };
+        self.cursor_position = self.freeform_input.len();
+    }
+
+    /// Answer every question a configured default covers without asking, then show the
+    /// first one still unanswered. Returns how many were answered.
+    pub fn answer_from_defaults(&mut self) -> usize {
+        let mut answered = 0;
+        for question in &self.questions {
+            if self.answers.iter().any(|a| a.question_id == question.id) {
+                continue;
+            }
+            if let Some(value) = defaults::answer_for(&self.answer_defaults, question) {
+                self.answers.push(Answer {
+                    question_id: question.id.clone(),
+                    value,
+                });
+                answered += 1;
+            }
+        }
+        if let Some(index) = self
+            .questions
+            .iter()
+            .position(|q| !self.answers.iter().any(|a| a.question_id == q.id))
+        {
+            self.current_question = index;
+            self.reset_question_input();
+        }
+        answered
     }
 
     /// Get the current question being displayed
@@ -169,10 +323,7 @@ impl PlanApp {
     pub fn next_question(&mut self) {
         if self.current_question + 1 < self.questions.len() {
             self.current_question += 1;
-            self.selected_option = None;
-            self.option_list_state.select(Some(0));
-            self.freeform_input.clear();
-            self.cursor_position = 0;
+            self.reset_question_input();
         }
     }
 
@@ -180,10 +331,7 @@ impl PlanApp {
     pub fn prev_question(&mut self) {
         if self.current_question > 0 {
             self.current_question -= 1;
-            self.selected_option = None;
-            self.option_list_state.select(Some(0));
-            self.freeform_input.clear();
-            self.cursor_position = 0;
+            self.reset_question_input();
         }
     }
 
@@ -224,19 +372,36 @@ impl PlanApp {
             };
 
             if !value.is_empty() {
-                // Replace existing answer for this question (don't add duplicates)
-                if let Some(existing) = self.answers.iter_mut().find(|a| a.question_id == q.id) {
-                    existing.value = value;
-                } else {
-                    self.answers.push(Answer {
-                        question_id: q.id.clone(),
-                        value,
-                    });
-                }
+                // Replace existing answer for this question (don't add duplicates), moving it
+                // last so undo takes back the newest answer
+                self.answers.retain(|a| a.question_id != q.id);
+                self.answers.push(Answer {
+                    question_id: q.id.clone(),
+                    value,
+                });
             }
         }
     }
 
+    /// Take back the most recent answer (key `u`) and go back to its question, with the
+    /// answer selected or typed in again so it can be changed. False if nothing is answered.
+    pub fn undo_answer(&mut self) -> bool {
+        let Some(answer) = self.answers.pop() else {
+            return false;
+        };
+        let Some(index) = self
+            .questions
+            .iter()
+            .position(|q| q.id == answer.question_id)
+        else {
+            return true;
+        };
+        self.current_question = index;
+        self.input_mode = InputMode::Normal;
+        self.show_answer(Some(answer.value));
+        true
+    }
+
     /// Enter editing mode for freeform input
     pub fn enter_editing(&mut self) {
         self.input_mode = InputMode::Editing;
@@ -261,6 +426,71 @@ impl PlanApp {
         }
     }
 
+    /// Insert a character, or a newline, at the idea cursor
+    pub fn idea_insert(&mut self, c: char) {
+        self.idea_input.insert(self.idea_cursor, c);
+        self.idea_cursor += c.len_utf8();
+    }
+
+    /// Delete the character before the idea cursor
+    pub fn idea_backspace(&mut self) {
+        if let Some(c) = self.idea_input[..self.idea_cursor].chars().next_back() {
+            self.idea_cursor -= c.len_utf8();
+            self.idea_input.remove(self.idea_cursor);
+        }
+    }
+
+    pub fn idea_left(&mut self) {
+        if let Some(c) = self.idea_input[..self.idea_cursor].chars().next_back() {
+            self.idea_cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn idea_right(&mut self) {
+        if let Some(c) = self.idea_input[self.idea_cursor..].chars().next() {
+            self.idea_cursor += c.len_utf8();
+        }
+    }
+
+    /// Move the idea cursor to the wrapped line above or below, keeping its column
+    pub fn idea_up_down(&mut self, down: bool) {
+        let lines = wrap_lines(&self.idea_input, self.idea_width);
+        let (row, column) = cursor_row_column(&self.idea_input, &lines, self.idea_cursor);
+        let target = if down {
+            row + 1
+        } else if row > 0 {
+            row - 1
+        } else {
+            return;
+        };
+        if let Some(line) = lines.get(target) {
+            let end = self.line_end(line);
+            self.idea_cursor = self.idea_input[line.start..end]
+                .char_indices()
+                .nth(column)
+                .map_or(end, |(i, _)| line.start + i);
+        }
+    }
+
+    /// Move the idea cursor to the start or end of its wrapped line
+    pub fn idea_home_end(&mut self, end: bool) {
+        let lines = wrap_lines(&self.idea_input, self.idea_width);
+        let (row, _) = cursor_row_column(&self.idea_input, &lines, self.idea_cursor);
+        let line = &lines[row];
+        self.idea_cursor = if end { self.line_end(line) } else { line.start };
+    }
+
+    /// Last cursor position on a wrapped line of the idea: before the space a soft wrap
+    /// breaks after, since the position after it is the start of the next line
+    fn line_end(&self, line: &Range<usize>) -> usize {
+        let soft_wrapped =
+            line.end < self.idea_input.len() && !self.idea_input[line.end..].starts_with('\n');
+        match self.idea_input[line.clone()].chars().next_back() {
+            Some(c) if soft_wrapped => line.end - c.len_utf8(),
+            _ => line.end,
+        }
+    }
+
     /// Move cursor left
     pub fn move_cursor_left(&mut self) {
         if self.cursor_position > 0 {
@@ -345,6 +575,13 @@ impl PlanApp {
 
     /// Draw the TUI
     pub fn draw(&mut self, frame: &mut Frame) {
+        self.render_screen(frame);
+        if self.show_help {
+            help::render(frame, &self.theme, &self.mode(), &self.help_sections());
+        }
+    }
+
+    fn render_screen(&mut self, frame: &mut Frame) {
         // Show idea input screen if awaiting initial idea
         if self.awaiting_idea {
             self.render_idea_input(frame, frame.area());
@@ -366,14 +603,87 @@ impl PlanApp {
 
         self.render_header(frame, header_area);
 
+        if let Some(review) = &mut self.review {
+            review.render(frame, main_area, &self.theme);
+            self.render_footer(frame, footer_area);
+            return;
+        }
+
         match self.phase {
             PlanPhase::Asking => self.render_questions(frame, main_area),
+            _ if !self.feed.is_empty() => {
+                let [log_area, feed_area] =
+                    Layout::vertical([Constraint::Fill(1), self.feed_height()]).areas(main_area);
+                self.render_status_panel(frame, log_area);
+                self.render_feed(frame, feed_area);
+            }
             _ => self.render_status_panel(frame, main_area),
         }
 
         self.render_footer(frame, footer_area);
     }
 
+    /// What the session is doing, for the help overlay's title
+    fn mode(&self) -> String {
+        if self.awaiting_idea {
+            "Describing the idea".to_string()
+        } else if self.processing {
+            "Waiting for Claude".to_string()
+        } else if self.review.is_some() {
+            "Reviewing the PRD".to_string()
+        } else if self.phase == PlanPhase::Asking && self.input_mode == InputMode::Editing {
+            "Asking, typing an answer".to_string()
+        } else {
+            self.phase.to_string()
+        }
+    }
+
+    /// Every key, for the help overlay
+    fn help_sections(&self) -> Vec<help::Section> {
+        vec![
+            (
+                "Questions",
+                vec![
+                    ("↑/↓", "choose an option"),
+                    ("Enter", "answer and go to the next question"),
+                    ("Tab/Shift+Tab", "next/previous question"),
+                    ("i", "type your own answer"),
+                    ("u", "undo the last answer"),
+                    ("Ctrl+Enter", "submit every answer"),
+                    ("x", "cancel the speculative turn"),
+                    ("q", "quit"),
+                ],
+            ),
+            (
+                "Typing an answer",
+                vec![
+                    ("Enter", "save it and go to the next question"),
+                    ("Esc", "stop typing"),
+                    ("←/→", "move the cursor"),
+                ],
+            ),
+            (
+                "Reviewing the PRD",
+                vec![
+                    ("↑/↓", "choose a task"),
+                    ("Enter/a", "accept and write the PRD"),
+                    ("e", "edit the task's description"),
+                    ("f", "send feedback and regenerate"),
+                    ("q", "quit without writing (resume with --resume)"),
+                ],
+            ),
+            (
+                "While Claude works",
+                vec![
+                    ("↑/↓", "scroll the log"),
+                    ("q", "quit once Claude finishes"),
+                    ("Ctrl+C", "kill Claude and quit"),
+                ],
+            ),
+            ("Help", vec![("?", "show this help")]),
+        ]
+    }
+
     fn render_header(&self, frame: &mut Frame, area: Rect) {
         let phase_indicators: Vec<Span> = [
             PlanPhase::Exploring,
@@ -385,9 +695,9 @@ impl PlanApp {
         .map(|p| {
             let symbol = if *p == self.phase { "●" } else { "○" };
             let color = if *p == self.phase {
-                Color::Green
+                self.theme.success
             } else {
-                Color::DarkGray
+                self.theme.dim
             };
             Span::styled(format!(" {} ", symbol), Style::default().fg(color))
         })
@@ -399,22 +709,22 @@ impl PlanApp {
             let answered = self.submitted_count;
             let total = self.submitted_total;
             vec![
-                Span::styled(" | Submitted: ", Style::default().fg(Color::Gray)),
+                Span::styled(" | Submitted: ", Style::default().fg(self.theme.muted)),
                 Span::styled(
                     format!("{}/{}", answered, total),
-                    Style::default().fg(Color::Green),
+                    Style::default().fg(self.theme.success),
                 ),
             ]
         } else if self.phase == PlanPhase::Asking && !self.questions.is_empty() {
             let answered = self.answered_count();
             let total = self.questions.len();
             let color = if self.all_answered() {
-                Color::Green
+                self.theme.success
             } else {
-                Color::Yellow
+                self.theme.warning
             };
             vec![
-                Span::styled(" | Answered: ", Style::default().fg(Color::Gray)),
+                Span::styled(" | Answered: ", Style::default().fg(self.theme.muted)),
                 Span::styled(
                     format!("{}/{}", answered, total),
                     Style::default().fg(color),
@@ -426,36 +736,70 @@ impl PlanApp {
 
         let mut header_line = vec![
             Span::styled("Ralph Plan", Style::default().add_modifier(Modifier::BOLD)),
-            Span::styled(" | Turn: ", Style::default().fg(Color::Gray)),
+            Span::styled(" | Turn: ", Style::default().fg(self.theme.muted)),
             Span::styled(
                 format!("#{}", self.turn_count),
-                Style::default().fg(Color::Cyan),
+                Style::default().fg(self.theme.accent),
             ),
         ];
         header_line.extend(progress_span);
+        if let Some(label) = self.speculation_label {
+            header_line.push(Span::styled(" | ", Style::default().fg(self.theme.muted)));
+            header_line.push(Span::styled(
+                format!("[SPECULATIVE] {}", label),
+                Style::default().fg(self.theme.highlight),
+            ));
+        }
+
+        let mut phase_line = vec![
+            Span::styled("Phase: ", Style::default().fg(self.theme.muted)),
+            Span::styled(
+                self.phase.to_string(),
+                Style::default()
+                    .fg(self.theme.warning)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(" | Cost: ", Style::default().fg(self.theme.muted)),
+            Span::styled(
+                format!("${:.2}", self.session_cost_usd),
+                Style::default().fg(self.theme.accent),
+            ),
+        ];
+        if !self.processing && self.phase == PlanPhase::Asking && !self.answers.is_empty() {
+            let (tokens, cost) = self.next_turn_preview();
+            let estimate = match cost {
+                Some(c) => format!("~{} tok, ~${:.2}", format_tokens(tokens), c),
+                None => format!("~{} tok", format_tokens(tokens)),
+            };
+            phase_line.push(Span::styled(
+                " | Next: ",
+                Style::default().fg(self.theme.muted),
+            ));
+            phase_line.push(Span::styled(estimate, Style::default().fg(self.theme.text)));
+        }
+        if let Some(threshold) = self.crossed_threshold() {
+            phase_line.push(Span::styled(
+                format!(" | ⚠ over ${:.2}", threshold),
+                Style::default()
+                    .fg(self.theme.error)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
 
         let lines = vec![
             Line::from(header_line),
-            Line::from(vec![
-                Span::styled("Phase: ", Style::default().fg(Color::Gray)),
-                Span::styled(
-                    self.phase.to_string(),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            ]),
+            Line::from(phase_line),
             Line::from(phase_indicators),
         ];
 
         let block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Green))
+            .border_style(Style::default().fg(self.theme.success))
             .title(" Ralph PRD Generator ")
             .title_style(
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(self.theme.success)
                     .add_modifier(Modifier::BOLD),
             )
             .padding(Padding::horizontal(1));
@@ -514,7 +858,7 @@ impl PlanApp {
                 Line::from(vec![
                     Span::styled(
                         format!("[{}] ", q.category.to_uppercase()),
-                        Style::default().fg(Color::Cyan),
+                        Style::default().fg(self.theme.accent),
                     ),
                     Span::styled(
                         format!(
@@ -522,27 +866,27 @@ impl PlanApp {
                             self.current_question + 1,
                             self.questions.len()
                         ),
-                        Style::default().fg(Color::Gray),
+                        Style::default().fg(self.theme.muted),
                     ),
                 ]),
                 Line::from(""),
                 Line::from(Span::styled(
                     &q.text,
                     Style::default()
-                        .fg(Color::White)
+                        .fg(self.theme.text)
                         .add_modifier(Modifier::BOLD),
                 )),
                 Line::from(""),
                 Line::from(Span::styled(
                     q.context.as_deref().unwrap_or(""),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(self.theme.dim),
                 )),
             ];
 
             let question_block = Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Plain)
-                .border_style(Style::default().fg(Color::Blue))
+                .border_style(Style::default().fg(self.theme.border))
                 .padding(Padding::horizontal(1));
 
             let question_widget = Paragraph::new(question_lines)
@@ -568,7 +912,7 @@ impl PlanApp {
                 let options_block = Block::default()
                     .borders(Borders::ALL)
                     .border_type(BorderType::Plain)
-                    .border_style(Style::default().fg(Color::Yellow))
+                    .border_style(Style::default().fg(self.theme.warning))
                     .title(" Options (↑↓ to select, Enter to confirm) ")
                     .padding(Padding::horizontal(1));
 
@@ -576,7 +920,7 @@ impl PlanApp {
                     .block(options_block)
                     .highlight_style(
                         Style::default()
-                            .fg(Color::Yellow)
+                            .fg(self.theme.warning)
                             .add_modifier(Modifier::BOLD),
                     )
                     .highlight_symbol("> ");
@@ -591,33 +935,36 @@ impl PlanApp {
                 let hint_lines = vec![
                     Line::from(""),
                     Line::from(vec![
-                        Span::styled("  ╭", Style::default().fg(Color::Yellow)),
+                        Span::styled("  ╭", Style::default().fg(self.theme.warning)),
                         Span::styled(
                             "───────────────────────────────────",
-                            Style::default().fg(Color::Yellow),
+                            Style::default().fg(self.theme.warning),
                         ),
-                        Span::styled("╮", Style::default().fg(Color::Yellow)),
+                        Span::styled("╮", Style::default().fg(self.theme.warning)),
                     ]),
                     Line::from(vec![
-                        Span::styled("  │  ", Style::default().fg(Color::Yellow)),
-                        Span::styled("PRESS ", Style::default().fg(Color::White)),
+                        Span::styled("  │  ", Style::default().fg(self.theme.warning)),
+                        Span::styled("PRESS ", Style::default().fg(self.theme.text)),
                         Span::styled(
                             " i ",
                             Style::default()
-                                .fg(Color::Black)
-                                .bg(Color::Yellow)
+                                .fg(self.theme.inverse)
+                                .bg(self.theme.warning)
                                 .add_modifier(Modifier::BOLD),
                         ),
-                        Span::styled(" TO TYPE YOUR RESPONSE", Style::default().fg(Color::White)),
-                        Span::styled("   │", Style::default().fg(Color::Yellow)),
+                        Span::styled(
+                            " TO TYPE YOUR RESPONSE",
+                            Style::default().fg(self.theme.text),
+                        ),
+                        Span::styled("   │", Style::default().fg(self.theme.warning)),
                     ]),
                     Line::from(vec![
-                        Span::styled("  ╰", Style::default().fg(Color::Yellow)),
+                        Span::styled("  ╰", Style::default().fg(self.theme.warning)),
                         Span::styled(
                             "───────────────────────────────────",
-                            Style::default().fg(Color::Yellow),
+                            Style::default().fg(self.theme.warning),
                         ),
-                        Span::styled("╯", Style::default().fg(Color::Yellow)),
+                        Span::styled("╯", Style::default().fg(self.theme.warning)),
                     ]),
                 ];
 
@@ -634,24 +981,24 @@ impl PlanApp {
                 // Make it MORE prominent when freeform is available
                 let (border_style, title_style, bg_hint) = if is_editing {
                     (
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(self.theme.warning),
                         Style::default()
-                            .fg(Color::Yellow)
+                            .fg(self.theme.warning)
                             .add_modifier(Modifier::BOLD),
                         "",
                     )
                 } else if has_options {
                     // Options exist but freeform allowed - highlight the input
                     (
-                        Style::default().fg(Color::Cyan),
-                        Style::default().fg(Color::Cyan),
+                        Style::default().fg(self.theme.accent),
+                        Style::default().fg(self.theme.accent),
                         " ← press 'i' ",
                     )
                 } else {
                     // No options - freeform is the only way
                     (
-                        Style::default().fg(Color::Yellow),
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(self.theme.warning),
+                        Style::default().fg(self.theme.warning),
                         "",
                     )
                 };
@@ -679,10 +1026,10 @@ impl PlanApp {
                 let display_text = if self.freeform_input.is_empty() && !is_editing {
                     Span::styled(
                         "Press 'i' to start typing...",
-                        Style::default().fg(Color::DarkGray),
+                        Style::default().fg(self.theme.dim),
                     )
                 } else {
-                    Span::styled(&self.freeform_input, Style::default().fg(Color::White))
+                    Span::styled(&self.freeform_input, Style::default().fg(self.theme.text))
                 };
 
                 let input_widget = Paragraph::new(Line::from(display_text)).block(input_block);
@@ -700,46 +1047,50 @@ impl PlanApp {
         }
     }
 
-    fn render_status_panel(&mut self, frame: &mut Frame, area: Rect) {
-        // Compute content height without borrowing self
-        let content_height = self
-            .response_logs
-            .get(self.current_log_index)
-            .map(|s| if s.is_empty() { 1 } else { s.lines().count() })
-            .unwrap_or(1);
-        let visible_height = area.height.saturating_sub(2) as usize;
-
-        self.log_scroll_state = ScrollbarState::default()
-            .content_length(content_height)
-            .viewport_content_length(visible_height)
-            .position(self.log_scroll_offset);
-
-        // Now we can borrow current_log for building lines
+    /// The current log as Markdown, or the status while nothing has been logged
+    fn render_log(&self) -> Vec<Line<'static>> {
         let current = self.current_log();
-        let lines: Vec<Line> = if current.is_empty() {
-            vec![
+        if current.is_empty() {
+            return vec![
                 Line::from(""),
                 Line::from(Span::styled(
                     self.status.clone(),
-                    Style::default().fg(Color::Yellow),
+                    Style::default().fg(self.theme.warning),
                 )),
                 Line::from(""),
                 Line::from(Span::styled(
                     "Waiting for Claude...",
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(self.theme.dim),
                 )),
-            ]
-        } else {
-            current
-                .lines()
-                .map(|line| {
-                    Line::from(Span::styled(
-                        line.to_string(),
-                        Style::default().fg(Color::White),
-                    ))
-                })
-                .collect()
-        };
+            ];
+        }
+        let mut lines = markdown::render(current, &self.theme);
+        for line in &mut lines {
+            // Coverage lines on the review screen: covered green, missing red
+            let text = line.spans.first().map_or("", |s| s.content.as_ref());
+            let color = if text.starts_with("✓ ") {
+                self.theme.success
+            } else if text.starts_with("✗ ") {
+                self.theme.error
+            } else {
+                continue;
+            };
+            for span in &mut line.spans {
+                span.style = span.style.fg(color);
+            }
+        }
+        lines
+    }
+
+    fn render_status_panel(&mut self, frame: &mut Frame, area: Rect) {
+        let lines = self.render_log();
+        let content_height = lines.len();
+        let visible_height = area.height.saturating_sub(2) as usize;
+
+        self.log_scroll_state = ScrollbarState::default()
+            .content_length(content_height)
+            .viewport_content_length(visible_height)
+            .position(self.log_scroll_offset);
 
         let title = match self.phase {
             PlanPhase::Exploring => " Exploring Codebase ",
@@ -751,11 +1102,11 @@ impl PlanApp {
         let block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Double)
-            .border_style(Style::default().fg(Color::Blue))
+            .border_style(Style::default().fg(self.theme.border))
             .title(title)
             .title_style(
                 Style::default()
-                    .fg(Color::Blue)
+                    .fg(self.theme.border)
                     .add_modifier(Modifier::BOLD),
             )
             .padding(Padding::horizontal(1));
@@ -784,6 +1135,42 @@ impl PlanApp {
         }
     }
 
+    /// Rows for the feed under the log: its lines, up to 10, and the borders
+    fn feed_height(&self) -> Constraint {
+        Constraint::Length(self.feed.len().min(10) as u16 + 2)
+    }
+
+    /// The turn's live feed, newest steps at the bottom
+    fn render_feed(&self, frame: &mut Frame, area: Rect) {
+        let visible = area.height.saturating_sub(2) as usize;
+        let start = self.feed.len().saturating_sub(visible);
+        let last = self.feed.len().saturating_sub(1);
+        let lines: Vec<Line> = self.feed[start..]
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let color = if start + i == last {
+                    self.theme.text
+                } else {
+                    self.theme.muted
+                };
+                Line::from(vec![
+                    Span::styled("› ", Style::default().fg(self.theme.dim)),
+                    Span::styled(step.as_str(), Style::default().fg(color)),
+                ])
+            })
+            .collect();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(self.theme.dim))
+            .title(format!(" Claude: {} step(s) ", self.feed.len()))
+            .title_style(Style::default().fg(self.theme.muted))
+            .padding(Padding::horizontal(1));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
     fn render_processing(&self, frame: &mut Frame, area: Rect) {
         let [header_area, main_area, footer_area] = Layout::vertical([
             Constraint::Length(5),
@@ -806,13 +1193,13 @@ impl PlanApp {
                 Span::styled(
                     format!("         {} ", spinner),
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(self.theme.warning)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
                     &self.processing_message,
                     Style::default()
-                        .fg(Color::White)
+                        .fg(self.theme.text)
                         .add_modifier(Modifier::BOLD),
                 ),
             ]),
@@ -822,7 +1209,7 @@ impl PlanApp {
                     "         Submitted {}/{} answers",
                     self.submitted_count, self.submitted_total
                 ),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(self.theme.muted),
             )),
             Line::from(""),
         ];
@@ -830,11 +1217,11 @@ impl PlanApp {
         let block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Double)
-            .border_style(Style::default().fg(Color::Yellow))
+            .border_style(Style::default().fg(self.theme.warning))
             .title(" Processing ")
             .title_style(
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(self.theme.warning)
                     .add_modifier(Modifier::BOLD),
             )
             .padding(Padding::horizontal(1));
@@ -843,24 +1230,56 @@ impl PlanApp {
             .block(block)
             .alignment(ratatui::layout::Alignment::Left);
 
-        frame.render_widget(paragraph, main_area);
+        if self.feed.is_empty() {
+            frame.render_widget(paragraph, main_area);
+        } else {
+            let [status_area, feed_area] =
+                Layout::vertical([Constraint::Length(8), Constraint::Fill(1)]).areas(main_area);
+            frame.render_widget(paragraph, status_area);
+            self.render_feed(frame, feed_area);
+        }
 
         // Processing footer
         let footer_spans = vec![
-            Span::styled(" ralph plan ", Style::default().fg(Color::Cyan)),
-            Span::styled("| ", Style::default().fg(Color::DarkGray)),
-            Span::styled("<Ctrl+C>", Style::default().fg(Color::Green)),
-            Span::styled(" cancel ", Style::default().fg(Color::Gray)),
+            Span::styled(" ralph plan ", Style::default().fg(self.theme.accent)),
+            Span::styled("| ", Style::default().fg(self.theme.dim)),
+            Span::styled("<Ctrl+C>", Style::default().fg(self.theme.success)),
+            Span::styled(" cancel ", Style::default().fg(self.theme.muted)),
         ];
 
         let footer =
-            Paragraph::new(Line::from(footer_spans)).style(Style::default().bg(Color::DarkGray));
+            Paragraph::new(Line::from(footer_spans)).style(Style::default().bg(self.theme.bar));
         frame.render_widget(footer, footer_area);
     }
 
     fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        let keybinds = match self.phase {
-            PlanPhase::Asking => {
+        let review_input = self.review.as_ref().map(|r| &r.input);
+        if let Some(Some(ReviewInput::Feedback(text))) = review_input {
+            let prompt = Line::from(vec![
+                Span::styled(" Feedback: ", Style::default().fg(self.theme.accent)),
+                Span::styled(text.clone(), Style::default().fg(self.theme.text)),
+                Span::styled("█", Style::default().fg(self.theme.muted)),
+                Span::styled(
+                    " (enter=send, esc=cancel)",
+                    Style::default().fg(self.theme.dim),
+                ),
+            ]);
+            let footer = Paragraph::new(prompt).style(Style::default().bg(self.theme.bar));
+            frame.render_widget(footer, area);
+            return;
+        }
+
+        let keybinds = match (review_input, self.phase) {
+            (Some(Some(_)), _) => vec![("<Enter>", "save"), ("<Esc>", "cancel")],
+            (Some(None), _) => vec![
+                ("<Enter>", "ACCEPT"),
+                ("<↑↓>", "tasks"),
+                ("<e>", "edit"),
+                ("<f>", "feedback"),
+                ("<q>", "quit"),
+                ("<?>", "help"),
+            ],
+            (None, PlanPhase::Asking) => {
                 if self.input_mode == InputMode::Editing {
                     vec![
                         ("<Esc>", "finish typing"),
@@ -873,6 +1292,7 @@ impl PlanApp {
                         ("<C-Enter>", "SUBMIT ALL"),
                         ("<↑↓>", "options"),
                         ("<Tab>", "review"),
+                        ("<u>", "undo"),
                         ("<q>", "quit"),
                     ]
                 } else {
@@ -882,30 +1302,31 @@ impl PlanApp {
                         ("<i>", "type"),
                         ("<Enter>", "answer"),
                         ("<q>", "quit"),
+                        ("<?>", "help"),
                     ]
                 }
             }
-            _ => vec![("<q>", "quit"), ("<↑↓>", "scroll")],
+            _ => vec![("<q>", "quit"), ("<↑↓>", "scroll"), ("<?>", "help")],
         };
 
         let mut spans = vec![
-            Span::styled(" ralph plan ", Style::default().fg(Color::Cyan)),
-            Span::styled("| ", Style::default().fg(Color::DarkGray)),
+            Span::styled(" ralph plan ", Style::default().fg(self.theme.accent)),
+            Span::styled("| ", Style::default().fg(self.theme.dim)),
         ];
 
         for (key, action) in keybinds {
-            spans.push(Span::styled(key, Style::default().fg(Color::Green)));
+            spans.push(Span::styled(key, Style::default().fg(self.theme.success)));
             spans.push(Span::styled(
                 format!(" {} ", action),
-                Style::default().fg(Color::Gray),
+                Style::default().fg(self.theme.muted),
             ));
         }
 
-        let footer = Paragraph::new(Line::from(spans)).style(Style::default().bg(Color::DarkGray));
+        let footer = Paragraph::new(Line::from(spans)).style(Style::default().bg(self.theme.bar));
         frame.render_widget(footer, area);
     }
 
-    fn render_idea_input(&self, frame: &mut Frame, area: Rect) {
+    fn render_idea_input(&mut self, frame: &mut Frame, area: Rect) {
         let [header_area, main_area, footer_area] = Layout::vertical([
             Constraint::Length(3),
             Constraint::Fill(1),
@@ -917,17 +1338,17 @@ impl PlanApp {
         let header_block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Green))
+            .border_style(Style::default().fg(self.theme.success))
             .title(" Ralph Plan ")
             .title_style(
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(self.theme.success)
                     .add_modifier(Modifier::BOLD),
             );
 
         let header = Paragraph::new(Line::from(vec![Span::styled(
             "Interactive PRD Generator",
-            Style::default().fg(Color::Cyan),
+            Style::default().fg(self.theme.accent),
         )]))
         .block(header_block)
         .alignment(ratatui::layout::Alignment::Center);
@@ -948,62 +1369,92 @@ impl PlanApp {
             Line::from(Span::styled(
                 "What do you want to build?",
                 Style::default()
-                    .fg(Color::White)
+                    .fg(self.theme.text)
                     .add_modifier(Modifier::BOLD),
             )),
             Line::from(""),
             Line::from(Span::styled(
                 "Describe your idea below. Claude will explore the codebase and generate a PRD.",
-                Style::default().fg(Color::DarkGray),
+                Style::default().fg(self.theme.dim),
             )),
         ];
 
         let prompt = Paragraph::new(prompt_lines).block(prompt_block);
         frame.render_widget(prompt, prompt_area);
 
-        // Input box
+        // Input box: wrapped, scrolled to keep the cursor in view
         let input_block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Yellow))
+            .border_style(Style::default().fg(self.theme.warning))
             .title(" Your Idea ")
-            .title_style(Style::default().fg(Color::Yellow))
+            .title_style(Style::default().fg(self.theme.warning))
             .padding(Padding::horizontal(1));
+        let inner = input_block.inner(input_area);
+        frame.render_widget(input_block, input_area);
+
+        self.idea_width = inner.width.max(1) as usize;
+        let lines = wrap_lines(&self.idea_input, self.idea_width);
+        let (row, column) = cursor_row_column(&self.idea_input, &lines, self.idea_cursor);
+        let visible = inner.height.max(1) as usize;
+        if row < self.idea_scroll {
+            self.idea_scroll = row;
+        } else if row >= self.idea_scroll + visible {
+            self.idea_scroll = row + 1 - visible;
+        }
 
-        let input_text = if self.idea_input.is_empty() {
-            Span::styled("Start typing...", Style::default().fg(Color::DarkGray))
+        let input_lines: Vec<Line> = if self.idea_input.is_empty() {
+            vec![Line::from(Span::styled(
+                "Start typing...",
+                Style::default().fg(self.theme.dim),
+            ))]
         } else {
-            Span::styled(&self.idea_input, Style::default().fg(Color::White))
+            lines
+                .iter()
+                .skip(self.idea_scroll)
+                .take(visible)
+                .map(|line| {
+                    Line::from(Span::styled(
+                        &self.idea_input[line.clone()],
+                        Style::default().fg(self.theme.text),
+                    ))
+                })
+                .collect()
         };
+        frame.render_widget(Paragraph::new(input_lines), inner);
 
-        let input = Paragraph::new(Line::from(input_text))
-            .block(input_block)
-            .wrap(Wrap { trim: false });
-
-        frame.render_widget(input, input_area);
-
-        // Position cursor
-        if !self.idea_input.is_empty() || self.idea_cursor > 0 {
-            frame.set_cursor_position((
-                input_area.x + self.idea_cursor as u16 + 2,
-                input_area.y + 1,
-            ));
-        } else {
-            frame.set_cursor_position((input_area.x + 2, input_area.y + 1));
+        if lines.len() > visible {
+            let mut scroll_state =
+                ScrollbarState::new(lines.len().saturating_sub(visible)).position(self.idea_scroll);
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                input_area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 0,
+                }),
+                &mut scroll_state,
+            );
         }
 
+        frame.set_cursor_position((
+            inner.x + column.min(self.idea_width - 1) as u16,
+            inner.y + (row - self.idea_scroll) as u16,
+        ));
+
         // Footer
         let footer_spans = vec![
-            Span::styled(" ralph plan ", Style::default().fg(Color::Cyan)),
-            Span::styled("| ", Style::default().fg(Color::DarkGray)),
-            Span::styled("<Enter>", Style::default().fg(Color::Green)),
-            Span::styled(" Start ", Style::default().fg(Color::Gray)),
-            Span::styled("<Esc>", Style::default().fg(Color::Green)),
-            Span::styled(" Quit ", Style::default().fg(Color::Gray)),
+            Span::styled(" ralph plan ", Style::default().fg(self.theme.accent)),
+            Span::styled("| ", Style::default().fg(self.theme.dim)),
+            Span::styled("<Enter>", Style::default().fg(self.theme.success)),
+            Span::styled(" Start ", Style::default().fg(self.theme.muted)),
+            Span::styled("<S-Enter>", Style::default().fg(self.theme.success)),
+            Span::styled(" New line ", Style::default().fg(self.theme.muted)),
+            Span::styled("<Esc>", Style::default().fg(self.theme.success)),
+            Span::styled(" Quit ", Style::default().fg(self.theme.muted)),
         ];
 
         let footer =
-            Paragraph::new(Line::from(footer_spans)).style(Style::default().bg(Color::DarkGray));
+            Paragraph::new(Line::from(footer_spans)).style(Style::default().bg(self.theme.bar));
         frame.render_widget(footer, footer_area);
     }
 
@@ -1014,7 +1465,7 @@ impl PlanApp {
 
     /// Scroll down in log view
     pub fn scroll_down(&mut self, amount: usize) {
-        let content_height = self.current_log().lines().count();
+        let content_height = self.render_log().len();
         self.log_scroll_offset = self
             .log_scroll_offset
             .saturating_add(amount)
@@ -1028,10 +1479,45 @@ impl Default for PlanApp {
     }
 }
 
+/// Byte ranges of `text` once wrapped to `width` columns: lines break at newlines, and
+/// otherwise after the last space that fits, or mid-word if there is none. A line broken
+/// at a space keeps the space.
+fn wrap_lines(text: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for paragraph in text.split('\n') {
+        let mut start = 0;
+        loop {
+            let rest = &paragraph[start..];
+            let Some((cut, _)) = rest.char_indices().nth(width) else {
+                lines.push(offset + start..offset + paragraph.len());
+                break;
+            };
+            let cut = rest[..cut].rfind(' ').map_or(cut, |space| space + 1);
+            lines.push(offset + start..offset + start + cut);
+            start += cut;
+        }
+        offset += paragraph.len() + 1;
+    }
+    lines
+}
+
+/// Wrapped line and column of `cursor`. At a soft wrap the cursor is at the start of the
+/// next line; at a newline, at the end of the line it ends.
+fn cursor_row_column(text: &str, lines: &[Range<usize>], cursor: usize) -> (usize, usize) {
+    let row = lines
+        .iter()
+        .rposition(|line| line.start <= cursor)
+        .unwrap_or(0);
+    let column = text[lines[row].start..cursor].chars().count();
+    (row, column)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::plan::protocol::QuestionOption;
+    use ralph_core::plan::protocol::QuestionOption;
 
     fn create_test_question(id: &str, with_options: bool) -> Question {
         Question {
@@ -1059,6 +1545,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn idea_wraps_at_spaces_and_newlines() {
+        let text = "Add a bookmarks page\n\nwith tags";
+        let lines: Vec<&str> = wrap_lines(text, 10).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(lines, vec!["Add a ", "bookmarks ", "page", "", "with tags"]);
+
+        let lines: Vec<&str> = wrap_lines("abcdefgh", 3)
+            .into_iter()
+            .map(|r| &"abcdefgh"[r])
+            .collect();
+        assert_eq!(lines, vec!["abc", "def", "gh"]);
+        assert_eq!(wrap_lines("", 10), vec![0..0]);
+    }
+
+    #[test]
+    fn idea_cursor_moves_across_wrapped_lines() {
+        let mut app = PlanApp::new();
+        app.idea_width = 10;
+        for c in "Add a bookmarks page\nwith tags".chars() {
+            app.idea_insert(c);
+        }
+        // End of "with tags": up lands on "page", up again on "bookmarks "
+        app.idea_up_down(false);
+        assert_eq!(app.idea_cursor, "Add a bookmarks page".len());
+        app.idea_up_down(false);
+        assert_eq!(app.idea_cursor, "Add a book".len());
+        app.idea_home_end(true);
+        assert_eq!(app.idea_cursor, "Add a bookmarks".len());
+        app.idea_home_end(false);
+        assert_eq!(app.idea_cursor, "Add a ".len());
+        app.idea_up_down(false);
+        app.idea_up_down(false);
+        assert_eq!(app.idea_cursor, 0);
+
+        app.idea_input = "né".to_string();
+        app.idea_cursor = app.idea_input.len();
+        app.idea_left();
+        assert_eq!(app.idea_cursor, 1);
+        app.idea_right();
+        app.idea_backspace();
+        assert_eq!(app.idea_input, "n");
+    }
+
     #[test]
     fn new_app_initialization() {
         let app = PlanApp::new();
@@ -1213,6 +1742,107 @@ mod tests {
         assert_eq!(app.answers[0].value, "B");
     }
 
+    #[test]
+    fn undo_answer_returns_to_the_newest_answer() {
+        let mut app = PlanApp::new();
+        app.set_questions(vec![
+            create_test_question("q1", true),
+            create_test_question("q2", false),
+        ]);
+        assert!(!app.undo_answer());
+
+        app.option_list_state.select(Some(1));
+        app.submit_answer();
+        app.next_question();
+        app.freeform_input = "Typed".to_string();
+        app.submit_answer();
+
+        assert!(app.undo_answer());
+        assert_eq!(app.current_question, 1);
+        assert_eq!(app.freeform_input, "Typed");
+        assert_eq!(app.answers.len(), 1);
+
+        assert!(app.undo_answer());
+        assert_eq!(app.current_question, 0);
+        assert_eq!(app.option_list_state.selected(), Some(1));
+        assert!(app.answers.is_empty());
+    }
+
+    #[test]
+    fn feed_shows_the_latest_steps() {
+        let mut app = PlanApp::new();
+        app.feed = (1..=30)
+            .map(|i| format!("Reading src/file{}.rs", i))
+            .collect();
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|f| app.draw(f)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Claude: 30 step(s)"));
+        assert!(screen.contains("Reading src/file30.rs"));
+        assert!(!screen.contains("Reading src/file20.rs"));
+    }
+
+    #[test]
+    fn restore_round_returns_to_the_saved_question() {
+        let mut app = PlanApp::new();
+        let round = QuestionRound {
+            questions: vec![
+                create_test_question("q1", true),
+                create_test_question("q2", false),
+            ],
+            answers: vec![Answer {
+                question_id: "q1".to_string(),
+                value: "A".to_string(),
+            }],
+            current: 1,
+            ..Default::default()
+        };
+        app.restore_round(&round);
+        assert_eq!(app.current_question, 1);
+        assert_eq!(app.answered_count(), 1);
+
+        app.restore_round(&QuestionRound {
+            current: 5,
+            ..round
+        });
+        assert_eq!(app.current_question, 1);
+    }
+
+    #[test]
+    fn configured_defaults_are_preselected_or_answered() {
+        let mut app = PlanApp::new();
+        app.answer_defaults = AnswerDefaults::from([(
+            "scope".to_string(),
+            [("q2", "B"), ("q3", "typed")]
+                .into_iter()
+                .map(|(topic, value)| (topic.to_string(), value.to_string()))
+                .collect(),
+        )]);
+        app.set_questions(vec![
+            create_test_question("q1", true),
+            create_test_question("q2", true),
+            create_test_question("q3", false),
+        ]);
+        assert_eq!(app.option_list_state.selected(), Some(0));
+        app.next_question();
+        assert_eq!(app.option_list_state.selected(), Some(1));
+        app.next_question();
+        assert_eq!(app.freeform_input, "typed");
+
+        assert_eq!(app.answer_from_defaults(), 2);
+        assert_eq!(app.current_question, 0);
+        assert!(!app.all_answered());
+        app.submit_answer();
+        assert!(app.all_answered());
+    }
+
     #[test]
     fn submit_answer_from_freeform() {
         let mut app = PlanApp::new();
@@ -1237,6 +1867,34 @@ mod tests {
         assert_eq!(app.answers[0].value, "Freeform only");
     }
 
+    #[test]
+    fn revisiting_a_question_shows_its_answer() {
+        let mut app = PlanApp::new();
+        app.set_questions(vec![
+            create_test_question("q1", true),
+            create_test_question("q2", true),
+            create_test_question("q3", false),
+        ]);
+        app.next_option();
+        app.submit_answer();
+        app.next_question();
+        app.input_mode = InputMode::Editing;
+        app.freeform_input = "Something else".to_string();
+        app.submit_answer();
+        app.input_mode = InputMode::Normal;
+        app.next_question();
+        assert_eq!(app.freeform_input, "");
+
+        app.prev_question();
+        assert_eq!(app.option_list_state.selected(), Some(0));
+        assert_eq!(app.freeform_input, "Something else");
+        assert_eq!(app.cursor_position, "Something else".len());
+
+        app.prev_question();
+        assert_eq!(app.option_list_state.selected(), Some(1));
+        assert_eq!(app.freeform_input, "");
+    }
+
     #[test]
     fn submit_empty_answer_not_added() {
         let mut app = PlanApp::new();
@@ -1514,6 +2172,43 @@ mod tests {
         assert_eq!(app.spinner_frame, 0); // Should wrap around
     }
 
+    #[test]
+    fn record_turn_cost_reports_new_thresholds() {
+        let mut app = PlanApp::new();
+        app.cost_thresholds = vec![1.0, 5.0];
+
+        assert_eq!(app.record_turn_cost(0.6), None);
+        assert_eq!(app.record_turn_cost(0.6), Some(1.0));
+        assert_eq!(app.record_turn_cost(0.6), None);
+        assert_eq!(app.crossed_threshold(), Some(1.0));
+        assert_eq!(app.record_turn_cost(4.0), Some(5.0));
+        assert_eq!(app.priced_turns, 4);
+    }
+
+    #[test]
+    fn crossed_threshold_none_without_thresholds() {
+        let mut app = PlanApp::new();
+        app.record_turn_cost(100.0);
+        assert_eq!(app.crossed_threshold(), None);
+    }
+
+    #[test]
+    fn next_turn_preview_uses_average_cost() {
+        let mut app = PlanApp::new();
+        app.answers.push(Answer {
+            question_id: "q1".to_string(),
+            value: "React".to_string(),
+        });
+
+        let (tokens, cost) = app.next_turn_preview();
+        assert!(tokens > 0);
+        assert_eq!(cost, None);
+
+        app.record_turn_cost(0.5);
+        app.record_turn_cost(1.5);
+        assert_eq!(app.next_turn_preview().1, Some(1.0));
+    }
+
     #[test]
     fn spinner_char_returns_braille() {
         let mut app = PlanApp::new();
@@ -1,5 +1,2 @@
 pub mod app;
-pub mod phases;
-pub mod prompts;
-pub mod protocol;
-pub mod session;
+pub mod review;
@@ -0,0 +1,301 @@
+//! The PRD review screen shown when a plan session completes: the generated PRD in a
+//! scrollable pane, where it can be accepted, sent back to Claude with feedback, or have
+//! its task descriptions edited before the file is written.
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Padding, Paragraph, Wrap},
+};
+
+use crate::theme::Theme;
+use ralph_core::plan::protocol::FinalPrd;
+
+/// Text being typed on the review screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewInput {
+    /// What Claude should change, sent with the PRD for a new version
+    Feedback(String),
+    /// A new description for the selected task
+    Description(String),
+}
+
+/// How the review ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewOutcome {
+    /// Write the PRD, with any edits
+    Accept,
+    /// Ask Claude for a new PRD with this feedback
+    Regenerate(String),
+    /// Leave without writing; the session can still be resumed
+    Quit,
+}
+
+/// A generated PRD waiting for the user's verdict
+#[derive(Debug)]
+pub struct PrdReview {
+    pub prd: FinalPrd,
+    /// The coverage check, shown above the PRD
+    pub coverage_report: String,
+    /// Index of the selected task
+    pub selected: usize,
+    pub input: Option<ReviewInput>,
+    /// First line of the pane in view
+    pub scroll: usize,
+}
+
+impl PrdReview {
+    pub fn new(prd: FinalPrd, coverage_report: String) -> Self {
+        Self {
+            prd,
+            coverage_report,
+            selected: 0,
+            input: None,
+            scroll: 0,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.prd.tasks.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Start editing the selected task's description, pre-filled with the current one
+    pub fn start_editing(&mut self) {
+        if let Some(task) = self.prd.tasks.get(self.selected) {
+            self.input = Some(ReviewInput::Description(task.description.clone()));
+        }
+    }
+
+    /// Start typing feedback for Claude
+    pub fn start_feedback(&mut self) {
+        self.input = Some(ReviewInput::Feedback(String::new()));
+    }
+
+    /// Add a character to whatever is being typed
+    pub fn push_char(&mut self, c: char) {
+        match &mut self.input {
+            Some(ReviewInput::Feedback(text) | ReviewInput::Description(text)) => text.push(c),
+            None => {}
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        match &mut self.input {
+            Some(ReviewInput::Feedback(text) | ReviewInput::Description(text)) => {
+                text.pop();
+            }
+            None => {}
+        }
+    }
+
+    /// Stop typing. With `save`, a description replaces the selected task's (unless it is
+    /// blank) and feedback is returned for sending.
+    pub fn finish_input(&mut self, save: bool) -> Option<String> {
+        match self.input.take() {
+            Some(ReviewInput::Description(text)) if save => {
+                let text = text.trim();
+                if let Some(task) = self.prd.tasks.get_mut(self.selected)
+                    && !text.is_empty()
+                {
+                    task.description = text.to_string();
+                }
+                None
+            }
+            Some(ReviewInput::Feedback(text)) if save => {
+                let text = text.trim();
+                (!text.is_empty()).then(|| text.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let (lines, selected_line) = self.lines(theme);
+
+        // Keep the selected task in view
+        let visible = area.height.saturating_sub(2) as usize;
+        if selected_line < self.scroll {
+            self.scroll = selected_line;
+        } else if visible > 0 && selected_line >= self.scroll + visible {
+            self.scroll = selected_line + 1 - visible;
+        }
+        self.scroll = self.scroll.min(lines.len().saturating_sub(1));
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .border_style(Style::default().fg(theme.border))
+            .title(format!(" Review PRD: {} task(s) ", self.prd.tasks.len()))
+            .title_style(
+                Style::default()
+                    .fg(theme.border)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .padding(Padding::horizontal(1));
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll as u16, 0));
+        frame.render_widget(paragraph, area);
+    }
+
+    /// The pane's lines, and the index of the selected task's line
+    fn lines(&self, theme: &Theme) -> (Vec<Line<'static>>, usize) {
+        let heading = Style::default()
+            .fg(theme.accent)
+            .add_modifier(Modifier::BOLD);
+        let text = Style::default().fg(theme.text);
+        let dim = Style::default().fg(theme.dim);
+
+        let mut lines = Vec::new();
+        for line in self.coverage_report.lines() {
+            let color = if line.trim_start().starts_with('✓') {
+                theme.success
+            } else if line.trim_start().starts_with('✗') {
+                theme.error
+            } else {
+                theme.text
+            };
+            lines.push(Line::from(Span::styled(
+                line.to_string(),
+                Style::default().fg(color),
+            )));
+        }
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+
+        lines.push(Line::from(Span::styled(
+            self.prd.name.clone(),
+            heading.add_modifier(Modifier::UNDERLINED),
+        )));
+        lines.push(Line::from(vec![
+            Span::styled("Quality gates: ", dim),
+            Span::styled(self.prd.quality_gates.join(", "), text),
+        ]));
+        if !self.prd.definition_of_done.is_empty() {
+            lines.push(Line::from(Span::styled("Definition of done:", dim)));
+            for item in &self.prd.definition_of_done {
+                lines.push(Line::from(Span::styled(format!("  - {}", item), text)));
+            }
+        }
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled("Tasks", heading)));
+
+        let mut selected_line = 0;
+        for (i, task) in self.prd.tasks.iter().enumerate() {
+            let number = Span::styled(format!("{:>2}. ", i + 1), dim);
+            let category = Span::styled(
+                format!("[{}] ", task.category),
+                Style::default().fg(theme.highlight),
+            );
+            let line = if i == self.selected {
+                selected_line = lines.len();
+                match &self.input {
+                    Some(ReviewInput::Description(input)) => Line::from(vec![
+                        number,
+                        category,
+                        Span::styled(input.clone(), text),
+                        Span::styled("█", Style::default().fg(theme.muted)),
+                    ]),
+                    _ => Line::from(vec![
+                        number,
+                        category,
+                        Span::styled(
+                            task.description.clone(),
+                            Style::default()
+                                .fg(theme.inverse)
+                                .bg(theme.warning)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                    ]),
+                }
+            } else {
+                Line::from(vec![
+                    number,
+                    category,
+                    Span::styled(task.description.clone(), text),
+                ])
+            };
+            lines.push(line);
+            for step in &task.steps {
+                lines.push(Line::from(Span::styled(format!("      - {}", step), dim)));
+            }
+        }
+        (lines, selected_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ralph_core::plan::protocol::Task;
+
+    fn review() -> PrdReview {
+        let task = |description: &str| Task {
+            category: "feature".to_string(),
+            description: description.to_string(),
+            steps: vec!["Step".to_string()],
+            passes: false,
+        };
+        PrdReview::new(
+            FinalPrd {
+                schema_version: ralph_core::prd::SCHEMA_VERSION,
+                name: "Bookmarks".to_string(),
+                quality_gates: vec!["cargo test".to_string()],
+                definition_of_done: Vec::new(),
+                tasks: vec![task("Add login"), task("Add logout")],
+                coverage: None,
+            },
+            String::new(),
+        )
+    }
+
+    #[test]
+    fn editing_a_description_replaces_it_unless_cancelled_or_blank() {
+        let mut review = review();
+        review.select_next();
+        review.select_next();
+        assert_eq!(review.selected, 1);
+
+        review.start_editing();
+        review.pop_char();
+        review.push_char('t');
+        review.push_char('s');
+        assert_eq!(review.finish_input(true), None);
+        assert_eq!(review.prd.tasks[1].description, "Add logouts");
+
+        review.start_editing();
+        review.push_char('!');
+        review.finish_input(false);
+        assert_eq!(review.prd.tasks[1].description, "Add logouts");
+
+        review.input = Some(ReviewInput::Description("  ".to_string()));
+        review.finish_input(true);
+        assert_eq!(review.prd.tasks[1].description, "Add logouts");
+    }
+
+    #[test]
+    fn feedback_is_returned_when_sent() {
+        let mut review = review();
+        review.start_feedback();
+        for c in " split task 2 ".chars() {
+            review.push_char(c);
+        }
+        assert_eq!(review.finish_input(true).as_deref(), Some("split task 2"));
+
+        review.start_feedback();
+        assert_eq!(review.finish_input(true), None);
+        review.start_feedback();
+        review.push_char('x');
+        assert_eq!(review.finish_input(false), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! Async waits for the TUI loops.
+//!
+//! A loop waiting on Claude, a gate or the keyboard runs on the current-thread runtime from
+//! `ralph_core::runtime` and sleeps until a key is pressed, a child exits or the spinner is
+//! due a redraw, instead of polling every 100ms. The rest of ralph stays synchronous and
+//! enters the runtime through `block_on`.
+
+use crossterm::event::{Event, EventStream, KeyEvent, KeyEventKind, MouseEvent};
+use futures_util::StreamExt;
+use std::future::pending;
+use std::io;
+use std::time::Duration;
+use tokio::time::{Interval, MissedTickBehavior};
+
+pub use ralph_core::runtime::{block_on, sleep_or_forever};
+
+/// How often a waiting loop redraws, to animate its spinner
+pub const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Ticks at `REDRAW_INTERVAL`, skipping any missed while a redraw was slow
+pub fn redraw_timer() -> Interval {
+    let mut timer = tokio::time::interval(REDRAW_INTERVAL);
+    timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    timer
+}
+
+/// A key press or a mouse event from the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+}
+
+/// Key presses from the terminal, or none at all for a run without one
+pub struct Keys {
+    events: Option<EventStream>,
+}
+
+impl Keys {
+    /// Read the keyboard
+    pub fn new() -> Self {
+        Self {
+            events: Some(EventStream::new()),
+        }
+    }
+
+    /// No keyboard: `next` never resolves
+    pub fn none() -> Self {
+        Self { events: None }
+    }
+
+    /// The next key press. Windows terminals report key releases too, which would make every
+    /// key count twice, so only presses come through. Safe to cancel in `select!`.
+    pub async fn next(&mut self) -> io::Result<KeyEvent> {
+        loop {
+            if let Input::Key(key) = self.next_input().await? {
+                return Ok(key);
+            }
+        }
+    }
+
+    /// The next key press or mouse event; mouse events only arrive while the terminal
+    /// captures the mouse (see `tui::init_run_terminal`). Safe to cancel in `select!`.
+    pub async fn next_input(&mut self) -> io::Result<Input> {
+        if let Some(events) = self.events.as_mut() {
+            while let Some(event) = events.next().await {
+                match event? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        return Ok(Input::Key(key));
+                    }
+                    Event::Mouse(mouse) => return Ok(Input::Mouse(mouse)),
+                    _ => {}
+                }
+            }
+        }
+        pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn keys_without_a_keyboard_never_resolve() {
+        let started = Instant::now();
+        let mut keys = Keys::none();
+        let timed_out = block_on(async {
+            tokio::select! {
+                _ = keys.next() => false,
+                () = sleep_or_forever(Some(Duration::from_millis(50))) => true,
+            }
+        });
+        assert!(timed_out);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,182 @@
+//! Colours for the build and plan TUIs, set under `[theme]` in `.ralph.toml`.
+//!
+//! ```toml
+//! [theme]
+//! preset = "light"   # "dark" (the default) or "light", for light terminal backgrounds
+//! accent = "#005f87" # any slot below: a colour name, "#rrggbb" or a 0-255 palette index
+//! ```
+
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Invalid colour \"{value}\" for theme.{slot}")]
+pub struct ThemeError {
+    pub slot: &'static str,
+    pub value: String,
+}
+
+/// What each part of the TUIs is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Panel borders
+    pub border: Color,
+    /// Titles, headings and values that should stand out
+    pub accent: Color,
+    /// Shortcut keys, passing gates, finished tasks
+    pub success: Color,
+    /// Pending work, prompts and anything needing a look
+    pub warning: Color,
+    /// Failures
+    pub error: Color,
+    /// The PRD name and inline code
+    pub highlight: Color,
+    /// Body text
+    pub text: Color,
+    /// Secondary text and shortcut labels
+    pub muted: Color,
+    /// Hints and things already done
+    pub dim: Color,
+    /// Background of the footer and the empty part of the progress gauge
+    pub bar: Color,
+    /// Background of inline code
+    pub code_bg: Color,
+    /// Text drawn on a `warning` background, like the selected answer
+    pub inverse: Color,
+}
+
+impl Theme {
+    /// The original palette, for dark terminals
+    pub fn dark() -> Self {
+        Self {
+            border: Color::Blue,
+            accent: Color::Cyan,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            highlight: Color::Magenta,
+            text: Color::White,
+            muted: Color::Gray,
+            dim: Color::DarkGray,
+            bar: Color::DarkGray,
+            code_bg: Color::Black,
+            inverse: Color::Black,
+        }
+    }
+
+    /// Darker colours that stay readable on a white or pale background
+    pub fn light() -> Self {
+        Self {
+            border: Color::Blue,
+            accent: Color::Rgb(0x00, 0x5f, 0x87),
+            success: Color::Rgb(0x00, 0x87, 0x00),
+            warning: Color::Rgb(0xaf, 0x5f, 0x00),
+            error: Color::Rgb(0xaf, 0x00, 0x00),
+            highlight: Color::Rgb(0x87, 0x00, 0x87),
+            text: Color::Black,
+            muted: Color::Rgb(0x4e, 0x4e, 0x4e),
+            dim: Color::Rgb(0x80, 0x80, 0x80),
+            bar: Color::Rgb(0xd0, 0xd0, 0xd0),
+            code_bg: Color::Rgb(0xe4, 0xe4, 0xe4),
+            inverse: Color::White,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Built-in themes `[theme] preset` can start from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// `[theme]`: a preset and any colours to change in it
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub preset: Preset,
+    pub border: Option<String>,
+    pub accent: Option<String>,
+    pub success: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+    pub highlight: Option<String>,
+    pub text: Option<String>,
+    pub muted: Option<String>,
+    pub dim: Option<String>,
+    pub bar: Option<String>,
+    pub code_bg: Option<String>,
+    pub inverse: Option<String>,
+}
+
+impl ThemeConfig {
+    /// The preset with the configured colours swapped in
+    pub fn resolve(&self) -> Result<Theme, ThemeError> {
+        let mut theme = match self.preset {
+            Preset::Dark => Theme::dark(),
+            Preset::Light => Theme::light(),
+        };
+        for (slot, value, color) in [
+            ("border", &self.border, &mut theme.border),
+            ("accent", &self.accent, &mut theme.accent),
+            ("success", &self.success, &mut theme.success),
+            ("warning", &self.warning, &mut theme.warning),
+            ("error", &self.error, &mut theme.error),
+            ("highlight", &self.highlight, &mut theme.highlight),
+            ("text", &self.text, &mut theme.text),
+            ("muted", &self.muted, &mut theme.muted),
+            ("dim", &self.dim, &mut theme.dim),
+            ("bar", &self.bar, &mut theme.bar),
+            ("code_bg", &self.code_bg, &mut theme.code_bg),
+            ("inverse", &self.inverse, &mut theme.inverse),
+        ] {
+            if let Some(value) = value {
+                *color = Color::from_str(value).map_err(|_| ThemeError {
+                    slot,
+                    value: value.clone(),
+                })?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_apply_on_top_of_the_preset() {
+        let config: ThemeConfig =
+            toml::from_str("preset = \"light\"\naccent = \"#112233\"\nborder = \"magenta\"\n")
+                .unwrap();
+        let theme = config.resolve().unwrap();
+        assert_eq!(theme.accent, Color::Rgb(0x11, 0x22, 0x33));
+        assert_eq!(theme.border, Color::Magenta);
+        assert_eq!(theme.text, Theme::light().text);
+        assert_eq!(ThemeConfig::default().resolve().unwrap(), Theme::dark());
+    }
+
+    #[test]
+    fn unknown_colours_name_their_slot() {
+        let config: ThemeConfig = toml::from_str("warning = \"mauve-ish\"\n").unwrap();
+        assert_eq!(
+            config.resolve(),
+            Err(ThemeError {
+                slot: "warning",
+                value: "mauve-ish".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! The table-of-contents screen (key `t`): the PRD's tasks in expected run order, with
+//! search and scrolling.
+
+use crossterm::event::KeyCode;
+use ralph_core::toc::TocEntry;
+
+/// State of the table-of-contents screen (key `t`)
+#[derive(Debug, Default)]
+pub struct TableOfContents {
+    pub entries: Vec<TocEntry>,
+    pub visible: bool,
+    /// Filter typed after `/`
+    pub query: String,
+    /// Whether keystrokes currently go to the search box
+    pub searching: bool,
+    pub scroll: usize,
+    /// Task clicked with the mouse, as an index into `filtered`; its full description is
+    /// shown under the table
+    pub selected: Option<usize>,
+}
+
+impl TableOfContents {
+    /// Replace the entries (e.g. after the PRD was reloaded), keeping the scroll in range
+    pub fn set_entries(&mut self, entries: Vec<TocEntry>) {
+        self.entries = entries;
+        self.scroll = self.scroll.min(self.filtered().len().saturating_sub(1));
+        self.selected = self.selected.filter(|&i| i < self.filtered().len());
+    }
+
+    /// Entries matching the search query
+    pub fn filtered(&self) -> Vec<&TocEntry> {
+        self.entries
+            .iter()
+            .filter(|e| self.query.is_empty() || e.matches(&self.query))
+            .collect()
+    }
+
+    /// Handle a key press while the screen is open. Returns false for keys it doesn't use,
+    /// so callers can still act on them (e.g. Ctrl+C).
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        if self.searching {
+            match code {
+                KeyCode::Char(c) => self.query.push(c),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                }
+                _ => return false,
+            }
+            self.scroll = 0;
+            self.selected = None;
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('t') | KeyCode::Char('T') => self.visible = false,
+            KeyCode::Esc if !self.query.is_empty() => {
+                self.query.clear();
+                self.selected = None;
+            }
+            KeyCode::Esc => self.visible = false,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Up => self.scroll_up(1),
+            KeyCode::Down => self.scroll_down(1),
+            KeyCode::PageUp => self.scroll_up(10),
+            KeyCode::PageDown => self.scroll_down(10),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+
+    pub fn scroll_down(&mut self, amount: usize) {
+        let last = self.filtered().len().saturating_sub(1);
+        self.scroll = (self.scroll + amount).min(last);
+    }
+
+    /// Select the task on the `row`th visible line of the table, or clear the selection
+    /// if it's already selected or the row is past the last task
+    pub fn click_row(&mut self, row: usize) {
+        let index = self.scroll + row;
+        self.selected = if index < self.filtered().len() && self.selected != Some(index) {
+            Some(index)
+        } else {
+            None
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ralph_core::prd::Prd;
+    use ralph_core::toc::build;
+
+    #[test]
+    fn search_filters_and_escape_clears() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Login page", "steps": [], "passes": false},
+            {"category": "docs", "description": "Readme", "steps": [], "passes": true}
+        ]}"#,
+        )
+        .unwrap();
+        let mut toc = TableOfContents::default();
+        toc.set_entries(build(&prd));
+
+        assert!(toc.handle_key(KeyCode::Char('/')));
+        for c in "DOCS".chars() {
+            toc.handle_key(KeyCode::Char(c));
+        }
+        toc.handle_key(KeyCode::Enter);
+        assert!(!toc.searching);
+        assert_eq!(toc.filtered().len(), 1);
+        assert_eq!(toc.filtered()[0].description, "Readme");
+
+        // First Esc clears the filter, second closes the screen
+        toc.visible = true;
+        toc.handle_key(KeyCode::Esc);
+        assert_eq!(toc.filtered().len(), 2);
+        assert!(toc.visible);
+        toc.handle_key(KeyCode::Esc);
+        assert!(!toc.visible);
+    }
+
+    #[test]
+    fn clicking_a_row_selects_it_and_again_clears_it() {
+        let prd: Prd = serde_json::from_str(
+            r#"{"name": "Test", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Login page", "steps": [], "passes": false},
+            {"category": "docs", "description": "Readme", "steps": [], "passes": false}
+        ]}"#,
+        )
+        .unwrap();
+        let mut toc = TableOfContents::default();
+        toc.set_entries(build(&prd));
+        toc.scroll_down(1);
+
+        toc.click_row(0);
+        assert_eq!(toc.selected, Some(1));
+        toc.click_row(0);
+        assert_eq!(toc.selected, None);
+        toc.click_row(5);
+        assert_eq!(toc.selected, None);
+    }
+
+    #[test]
+    fn unhandled_keys_fall_through() {
+        let mut toc = TableOfContents::default();
+        assert!(!toc.handle_key(KeyCode::Char('q')));
+        assert!(!toc.handle_key(KeyCode::Left));
+    }
+}
@@ -1,8 +1,236 @@
-use ratatui::DefaultTerminal;
+use crossterm::cursor::Show;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyEvent, KeyEventKind,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::execute;
+use crossterm::terminal::{EnterAlternateScreen, disable_raw_mode, enable_raw_mode};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::Rect;
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::io::{self, IsTerminal, Write};
+use std::ops::{Deref, DerefMut};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::watch;
 
-pub fn init_terminal() -> DefaultTerminal {
-    ratatui::init()
+/// Whether the full-screen TUI has the terminal in raw mode on the alternate screen
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the build TUI has turned on mouse capture, which has to be turned off again
+static MOUSE: AtomicBool = AtomicBool::new(false);
+
+/// Whether keys are reported with modifiers the legacy encoding drops, which has to be
+/// turned off again
+static KEYBOARD: AtomicBool = AtomicBool::new(false);
+
+/// Whether SIGTERM and SIGHUP are left to the build loop instead of exiting straight away
+static DEFER_TERMINATION: AtomicBool = AtomicBool::new(false);
+
+/// The SIGTERM or SIGHUP waiting for the build loop to stop
+static TERMINATION: LazyLock<watch::Sender<Option<i32>>> =
+    LazyLock::new(|| watch::Sender::new(None));
+
+/// Restore the terminal before a panic message is printed, and before SIGINT, SIGTERM or
+/// SIGHUP ends the process, so neither leaves the shell in raw mode
+pub fn install_restore_hooks() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_if_active();
+        hook(info);
+    }));
+    #[cfg(unix)]
+    watch_signals();
+}
+
+/// Hand the terminal back to the shell if the TUI has it. Safe to call more than once and
+/// from any thread.
+pub fn restore_if_active() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        if MOUSE.swap(false, Ordering::SeqCst) {
+            let _ = execute!(io::stdout(), DisableMouseCapture);
+        }
+        if KEYBOARD.swap(false, Ordering::SeqCst) {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        ratatui::restore();
+        // Normally shown again when the Terminal is dropped, which exiting skips
+        let _ = execute!(io::stdout(), Show);
+    }
+}
+
+/// Leave SIGTERM and SIGHUP to the caller, which polls `termination_signal` and stops
+/// cleanly. A second signal still exits straight away.
+pub fn defer_termination() {
+    DEFER_TERMINATION.store(true, Ordering::SeqCst);
+}
+
+/// The SIGTERM or SIGHUP received since `defer_termination`, if any
+pub fn termination_signal() -> Option<i32> {
+    *TERMINATION.borrow()
 }
-pub fn restore_terminal() {
-    ratatui::restore()
+
+/// Resolves once `termination_signal` has a signal
+pub async fn terminated() {
+    let mut signal = TERMINATION.subscribe();
+    // The sender is never dropped, so this only returns with a signal in
+    let _ = signal.wait_for(Option::is_some).await;
+}
+
+/// Name of a signal for messages, e.g. "SIGTERM"
+pub fn signal_name(signal: i32) -> String {
+    #[cfg(unix)]
+    if let Some(name) = signal_hook::low_level::signal_name(signal) {
+        return name.to_string();
+    }
+    format!("signal {}", signal)
+}
+
+/// In raw mode Ctrl+C is a key press, but a signal can still come from `kill` or a closed
+/// terminal. Exits with the shell's 128 + signal status once the terminal is restored,
+/// unless the build loop has taken over SIGTERM and SIGHUP to save its state first.
+#[cfg(unix)]
+fn watch_signals() {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+
+    let Ok(mut signals) = signal_hook::iterator::Signals::new([SIGINT, SIGTERM, SIGHUP]) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            if signal != SIGINT
+                && DEFER_TERMINATION.load(Ordering::SeqCst)
+                && TERMINATION.send_if_modified(|pending| {
+                    pending.is_none() && pending.replace(signal).is_none()
+                })
+            {
+                tracing::info!(signal, "Stopping the build on signal");
+                continue;
+            }
+            tracing::info!(signal, "Exiting on signal");
+            restore_if_active();
+            crate::logging::shutdown();
+            std::process::exit(128 + signal);
+        }
+    });
+}
+
+/// Raw mode and the alternate screen, undone again if the second step fails
+fn enter_full_screen() -> io::Result<()> {
+    enable_raw_mode()?;
+    ACTIVE.store(true, Ordering::SeqCst);
+    execute!(io::stdout(), EnterAlternateScreen).inspect_err(|_| restore_if_active())
+}
+
+/// A terminal that is handed back to the shell when dropped, so an error returned with `?`
+/// never leaves raw mode or the alternate screen behind
+pub struct TerminalGuard<B: Backend> {
+    terminal: Terminal<B>,
+    restore: bool,
+}
+
+impl<B: Backend> Deref for TerminalGuard<B> {
+    type Target = Terminal<B>;
+
+    fn deref(&self) -> &Terminal<B> {
+        &self.terminal
+    }
+}
+
+impl<B: Backend> DerefMut for TerminalGuard<B> {
+    fn deref_mut(&mut self) -> &mut Terminal<B> {
+        &mut self.terminal
+    }
+}
+
+impl<B: Backend> Drop for TerminalGuard<B> {
+    fn drop(&mut self) {
+        if self.restore {
+            restore_if_active()
+        }
+    }
+}
+
+/// The full-screen TUI used by `plan`, `view` and `tutorial`
+pub fn init_terminal() -> io::Result<TerminalGuard<CrosstermBackend<io::Stdout>>> {
+    enter_full_screen()?;
+    let terminal =
+        Terminal::new(CrosstermBackend::new(io::stdout())).inspect_err(|_| restore_if_active())?;
+    Ok(TerminalGuard {
+        terminal,
+        restore: true,
+    })
+}
+
+/// Terminal for `ralph build`, which can also run without a screen
+pub type RunTerminal = TerminalGuard<CrosstermBackend<Box<dyn Write>>>;
+
+/// Switch raw mode on and straight back off, to check the terminal supports it
+pub fn probe_raw_mode() -> io::Result<()> {
+    enable_raw_mode()?;
+    disable_raw_mode()
+}
+
+/// Whether stdout is a terminal the TUI can take over
+pub fn is_interactive() -> bool {
+    io::stdout().is_terminal()
+}
+
+/// The build TUI, or with `headless` a fixed-size terminal that draws into a sink: the loop
+/// runs unchanged while the terminal is left alone (no raw mode, no alternate screen). The
+/// TUI captures the mouse for scrolling and clicking; most terminals still select text
+/// with Shift held.
+pub fn init_run_terminal(headless: bool) -> io::Result<RunTerminal> {
+    if headless {
+        let backend = CrosstermBackend::new(Box::new(io::sink()) as Box<dyn Write>);
+        let viewport = Viewport::Fixed(Rect::new(0, 0, 120, 40));
+        return Ok(TerminalGuard {
+            terminal: Terminal::with_options(backend, TerminalOptions { viewport })?,
+            restore: false,
+        });
+    }
+    enter_full_screen()?;
+    execute!(io::stdout(), EnableMouseCapture).inspect_err(|_| restore_if_active())?;
+    MOUSE.store(true, Ordering::SeqCst);
+    let terminal = Terminal::new(CrosstermBackend::new(
+        Box::new(io::stdout()) as Box<dyn Write>
+    ))
+    .inspect_err(|_| restore_if_active())?;
+    Ok(TerminalGuard {
+        terminal,
+        restore: true,
+    })
+}
+
+/// Ask the terminal to tell Shift+Enter and Ctrl+Enter apart from Enter, where it supports
+/// the kitty keyboard protocol. Elsewhere those arrive as plain Enter, so keys that need
+/// them should have a fallback.
+pub fn enable_key_disambiguation() {
+    if ACTIVE.load(Ordering::SeqCst)
+        && !KEYBOARD.load(Ordering::SeqCst)
+        && matches!(
+            crossterm::terminal::supports_keyboard_enhancement(),
+            Ok(true)
+        )
+        && execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )
+        .is_ok()
+    {
+        KEYBOARD.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Wait up to `timeout` for a key press. Windows terminals report key releases too, which
+/// would make every key count twice, so only presses are returned.
+pub fn poll_key_press(timeout: Duration) -> io::Result<Option<KeyEvent>> {
+    if event::poll(timeout)?
+        && let Event::Key(key) = event::read()?
+        && key.kind == KeyEventKind::Press
+    {
+        return Ok(Some(key));
+    }
+    Ok(None)
 }
@@ -9,6 +9,27 @@ fn ralph_cmd() -> Command {
     Command::cargo_bin("ralph").unwrap()
 }
 
+/// A `claude` on PATH that answers every call with `output`, for running real build loops
+#[cfg(unix)]
+fn fake_claude(dir: &std::path::Path, output: &str) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = dir.join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let script = bin.join("claude");
+    std::fs::write(
+        &script,
+        format!("#!/bin/sh\ncat <<'EOF'\n{}\nEOF\n", output),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
 #[test]
 fn cli_no_args_shows_help() {
     ralph_cmd()
@@ -71,6 +92,673 @@ fn cli_build_nonexistent_prd_fails() {
         .failure();
 }
 
+#[test]
+fn cli_build_finds_the_prd_when_not_given_one() {
+    let temp_dir = TempDir::new().unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No PRD found at plans/prd.json, prd.json, .ralph/prd.json",
+        ));
+
+    let done = r#"{"name": "Done", "quality_gates": [], "tasks": []}"#;
+    std::fs::write(temp_dir.path().join("prd.json"), done).unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .arg("build")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Nothing to do: prd.json has no tasks.",
+        ));
+
+    std::fs::create_dir_all(temp_dir.path().join(".ralph")).unwrap();
+    std::fs::write(temp_dir.path().join(".ralph/prd.json"), done).unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Found several PRDs (prd.json, .ralph/prd.json)",
+        ));
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["build", ".ralph/prd.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Nothing to do: .ralph/prd.json has no tasks.",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_all_works_through_the_plans_prds() {
+    let temp_dir = TempDir::new().unwrap();
+    let plans = temp_dir.path().join("plans");
+    std::fs::create_dir_all(&plans).unwrap();
+    let pending = |description: &str| {
+        format!(
+            r#"{{"name": "Demo", "quality_gates": [], "tasks": [
+                {{"category": "feature", "description": "{}", "steps": [], "passes": false}}
+            ]}}"#,
+            description
+        )
+    };
+    std::fs::write(plans.join("auth.json"), pending("Add login")).unwrap();
+    std::fs::write(plans.join("billing.json"), pending("Add invoices")).unwrap();
+    std::fs::write(
+        plans.join("done.json"),
+        r#"{"name": "Done", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Shipped", "steps": [], "passes": true}
+        ]}"#,
+    )
+    .unwrap();
+    std::fs::write(plans.join("notes.md"), "# Notes\n").unwrap();
+    let path = fake_claude(temp_dir.path(), "{}");
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &path)
+        .args(["build", "--all", "--dry-run"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: skipping a file that isn't a PRD",
+        ))
+        .stderr(predicate::str::contains("plans/notes.md"))
+        .stdout(predicate::str::contains("==> plans/auth.json (1 of 3)"))
+        .stdout(predicate::str::contains(
+            "Next task: #1 (feature) Add invoices",
+        ))
+        .stdout(predicate::str::contains(
+            "Workspace: 1 of 3 PRD(s) complete, 0 loop(s), $0.00",
+        ))
+        .stdout(predicate::str::contains(
+            "! plans/billing.json: incomplete, 0/1 task(s) passing",
+        ))
+        .stdout(predicate::str::contains(
+            "✓ plans/done.json: complete, 1/1 task(s) passing",
+        ));
+    assert!(temp_dir.path().join(".ralph/workspace.json").exists());
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["build", "--prd", "done.json,missing.json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("- plans/missing.json").not())
+        .stdout(predicate::str::contains("✗ missing.json: failed"));
+
+    // A dry run that finds problems in one PRD still checks the rest
+    std::fs::write(
+        plans.join("api.json"),
+        r#"{"name": "API", "quality_gates": ["ralph-no-such-gate"], "tasks": [
+            {"category": "feature", "description": "Add routes", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &path)
+        .args(["build", "--prd", "api.json,billing.json", "--dry-run"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("==> plans/billing.json (2 of 2)"))
+        .stdout(predicate::str::contains(
+            "✗ plans/api.json: failed: The dry run found problems",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_all_shares_the_cost_budget_between_prds() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    git_repo(temp_dir.path());
+    let plans = temp_dir.path().join("plans");
+    std::fs::create_dir_all(&plans).unwrap();
+    for (name, description) in [
+        ("auth.json", "Add login"),
+        ("billing.json", "Add invoices"),
+        ("search.json", "Add search"),
+    ] {
+        std::fs::write(
+            plans.join(name),
+            format!(
+                r#"{{"name": "Demo", "quality_gates": [], "tasks": [
+                    {{"category": "feature", "description": "{}", "steps": [], "passes": false}}
+                ]}}"#,
+                description
+            ),
+        )
+        .unwrap();
+    }
+    let path = fake_claude(temp_dir.path(), "");
+    // Each loop finishes the task of the PRD it was given, for $0.60
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+for prd in plans/*.json; do
+  case "$*" in
+    *"@$prd"*) sed -i.bak 's/"passes": false/"passes": true/' "$prd"; rm -f "$prd.bak" ;;
+  esac
+done
+git add -A && git commit -qm "Loop"
+echo '{"type": "result", "is_error": false, "total_cost_usd": 0.6,
+  "structured_output": {"task_number": 1, "status": "completed", "summary": "Done",
+  "prd_complete": true}}'
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &path)
+        .args(["build", "--all", "--no-tui", "--max-cost", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Workspace: 2 of 3 PRD(s) complete, 2 loop(s), $1.20",
+        ))
+        .stdout(predicate::str::contains("- plans/search.json: not started"))
+        .stdout(predicate::str::contains(
+            "Stopped: the workspace's --max-cost budget is used up",
+        ));
+}
+
+#[test]
+fn cli_build_all_tasks_passing_exits_cleanly() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Done", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Shipped", "steps": [], "passes": true}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args(["build", "--prd-path", prd_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to do"));
+}
+
+#[test]
+fn cli_build_dry_run_prints_prompt_without_claude() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": ["definitely-not-a-real-gate-xyz"], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": ["Form"], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    // Exit status depends on whether claude is installed, so only the output is checked
+    ralph_cmd()
+        .args([
+            "build",
+            "--dry-run",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .stdout(predicate::str::contains("Dry run"))
+        .stdout(predicate::str::contains(
+            "Next task: #1 (feature) Add login",
+        ))
+        .stdout(predicate::str::contains(
+            "definitely-not-a-real-gate-xyz not found on PATH",
+        ))
+        .stdout(predicate::str::contains("─── Prompt"))
+        .stdout(predicate::str::contains("@progress.txt"));
+}
+
+#[test]
+fn cli_build_dry_run_runs_a_task_in_its_workdir() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("packages/api")).unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Mono", "quality_gates": [], "tasks": [
+            {"category": "api", "description": "Add route", "steps": [], "passes": false,
+             "workdir": "packages/api"},
+            {"category": "web", "description": "Add page", "steps": [], "passes": false,
+             "workdir": "packages/web"}
+        ]}"#,
+    )
+    .unwrap();
+    std::fs::write(temp_dir.path().join("CLAUDE.md"), "# Conventions\n").unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["build", "--dry-run", "prd.json"])
+        .assert()
+        .stdout(predicate::str::contains(
+            "Next task: #1 (api) Add route [picked by ralph for its workdir]",
+        ))
+        .stdout(predicate::str::contains("Workdir:   packages/api"))
+        .stdout(predicate::str::contains("✓ workdir packages/api"))
+        .stdout(predicate::str::contains(
+            "✗ workdir packages/web doesn't exist",
+        ))
+        .stdout(predicate::str::contains("@../../prd.json"))
+        .stdout(predicate::str::contains("@../../progress.txt"))
+        .stdout(predicate::str::contains("@progress.txt").not())
+        .stdout(predicate::str::contains("@../../CLAUDE.md"))
+        .stdout(predicate::str::contains("## Working Directory"));
+}
+
+#[test]
+fn cli_build_task_out_of_range_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "build",
+            "--task",
+            "5",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--task 5 is out of range"));
+}
+
+#[test]
+fn cli_build_category_without_matching_tasks_exits_cleanly() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "build",
+            "--category",
+            "docs,test",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "no pending tasks in categories docs, test",
+        ));
+}
+
+#[test]
+fn cli_build_rejects_unknown_notify_level() {
+    ralph_cmd()
+        .args(["build", "--notify", "desktop=loud"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unknown notification level 'loud'",
+        ));
+}
+
+#[test]
+fn cli_build_dry_run_shows_verify_model() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "build",
+            "--dry-run",
+            "--verify",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .stdout(predicate::str::contains("each completed task with haiku"));
+}
+
+#[test]
+fn cli_build_start_at_rejects_bad_and_past_times() {
+    ralph_cmd()
+        .args(["build", "--start-at", "tonight"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid start time 'tonight'"));
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--start-at",
+            "2000-01-01T02:00",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--start-at time has already passed",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_start_at_waits_before_the_first_loop() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "structured_output": {"task_number": 1,
+            "status": "completed", "summary": "Done", "prd_complete": false}}"#,
+    );
+    // Two seconds from now, so the next such time on the clock is today's (or just past
+    // midnight's)
+    let start = chrono::Local::now() + chrono::Duration::seconds(2);
+
+    let began = std::time::Instant::now();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+            "--start-at",
+            &start.format("%H:%M:%S").to_string(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Waiting to start at"))
+        .stdout(predicate::str::contains("Spawning Claude"));
+    assert!(began.elapsed() >= std::time::Duration::from_secs(1));
+}
+
+#[test]
+fn cli_build_dry_run_shows_definition_of_done() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [],
+            "definition_of_done": ["CHANGELOG updated", "`make docs` succeeds"],
+            "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "build",
+            "--dry-run",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .stdout(predicate::str::contains(
+            "Done when: CHANGELOG updated; `make docs` succeeds",
+        ))
+        .stdout(predicate::str::contains("## Definition of Done"));
+}
+
+#[test]
+fn cli_build_dry_run_shows_escalation_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "build",
+            "--dry-run",
+            "--escalate-after",
+            "--escalate-steps",
+            "history,pause",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .stdout(predicate::str::contains(
+            "Escalate:  after 2 failed loop(s) on a task: history, pause (model opus)",
+        ));
+}
+
+#[test]
+fn cli_build_slack_needs_webhook() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .env_remove("RALPH_SLACK_WEBHOOK")
+        .args([
+            "build",
+            "--dry-run",
+            "--notify",
+            "slack=failures",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--notify slack needs a webhook: pass --slack-webhook or set RALPH_SLACK_WEBHOOK",
+        ));
+}
+
+#[test]
+fn cli_build_discord_reads_ralph_toml() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let build = |notify: &str| {
+        ralph_cmd()
+            .current_dir(temp_dir.path())
+            .args([
+                "build",
+                "--dry-run",
+                "--notify",
+                notify,
+                "--prd-path",
+                "prd.json",
+            ])
+            .assert()
+    };
+
+    build("discord").failure().stderr(predicate::str::contains(
+        "--notify discord needs a webhook: add [notify.discord]",
+    ));
+
+    std::fs::write(
+        temp_dir.path().join(".ralph.toml"),
+        "[notify.discord]\nwebhook = \"https://example.test/hook\"\nlevel = \"loud\"\n",
+    )
+    .unwrap();
+    build("desktop=off")
+        .failure()
+        .stderr(predicate::str::contains(
+            "unknown notification level 'loud'",
+        ));
+
+    std::fs::write(
+        temp_dir.path().join(".ralph.toml"),
+        "[notify.discord]\nwebhook = \"https://example.test/hook\"\n",
+    )
+    .unwrap();
+    build("discord=failures").success();
+}
+
+#[test]
+fn cli_build_review_needs_the_tui() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["build", "--prd-path", "prd.json", "--review", "--no-tui"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--review shows each diff in the TUI",
+        ));
+}
+
+#[test]
+fn cli_build_once_requires_task() {
+    ralph_cmd()
+        .args(["build", "--once"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--task <N>"));
+}
+
+#[test]
+fn cli_build_once_dry_run_reruns_passing_task() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": true},
+            {"category": "feature", "description": "Add logout", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "build",
+            "--task",
+            "1",
+            "--once",
+            "--dry-run",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .stdout(predicate::str::contains("loops 1"))
+        .stdout(predicate::str::contains("wasn't satisfactory"))
+        .stdout(predicate::str::contains("Add logout").not());
+}
+
+#[test]
+fn cli_build_task_already_passing_exits_cleanly() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": true},
+            {"category": "feature", "description": "Add logout", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "build",
+            "--task",
+            "1",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "task #1 (Add login) already passes",
+        ));
+}
+
+#[test]
+fn cli_build_empty_prd_exits_cleanly() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"name": "Empty", "quality_gates": [], "tasks": []}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args(["build", "--prd-path", prd_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("has no tasks"));
+}
+
 #[test]
 fn cli_build_invalid_prd_fails() {
     let temp_dir = TempDir::new().unwrap();
@@ -137,3 +825,1825 @@ fn cli_build_max_loops_flag() {
         .success()
         .stdout(predicate::str::contains("-l, --max-loops"));
 }
+
+#[test]
+fn cli_build_max_cost_flag() {
+    ralph_cmd()
+        .args(["build", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--max-cost <USD>"));
+}
+
+#[test]
+fn cli_view_help() {
+    ralph_cmd()
+        .args(["view", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("read-only"))
+        .stdout(predicate::str::contains("--prd-path"));
+}
+
+#[test]
+fn cli_view_nonexistent_prd_fails() {
+    ralph_cmd()
+        .args(["view", "--prd-path", "/nonexistent/path/prd.json"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn cli_build_loop_timeout_flag() {
+    ralph_cmd()
+        .args(["build", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--loop-timeout <MINUTES>"));
+}
+
+#[test]
+fn cli_plan_speculate_flag_accepted() {
+    ralph_cmd()
+        .args(["plan", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--speculate"));
+}
+
+#[test]
+fn cli_plan_cost_warn_flag() {
+    ralph_cmd()
+        .args(["plan", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--cost-warn <USD>"));
+}
+
+#[test]
+fn cli_plan_from_todos_without_todos_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--from-todos", "--output", "plans/prd.json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No TODO/FIXME/HACK comments found",
+        ));
+}
+
+#[test]
+fn cli_plan_rejects_an_empty_description_file_or_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join("spec.md"), "\n  \n").unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--description-file", "spec.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The description in spec.md is empty",
+        ));
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--description-file", "missing.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Could not read the description from missing.md",
+        ));
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--stdin"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The description in standard input is empty",
+        ));
+
+    ralph_cmd()
+        .args(["plan", "--stdin", "--description", "Add tags"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn cli_plan_unknown_template_lists_the_available_ones() {
+    let temp_dir = TempDir::new().unwrap();
+    let templates = temp_dir.path().join(".ralph/templates");
+    std::fs::create_dir_all(&templates).unwrap();
+    std::fs::write(
+        templates.join("service.md"),
+        "Every endpoint needs a metric.\n",
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--template", "mobile", "--description", "An app"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown plan template 'mobile'; available: web-app, cli-tool, library, bugfix-sprint, service",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_plan_from_failing_tests_writes_prd() {
+    let temp_dir = TempDir::new().unwrap();
+    let script = "echo 'FAILED tests/test_api.py::test_create - KeyError'; exit 1";
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--from-failing-tests", "--test-cmd", script])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Fix 1 failing test(s) in tests/test_api.py",
+        ));
+
+    let prd = std::fs::read_to_string(temp_dir.path().join("plans/prd.json")).unwrap();
+    assert!(prd.contains("Make `test_create` pass (KeyError)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_plan_from_failing_tests_with_passing_suite_fails() {
+    let temp_dir = TempDir::new().unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["plan", "--from-failing-tests", "--test-cmd", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no failing tests"));
+}
+
+#[test]
+fn cli_migrate_upgrades_unversioned_prd() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(&prd_path, r#"{"name": "Old", "tasks": ["Add login"]}"#).unwrap();
+
+    ralph_cmd()
+        .args(["migrate", prd_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from schema version 0 to 1"));
+
+    assert!(temp_dir.path().join("prd.json.v0.bak").exists());
+    let migrated = std::fs::read_to_string(&prd_path).unwrap();
+    assert!(migrated.contains("\"schema_version\": 1"));
+
+    ralph_cmd()
+        .args(["migrate", prd_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing to do"));
+}
+
+#[test]
+fn cli_stats_detailed_groups_by_category_and_model() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        temp_dir.path().join(".ralph-stats.json"),
+        r#"{"iterations": [
+            {"run_id": "a", "loop_number": 1, "category": "feature", "model": "claude-sonnet", "status": "completed", "duration_secs": 120.0, "cost_usd": 0.5, "finished_at": "2025-01-01T00:00:00Z"},
+            {"run_id": "a", "loop_number": 2, "category": "refactor", "model": "claude-opus", "status": "completed", "duration_secs": 3600.0, "cost_usd": 4.0, "finished_at": "2025-01-01T01:00:00Z"}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "stats",
+            "--detailed",
+            "--prd-path",
+            prd_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 iterations, $4.50 total"))
+        .stdout(predicate::str::contains("By category:"))
+        .stdout(predicate::str::contains("refactor"))
+        .stdout(predicate::str::contains("claude-opus"));
+}
+
+#[test]
+fn cli_stats_without_history() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    ralph_cmd()
+        .args(["stats", "--prd-path", prd_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No iterations recorded"));
+}
+
+#[test]
+fn cli_import_markdown_writes_prd() {
+    let temp_dir = TempDir::new().unwrap();
+    let markdown = temp_dir.path().join("tasks.md");
+    let output = temp_dir.path().join("plans").join("prd.json");
+    std::fs::write(
+        &markdown,
+        "# Demo\n\n## Quality gates\n- `cargo test`\n\n## Feature\n- [ ] Add login\n  - Form\n",
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .args([
+            "import",
+            markdown.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 task(s)"))
+        .stdout(predicate::str::contains("[feature] Add login"));
+
+    let prd = std::fs::read_to_string(&output).unwrap();
+    assert!(prd.contains("\"cargo test\""));
+    assert!(prd.contains("\"Form\""));
+
+    // Refuses to overwrite without --force
+    ralph_cmd()
+        .args([
+            "import",
+            markdown.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+}
+
+#[test]
+fn cli_tasks_add_edit_done_roundtrip() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        &prd_path,
+        r#"{"schema_version": 1, "name": "Demo", "quality_gates": [], "tasks": []}"#,
+    )
+    .unwrap();
+    let prd = prd_path.to_str().unwrap();
+
+    ralph_cmd()
+        .args([
+            "tasks",
+            "add",
+            "Add login",
+            "-c",
+            "feature",
+            "-s",
+            "Form",
+            "-p",
+            prd,
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added task #1: Add login"));
+    ralph_cmd()
+        .args(["tasks", "edit", "1", "--priority", "high", "-p", prd])
+        .assert()
+        .success();
+    ralph_cmd()
+        .args(["tasks", "list", "-p", prd])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Add login  (high priority)"))
+        .stdout(predicate::str::contains("1. Form"));
+    ralph_cmd()
+        .args(["tasks", "done", "1", "-p", prd])
+        .assert()
+        .success();
+    ralph_cmd()
+        .args(["tasks", "list", "--all", "-p", prd])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "0 pending, 0 passing, 1 completed",
+        ));
+    ralph_cmd()
+        .args(["tasks", "remove", "1", "-p", prd])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No task #1"));
+}
+
+#[test]
+fn cli_tasks_reports_invalid_prd_without_panicking() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(&prd_path, "{not json").unwrap();
+
+    ralph_cmd()
+        .args(["tasks", "list", "-p", prd_path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error: Invalid JSON formatting"))
+        .stderr(predicate::str::contains("panicked").not());
+}
+
+#[test]
+fn cli_prompt_show_uses_the_project_prompt() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"schema_version": 1, "name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["prompt", "show", "-p", "prd.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("@prd.json"))
+        .stdout(predicate::str::contains("@progress.txt"))
+        .stderr(predicate::str::contains("Built-in loop instructions"));
+
+    std::fs::create_dir_all(temp_dir.path().join(".ralph")).unwrap();
+    std::fs::write(
+        temp_dir.path().join(".ralph/prompt.md"),
+        "Work from {{prd_path}}, noting progress in {{progress_file}}.\n{{task_constraints}}\nCommit as you go.\n",
+    )
+    .unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["prompt", "show", "-p", "prd.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Work from prd.json, noting progress in progress.txt.",
+        ))
+        .stdout(predicate::str::contains("1. Task #1 (feature): Add login"))
+        .stdout(predicate::str::ends_with("Commit as you go.\n"))
+        .stderr(predicate::str::contains("from ./.ralph/prompt.md"));
+}
+
+#[test]
+fn cli_build_no_gates_flag() {
+    ralph_cmd()
+        .args(["build", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--no-gates"));
+}
+
+#[test]
+fn cli_build_completion_flags() {
+    ralph_cmd()
+        .args(["build", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--complete-when <DETECTOR>"))
+        .stdout(predicate::str::contains("--exit-clause <TEXT>"))
+        .stdout(predicate::str::contains("all-tasks"));
+}
+
+#[test]
+fn cli_build_rejects_unknown_detector() {
+    ralph_cmd()
+        .args(["build", "--complete-when", "vibes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[test]
+fn cli_storage_flag_is_global() {
+    ralph_cmd()
+        .args(["view", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--storage <STORAGE>"))
+        .stdout(predicate::str::contains("sqlite"));
+}
+
+#[cfg(not(feature = "sqlite"))]
+#[test]
+fn cli_storage_sqlite_requires_feature() {
+    ralph_cmd()
+        .args(["--storage", "sqlite", "view"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("`sqlite` feature"));
+}
+
+#[test]
+fn cli_build_branch_flag() {
+    ralph_cmd()
+        .args(["build", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--branch [<NAME>]"));
+}
+
+#[test]
+fn cli_tutorial_refuses_existing_dir() {
+    let temp_dir = TempDir::new().unwrap();
+
+    ralph_cmd()
+        .args(["tutorial", "--dir", temp_dir.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"))
+        .stderr(predicate::str::contains("--force"));
+}
+
+#[test]
+fn cli_history_lists_runs_and_loops() {
+    let temp_dir = TempDir::new().unwrap();
+    let prd_path = temp_dir.path().join("prd.json");
+    std::fs::write(
+        temp_dir.path().join(".ralph-stats.json"),
+        r#"{"iterations": [
+            {"run_id": "0123abcd-run", "run_label": "nightly", "loop_number": 1, "task_number": 2,
+             "status": "completed", "summary": "Added the login form", "commit": "deadbeef1234",
+             "duration_secs": 90.0, "cost_usd": 0.5, "finished_at": "2026-01-01T12:00:00Z"}
+        ]}"#,
+    )
+    .unwrap();
+    let prd = prd_path.to_str().unwrap();
+
+    ralph_cmd()
+        .args(["history", "-p", prd])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0123abcd"))
+        .stdout(predicate::str::contains("nightly"));
+
+    ralph_cmd()
+        .args(["history", "-p", prd, "nightly"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("task 2"))
+        .stdout(predicate::str::contains("deadbee"))
+        .stdout(predicate::str::contains("Added the login form"));
+
+    ralph_cmd()
+        .args(["history", "-p", prd, "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No run matches"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_daemon_runs_passes_until_the_prd_is_complete() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join(".ralph.toml"),
+        "[daemon]\nschedule = \"0 2 * * *\"\nbuild_args = [\"--max-loops\", \"1\"]\n",
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // Claude marks the task done, as a real loop would
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+sed -i.bak 's/"passes": false/"passes": true/' prd.json
+echo '{"type": "result", "is_error": false, "structured_output": {"task_number": 1,
+  "status": "completed", "summary": "Done", "prd_complete": true}}'
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args(["daemon", "--prd-path", "prd.json", "--now"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Starting build pass #1: daemon "))
+        .stdout(predicate::str::contains("Build pass #1 finished"))
+        .stdout(predicate::str::contains(
+            "PRD Demo is complete; the daemon is stopping",
+        ));
+
+    // The pass is in the run history under its label
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["history", "--prd-path", "prd.json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("daemon "));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_daemon_stops_after_max_passes() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "structured_output": {"task_number": 1,
+            "status": "in_progress", "summary": "Started", "prd_complete": false}}"#,
+    );
+    std::fs::write(
+        temp_dir.path().join(".ralph.toml"),
+        "[daemon]\nbuild_args = [\"--max-loops\", \"1\"]\n",
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "daemon",
+            "--prd-path",
+            "prd.json",
+            "--schedule",
+            "0 2 * * *",
+            "--now",
+            "--max-passes",
+            "1",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Ran 1 pass(es) with 1 task(s) still pending",
+        ));
+}
+
+#[test]
+fn cli_daemon_needs_a_valid_schedule() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": []}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["daemon", "--prd-path", "prd.json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No schedule"));
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["daemon", "--prd-path", "prd.json", "--schedule", "nightly"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid schedule 'nightly'"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_without_a_terminal_prints_log_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "total_cost_usd": 0.25,
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Sketched the login form", "prd_complete": false}}"#,
+    );
+
+    // stdout is a pipe here, so the run is headless without --no-tui
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args(["build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"(?m)^\[\d{4}-\d\d-\d\d \d\d:\d\d:\d\d\] Summary: Sketched the login form$",
+            )
+            .unwrap(),
+        )
+        .stdout(predicate::str::contains("Ralph Session Complete"))
+        .stdout(predicate::str::contains("\x1b[").not());
+
+    // The loop's raw output outlives the run
+    let logs = temp_dir.path().join(".ralph").join("logs");
+    let run_dir = std::fs::read_dir(&logs).unwrap().next().unwrap().unwrap();
+    let run_id = run_dir.file_name().into_string().unwrap();
+    assert!(run_dir.path().join("loop-1.json").exists());
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args([
+            "history",
+            &run_id[..8],
+            "--loop",
+            "1",
+            "--prd-path",
+            "prd.json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Summary: Sketched the login form"))
+        .stdout(predicate::str::contains(
+            "─── Claude output (attempt 1) ───",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_passes_claude_options_through() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join(".ralph.toml"),
+        "[claude]\nmcp_config = [\".mcp.json\"]\nsettings = \"configured.json\"\n",
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false,
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Sketched the login form", "prd_complete": false}}"#,
+    );
+    // Have the fake record the arguments it was started with
+    let script = temp_dir.path().join("bin").join("claude");
+    let body = std::fs::read_to_string(&script).unwrap().replacen(
+        "#!/bin/sh\n",
+        "#!/bin/sh\nprintf '%s\\n' \"$@\" > args.txt\n",
+        1,
+    );
+    std::fs::write(&script, body).unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+            "--mcp-config",
+            "db.json",
+            "--add-dir",
+            "../shared",
+            "--settings",
+            "flag.json",
+            "--disallowed-tool",
+            "WebSearch",
+        ])
+        .assert()
+        .success();
+
+    let args = std::fs::read_to_string(temp_dir.path().join("args.txt")).unwrap();
+    assert!(args.contains("--mcp-config\n.mcp.json\n--mcp-config\ndb.json\n"));
+    assert!(args.contains("--add-dir\n../shared\n"));
+    assert!(args.contains("--settings\nflag.json\n"));
+    assert!(!args.contains("configured.json"));
+    assert!(args.contains("--disallowedTools\nWebSearch\n"));
+    assert!(args.contains("bypassPermissions"));
+
+    // An allow-list turns off the permission bypass, or it would allow everything anyway
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+            "--allowed-tool",
+            "Read",
+            "--allowed-tool",
+            "Bash(cargo:*)",
+        ])
+        .assert()
+        .success();
+    let args = std::fs::read_to_string(temp_dir.path().join("args.txt")).unwrap();
+    assert!(args.contains("--allowedTools\nRead\nBash(cargo:*)\n"));
+    assert!(!args.contains("bypassPermissions"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_redacts_secrets_from_logs() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join(".ralph.toml"),
+        "[redact]\npatterns = [\"db-host-[0-9]+\"]\n",
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "result": "Found STRIPE_SECRET=sk_live_123",
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Wired GITHUB_TOKEN=abc123 to db-host-7", "prd_complete": false}}"#,
+    );
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args(["build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Summary: Wired GITHUB_TOKEN=[REDACTED] to [REDACTED]",
+        ))
+        .stdout(predicate::str::contains("abc123").not());
+
+    let logs = temp_dir.path().join(".ralph").join("logs");
+    let run_dir = std::fs::read_dir(&logs).unwrap().next().unwrap().unwrap();
+    let loop_log = std::fs::read_to_string(run_dir.path().join("loop-1.json")).unwrap();
+    assert!(loop_log.contains("STRIPE_SECRET=[REDACTED]"));
+    assert!(!loop_log.contains("sk_live_123"));
+    assert!(!loop_log.contains("abc123"));
+    assert!(!loop_log.contains("db-host-7"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_code_review_sends_the_task_back() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // The reviewer is told about request_changes in its schema; the loop isn't
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+case "$*" in
+  *request_changes*) echo '{"type": "result", "is_error": false, "total_cost_usd": 0.1,
+    "structured_output": {"verdict": "request_changes", "summary": "Login accepts empty passwords",
+    "issues": [{"file": "src/login.rs", "severity": "blocker", "comment": "Reject empty passwords"}]}}' ;;
+  *) echo '{"type": "result", "is_error": false, "structured_output": {"task_number": 1,
+    "status": "completed", "summary": "Added login", "prd_complete": false}}' ;;
+esac
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+            "--code-review",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Code review of task #1 requested changes: Login accepts empty passwords",
+        ))
+        .stdout(predicate::str::contains(
+            "- [blocker] src/login.rs: Reject empty passwords",
+        ));
+    let prd = std::fs::read_to_string(temp_dir.path().join("prd.json")).unwrap();
+    assert!(prd.contains(r#""passes": false"#));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_with_tests_runs_a_test_writer() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false},
+            {"category": "docs", "description": "Document login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // The test writer's schema asks for tests_added; the loop's doesn't. Loops complete
+    // task 1 and then task 2, a docs task that gets no test writer.
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+case "$*" in
+  *tests_added*) echo tests >> writers.txt; echo '{"type": "result", "is_error": false,
+    "structured_output": {"summary": "Covered empty passwords", "test_files": ["tests/login.rs"],
+    "tests_added": 3, "committed": true}}' ;;
+  *) n=$(ls loops.* 2>/dev/null | wc -l); n=$((n + 1)); touch loops.$n
+    echo "{\"type\": \"result\", \"is_error\": false, \"structured_output\": {\"task_number\": $n,
+    \"status\": \"completed\", \"summary\": \"Done\", \"prd_complete\": false}}" ;;
+esac
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "2",
+            "--with-tests",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "3 test(s) added in tests/login.rs: Covered empty passwords",
+        ));
+    let writers = std::fs::read_to_string(temp_dir.path().join("writers.txt")).unwrap();
+    assert_eq!(writers.lines().count(), 1);
+}
+
+/// A fake claude for `--fix-gates`: loops complete task 1, and the fixer (whose schema asks
+/// whether it `fixed` the gates) creates the file the gate checks for on attempt `fixed_on`
+#[cfg(unix)]
+fn gate_fixing_claude(dir: &std::path::Path, fixed_on: u32) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::write(
+        dir.join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": ["test -f fixed.txt"], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(dir, "");
+    std::fs::write(
+        dir.join("bin").join("claude"),
+        format!(
+            r#"#!/bin/sh
+case "$*" in
+  *'"fixed"'*) echo fix >> fixes.txt
+    if [ "$(wc -l < fixes.txt)" -ge {fixed_on} ]; then touch fixed.txt; fi
+    echo '{{"type": "result", "is_error": false, "structured_output": {{"summary": "Tried a fix",
+      "fixed": true, "committed": true}}}}' ;;
+  *) echo '{{"type": "result", "is_error": false, "structured_output": {{"task_number": 1,
+    "status": "completed", "summary": "Done", "prd_complete": false}}}}' ;;
+esac
+"#
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        dir.join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+    path
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_fix_gates_retries_until_the_gates_pass() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = gate_fixing_claude(temp_dir.path(), 2);
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+            "--fix-gates",
+            "3",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Gate fix attempt 2 of 3"))
+        .stdout(predicate::str::contains("Gate fix attempt 3 of 3").not())
+        .stdout(predicate::str::contains("still fail").not());
+    let fixes = std::fs::read_to_string(temp_dir.path().join("fixes.txt")).unwrap();
+    assert_eq!(fixes.lines().count(), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_fix_gates_blocks_the_task_when_attempts_run_out() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = gate_fixing_claude(temp_dir.path(), 5);
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+            "--fix-gates",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Quality gates still fail after 2 fix attempt(s)",
+        ));
+    let prd = std::fs::read_to_string(temp_dir.path().join("prd.json")).unwrap();
+    assert!(prd.contains(r#""passes": false"#));
+}
+
+#[test]
+fn cli_build_fix_gates_conflicts_with_no_gates() {
+    ralph_cmd()
+        .args(["build", "--fix-gates", "2", "--no-gates"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+/// A git repository with one commit, and the identity ralph's merges need
+#[cfg(unix)]
+fn git_repo(dir: &std::path::Path) {
+    for args in [
+        &["init", "-q"][..],
+        &["config", "user.name", "Test"],
+        &["config", "user.email", "test@example.com"],
+        &["commit", "-q", "--allow-empty", "-m", "start"],
+    ] {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_parallel_merges_independent_tasks() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    git_repo(temp_dir.path());
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "api", "description": "Add login API", "steps": [], "passes": false},
+            {"category": "ui", "description": "Add login page", "steps": [], "passes": false},
+            {"category": "ui", "description": "Add logout button", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // Each worker commits a file of its own. Task numbers shift as tasks are archived, so
+    // the worker goes by its task's description (the journal names the earlier tasks too).
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+case "$*" in
+  *'"description": "Add login API"'*) n=1 ;;
+  *'"description": "Add login page"'*) n=2 ;;
+  *) n=3 ;;
+esac
+echo "task $n" > "task$n.txt"
+git add . && git commit -qm "Task $n"
+echo "{\"type\": \"result\", \"is_error\": false, \"structured_output\": {\"task_number\": $n,
+  \"status\": \"completed\", \"summary\": \"Did task $n\", \"prd_complete\": false}}"
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "3",
+            "--parallel",
+            "3",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "worker 1 started on task #1: Add login API",
+        ))
+        .stdout(predicate::str::contains(
+            "worker 2 started on task #2: Add login page",
+        ))
+        // The third task shares a category with the second, so it waits for that worker
+        .stdout(predicate::str::contains(
+            "started on task #1: Add logout button",
+        ))
+        .stdout(predicate::str::contains(
+            "Workers done: 3 of 3 task(s) completed",
+        ));
+
+    for n in 1..=3 {
+        assert!(temp_dir.path().join(format!("task{}.txt", n)).exists());
+    }
+    let prd = std::fs::read_to_string(temp_dir.path().join("prd.json")).unwrap();
+    assert!(!prd.contains("Add logout button"));
+    let branches = std::process::Command::new("git")
+        .args(["branch", "--list", "ralph/parallel/*"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(branches.stdout.is_empty(), "merged branches are deleted");
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_parallel_leaves_a_conflicting_task_pending() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    git_repo(temp_dir.path());
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "api", "description": "Add login API", "steps": [], "passes": false},
+            {"category": "ui", "description": "Add login page", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // Both workers write the same file; the second finishes last
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+case "$*" in
+  *"Add login API"*) n=1 ;;
+  *) n=2; sleep 1 ;;
+esac
+echo "task $n" > shared.txt
+git add . && git commit -qm "Task $n"
+echo "{\"type\": \"result\", \"is_error\": false, \"structured_output\": {\"task_number\": $n,
+  \"status\": \"completed\", \"summary\": \"Did task $n\", \"prd_complete\": false}}"
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "2",
+            "--parallel",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Task #2 conflicts with work merged before it (shared.txt)",
+        ));
+
+    assert_eq!(
+        std::fs::read_to_string(temp_dir.path().join("shared.txt")).unwrap(),
+        "task 1\n"
+    );
+    let prd = std::fs::read_to_string(temp_dir.path().join("prd.json")).unwrap();
+    assert!(prd.contains("Add login page"));
+    assert!(!prd.contains("Add login API"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_parallel_dispatches_dependents_once_merged() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    git_repo(temp_dir.path());
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "api", "description": "Add login API", "steps": [], "passes": false},
+            {"category": "api", "description": "Add logout API", "steps": [], "passes": false,
+             "depends_on": ["Add login API"]},
+            {"category": "docs", "description": "Document login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // The logout worker needs the login worker's file in its work tree
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+case "$*" in
+  *"Add logout API"*) n=logout; test -f login.txt || exit 1 ;;
+  *"Add login API"*) n=login ;;
+  *) n=docs ;;
+esac
+echo "$n" > "$n.txt"
+git add . && git commit -qm "$n"
+echo "{\"type\": \"result\", \"is_error\": false, \"structured_output\": {\"task_number\": 1,
+  \"status\": \"completed\", \"summary\": \"Did $n\", \"prd_complete\": false}}"
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "3",
+            "--parallel",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Workers done: 3 of 3 task(s) completed",
+        ));
+
+    assert!(temp_dir.path().join("logout.txt").exists());
+    let run_state: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(temp_dir.path().join(".ralph-run.json")).unwrap(),
+    )
+    .unwrap();
+    let logout: Vec<(&str, u64)> = run_state["task_transitions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|t| t["task"] == "Add logout API")
+        .map(|t| (t["state"].as_str().unwrap(), t["loop"].as_u64().unwrap()))
+        .collect();
+    // Dispatched as the third loop, once the login task was merged
+    assert_eq!(logout, vec![("running", 3), ("completed", 3)]);
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_rollback_restores_the_repo_and_prd_before_a_loop() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    git_repo(temp_dir.path());
+    let prd = r#"{"name": "Demo", "quality_gates": [], "tasks": [
+        {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+    ]}"#;
+    std::fs::write(temp_dir.path().join("prd.json"), prd).unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // Claude commits its work and marks the task done
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+echo login > login.txt
+git add login.txt && git commit -qm "Add login"
+sed -i.bak 's/"passes": false/"passes": true/' prd.json
+echo '{"type": "result", "is_error": false, "structured_output": {"task_number": 1,
+  "status": "completed", "summary": "Done", "prd_complete": true}}'
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &path)
+        .args([
+            "build",
+            "--no-tui",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+        ])
+        .assert()
+        .success();
+    assert!(temp_dir.path().join("login.txt").exists());
+
+    // Not at a terminal and no --yes: nothing happens
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["rollback", "--prd-path", "prd.json", "--loop", "1"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("(1 commit(s) dropped)"))
+        .stderr(predicate::str::contains("pass --yes"));
+    assert!(temp_dir.path().join("login.txt").exists());
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["rollback", "--prd-path", "prd.json", "--loop", "1", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rolled back to before loop #1."))
+        .stdout(predicate::str::contains("git reset --hard"));
+    assert!(!temp_dir.path().join("login.txt").exists());
+    assert_eq!(
+        std::fs::read_to_string(temp_dir.path().join("prd.json")).unwrap(),
+        prd
+    );
+    let run: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(temp_dir.path().join(".ralph-run.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(run["loop_count"], 0);
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["rollback", "--prd-path", "prd.json", "--loop", "2", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No checkpoint for loop #2"));
+
+    // prd.md shares prd.json's state files: its run can't roll back prd.json
+    let mut run = run;
+    run["prd_path"] = "prd.md".into();
+    std::fs::write(temp_dir.path().join(".ralph-run.json"), run.to_string()).unwrap();
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["rollback", "--prd-path", "prd.json", "--loop", "1", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass -p prd.md to roll it back"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_counts_the_repair_of_unreadable_output() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(temp_dir.path(), "");
+    // The loop's output can't be read; the repair's can, and it cost something
+    std::fs::write(
+        temp_dir.path().join("bin").join("claude"),
+        r#"#!/bin/sh
+case "$*" in
+  *"Given this raw output from Claude"*)
+    echo '{"type": "result", "is_error": false, "total_cost_usd": 0.05,
+      "structured_output": {"task_number": 1, "status": "in_progress",
+      "summary": "Started login", "prd_complete": false}}' ;;
+  *) echo "Started on the login form" ;;
+esac
+"#,
+    )
+    .unwrap();
+    std::fs::set_permissions(
+        temp_dir.path().join("bin").join("claude"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &path)
+        .args([
+            "build",
+            "--no-tui",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Repaired Claude's output with haiku",
+        ));
+    let stats: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(temp_dir.path().join(".ralph-stats.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stats["iterations"][0]["cost_usd"], 0.05);
+}
+
+#[test]
+fn cli_build_parallel_rejects_review() {
+    ralph_cmd()
+        .args(["build", "--parallel", "2", "--review"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_events_are_json_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "total_cost_usd": 0.25,
+            "structured_output": {"task_number": 1, "status": "completed",
+            "summary": "Added the login form", "prd_complete": false}}"#,
+    );
+
+    let output = ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--events",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let events: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every line is a JSON event"))
+        .collect();
+    let names: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(names.first(), Some(&"loop_started"));
+    assert!(names.contains(&"stream_chunk"));
+    assert!(names.contains(&"claude_result"));
+    assert_eq!(names.last(), Some(&"finished"));
+
+    let completed = events
+        .iter()
+        .find(|e| e["event"] == "task_completed")
+        .unwrap();
+    assert_eq!(completed["task"], 1);
+    assert_eq!(completed["description"], "Add login");
+    assert_eq!(completed["summary"], "Added the login form");
+    assert_eq!(events.last().unwrap()["loops"], 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_output_jsonl_to_a_file() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": ["true"], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "total_cost_usd": 0.25,
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Sketched the login form", "prd_complete": false}}"#,
+    );
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--output",
+            "jsonl",
+            "--output-file",
+            "events.jsonl",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+        ])
+        .assert()
+        .success()
+        // stdout keeps the log lines and summary for people
+        .stdout(predicate::str::contains("Ralph Session Complete"));
+
+    let events = std::fs::read_to_string(temp_dir.path().join("events.jsonl")).unwrap();
+    let gate = events
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|e| e["event"] == "gate_result")
+        .unwrap();
+    assert_eq!(gate["command"], "true");
+    assert_eq!(gate["passed"], true);
+    assert_eq!(gate["exit_code"], 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_parallel_gates_run_at_the_same_time() {
+    let temp_dir = TempDir::new().unwrap();
+    // The first gate only passes if the second one runs while it waits
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [
+            "for i in $(seq 100); do [ -f second-ran ] && exit 0; sleep 0.05; done; exit 1",
+            "touch second-ran"
+        ], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "total_cost_usd": 0.25,
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Sketched the login form", "prd_complete": false}}"#,
+    );
+
+    let output = ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args([
+            "build",
+            "--parallel-gates",
+            "--output",
+            "jsonl",
+            "--prd-path",
+            "prd.json",
+            "--max-loops",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let gates: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter(|e| e["event"] == "gate_result")
+        .collect();
+    assert_eq!(gates.len(), 2);
+    // Reported in PRD order, however they finished
+    assert_eq!(gates[1]["command"], "touch second-ran");
+    assert!(gates.iter().all(|gate| gate["passed"] == true));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_verbose_logs_claude_invocations() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "total_cost_usd": 0.25,
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Sketched the login form", "prd_complete": false}}"#,
+    );
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args(["-vv", "build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .assert()
+        .success();
+
+    let log = std::fs::read_to_string(temp_dir.path().join(".ralph/ralph.log")).unwrap();
+    assert!(log.contains("Spawning claude"));
+    assert!(log.contains("--output-format"));
+    assert!(log.contains("Claude exited"));
+    assert!(log.contains("Sketched the login form"));
+}
+
+#[test]
+fn cli_build_reports_a_bad_completed_json_without_panicking() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    std::fs::write(temp_dir.path().join("completed.json"), "not json").unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Error: Invalid JSON formatting in completed.json",
+        ))
+        .stderr(predicate::str::contains("panicked").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_without_claude_fails_cleanly() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let empty_bin = temp_dir.path().join("bin");
+    std::fs::create_dir_all(&empty_bin).unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", &empty_bin)
+        .args(["build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error: Failed to run claude"))
+        .stderr(predicate::str::contains("panicked").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_build_saves_a_checkpoint_on_sigterm() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let bin = temp_dir.path().join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let claude = bin.join("claude");
+    std::fs::write(&claude, "#!/bin/sh\nsleep 10\n").unwrap();
+    std::fs::set_permissions(&claude, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_ralph"))
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .args(["build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let killed = std::process::Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    // Exited through ralph's handler rather than being killed by the signal
+    assert_eq!(child.wait().unwrap().code(), Some(128 + 15));
+
+    // The run is marked for resuming and the killed loop's log was written
+    let run_state = std::fs::read_to_string(temp_dir.path().join(".ralph-run.json")).unwrap();
+    let run_state: serde_json::Value = serde_json::from_str(&run_state).unwrap();
+    assert_eq!(run_state["interrupted_by"], "SIGTERM");
+    assert_eq!(run_state["loop_count"], 1);
+    let run_id = run_state["id"].as_str().unwrap();
+    let loop_log = temp_dir
+        .path()
+        .join(".ralph/logs")
+        .join(run_id)
+        .join("loop-1.json");
+    assert!(
+        std::fs::read_to_string(loop_log)
+            .unwrap()
+            .contains("\"interrupted\"")
+    );
+}
+
+#[cfg(all(unix, feature = "otel"))]
+#[test]
+fn cli_build_exports_loop_spans_over_otlp() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    // A collector that accepts OTLP/HTTP posts and hands back their paths and bodies
+    let collector = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", collector.local_addr().unwrap());
+    let (sender, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in collector.incoming() {
+            let mut reader = BufReader::new(stream.unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            let _ = sender.send((request_line, body));
+        }
+    });
+
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": ["true"], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let path = fake_claude(
+        temp_dir.path(),
+        r#"{"type": "result", "is_error": false, "total_cost_usd": 0.25,
+            "structured_output": {"task_number": 1, "status": "in_progress",
+            "summary": "Sketched the login form", "prd_complete": false}}"#,
+    );
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .env("PATH", path)
+        .env("OTEL_EXPORTER_OTLP_ENDPOINT", &endpoint)
+        .args(["build", "--prd-path", "prd.json", "--max-loops", "1"])
+        .assert()
+        .success();
+
+    let (request_line, body) = received
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .unwrap();
+    assert!(request_line.starts_with("POST /v1/traces "));
+    let has = |name: &str| body.windows(name.len()).any(|w| w == name.as_bytes());
+    for name in ["ralph", "loop", "claude", "gate", "in_progress"] {
+        assert!(has(name), "no {} in the exported spans", name);
+    }
+}
+
+#[test]
+fn cli_mcp_answers_over_stdio() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"name": "Demo", "quality_gates": [], "tasks": [
+            {"category": "feature", "description": "Add login", "steps": [], "passes": false}
+        ]}"#,
+    )
+    .unwrap();
+    let requests = [
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-06-18", "capabilities": {}, "clientInfo": {"name": "test", "version": "1"}}}"#,
+        r#"{"jsonrpc": "2.0", "method": "notifications/initialized"}"#,
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "tools/call", "params": {"name": "mark_task_done", "arguments": {"number": 1}}}"#,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "resources/read", "params": {"uri": "ralph://prd"}}"#,
+    ];
+
+    let output = ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["mcp", "--prd-path", "prd.json"])
+        .write_stdin(requests.join("\n") + "\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let responses: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // The notification gets no answer
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0]["result"]["serverInfo"]["name"], "ralph");
+    assert_eq!(responses[1]["result"]["isError"], false);
+    let prd = responses[2]["result"]["contents"][0]["text"]
+        .as_str()
+        .unwrap();
+    assert!(prd.contains("\"passes\": true"));
+}
+
+#[test]
+fn cli_doctor_reports_a_missing_gate_program_with_a_fix() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join("prd.json"),
+        r#"{"schema_version": 1, "name": "Demo", "quality_gates": ["ralph-doctor-no-such-program"], "tasks": []}"#,
+    )
+    .unwrap();
+
+    ralph_cmd()
+        .current_dir(temp_dir.path())
+        .args(["doctor", "-p", "prd.json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "✓ PRD prd.json parses (0 task(s))",
+        ))
+        .stdout(predicate::str::contains(
+            "✗ gate `ralph-doctor-no-such-program`: ralph-doctor-no-such-program not found on PATH",
+        ))
+        .stdout(predicate::str::contains(
+            "Fix: install ralph-doctor-no-such-program, or change the gate in prd.json",
+        ))
+        .stderr(predicate::str::contains("check(s) failed"));
+}